@@ -0,0 +1,9 @@
+-- Migration 004: Per-network fee data
+-- Tags each fee data point with the Stellar network it was collected from so
+-- one database can hold several networks side by side. Rows written before
+-- this migration keep a NULL network until the primary network claims them.
+
+ALTER TABLE fee_data_points ADD COLUMN network TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_fee_data_points_network_timestamp
+    ON fee_data_points (network, timestamp);
@@ -0,0 +1,7 @@
+-- Migration 005: Envelope details
+-- Details decoded from the transaction envelope XDR. All NULL when the
+-- provider did not supply an envelope.
+
+ALTER TABLE fee_data_points ADD COLUMN operation_count INTEGER;
+ALTER TABLE fee_data_points ADD COLUMN fee_bump INTEGER;
+ALTER TABLE fee_data_points ADD COLUMN max_fee INTEGER;
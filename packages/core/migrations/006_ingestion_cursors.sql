@@ -0,0 +1,10 @@
+-- Migration 006: Ingestion cursors
+-- Last Horizon paging token processed per network, so ingestion resumes
+-- where it stopped after a restart. An unscoped repository uses ''.
+
+CREATE TABLE IF NOT EXISTS ingestion_cursors (
+    network         TEXT    PRIMARY KEY NOT NULL,
+    paging_token    TEXT    NOT NULL,
+    ledger_sequence INTEGER NOT NULL,
+    updated_at      TEXT    NOT NULL DEFAULT (datetime('now'))
+);
@@ -0,0 +1,5 @@
+-- Migration 007: Fee-bump inner fee
+-- Fee bid of the inner transaction for fee-bump transactions; `max_fee`
+-- holds the outer (sponsoring) bid. NULL for regular transactions.
+
+ALTER TABLE fee_data_points ADD COLUMN inner_fee INTEGER;
@@ -0,0 +1,18 @@
+-- Migration 008: Ledgers
+-- Close time and capacity of recently closed ledgers, so congestion can be
+-- judged by how full ledgers are rather than by fee levels alone.
+-- `operation_count` covers the whole transaction set, failed transactions
+-- included. An unscoped repository uses '' as the network.
+
+CREATE TABLE IF NOT EXISTS ledgers (
+    network           TEXT    NOT NULL DEFAULT '',
+    sequence          INTEGER NOT NULL,
+    closed_at         TEXT    NOT NULL,
+    transaction_count INTEGER NOT NULL,
+    operation_count   INTEGER NOT NULL,
+    max_tx_set_size   INTEGER NOT NULL,
+    base_fee          INTEGER NOT NULL,
+    PRIMARY KEY (network, sequence)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledgers_closed_at ON ledgers (closed_at);
@@ -0,0 +1,17 @@
+-- Migration 009: Surge episodes
+-- One row per completed period in which fees stayed at or above a multiple
+-- of the baseline fee. An unscoped repository uses '' as the network.
+
+CREATE TABLE IF NOT EXISTS surge_episodes (
+    id               INTEGER PRIMARY KEY AUTOINCREMENT,
+    network          TEXT    NOT NULL DEFAULT '',
+    started_at       TEXT    NOT NULL,
+    ended_at         TEXT    NOT NULL,
+    duration_seconds INTEGER NOT NULL,
+    peak_fee         INTEGER NOT NULL,
+    baseline_fee     REAL    NOT NULL,
+    sample_count     INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_surge_episodes_network_started_at
+    ON surge_episodes (network, started_at);
@@ -0,0 +1,6 @@
+-- Migration 010: Operation category
+-- Dominant operation type of the transaction (payment, path_payment,
+-- offer_management, contract_invoke or other), decoded from the envelope.
+-- NULL when the envelope was not available.
+
+ALTER TABLE fee_data_points ADD COLUMN operation_category TEXT;
@@ -0,0 +1,13 @@
+-- Migration 011: All-time fee extremes
+-- Lowest and highest fee seen per network, kept past the fee retention
+-- window so they survive restarts. An unscoped repository uses ''.
+
+CREATE TABLE IF NOT EXISTS fee_extremes (
+    network          TEXT    NOT NULL,
+    kind             TEXT    NOT NULL,  -- 'min' or 'max'
+    fee_amount       INTEGER NOT NULL,
+    timestamp        TEXT    NOT NULL,
+    transaction_hash TEXT    NOT NULL,
+    ledger_sequence  INTEGER NOT NULL,
+    PRIMARY KEY (network, kind)
+);
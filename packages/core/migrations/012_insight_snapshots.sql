@@ -0,0 +1,14 @@
+-- Migration 012: Insight snapshots
+-- Full insights (all windows, congestion state, extremes) captured on a
+-- fixed cadence, stored as JSON so history can be served as a time series.
+-- An unscoped repository uses '' as the network.
+
+CREATE TABLE IF NOT EXISTS insight_snapshots (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    network     TEXT    NOT NULL DEFAULT '',
+    captured_at TEXT    NOT NULL,
+    insights    TEXT    NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_insight_snapshots_network_captured_at
+    ON insight_snapshots (network, captured_at);
@@ -0,0 +1,22 @@
+-- Migration 013: Fee rollups
+-- Fees summarised per closed 1m / 5m / 1h / 1d bucket, so long-range
+-- queries avoid scanning raw points. Kept past the fee retention window.
+-- An unscoped repository uses '' as the network.
+
+CREATE TABLE IF NOT EXISTS fee_rollups (
+    network      TEXT    NOT NULL DEFAULT '',
+    resolution   TEXT    NOT NULL,  -- '1m', '5m', '1h' or '1d'
+    bucket_start TEXT    NOT NULL,
+    sample_count INTEGER NOT NULL,
+    min_fee      INTEGER NOT NULL,
+    max_fee      INTEGER NOT NULL,
+    avg_fee      REAL    NOT NULL,
+    p10          INTEGER NOT NULL,
+    p25          INTEGER NOT NULL,
+    p50          INTEGER NOT NULL,
+    p75          INTEGER NOT NULL,
+    p90          INTEGER NOT NULL,
+    p95          INTEGER NOT NULL,
+    p99          INTEGER NOT NULL,
+    PRIMARY KEY (network, resolution, bucket_start)
+);
@@ -0,0 +1,14 @@
+-- Migration 014: Congestion samples
+-- The congestion state at every persisted insight snapshot, kept for a
+-- month regardless of fee retention so time-in-state can be reported.
+-- An unscoped repository uses '' as the network.
+
+CREATE TABLE IF NOT EXISTS congestion_samples (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    network     TEXT    NOT NULL DEFAULT '',
+    captured_at TEXT    NOT NULL,
+    state       TEXT    NOT NULL  -- 'normal', 'elevated' or 'congested'
+);
+
+CREATE INDEX IF NOT EXISTS idx_congestion_samples_network_captured_at
+    ON congestion_samples (network, captured_at);
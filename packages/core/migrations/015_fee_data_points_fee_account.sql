@@ -0,0 +1,8 @@
+-- Migration 015: Fee account
+-- Account that paid the fee (the fee source for fee bumps). Recorded only
+-- when TRACK_FEE_ACCOUNTS is enabled; NULL otherwise.
+
+ALTER TABLE fee_data_points ADD COLUMN fee_account TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_fee_data_points_fee_account_timestamp
+    ON fee_data_points (fee_account, timestamp);
@@ -0,0 +1,13 @@
+-- Migration 016: Fee budgets
+-- Daily or weekly fee limits per paying account, in stroops. Spend is
+-- tracked against them from recorded fee accounts (TRACK_FEE_ACCOUNTS).
+-- An unscoped repository uses '' as the network.
+
+CREATE TABLE IF NOT EXISTS fee_budgets (
+    network    TEXT    NOT NULL DEFAULT '',
+    account    TEXT    NOT NULL,
+    period     TEXT    NOT NULL,
+    limit_fee  INTEGER NOT NULL,
+    updated_at TEXT    NOT NULL,
+    PRIMARY KEY (network, account, period)
+);
@@ -0,0 +1,6 @@
+-- Migration 017: Leaderboard index
+-- Covers the fee leaderboard's range scan, so ranking payers by total fee
+-- reads only the index.
+
+CREATE INDEX IF NOT EXISTS idx_fee_data_points_timestamp_fee_account
+    ON fee_data_points (timestamp, fee_account, fee_amount, network);
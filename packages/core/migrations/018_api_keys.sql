@@ -0,0 +1,13 @@
+-- Migration 018: API keys
+-- Keys accepted in the X-API-Key header besides the configured API_KEY.
+-- Only a SHA-256 hash of each key is stored; `prefix` is its first
+-- characters, kept so that a key can be recognised in listings.
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    name       TEXT    NOT NULL,
+    key_hash   TEXT    NOT NULL UNIQUE,
+    prefix     TEXT    NOT NULL,
+    created_at TEXT    NOT NULL,
+    revoked_at TEXT
+);
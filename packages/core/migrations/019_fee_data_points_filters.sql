@@ -0,0 +1,9 @@
+-- Migration 019: Fee history filters
+-- Serve /fees/history lookups by ledger range and transaction hash prefix
+-- without scanning the whole time range.
+
+CREATE INDEX IF NOT EXISTS idx_fee_data_points_network_ledger_sequence
+    ON fee_data_points (network, ledger_sequence);
+
+CREATE INDEX IF NOT EXISTS idx_fee_data_points_transaction_hash
+    ON fee_data_points (transaction_hash);
@@ -0,0 +1,16 @@
+-- Migration 020: Congestion episodes
+-- One row per period the congestion detector spent above 'normal',
+-- opened and closed from its state transitions and kept past the fee
+-- retention window. An unscoped repository uses '' as the network.
+
+CREATE TABLE IF NOT EXISTS congestion_episodes (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    network         TEXT    NOT NULL DEFAULT '',
+    started_at      TEXT    NOT NULL,
+    ended_at        TEXT,             -- NULL while the episode is ongoing
+    peak_state      TEXT    NOT NULL, -- 'elevated' or 'congested'
+    peak_multiplier REAL    NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_congestion_episodes_network_id
+    ON congestion_episodes (network, id);
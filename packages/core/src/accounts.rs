@@ -0,0 +1,236 @@
+//! Per-account fee spend.
+//!
+//! With `TRACK_FEE_ACCOUNTS` enabled every stored fee keeps the account
+//! that paid it — the fee source for fee bumps, so sponsors are charged
+//! rather than the accounts they sponsor. Spend is then summed per account
+//! and per UTC day, which is what exchanges and anchors audit against.
+//!
+//! Accounts can also be given a daily or weekly fee budget, tracked live by
+//! the insights engine; see [`track_budget`].
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::insights::{FeeBudget, FeeInsightsEngine};
+use crate::repository::FeeRepository;
+
+/// Fees one account paid on one UTC day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyFeeSpend {
+    pub day: NaiveDate,
+    pub transaction_count: u64,
+    /// In stroops.
+    pub total_fee: u64,
+}
+
+/// Fees one account paid over a range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountFeeSpend {
+    pub account: String,
+    pub transaction_count: u64,
+    /// In stroops.
+    pub total_fee: u64,
+    pub average_fee: f64,
+    /// Days with at least one transaction, oldest first.
+    pub daily: Vec<DailyFeeSpend>,
+}
+
+/// Spend per account in `[from, to)`, biggest spender first, keeping at
+/// most `limit` accounts. `account` restricts the result to one account.
+pub async fn account_fee_spend(
+    repo: &FeeRepository,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    account: Option<&str>,
+    limit: usize,
+) -> Result<Vec<AccountFeeSpend>, sqlx::Error> {
+    let rows = repo.fetch_daily_account_fees(from, to, account).await?;
+
+    // Rows arrive grouped by account
+    let mut spends: Vec<AccountFeeSpend> = Vec::new();
+    for (account, day) in rows {
+        match spends.last_mut() {
+            Some(spend) if spend.account == account => spend.daily.push(day),
+            _ => spends.push(AccountFeeSpend {
+                account,
+                transaction_count: 0,
+                total_fee: 0,
+                average_fee: 0.0,
+                daily: vec![day],
+            }),
+        }
+    }
+    for spend in &mut spends {
+        spend.transaction_count = spend.daily.iter().map(|d| d.transaction_count).sum();
+        spend.total_fee = spend.daily.iter().map(|d| d.total_fee).sum();
+        spend.average_fee = spend.total_fee as f64 / spend.transaction_count as f64;
+    }
+
+    spends.sort_by(|a, b| {
+        b.total_fee
+            .cmp(&a.total_fee)
+            .then_with(|| a.account.cmp(&b.account))
+    });
+    spends.truncate(limit);
+    Ok(spends)
+}
+
+/// Track `budget` on `engine`, starting from what its account already
+/// paid this period according to stored fees.
+pub async fn track_budget(
+    repo: &FeeRepository,
+    engine: &RwLock<FeeInsightsEngine>,
+    budget: FeeBudget,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    let spent = repo
+        .fetch_account_fee_total(&budget.account, budget.period.start(now), now)
+        .await?;
+    engine.write().await.set_fee_budget(budget, spent, now);
+    Ok(())
+}
+
+/// Track every stored budget on `engine`. Returns how many were restored.
+pub async fn restore_budgets(
+    repo: &FeeRepository,
+    engine: &RwLock<FeeInsightsEngine>,
+) -> Result<usize, sqlx::Error> {
+    let budgets = repo.list_fee_budgets().await?;
+    let count = budgets.len();
+    for budget in budgets {
+        track_budget(repo, engine, budget).await?;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::insights::{
+        BudgetPeriod, EnvelopeDetails, FeeDataPoint, InsightEvent, InsightsConfig,
+    };
+
+    fn paid_by(account: &str, fee_amount: u64, timestamp: DateTime<Utc>) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp,
+            transaction_hash: format!("{}_{}_{}", account, fee_amount, timestamp),
+            ledger_sequence: 1,
+            envelope: Some(EnvelopeDetails {
+                operation_count: 1,
+                max_fee: fee_amount,
+                fee_account: Some(account.to_string()),
+                ..EnvelopeDetails::default()
+            }),
+            soroban: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn sums_spend_per_account_and_day() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = FeeRepository::new(pool).with_fee_accounts(true);
+        let day = |d: u32, h: u32| {
+            NaiveDate::from_ymd_opt(2026, 3, d)
+                .unwrap()
+                .and_hms_opt(h, 0, 0)
+                .unwrap()
+                .and_utc()
+        };
+        repo.insert_fee_points(&[
+            paid_by("GEXCHANGE", 100, day(1, 9)),
+            paid_by("GEXCHANGE", 300, day(1, 23)),
+            paid_by("GEXCHANGE", 1_000, day(2, 0)),
+            paid_by("GANCHOR", 200, day(2, 12)),
+            paid_by("GEXCHANGE", 5_000, day(5, 0)),
+        ])
+        .await
+        .unwrap();
+
+        let spends = account_fee_spend(&repo, day(1, 0), day(3, 0), None, 10)
+            .await
+            .unwrap();
+        assert_eq!(spends.len(), 2);
+        let exchange = &spends[0];
+        assert_eq!(exchange.account, "GEXCHANGE");
+        assert_eq!((exchange.transaction_count, exchange.total_fee), (3, 1_400));
+        assert_eq!(exchange.daily.len(), 2);
+        assert_eq!(exchange.daily[0].total_fee, 400);
+        assert_eq!(spends[1].average_fee, 200.0);
+
+        let anchor = account_fee_spend(&repo, day(1, 0), day(3, 0), Some("GANCHOR"), 10)
+            .await
+            .unwrap();
+        assert_eq!(anchor.len(), 1);
+        assert_eq!(anchor[0].total_fee, 200);
+
+        let top = account_fee_spend(&repo, day(1, 0), day(3, 0), None, 1)
+            .await
+            .unwrap();
+        assert_eq!(top.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn accounts_are_not_stored_unless_enabled() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = FeeRepository::new(pool);
+        let now = Utc::now();
+        repo.insert_fee_points(&[paid_by("GEXCHANGE", 100, now)])
+            .await
+            .unwrap();
+
+        let hour = chrono::Duration::hours(1);
+        let spends = account_fee_spend(&repo, now - hour, now + hour, None, 10)
+            .await
+            .unwrap();
+        assert!(spends.is_empty());
+    }
+
+    #[tokio::test]
+    async fn restored_budgets_start_from_stored_spend() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = FeeRepository::new(pool).with_fee_accounts(true);
+        let now = Utc::now();
+        repo.insert_fee_points(&[
+            paid_by("GEXCHANGE", 800, BudgetPeriod::Daily.start(now)),
+            paid_by(
+                "GEXCHANGE",
+                9_000,
+                BudgetPeriod::Daily.start(now) - chrono::Duration::hours(1),
+            ),
+        ])
+        .await
+        .unwrap();
+        repo.upsert_fee_budget(&FeeBudget {
+            account: "GEXCHANGE".to_string(),
+            period: BudgetPeriod::Daily,
+            limit: 1_000,
+        })
+        .await
+        .unwrap();
+
+        let engine = RwLock::new(FeeInsightsEngine::new(InsightsConfig::default()));
+        assert_eq!(restore_budgets(&repo, &engine).await.unwrap(), 1);
+        let statuses = engine.read().await.get_budget_statuses();
+        assert_eq!((statuses[0].spent, statuses[0].remaining), (800, 200));
+
+        let mut events = engine.read().await.subscribe_events();
+        let later = Utc::now() + chrono::Duration::seconds(1);
+        engine
+            .write()
+            .await
+            .process_fee_data(&[paid_by("GEXCHANGE", 300, later)])
+            .await
+            .unwrap();
+        let exceeded = std::iter::from_fn(|| events.try_recv().ok())
+            .find_map(|event| match event {
+                InsightEvent::BudgetExceeded(status) => Some(status),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(exceeded.account, "GEXCHANGE");
+        assert_eq!(exceeded.spent, 1_100);
+    }
+}
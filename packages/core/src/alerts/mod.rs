@@ -8,7 +8,7 @@ use tokio::sync::Mutex;
 
 use crate::insights::{InsightsUpdate, SpikeSeverity};
 
-use self::webhook::{AlertPayload, WebhookDelivery};
+use self::webhook::{AlertPayload, CongestionStatePayload, FeeAnomalyPayload, WebhookDelivery};
 
 #[derive(Clone)]
 pub struct AlertManager {
@@ -38,6 +38,45 @@ impl AlertManager {
             return;
         };
 
+        let anomalies = &update.insights.anomalies;
+        if anomalies.is_anomaly || !anomalies.anomalies.is_empty() {
+            let payload = FeeAnomalyPayload {
+                event: "fee_anomaly_detected".to_string(),
+                window_anomaly: anomalies.is_anomaly,
+                window_score: anomalies.window_score,
+                anomalies: anomalies.anomalies.clone(),
+                network: self.network.clone(),
+                timestamp: Utc::now(),
+            };
+
+            let delivery = delivery.clone();
+            tokio::spawn(async move {
+                if let Err(err) = delivery.send_with_retry(&payload).await {
+                    tracing::error!("Webhook dispatch failed: {}", err);
+                }
+            });
+        }
+
+        for transition in &update.state_transitions {
+            let payload = CongestionStatePayload {
+                event: "congestion_state_changed".to_string(),
+                from: transition.from,
+                to: transition.to,
+                fee_ratio: transition.fee_ratio,
+                capacity_congested: transition.capacity_congested,
+                changed_at: transition.at,
+                network: self.network.clone(),
+                timestamp: Utc::now(),
+            };
+
+            let delivery = delivery.clone();
+            tokio::spawn(async move {
+                if let Err(err) = delivery.send_with_retry(&payload).await {
+                    tracing::error!("Webhook dispatch failed: {}", err);
+                }
+            });
+        }
+
         // Build the set of IDs that are still active in the congestion window.
         // Prune `seen_spikes` to those IDs so the set stays bounded by the
         // size of `recent_spikes` (which the detector already caps at 1 000).
@@ -130,12 +169,13 @@ mod tests {
     use super::*;
     use chrono::{DateTime, Duration, Utc};
     use wiremock::{
-        matchers::{method, path},
+        matchers::{body_partial_json, method, path},
         Mock, MockServer, ResponseTemplate,
     };
 
     use crate::insights::{
-        AverageResult, CongestionTrends, CurrentInsights, DataQuality, FeeSpike, RollingAverages,
+        AnomalyReport, AverageResult, AveragingMethod, CongestionState, CongestionTransition,
+        CongestionTrends, CurrentInsights, DataQuality, FeeAnomaly, FeeSpike, RollingAverages,
         SpikeSeverity, TimeWindow, TrendIndicator, TrendStrength,
     };
 
@@ -157,6 +197,7 @@ mod tests {
             name: "1h".to_string(),
             duration: Duration::hours(1),
             min_samples: 1,
+            averaging: AveragingMethod::Simple,
         };
         let avg = AverageResult {
             value: 130.5,
@@ -164,6 +205,16 @@ mod tests {
             is_partial: false,
             calculated_at: now,
             time_window: window.clone(),
+            fee_bump_count: 0,
+            fee_bump_volume_share: 0.0,
+            avg_fee_per_operation: None,
+            soroban: None,
+            percentiles: None,
+            histogram: None,
+            by_category: Vec::new(),
+            volatility: None,
+            trend: None,
+            bid_spread: None,
         };
 
         InsightsUpdate {
@@ -178,20 +229,29 @@ mod tests {
                         value: 100,
                         timestamp: now,
                         transaction_hash: "min".to_string(),
+                        ledger_sequence: 1,
                     },
                     current_max: crate::insights::ExtremeValue {
                         value: 5000,
                         timestamp: now,
                         transaction_hash: "max".to_string(),
+                        ledger_sequence: 1,
                     },
                     period_start: now - Duration::hours(1),
                     period_end: now,
+                    all_time: None,
+                    last_24h: None,
+                    by_window: Vec::new(),
                 },
                 congestion_trends: CongestionTrends {
                     current_trend: TrendIndicator::Rising,
                     recent_spikes: vec![spike],
                     trend_strength: TrendStrength::Strong,
                     predicted_duration: None,
+                    capacity_utilization: None,
+                    congestion_score: None,
+                    congestion_state: CongestionState::Elevated,
+                    seasonal_ratio: None,
                 },
                 last_updated: now,
                 data_quality: DataQuality {
@@ -200,9 +260,15 @@ mod tests {
                     has_gaps: false,
                     last_gap: None,
                 },
+                anomalies: AnomalyReport::default(),
+                surge_pricing: None,
+                custom: Default::default(),
             },
             processing_time: Duration::milliseconds(1),
             data_points_processed: 1,
+            state_transitions: Vec::new(),
+            fee_ratio: None,
+            completed_surges: Vec::new(),
         }
     }
 
@@ -269,4 +335,69 @@ mod tests {
         manager.check_and_dispatch(&update).await;
         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
     }
+
+    #[tokio::test]
+    async fn state_transition_dispatches_webhook() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(body_partial_json(serde_json::json!({
+                "event": "congestion_state_changed",
+                "from": "normal",
+                "to": "elevated",
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let manager = AlertManager::new(
+            Some(format!("{}/hook", server.uri())),
+            SpikeSeverity::Critical,
+            "mainnet".to_string(),
+        );
+        let mut update = build_update_with_spike(SpikeSeverity::Minor);
+        update.state_transitions.push(CongestionTransition {
+            from: CongestionState::Normal,
+            to: CongestionState::Elevated,
+            at: Utc::now(),
+            fee_ratio: 1.6,
+            capacity_congested: false,
+        });
+
+        manager.check_and_dispatch(&update).await;
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    #[tokio::test]
+    async fn anomalous_fees_dispatch_webhook() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(body_partial_json(serde_json::json!({
+                "event": "fee_anomaly_detected",
+                "window_anomaly": false,
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let manager = AlertManager::new(
+            Some(format!("{}/hook", server.uri())),
+            SpikeSeverity::Critical,
+            "mainnet".to_string(),
+        );
+        let mut update = build_update_with_spike(SpikeSeverity::Minor);
+        update.insights.anomalies.anomalies.push(FeeAnomaly {
+            transaction_hash: "odd".to_string(),
+            fee_amount: 50_000,
+            timestamp: Utc::now(),
+            score: 42.0,
+            median_fee: 100.0,
+        });
+
+        manager.check_and_dispatch(&update).await;
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
 }
@@ -4,6 +4,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::insights::{CongestionState, FeeAnomaly};
+
 const REQUEST_TIMEOUT_SECONDS: u64 = 10;
 const MAX_ATTEMPTS: usize = 2;
 const RETRY_DELAY_SECONDS: u64 = 2;
@@ -21,6 +23,31 @@ pub struct AlertPayload {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Sent when the debounced congestion state changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CongestionStatePayload {
+    pub event: String,
+    pub from: CongestionState,
+    pub to: CongestionState,
+    pub fee_ratio: f64,
+    pub capacity_congested: bool,
+    pub changed_at: DateTime<Utc>,
+    pub network: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Sent when a batch contains statistical outliers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeAnomalyPayload {
+    pub event: String,
+    /// `true` when the batch as a whole deviated, not just single fees.
+    pub window_anomaly: bool,
+    pub window_score: Option<f64>,
+    pub anomalies: Vec<FeeAnomaly>,
+    pub network: String,
+    pub timestamp: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone)]
 pub struct WebhookDelivery {
     client: reqwest::Client,
@@ -46,11 +73,11 @@ impl WebhookDelivery {
     }
 
     #[allow(dead_code)]
-    pub async fn send(&self, payload: &AlertPayload) -> Result<(), WebhookError> {
+    pub async fn send<P: Serialize>(&self, payload: &P) -> Result<(), WebhookError> {
         self.send_with_retry(payload).await
     }
 
-    pub async fn send_with_retry(&self, payload: &AlertPayload) -> Result<(), WebhookError> {
+    pub async fn send_with_retry<P: Serialize>(&self, payload: &P) -> Result<(), WebhookError> {
         let mut last_error: Option<WebhookError> = None;
 
         for attempt in 1..=MAX_ATTEMPTS {
@@ -0,0 +1,143 @@
+//! Per-account fee spend.
+//!
+//! Routes (data exists only with `TRACK_FEE_ACCOUNTS` enabled):
+//! - `GET /fees/accounts?from=…&to=…&limit=…` — accounts by fees paid,
+//!   biggest first, each with a per-day breakdown
+//! - `GET /fees/accounts/:account?from=…&to=…` — one account's spend
+//!
+//! `to` defaults to now and `from` to 7 days before `to`. `limit` defaults
+//! to 50 and is capped at 500.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::accounts::{account_fee_spend, AccountFeeSpend};
+use crate::error::AppError;
+use crate::repository::FeeRepository;
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+/// Shared state for the account routes.
+pub type AccountsState = Arc<FeeRepository>;
+
+#[derive(Debug, Deserialize)]
+pub struct AccountsQuery {
+    /// RFC 3339 timestamps.
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+/// `(from, to)` of the query, or 400 when it does not start before it ends.
+fn range(params: &AccountsQuery) -> Result<(DateTime<Utc>, DateTime<Utc>), AppError> {
+    let to = params.to.unwrap_or_else(Utc::now);
+    let from = params.from.unwrap_or(to - chrono::Duration::days(7));
+    if from >= to {
+        return Err(AppError::BadRequest(
+            "Range must start before it ends".to_string(),
+        ));
+    }
+    Ok((from, to))
+}
+
+/// `GET /fees/accounts` — biggest fee payers in the range.
+pub async fn list_account_spend(
+    State(repo): State<AccountsState>,
+    Query(params): Query<AccountsQuery>,
+) -> Result<Json<Vec<AccountFeeSpend>>, AppError> {
+    let (from, to) = range(&params)?;
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    Ok(Json(account_fee_spend(&repo, from, to, None, limit).await?))
+}
+
+/// `GET /fees/accounts/:account` — one account's spend in the range.
+pub async fn get_account_spend(
+    State(repo): State<AccountsState>,
+    Path(account): Path<String>,
+    Query(params): Query<AccountsQuery>,
+) -> Result<Json<AccountFeeSpend>, AppError> {
+    let (from, to) = range(&params)?;
+    account_fee_spend(&repo, from, to, Some(&account), 1)
+        .await?
+        .pop()
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("No recorded fees paid by {}", account)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::db::create_pool;
+    use crate::insights::{EnvelopeDetails, FeeDataPoint};
+
+    #[tokio::test]
+    async fn serves_spend_per_account() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = Arc::new(FeeRepository::new(pool).with_fee_accounts(true));
+        let now = Utc::now();
+        let points: Vec<FeeDataPoint> = [("GEXCHANGE", 300), ("GEXCHANGE", 500), ("GANCHOR", 100)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (account, fee_amount))| FeeDataPoint {
+                fee_amount,
+                timestamp: now - chrono::Duration::minutes(i as i64 + 1),
+                transaction_hash: format!("tx_{}", i),
+                ledger_sequence: i as u64,
+                envelope: Some(EnvelopeDetails {
+                    operation_count: 1,
+                    fee_account: Some(account.to_string()),
+                    ..EnvelopeDetails::default()
+                }),
+                soroban: None,
+            })
+            .collect();
+        repo.insert_fee_points(&points).await.unwrap();
+
+        let app = Router::new()
+            .route("/fees/accounts", get(list_account_spend))
+            .route("/fees/accounts/:account", get(get_account_spend))
+            .with_state(repo);
+        let get_json = |uri: &str| {
+            let app = app.clone();
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            async move {
+                let resp = app.oneshot(request).await.unwrap();
+                let status = resp.status();
+                let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&bytes).unwrap(),
+                )
+            }
+        };
+
+        let (status, json) = get_json("/fees/accounts").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json[0]["account"], "GEXCHANGE");
+        assert_eq!(json[0]["total_fee"], 800);
+        assert_eq!(json[1]["account"], "GANCHOR");
+
+        let (status, json) = get_json("/fees/accounts/GANCHOR").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["transaction_count"], 1);
+
+        let (status, _) = get_json("/fees/accounts/GUNKNOWN").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
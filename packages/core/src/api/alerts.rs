@@ -18,6 +18,7 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::error::AppError;
 use crate::repository::{AlertConfig, AlertEvent, FeeRepository, VALID_THRESHOLDS};
 
 /// Shared state for the alerts routes.
@@ -113,40 +114,26 @@ fn is_safe_webhook_url(url: &str) -> bool {
 pub async fn create_alert(
     State(repo): State<AlertsState>,
     Json(body): Json<CreateAlertRequest>,
-) -> Result<(StatusCode, Json<CreateAlertResponse>), (StatusCode, Json<serde_json::Value>)> {
+) -> Result<(StatusCode, Json<CreateAlertResponse>), AppError> {
     let threshold = body.threshold.as_deref().unwrap_or("Major");
 
     if !is_valid_threshold(threshold) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": format!(
-                    "Invalid threshold '{}'. Must be one of: {}",
-                    threshold,
-                    VALID_THRESHOLDS.join(", ")
-                )
-            })),
-        ));
+        return Err(AppError::BadRequest(format!(
+            "Invalid threshold '{}'. Must be one of: {}",
+            threshold,
+            VALID_THRESHOLDS.join(", ")
+        )));
     }
 
     if !is_safe_webhook_url(&body.webhook_url) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": "Invalid webhook_url: must be an HTTPS URL with a public hostname"
-            })),
+        return Err(AppError::BadRequest(
+            "Invalid webhook_url: must be an HTTPS URL with a public hostname".to_string(),
         ));
     }
 
     let id = repo
         .insert_alert_config(&body.webhook_url, threshold)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": e.to_string() })),
-            )
-        })?;
+        .await?;
 
     Ok((StatusCode::CREATED, Json(CreateAlertResponse { id })))
 }
@@ -154,15 +141,8 @@ pub async fn create_alert(
 /// `GET /alerts/config` — list all registered webhook configs.
 pub async fn list_alerts(
     State(repo): State<AlertsState>,
-) -> Result<Json<Vec<AlertConfig>>, (StatusCode, Json<serde_json::Value>)> {
-    let configs = repo.list_alert_configs().await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({ "error": e.to_string() })),
-        )
-    })?;
-
-    Ok(Json(configs))
+) -> Result<Json<Vec<AlertConfig>>, AppError> {
+    Ok(Json(repo.list_alert_configs().await?))
 }
 
 /// `PATCH /alerts/config/:id` — update threshold and/or enabled state.
@@ -170,55 +150,32 @@ pub async fn update_alert(
     State(repo): State<AlertsState>,
     Path(id): Path<i64>,
     Json(body): Json<UpdateAlertRequest>,
-) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+) -> Result<StatusCode, AppError> {
     // Fetch current config to apply partial updates.
-    let configs = repo.list_alert_configs().await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({ "error": e.to_string() })),
-        )
-    })?;
-
-    let current = configs.iter().find(|c| c.id == id).ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({ "error": "Alert config not found" })),
-        )
-    })?;
+    let configs = repo.list_alert_configs().await?;
+
+    let current = configs
+        .iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| AppError::NotFound("Alert config not found".to_string()))?;
 
     let threshold = body.threshold.as_deref().unwrap_or(&current.threshold);
     let enabled = body.enabled.unwrap_or(current.enabled);
 
     if !is_valid_threshold(threshold) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": format!(
-                    "Invalid threshold '{}'. Must be one of: {}",
-                    threshold,
-                    VALID_THRESHOLDS.join(", ")
-                )
-            })),
-        ));
+        return Err(AppError::BadRequest(format!(
+            "Invalid threshold '{}'. Must be one of: {}",
+            threshold,
+            VALID_THRESHOLDS.join(", ")
+        )));
     }
 
-    let updated = repo
-        .update_alert_config(id, threshold, enabled)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": e.to_string() })),
-            )
-        })?;
+    let updated = repo.update_alert_config(id, threshold, enabled).await?;
 
     if updated {
         Ok(StatusCode::NO_CONTENT)
     } else {
-        Err((
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({ "error": "Alert config not found" })),
-        ))
+        Err(AppError::NotFound("Alert config not found".to_string()))
     }
 }
 
@@ -226,21 +183,13 @@ pub async fn update_alert(
 pub async fn delete_alert(
     State(repo): State<AlertsState>,
     Path(id): Path<i64>,
-) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
-    let deleted = repo.delete_alert_config(id).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({ "error": e.to_string() })),
-        )
-    })?;
+) -> Result<StatusCode, AppError> {
+    let deleted = repo.delete_alert_config(id).await?;
 
     if deleted {
         Ok(StatusCode::NO_CONTENT)
     } else {
-        Err((
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({ "error": "Alert config not found" })),
-        ))
+        Err(AppError::NotFound("Alert config not found".to_string()))
     }
 }
 
@@ -268,36 +217,25 @@ pub struct AlertHistoryResponse {
 pub async fn get_alert_history(
     State(repo): State<AlertsState>,
     Query(params): Query<AlertHistoryQuery>,
-) -> Result<Json<AlertHistoryResponse>, (StatusCode, Json<serde_json::Value>)> {
+) -> Result<Json<AlertHistoryResponse>, AppError> {
     let limit = params.limit.unwrap_or(20).clamp(1, 100);
     let severity = params.severity.as_deref();
     let delivered = params.delivered;
 
     if let Some(sev) = severity {
         if !is_valid_threshold(sev) {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({
-                    "error": format!(
-                        "Invalid severity '{}'. Must be one of: {}",
-                        sev,
-                        VALID_THRESHOLDS.join(", ")
-                    )
-                })),
-            ));
+            return Err(AppError::BadRequest(format!(
+                "Invalid severity '{}'. Must be one of: {}",
+                sev,
+                VALID_THRESHOLDS.join(", ")
+            )));
         }
     }
 
     let (items, total) = tokio::try_join!(
         repo.query_alert_history(limit, severity, delivered),
         repo.count_alert_events(severity, delivered),
-    )
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({ "error": e.to_string() })),
-        )
-    })?;
+    )?;
 
     Ok(Json(AlertHistoryResponse { total, items }))
 }
@@ -0,0 +1,208 @@
+//! Several fee history queries in one request.
+//!
+//! Routes:
+//! - `POST /fees/query` — a list of named `/fees/history` queries, run
+//!   together; each result, or its problem document, is returned under
+//!   its name
+
+use std::collections::{BTreeMap, HashSet};
+
+use axum::{extract::State, Json};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::fees::{stored_fee_history, window_fee_history, FeeHistoryQuery, FeesState};
+use crate::error::AppError;
+
+/// Most queries one request may hold.
+pub const MAX_BATCH_QUERIES: usize = 20;
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(Debug, Deserialize)]
+pub struct BatchQueryRequest {
+    pub queries: Vec<NamedQuery>,
+}
+
+/// A `/fees/history` query and the name its result is returned under
+#[derive(Debug, Deserialize)]
+pub struct NamedQuery {
+    pub name: String,
+    /// The query parameters `/fees/history` accepts.
+    #[serde(flatten)]
+    pub query: FeeHistoryQuery,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchQueryResponse {
+    pub results: BTreeMap<String, BatchResult>,
+}
+
+/// What `/fees/history` would have answered for one query
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum BatchResult {
+    Ok(Value),
+    /// The problem document `/fees/history` would have answered with.
+    Err(Value),
+}
+
+/// `POST /fees/query` — run every query; one failing does not fail the
+/// others.
+pub async fn batch_query(
+    State(state): State<FeesState>,
+    Json(request): Json<BatchQueryRequest>,
+) -> Result<Json<BatchQueryResponse>, AppError> {
+    let bad_request = AppError::BadRequest;
+    if request.queries.is_empty() || request.queries.len() > MAX_BATCH_QUERIES {
+        return Err(bad_request(format!(
+            "Send between 1 and {} queries",
+            MAX_BATCH_QUERIES
+        )));
+    }
+    let mut names = HashSet::new();
+    for query in &request.queries {
+        if query.name.is_empty() || query.name.len() > MAX_NAME_LEN {
+            return Err(bad_request(format!(
+                "Query names must be 1 to {} characters",
+                MAX_NAME_LEN
+            )));
+        }
+        if !names.insert(query.name.as_str()) {
+            return Err(bad_request(format!("Duplicate query name: {}", query.name)));
+        }
+    }
+
+    let results = join_all(request.queries.into_iter().map(|named| {
+        let state = &state;
+        async move {
+            let result = if named.query.is_stored_range() {
+                stored_fee_history(state, named.query)
+                    .await
+                    .and_then(|page| to_value(&page))
+            } else {
+                window_fee_history(state, named.query.window)
+                    .await
+                    .and_then(|history| to_value(&history))
+            };
+            let result = match result {
+                Ok(value) => BatchResult::Ok(value),
+                Err(err) => BatchResult::Err(err.problem()),
+            };
+            (named.name, result)
+        }
+    }))
+    .await;
+
+    Ok(Json(BatchQueryResponse {
+        results: results.into_iter().collect(),
+    }))
+}
+
+fn to_value<T: Serialize>(result: &T) -> Result<Value, AppError> {
+    serde_json::to_value(result)
+        .map_err(|err| AppError::Unknown(format!("Failed to serialize result: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration as StdDuration;
+
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use chrono::{Duration, Utc};
+    use http_body_util::BodyExt;
+    use serde_json::json;
+    use tokio::sync::{Mutex, RwLock};
+    use tower::ServiceExt;
+
+    use crate::api::fees::FeesApiState;
+    use crate::cache::ResponseCache;
+    use crate::insights::FeeDataPoint;
+    use crate::repository::FeeRepository;
+    use crate::store::FeeHistoryStore;
+
+    async fn post_queries(app: &Router, body: Value) -> (StatusCode, Value) {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/fees/query")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn answers_each_named_query() {
+        let pool = crate::db::create_pool("sqlite::memory:").await.unwrap();
+        let repository = Arc::new(FeeRepository::new(pool));
+        let points: Vec<FeeDataPoint> = (0..3)
+            .map(|idx| FeeDataPoint {
+                fee_amount: 100 * (idx + 1),
+                timestamp: Utc::now() - Duration::minutes(30 - idx as i64),
+                transaction_hash: format!("tx-{}", idx),
+                ledger_sequence: 1_000 + idx,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        repository.insert_fee_points(&points).await.unwrap();
+        let mut store = FeeHistoryStore::new(100);
+        for point in &points {
+            store.push(point.clone());
+        }
+        let app = Router::new()
+            .route("/fees/query", post(batch_query))
+            .with_state(Arc::new(FeesApiState {
+                fee_stats_provider: None,
+                fee_cache: Arc::new(Mutex::new(ResponseCache::new(StdDuration::from_secs(5)))),
+                fee_store: Arc::new(RwLock::new(store)),
+                insights_engine: None,
+                repository: Some(repository),
+            }));
+
+        let (status, body) = post_queries(
+            &app,
+            json!({ "queries": [
+                { "name": "last_hour", "window": "1h" },
+                { "name": "expensive", "min_fee": 200, "limit": 1 },
+                { "name": "hourly", "resolution": "1h" },
+                { "name": "broken", "window": "2d" },
+            ]}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let results = &body["results"];
+        assert_eq!(results["last_hour"]["data_points"], 3);
+        assert_eq!(results["expensive"]["total"], 2);
+        assert_eq!(results["expensive"]["items"][0]["fee_amount"], 200);
+        assert!(results["expensive"]["next_cursor"].is_string());
+        assert_eq!(results["hourly"]["resolution"], "1h");
+        assert_eq!(results["broken"]["status"], 400);
+        assert_eq!(results["broken"]["code"], "invalid_request");
+        assert!(results["broken"]["detail"].is_string());
+
+        let (status, _) = post_queries(
+            &app,
+            json!({ "queries": [{ "name": "a" }, { "name": "a", "window": "6h" }] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = post_queries(&app, json!({ "queries": [] })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
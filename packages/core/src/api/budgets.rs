@@ -0,0 +1,201 @@
+//! Per-account fee budgets.
+//!
+//! Routes (spend is only seen with `TRACK_FEE_ACCOUNTS` enabled):
+//! - `GET    /fees/budgets`                  — spend against every budget
+//! - `PUT    /fees/budgets/:account/:period` — set a `daily` or `weekly`
+//!   budget from a `{"limit": stroops}` body
+//! - `DELETE /fees/budgets/:account/:period` — stop tracking a budget
+//!
+//! Crossing a budget publishes a `budget_exceeded` event on
+//! `/insights/events`.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use tokio::sync::RwLock;
+
+use crate::accounts::track_budget;
+use crate::error::AppError;
+use crate::insights::{BudgetPeriod, BudgetStatus, FeeBudget, FeeInsightsEngine};
+use crate::repository::FeeRepository;
+
+/// Shared state for the budget routes.
+pub struct BudgetsState {
+    pub insights_engine: Arc<RwLock<FeeInsightsEngine>>,
+    pub repository: Arc<FeeRepository>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetBudgetRequest {
+    /// In stroops.
+    pub limit: u64,
+}
+
+fn parse_period(period: &str) -> Result<BudgetPeriod, AppError> {
+    BudgetPeriod::parse(period).ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "Invalid period '{}'. Must be one of: daily, weekly",
+            period
+        ))
+    })
+}
+
+/// `GET /fees/budgets` — spend against every budget this period.
+pub async fn list_budgets(State(state): State<Arc<BudgetsState>>) -> Json<Vec<BudgetStatus>> {
+    Json(state.insights_engine.read().await.get_budget_statuses())
+}
+
+/// `PUT /fees/budgets/:account/:period` — set or replace a budget.
+pub async fn set_budget(
+    State(state): State<Arc<BudgetsState>>,
+    Path((account, period)): Path<(String, String)>,
+    Json(body): Json<SetBudgetRequest>,
+) -> Result<Json<BudgetStatus>, AppError> {
+    let period = parse_period(&period)?;
+    if body.limit == 0 {
+        return Err(AppError::BadRequest(
+            "Budget limit must be positive".to_string(),
+        ));
+    }
+
+    let budget = FeeBudget {
+        account: account.clone(),
+        period,
+        limit: body.limit,
+    };
+    state.repository.upsert_fee_budget(&budget).await?;
+    track_budget(&state.repository, &state.insights_engine, budget).await?;
+
+    state
+        .insights_engine
+        .read()
+        .await
+        .get_budget_statuses()
+        .into_iter()
+        .find(|status| status.account == account && status.period == period)
+        .map(Json)
+        .ok_or_else(|| AppError::Unknown("Budget was not tracked".to_string()))
+}
+
+/// `DELETE /fees/budgets/:account/:period` — stop tracking a budget.
+pub async fn delete_budget(
+    State(state): State<Arc<BudgetsState>>,
+    Path((account, period)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    let period = parse_period(&period)?;
+    let deleted = state.repository.delete_fee_budget(&account, period).await?;
+    let untracked = state
+        .insights_engine
+        .write()
+        .await
+        .remove_fee_budget(&account, period);
+
+    if deleted || untracked {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::NotFound(format!(
+            "No {} budget for account {}",
+            period.as_str(),
+            account
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use chrono::Utc;
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::db::create_pool;
+    use crate::insights::{EnvelopeDetails, FeeDataPoint, InsightsConfig};
+
+    #[tokio::test]
+    async fn sets_lists_and_deletes_budgets() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repository = Arc::new(FeeRepository::new(pool).with_fee_accounts(true));
+        repository
+            .insert_fee_points(&[FeeDataPoint {
+                fee_amount: 400,
+                timestamp: BudgetPeriod::Weekly.start(Utc::now()),
+                transaction_hash: "tx_1".to_string(),
+                ledger_sequence: 1,
+                envelope: Some(EnvelopeDetails {
+                    fee_account: Some("GEXCHANGE".to_string()),
+                    ..EnvelopeDetails::default()
+                }),
+                soroban: None,
+            }])
+            .await
+            .unwrap();
+        let state = Arc::new(BudgetsState {
+            insights_engine: Arc::new(RwLock::new(FeeInsightsEngine::new(
+                InsightsConfig::default(),
+            ))),
+            repository: repository.clone(),
+        });
+        let app = Router::new()
+            .route("/fees/budgets", get(list_budgets))
+            .route(
+                "/fees/budgets/:account/:period",
+                axum::routing::put(set_budget).delete(delete_budget),
+            )
+            .with_state(state);
+        let send = |method: &str, uri: &str, body: Option<&str>| {
+            let app = app.clone();
+            let mut request = Request::builder().method(method).uri(uri);
+            if body.is_some() {
+                request = request.header("content-type", "application/json");
+            }
+            let request = request
+                .body(body.map(|b| Body::from(b.to_string())).unwrap_or_default())
+                .unwrap();
+            async move {
+                let resp = app.oneshot(request).await.unwrap();
+                let status = resp.status();
+                let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&bytes).unwrap_or_default(),
+                )
+            }
+        };
+
+        let (status, json) = send(
+            "PUT",
+            "/fees/budgets/GEXCHANGE/weekly",
+            Some(r#"{"limit": 1000}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["spent"], 400);
+        assert_eq!(json["remaining"], 600);
+        assert_eq!(json["exceeded"], false);
+        assert_eq!(repository.list_fee_budgets().await.unwrap().len(), 1);
+
+        let (status, _) = send(
+            "PUT",
+            "/fees/budgets/GEXCHANGE/monthly",
+            Some(r#"{"limit": 1000}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (_, json) = send("GET", "/fees/budgets", None).await;
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["period"], "weekly");
+
+        let (status, _) = send("DELETE", "/fees/budgets/GEXCHANGE/weekly", None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send("DELETE", "/fees/budgets/GEXCHANGE/weekly", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(repository.list_fee_budgets().await.unwrap().is_empty());
+    }
+}
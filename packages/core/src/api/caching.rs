@@ -0,0 +1,296 @@
+//! `Cache-Control` and `Expires` for the fee and insights routes.
+//!
+//! Each successful `GET` or `HEAD` response gets a lifetime from the
+//! route's [`CachePolicy`], so CDNs and reverse proxies can cache the API:
+//! - live data (current fees, insights, congestion) — 10 seconds, then
+//!   served stale for 20 more while revalidating
+//! - historical ranges — a minute, or a day and `immutable` once the
+//!   requested range (`end`/`to`) ended more than an hour ago and can no
+//!   longer change
+//! - per-client data (fee accounts and budgets) — `private`
+//!
+//! Handlers that set their own `Cache-Control` keep it. Either way
+//! `Expires` is set to match its `max-age`, and the response varies on the
+//! client's credentials, so a shared cache never serves one client's
+//! response to another. Every `GET` route also answers `HEAD` with the
+//! same headers and no body.
+//!
+//! Routes get the same policy under `/v1` and `/networks/{name}`.
+
+use axum::{
+    extract::{Query, Request},
+    http::{header, HeaderMap, HeaderValue, Method, Uri},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+
+use super::headers::{cache_control, last_modified};
+use super::networks::route_path;
+
+/// Request headers carrying the client's credentials.
+const VARY_CREDENTIALS: &str = "x-api-key, authorization";
+
+/// Seconds live data may be cached, then served stale while revalidating.
+const LIVE_MAX_AGE: u32 = 10;
+const LIVE_SWR: u32 = 20;
+
+/// Seconds a range that may still change may be cached.
+const OPEN_RANGE_MAX_AGE: u32 = 60;
+
+/// Seconds a range that has settled may be cached.
+const SETTLED_RANGE_MAX_AGE: u32 = 86_400;
+
+/// How long after its end a range is taken to be final. Late fees only
+/// arrive through backfills and the rollups of the last bucket.
+const SETTLE_AFTER_MINUTES: i64 = 60;
+
+/// How long a route's responses may be cached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Changes with every processed batch.
+    Live,
+    /// A time range; long-lived once it lies far enough in the past.
+    Range,
+    /// Only for the client that asked.
+    Private,
+}
+
+impl CachePolicy {
+    /// Policy of a fee or insights route, on any network and API version.
+    /// `None` for streams and routes outside that API.
+    pub fn for_path(path: &str) -> Option<Self> {
+        let path = route_path(path);
+        const RANGES: &[&str] = &[
+            "/fees/history",
+            "/fees/rollups",
+            "/fees/heatmap",
+            "/fees/stats",
+            "/fees/export",
+            "/insights/range",
+            "/insights/compare",
+            "/insights/snapshots",
+            "/insights/surges",
+            "/congestion/history",
+            "/ledgers/",
+        ];
+        const PRIVATE: &[&str] = &["/fees/accounts", "/fees/budgets"];
+        const STREAMS: &[&str] = &["/ws/", "/events/", "/insights/events"];
+
+        if STREAMS.iter().any(|prefix| path.starts_with(prefix)) {
+            None
+        } else if RANGES.iter().any(|prefix| path.starts_with(prefix)) {
+            Some(Self::Range)
+        } else if PRIVATE.iter().any(|prefix| path.starts_with(prefix)) {
+            Some(Self::Private)
+        } else if ["/fees/", "/insights", "/congestion"]
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+        {
+            Some(Self::Live)
+        } else {
+            None
+        }
+    }
+
+    /// `Cache-Control` for a request to `uri`, at `now`.
+    pub fn cache_control(self, uri: &Uri, now: DateTime<Utc>) -> HeaderValue {
+        match self {
+            Self::Live => cache_control(LIVE_MAX_AGE, LIVE_SWR),
+            Self::Range if range_settled(uri, now) => {
+                HeaderValue::from_str(&format!("max-age={}, immutable", SETTLED_RANGE_MAX_AGE))
+                    .unwrap_or_else(|_| HeaderValue::from_static("no-store"))
+            }
+            Self::Range => cache_control(OPEN_RANGE_MAX_AGE, OPEN_RANGE_MAX_AGE),
+            Self::Private => {
+                HeaderValue::from_str(&format!("private, max-age={}", OPEN_RANGE_MAX_AGE))
+                    .unwrap_or_else(|_| HeaderValue::from_static("no-store"))
+            }
+        }
+    }
+}
+
+/// End of the range a request asks for, under either name routes use.
+#[derive(Debug, Deserialize)]
+struct RangeEnd {
+    end: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+/// Whether the request's range ended an hour or more before `now`.
+fn range_settled(uri: &Uri, now: DateTime<Utc>) -> bool {
+    Query::<RangeEnd>::try_from_uri(uri)
+        .ok()
+        .and_then(|Query(range)| range.end.or(range.to))
+        .is_some_and(|end| end <= now - Duration::minutes(SETTLE_AFTER_MINUTES))
+}
+
+/// `max-age` of a `Cache-Control` value, unless it forbids storing.
+fn max_age(headers: &HeaderMap) -> Option<i64> {
+    let value = headers.get(header::CACHE_CONTROL)?.to_str().ok()?;
+    if value.contains("no-store") || value.contains("no-cache") {
+        return None;
+    }
+    value
+        .split(',')
+        .find_map(|directive| directive.trim().strip_prefix("max-age="))
+        .and_then(|seconds| seconds.parse().ok())
+}
+
+/// Set `Cache-Control` from the route's policy, unless the handler did, a
+/// matching `Expires` and `Vary` on the credentials, on successful `GET`
+/// and `HEAD` responses.
+pub async fn cache_headers(request: Request, next: Next) -> Response {
+    let cacheable = matches!(*request.method(), Method::GET | Method::HEAD);
+    let policy = CachePolicy::for_path(request.uri().path());
+    let uri = request.uri().clone();
+
+    let mut response = next.run(request).await;
+    let status = response.status();
+    if !cacheable || !(status.is_success() || status.as_u16() == 304) {
+        return response;
+    }
+
+    let now = Utc::now();
+    let headers = response.headers_mut();
+    headers.append(header::VARY, HeaderValue::from_static(VARY_CREDENTIALS));
+    if !headers.contains_key(header::CACHE_CONTROL) {
+        if let Some(policy) = policy {
+            headers.insert(header::CACHE_CONTROL, policy.cache_control(&uri, now));
+        }
+    }
+    if !headers.contains_key(header::EXPIRES) {
+        if let Some(seconds) = max_age(headers) {
+            headers.insert(
+                header::EXPIRES,
+                last_modified(now + Duration::seconds(seconds)),
+            );
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get, Json, Router};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    #[test]
+    fn routes_get_the_policy_of_their_data() {
+        assert_eq!(
+            CachePolicy::for_path("/fees/current"),
+            Some(CachePolicy::Live)
+        );
+        assert_eq!(
+            CachePolicy::for_path("/insights/averages"),
+            Some(CachePolicy::Live)
+        );
+        assert_eq!(
+            CachePolicy::for_path("/fees/export.csv"),
+            Some(CachePolicy::Range)
+        );
+        assert_eq!(
+            CachePolicy::for_path("/ledgers/42/fees"),
+            Some(CachePolicy::Range)
+        );
+        assert_eq!(
+            CachePolicy::for_path("/fees/accounts/GABC"),
+            Some(CachePolicy::Private)
+        );
+        assert_eq!(
+            CachePolicy::for_path("/networks/testnet/fees/history"),
+            Some(CachePolicy::Range)
+        );
+        assert_eq!(
+            CachePolicy::for_path("/v1/networks/testnet/fees/accounts/GABC"),
+            Some(CachePolicy::Private)
+        );
+        assert_eq!(
+            CachePolicy::for_path("/v1/insights"),
+            Some(CachePolicy::Live)
+        );
+        assert_eq!(CachePolicy::for_path("/ws/fees"), None);
+        assert_eq!(CachePolicy::for_path("/networks/testnet/ws/fees"), None);
+        assert_eq!(CachePolicy::for_path("/health"), None);
+    }
+
+    #[test]
+    fn ranges_are_immutable_only_once_settled() {
+        let now = Utc::now();
+        let at = |ago: Duration| {
+            (now - ago)
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+                .replace(':', "%3A")
+        };
+        let control = |uri: String| CachePolicy::Range.cache_control(&uri.parse().unwrap(), now);
+
+        assert_eq!(
+            control(format!("/fees/history?end={}", at(Duration::days(2)))),
+            "max-age=86400, immutable"
+        );
+        assert_eq!(
+            control(format!("/insights/range?to={}", at(Duration::minutes(5)))),
+            "max-age=60, stale-while-revalidate=60"
+        );
+        assert_eq!(
+            control("/fees/rollups?hours=24".to_string()),
+            "max-age=60, stale-while-revalidate=60"
+        );
+    }
+
+    #[tokio::test]
+    async fn layer_sets_headers_and_head_answers_without_a_body() {
+        let app = Router::new()
+            .route("/fees/trend", get(|| async { Json(serde_json::json!({})) }))
+            .route(
+                "/insights",
+                get(|| async { ([(header::CACHE_CONTROL, "max-age=30")], "{}") }),
+            )
+            .route(
+                "/fees/history",
+                get(|| async { (StatusCode::BAD_REQUEST, "bad") }),
+            )
+            .layer(axum::middleware::from_fn(cache_headers));
+        let send = |method: Method, uri: &str| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            app.clone().oneshot(request)
+        };
+
+        let response = send(Method::GET, "/fees/trend").await.unwrap();
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "max-age=10, stale-while-revalidate=20"
+        );
+        assert!(response.headers().contains_key(header::EXPIRES));
+        assert_eq!(response.headers()[header::VARY], "x-api-key, authorization");
+
+        let response = send(Method::HEAD, "/fees/trend").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "max-age=10, stale-while-revalidate=20"
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.is_empty());
+
+        // A handler's own lifetime is kept and sets Expires
+        let response = send(Method::GET, "/insights").await.unwrap();
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "max-age=30");
+        let expires = response.headers()[header::EXPIRES].to_str().unwrap();
+        let expires = DateTime::parse_from_rfc2822(&expires.replace("GMT", "+0000")).unwrap();
+        let ahead = expires.with_timezone(&Utc) - Utc::now();
+        assert!(ahead > Duration::seconds(25) && ahead <= Duration::seconds(30));
+
+        // Errors are not cached
+        let response = send(Method::GET, "/fees/history").await.unwrap();
+        assert!(!response.headers().contains_key(header::CACHE_CONTROL));
+        assert!(!response.headers().contains_key(header::EXPIRES));
+    }
+}
@@ -0,0 +1,147 @@
+//! Fee comparison between two time ranges.
+//!
+//! Routes:
+//! - `GET /insights/compare?from=…&to=…&baseline_from=…&baseline_to=…` —
+//!   stats for both ranges and the percent change between them. `to`
+//!   defaults to now, `from` to 7 days before `to`, and the baseline to the
+//!   equally long range just before `from`.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::comparison::{compare_ranges, RangeComparison};
+use crate::error::AppError;
+use crate::repository::FeeRepository;
+
+/// Shared state for the comparison route.
+pub type CompareState = Arc<FeeRepository>;
+
+#[derive(Debug, Deserialize)]
+pub struct CompareQuery {
+    /// RFC 3339 timestamps.
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub baseline_from: Option<DateTime<Utc>>,
+    pub baseline_to: Option<DateTime<Utc>>,
+}
+
+/// `GET /insights/compare` — how fees in one range compare to another.
+pub async fn compare(
+    State(repo): State<CompareState>,
+    Query(params): Query<CompareQuery>,
+) -> Result<Json<RangeComparison>, AppError> {
+    let to = params.to.unwrap_or_else(Utc::now);
+    let from = params.from.unwrap_or(to - chrono::Duration::days(7));
+    let baseline_to = params.baseline_to.unwrap_or(from);
+    let baseline_from = params.baseline_from.unwrap_or(baseline_to - (to - from));
+
+    if from >= to || baseline_from >= baseline_to {
+        return Err(AppError::BadRequest(
+            "Each range must start before it ends".to_string(),
+        ));
+    }
+
+    let comparison = compare_ranges(&repo, (baseline_from, baseline_to), (from, to)).await?;
+
+    Ok(Json(comparison))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::db::create_pool;
+    use crate::insights::{FeeDataPoint, SurgeEpisode};
+    use crate::rollup::roll_up_closed_buckets;
+
+    #[tokio::test]
+    async fn compares_a_range_with_the_one_before_it() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = Arc::new(FeeRepository::new(pool));
+        let now = Utc::now();
+        let hour = |h: i64| now - chrono::Duration::hours(h);
+
+        // Last hour: 200 stroops, two hours back: 100
+        let points: Vec<FeeDataPoint> = [(2, 100), (1, 200)]
+            .into_iter()
+            .flat_map(|(hours_ago, fee_amount)| {
+                (0..10).map(move |i| FeeDataPoint {
+                    fee_amount,
+                    timestamp: hour(hours_ago) + chrono::Duration::minutes(5 * i + 2),
+                    transaction_hash: format!("tx_{}_{}", hours_ago, i),
+                    ledger_sequence: i as u64,
+                    envelope: None,
+                    soroban: None,
+                })
+            })
+            .collect();
+        repo.insert_fee_points(&points).await.unwrap();
+        roll_up_closed_buckets(&repo, now).await.unwrap();
+        repo.insert_surge_episodes(&[SurgeEpisode {
+            start_time: hour(1) - chrono::Duration::minutes(10),
+            end_time: hour(1) + chrono::Duration::minutes(20),
+            duration: chrono::Duration::minutes(30),
+            peak_fee: 400,
+            baseline_fee: 100.0,
+            sample_count: 4,
+        }])
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/insights/compare", get(compare))
+            .with_state(repo);
+        let get_json = |uri: String| {
+            let app = app.clone();
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            async move {
+                let resp = app.oneshot(request).await.unwrap();
+                let status = resp.status();
+                let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&bytes).unwrap(),
+                )
+            }
+        };
+        let param = |t: DateTime<Utc>| t.to_rfc3339().replace('+', "%2B");
+
+        let (status, json) = get_json(format!(
+            "/insights/compare?from={}&to={}",
+            param(hour(1)),
+            param(now)
+        ))
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["baseline"]["sample_count"], 10);
+        assert_eq!(json["current"]["sample_count"], 10);
+        assert_eq!(json["baseline"]["avg_fee"], 100.0);
+        assert_eq!(json["avg_fee_change_pct"], 100.0);
+        assert_eq!(json["p90_fee_change_pct"], 100.0);
+        assert_eq!(json["baseline"]["congestion_seconds"], 600);
+        assert_eq!(json["current"]["congestion_seconds"], 1_200);
+        assert_eq!(json["congestion_change_pct"], 100.0);
+
+        let (status, _) = get_json(format!(
+            "/insights/compare?from={}&to={}",
+            param(now),
+            param(hour(1))
+        ))
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
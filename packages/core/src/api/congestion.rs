@@ -0,0 +1,297 @@
+//! Congestion now and in the past.
+//!
+//! Routes:
+//! - `GET /congestion` — the detector's current state, the fee ratio it
+//!   is judged on and the ongoing episode, if any
+//! - `GET /congestion/history?limit=…&cursor=…` — congestion episodes,
+//!   newest first: when each started and ended, the most congested state
+//!   reached and the peak fee multiplier. `limit` defaults to 50 and is
+//!   capped at 500; each page's `next_cursor` fetches the following one
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::error::AppError;
+use crate::insights::{CongestionEpisode, CongestionState, FeeInsightsEngine, TrendIndicator};
+use crate::repository::FeeRepository;
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+/// Shared state for the congestion routes
+#[derive(Clone)]
+pub struct CongestionApiState {
+    pub insights_engine: Arc<RwLock<FeeInsightsEngine>>,
+    pub repository: Arc<FeeRepository>,
+}
+
+/// A congestion episode with its length
+#[derive(Debug, Serialize)]
+pub struct EpisodeView {
+    #[serde(flatten)]
+    pub episode: CongestionEpisode,
+    /// `None` while the episode is ongoing.
+    pub duration_seconds: Option<i64>,
+}
+
+impl From<CongestionEpisode> for EpisodeView {
+    fn from(episode: CongestionEpisode) -> Self {
+        Self {
+            duration_seconds: episode
+                .end_time
+                .map(|end| (end - episode.start_time).num_seconds()),
+            episode,
+        }
+    }
+}
+
+/// `GET /congestion` response
+#[derive(Debug, Serialize)]
+pub struct CurrentCongestion {
+    pub state: CongestionState,
+    pub trend: TrendIndicator,
+    /// Mean fee over baseline of the latest batch.
+    pub fee_ratio: Option<f64>,
+    pub score: Option<f64>,
+    pub capacity_utilization: Option<f64>,
+    /// `None` while the network is not congested.
+    pub episode: Option<EpisodeView>,
+    /// `None` before any fees have been processed.
+    pub last_updated: Option<DateTime<Utc>>,
+}
+
+/// `GET /congestion/history` response
+#[derive(Debug, Serialize)]
+pub struct CongestionHistory {
+    pub items: Vec<EpisodeView>,
+    /// Fetches the following page; `None` on the last one.
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CongestionHistoryQuery {
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+/// `GET /congestion` — the current congestion state.
+pub async fn current_congestion(
+    State(state): State<Arc<CongestionApiState>>,
+) -> Result<Json<CurrentCongestion>, AppError> {
+    let (trends, fee_ratio, last_updated) = {
+        let engine = state.insights_engine.read().await;
+        (
+            engine.get_congestion_trends(),
+            engine.get_fee_ratio(),
+            engine.get_last_update(),
+        )
+    };
+    let episode = state
+        .repository
+        .open_congestion_episode()
+        .await?
+        .filter(|_| trends.congestion_state != CongestionState::Normal);
+
+    Ok(Json(CurrentCongestion {
+        state: trends.congestion_state,
+        trend: trends.current_trend,
+        fee_ratio,
+        score: trends.congestion_score,
+        capacity_utilization: trends.capacity_utilization,
+        episode: episode.map(EpisodeView::from),
+        last_updated,
+    }))
+}
+
+/// `GET /congestion/history` — a page of congestion episodes.
+pub async fn congestion_history(
+    State(state): State<Arc<CongestionApiState>>,
+    Query(params): Query<CongestionHistoryQuery>,
+) -> Result<Json<CongestionHistory>, AppError> {
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let before = params
+        .cursor
+        .as_deref()
+        .map(|token| {
+            decode_cursor(token).ok_or_else(|| AppError::BadRequest("Invalid cursor".to_string()))
+        })
+        .transpose()?;
+
+    // One extra row tells whether another page follows
+    let mut episodes = state
+        .repository
+        .fetch_congestion_episodes(before, limit + 1)
+        .await?;
+    let next_cursor = if episodes.len() > limit {
+        episodes.truncate(limit);
+        episodes.last().map(|episode| encode_cursor(episode.id))
+    } else {
+        None
+    };
+
+    Ok(Json(CongestionHistory {
+        items: episodes.into_iter().map(EpisodeView::from).collect(),
+        next_cursor,
+    }))
+}
+
+fn encode_cursor(id: i64) -> String {
+    URL_SAFE_NO_PAD.encode(format!("e|{}", id))
+}
+
+fn decode_cursor(token: &str) -> Option<i64> {
+    let raw = String::from_utf8(URL_SAFE_NO_PAD.decode(token).ok()?).ok()?;
+    raw.strip_prefix("e|")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use chrono::Duration;
+    use http_body_util::BodyExt;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use crate::db::create_pool;
+    use crate::insights::{CongestionTransition, InsightsConfig};
+
+    fn transition(
+        from: CongestionState,
+        to: CongestionState,
+        at: DateTime<Utc>,
+        fee_ratio: f64,
+    ) -> CongestionTransition {
+        CongestionTransition {
+            from,
+            to,
+            at,
+            fee_ratio,
+            capacity_congested: false,
+        }
+    }
+
+    async fn get_json(app: &Router, uri: &str) -> (StatusCode, Value) {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn episodes_are_recorded_from_transitions_and_paged() {
+        use CongestionState::*;
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repository = Arc::new(FeeRepository::new(pool));
+        let start = Utc::now() - Duration::hours(5);
+        let at = |minutes: i64| start + Duration::minutes(minutes);
+
+        // Elevated, then congested, then normal again
+        repository
+            .record_congestion_episodes(
+                &[transition(Normal, Elevated, at(0), 1.6)],
+                Elevated,
+                Some(1.7),
+                at(0),
+            )
+            .await
+            .unwrap();
+        repository
+            .record_congestion_episodes(
+                &[transition(Elevated, Congested, at(10), 2.5)],
+                Congested,
+                Some(3.1),
+                at(10),
+            )
+            .await
+            .unwrap();
+        repository
+            .record_congestion_episodes(
+                &[
+                    transition(Congested, Elevated, at(20), 1.9),
+                    transition(Elevated, Normal, at(30), 1.1),
+                ],
+                Normal,
+                Some(1.1),
+                at(30),
+            )
+            .await
+            .unwrap();
+        // A second episode, still open
+        repository
+            .record_congestion_episodes(
+                &[transition(Normal, Elevated, at(60), 1.8)],
+                Elevated,
+                Some(1.8),
+                at(60),
+            )
+            .await
+            .unwrap();
+
+        let app = Router::new()
+            .route("/congestion", get(current_congestion))
+            .route("/congestion/history", get(congestion_history))
+            .with_state(Arc::new(CongestionApiState {
+                insights_engine: Arc::new(RwLock::new(FeeInsightsEngine::new(
+                    InsightsConfig::default(),
+                ))),
+                repository: repository.clone(),
+            }));
+
+        let (status, page) = get_json(&app, "/congestion/history?limit=1").await;
+        assert_eq!(status, StatusCode::OK);
+        let open = &page["items"][0];
+        assert_eq!(open["peak_state"], "elevated");
+        assert!(open["end_time"].is_null());
+        assert!(open["duration_seconds"].is_null());
+
+        let cursor = page["next_cursor"].as_str().unwrap();
+        let (_, page) = get_json(
+            &app,
+            &format!("/congestion/history?limit=1&cursor={}", cursor),
+        )
+        .await;
+        let closed = &page["items"][0];
+        assert_eq!(closed["peak_state"], "congested");
+        assert_eq!(closed["peak_multiplier"], 3.1);
+        assert_eq!(closed["duration_seconds"], 30 * 60);
+        assert!(page["next_cursor"].is_null());
+
+        let (status, _) = get_json(&app, "/congestion/history?cursor=bogus").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // The engine has seen nothing, so no episode is ongoing for it
+        let (status, current) = get_json(&app, "/congestion").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(current["state"], "normal");
+        assert!(current["episode"].is_null());
+
+        // Back to normal without a transition, as after a restart
+        repository
+            .record_congestion_episodes(&[], Normal, Some(1.0), at(90))
+            .await
+            .unwrap();
+        assert!(repository
+            .open_congestion_episode()
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
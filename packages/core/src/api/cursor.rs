@@ -0,0 +1,145 @@
+//! Opaque cursors for paging through stored fee history.
+//!
+//! A cursor names the last point or rollup a client received and the next
+//! page starts right after it. Cursors are found through the timestamp
+//! index, so the millionth page costs what the first does, where an offset
+//! has to step over every row before it.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+
+use super::fees::FeeHistoryItems;
+use crate::repository::{FeeRepository, PointFilter, PointKey};
+use crate::rollup::Resolution;
+
+/// Position in stored fee history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryCursor {
+    /// After this raw fee data point.
+    Point(PointKey),
+    /// After the rollup whose bucket starts at this time.
+    Rollup(DateTime<Utc>),
+}
+
+impl HistoryCursor {
+    /// URL-safe token handed to clients.
+    pub fn encode(&self) -> String {
+        let raw = match self {
+            Self::Point(key) => format!("p|{}|{}", key.timestamp.to_rfc3339(), key.id),
+            Self::Rollup(bucket_start) => format!("r|{}", bucket_start.to_rfc3339()),
+        };
+        URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    /// `None` for a token [`HistoryCursor::encode`] did not produce.
+    pub fn decode(token: &str) -> Option<Self> {
+        let raw = String::from_utf8(URL_SAFE_NO_PAD.decode(token).ok()?).ok()?;
+        let timestamp = |value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .ok()
+                .map(|t| t.with_timezone(&Utc))
+        };
+        let parts: Vec<&str> = raw.split('|').collect();
+        match parts.as_slice() {
+            ["p", at, id] => Some(Self::Point(PointKey {
+                timestamp: timestamp(at)?,
+                id: id.parse().ok()?,
+            })),
+            ["r", bucket_start] => Some(Self::Rollup(timestamp(bucket_start)?)),
+            _ => None,
+        }
+    }
+
+    /// Whether the cursor continues a listing at `resolution`, `None`
+    /// being raw points.
+    pub fn matches(&self, resolution: Option<Resolution>) -> bool {
+        matches!(
+            (self, resolution),
+            (Self::Point(_), None) | (Self::Rollup(_), Some(_))
+        )
+    }
+}
+
+/// Up to `limit` points matching `filter`, or rollups at `resolution`, in
+/// `[start, end)` after `after` and after skipping `offset`, with the
+/// cursor of the following page; `None` on the last page. A cursor that
+/// does not match `resolution` is ignored, as is `filter` for rollups.
+#[allow(clippy::too_many_arguments)]
+pub async fn fetch_page(
+    repository: &FeeRepository,
+    resolution: Option<Resolution>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    filter: &PointFilter,
+    after: Option<&HistoryCursor>,
+    limit: usize,
+    offset: usize,
+) -> Result<(FeeHistoryItems, Option<HistoryCursor>), sqlx::Error> {
+    // One extra row tells whether another page follows
+    match resolution {
+        None => {
+            let after = match after {
+                Some(HistoryCursor::Point(key)) => Some(key),
+                _ => None,
+            };
+            let mut rows = repository
+                .fetch_page_between(start, end, filter, after, limit + 1, offset)
+                .await?;
+            let more = rows.len() > limit;
+            rows.truncate(limit);
+            let next = rows
+                .last()
+                .filter(|_| more)
+                .map(|(key, _)| HistoryCursor::Point(key.clone()));
+            let points = rows.into_iter().map(|(_, point)| point).collect();
+            Ok((FeeHistoryItems::Raw(points), next))
+        }
+        Some(resolution) => {
+            let after = match after {
+                Some(HistoryCursor::Rollup(bucket_start)) => Some(*bucket_start),
+                _ => None,
+            };
+            let mut rollups = repository
+                .fetch_rollup_page_between(resolution, start, end, after, limit + 1, offset)
+                .await?;
+            let more = rollups.len() > limit;
+            rollups.truncate(limit);
+            let next = rollups
+                .last()
+                .filter(|_| more)
+                .map(|rollup| HistoryCursor::Rollup(rollup.bucket_start));
+            Ok((FeeHistoryItems::Rollups(rollups), next))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursors_round_trip_and_reject_tampering() {
+        let point = HistoryCursor::Point(PointKey {
+            timestamp: "2024-05-01T12:00:00.250Z".parse().unwrap(),
+            id: 42,
+        });
+        let rollup = HistoryCursor::Rollup("2024-05-01T12:00:00Z".parse().unwrap());
+        for cursor in [&point, &rollup] {
+            let token = cursor.encode();
+            assert!(token
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+            assert_eq!(HistoryCursor::decode(&token).as_ref(), Some(cursor));
+        }
+
+        assert!(point.matches(None));
+        assert!(!point.matches(Some(Resolution::OneHour)));
+        assert!(rollup.matches(Some(Resolution::OneHour)));
+
+        assert_eq!(HistoryCursor::decode("not a cursor"), None);
+        assert_eq!(
+            HistoryCursor::decode(&URL_SAFE_NO_PAD.encode("p|2024-05-01T12:00:00Z|x")),
+            None
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Resumable Server-Sent Events stream of engine updates.
+//!
+//! Routes:
+//! - `GET /events/fees` — an `insights` event after every processed batch
+//!   and each [`InsightEvent`] under its own name (`congestion_entered`,
+//!   `congestion_cleared`, `anomaly_detected`, `surge_started`,
+//!   `budget_exceeded`), with keep-alive comments in between
+//!
+//! Every event carries an `id`. A client reconnecting with `Last-Event-ID`
+//! first receives the events it missed that are still among the last
+//! [`REPLAY_CAPACITY`]. Ids restart with the process, so an id newer than
+//! any logged replays everything kept.
+
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::{Stream, StreamExt};
+use tokio::sync::{broadcast, broadcast::error::RecvError, RwLock};
+
+use crate::insights::{FeeInsightsEngine, InsightEvent};
+
+/// Events kept for clients resuming with `Last-Event-ID`.
+pub const REPLAY_CAPACITY: usize = 1_000;
+
+/// Shared state for the events route.
+pub type EventsState = Arc<FeeEventLog>;
+
+/// An engine update, numbered and encoded for SSE
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedEvent {
+    pub id: u64,
+    pub name: String,
+    /// JSON payload.
+    pub data: String,
+}
+
+/// Numbers one engine's updates and keeps the latest for replay
+#[derive(Debug)]
+pub struct FeeEventLog {
+    recent: Mutex<VecDeque<Arc<LoggedEvent>>>,
+    sender: broadcast::Sender<Arc<LoggedEvent>>,
+}
+
+impl FeeEventLog {
+    /// Log every update `engine` publishes from now on.
+    pub async fn start(engine: Arc<RwLock<FeeInsightsEngine>>) -> Arc<Self> {
+        let (mut batches, mut events) = {
+            let engine = engine.read().await;
+            (engine.subscribe_batches(), engine.subscribe_events())
+        };
+        let (sender, _) = broadcast::channel(REPLAY_CAPACITY);
+        let log = Arc::new(Self {
+            recent: Mutex::new(VecDeque::with_capacity(REPLAY_CAPACITY)),
+            sender,
+        });
+
+        let writer = log.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    batch = batches.recv() => match batch {
+                        Ok(batch) => writer.record("insights", &batch.insights),
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    },
+                    event = events.recv() => match event {
+                        Ok(event) => writer.record(event_name(&event), &event),
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    },
+                }
+            }
+        });
+        log
+    }
+
+    fn record(&self, name: &str, payload: &impl serde::Serialize) {
+        let data = match serde_json::to_string(payload) {
+            Ok(data) => data,
+            Err(err) => {
+                tracing::warn!("Failed to encode {} event: {}", name, err);
+                return;
+            }
+        };
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        let event = Arc::new(LoggedEvent {
+            id: recent.back().map_or(1, |last| last.id + 1),
+            name: name.to_string(),
+            data,
+        });
+        if recent.len() == REPLAY_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(event.clone());
+        // Sent under the lock so `resume` never sees an event twice
+        let _ = self.sender.send(event);
+    }
+
+    /// Events logged after `last_id` and a receiver for every later one.
+    pub fn resume(
+        &self,
+        last_id: Option<u64>,
+    ) -> (Vec<Arc<LoggedEvent>>, broadcast::Receiver<Arc<LoggedEvent>>) {
+        let recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        let newest = recent.back().map_or(0, |last| last.id);
+        let missed = match last_id {
+            None => Vec::new(),
+            Some(last_id) => recent
+                .iter()
+                .filter(|event| last_id > newest || event.id > last_id)
+                .cloned()
+                .collect(),
+        };
+        (missed, self.sender.subscribe())
+    }
+}
+
+/// SSE event name of an engine event, as it is tagged in JSON.
+fn event_name(event: &InsightEvent) -> &'static str {
+    match event {
+        InsightEvent::CongestionEntered(_) => "congestion_entered",
+        InsightEvent::CongestionCleared(_) => "congestion_cleared",
+        InsightEvent::AnomalyDetected(_) => "anomaly_detected",
+        InsightEvent::SurgeStarted { .. } => "surge_started",
+        InsightEvent::BudgetExceeded(_) => "budget_exceeded",
+    }
+}
+
+/// `GET /events/fees` — stream engine updates, replaying any missed since
+/// `Last-Event-ID`.
+pub async fn stream_fee_events(
+    State(log): State<EventsState>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let last_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok());
+    let (missed, receiver) = log.resume(last_id);
+
+    let live = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                // A slow client skips what it missed
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    let stream = futures::stream::iter(missed).chain(live).map(|event| {
+        Ok(Event::default()
+            .id(event.id.to_string())
+            .event(&event.name)
+            .data(&event.data))
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use chrono::Utc;
+    use http_body_util::BodyExt;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    use crate::insights::{FeeDataPoint, InsightsConfig};
+
+    fn batch(index: u64) -> Vec<FeeDataPoint> {
+        vec![FeeDataPoint {
+            fee_amount: 100,
+            timestamp: Utc::now(),
+            transaction_hash: format!("tx_{}", index),
+            ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
+        }]
+    }
+
+    #[tokio::test]
+    async fn replays_events_after_last_event_id() {
+        let engine = Arc::new(RwLock::new(FeeInsightsEngine::new(
+            InsightsConfig::default(),
+        )));
+        let log = FeeEventLog::start(engine.clone()).await;
+        for index in 0..3 {
+            engine
+                .write()
+                .await
+                .process_fee_data(&batch(index))
+                .await
+                .unwrap();
+        }
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while log.resume(Some(0)).0.len() < 3 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let (missed, _) = log.resume(Some(1));
+        let ids: Vec<u64> = missed.iter().map(|event| event.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+        // An id from before a restart replays everything kept
+        assert_eq!(log.resume(Some(99)).0.len(), 3);
+        assert!(log.resume(None).0.is_empty());
+
+        let app = Router::new()
+            .route("/events/fees", get(stream_fee_events))
+            .with_state(log);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/events/fees")
+                    .header("last-event-id", "2")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let mut body = response.into_body();
+        let frame = tokio::time::timeout(Duration::from_secs(5), body.frame())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let text = String::from_utf8(frame.into_data().unwrap().to_vec()).unwrap();
+        assert!(text.contains("event: insights"));
+        assert!(text.contains("id: 3"));
+    }
+}
@@ -0,0 +1,534 @@
+//! Stored fee history as CSV or Parquet.
+//!
+//! Routes:
+//! - `GET /fees/export.csv?start=…&end=…&resolution=…` — every stored fee
+//!   in the range, raw (default) or as `1m`, `5m`, `1h` or `1d` rollups,
+//!   oldest first. `end` defaults to now and `start` to 24 hours before
+//!   `end`
+//! - `GET /fees/export.parquet?start=…&end=…&resolution=…` — the same rows
+//!   as a Snappy-compressed Parquet file (`parquet` feature)
+//!
+//! Rows are read [`EXPORT_CHUNK`] at a time by cursor. The CSV body is streamed, so a
+//! range of any length never sits in memory at once; a Parquet file is
+//! only readable once its footer is written and is built in memory first.
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use futures::{stream, StreamExt};
+use serde::Deserialize;
+
+use super::cursor::{fetch_page, HistoryCursor};
+use super::fees::FeeHistoryItems;
+use crate::error::AppError;
+use crate::insights::FeeDataPoint;
+use crate::repository::{FeeRepository, PointFilter};
+use crate::rollup::{FeeRollup, Resolution};
+
+/// Shared state for the export route.
+pub type ExportState = Arc<FeeRepository>;
+
+/// Rows read from the database per query.
+pub const EXPORT_CHUNK: usize = 1_000;
+
+const RAW_HEADER: &str = "timestamp,ledger_sequence,transaction_hash,fee_amount,\
+operation_count,fee_bump,max_fee,inner_fee,operation_category\n";
+
+const ROLLUP_HEADER: &str = "bucket_start,resolution,sample_count,min_fee,max_fee,avg_fee,\
+p10,p25,p50,p75,p90,p95,p99\n";
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// RFC 3339 timestamps.
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    /// `raw` (default), `1m`, `5m`, `1h` or `1d`.
+    pub resolution: Option<String>,
+}
+
+/// Validated export range
+#[derive(Debug, Clone, Copy)]
+struct ExportRange {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    /// `None` for raw fee data points.
+    resolution: Option<Resolution>,
+}
+
+impl ExportRange {
+    fn parse(params: &ExportQuery) -> Result<Self, AppError> {
+        let bad_request = AppError::BadRequest;
+
+        let end = params.end.unwrap_or_else(Utc::now);
+        let start = params.start.unwrap_or(end - Duration::hours(24));
+        if start >= end {
+            return Err(bad_request("Range must start before it ends".to_string()));
+        }
+        let resolution = match params.resolution.as_deref() {
+            None | Some("raw") => None,
+            Some(value) => Some(Resolution::parse(value).ok_or_else(|| {
+                bad_request(format!(
+                    "Unsupported resolution: {} (use raw, 1m, 5m, 1h or 1d)",
+                    value
+                ))
+            })?),
+        };
+        Ok(Self {
+            start,
+            end,
+            resolution,
+        })
+    }
+}
+
+/// `GET /fees/export.csv` — stream a range of stored fees as CSV.
+pub async fn export_csv(
+    State(repository): State<ExportState>,
+    Query(params): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    let ExportRange {
+        start,
+        end,
+        resolution,
+    } = ExportRange::parse(&params)?;
+
+    let header_row = match resolution {
+        None => RAW_HEADER,
+        Some(_) => ROLLUP_HEADER,
+    };
+    // `None` once the last chunk has been read
+    let rows = stream::try_unfold(
+        Some(None),
+        move |position: Option<Option<HistoryCursor>>| {
+            let repository = repository.clone();
+            async move {
+                let Some(after) = position else {
+                    return Ok(None);
+                };
+                let (items, next) = fetch_page(
+                    &repository,
+                    resolution,
+                    start,
+                    end,
+                    &PointFilter::default(),
+                    after.as_ref(),
+                    EXPORT_CHUNK,
+                    0,
+                )
+                .await?;
+                let csv: String = match items {
+                    FeeHistoryItems::Raw(points) => points.iter().map(raw_row).collect(),
+                    FeeHistoryItems::Rollups(rollups) => rollups.iter().map(rollup_row).collect(),
+                };
+                Ok::<_, sqlx::Error>(Some((csv, next.map(Some))))
+            }
+        },
+    );
+    let body = stream::once(async move { Ok(header_row.to_string()) }).chain(rows);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"fees.csv\"",
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn raw_row(point: &FeeDataPoint) -> String {
+    let envelope = point.envelope.as_ref();
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    format!(
+        "{},{},{},{},{},{},{},{},{}\n",
+        timestamp(point.timestamp),
+        point.ledger_sequence,
+        point.transaction_hash,
+        point.fee_amount,
+        optional(envelope.map(|e| e.operation_count.to_string())),
+        optional(envelope.map(|e| e.fee_bump.to_string())),
+        optional(envelope.map(|e| e.max_fee.to_string())),
+        optional(
+            envelope
+                .and_then(|e| e.inner_fee)
+                .map(|fee| fee.to_string())
+        ),
+        optional(
+            envelope
+                .and_then(|e| e.category)
+                .map(|c| c.as_str().to_string())
+        ),
+    )
+}
+
+fn rollup_row(rollup: &FeeRollup) -> String {
+    let p = &rollup.percentiles;
+    format!(
+        "{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+        timestamp(rollup.bucket_start),
+        rollup.resolution.as_str(),
+        rollup.sample_count,
+        rollup.min_fee,
+        rollup.max_fee,
+        rollup.avg_fee,
+        p.p10,
+        p.p25,
+        p.p50,
+        p.p75,
+        p.p90,
+        p.p95,
+        p.p99,
+    )
+}
+
+/// `GET /fees/export.parquet` — a range of stored fees as a Parquet file.
+#[cfg(feature = "parquet")]
+pub async fn export_parquet(
+    State(repository): State<ExportState>,
+    Query(params): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    let range = ExportRange::parse(&params)?;
+    let body = columnar::write(&repository, range)
+        .await
+        .map_err(AppError::Unknown)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/vnd.apache.parquet"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"fees.parquet\"",
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Arrow record batches of exported rows, written as Parquet.
+#[cfg(feature = "parquet")]
+mod columnar {
+    use std::sync::Arc;
+
+    use arrow_array::{
+        ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, TimestampMillisecondArray,
+        UInt32Array, UInt64Array,
+    };
+    use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+
+    use super::{ExportRange, EXPORT_CHUNK};
+    use crate::api::cursor::fetch_page;
+    use crate::api::fees::FeeHistoryItems;
+    use crate::insights::FeeDataPoint;
+    use crate::repository::{FeeRepository, PointFilter};
+    use crate::rollup::FeeRollup;
+
+    /// Every row in `range` as a Parquet file.
+    pub(super) async fn write(
+        repository: &FeeRepository,
+        range: ExportRange,
+    ) -> Result<Vec<u8>, String> {
+        let schema = match range.resolution {
+            None => raw_schema(),
+            Some(_) => rollup_schema(),
+        };
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut writer = ArrowWriter::try_new(Vec::new(), schema.clone(), Some(properties))
+            .map_err(|e| e.to_string())?;
+
+        let mut after = None;
+        loop {
+            let (items, next) = fetch_page(
+                repository,
+                range.resolution,
+                range.start,
+                range.end,
+                &PointFilter::default(),
+                after.as_ref(),
+                EXPORT_CHUNK,
+                0,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            let batch = match items {
+                FeeHistoryItems::Raw(points) => raw_batch(&schema, &points),
+                FeeHistoryItems::Rollups(rollups) => rollup_batch(&schema, &rollups),
+            };
+            writer
+                .write(&batch.map_err(|e| e.to_string())?)
+                .map_err(|e| e.to_string())?;
+            match next {
+                Some(cursor) => after = Some(cursor),
+                None => break,
+            }
+        }
+
+        writer.into_inner().map_err(|e| e.to_string())
+    }
+
+    fn timestamp_type() -> DataType {
+        DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+    }
+
+    fn raw_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("timestamp", timestamp_type(), false),
+            Field::new("ledger_sequence", DataType::UInt64, false),
+            Field::new("transaction_hash", DataType::Utf8, false),
+            Field::new("fee_amount", DataType::UInt64, false),
+            Field::new("operation_count", DataType::UInt32, true),
+            Field::new("fee_bump", DataType::Boolean, true),
+            Field::new("max_fee", DataType::UInt64, true),
+            Field::new("inner_fee", DataType::UInt64, true),
+            Field::new("operation_category", DataType::Utf8, true),
+        ]))
+    }
+
+    fn rollup_schema() -> SchemaRef {
+        let mut fields = vec![
+            Field::new("bucket_start", timestamp_type(), false),
+            Field::new("resolution", DataType::Utf8, false),
+            Field::new("sample_count", DataType::UInt64, false),
+            Field::new("min_fee", DataType::UInt64, false),
+            Field::new("max_fee", DataType::UInt64, false),
+            Field::new("avg_fee", DataType::Float64, false),
+        ];
+        for name in ["p10", "p25", "p50", "p75", "p90", "p95", "p99"] {
+            fields.push(Field::new(name, DataType::UInt64, false));
+        }
+        Arc::new(Schema::new(fields))
+    }
+
+    fn raw_batch(schema: &SchemaRef, points: &[FeeDataPoint]) -> Result<RecordBatch, ArrowError> {
+        let envelopes = || points.iter().map(|p| p.envelope.as_ref());
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(
+                TimestampMillisecondArray::from_iter_values(
+                    points.iter().map(|p| p.timestamp.timestamp_millis()),
+                )
+                .with_timezone("UTC"),
+            ),
+            Arc::new(UInt64Array::from_iter_values(
+                points.iter().map(|p| p.ledger_sequence),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                points.iter().map(|p| p.transaction_hash.as_str()),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                points.iter().map(|p| p.fee_amount),
+            )),
+            Arc::new(
+                envelopes()
+                    .map(|e| e.map(|e| e.operation_count))
+                    .collect::<UInt32Array>(),
+            ),
+            Arc::new(
+                envelopes()
+                    .map(|e| e.map(|e| e.fee_bump))
+                    .collect::<BooleanArray>(),
+            ),
+            Arc::new(
+                envelopes()
+                    .map(|e| e.map(|e| e.max_fee))
+                    .collect::<UInt64Array>(),
+            ),
+            Arc::new(
+                envelopes()
+                    .map(|e| e.and_then(|e| e.inner_fee))
+                    .collect::<UInt64Array>(),
+            ),
+            Arc::new(
+                envelopes()
+                    .map(|e| e.and_then(|e| e.category).map(|c| c.as_str()))
+                    .collect::<StringArray>(),
+            ),
+        ];
+        RecordBatch::try_new(schema.clone(), columns)
+    }
+
+    fn rollup_batch(schema: &SchemaRef, rollups: &[FeeRollup]) -> Result<RecordBatch, ArrowError> {
+        let fees = |fee: fn(&FeeRollup) -> u64| -> ArrayRef {
+            Arc::new(UInt64Array::from_iter_values(rollups.iter().map(fee)))
+        };
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(
+                TimestampMillisecondArray::from_iter_values(
+                    rollups.iter().map(|r| r.bucket_start.timestamp_millis()),
+                )
+                .with_timezone("UTC"),
+            ),
+            Arc::new(StringArray::from_iter_values(
+                rollups.iter().map(|r| r.resolution.as_str()),
+            )),
+            fees(|r| r.sample_count),
+            fees(|r| r.min_fee),
+            fees(|r| r.max_fee),
+            Arc::new(Float64Array::from_iter_values(
+                rollups.iter().map(|r| r.avg_fee),
+            )),
+            fees(|r| r.percentiles.p10),
+            fees(|r| r.percentiles.p25),
+            fees(|r| r.percentiles.p50),
+            fees(|r| r.percentiles.p75),
+            fees(|r| r.percentiles.p90),
+            fees(|r| r.percentiles.p95),
+            fees(|r| r.percentiles.p99),
+        ];
+        RecordBatch::try_new(schema.clone(), columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::db::create_pool;
+    use crate::insights::{EnvelopeDetails, OperationCategory};
+    use crate::rollup::roll_up_closed_buckets;
+
+    async fn get_csv(app: &Router, uri: &str) -> (StatusCode, String) {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn streams_points_and_rollups_across_chunks() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repository = Arc::new(FeeRepository::new(pool));
+        let start = Utc::now() - Duration::hours(3);
+        let points: Vec<FeeDataPoint> = (0..EXPORT_CHUNK as u64 + 5)
+            .map(|i| FeeDataPoint {
+                fee_amount: 100 + i,
+                timestamp: start + Duration::seconds(i as i64),
+                transaction_hash: format!("tx_{}", i),
+                ledger_sequence: i,
+                envelope: (i == 0).then_some(EnvelopeDetails {
+                    operation_count: 2,
+                    fee_bump: false,
+                    max_fee: 500,
+                    inner_fee: None,
+                    category: Some(OperationCategory::Payment),
+                    fee_account: None,
+                }),
+                soroban: None,
+            })
+            .collect();
+        repository.insert_fee_points(&points).await.unwrap();
+        roll_up_closed_buckets(&repository, Utc::now())
+            .await
+            .unwrap();
+
+        let app = Router::new()
+            .route("/fees/export.csv", get(export_csv))
+            .with_state(repository);
+
+        let (status, csv) = get_csv(&app, "/fees/export.csv").await;
+        assert_eq!(status, StatusCode::OK);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 1 + EXPORT_CHUNK + 5);
+        assert_eq!(lines[0], RAW_HEADER.trim_end());
+        assert!(lines[1].ends_with(",0,tx_0,100,2,false,500,,payment"));
+        assert!(lines[EXPORT_CHUNK + 5].ends_with(",tx_1004,1104,,,,,"));
+
+        let (status, csv) = get_csv(&app, "/fees/export.csv?resolution=1d").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(csv.starts_with(ROLLUP_HEADER));
+
+        let (status, _) = get_csv(&app, "/fees/export.csv?resolution=2h").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get_csv(
+            &app,
+            "/fees/export.csv?start=2024-01-02T00:00:00Z&end=2024-01-01T00:00:00Z",
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[tokio::test]
+    async fn writes_points_as_parquet() {
+        use arrow_array::{Array, RecordBatch, UInt64Array};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repository = Arc::new(FeeRepository::new(pool));
+        let start = Utc::now() - Duration::hours(3);
+        let points: Vec<FeeDataPoint> = (0..EXPORT_CHUNK as u64 + 5)
+            .map(|i| FeeDataPoint {
+                fee_amount: 100 + i,
+                timestamp: start + Duration::seconds(i as i64),
+                transaction_hash: format!("tx_{}", i),
+                ledger_sequence: i,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        repository.insert_fee_points(&points).await.unwrap();
+
+        let app = Router::new()
+            .route("/fees/export.parquet", get(export_parquet))
+            .with_state(repository);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/fees/export.parquet")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        let batches: Vec<RecordBatch> = ParquetRecordBatchReaderBuilder::try_new(body)
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let rows: usize = batches.iter().map(RecordBatch::num_rows).sum();
+        assert_eq!(rows, EXPORT_CHUNK + 5);
+        let fees = batches[0]
+            .column_by_name("fee_amount")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        assert_eq!(fees.value(0), 100);
+        assert!(batches[0]
+            .column_by_name("operation_count")
+            .unwrap()
+            .is_null(0));
+    }
+}
@@ -1,22 +1,54 @@
+//! Current, recent and trending fees.
+//!
+//! Routes:
+//! - `GET /fees/current` — Horizon's latest `/fee_stats`, cached briefly,
+//!   plus an `insights` object read from the engine's own state: the
+//!   short-term average and percentiles, congestion, the last ledger seen
+//!   and how old the data is
+//! - `GET /fees/current?wait_for_change=30s` — the same, long-polled: held
+//!   open until the engine processes new fees or the wait (at most `60s`)
+//!   runs out. A client that sends the previous response's `Last-Modified`
+//!   back as `If-Modified-Since` is answered at once when it is already
+//!   behind, so no update is missed between polls
+//! - `GET /fees/history?window=…` — stored fees of the last `1h` (default),
+//!   `6h` or `24h` with a summary
+//! - `GET /fees/history?start=…&end=…&resolution=…&limit=…&cursor=…` — a
+//!   page of fees from the database, raw (default) or as `1m`, `5m`, `1h`
+//!   or `1d` rollups. `end` defaults to now and `start` to 24 hours before
+//!   `end`; `limit` defaults to 500 and is capped at 5,000. Each page's
+//!   `next_cursor` fetches the following one; `offset` is still accepted
+//!   but slows down on deep pages. Raw fees can be narrowed with
+//!   `min_fee`, `max_fee`, `min_ledger`, `max_ledger`, `tx_hash_prefix`
+//!   and `operation_type`; `network` reads another network's stored fees
+//! - `GET /fees/trend` — how each window's average compares with the next
+//!   longer one
+
 use std::sync::Arc;
+use std::time::Duration as StdDuration;
 
 use async_trait::async_trait;
 use axum::{
     body::Body,
     extract::{Query, State},
     http::{header, HeaderMap, StatusCode},
-    response::Response,
+    response::{IntoResponse, Response},
     Json,
 };
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
 use tokio::sync::{Mutex, RwLock};
 
+use super::cursor::{fetch_page, HistoryCursor};
 use super::headers::{cache_control, compute_etag, if_none_match_matches, last_modified};
 use crate::cache::ResponseCache;
+use crate::config::StellarNetwork;
 use crate::error::AppError;
-use crate::insights::{FeeDataPoint, FeeInsightsEngine, TrendIndicator, TrendStrength};
+use crate::insights::{
+    CongestionState, FeeDataPoint, FeeDistribution, FeeInsightsEngine, FeeTrend, OperationCategory,
+    TrendIndicator, TrendStrength,
+};
+use crate::repository::{FeeRepository, PointFilter};
+use crate::rollup::{FeeRollup, Resolution};
 use crate::services::horizon::HorizonClient;
 use crate::store::FeeHistoryStore;
 
@@ -60,6 +92,8 @@ pub struct FeesApiState {
     pub fee_cache: Arc<Mutex<ResponseCache<CurrentFeeResponse>>>,
     pub fee_store: Arc<RwLock<FeeHistoryStore>>,
     pub insights_engine: Option<Arc<RwLock<FeeInsightsEngine>>>,
+    /// Serves `/fees/history` ranges beyond the in-memory store.
+    pub repository: Option<Arc<FeeRepository>>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -86,10 +120,43 @@ pub struct CurrentFeeResponse {
     pub percentiles: PercentileFees,
 }
 
+/// The engine's latest view of the fee market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatestFeeInsights {
+    /// Short-term window average, in stroops.
+    pub average_fee: f64,
+    pub sample_count: usize,
+    /// Short-term window percentiles; `None` while the window is empty.
+    pub percentiles: Option<FeeDistribution>,
+    pub congestion: TrendIndicator,
+    pub congestion_state: CongestionState,
+    /// Ledger of the newest stored fee; `None` before any has been seen.
+    pub last_ledger: Option<u64>,
+    /// When the engine last processed fees; `None` before it has.
+    pub last_updated: Option<DateTime<Utc>>,
+    /// Seconds since `last_updated`.
+    pub data_age_seconds: Option<i64>,
+}
+
+/// `/fees/current` body: Horizon's fee stats with the engine's insights.
+/// Horizon's fields are left out while it cannot be reached.
+#[derive(Serialize)]
+struct CurrentFeesBody<'a> {
+    #[serde(flatten)]
+    network: Option<&'a CurrentFeeResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    insights: Option<LatestFeeInsights>,
+}
+
+/// Longest `wait_for_change` a `/fees/current` request may ask for.
+const MAX_WAIT_FOR_CHANGE: StdDuration = StdDuration::from_secs(60);
+
 const FEES_CURRENT_MAX_AGE: u32 = 5;
 const FEES_CURRENT_SWR: u32 = 10;
 const FEES_HISTORY_MAX_AGE: u32 = 30;
 const FEES_HISTORY_SWR: u32 = 60;
+const HISTORY_PAGE_DEFAULT_LIMIT: usize = 500;
+const HISTORY_PAGE_MAX_LIMIT: usize = 5_000;
 
 async fn resolve_last_modified(state: &FeesState) -> axum::http::HeaderValue {
     let timestamp = match state.insights_engine.as_ref() {
@@ -141,31 +208,74 @@ fn json_cache_response(
         })
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct CurrentFeesQuery {
+    /// Long-poll for up to this long, e.g. `30s`.
+    pub wait_for_change: Option<String>,
+}
+
 pub async fn current_fees(
     State(state): State<FeesState>,
+    Query(query): Query<CurrentFeesQuery>,
     request_headers: HeaderMap,
 ) -> Result<Response, AppError> {
+    if let Some(wait) = query.wait_for_change.as_deref() {
+        let wait = parse_wait(wait).ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "Invalid wait_for_change: {} (use whole seconds up to {}s, e.g. 30s)",
+                wait,
+                MAX_WAIT_FOR_CHANGE.as_secs()
+            ))
+        })?;
+        wait_for_insights_change(&state, &request_headers, wait).await;
+    }
+
     // Hold the lock across both the staleness check and the cache write to
     // prevent a thundering-herd where multiple concurrent requests all see a
     // stale cache and all fire upstream fetches simultaneously.
     // tokio::sync::Mutex is safe to hold across .await points.
     let mut cache = state.fee_cache.lock().await;
-    let payload = if cache.is_fresh() {
-        cache
+    let fetched = if cache.is_fresh() {
+        Ok(cache
             .get()
-            .expect("cache freshness invariant violated: is_fresh() but get() is None")
+            .expect("cache freshness invariant violated: is_fresh() but get() is None"))
     } else {
         let provider = state.fee_stats_provider.as_ref().ok_or_else(|| {
             AppError::Config("Fee stats provider missing from fees state".to_string())
         })?;
-        let fresh = provider.fetch_current_fees().await?;
-        cache.set(fresh.clone());
-        fresh
+        let fetched = provider.fetch_current_fees().await;
+        if let Ok(fresh) = &fetched {
+            cache.set(fresh.clone());
+        }
+        fetched
     };
     drop(cache);
 
-    let body = serde_json::to_vec(&payload).map_err(|err| AppError::Parse(err.to_string()))?;
-    let etag = compute_etag(&body);
+    let insights = latest_insights(&state).await;
+    // The engine's insights are still worth serving without Horizon's
+    let payload = match fetched {
+        Ok(payload) => Some(payload),
+        Err(err) if insights.as_ref().is_some_and(|i| i.last_updated.is_some()) => {
+            tracing::warn!("Serving /fees/current without Horizon fee stats: {}", err);
+            None
+        }
+        Err(err) => return Err(err),
+    };
+    // `data_age_seconds` ticks every second; the ETag only follows the data
+    let unaged = CurrentFeesBody {
+        network: payload.as_ref(),
+        insights: insights.clone().map(|insights| LatestFeeInsights {
+            data_age_seconds: None,
+            ..insights
+        }),
+    };
+    let etag =
+        compute_etag(&serde_json::to_vec(&unaged).map_err(|err| AppError::Parse(err.to_string()))?);
+    let body = serde_json::to_vec(&CurrentFeesBody {
+        network: payload.as_ref(),
+        insights,
+    })
+    .map_err(|err| AppError::Parse(err.to_string()))?;
     let last_modified_value = resolve_last_modified(&state).await;
 
     if if_none_match_matches(&request_headers, &etag) {
@@ -186,9 +296,159 @@ pub async fn current_fees(
     ))
 }
 
+/// Wait up to `wait` for the engine to process its next batch. Returns at
+/// once without an engine, or when `If-Modified-Since` shows the client
+/// has not seen the latest update.
+async fn wait_for_insights_change(state: &FeesState, headers: &HeaderMap, wait: StdDuration) {
+    let Some(engine) = state.insights_engine.as_ref() else {
+        return;
+    };
+    let seen = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok());
+    let mut batches = {
+        let engine = engine.read().await;
+        if let (Some(seen), Some(updated)) = (seen, engine.get_last_update()) {
+            // Last-Modified has whole seconds only
+            if updated.timestamp() > seen.timestamp() {
+                return;
+            }
+        }
+        engine.subscribe_batches()
+    };
+    // Any outcome, a missed batch included, means the insights moved on
+    let _ = tokio::time::timeout(wait, batches.recv()).await;
+}
+
+/// Whole seconds such as `30s` (or `30`), up to [`MAX_WAIT_FOR_CHANGE`].
+fn parse_wait(value: &str) -> Option<StdDuration> {
+    let seconds: u64 = value.strip_suffix('s').unwrap_or(value).parse().ok()?;
+    let wait = StdDuration::from_secs(seconds);
+    (wait <= MAX_WAIT_FOR_CHANGE).then_some(wait)
+}
+
+/// Insights from the engine and store, or `None` without an engine.
+async fn latest_insights(state: &FeesState) -> Option<LatestFeeInsights> {
+    let engine = state.insights_engine.as_ref()?.read().await;
+    let insights = engine.get_current_insights();
+    let last_updated = engine.get_last_update();
+    drop(engine);
+    let last_ledger = state
+        .fee_store
+        .read()
+        .await
+        .get_last_n(1)
+        .first()
+        .map(|point| point.ledger_sequence);
+    let short_term = insights.rolling_averages.short_term;
+
+    Some(LatestFeeInsights {
+        average_fee: short_term.value,
+        sample_count: short_term.sample_count,
+        percentiles: short_term.percentiles,
+        congestion: insights.congestion_trends.current_trend,
+        congestion_state: insights.congestion_trends.congestion_state,
+        last_ledger,
+        last_updated,
+        data_age_seconds: last_updated.map(|at| (Utc::now() - at).num_seconds()),
+    })
+}
+
 #[derive(Debug, Deserialize)]
 pub struct FeeHistoryQuery {
     pub window: Option<String>,
+    /// RFC 3339 timestamps; either one switches to a database range.
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    /// `raw`, `1m`, `5m`, `1h` or `1d`; also switches to a database range.
+    pub resolution: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    /// `next_cursor` of the previous page; not combined with `offset`.
+    pub cursor: Option<String>,
+    /// Filters on raw fees, in stroops and ledger sequences, inclusive.
+    pub min_fee: Option<u64>,
+    pub max_fee: Option<u64>,
+    pub min_ledger: Option<u64>,
+    pub max_ledger: Option<u64>,
+    /// Hex start of the transaction hash, in either case.
+    pub tx_hash_prefix: Option<String>,
+    /// `payment`, `path_payment`, `offer_management`, `contract_invoke`
+    /// or `other`.
+    pub operation_type: Option<String>,
+    /// Network to read instead of the one the route serves.
+    pub network: Option<String>,
+}
+
+impl FeeHistoryQuery {
+    /// Whether the query needs the database rather than the in-memory
+    /// store.
+    pub(crate) fn is_stored_range(&self) -> bool {
+        self.start.is_some()
+            || self.end.is_some()
+            || self.resolution.is_some()
+            || self.cursor.is_some()
+            || self.network.is_some()
+            || self.has_point_filter()
+    }
+
+    fn has_point_filter(&self) -> bool {
+        self.min_fee.is_some()
+            || self.max_fee.is_some()
+            || self.min_ledger.is_some()
+            || self.max_ledger.is_some()
+            || self.tx_hash_prefix.is_some()
+            || self.operation_type.is_some()
+    }
+
+    /// The raw-fee filters, or why they are invalid.
+    fn point_filter(&self) -> Result<PointFilter, String> {
+        if let (Some(min), Some(max)) = (self.min_fee, self.max_fee) {
+            if min > max {
+                return Err("min_fee must not exceed max_fee".to_string());
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_ledger, self.max_ledger) {
+            if min > max {
+                return Err("min_ledger must not exceed max_ledger".to_string());
+            }
+        }
+        let hash_prefix = match self.tx_hash_prefix.as_deref() {
+            None => None,
+            Some(prefix)
+                if !prefix.is_empty()
+                    && prefix.len() <= 64
+                    && prefix.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                Some(prefix.to_ascii_lowercase())
+            }
+            Some(prefix) => {
+                return Err(format!(
+                    "Invalid tx_hash_prefix: {} (use 1 to 64 hex digits)",
+                    prefix
+                ))
+            }
+        };
+        let operation_category = match self.operation_type.as_deref() {
+            None => None,
+            Some(value) => Some(OperationCategory::parse(value).ok_or_else(|| {
+                format!(
+                    "Unsupported operation_type: {} (use {})",
+                    value,
+                    OperationCategory::ALL.map(|c| c.as_str()).join(", ")
+                )
+            })?),
+        };
+        Ok(PointFilter {
+            min_fee: self.min_fee,
+            max_fee: self.max_fee,
+            min_ledger: self.min_ledger,
+            max_ledger: self.max_ledger,
+            hash_prefix,
+            operation_category,
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -210,41 +470,49 @@ pub struct FeeHistoryResponse {
     pub summary: FeeSummary,
 }
 
+/// A page of stored fees, raw or rolled up
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeeHistoryPage {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// `raw` or the rollup resolution.
+    pub resolution: String,
+    /// Points or rollups in the whole range; not counted when paging by
+    /// cursor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    pub limit: usize,
+    pub offset: usize,
+    /// Offset of the following page; `None` on the last one and when
+    /// paging by cursor.
+    pub next_offset: Option<usize>,
+    /// Cursor of the following page; `None` on the last one.
+    pub next_cursor: Option<String>,
+    pub items: FeeHistoryItems,
+}
+
+/// Oldest first
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FeeHistoryItems {
+    Raw(Vec<FeeDataPoint>),
+    Rollups(Vec<FeeRollup>),
+}
+
 pub async fn fee_history(
     State(state): State<FeesState>,
     Query(params): Query<FeeHistoryQuery>,
     request_headers: HeaderMap,
-) -> Result<Response, (StatusCode, Json<Value>)> {
-    let window = params.window.unwrap_or_else(|| "1h".to_string());
-    let duration = parse_window(&window).ok_or_else(|| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": format!("Unsupported window value: {}", window) })),
-        )
-    })?;
-
-    let to = Utc::now();
-    let from = to - duration;
-    let fees = {
-        let store = state.fee_store.read().await;
-        store.get_since(from)
-    };
-    let summary = compute_summary(&fees);
+) -> Result<Response, AppError> {
+    if params.is_stored_range() {
+        return stored_fee_history(&state, params)
+            .await
+            .map(|page| Json(page).into_response());
+    }
 
-    let payload = FeeHistoryResponse {
-        window,
-        from,
-        to,
-        data_points: fees.len(),
-        fees,
-        summary,
-    };
-    let body = serde_json::to_vec(&payload).map_err(|err| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": format!("Failed to serialize fee history: {}", err) })),
-        )
-    })?;
+    let payload = window_fee_history(&state, params.window).await?;
+    let body = serde_json::to_vec(&payload)
+        .map_err(|err| AppError::Unknown(format!("Failed to serialize fee history: {}", err)))?;
     let etag = compute_etag(&body);
     let last_modified_value = resolve_last_modified(&state).await;
 
@@ -266,6 +534,133 @@ pub async fn fee_history(
     ))
 }
 
+/// `/fees/history` over a recent window of the in-memory store, `1h` by
+/// default.
+pub(crate) async fn window_fee_history(
+    state: &FeesState,
+    window: Option<String>,
+) -> Result<FeeHistoryResponse, AppError> {
+    let window = window.unwrap_or_else(|| "1h".to_string());
+    let duration = parse_window(&window)
+        .ok_or_else(|| AppError::BadRequest(format!("Unsupported window value: {}", window)))?;
+
+    let to = Utc::now();
+    let from = to - duration;
+    let fees = {
+        let store = state.fee_store.read().await;
+        store.get_since(from)
+    };
+    let summary = compute_summary(&fees);
+
+    Ok(FeeHistoryResponse {
+        window,
+        from,
+        to,
+        data_points: fees.len(),
+        fees,
+        summary,
+    })
+}
+
+/// `/fees/history` over a range of the database.
+pub(crate) async fn stored_fee_history(
+    state: &FeesState,
+    params: FeeHistoryQuery,
+) -> Result<FeeHistoryPage, AppError> {
+    let bad_request = AppError::BadRequest;
+    let repository = state.repository.as_ref().ok_or_else(|| {
+        AppError::Unavailable("Fee history storage is not configured".to_string())
+    })?;
+    let scoped;
+    let repository = match params.network.as_deref() {
+        None => repository.as_ref(),
+        Some(value) => {
+            let network = StellarNetwork::parse(value).ok_or_else(|| {
+                bad_request(format!(
+                    "Unsupported network: {} (use testnet, mainnet or futurenet)",
+                    value
+                ))
+            })?;
+            scoped = repository.for_network(network);
+            &scoped
+        }
+    };
+
+    let end = params.end.unwrap_or_else(Utc::now);
+    let start = params.start.unwrap_or(end - Duration::hours(24));
+    if start >= end {
+        return Err(bad_request("Range must start before it ends".to_string()));
+    }
+    let resolution = match params.resolution.as_deref() {
+        None | Some("raw") => None,
+        Some(value) => Some(Resolution::parse(value).ok_or_else(|| {
+            bad_request(format!(
+                "Unsupported resolution: {} (use raw, 1m, 5m, 1h or 1d)",
+                value
+            ))
+        })?),
+    };
+    if resolution.is_some() && params.has_point_filter() {
+        return Err(bad_request(
+            "Filters apply to raw fees only; drop resolution".to_string(),
+        ));
+    }
+    let filter = params.point_filter().map_err(bad_request)?;
+    let cursor = match params.cursor.as_deref() {
+        None => None,
+        Some(_) if params.offset.is_some() => {
+            return Err(bad_request("Use either cursor or offset".to_string()))
+        }
+        Some(token) => Some(
+            HistoryCursor::decode(token)
+                .filter(|cursor| cursor.matches(resolution))
+                .ok_or_else(|| bad_request("Invalid cursor for this resolution".to_string()))?,
+        ),
+    };
+    let limit = params
+        .limit
+        .unwrap_or(HISTORY_PAGE_DEFAULT_LIMIT)
+        .clamp(1, HISTORY_PAGE_MAX_LIMIT);
+    let offset = params.offset.unwrap_or(0);
+
+    // Counting walks the whole range, so cursor pages skip it
+    let total = match (&cursor, resolution) {
+        (Some(_), _) => None,
+        (None, None) => Some(repository.count_between(start, end, &filter).await?),
+        (None, Some(resolution)) => Some(
+            repository
+                .count_rollups_between(resolution, start, end)
+                .await?,
+        ),
+    };
+    let (items, next_cursor) = fetch_page(
+        repository,
+        resolution,
+        start,
+        end,
+        &filter,
+        cursor.as_ref(),
+        limit,
+        offset,
+    )
+    .await?;
+
+    Ok(FeeHistoryPage {
+        start,
+        end,
+        resolution: resolution.map_or("raw", Resolution::as_str).to_string(),
+        total,
+        limit,
+        offset,
+        next_offset: next_cursor
+            .as_ref()
+            .filter(|_| cursor.is_none())
+            .map(|_| offset + limit),
+        next_cursor: next_cursor.map(|cursor| cursor.encode()),
+        items,
+    })
+}
+
 fn parse_window(value: &str) -> Option<Duration> {
     match value {
         "1h" => Some(Duration::hours(1)),
@@ -319,11 +714,20 @@ pub struct TrendChanges {
     pub twenty_four_h_pct: Option<f64>,
 }
 
+/// Fitted direction of fees within each rolling window.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrendDirections {
+    pub short_term: Option<FeeTrend>,
+    pub medium_term: Option<FeeTrend>,
+    pub long_term: Option<FeeTrend>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FeeTrendResponse {
     pub status: String,
     pub trend_strength: String,
     pub changes: TrendChanges,
+    pub directions: TrendDirections,
     pub recent_spike_count: usize,
     pub predicted_congestion_minutes: Option<i64>,
     pub last_updated: DateTime<Utc>,
@@ -353,6 +757,11 @@ pub async fn fee_trend(State(state): State<FeesState>) -> Result<Json<FeeTrendRe
         status: trend_indicator_to_string(&insights.congestion_trends.current_trend),
         trend_strength: trend_strength_to_string(&insights.congestion_trends.trend_strength),
         changes,
+        directions: TrendDirections {
+            short_term: averages.short_term.trend,
+            medium_term: averages.medium_term.trend,
+            long_term: averages.long_term.trend,
+        },
         recent_spike_count: insights.congestion_trends.recent_spikes.len(),
         predicted_congestion_minutes: insights
             .congestion_trends
@@ -397,7 +806,9 @@ mod tests {
     use std::sync::Mutex as StdMutex;
     use std::time::Duration as StdDuration;
 
-    use crate::insights::InsightsConfig;
+    use serde_json::Value;
+
+    use crate::insights::{EnvelopeDetails, InsightsConfig};
     use axum::{
         body::{to_bytes, Body},
         http::{Request, StatusCode},
@@ -455,6 +866,7 @@ mod tests {
             fee_cache: default_cache(),
             fee_store: Arc::new(RwLock::new(store)),
             insights_engine: None,
+            repository: None,
         })
     }
 
@@ -464,6 +876,7 @@ mod tests {
             fee_cache: default_cache(),
             fee_store: Arc::new(RwLock::new(FeeHistoryStore::new(100))),
             insights_engine: Some(Arc::new(RwLock::new(engine))),
+            repository: None,
         })
     }
 
@@ -476,6 +889,7 @@ mod tests {
             fee_cache: Arc::new(Mutex::new(ResponseCache::new(ttl))),
             fee_store: Arc::new(RwLock::new(FeeHistoryStore::new(100))),
             insights_engine: None,
+            repository: None,
         })
     }
 
@@ -508,6 +922,8 @@ mod tests {
                 timestamp: Utc::now() - ChronoDuration::minutes(minutes_ago_start - idx as i64),
                 transaction_hash: format!("tx-{}", idx),
                 ledger_sequence: 50_000_000 + idx as u64,
+                envelope: None,
+                soroban: None,
             })
             .collect()
     }
@@ -702,6 +1118,451 @@ mod tests {
         assert!(body.is_empty(), "304 response should not include body");
     }
 
+    #[tokio::test]
+    async fn current_fees_includes_engine_insights() {
+        let points = test_points(5, 4);
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        engine.process_fee_data(&points).await.unwrap();
+        let mut store = FeeHistoryStore::new(100);
+        for point in points {
+            store.push(point);
+        }
+        let mock = MockFeeStatsProvider::new(vec![make_current_fee_response("100")]);
+        let state = Arc::new(FeesApiState {
+            fee_stats_provider: Some(Arc::new(mock)),
+            fee_cache: default_cache(),
+            fee_store: Arc::new(RwLock::new(store)),
+            insights_engine: Some(Arc::new(RwLock::new(engine))),
+            repository: None,
+        });
+
+        let app = Router::new()
+            .route("/fees/current", get(current_fees))
+            .with_state(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/fees/current")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["base_fee"], "100");
+        let insights = &json["insights"];
+        assert_eq!(insights["sample_count"], 5);
+        assert_eq!(insights["average_fee"], 300.0);
+        assert_eq!(insights["percentiles"]["p50"], 300);
+        assert_eq!(insights["congestion_state"], "normal");
+        assert_eq!(insights["last_ledger"], 50_000_004);
+        assert!(insights["data_age_seconds"].as_i64().unwrap() >= 0);
+    }
+
+    #[tokio::test]
+    async fn current_fees_serves_engine_insights_when_horizon_fails() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        engine.process_fee_data(&test_points(5, 4)).await.unwrap();
+        let mock = MockFeeStatsProvider::new(Vec::new());
+        let state = Arc::new(FeesApiState {
+            fee_stats_provider: Some(Arc::new(mock.clone())),
+            fee_cache: default_cache(),
+            fee_store: Arc::new(RwLock::new(FeeHistoryStore::new(100))),
+            insights_engine: Some(Arc::new(RwLock::new(engine))),
+            repository: None,
+        });
+
+        let app = Router::new()
+            .route("/fees/current", get(current_fees))
+            .with_state(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/fees/current")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert!(json.get("base_fee").is_none());
+        assert_eq!(json["insights"]["sample_count"], 5);
+        assert_eq!(mock.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn current_fees_fails_when_horizon_fails_before_any_insights() {
+        let engine = FeeInsightsEngine::new(InsightsConfig::default());
+        let state = Arc::new(FeesApiState {
+            fee_stats_provider: Some(Arc::new(MockFeeStatsProvider::new(Vec::new()))),
+            fee_cache: default_cache(),
+            fee_store: Arc::new(RwLock::new(FeeHistoryStore::new(100))),
+            insights_engine: Some(Arc::new(RwLock::new(engine))),
+            repository: None,
+        });
+
+        let app = Router::new()
+            .route("/fees/current", get(current_fees))
+            .with_state(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/fees/current")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_ne!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn current_fees_etag_holds_while_the_data_ages() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        engine.process_fee_data(&test_points(5, 4)).await.unwrap();
+        let mock = MockFeeStatsProvider::new(vec![make_current_fee_response("100")]);
+        let app = Router::new()
+            .route("/fees/current", get(current_fees))
+            .with_state(Arc::new(FeesApiState {
+                fee_stats_provider: Some(Arc::new(mock)),
+                fee_cache: Arc::new(Mutex::new(ResponseCache::new(StdDuration::from_secs(60)))),
+                fee_store: Arc::new(RwLock::new(FeeHistoryStore::new(100))),
+                insights_engine: Some(Arc::new(RwLock::new(engine))),
+                repository: None,
+            }));
+        let request = |etag: Option<&str>| {
+            let mut request = Request::builder().uri("/fees/current");
+            if let Some(etag) = etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let first = app.clone().oneshot(request(None)).await.unwrap();
+        let etag = first.headers()[header::ETAG].to_str().unwrap().to_string();
+
+        // Long enough for `data_age_seconds` to move on
+        tokio::time::sleep(StdDuration::from_millis(1_100)).await;
+        let second = app.oneshot(request(Some(&etag))).await.unwrap();
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(second.headers()[header::ETAG], etag.as_str());
+    }
+
+    fn long_poll_app(engine: Arc<RwLock<FeeInsightsEngine>>) -> Router {
+        let mock = MockFeeStatsProvider::new(vec![make_current_fee_response("100")]);
+        Router::new()
+            .route("/fees/current", get(current_fees))
+            .with_state(Arc::new(FeesApiState {
+                fee_stats_provider: Some(Arc::new(mock)),
+                fee_cache: default_cache(),
+                fee_store: Arc::new(RwLock::new(FeeHistoryStore::new(100))),
+                insights_engine: Some(engine),
+                repository: None,
+            }))
+    }
+
+    fn long_poll(if_modified_since: Option<&str>) -> Request<Body> {
+        let mut request = Request::builder().uri("/fees/current?wait_for_change=30s");
+        if let Some(value) = if_modified_since {
+            request = request.header(header::IF_MODIFIED_SINCE, value);
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn current_fees_long_polls_until_insights_change() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        engine.process_fee_data(&test_points(5, 10)).await.unwrap();
+        let engine = Arc::new(RwLock::new(engine));
+        let app = long_poll_app(engine.clone());
+
+        // A client already behind the engine is answered at once
+        let stale = last_modified(Utc::now() - ChronoDuration::hours(1));
+        let response = tokio::time::timeout(
+            StdDuration::from_secs(5),
+            app.clone().oneshot(long_poll(stale.to_str().ok())),
+        )
+        .await
+        .expect("stale client should not wait")
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let seen = response.headers()[header::LAST_MODIFIED]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let before: Value = serde_json::from_slice(&body).unwrap();
+
+        // An up-to-date client waits for the next batch
+        let waiting = tokio::spawn(app.oneshot(long_poll(Some(&seen))));
+        tokio::time::sleep(StdDuration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+        engine
+            .write()
+            .await
+            .process_fee_data(&test_points(3, 2))
+            .await
+            .unwrap();
+        let response = tokio::time::timeout(StdDuration::from_secs(5), waiting)
+            .await
+            .expect("update should end the wait")
+            .unwrap()
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_ne!(
+            json["insights"]["last_updated"],
+            before["insights"]["last_updated"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn current_fees_long_poll_times_out_with_the_current_fees() {
+        let engine = Arc::new(RwLock::new(FeeInsightsEngine::new(
+            InsightsConfig::default(),
+        )));
+        let app = long_poll_app(engine);
+
+        let started = tokio::time::Instant::now();
+        let response = app.clone().oneshot(long_poll(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(started.elapsed() >= StdDuration::from_secs(30));
+
+        for wait in ["61s", "soon", "-1"] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("/fees/current?wait_for_change={}", wait))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", wait);
+        }
+    }
+
+    #[tokio::test]
+    async fn current_fees_omits_insights_without_an_engine() {
+        let mock = MockFeeStatsProvider::new(vec![make_current_fee_response("100")]);
+        let state = make_fee_state_with_provider(Arc::new(mock), StdDuration::from_secs(60));
+
+        let app = Router::new()
+            .route("/fees/current", get(current_fees))
+            .with_state(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/fees/current")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert!(json.get("insights").is_none());
+    }
+
+    #[tokio::test]
+    async fn fee_history_pages_through_a_stored_range() {
+        let pool = crate::db::create_pool("sqlite::memory:").await.unwrap();
+        let repository = Arc::new(FeeRepository::new(pool));
+        let points = test_points(5, 120);
+        repository.insert_fee_points(&points).await.unwrap();
+        let start = points[0].timestamp;
+        let state = Arc::new(FeesApiState {
+            fee_stats_provider: None,
+            fee_cache: default_cache(),
+            fee_store: Arc::new(RwLock::new(FeeHistoryStore::new(100))),
+            insights_engine: None,
+            repository: Some(repository),
+        });
+        let app = Router::new()
+            .route("/fees/history", get(fee_history))
+            .with_state(state);
+        let get_page = |uri: String| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap())
+            }
+        };
+        let start_param = start.to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+
+        let (status, page) = get_page(format!(
+            "/fees/history?start={}&limit=2&offset=2",
+            start_param
+        ))
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["resolution"], "raw");
+        assert_eq!(page["total"], 5);
+        assert_eq!(page["next_offset"], 4);
+        let hashes: Vec<&str> = page["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["transaction_hash"].as_str().unwrap())
+            .collect();
+        assert_eq!(hashes, vec!["tx-2", "tx-3"]);
+
+        let (_, page) = get_page(format!(
+            "/fees/history?start={}&limit=2&offset=4",
+            start_param
+        ))
+        .await;
+        assert_eq!(page["items"].as_array().unwrap().len(), 1);
+        assert!(page["next_offset"].is_null());
+        assert!(page["next_cursor"].is_null());
+
+        // Cursors walk the same range without offsets or counts
+        let mut uri = format!("/fees/history?start={}&limit=2", start_param);
+        let mut hashes = Vec::new();
+        loop {
+            let (status, page) = get_page(uri.clone()).await;
+            assert_eq!(status, StatusCode::OK);
+            for item in page["items"].as_array().unwrap() {
+                hashes.push(item["transaction_hash"].as_str().unwrap().to_string());
+            }
+            let Some(cursor) = page["next_cursor"].as_str() else {
+                break;
+            };
+            uri = format!(
+                "/fees/history?start={}&limit=2&cursor={}",
+                start_param, cursor
+            );
+            let (_, next) = get_page(uri.clone()).await;
+            assert!(next.get("total").is_none());
+            assert!(next["next_offset"].is_null());
+        }
+        assert_eq!(hashes, vec!["tx-0", "tx-1", "tx-2", "tx-3", "tx-4"]);
+
+        let (_, page) = get_page(format!("/fees/history?start={}&limit=2", start_param)).await;
+        let cursor = page["next_cursor"].as_str().unwrap();
+        let (status, _) = get_page(format!(
+            "/fees/history?start={}&cursor={}&offset=2",
+            start_param, cursor
+        ))
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get_page(format!("/fees/history?resolution=1h&cursor={}", cursor)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, page) = get_page("/fees/history?resolution=1h".to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["resolution"], "1h");
+        assert_eq!(page["total"], 0);
+
+        let (status, _) = get_page("/fees/history?resolution=2h".to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn fee_history_filters_stored_fees() {
+        let pool = crate::db::create_pool("sqlite::memory:").await.unwrap();
+        let repository = FeeRepository::new(pool).with_network(StellarNetwork::Testnet);
+        let categories = [
+            OperationCategory::Payment,
+            OperationCategory::ContractInvoke,
+            OperationCategory::Payment,
+            OperationCategory::Other,
+            OperationCategory::Payment,
+        ];
+        let mut points = test_points(5, 120);
+        for (idx, point) in points.iter_mut().enumerate() {
+            point.transaction_hash = format!("{}{}ff", ["ab", "cd"][idx % 2], idx);
+            point.envelope = Some(EnvelopeDetails {
+                operation_count: 1,
+                category: Some(categories[idx]),
+                ..Default::default()
+            });
+        }
+        repository.insert_fee_points(&points).await.unwrap();
+        repository
+            .for_network(StellarNetwork::Mainnet)
+            .insert_fee_points(&test_points(2, 120))
+            .await
+            .unwrap();
+        let state = Arc::new(FeesApiState {
+            fee_stats_provider: None,
+            fee_cache: default_cache(),
+            fee_store: Arc::new(RwLock::new(FeeHistoryStore::new(100))),
+            insights_engine: None,
+            repository: Some(Arc::new(repository)),
+        });
+        let app = Router::new()
+            .route("/fees/history", get(fee_history))
+            .with_state(state);
+        let get_page = |query: &str| {
+            let request = Request::builder()
+                .uri(format!("/fees/history?{}", query))
+                .body(Body::empty())
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap())
+            }
+        };
+        let hashes = |page: &Value| -> Vec<String> {
+            page["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["transaction_hash"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // Fees are 100..=500 on ledgers 50_000_000..=50_000_004
+        let (status, page) = get_page("min_fee=200&max_fee=400&min_ledger=50000002").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["total"], 2);
+        assert_eq!(hashes(&page), vec!["ab2ff", "cd3ff"]);
+
+        let (_, page) = get_page("tx_hash_prefix=AB&operation_type=payment&limit=1").await;
+        assert_eq!(page["total"], 3);
+        assert_eq!(hashes(&page), vec!["ab0ff"]);
+        let cursor = page["next_cursor"].as_str().unwrap().to_string();
+        let (_, page) = get_page(&format!(
+            "tx_hash_prefix=ab&operation_type=payment&limit=5&cursor={}",
+            cursor
+        ))
+        .await;
+        assert_eq!(hashes(&page), vec!["ab2ff", "ab4ff"]);
+
+        let (_, page) = get_page("network=mainnet").await;
+        assert_eq!(hashes(&page), vec!["tx-0", "tx-1"]);
+
+        for query in [
+            "min_fee=300&max_fee=200",
+            "min_ledger=2&max_ledger=1",
+            "tx_hash_prefix=xyz",
+            "operation_type=swap",
+            "network=devnet",
+            "resolution=1h&min_fee=100",
+        ] {
+            let (status, _) = get_page(query).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+        }
+    }
+
     #[tokio::test]
     async fn fee_history_returns_data_points_and_summary_for_supported_windows() {
         for window in ["1h", "6h", "24h"] {
@@ -759,42 +1620,56 @@ mod tests {
                 timestamp: now - ChronoDuration::minutes(60),
                 transaction_hash: "tx1".to_string(),
                 ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 100,
                 timestamp: now - ChronoDuration::minutes(50),
                 transaction_hash: "tx2".to_string(),
                 ledger_sequence: 2,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 100,
                 timestamp: now - ChronoDuration::minutes(40),
                 transaction_hash: "tx3".to_string(),
                 ledger_sequence: 3,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 100,
                 timestamp: now - ChronoDuration::minutes(30),
                 transaction_hash: "tx4".to_string(),
                 ledger_sequence: 4,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 100,
                 timestamp: now - ChronoDuration::minutes(20),
                 transaction_hash: "tx5".to_string(),
                 ledger_sequence: 5,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: high_fee,
                 timestamp: now - ChronoDuration::minutes(10),
                 transaction_hash: "tx6".to_string(),
                 ledger_sequence: 6,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 100,
                 timestamp: now,
                 transaction_hash: "tx7".to_string(),
                 ledger_sequence: 7,
+                envelope: None,
+                soroban: None,
             },
         ]
     }
@@ -807,18 +1682,24 @@ mod tests {
                 timestamp: now - ChronoDuration::minutes(50),
                 transaction_hash: "n1".to_string(),
                 ledger_sequence: 11,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 110,
                 timestamp: now - ChronoDuration::minutes(40),
                 transaction_hash: "n2".to_string(),
                 ledger_sequence: 12,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 120,
                 timestamp: now - ChronoDuration::minutes(30),
                 transaction_hash: "n3".to_string(),
                 ledger_sequence: 13,
+                envelope: None,
+                soroban: None,
             },
         ]
     }
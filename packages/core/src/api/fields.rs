@@ -0,0 +1,280 @@
+//! Sparse fieldsets: `?fields=` on JSON responses.
+//!
+//! `?fields=rolling_averages.short_term.average_fee,congestion_trends`
+//! keeps only the listed fields of a response. A dotted path selects a
+//! field inside an object; inside an array it applies to every element.
+//! Fields a response does not have are ignored, since optional fields are
+//! left out of some responses altogether.
+//!
+//! Selection works on the serialized JSON, so any JSON route gets it by
+//! adding [`select_fields`] as a layer. It must sit inside any compression
+//! layer, where the body is still plain JSON.
+
+use std::collections::BTreeMap;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Query, Request},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::headers::{compute_etag, if_none_match_matches, not_modified};
+use crate::error::AppError;
+
+/// Most paths one `fields` parameter may list.
+pub const MAX_FIELDS: usize = 50;
+
+#[derive(Debug, Deserialize)]
+struct FieldsQuery {
+    fields: Option<String>,
+}
+
+/// Parsed `fields` parameter: the tree of fields to keep.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldSelection {
+    /// Keep this value whole.
+    whole: bool,
+    children: BTreeMap<String, FieldSelection>,
+}
+
+impl FieldSelection {
+    /// Parse a comma-separated list of dotted paths.
+    pub fn parse(fields: &str) -> Result<Self, String> {
+        let paths: Vec<&str> = fields.split(',').map(str::trim).collect();
+        if paths.len() > MAX_FIELDS {
+            return Err(format!("At most {} fields may be selected", MAX_FIELDS));
+        }
+        let mut selection = Self::default();
+        for path in paths {
+            let segments: Vec<&str> = path.split('.').collect();
+            if segments.iter().any(|segment| segment.is_empty()) {
+                return Err(format!("Invalid field: '{}'", path));
+            }
+            selection.insert(&segments);
+        }
+        Ok(selection)
+    }
+
+    fn insert(&mut self, segments: &[&str]) {
+        let Some((first, rest)) = segments.split_first() else {
+            // A field listed whole also covers any of its subfields.
+            self.whole = true;
+            self.children.clear();
+            return;
+        };
+        if self.whole {
+            return;
+        }
+        self.children
+            .entry(first.to_string())
+            .or_default()
+            .insert(rest);
+    }
+
+    /// `value` with only the selected fields.
+    pub fn apply(&self, value: Value) -> Value {
+        if self.whole {
+            return value;
+        }
+        match value {
+            Value::Object(mut object) => {
+                let mut selected = Map::new();
+                for (name, child) in &self.children {
+                    if let Some(field) = object.remove(name) {
+                        selected.insert(name.clone(), child.apply(field));
+                    }
+                }
+                Value::Object(selected)
+            }
+            Value::Array(items) => {
+                Value::Array(items.into_iter().map(|item| self.apply(item)).collect())
+            }
+            other => other,
+        }
+    }
+}
+
+/// Trim successful JSON responses to the request's `fields`, if it has
+/// any. Their ETag is recomputed over the trimmed body, and a matching
+/// `If-None-Match` is answered with `304 Not Modified`.
+pub async fn select_fields(request: Request, next: Next) -> Response {
+    let fields = Query::<FieldsQuery>::try_from_uri(request.uri())
+        .ok()
+        .and_then(|Query(query)| query.fields);
+    let Some(fields) = fields else {
+        return next.run(request).await;
+    };
+    let selection = match FieldSelection::parse(&fields) {
+        Ok(selection) => selection,
+        Err(message) => return AppError::BadRequest(message).into_response(),
+    };
+    let request_headers = request.headers().clone();
+
+    let response = next.run(request).await;
+    rewrite_json(&request_headers, response, |value| selection.apply(value)).await
+}
+
+/// Rewrite a successful JSON response's body with `rewrite`, for layers
+/// that reshape responses. Their ETag is recomputed over the new body,
+/// and a matching `If-None-Match` is answered with `304 Not Modified`.
+/// Other responses pass through untouched.
+pub(crate) async fn rewrite_json(
+    request_headers: &HeaderMap,
+    response: Response,
+    rewrite: impl FnOnce(Value) -> Value,
+) -> Response {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !response.status().is_success() || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            return AppError::Unknown(format!("Failed to read response: {}", err)).into_response()
+        }
+    };
+    let Ok(value) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    let body = serde_json::to_vec(&rewrite(value)).unwrap_or_default();
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    if parts.headers.contains_key(header::ETAG) {
+        let etag = compute_etag(&body);
+        if if_none_match_matches(request_headers, &etag) {
+            let cache_control = parts
+                .headers
+                .get(header::CACHE_CONTROL)
+                .cloned()
+                .unwrap_or_else(|| HeaderValue::from_static("no-cache"));
+            let last_modified = parts.headers.get(header::LAST_MODIFIED).cloned();
+            return not_modified(&etag, cache_control, last_modified);
+        }
+        if let Ok(value) = HeaderValue::from_str(&etag) {
+            parts.headers.insert(header::ETAG, value);
+        }
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::get, Json, Router};
+    use http_body_util::BodyExt;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    fn sample() -> Value {
+        json!({
+            "windows": [
+                { "name": "short_term", "average_fee": 120.0, "percentiles": { "p50": 100, "p90": 200 } },
+                { "name": "long_term", "average_fee": 110.0, "percentiles": null },
+            ],
+            "congestion": { "level": "normal", "score": 0.2 },
+            "anomaly_detected": false,
+        })
+    }
+
+    fn select(fields: &str) -> Value {
+        FieldSelection::parse(fields).unwrap().apply(sample())
+    }
+
+    #[test]
+    fn keeps_only_selected_fields() {
+        assert_eq!(
+            select("congestion,anomaly_detected"),
+            json!({ "congestion": { "level": "normal", "score": 0.2 }, "anomaly_detected": false })
+        );
+        assert_eq!(
+            select("congestion.level"),
+            json!({ "congestion": { "level": "normal" } })
+        );
+    }
+
+    #[test]
+    fn paths_apply_to_every_array_element() {
+        assert_eq!(
+            select("windows.name,windows.percentiles.p90"),
+            json!({ "windows": [
+                { "name": "short_term", "percentiles": { "p90": 200 } },
+                { "name": "long_term", "percentiles": null },
+            ]})
+        );
+    }
+
+    #[test]
+    fn whole_field_wins_over_subfields_and_unknown_fields_are_ignored() {
+        assert_eq!(
+            select("congestion.level, congestion, missing.field"),
+            json!({ "congestion": { "level": "normal", "score": 0.2 } })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_or_too_many_fields() {
+        for invalid in ["", "congestion,", "congestion..level", ".level"] {
+            assert!(FieldSelection::parse(invalid).is_err(), "{:?}", invalid);
+        }
+        let many = vec!["a"; MAX_FIELDS + 1].join(",");
+        assert!(FieldSelection::parse(&many).is_err());
+    }
+
+    async fn fetch(app: &Router, uri: &str, if_none_match: Option<&str>) -> Response {
+        let mut request = Request::builder().uri(uri);
+        if let Some(etag) = if_none_match {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        app.clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn layer_trims_json_responses_and_their_etags() {
+        let app = Router::new()
+            .route(
+                "/insights",
+                get(|| async { ([(header::ETAG, "\"full\"")], Json(sample())) }),
+            )
+            .route("/text", get(|| async { "plain" }))
+            .layer(axum::middleware::from_fn(select_fields));
+
+        let response = fetch(&app, "/insights", None).await;
+        assert_eq!(response.headers()[header::ETAG], "\"full\"");
+
+        let response = fetch(&app, "/insights?fields=anomaly_detected", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_ne!(etag, "\"full\"");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            json!({ "anomaly_detected": false })
+        );
+
+        let response = fetch(&app, "/insights?fields=anomaly_detected", Some(&etag)).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let response = fetch(&app, "/text?fields=anything", None).await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"plain");
+
+        let response = fetch(&app, "/insights?fields=a..b", None).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,487 @@
+//! GraphQL API (`graphql` feature).
+//!
+//! Routes:
+//! - `POST /graphql` — a standard GraphQL request (`query`, `variables`,
+//!   `operationName`) against the schema below
+//!
+//! Queries take an optional `from`/`to` range, defaulting to the last hour:
+//! - `fees(filter, limit, offset)` — stored fee points, oldest first
+//! - `aggregate(filter)` — count, total, extremes and percentiles of the
+//!   matching fees
+//! - `insights` — the engine's current window averages and congestion
+//! - `snapshots` — persisted insight snapshots
+//! - `congestionChanges` — when the sampled congestion state changed
+//! - `surges` — completed surge episodes
+//!
+//! `filter` narrows by `fee` and `ledger` ranges, `feeBump`, `category` and
+//! `feeAccount`. Queries are limited in depth and complexity.
+
+use std::sync::Arc;
+
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, InputObject, Object, Schema, SimpleObject,
+};
+use axum::{extract::State, Json};
+use chrono::{DateTime, Duration, Utc};
+use tokio::sync::RwLock;
+
+use crate::insights::calculator::fee_distribution;
+use crate::insights::{
+    AverageResult, CurrentInsights, FeeDataPoint, FeeDistribution, FeeInsightsEngine,
+    OperationCategory,
+};
+use crate::repository::FeeRepository;
+
+/// Most fee points one `fees` query returns.
+const MAX_LIMIT: usize = 1_000;
+
+/// The compiled schema, with the engine and storage it reads.
+pub type FeeSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Build the schema over one network's engine and storage.
+pub fn build_schema(
+    insights_engine: Arc<RwLock<FeeInsightsEngine>>,
+    repository: Arc<FeeRepository>,
+) -> FeeSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(insights_engine)
+        .data(repository)
+        .limit_depth(8)
+        .limit_complexity(500)
+        .finish()
+}
+
+/// `POST /graphql` — execute a GraphQL request.
+pub async fn graphql(
+    State(schema): State<FeeSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+/// A stored fee
+#[derive(Debug, SimpleObject)]
+pub struct FeePoint {
+    pub transaction_hash: String,
+    pub ledger_sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    /// Charged fee, in stroops.
+    pub fee_amount: u64,
+    /// Fee bid; null when the envelope was not decoded.
+    pub max_fee: Option<u64>,
+    pub operation_count: Option<u32>,
+    pub fee_bump: bool,
+    pub category: Option<String>,
+    pub fee_account: Option<String>,
+}
+
+impl From<FeeDataPoint> for FeePoint {
+    fn from(point: FeeDataPoint) -> Self {
+        Self {
+            max_fee: point.max_fee(),
+            operation_count: point.operation_count(),
+            fee_bump: point.is_fee_bump(),
+            category: point
+                .operation_category()
+                .map(|category| category.as_str().to_string()),
+            fee_account: point.envelope.and_then(|e| e.fee_account),
+            transaction_hash: point.transaction_hash,
+            ledger_sequence: point.ledger_sequence,
+            timestamp: point.timestamp,
+            fee_amount: point.fee_amount,
+        }
+    }
+}
+
+/// Inclusive bounds; either may be omitted
+#[derive(Debug, Default, InputObject)]
+pub struct U64Range {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl U64Range {
+    fn contains(&self, value: u64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+/// Conditions a fee point must all meet
+#[derive(Debug, Default, InputObject)]
+pub struct FeeFilter {
+    /// Charged fee, in stroops.
+    pub fee: Option<U64Range>,
+    pub ledger: Option<U64Range>,
+    pub fee_bump: Option<bool>,
+    /// `payment`, `path_payment`, `offer_management`, `contract_invoke` or
+    /// `other`.
+    pub category: Option<String>,
+    pub fee_account: Option<String>,
+}
+
+impl FeeFilter {
+    fn matches(&self, point: &FeeDataPoint, category: Option<OperationCategory>) -> bool {
+        self.fee
+            .as_ref()
+            .is_none_or(|r| r.contains(point.fee_amount))
+            && self
+                .ledger
+                .as_ref()
+                .is_none_or(|r| r.contains(point.ledger_sequence))
+            && self.fee_bump.is_none_or(|b| b == point.is_fee_bump())
+            && category.is_none_or(|c| point.operation_category() == Some(c))
+            && self.fee_account.as_deref().is_none_or(|account| {
+                point
+                    .envelope
+                    .as_ref()
+                    .and_then(|e| e.fee_account.as_deref())
+                    == Some(account)
+            })
+    }
+}
+
+/// Nearest-rank percentiles, in stroops
+#[derive(Debug, SimpleObject)]
+pub struct Percentiles {
+    pub p10: u64,
+    pub p25: u64,
+    pub p50: u64,
+    pub p75: u64,
+    pub p90: u64,
+    pub p95: u64,
+    pub p99: u64,
+}
+
+impl From<FeeDistribution> for Percentiles {
+    fn from(d: FeeDistribution) -> Self {
+        Self {
+            p10: d.p10,
+            p25: d.p25,
+            p50: d.p50,
+            p75: d.p75,
+            p90: d.p90,
+            p95: d.p95,
+            p99: d.p99,
+        }
+    }
+}
+
+/// Summary of the fees matching a filter
+#[derive(Debug, SimpleObject)]
+pub struct FeeAggregate {
+    pub count: u64,
+    pub total_fee: u64,
+    pub min_fee: Option<u64>,
+    pub max_fee: Option<u64>,
+    pub average_fee: Option<f64>,
+    pub percentiles: Option<Percentiles>,
+}
+
+/// One rolling window's average
+#[derive(Debug, SimpleObject)]
+pub struct WindowAverage {
+    pub name: String,
+    pub average_fee: f64,
+    pub sample_count: u64,
+    pub is_partial: bool,
+    pub percentiles: Option<Percentiles>,
+}
+
+impl From<AverageResult> for WindowAverage {
+    fn from(result: AverageResult) -> Self {
+        Self {
+            name: result.time_window.name,
+            average_fee: result.value,
+            sample_count: result.sample_count as u64,
+            is_partial: result.is_partial,
+            percentiles: result.percentiles.map(Percentiles::from),
+        }
+    }
+}
+
+/// Window averages and congestion at one moment
+#[derive(Debug, SimpleObject)]
+pub struct InsightsView {
+    pub last_updated: DateTime<Utc>,
+    /// `normal`, `elevated` or `congested`.
+    pub congestion_state: String,
+    pub windows: Vec<WindowAverage>,
+}
+
+impl From<CurrentInsights> for InsightsView {
+    fn from(insights: CurrentInsights) -> Self {
+        let averages = insights.rolling_averages;
+        Self {
+            last_updated: insights.last_updated,
+            congestion_state: insights
+                .congestion_trends
+                .congestion_state
+                .as_str()
+                .to_string(),
+            windows: vec![
+                averages.short_term.into(),
+                averages.medium_term.into(),
+                averages.long_term.into(),
+            ],
+        }
+    }
+}
+
+/// The sampled congestion state changed
+#[derive(Debug, SimpleObject)]
+pub struct CongestionChange {
+    pub at: DateTime<Utc>,
+    pub state: String,
+}
+
+/// A completed surge episode
+#[derive(Debug, SimpleObject)]
+pub struct Surge {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub peak_fee: u64,
+    pub baseline_fee: f64,
+    pub sample_count: u64,
+}
+
+/// `[from, to)`, defaulting to the hour before `to`, which defaults to now.
+fn resolve_range(
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> async_graphql::Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let to = to.unwrap_or_else(Utc::now);
+    let from = from.unwrap_or(to - Duration::hours(1));
+    if from >= to {
+        return Err("Range must start before it ends".into());
+    }
+    Ok((from, to))
+}
+
+/// Stored points in the range that pass `filter`, oldest first.
+async fn matching_points(
+    ctx: &Context<'_>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    filter: Option<FeeFilter>,
+) -> async_graphql::Result<Vec<FeeDataPoint>> {
+    let (from, to) = resolve_range(from, to)?;
+    let filter = filter.unwrap_or_default();
+    let category = match filter.category.as_deref() {
+        None => None,
+        Some(value) => Some(
+            OperationCategory::parse(value)
+                .ok_or_else(|| format!("Unknown operation category: {}", value))?,
+        ),
+    };
+    let repository = ctx.data::<Arc<FeeRepository>>()?;
+    let mut points = repository.fetch_between(from, to).await?;
+    points.retain(|point| filter.matches(point, category));
+    Ok(points)
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Stored fee points in the range, oldest first.
+    async fn fees(
+        &self,
+        ctx: &Context<'_>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        filter: Option<FeeFilter>,
+        #[graphql(default = 100)] limit: usize,
+        #[graphql(default = 0)] offset: usize,
+    ) -> async_graphql::Result<Vec<FeePoint>> {
+        let points = matching_points(ctx, from, to, filter).await?;
+        Ok(points
+            .into_iter()
+            .skip(offset)
+            .take(limit.clamp(1, MAX_LIMIT))
+            .map(FeePoint::from)
+            .collect())
+    }
+
+    /// Count, total, extremes and percentiles of the fees in the range.
+    async fn aggregate(
+        &self,
+        ctx: &Context<'_>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        filter: Option<FeeFilter>,
+    ) -> async_graphql::Result<FeeAggregate> {
+        let points = matching_points(ctx, from, to, filter).await?;
+        let mut fees: Vec<u64> = points.iter().map(|p| p.fee_amount).collect();
+        fees.sort_unstable();
+        let total_fee: u64 = fees.iter().sum();
+        Ok(FeeAggregate {
+            count: fees.len() as u64,
+            total_fee,
+            min_fee: fees.first().copied(),
+            max_fee: fees.last().copied(),
+            average_fee: (!fees.is_empty()).then(|| total_fee as f64 / fees.len() as f64),
+            percentiles: fee_distribution(&fees).map(Percentiles::from),
+        })
+    }
+
+    /// The engine's current window averages and congestion.
+    async fn insights(&self, ctx: &Context<'_>) -> async_graphql::Result<InsightsView> {
+        let engine = ctx.data::<Arc<RwLock<FeeInsightsEngine>>>()?;
+        let insights = engine.read().await.get_current_insights();
+        Ok(insights.into())
+    }
+
+    /// Persisted insight snapshots in the range, oldest first.
+    async fn snapshots(
+        &self,
+        ctx: &Context<'_>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> async_graphql::Result<Vec<InsightsView>> {
+        let (from, to) = resolve_range(from, to)?;
+        let repository = ctx.data::<Arc<FeeRepository>>()?;
+        Ok(repository
+            .fetch_insight_snapshots_since(from)
+            .await?
+            .into_iter()
+            .filter(|snapshot| snapshot.last_updated < to)
+            .map(InsightsView::from)
+            .collect())
+    }
+
+    /// Times the sampled congestion state changed in the range.
+    async fn congestion_changes(
+        &self,
+        ctx: &Context<'_>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> async_graphql::Result<Vec<CongestionChange>> {
+        let (from, to) = resolve_range(from, to)?;
+        let repository = ctx.data::<Arc<FeeRepository>>()?;
+        let samples = repository.fetch_congestion_samples_since(from).await?;
+        let mut previous = None;
+        Ok(samples
+            .into_iter()
+            .filter(|(at, _)| *at < to)
+            .filter(|&(_, state)| previous.replace(state) != Some(state))
+            .map(|(at, state)| CongestionChange {
+                at,
+                state: state.as_str().to_string(),
+            })
+            .collect())
+    }
+
+    /// Surge episodes overlapping the range, oldest first.
+    async fn surges(
+        &self,
+        ctx: &Context<'_>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> async_graphql::Result<Vec<Surge>> {
+        let (from, to) = resolve_range(from, to)?;
+        let repository = ctx.data::<Arc<FeeRepository>>()?;
+        Ok(repository
+            .fetch_surge_episodes_between(from, to)
+            .await?
+            .into_iter()
+            .map(|episode| Surge {
+                start_time: episode.start_time,
+                end_time: episode.end_time,
+                peak_fee: episode.peak_fee,
+                baseline_fee: episode.baseline_fee,
+                sample_count: episode.sample_count as u64,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::insights::{EnvelopeDetails, InsightsConfig};
+
+    fn point(fee_amount: u64, ledger_sequence: u64, fee_bump: bool) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp: Utc::now() - Duration::minutes(5),
+            transaction_hash: format!("tx_{}", ledger_sequence),
+            ledger_sequence,
+            envelope: Some(EnvelopeDetails {
+                operation_count: 1,
+                fee_bump,
+                max_fee: fee_amount * 2,
+                category: Some(OperationCategory::Payment),
+                ..EnvelopeDetails::default()
+            }),
+            soroban: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn filters_and_aggregates_stored_fees() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repository = Arc::new(FeeRepository::new(pool));
+        repository
+            .insert_fee_points(&[
+                point(100, 1, false),
+                point(200, 2, true),
+                point(300, 3, false),
+                point(400, 4, false),
+            ])
+            .await
+            .unwrap();
+        let engine = Arc::new(RwLock::new(FeeInsightsEngine::new(
+            InsightsConfig::default(),
+        )));
+        let schema = build_schema(engine, repository);
+
+        let response = schema
+            .execute(
+                r#"{
+                    fees(filter: { fee: { min: 150 }, feeBump: false }) {
+                        feeAmount ledgerSequence maxFee category
+                    }
+                    aggregate(filter: { ledger: { max: 3 } }) {
+                        count totalFee maxFee percentiles { p50 }
+                    }
+                    insights { congestionState windows { name sampleCount } }
+                }"#,
+            )
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+
+        let fees = data["fees"].as_array().unwrap();
+        assert_eq!(fees.len(), 2);
+        assert_eq!(fees[0]["feeAmount"], 300);
+        assert_eq!(fees[0]["maxFee"], 600);
+        assert_eq!(fees[0]["category"], "payment");
+        // Only the requested fields are returned
+        assert!(fees[0].get("transactionHash").is_none());
+
+        assert_eq!(data["aggregate"]["count"], 3);
+        assert_eq!(data["aggregate"]["totalFee"], 600);
+        assert_eq!(data["aggregate"]["maxFee"], 300);
+        assert_eq!(data["aggregate"]["percentiles"]["p50"], 200);
+
+        assert_eq!(data["insights"]["congestionState"], "normal");
+        assert_eq!(data["insights"]["windows"][0]["name"], "short_term");
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_categories() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let schema = build_schema(
+            Arc::new(RwLock::new(FeeInsightsEngine::new(
+                InsightsConfig::default(),
+            ))),
+            Arc::new(FeeRepository::new(pool)),
+        );
+        let response = schema
+            .execute(r#"{ fees(filter: { category: "staking" }) { feeAmount } }"#)
+            .await;
+        assert_eq!(response.errors.len(), 1);
+        assert!(response.errors[0].message.contains("staking"));
+    }
+}
@@ -0,0 +1,526 @@
+//! gRPC API (`grpc-server` feature).
+//!
+//! Serves the `FeeService` in `proto/fee_service.proto` — insights, stored
+//! fee history, fee recommendations and a server stream of processed
+//! batches — for backend consumers that want typed messages instead of
+//! polling JSON. [`router`] answers the service's calls as an axum router,
+//! so it is served on its own HTTP/2 port with the REST API's
+//! authentication layers in front of it. It serves the primary network.
+//!
+//! Calls are decoded and encoded by tonic's `Grpc` over the hand-written
+//! messages in [`proto`]; a method the service does not have is answered
+//! with `UNIMPLEMENTED`.
+
+pub mod proto;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    response::{IntoResponse, Response},
+    routing::post,
+    Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use futures::Stream;
+use tokio::sync::{broadcast::error::RecvError, RwLock};
+use tonic::{
+    codec::ProstCodec,
+    server::{Grpc, ServerStreamingService, UnaryService},
+    Status,
+};
+
+use crate::insights::{
+    AverageResult, CurrentInsights, FeeDataPoint, FeeDistribution, FeeInsightsEngine,
+    ProcessedBatch, TrendIndicator, Urgency,
+};
+use crate::repository::{FeeRepository, PointFilter};
+
+/// Fully qualified name of the service.
+pub const SERVICE: &str = "stellar.fees.v1.FeeService";
+
+/// Fees `GetFeeHistory` returns when the request sets no limit.
+const DEFAULT_HISTORY_LIMIT: usize = 500;
+
+/// Most fees one `GetFeeHistory` call returns.
+const MAX_HISTORY_LIMIT: usize = 5_000;
+
+/// Most operations a Stellar transaction may hold.
+const MAX_OPERATIONS: u32 = 100;
+
+/// What the service reads
+#[derive(Clone)]
+pub struct GrpcState {
+    pub insights_engine: Arc<RwLock<FeeInsightsEngine>>,
+    pub repository: Arc<FeeRepository>,
+}
+
+/// The `FeeService` methods as routes.
+pub fn router(state: GrpcState) -> Router {
+    let method = |name: &str| format!("/{}/{}", SERVICE, name);
+    Router::new()
+        .route(&method("GetInsights"), post(get_insights))
+        .route(&method("GetFeeHistory"), post(get_fee_history))
+        .route(
+            &method("GetFeeRecommendation"),
+            post(get_fee_recommendation),
+        )
+        .route(&method("StreamUpdates"), post(stream_updates))
+        .fallback(unimplemented)
+        .with_state(state)
+}
+
+async fn get_insights(State(state): State<GrpcState>, request: Request) -> Response {
+    unary(request, move |_: proto::GetInsightsRequest| async move {
+        let insights = state.insights_engine.read().await.get_current_insights();
+        Ok(insights_message(insights))
+    })
+    .await
+}
+
+async fn get_fee_history(State(state): State<GrpcState>, request: Request) -> Response {
+    unary(
+        request,
+        move |request: proto::GetFeeHistoryRequest| async move {
+            let end = match request.end_ms {
+                0 => Some(Utc::now()),
+                ms => DateTime::from_timestamp_millis(ms),
+            };
+            let start = match request.start_ms {
+                0 => end.map(|end| end - Duration::hours(1)),
+                ms => DateTime::from_timestamp_millis(ms),
+            };
+            let (Some(start), Some(end)) = (start, end) else {
+                return Err(Status::invalid_argument("Timestamp out of range"));
+            };
+            if start >= end {
+                return Err(Status::invalid_argument("start_ms must be before end_ms"));
+            }
+            let limit = match request.limit {
+                0 => DEFAULT_HISTORY_LIMIT,
+                limit => (limit as usize).min(MAX_HISTORY_LIMIT),
+            };
+
+            // One extra point tells whether the range holds more
+            let mut points = state
+                .repository
+                .fetch_page_between(start, end, &PointFilter::default(), None, limit + 1, 0)
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+            let truncated = points.len() > limit;
+            points.truncate(limit);
+
+            Ok(proto::FeeHistory {
+                points: points
+                    .into_iter()
+                    .map(|(_, point)| point_message(&point))
+                    .collect(),
+                truncated,
+            })
+        },
+    )
+    .await
+}
+
+async fn get_fee_recommendation(State(state): State<GrpcState>, request: Request) -> Response {
+    unary(
+        request,
+        move |request: proto::GetFeeRecommendationRequest| async move {
+            let urgency = match request.urgency() {
+                proto::Urgency::Economy => Urgency::Economy,
+                proto::Urgency::Unspecified | proto::Urgency::Standard => Urgency::Standard,
+                proto::Urgency::Priority => Urgency::Priority,
+            };
+            let operations = request.operations.max(1);
+            if operations > MAX_OPERATIONS {
+                return Err(Status::invalid_argument(format!(
+                    "operations must be between 1 and {}",
+                    MAX_OPERATIONS
+                )));
+            }
+
+            let recommendation = state.insights_engine.read().await.get_fee_recommendation();
+            let per_operation = urgency.pick(&recommendation);
+            let percentile = recommendation
+                .time_window
+                .as_ref()
+                .map(|_| format!("p{}", urgency.percentile(&recommendation.congestion)));
+            Ok(proto::FeeRecommendation {
+                fee: per_operation.saturating_mul(u64::from(operations)),
+                inclusion_fee_per_operation: per_operation,
+                percentile: percentile.unwrap_or_default(),
+                time_window: recommendation.time_window.unwrap_or_default(),
+                congestion: trend_name(&recommendation.congestion).to_string(),
+                generated_at_ms: recommendation.generated_at.timestamp_millis(),
+            })
+        },
+    )
+    .await
+}
+
+async fn stream_updates(State(state): State<GrpcState>, request: Request) -> Response {
+    let service = ServerStream::new(move |request: proto::StreamUpdatesRequest| {
+        subscribe_updates(state, request.min_fee)
+    });
+    Grpc::new(ProstCodec::default())
+        .server_streaming(service, request)
+        .await
+        .into_response()
+}
+
+/// Processed batches from now on, without fees charged less than `min_fee`.
+async fn subscribe_updates(state: GrpcState, min_fee: u64) -> Result<UpdateStream, Status> {
+    let receiver = state.insights_engine.read().await.subscribe_batches();
+    let updates = futures::stream::unfold(receiver, move |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(batch) => return Some((Ok(update_message(&batch, min_fee)), receiver)),
+                // A slow client skips the batches it missed
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Ok(Box::pin(updates))
+}
+
+async fn unimplemented() -> Response {
+    Status::unimplemented("").into_http().into_response()
+}
+
+/// Answer a unary call with `handler`.
+async fn unary<Req, Resp, F, Fut>(request: Request, handler: F) -> Response
+where
+    Req: prost::Message + Default + Send + 'static,
+    Resp: prost::Message + Send + 'static,
+    F: FnOnce(Req) -> Fut,
+    Fut: Future<Output = Result<Resp, Status>> + Send + 'static,
+{
+    Grpc::new(ProstCodec::<Resp, Req>::default())
+        .unary(Unary(Some(handler)), request)
+        .await
+        .into_response()
+}
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = Result<tonic::Response<T>, Status>> + Send>>;
+
+type UpdateStream = Pin<Box<dyn Stream<Item = Result<proto::FeeUpdate, Status>> + Send>>;
+
+/// A unary method answering one call with a closure
+struct Unary<F>(Option<F>);
+
+impl<Req, Resp, F, Fut> UnaryService<Req> for Unary<F>
+where
+    F: FnOnce(Req) -> Fut,
+    Fut: Future<Output = Result<Resp, Status>> + Send + 'static,
+    Resp: 'static,
+{
+    type Response = Resp;
+    type Future = BoxFuture<Resp>;
+
+    fn call(&mut self, request: tonic::Request<Req>) -> Self::Future {
+        answer_once(self.0.take(), request)
+    }
+}
+
+/// A server-streaming method answering one call with a closure
+struct ServerStream<F>(Option<F>);
+
+impl<F> ServerStream<F> {
+    fn new(handler: F) -> Self {
+        Self(Some(handler))
+    }
+}
+
+impl<Req, F, Fut> ServerStreamingService<Req> for ServerStream<F>
+where
+    F: FnOnce(Req) -> Fut,
+    Fut: Future<Output = Result<UpdateStream, Status>> + Send + 'static,
+{
+    type Response = proto::FeeUpdate;
+    type ResponseStream = UpdateStream;
+    type Future = BoxFuture<UpdateStream>;
+
+    fn call(&mut self, request: tonic::Request<Req>) -> Self::Future {
+        answer_once(self.0.take(), request)
+    }
+}
+
+/// Run `handler` on `request`; tonic calls a method's service once per
+/// call, so it is only missing if that changes.
+fn answer_once<Req, Resp, F, Fut>(
+    handler: Option<F>,
+    request: tonic::Request<Req>,
+) -> BoxFuture<Resp>
+where
+    F: FnOnce(Req) -> Fut,
+    Fut: Future<Output = Result<Resp, Status>> + Send + 'static,
+    Resp: 'static,
+{
+    let Some(handler) = handler else {
+        return Box::pin(async { Err(Status::internal("method called twice")) });
+    };
+    let response = handler(request.into_inner());
+    Box::pin(async move { response.await.map(tonic::Response::new) })
+}
+
+fn trend_name(trend: &TrendIndicator) -> &'static str {
+    match trend {
+        TrendIndicator::Normal => "normal",
+        TrendIndicator::Rising => "rising",
+        TrendIndicator::Congested => "congested",
+        TrendIndicator::Declining => "declining",
+    }
+}
+
+fn insights_message(insights: CurrentInsights) -> proto::Insights {
+    let averages = insights.rolling_averages;
+    proto::Insights {
+        last_updated_ms: insights.last_updated.timestamp_millis(),
+        congestion_state: insights
+            .congestion_trends
+            .congestion_state
+            .as_str()
+            .to_string(),
+        congestion_score: insights.congestion_trends.congestion_score,
+        windows: [
+            averages.short_term,
+            averages.medium_term,
+            averages.long_term,
+        ]
+        .into_iter()
+        .map(window_message)
+        .collect(),
+        min_fee: insights.extremes.current_min.value,
+        max_fee: insights.extremes.current_max.value,
+    }
+}
+
+fn window_message(average: AverageResult) -> proto::WindowAverage {
+    proto::WindowAverage {
+        name: average.time_window.name,
+        average_fee: average.value,
+        sample_count: average.sample_count as u64,
+        is_partial: average.is_partial,
+        percentiles: average.percentiles.map(percentiles_message),
+    }
+}
+
+fn percentiles_message(d: FeeDistribution) -> proto::Percentiles {
+    proto::Percentiles {
+        p10: d.p10,
+        p25: d.p25,
+        p50: d.p50,
+        p75: d.p75,
+        p90: d.p90,
+        p95: d.p95,
+        p99: d.p99,
+    }
+}
+
+fn point_message(point: &FeeDataPoint) -> proto::FeePoint {
+    let envelope = point.envelope.as_ref();
+    proto::FeePoint {
+        fee_amount: point.fee_amount,
+        timestamp_ms: point.timestamp.timestamp_millis(),
+        transaction_hash: point.transaction_hash.clone(),
+        ledger_sequence: point.ledger_sequence,
+        max_fee: envelope.map(|e| e.max_fee),
+        operation_count: envelope.map(|e| e.operation_count),
+        fee_bump: envelope.is_some_and(|e| e.fee_bump),
+    }
+}
+
+fn update_message(batch: &ProcessedBatch, min_fee: u64) -> proto::FeeUpdate {
+    proto::FeeUpdate {
+        points: batch
+            .points
+            .iter()
+            .filter(|point| point.fee_amount >= min_fee)
+            .map(point_message)
+            .collect(),
+        insights: Some(insights_message(batch.insights.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use http_body_util::BodyExt;
+    use prost::Message;
+    use tower::ServiceExt;
+
+    use crate::db::create_pool;
+    use crate::insights::InsightsConfig;
+
+    fn points(fees: &[u64]) -> Vec<FeeDataPoint> {
+        fees.iter()
+            .enumerate()
+            .map(|(i, &fee_amount)| FeeDataPoint {
+                fee_amount,
+                timestamp: Utc::now() - Duration::minutes(fees.len() as i64 - i as i64),
+                transaction_hash: format!("tx_{}", i),
+                ledger_sequence: 100 + i as u64,
+                envelope: None,
+                soroban: None,
+            })
+            .collect()
+    }
+
+    async fn state() -> GrpcState {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repository = Arc::new(FeeRepository::new(pool));
+        let fees = points(&[100, 200, 300, 400]);
+        repository.insert_fee_points(&fees).await.unwrap();
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        engine.process_fee_data(&fees).await.unwrap();
+        GrpcState {
+            insights_engine: Arc::new(RwLock::new(engine)),
+            repository,
+        }
+    }
+
+    fn grpc_request(method: &str, message: impl Message) -> Request {
+        let payload = message.encode_to_vec();
+        let mut body = vec![0];
+        body.extend((payload.len() as u32).to_be_bytes());
+        body.extend(payload);
+        Request::post(format!("/{}/{}", SERVICE, method))
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    /// The decoded reply, or the non-OK `grpc-status`.
+    async fn call<T: Message + Default>(
+        state: &GrpcState,
+        method: &str,
+        message: impl Message,
+    ) -> Result<T, i32> {
+        let response = router(state.clone())
+            .oneshot(grpc_request(method, message))
+            .await
+            .unwrap();
+        let headers = response.headers().clone();
+        let collected = response.into_body().collect().await.unwrap();
+        let status = headers
+            .get("grpc-status")
+            .or_else(|| collected.trailers().and_then(|t| t.get("grpc-status")))
+            .map(|v| v.to_str().unwrap().parse::<i32>().unwrap());
+        match status {
+            Some(0) => {}
+            Some(code) => return Err(code),
+            None => panic!("no grpc-status"),
+        }
+        let body = collected.to_bytes();
+        Ok(T::decode(&body[5..]).unwrap())
+    }
+
+    #[tokio::test]
+    async fn answers_insights_and_history() {
+        let state = state().await;
+
+        let insights: proto::Insights = call(&state, "GetInsights", proto::GetInsightsRequest {})
+            .await
+            .unwrap();
+        assert_eq!(insights.congestion_state, "normal");
+        assert_eq!(insights.windows.len(), 3);
+        assert_eq!(insights.windows[0].sample_count, 4);
+        let extremes = state
+            .insights_engine
+            .read()
+            .await
+            .get_current_insights()
+            .extremes;
+        assert_eq!(insights.min_fee, extremes.current_min.value);
+        assert_eq!(insights.max_fee, extremes.current_max.value);
+
+        let history: proto::FeeHistory = call(
+            &state,
+            "GetFeeHistory",
+            proto::GetFeeHistoryRequest {
+                limit: 3,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let fees: Vec<u64> = history.points.iter().map(|p| p.fee_amount).collect();
+        assert_eq!(fees, vec![100, 200, 300]);
+        assert!(history.truncated);
+    }
+
+    #[tokio::test]
+    async fn recommends_fees_and_rejects_bad_arguments() {
+        let state = state().await;
+
+        let standard: proto::FeeRecommendation = call(
+            &state,
+            "GetFeeRecommendation",
+            proto::GetFeeRecommendationRequest::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(standard.percentile, "p50");
+        assert_eq!(standard.fee, standard.inclusion_fee_per_operation);
+
+        let priority: proto::FeeRecommendation = call(
+            &state,
+            "GetFeeRecommendation",
+            proto::GetFeeRecommendationRequest {
+                urgency: proto::Urgency::Priority as i32,
+                operations: 2,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(priority.inclusion_fee_per_operation > standard.inclusion_fee_per_operation);
+        assert_eq!(priority.fee, priority.inclusion_fee_per_operation * 2);
+
+        let invalid = call::<proto::FeeRecommendation>(
+            &state,
+            "GetFeeRecommendation",
+            proto::GetFeeRecommendationRequest {
+                urgency: 0,
+                operations: MAX_OPERATIONS + 1,
+            },
+        )
+        .await;
+        assert_eq!(invalid, Err(tonic::Code::InvalidArgument as i32));
+
+        let missing =
+            call::<proto::Insights>(&state, "GetSomethingElse", proto::GetInsightsRequest {}).await;
+        assert_eq!(missing, Err(tonic::Code::Unimplemented as i32));
+    }
+
+    #[tokio::test]
+    async fn streams_processed_batches() {
+        let state = state().await;
+        let response = router(state.clone())
+            .oneshot(grpc_request(
+                "StreamUpdates",
+                proto::StreamUpdatesRequest { min_fee: 250 },
+            ))
+            .await
+            .unwrap();
+        let mut body = response.into_body();
+
+        state
+            .insights_engine
+            .write()
+            .await
+            .process_fee_data(&points(&[200, 500]))
+            .await
+            .unwrap();
+
+        let frame = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        let update = proto::FeeUpdate::decode(&frame[5..]).unwrap();
+        let fees: Vec<u64> = update.points.iter().map(|p| p.fee_amount).collect();
+        assert_eq!(fees, vec![500]);
+        assert_eq!(update.insights.unwrap().windows.len(), 3);
+    }
+}
@@ -0,0 +1,139 @@
+//! Messages of `proto/fee_service.proto`
+//!
+//! Written by hand with `prost`'s derives instead of generated, which keeps
+//! `protoc` out of the build. Field numbers and types must match the
+//! schema.
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetInsightsRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Insights {
+    #[prost(int64, tag = "1")]
+    pub last_updated_ms: i64,
+    #[prost(string, tag = "2")]
+    pub congestion_state: String,
+    #[prost(double, optional, tag = "3")]
+    pub congestion_score: Option<f64>,
+    #[prost(message, repeated, tag = "4")]
+    pub windows: Vec<WindowAverage>,
+    #[prost(uint64, tag = "5")]
+    pub min_fee: u64,
+    #[prost(uint64, tag = "6")]
+    pub max_fee: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct WindowAverage {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(double, tag = "2")]
+    pub average_fee: f64,
+    #[prost(uint64, tag = "3")]
+    pub sample_count: u64,
+    #[prost(bool, tag = "4")]
+    pub is_partial: bool,
+    #[prost(message, optional, tag = "5")]
+    pub percentiles: Option<Percentiles>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Percentiles {
+    #[prost(uint64, tag = "1")]
+    pub p10: u64,
+    #[prost(uint64, tag = "2")]
+    pub p25: u64,
+    #[prost(uint64, tag = "3")]
+    pub p50: u64,
+    #[prost(uint64, tag = "4")]
+    pub p75: u64,
+    #[prost(uint64, tag = "5")]
+    pub p90: u64,
+    #[prost(uint64, tag = "6")]
+    pub p95: u64,
+    #[prost(uint64, tag = "7")]
+    pub p99: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetFeeHistoryRequest {
+    #[prost(int64, tag = "1")]
+    pub start_ms: i64,
+    #[prost(int64, tag = "2")]
+    pub end_ms: i64,
+    #[prost(uint32, tag = "3")]
+    pub limit: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FeeHistory {
+    #[prost(message, repeated, tag = "1")]
+    pub points: Vec<FeePoint>,
+    #[prost(bool, tag = "2")]
+    pub truncated: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FeePoint {
+    #[prost(uint64, tag = "1")]
+    pub fee_amount: u64,
+    #[prost(int64, tag = "2")]
+    pub timestamp_ms: i64,
+    #[prost(string, tag = "3")]
+    pub transaction_hash: String,
+    #[prost(uint64, tag = "4")]
+    pub ledger_sequence: u64,
+    #[prost(uint64, optional, tag = "5")]
+    pub max_fee: Option<u64>,
+    #[prost(uint32, optional, tag = "6")]
+    pub operation_count: Option<u32>,
+    #[prost(bool, tag = "7")]
+    pub fee_bump: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum Urgency {
+    Unspecified = 0,
+    Economy = 1,
+    Standard = 2,
+    Priority = 3,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetFeeRecommendationRequest {
+    #[prost(enumeration = "Urgency", tag = "1")]
+    pub urgency: i32,
+    #[prost(uint32, tag = "2")]
+    pub operations: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FeeRecommendation {
+    #[prost(uint64, tag = "1")]
+    pub fee: u64,
+    #[prost(uint64, tag = "2")]
+    pub inclusion_fee_per_operation: u64,
+    #[prost(string, tag = "3")]
+    pub percentile: String,
+    #[prost(string, tag = "4")]
+    pub time_window: String,
+    #[prost(string, tag = "5")]
+    pub congestion: String,
+    #[prost(int64, tag = "6")]
+    pub generated_at_ms: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StreamUpdatesRequest {
+    #[prost(uint64, tag = "1")]
+    pub min_fee: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FeeUpdate {
+    #[prost(message, repeated, tag = "1")]
+    pub points: Vec<FeePoint>,
+    #[prost(message, optional, tag = "2")]
+    pub insights: Option<Insights>,
+}
@@ -1,4 +1,8 @@
-use axum::http::{header, HeaderMap, HeaderValue};
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
 use chrono::{DateTime, Utc};
 
 /// Compute a stable quoted ETag from response bytes using FNV-1a (64-bit).
@@ -11,10 +15,9 @@ pub fn compute_etag(body: &[u8]) -> String {
     const FNV_OFFSET: u64 = 14_695_981_039_346_656_037;
     const FNV_PRIME: u64 = 1_099_511_628_211;
 
-    let hash = body
-        .iter()
-        .fold(FNV_OFFSET, |acc, &byte| acc ^ (byte as u64))
-        .wrapping_mul(FNV_PRIME);
+    let hash = body.iter().fold(FNV_OFFSET, |acc, &byte| {
+        (acc ^ byte as u64).wrapping_mul(FNV_PRIME)
+    });
 
     format!("\"{:x}\"", hash)
 }
@@ -35,19 +38,68 @@ pub fn last_modified(timestamp: DateTime<Utc>) -> HeaderValue {
     HeaderValue::from_str(&formatted).unwrap_or_else(|_| HeaderValue::from_static("0"))
 }
 
-/// Returns true when `If-None-Match` contains `*` or the exact current ETag.
+/// Returns true when `If-None-Match` contains `*` or the current ETag.
+///
+/// Tags are compared weakly, as RFC 9110 requires for `If-None-Match`, so a
+/// `W/` prefix added by a proxy still matches.
 pub fn if_none_match_matches(headers: &HeaderMap, current_etag: &str) -> bool {
+    let current_etag = current_etag.trim_start_matches("W/");
     headers
         .get(header::IF_NONE_MATCH)
         .and_then(|value| value.to_str().ok())
         .map(|raw| {
             raw.split(',')
                 .map(|tag| tag.trim())
-                .any(|tag| tag == "*" || tag == current_etag)
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == current_etag)
         })
         .unwrap_or(false)
 }
 
+/// 304 Not Modified with the validators and caching headers the full
+/// response would carry.
+pub fn not_modified(
+    etag: &str,
+    cache_control: HeaderValue,
+    last_modified: Option<HeaderValue>,
+) -> Response {
+    let mut response = StatusCode::NOT_MODIFIED.into_response();
+    set_validators(response.headers_mut(), etag, cache_control, last_modified);
+    response
+}
+
+/// 200 with a JSON `body`, its ETag and caching headers.
+pub fn json_with_etag(
+    body: Vec<u8>,
+    etag: &str,
+    cache_control: HeaderValue,
+    last_modified: Option<HeaderValue>,
+) -> Response {
+    let mut response = Response::new(Body::from(body));
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    set_validators(headers, etag, cache_control, last_modified);
+    response
+}
+
+fn set_validators(
+    headers: &mut HeaderMap,
+    etag: &str,
+    cache_control: HeaderValue,
+    last_modified: Option<HeaderValue>,
+) {
+    headers.insert(header::CACHE_CONTROL, cache_control);
+    // Tags come from compute_etag, which only emits hex digits
+    if let Ok(etag) = HeaderValue::from_str(etag) {
+        headers.insert(header::ETAG, etag);
+    }
+    if let Some(last_modified) = last_modified {
+        headers.insert(header::LAST_MODIFIED, last_modified);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,6 +111,31 @@ mod tests {
         assert!(etag.ends_with('"'));
     }
 
+    #[test]
+    fn etag_depends_on_every_byte_and_its_position() {
+        assert_ne!(compute_etag(b"ab"), compute_etag(b"ba"));
+        assert_ne!(
+            compute_etag(b"{\"fee\":100}"),
+            compute_etag(b"{\"fee\":010}")
+        );
+        assert_eq!(
+            compute_etag(b""),
+            format!("\"{:x}\"", 14_695_981_039_346_656_037u64)
+        );
+    }
+
+    #[test]
+    fn if_none_match_compares_weakly() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_static("\"old\", W/\"abc\""),
+        );
+
+        assert!(if_none_match_matches(&headers, "\"abc\""));
+        assert!(!if_none_match_matches(&headers, "\"ab\""));
+    }
+
     #[test]
     fn if_none_match_matches_exact_tag() {
         let mut headers = HeaderMap::new();
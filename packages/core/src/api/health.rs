@@ -1,13 +1,572 @@
+//! Liveness, readiness and component health.
+//!
+//! Routes:
+//! - `GET /health/live` — whether the scheduler is still ticking; 503 once
+//!   it has been silent for longer than [`HealthState::stale_after`]
+//! - `GET /health/ready` — whether the API can serve current data: the
+//!   database answers and the engine was updated recently
+//! - `GET /health` — every component: database, fee data provider,
+//!   scheduler and data freshness. Missing or stale data, like an
+//!   unreachable provider, only degrades it: stored data is still served
+//! - `GET /health/provider` — operational detail of the fee data provider,
+//!   including which failover backend is serving and each backend's health
+//!
+//! The first three answer `{"status": …, "checks": {…}}` where `status` is
+//! the worst component status: `ok`, `degraded` or `down`. Any `down`
+//! component makes the response a 503.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use axum::{
-    body::Body,
+    extract::State,
     http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
+    Json,
 };
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::{Mutex, RwLock};
+
+use crate::insights::failover::AggregateHealth;
+use crate::insights::provider::{CircuitState, FeeDataProvider, ProviderStatus};
+use crate::insights::FeeInsightsEngine;
+use crate::repository::FeeRepository;
+use crate::scheduler::SchedulerHeartbeat;
+
+/// Provider metadata older than this is refreshed by `GET /health/provider`.
+const METADATA_MAX_AGE_SECONDS: i64 = 60;
+
+/// Longest `GET /health` waits for the provider's own health check.
+const PROVIDER_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long `GET /health` and `GET /health/provider` reuse the provider's
+/// last health check or metadata refresh attempt, so the unauthenticated
+/// routes cannot make every request an upstream call.
+const PROVIDER_PROBE_TTL: Duration = Duration::from_secs(10);
+
+/// A fee data provider, as `HealthState` holds it.
+pub type ProviderHealthState = Arc<dyn FeeDataProvider + Send + Sync>;
+
+/// Shared state for every `/health` route
+#[derive(Clone)]
+pub struct HealthState {
+    pub repository: Arc<FeeRepository>,
+    pub provider: ProviderHealthState,
+    pub insights_engine: Arc<RwLock<FeeInsightsEngine>>,
+    pub heartbeat: Arc<SchedulerHeartbeat>,
+    /// Longest the scheduler or the engine may go without an update.
+    pub stale_after: chrono::Duration,
+    pub provider_probe: Arc<ProviderProbe>,
+}
+
+/// The provider's last health check and when it ran. Held while probing,
+/// so concurrent requests wait for that probe instead of starting their own.
+#[derive(Default)]
+pub struct ProviderProbe {
+    last: Mutex<Option<(Instant, ComponentHealth)>>,
+    /// When `GET /health/provider` last tried to refresh metadata.
+    last_refresh: Mutex<Option<Instant>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    /// Working, but with reduced functionality.
+    Degraded,
+    Down,
+}
+
+/// Outcome of checking one component
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentHealth {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Last scheduler beat or engine update, for the time-based checks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+impl ComponentHealth {
+    fn ok() -> Self {
+        Self {
+            status: HealthStatus::Ok,
+            detail: None,
+            last_seen: None,
+        }
+    }
+
+    fn failing(status: HealthStatus, detail: impl Into<String>) -> Self {
+        Self {
+            status,
+            detail: Some(detail.into()),
+            last_seen: None,
+        }
+    }
+}
+
+impl ProviderProbe {
+    /// Whether a metadata refresh may run now, claiming it if so.
+    async fn may_refresh(&self) -> bool {
+        let mut last = self.last_refresh.lock().await;
+        if last.is_some_and(|at| at.elapsed() < PROVIDER_PROBE_TTL) {
+            return false;
+        }
+        *last = Some(Instant::now());
+        true
+    }
+}
+
+impl HealthState {
+    async fn check_database(&self) -> ComponentHealth {
+        match self.repository.ping().await {
+            Ok(()) => ComponentHealth::ok(),
+            Err(err) => ComponentHealth::failing(HealthStatus::Down, err.to_string()),
+        }
+    }
+
+    /// Degraded rather than down: stored data is still served while the
+    /// provider is unavailable. Probes at most once per
+    /// [`PROVIDER_PROBE_TTL`].
+    async fn check_provider(&self) -> ComponentHealth {
+        let mut last = self.provider_probe.last.lock().await;
+        if let Some((at, health)) = last.as_ref() {
+            if at.elapsed() < PROVIDER_PROBE_TTL {
+                return health.clone();
+            }
+        }
+        let health = self.probe_provider().await;
+        *last = Some((Instant::now(), health.clone()));
+        health
+    }
+
+    async fn probe_provider(&self) -> ComponentHealth {
+        match tokio::time::timeout(PROVIDER_CHECK_TIMEOUT, self.provider.health_check()).await {
+            Ok(Ok(())) => match down_backends(&self.provider.provider_status()) {
+                Some(down) => ComponentHealth::failing(
+                    HealthStatus::Degraded,
+                    format!("{}: backends down: {}", self.provider.provider_name(), down),
+                ),
+                None => ComponentHealth::ok(),
+            },
+            Ok(Err(err)) => ComponentHealth::failing(
+                HealthStatus::Degraded,
+                format!("{}: {}", self.provider.provider_name(), err),
+            ),
+            Err(_) => ComponentHealth::failing(
+                HealthStatus::Degraded,
+                format!("{}: health check timed out", self.provider.provider_name()),
+            ),
+        }
+    }
+
+    fn check_scheduler(&self) -> ComponentHealth {
+        match self.heartbeat.last_beat() {
+            None => ComponentHealth::failing(HealthStatus::Degraded, "No poll has completed yet"),
+            Some(at) => self.check_age(at, "Scheduler has not ticked"),
+        }
+    }
+
+    /// `missing` before the first batch and once the data is stale.
+    async fn check_data(&self, missing: HealthStatus) -> ComponentHealth {
+        match self.insights_engine.read().await.get_last_update() {
+            None => ComponentHealth::failing(missing, "No fee data received yet"),
+            Some(at) => self.check_age_as(at, "No new fee data", missing),
+        }
+    }
+
+    /// Down once `at` is older than `stale_after`.
+    fn check_age(&self, at: DateTime<Utc>, stale: &str) -> ComponentHealth {
+        self.check_age_as(at, stale, HealthStatus::Down)
+    }
+
+    /// `status` once `at` is older than `stale_after`.
+    fn check_age_as(
+        &self,
+        at: DateTime<Utc>,
+        stale: &str,
+        status: HealthStatus,
+    ) -> ComponentHealth {
+        let age = Utc::now() - at;
+        let mut health = if age > self.stale_after {
+            ComponentHealth::failing(status, format!("{} for {}s", stale, age.num_seconds()))
+        } else {
+            ComponentHealth::ok()
+        };
+        health.last_seen = Some(at);
+        health
+    }
+}
+
+/// Failover backends last seen down, when some but not all of them are.
+fn down_backends(status: &ProviderStatus) -> Option<String> {
+    let failover = status.failover.as_ref()?;
+    (failover.health == Some(AggregateHealth::Degraded)).then(|| {
+        failover
+            .backends
+            .iter()
+            .filter(|backend| !backend.healthy)
+            .map(|backend| backend.label.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    })
+}
+
+/// Report `checks`, with a 503 when any of them is down.
+fn health_response(checks: BTreeMap<&'static str, ComponentHealth>) -> Response {
+    let status = checks
+        .values()
+        .map(|check| check.status)
+        .max()
+        .unwrap_or(HealthStatus::Ok);
+    let code = match status {
+        HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::OK,
+    };
+    (
+        code,
+        [(header::CACHE_CONTROL, HeaderValue::from_static("no-store"))],
+        Json(json!({ "status": status, "checks": checks })),
+    )
+        .into_response()
+}
+
+/// `GET /health` — status of every component. Missing or stale data
+/// degrades it; only a failed database or a stalled scheduler is a 503.
+pub async fn health(State(state): State<HealthState>) -> Response {
+    let (database, provider, data) = tokio::join!(
+        state.check_database(),
+        state.check_provider(),
+        state.check_data(HealthStatus::Degraded)
+    );
+    health_response(BTreeMap::from([
+        ("database", database),
+        ("provider", provider),
+        ("scheduler", state.check_scheduler()),
+        ("data", data),
+    ]))
+}
+
+/// `GET /health/live` — whether the scheduler is still ticking. A process
+/// that has not completed its first poll yet is live.
+pub async fn live(State(state): State<HealthState>) -> Response {
+    health_response(BTreeMap::from([("scheduler", state.check_scheduler())]))
+}
+
+/// `GET /health/ready` — whether the database answers and the data is
+/// fresh.
+pub async fn ready(State(state): State<HealthState>) -> Response {
+    let (database, data) =
+        tokio::join!(state.check_database(), state.check_data(HealthStatus::Down));
+    health_response(BTreeMap::from([("database", database), ("data", data)]))
+}
+
+/// `GET /health/provider` — operational state of the active fee data
+/// provider, including its circuit breaker, rate-limit window, call stats
+/// and network metadata.
+///
+/// `health` is the provider's last health check, shared with `GET /health`
+/// and rerun at most once per [`PROVIDER_PROBE_TTL`]. Metadata is
+/// refreshed first if it is older than a minute, again at most once per
+/// TTL; if the refresh fails the cached copy is reported with
+/// `metadata_stale: true`. Always answers 200; `status` is `degraded` while
+/// the health check fails, the circuit is not closed or a failover backend
+/// was last seen down.
+pub async fn provider_health(State(state): State<HealthState>) -> impl IntoResponse {
+    let provider = &state.provider;
+    let health = state.check_provider().await;
+    let status = provider.provider_status();
+    let degraded = health.status != HealthStatus::Ok
+        || status
+            .circuit_breaker
+            .as_ref()
+            .is_some_and(|b| b.state != CircuitState::Closed)
+        || status.failover.as_ref().is_some_and(|f| {
+            matches!(
+                f.health,
+                Some(AggregateHealth::Degraded | AggregateHealth::Unavailable)
+            )
+        });
+
+    let max_age = chrono::Duration::seconds(METADATA_MAX_AGE_SECONDS);
+    let mut metadata = provider.get_metadata();
+    if metadata.is_stale(max_age) && state.provider_probe.may_refresh().await {
+        match provider.refresh_metadata().await {
+            Ok(refreshed) => metadata = refreshed,
+            Err(err) => tracing::warn!(
+                "Failed to refresh {} metadata: {}",
+                provider.provider_name(),
+                err
+            ),
+        }
+    }
+
+    let body: Value = json!({
+        "status": if degraded { "degraded" } else { "ok" },
+        "provider": provider.provider_name(),
+        "health": health,
+        "circuit_breaker": status.circuit_breaker,
+        "rate_limit": status.rate_limit,
+        "stats": status.stats,
+        "failover": status.failover,
+        "metadata_stale": metadata.is_stale(max_age),
+        "metadata": metadata,
+    });
+
+    (
+        [(header::CACHE_CONTROL, HeaderValue::from_static("no-store"))],
+        Json(body),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::insights::config::CircuitBreakerConfig;
+    use crate::insights::error::ProviderError;
+    use crate::insights::{CircuitBreakerProvider, FailoverFeeDataProvider};
+    use crate::insights::{FeeDataPoint, InsightsConfig};
+    use crate::services::mock_horizon::MockHorizonClient;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    async fn get_provider_health(provider: ProviderHealthState) -> Value {
+        get_provider_health_of(&health_state(provider).await).await
+    }
+
+    async fn get_provider_health_of(state: &HealthState) -> Value {
+        let app = Router::new()
+            .route("/health/provider", get(provider_health))
+            .with_state(state.clone());
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/health/provider")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn provider_without_breaker_reports_ok() {
+        let json = get_provider_health(Arc::new(MockHorizonClient::new())).await;
+        assert_eq!(json["status"], "ok");
+        assert_eq!(json["provider"], "MockHorizon");
+        assert!(json["circuit_breaker"].is_null());
+    }
+
+    #[tokio::test]
+    async fn reports_metadata_and_flags_it_stale_when_never_refreshed() {
+        let json = get_provider_health(Arc::new(MockHorizonClient::new())).await;
+        assert_eq!(json["metadata"]["max_batch_size"], 100);
+        assert!(json["metadata"]["latest_ledger"].is_null());
+        assert_eq!(json["metadata_stale"], true);
+    }
+
+    #[tokio::test]
+    async fn open_circuit_reports_degraded() {
+        let breaker = CircuitBreakerProvider::new(
+            MockHorizonClient::new().with_error(ProviderError::ServiceUnavailable),
+            CircuitBreakerConfig {
+                failure_threshold: 1,
+                ..CircuitBreakerConfig::default()
+            },
+        );
+        breaker.fetch_latest_fees().await.ok();
+
+        let json = get_provider_health(Arc::new(breaker)).await;
+        assert_eq!(json["status"], "degraded");
+        assert_eq!(json["circuit_breaker"]["state"], "open");
+        assert_eq!(json["circuit_breaker"]["consecutive_failures"], 1);
+    }
+
+    fn half_down_failover() -> FailoverFeeDataProvider {
+        FailoverFeeDataProvider::new()
+            .with_backend(
+                "primary",
+                Arc::new(MockHorizonClient::new().with_healthy(false)),
+            )
+            .with_backend(
+                "secondary",
+                Arc::new(MockHorizonClient::new().with_healthy(true)),
+            )
+    }
+
+    #[tokio::test]
+    async fn failover_with_a_backend_down_reports_degraded() {
+        let failover = half_down_failover();
+        failover.health_report().await;
+
+        let json = get_provider_health(Arc::new(failover)).await;
+        assert_eq!(json["status"], "degraded");
+        assert_eq!(json["failover"]["health"], "degraded");
+        assert_eq!(json["failover"]["backends"][0]["label"], "primary");
+        assert_eq!(json["failover"]["backends"][0]["healthy"], false);
+    }
+
+    #[tokio::test]
+    async fn health_reports_failover_backends_down() {
+        let state = health_state(Arc::new(half_down_failover())).await;
+
+        let (_, json) = get_health(&state, "/health").await;
+        assert_eq!(json["checks"]["provider"]["status"], "degraded");
+        assert_eq!(
+            json["checks"]["provider"]["detail"],
+            "Failover: backends down: primary"
+        );
+    }
+
+    async fn health_state(provider: ProviderHealthState) -> HealthState {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        HealthState {
+            repository: Arc::new(FeeRepository::new(pool)),
+            provider,
+            insights_engine: Arc::new(RwLock::new(FeeInsightsEngine::new(
+                InsightsConfig::default(),
+            ))),
+            heartbeat: Arc::new(SchedulerHeartbeat::new()),
+            stale_after: chrono::Duration::minutes(1),
+            provider_probe: Arc::new(ProviderProbe::default()),
+        }
+    }
+
+    async fn get_health(state: &HealthState, uri: &str) -> (StatusCode, Value) {
+        let app = Router::new()
+            .route("/health", get(health))
+            .route("/health/live", get(live))
+            .route("/health/ready", get(ready))
+            .with_state(state.clone());
+        let resp = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn ready_once_data_arrives_and_reports_each_component() {
+        let state = health_state(Arc::new(MockHorizonClient::new().with_healthy(false))).await;
+
+        // Before the first poll: live, but nothing to serve yet
+        let (status, json) = get_health(&state, "/health/live").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["status"], "degraded");
+        let (status, json) = get_health(&state, "/health/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["checks"]["database"]["status"], "ok");
+        assert_eq!(json["checks"]["data"]["status"], "down");
+        let (status, json) = get_health(&state, "/health").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["checks"]["data"]["status"], "degraded");
+
+        state.heartbeat.beat();
+        state
+            .insights_engine
+            .write()
+            .await
+            .process_fee_data(&[FeeDataPoint {
+                fee_amount: 100,
+                timestamp: chrono::Utc::now(),
+                transaction_hash: "tx".to_string(),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            }])
+            .await
+            .unwrap();
+
+        let (status, json) = get_health(&state, "/health/ready").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["status"], "ok");
+
+        // An unhealthy provider degrades the service without taking it down
+        let (status, json) = get_health(&state, "/health").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["status"], "degraded");
+        assert_eq!(json["checks"]["provider"]["status"], "degraded");
+        assert_eq!(json["checks"]["scheduler"]["status"], "ok");
+        assert_eq!(json["checks"]["data"]["status"], "ok");
+        assert!(json["checks"]["data"]["last_seen"].is_string());
+    }
+
+    #[tokio::test]
+    async fn provider_is_probed_at_most_once_per_ttl() {
+        let provider = MockHorizonClient::new();
+        let health_checks = provider.health_checks.clone();
+        let state = health_state(Arc::new(provider)).await;
+
+        for _ in 0..3 {
+            let (_, json) = get_health(&state, "/health").await;
+            assert_eq!(json["checks"]["provider"]["status"], "ok");
+        }
+        assert_eq!(health_checks.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn provider_route_reuses_the_cached_probe() {
+        let provider = MockHorizonClient::new();
+        let health_checks = provider.health_checks.clone();
+        let state = health_state(Arc::new(provider)).await;
+
+        let (_, json) = get_health(&state, "/health").await;
+        assert_eq!(json["checks"]["provider"]["status"], "ok");
+        for _ in 0..3 {
+            let json = get_provider_health_of(&state).await;
+            assert_eq!(json["health"]["status"], "ok");
+        }
+        assert_eq!(health_checks.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn stale_data_degrades_health_and_fails_readiness() {
+        let mut state = health_state(Arc::new(MockHorizonClient::new())).await;
+        state
+            .insights_engine
+            .write()
+            .await
+            .process_fee_data(&[FeeDataPoint {
+                fee_amount: 100,
+                timestamp: chrono::Utc::now(),
+                transaction_hash: "tx".to_string(),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            }])
+            .await
+            .unwrap();
+        state.stale_after = chrono::Duration::zero();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let (_, json) = get_health(&state, "/health").await;
+        assert_eq!(json["checks"]["data"]["status"], "degraded");
+        let (status, json) = get_health(&state, "/health/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["checks"]["data"]["status"], "down");
+    }
+
+    #[tokio::test]
+    async fn stalled_scheduler_fails_liveness() {
+        let mut state = health_state(Arc::new(MockHorizonClient::new())).await;
+        state.stale_after = chrono::Duration::zero();
+        state.heartbeat.beat();
+        tokio::time::sleep(Duration::from_millis(5)).await;
 
-pub async fn health() -> impl IntoResponse {
-    Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CACHE_CONTROL, HeaderValue::from_static("no-store"))
-        .body(Body::from("ok"))
-        .expect("health response should be valid")
+        let (status, json) = get_health(&state, "/health/live").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["status"], "down");
+        assert_eq!(json["checks"]["scheduler"]["status"], "down");
+    }
 }
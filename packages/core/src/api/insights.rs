@@ -1,19 +1,33 @@
 //! Insights API endpoints
 
 use axum::{
-    body::Body,
-    extract::State,
-    http::{header, HeaderMap, StatusCode},
-    response::{Json, Response},
-    routing::get,
+    extract::{Query, State},
+    http::HeaderMap,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json, Response,
+    },
+    routing::{get, post},
     Router,
 };
+use futures::Stream;
+use serde::Deserialize;
 use serde_json::Value;
+use std::convert::Infallible;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast::error::RecvError, RwLock};
 
-use super::headers::{cache_control, compute_etag, if_none_match_matches, last_modified};
-use crate::insights::{CongestionTrends, FeeExtremes, FeeInsightsEngine, RollingAverages};
+use super::fields::select_fields;
+use super::headers::{
+    cache_control, compute_etag, if_none_match_matches, json_with_etag, last_modified, not_modified,
+};
+use super::timezone::render_timezone;
+use crate::error::AppError;
+use crate::insights::{
+    CongestionTrends, FeeExtremes, FeeForecast, FeeInsightsEngine, FeeRecommendation,
+    FeeStatsCrossCheck, InclusionEstimate, InsightsError, MarketDepth, RollingAverages,
+    SeasonalityProfile, SorobanFeeEstimate, SorobanResources, SurgePricing,
+};
 
 /// Shared state for the insights API
 pub type InsightsState = Arc<RwLock<FeeInsightsEngine>>;
@@ -26,58 +40,80 @@ pub fn create_insights_router(insights_engine: InsightsState) -> Router {
         .route("/insights/extremes", get(get_extremes))
         .route("/insights/congestion", get(get_congestion_trends))
         .route("/insights/health", get(get_insights_health))
+        .route("/insights/fee-stats", get(get_fee_stats_cross_check))
+        .route("/insights/forecast", get(get_fee_forecast))
+        .route("/insights/recommendation", get(get_fee_recommendation))
+        .route("/insights/inclusion", get(get_inclusion_estimate))
+        .route("/insights/market-depth", get(get_market_depth))
+        .route("/insights/soroban-estimate", post(estimate_soroban_fee))
+        .route("/insights/seasonality", get(get_seasonality_profile))
+        .route("/insights/surge-pricing", get(get_surge_pricing))
+        .route("/insights/events", get(stream_events))
+        .route_layer(axum::middleware::from_fn(select_fields))
+        .route_layer(axum::middleware::from_fn(render_timezone))
         .with_state(insights_engine)
 }
 
+/// Seconds `/insights` may be cached, then served stale while revalidating.
+pub(crate) const INSIGHTS_MAX_AGE: u32 = 10;
+pub(crate) const INSIGHTS_SWR: u32 = 20;
+
+/// ETag of `representation` of the engine's insights, known without
+/// serializing them: they only change when the engine processes fees.
+/// `None` before it has.
+pub(crate) fn insights_etag(engine: &FeeInsightsEngine, representation: &str) -> Option<String> {
+    engine.get_last_update().map(|updated| {
+        compute_etag(format!("{}:{}", representation, updated.to_rfc3339()).as_bytes())
+    })
+}
+
 /// Get current insights
 async fn get_current_insights(
     State(engine): State<InsightsState>,
     request_headers: HeaderMap,
-) -> Result<Response, (StatusCode, Json<Value>)> {
+) -> Result<Response, AppError> {
     let engine = engine.read().await;
+    let cache_control = cache_control(INSIGHTS_MAX_AGE, INSIGHTS_SWR);
+    let etag = insights_etag(&engine, "insights");
+    if let (Some(etag), Some(updated)) = (&etag, engine.get_last_update()) {
+        if if_none_match_matches(&request_headers, etag) {
+            return Ok(not_modified(
+                etag,
+                cache_control,
+                Some(last_modified(updated)),
+            ));
+        }
+    }
+
     let insights = engine.get_current_insights();
-    let body = serde_json::to_vec(&insights).map_err(|err| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({ "error": format!("Failed to serialize insights: {}", err) })),
-        )
-    })?;
-    let etag = compute_etag(&body);
-    let last_modified_value = last_modified(insights.last_updated);
+    drop(engine);
+    let body = serde_json::to_vec(&insights)
+        .map_err(|err| AppError::Unknown(format!("Failed to serialize insights: {}", err)))?;
+    let etag = etag.unwrap_or_else(|| compute_etag(&body));
+    let last_modified_value = Some(last_modified(insights.last_updated));
 
     if if_none_match_matches(&request_headers, &etag) {
-        return Ok(Response::builder()
-            .status(StatusCode::NOT_MODIFIED)
-            .header(header::CACHE_CONTROL, cache_control(10, 20))
-            .header(header::ETAG, etag.as_str())
-            .header(header::LAST_MODIFIED, last_modified_value)
-            .body(Body::empty())
-            .expect("304 insights response should be valid"));
+        return Ok(not_modified(&etag, cache_control, last_modified_value));
     }
-
-    Ok(Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "application/json")
-        .header(header::CACHE_CONTROL, cache_control(10, 20))
-        .header(header::ETAG, etag.as_str())
-        .header(header::LAST_MODIFIED, last_modified_value)
-        .body(Body::from(body))
-        .expect("insights response should be valid"))
+    Ok(json_with_etag(
+        body,
+        &etag,
+        cache_control,
+        last_modified_value,
+    ))
 }
 
 /// Get rolling averages
 async fn get_rolling_averages(
     State(engine): State<InsightsState>,
-) -> Result<Json<RollingAverages>, (StatusCode, Json<Value>)> {
+) -> Result<Json<RollingAverages>, AppError> {
     let engine = engine.read().await;
     let averages = engine.get_rolling_averages();
     Ok(Json(averages))
 }
 
 /// Get fee extremes
-async fn get_extremes(
-    State(engine): State<InsightsState>,
-) -> Result<Json<FeeExtremes>, (StatusCode, Json<Value>)> {
+async fn get_extremes(State(engine): State<InsightsState>) -> Result<Json<FeeExtremes>, AppError> {
     let engine = engine.read().await;
     let extremes = engine.get_extremes();
     Ok(Json(extremes))
@@ -86,16 +122,140 @@ async fn get_extremes(
 /// Get congestion trends
 async fn get_congestion_trends(
     State(engine): State<InsightsState>,
-) -> Result<Json<CongestionTrends>, (StatusCode, Json<Value>)> {
+) -> Result<Json<CongestionTrends>, AppError> {
     let engine = engine.read().await;
     let trends = engine.get_congestion_trends();
     Ok(Json(trends))
 }
 
-/// Get insights engine health status
-async fn get_insights_health(
+/// Compare our rolling average with Horizon's latest `/fee_stats`
+async fn get_fee_stats_cross_check(
+    State(engine): State<InsightsState>,
+) -> Result<Json<FeeStatsCrossCheck>, AppError> {
+    let engine = engine.read().await;
+    engine
+        .get_fee_stats_cross_check()
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound("No fee_stats snapshot recorded yet".to_string()))
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastQuery {
+    /// Minutes ahead to forecast; defaults to 10.
+    minutes: Option<i64>,
+}
+
+/// Forecast fees a few minutes ahead
+async fn get_fee_forecast(
+    State(engine): State<InsightsState>,
+    Query(params): Query<ForecastQuery>,
+) -> Result<Json<FeeForecast>, AppError> {
+    let minutes = params.minutes.unwrap_or(10);
+    let horizon = chrono::Duration::try_minutes(minutes)
+        .ok_or_else(|| AppError::BadRequest(format!("Unsupported minutes value: {}", minutes)))?;
+    let engine = engine.read().await;
+    engine
+        .get_forecast(horizon)
+        .map(Json)
+        .map_err(|err| match err {
+            InsightsError::InsufficientData { .. } => AppError::Unavailable(err.to_string()),
+            _ => AppError::BadRequest(err.to_string()),
+        })
+}
+
+/// Suggest fee bids by inclusion urgency
+async fn get_fee_recommendation(
     State(engine): State<InsightsState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<FeeRecommendation>, AppError> {
+    let engine = engine.read().await;
+    Ok(Json(engine.get_fee_recommendation()))
+}
+
+#[derive(Debug, Deserialize)]
+struct InclusionQuery {
+    /// Proposed fee bid, in stroops.
+    fee: u64,
+}
+
+/// Estimate how soon a fee bid would be included
+async fn get_inclusion_estimate(
+    State(engine): State<InsightsState>,
+    Query(params): Query<InclusionQuery>,
+) -> Result<Json<InclusionEstimate>, AppError> {
+    let engine = engine.read().await;
+    Ok(Json(engine.estimate_inclusion(params.fee)))
+}
+
+#[derive(Debug, Deserialize)]
+struct MarketDepthQuery {
+    /// Per-operation bid to place among recent bids, in stroops.
+    fee: Option<u64>,
+}
+
+/// Show how recent fee bids are spread across fee levels
+async fn get_market_depth(
+    State(engine): State<InsightsState>,
+    Query(params): Query<MarketDepthQuery>,
+) -> Result<Json<MarketDepth>, AppError> {
+    let engine = engine.read().await;
+    Ok(Json(engine.get_market_depth(params.fee)))
+}
+
+/// Price a Soroban invocation footprint end to end
+async fn estimate_soroban_fee(
+    State(engine): State<InsightsState>,
+    Json(resources): Json<SorobanResources>,
+) -> Result<Json<SorobanFeeEstimate>, AppError> {
+    let engine = engine.read().await;
+    Ok(Json(engine.estimate_soroban_fee(&resources)))
+}
+
+/// Stream congestion changes, anomalies and surge starts as Server-Sent
+/// Events named after their `event` field
+async fn stream_events(
+    State(engine): State<InsightsState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = engine.read().await.subscribe_events();
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let data = serde_json::to_value(&event).unwrap_or_default();
+                    let name = data["event"].as_str().unwrap_or("insight").to_string();
+                    let sse = Event::default().event(name).data(data.to_string());
+                    return Some((Ok(sse), receiver));
+                }
+                // A slow client skips what it missed
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Get the current surge pricing status
+async fn get_surge_pricing(
+    State(engine): State<InsightsState>,
+) -> Result<Json<SurgePricing>, AppError> {
+    let engine = engine.read().await;
+    engine
+        .get_surge_pricing()
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound("No classic transaction fees seen yet".to_string()))
+}
+
+/// Get typical fees by hour of day and day of week
+async fn get_seasonality_profile(
+    State(engine): State<InsightsState>,
+) -> Result<Json<SeasonalityProfile>, AppError> {
+    let engine = engine.read().await;
+    Ok(Json(engine.get_seasonality_profile()))
+}
+
+/// Get insights engine health status
+async fn get_insights_health(State(engine): State<InsightsState>) -> Result<Json<Value>, AppError> {
     let engine = engine.read().await;
 
     let health_info = serde_json::json!({
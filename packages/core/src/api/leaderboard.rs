@@ -0,0 +1,117 @@
+//! Fee leaderboard.
+//!
+//! Routes:
+//! - `GET /insights/leaderboard?from=…&to=…&limit=…` — the accounts that
+//!   paid the most in fees and the most expensive transactions in the range
+//!
+//! `to` defaults to now and `from` to 24 hours before `to`. `limit` applies
+//! to each list, defaults to 10 and is capped at 100.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::error::AppError;
+use crate::leaderboard::{fee_leaderboard, FeeLeaderboard};
+use crate::repository::FeeRepository;
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 100;
+
+/// Shared state for the leaderboard route.
+pub type LeaderboardState = Arc<FeeRepository>;
+
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardQuery {
+    /// RFC 3339 timestamps.
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+/// `GET /insights/leaderboard` — top fee payers and transactions.
+pub async fn leaderboard(
+    State(repo): State<LeaderboardState>,
+    Query(params): Query<LeaderboardQuery>,
+) -> Result<Json<FeeLeaderboard>, AppError> {
+    let to = params.to.unwrap_or_else(Utc::now);
+    let from = params.from.unwrap_or(to - chrono::Duration::hours(24));
+    if from >= to {
+        return Err(AppError::BadRequest(
+            "Range must start before it ends".to_string(),
+        ));
+    }
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    Ok(Json(fee_leaderboard(&repo, from, to, limit).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::db::create_pool;
+    use crate::insights::FeeDataPoint;
+
+    #[tokio::test]
+    async fn serves_the_most_expensive_transactions() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = Arc::new(FeeRepository::new(pool));
+        let points: Vec<FeeDataPoint> = (1..=20u64)
+            .map(|i| FeeDataPoint {
+                fee_amount: 100 * i,
+                timestamp: Utc::now() - chrono::Duration::minutes(i as i64),
+                transaction_hash: format!("tx_{}", i),
+                ledger_sequence: i,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        repo.insert_fee_points(&points).await.unwrap();
+
+        let app = Router::new()
+            .route("/insights/leaderboard", get(leaderboard))
+            .with_state(repo);
+        let get_json = |uri: &str| {
+            let app = app.clone();
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            async move {
+                let resp = app.oneshot(request).await.unwrap();
+                let status = resp.status();
+                let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&bytes).unwrap(),
+                )
+            }
+        };
+
+        let (status, json) = get_json("/insights/leaderboard?limit=3").await;
+        assert_eq!(status, StatusCode::OK);
+        let transactions = json["transactions"].as_array().unwrap();
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(transactions[0]["transaction_hash"], "tx_20");
+        assert_eq!(transactions[2]["fee_amount"], 1_800);
+        // Fee accounts are not recorded by default
+        assert!(json["payers"].as_array().unwrap().is_empty());
+
+        let (_, json) = get_json("/insights/leaderboard").await;
+        assert_eq!(
+            json["transactions"].as_array().unwrap().len(),
+            DEFAULT_LIMIT
+        );
+    }
+}
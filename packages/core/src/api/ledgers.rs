@@ -0,0 +1,172 @@
+//! Fees of a single ledger.
+//!
+//! Routes:
+//! - `GET /ledgers/:sequence/fees` — every stored fee of the ledger, oldest
+//!   first, with a summary: count, min, average, max and percentiles of
+//!   the fees, plus the ledger's close time, base fee and capacity
+//!   utilization when the provider reported the ledger. Answers 404 when
+//!   nothing is stored for it, e.g. once retention has pruned it
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::error::AppError;
+use crate::insights::calculator::fee_distribution;
+use crate::insights::{FeeDataPoint, FeeDistribution, LedgerInfo};
+use crate::repository::FeeRepository;
+
+/// Shared state for the ledger routes.
+pub type LedgersState = Arc<FeeRepository>;
+
+/// What was charged in one ledger
+#[derive(Debug, Serialize)]
+pub struct LedgerFeeSummary {
+    pub count: usize,
+    /// In stroops; `None` when no fees are stored for the ledger.
+    pub min_fee: Option<u64>,
+    pub avg_fee: Option<f64>,
+    pub max_fee: Option<u64>,
+    pub percentiles: Option<FeeDistribution>,
+    /// The rest is `None` when the provider did not report the ledger.
+    pub closed_at: Option<DateTime<Utc>>,
+    pub base_fee: Option<u64>,
+    pub transaction_count: Option<u32>,
+    pub operation_count: Option<u32>,
+    pub max_tx_set_size: Option<u32>,
+    /// Share (0.0–1.0) of the ledger's operation capacity used.
+    pub capacity_utilization: Option<f64>,
+}
+
+impl LedgerFeeSummary {
+    fn new(fees: &[FeeDataPoint], ledger: Option<&LedgerInfo>) -> Self {
+        let amounts: Vec<u64> = fees.iter().map(|point| point.fee_amount).collect();
+        let total: u64 = amounts.iter().sum();
+        Self {
+            count: amounts.len(),
+            min_fee: amounts.iter().copied().min(),
+            avg_fee: (!amounts.is_empty()).then(|| total as f64 / amounts.len() as f64),
+            max_fee: amounts.iter().copied().max(),
+            percentiles: fee_distribution(&amounts),
+            closed_at: ledger.map(|l| l.closed_at),
+            base_fee: ledger.map(|l| l.base_fee),
+            transaction_count: ledger.map(|l| l.transaction_count),
+            operation_count: ledger.map(|l| l.operation_count),
+            max_tx_set_size: ledger.map(|l| l.max_tx_set_size),
+            capacity_utilization: ledger.and_then(LedgerInfo::capacity_utilization),
+        }
+    }
+}
+
+/// `GET /ledgers/:sequence/fees` response
+#[derive(Debug, Serialize)]
+pub struct LedgerFeesResponse {
+    pub sequence: u64,
+    pub summary: LedgerFeeSummary,
+    pub fees: Vec<FeeDataPoint>,
+}
+
+/// `GET /ledgers/:sequence/fees` — one ledger's fees and summary.
+pub async fn ledger_fees(
+    State(repo): State<LedgersState>,
+    Path(sequence): Path<u64>,
+) -> Result<Json<LedgerFeesResponse>, AppError> {
+    let ledger = repo.fetch_ledger(sequence).await?;
+    let fees = repo.fetch_ledger_fees(sequence).await?;
+    if ledger.is_none() && fees.is_empty() {
+        return Err(AppError::NotFound(format!(
+            "Nothing stored for ledger {}",
+            sequence
+        )));
+    }
+
+    Ok(Json(LedgerFeesResponse {
+        sequence,
+        summary: LedgerFeeSummary::new(&fees, ledger.as_ref()),
+        fees,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use crate::db::create_pool;
+
+    fn point(fee_amount: u64, ledger_sequence: u64) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp: Utc::now(),
+            transaction_hash: format!("tx_{}_{}", ledger_sequence, fee_amount),
+            ledger_sequence,
+            envelope: None,
+            soroban: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn summarises_one_ledger() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = Arc::new(FeeRepository::new(pool));
+        repo.insert_fee_points(&[point(100, 7), point(300, 7), point(200, 7), point(900, 8)])
+            .await
+            .unwrap();
+        repo.insert_ledgers(&[LedgerInfo {
+            sequence: 7,
+            closed_at: Utc::now(),
+            transaction_count: 3,
+            operation_count: 250,
+            max_tx_set_size: 1000,
+            base_fee: 100,
+        }])
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/ledgers/:sequence/fees", get(ledger_fees))
+            .with_state(repo);
+        let get_json = |uri: &str| {
+            let app = app.clone();
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                (status, serde_json::from_slice::<Value>(&body).unwrap())
+            }
+        };
+
+        let (status, json) = get_json("/ledgers/7/fees").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["fees"].as_array().unwrap().len(), 3);
+        let summary = &json["summary"];
+        assert_eq!(summary["count"], 3);
+        assert_eq!(summary["min_fee"], 100);
+        assert_eq!(summary["avg_fee"], 200.0);
+        assert_eq!(summary["max_fee"], 300);
+        assert_eq!(summary["capacity_utilization"], 0.25);
+
+        // Fees without a reported ledger are still summarised
+        let (status, json) = get_json("/ledgers/8/fees").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["summary"]["count"], 1);
+        assert!(json["summary"]["capacity_utilization"].is_null());
+
+        let (status, _) = get_json("/ledgers/9/fees").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
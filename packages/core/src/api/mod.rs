@@ -1,5 +1,31 @@
+pub mod accounts;
+pub mod admin;
 pub mod alerts;
+pub mod batch;
+pub mod budgets;
+pub mod caching;
+pub mod compare;
+pub mod congestion;
+pub mod cursor;
+pub mod events;
+pub mod export;
 pub mod fees;
+pub mod fields;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "grpc-server")]
+pub mod grpc;
 pub mod headers;
 pub mod health;
 pub mod insights;
+pub mod leaderboard;
+pub mod ledgers;
+pub mod networks;
+pub mod range;
+pub mod recommendation;
+pub mod rollups;
+pub mod snapshots;
+pub mod surges;
+pub mod timezone;
+pub mod v1;
+pub mod ws;
@@ -0,0 +1,116 @@
+//! Network discovery endpoint.
+//!
+//! Routes:
+//! - `GET /networks` — the networks this deployment tracks and where their
+//!   fee and insights routes are mounted
+//! - `GET /networks/compare` — headline insights for every tracked network
+//!   side by side, primary first
+
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use crate::config::StellarNetwork;
+use crate::insights::{compare_networks, CrossNetworkComparison, FeeInsightsEngine};
+
+/// Every tracked network's insights engine, primary first.
+pub type CompareNetworksState = Arc<Vec<Arc<RwLock<FeeInsightsEngine>>>>;
+
+/// One tracked network and the prefix its routes are served under.
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkInfo {
+    pub name: String,
+    pub path: String,
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworksResponse {
+    pub networks: Vec<NetworkInfo>,
+}
+
+impl NetworksResponse {
+    /// Describe `primary` followed by `additional`, in that order.
+    pub fn new(primary: StellarNetwork, additional: &[StellarNetwork]) -> Self {
+        let networks = std::iter::once(primary)
+            .chain(additional.iter().copied())
+            .map(|network| NetworkInfo {
+                name: network.as_str().to_string(),
+                path: network_path(network),
+                primary: network == primary,
+            })
+            .collect();
+        Self { networks }
+    }
+}
+
+/// Prefix under which a network's fee and insights routes are nested.
+pub fn network_path(network: StellarNetwork) -> String {
+    format!("/networks/{}", network.as_str())
+}
+
+/// `path` as served unprefixed on the primary network: without a version
+/// prefix or a `/networks/{name}` scope.
+pub fn route_path(path: &str) -> &str {
+    let path = match path.strip_prefix(super::v1::PREFIX) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+        _ => path,
+    };
+    match path.strip_prefix("/networks/") {
+        Some(rest) => match rest.find('/') {
+            Some(slash) => &rest[slash..],
+            None => path,
+        },
+        None => path,
+    }
+}
+
+/// `GET /networks`
+pub async fn list_networks(State(state): State<Arc<NetworksResponse>>) -> Json<NetworksResponse> {
+    Json((*state).clone())
+}
+
+/// `GET /networks/compare`
+pub async fn compare_tracked_networks(
+    State(engines): State<CompareNetworksState>,
+) -> Json<CrossNetworkComparison> {
+    let mut guards = Vec::with_capacity(engines.len());
+    for engine in engines.iter() {
+        guards.push(engine.read().await);
+    }
+    let engines: Vec<&FeeInsightsEngine> = guards.iter().map(|guard| &**guard).collect();
+    Json(compare_networks(&engines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_primary_first_with_route_prefixes() {
+        let response = NetworksResponse::new(
+            StellarNetwork::Mainnet,
+            &[StellarNetwork::Testnet, StellarNetwork::Futurenet],
+        );
+
+        let names: Vec<_> = response.networks.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["mainnet", "testnet", "futurenet"]);
+        assert!(response.networks[0].primary);
+        assert!(!response.networks[1].primary);
+        assert_eq!(response.networks[2].path, "/networks/futurenet");
+    }
+
+    #[test]
+    fn route_paths_drop_version_and_network_scope() {
+        assert_eq!(route_path("/v1/fees/history"), "/fees/history");
+        assert_eq!(
+            route_path("/networks/testnet/fees/current"),
+            "/fees/current"
+        );
+        assert_eq!(route_path("/v1/networks/testnet/insights"), "/insights");
+        assert_eq!(route_path("/networks/compare"), "/networks/compare");
+        assert_eq!(route_path("/v10/fees"), "/v10/fees");
+    }
+}
@@ -0,0 +1,230 @@
+//! On-demand insights over a stored time range.
+//!
+//! Routes:
+//! - `GET /insights/range?from=…&to=…&averaging=…&smoothing_factor=…` —
+//!   every stored fee in the range summarised as one window. `to` defaults
+//!   to now and `from` to an hour before `to`; `averaging` is `simple`
+//!   (default), `operation_weighted` or `exponential`, which also needs
+//!   `smoothing_factor`. Ranges span at most `MAX_RANGE_HOURS` and hold at
+//!   most `MAX_RANGE_POINTS` fees; larger ones are rejected.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::sync::RwLock;
+
+use crate::error::AppError;
+use crate::insights::{AveragingMethod, FeeInsightsEngine, InsightsError, RangeInsights};
+use crate::repository::FeeRepository;
+
+/// Longest range that may be summarised.
+const MAX_RANGE_HOURS: i64 = 24 * 7;
+
+/// Most stored fees a range may hold, all of which are read into memory.
+const MAX_RANGE_POINTS: usize = 100_000;
+
+/// Shared state for the range route
+#[derive(Clone)]
+pub struct RangeState {
+    pub insights_engine: Arc<RwLock<FeeInsightsEngine>>,
+    pub repository: Arc<FeeRepository>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RangeQuery {
+    /// RFC 3339 timestamps.
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub averaging: Option<String>,
+    pub smoothing_factor: Option<f64>,
+}
+
+/// `GET /insights/range` — insights for an arbitrary stored range.
+pub async fn range_insights(
+    State(state): State<Arc<RangeState>>,
+    Query(params): Query<RangeQuery>,
+) -> Result<Json<RangeInsights>, AppError> {
+    let to = params.to.unwrap_or_else(Utc::now);
+    let from = params.from.unwrap_or(to - chrono::Duration::hours(1));
+    let averaging = match params.averaging.as_deref().unwrap_or("simple") {
+        "simple" => AveragingMethod::Simple,
+        "operation_weighted" => AveragingMethod::OperationWeighted,
+        "exponential" => AveragingMethod::Exponential {
+            smoothing_factor: params.smoothing_factor.ok_or_else(|| {
+                AppError::BadRequest("Exponential averaging needs smoothing_factor".to_string())
+            })?,
+        },
+        other => {
+            return Err(AppError::BadRequest(format!(
+                "Unknown averaging '{}'",
+                other
+            )))
+        }
+    };
+
+    if to - from > chrono::Duration::hours(MAX_RANGE_HOURS) {
+        return Err(AppError::BadRequest(format!(
+            "Range spans more than {} hours",
+            MAX_RANGE_HOURS
+        )));
+    }
+
+    // The database is read without holding the engine
+    let config = state.insights_engine.read().await.get_config().clone();
+    FeeInsightsEngine::compute_range_insights(
+        &config,
+        &state.repository,
+        from,
+        to,
+        averaging,
+        MAX_RANGE_POINTS,
+    )
+    .await
+    .map(Json)
+    .map_err(|err| match err {
+        InsightsError::StorageError { .. } => AppError::Storage(err.to_string()),
+        _ => AppError::BadRequest(err.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::db::create_pool;
+    use crate::insights::{FeeDataPoint, InsightsConfig};
+
+    #[tokio::test]
+    async fn summarises_only_the_requested_range() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repository = Arc::new(FeeRepository::new(pool));
+        let now = Utc::now();
+        let minutes_ago = |m: i64| now - chrono::Duration::minutes(m);
+
+        // 100s three hours back, 300s within the last hour
+        let points: Vec<FeeDataPoint> = (0..20i64)
+            .map(|i| {
+                let (fee_amount, timestamp) = if i < 10 {
+                    (100, minutes_ago(180 - i))
+                } else {
+                    (300, minutes_ago(30 - i))
+                };
+                FeeDataPoint {
+                    fee_amount,
+                    timestamp,
+                    transaction_hash: format!("tx_{}", i),
+                    ledger_sequence: i as u64,
+                    envelope: None,
+                    soroban: None,
+                }
+            })
+            .collect();
+        repository.insert_fee_points(&points).await.unwrap();
+
+        let app = Router::new()
+            .route("/insights/range", get(range_insights))
+            .with_state(Arc::new(RangeState {
+                insights_engine: Arc::new(RwLock::new(FeeInsightsEngine::new(
+                    InsightsConfig::default(),
+                ))),
+                repository,
+            }));
+        let get_json = |uri: String| {
+            let app = app.clone();
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            async move {
+                let resp = app.oneshot(request).await.unwrap();
+                let status = resp.status();
+                let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&bytes).unwrap(),
+                )
+            }
+        };
+        let param = |t: DateTime<Utc>| t.to_rfc3339().replace('+', "%2B");
+
+        let (status, json) = get_json(format!(
+            "/insights/range?from={}&to={}",
+            param(minutes_ago(240)),
+            param(minutes_ago(120))
+        ))
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["summary"]["sample_count"], 10);
+        assert_eq!(json["summary"]["value"], 100.0);
+        assert_eq!(json["max_fee"], 100);
+
+        let (status, json) = get_json("/insights/range".to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["summary"]["sample_count"], 10);
+        assert_eq!(json["min_fee"], 300);
+
+        let (status, _) = get_json(format!(
+            "/insights/range?from={}&to={}",
+            param(now),
+            param(minutes_ago(60))
+        ))
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = get_json("/insights/range?averaging=exponential".to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = get_json(format!(
+            "/insights/range?from={}&to={}",
+            param(now - chrono::Duration::hours(MAX_RANGE_HOURS + 1)),
+            param(now)
+        ))
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn rejects_ranges_holding_too_many_fees() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repository = FeeRepository::new(pool);
+        let now = Utc::now();
+        let points: Vec<FeeDataPoint> = (0..6i64)
+            .map(|i| FeeDataPoint {
+                fee_amount: 100,
+                timestamp: now - chrono::Duration::minutes(10 - i),
+                transaction_hash: format!("tx_{}", i),
+                ledger_sequence: i as u64,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        repository.insert_fee_points(&points).await.unwrap();
+        let config = InsightsConfig::default();
+        let range = |max_points| {
+            FeeInsightsEngine::compute_range_insights(
+                &config,
+                &repository,
+                now - chrono::Duration::hours(1),
+                now,
+                AveragingMethod::Simple,
+                max_points,
+            )
+        };
+
+        assert_eq!(range(6).await.unwrap().summary.sample_count, 6);
+        assert!(matches!(
+            range(5).await,
+            Err(InsightsError::InvalidData { .. })
+        ));
+    }
+}
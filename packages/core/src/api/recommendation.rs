@@ -0,0 +1,271 @@
+//! A fee to set on a transaction, for wallets.
+//!
+//! Routes:
+//! - `GET /fees/recommendation?urgency=…&operations=…` — the fee to bid at
+//!   `economy`, `standard` (default) or `priority` urgency for a
+//!   transaction of `operations` (default 1), with the inputs it was
+//!   worked out from. Soroban invocations also pass their resources —
+//!   `instructions`, `read_entries`, `write_entries`, `disk_read_bytes`,
+//!   `write_bytes`, `transaction_size_bytes`, `contract_events_bytes` and
+//!   `rent_fee` — and get the resource fee added
+
+use axum::{
+    extract::{rejection::QueryRejection, Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::fees::FeesState;
+use crate::error::AppError;
+use crate::insights::{SorobanResourceFee, SorobanResources, TrendIndicator, Urgency};
+
+/// Most operations a Stellar transaction may hold.
+pub const MAX_OPERATIONS: u32 = 100;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RecommendationQuery {
+    pub urgency: Option<Urgency>,
+    pub operations: Option<u32>,
+    pub instructions: Option<u32>,
+    pub read_entries: Option<u32>,
+    pub write_entries: Option<u32>,
+    pub disk_read_bytes: Option<u32>,
+    pub write_bytes: Option<u32>,
+    pub transaction_size_bytes: Option<u32>,
+    pub contract_events_bytes: Option<u32>,
+    pub rent_fee: Option<u64>,
+}
+
+impl RecommendationQuery {
+    /// The Soroban resources given, if any were; the rest count as zero.
+    fn soroban_resources(&self) -> Option<SorobanResources> {
+        let given = [
+            self.instructions,
+            self.read_entries,
+            self.write_entries,
+            self.disk_read_bytes,
+            self.write_bytes,
+            self.transaction_size_bytes,
+            self.contract_events_bytes,
+        ]
+        .iter()
+        .any(Option::is_some)
+            || self.rent_fee.is_some();
+        given.then(|| SorobanResources {
+            instructions: self.instructions.unwrap_or(0),
+            read_entries: self.read_entries.unwrap_or(0),
+            write_entries: self.write_entries.unwrap_or(0),
+            disk_read_bytes: self.disk_read_bytes.unwrap_or(0),
+            write_bytes: self.write_bytes.unwrap_or(0),
+            transaction_size_bytes: self.transaction_size_bytes.unwrap_or(0),
+            contract_events_bytes: self.contract_events_bytes.unwrap_or(0),
+            rent_fee: self.rent_fee.unwrap_or(0),
+        })
+    }
+}
+
+/// `GET /fees/recommendation` response
+#[derive(Debug, Serialize)]
+pub struct RecommendationResponse {
+    pub urgency: Urgency,
+    /// Fee to set on the transaction, in stroops: the inclusion fee plus
+    /// any resource fee.
+    pub fee: u64,
+    pub inputs: RecommendationInputs,
+}
+
+/// What the recommended fee was worked out from
+#[derive(Debug, Serialize)]
+pub struct RecommendationInputs {
+    pub operations: u32,
+    /// Bid per operation, in stroops.
+    pub inclusion_fee_per_operation: u64,
+    /// Percentile of recent fees bid, e.g. `p75`; `None` when there were
+    /// no recent fees and the network minimum was bid.
+    pub percentile: Option<String>,
+    /// Window whose fee distribution was used.
+    pub time_window: Option<String>,
+    /// Congestion the percentile was chosen for.
+    pub congestion: TrendIndicator,
+    /// Set for Soroban invocations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resources: Option<SorobanResources>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_fee: Option<SorobanResourceFee>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// `GET /fees/recommendation` — suggested fee for one transaction.
+pub async fn fee_recommendation(
+    State(state): State<FeesState>,
+    query: Result<Query<RecommendationQuery>, QueryRejection>,
+) -> Result<Json<RecommendationResponse>, AppError> {
+    let Query(query) = query.map_err(|rejection| AppError::BadRequest(rejection.body_text()))?;
+    let urgency = query.urgency.unwrap_or(Urgency::Standard);
+    let resources = query.soroban_resources();
+    let operations = query.operations.unwrap_or(1);
+    if operations == 0 || operations > MAX_OPERATIONS {
+        return Err(AppError::BadRequest(format!(
+            "operations must be between 1 and {}",
+            MAX_OPERATIONS
+        )));
+    }
+    if resources.is_some() && operations != 1 {
+        return Err(AppError::BadRequest(
+            "Soroban transactions hold exactly one operation".to_string(),
+        ));
+    }
+
+    let engine = state
+        .insights_engine
+        .as_ref()
+        .ok_or_else(|| AppError::Config("Insights engine missing from fees state".to_string()))?;
+    let engine = engine.read().await;
+    let (recommendation, resource_fee) = match &resources {
+        Some(resources) => {
+            let estimate = engine.estimate_soroban_fee(resources);
+            (estimate.inclusion_fee, Some(estimate.resource_fee))
+        }
+        None => (engine.get_fee_recommendation(), None),
+    };
+    drop(engine);
+
+    let per_operation = urgency.pick(&recommendation);
+    let inclusion_fee = per_operation.saturating_mul(u64::from(operations));
+    let fee = inclusion_fee.saturating_add(resource_fee.as_ref().map_or(0, |fee| fee.total));
+    let percentile = recommendation
+        .time_window
+        .as_ref()
+        .map(|_| format!("p{}", urgency.percentile(&recommendation.congestion)));
+
+    Ok(Json(RecommendationResponse {
+        urgency,
+        fee,
+        inputs: RecommendationInputs {
+            operations,
+            inclusion_fee_per_operation: per_operation,
+            percentile,
+            time_window: recommendation.time_window,
+            congestion: recommendation.congestion,
+            resources,
+            resource_fee,
+            generated_at: recommendation.generated_at,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration as StdDuration;
+
+    use axum::{body::Body, http::Request, http::StatusCode, routing::get, Router};
+    use http_body_util::BodyExt;
+    use serde_json::Value;
+    use tokio::sync::{Mutex, RwLock};
+    use tower::ServiceExt;
+
+    use crate::api::fees::FeesApiState;
+    use crate::cache::ResponseCache;
+    use crate::insights::{FeeDataPoint, FeeInsightsEngine, InsightsConfig};
+    use crate::store::FeeHistoryStore;
+
+    async fn app() -> Router {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        let points: Vec<FeeDataPoint> = (1..=20)
+            .map(|i| FeeDataPoint {
+                fee_amount: 100 * i,
+                timestamp: Utc::now(),
+                transaction_hash: format!("tx_{}", i),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        engine.process_fee_data(&points).await.unwrap();
+        Router::new()
+            .route("/fees/recommendation", get(fee_recommendation))
+            .with_state(Arc::new(FeesApiState {
+                fee_stats_provider: None,
+                fee_cache: Arc::new(Mutex::new(ResponseCache::new(StdDuration::from_secs(5)))),
+                fee_store: Arc::new(RwLock::new(FeeHistoryStore::new(10))),
+                insights_engine: Some(Arc::new(RwLock::new(engine))),
+                repository: None,
+            }))
+    }
+
+    async fn get_json(app: &Router, uri: &str) -> (StatusCode, Value) {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn recommends_a_fee_and_explains_it() {
+        let app = app().await;
+
+        let (status, standard) = get_json(&app, "/fees/recommendation").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(standard["urgency"], "standard");
+        assert_eq!(standard["inputs"]["operations"], 1);
+        assert_eq!(standard["inputs"]["percentile"], "p50");
+        assert_eq!(standard["inputs"]["time_window"], "short_term");
+        assert_eq!(
+            standard["fee"],
+            standard["inputs"]["inclusion_fee_per_operation"]
+        );
+        assert!(standard["inputs"].get("resource_fee").is_none());
+
+        let (_, priority) =
+            get_json(&app, "/fees/recommendation?urgency=priority&operations=3").await;
+        let per_operation = priority["inputs"]["inclusion_fee_per_operation"]
+            .as_u64()
+            .unwrap();
+        assert!(per_operation > standard["fee"].as_u64().unwrap());
+        assert_eq!(priority["fee"], per_operation * 3);
+    }
+
+    #[tokio::test]
+    async fn adds_the_resource_fee_for_soroban_invocations() {
+        let app = app().await;
+        let (status, body) = get_json(
+            &app,
+            "/fees/recommendation?urgency=economy&instructions=5000000&read_entries=2\
+             &write_entries=1&transaction_size_bytes=600",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let resource_fee = body["inputs"]["resource_fee"]["total"].as_u64().unwrap();
+        assert!(resource_fee > 0);
+        assert_eq!(
+            body["fee"].as_u64().unwrap(),
+            body["inputs"]["inclusion_fee_per_operation"]
+                .as_u64()
+                .unwrap()
+                + resource_fee
+        );
+        assert_eq!(body["inputs"]["resources"]["instructions"], 5_000_000);
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_inputs() {
+        let app = app().await;
+        for uri in [
+            "/fees/recommendation?urgency=whenever",
+            "/fees/recommendation?operations=0",
+            "/fees/recommendation?operations=101",
+            "/fees/recommendation?operations=2&instructions=1000",
+        ] {
+            let (status, body) = get_json(&app, uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body["code"], "invalid_request", "{}", uri);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! Downsampled fee history.
+//!
+//! Routes:
+//! - `GET /fees/rollups?resolution=1h&hours=168` — per-bucket fee summaries
+//!   at `1m`, `5m`, `1h` or `1d` resolution, oldest first
+//! - `GET /fees/heatmap?resolution=1h&hours=168&fee_buckets=20` — the same
+//!   rollups as a time × fee bucket grid of estimated counts
+//! - `GET /fees/stats?bucket=5m&start=…&end=…` — min, average, max and
+//!   percentiles per `bucket` (any whole number of `m`, `h` or `d`) between
+//!   `start` and `end`, merged from the coarsest rollups that fit, ready to
+//!   chart
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::repository::FeeRepository;
+use crate::rollup::{
+    bucket_stats, parse_bucket, source_resolution, stats_bucket_start, FeeHeatmap, FeeRollup,
+    FeeStatsBucket, Resolution,
+};
+
+/// Shared state for the rollup routes.
+pub type RollupsState = Arc<FeeRepository>;
+
+/// Furthest back a rollup query may look.
+const MAX_HOURS: i64 = 24 * 365;
+
+/// Most fee buckets a heatmap may have.
+const MAX_FEE_BUCKETS: usize = 100;
+
+/// Most buckets one stats query may span.
+const MAX_STATS_BUCKETS: i64 = 2000;
+
+#[derive(Debug, Deserialize)]
+pub struct RollupQuery {
+    /// `1m`, `5m`, `1h` or `1d`; defaults to `1h`.
+    pub resolution: Option<String>,
+    /// Hours to look back; defaults to 24 and is clamped to 1–8760.
+    pub hours: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HeatmapQuery {
+    /// `1m`, `5m`, `1h` or `1d`; defaults to `1h`.
+    pub resolution: Option<String>,
+    /// Hours to look back; defaults to 24 and is clamped to 1–8760.
+    pub hours: Option<i64>,
+    /// Fee buckets; defaults to 20 and is clamped to 1–100.
+    pub fee_buckets: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    /// Bucket width such as `5m`, `1h` or `7d`; defaults to `1h`.
+    pub bucket: Option<String>,
+    /// Defaults to 24 hours before `end`.
+    pub start: Option<DateTime<Utc>>,
+    /// Defaults to now.
+    pub end: Option<DateTime<Utc>>,
+}
+
+/// `GET /fees/stats` response
+#[derive(Debug, Serialize)]
+pub struct FeeStatsResponse {
+    pub bucket: String,
+    /// Rollups the buckets were merged from.
+    pub resolution: Resolution,
+    /// `start` rounded down to a bucket boundary.
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Oldest first; buckets without fees are left out.
+    pub buckets: Vec<FeeStatsBucket>,
+}
+
+/// `GET /fees/rollups` — rollups at `resolution` from the last `hours`.
+pub async fn list_rollups(
+    State(repo): State<RollupsState>,
+    Query(params): Query<RollupQuery>,
+) -> Result<Json<Vec<FeeRollup>>, AppError> {
+    let (_, rollups) = fetch_rollups(&repo, &params).await?;
+    Ok(Json(rollups))
+}
+
+/// `GET /fees/heatmap` — rollups from the last `hours` spread over
+/// `fee_buckets` logarithmic fee buckets.
+pub async fn fee_heatmap(
+    State(repo): State<RollupsState>,
+    Query(params): Query<HeatmapQuery>,
+) -> Result<Json<FeeHeatmap>, AppError> {
+    let range = RollupQuery {
+        resolution: params.resolution,
+        hours: params.hours,
+    };
+    let (resolution, rollups) = fetch_rollups(&repo, &range).await?;
+    let fee_buckets = params.fee_buckets.unwrap_or(20).clamp(1, MAX_FEE_BUCKETS);
+    Ok(Json(FeeHeatmap::from_rollups(
+        resolution,
+        &rollups,
+        fee_buckets,
+    )))
+}
+
+/// `GET /fees/stats` — fee statistics per `bucket` between `start` and
+/// `end`.
+pub async fn fee_stats(
+    State(repo): State<RollupsState>,
+    Query(params): Query<StatsQuery>,
+) -> Result<Json<FeeStatsResponse>, AppError> {
+    let bad_request = AppError::BadRequest;
+    let label = params.bucket.unwrap_or_else(|| "1h".to_string());
+    let bucket = parse_bucket(&label).ok_or_else(|| {
+        bad_request(format!(
+            "Invalid bucket: {} (use a whole number of m, h or d, e.g. 5m)",
+            label
+        ))
+    })?;
+    let resolution = source_resolution(bucket)
+        .ok_or_else(|| bad_request(format!("Invalid bucket: {}", label)))?;
+    let end = params.end.unwrap_or_else(Utc::now);
+    let start = stats_bucket_start(bucket, params.start.unwrap_or(end - Duration::hours(24)));
+    if start >= end {
+        return Err(bad_request("start must be before end".to_string()));
+    }
+    let span = (end - start).num_seconds();
+    let buckets = span / bucket.num_seconds() + i64::from(span % bucket.num_seconds() != 0);
+    if buckets > MAX_STATS_BUCKETS {
+        return Err(bad_request(format!(
+            "Range spans {} buckets; at most {} are allowed",
+            buckets, MAX_STATS_BUCKETS
+        )));
+    }
+
+    let rollups = repo.fetch_rollups_between(resolution, start, end).await?;
+
+    Ok(Json(FeeStatsResponse {
+        bucket: label,
+        resolution,
+        start,
+        end,
+        buckets: bucket_stats(&rollups, bucket),
+    }))
+}
+
+async fn fetch_rollups(
+    repo: &FeeRepository,
+    params: &RollupQuery,
+) -> Result<(Resolution, Vec<FeeRollup>), AppError> {
+    let resolution = match params.resolution.as_deref() {
+        None => Resolution::OneHour,
+        Some(value) => Resolution::parse(value).ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "Unsupported resolution: {} (use 1m, 5m, 1h or 1d)",
+                value
+            ))
+        })?,
+    };
+    let hours = params.hours.unwrap_or(24).clamp(1, MAX_HOURS);
+    let since = Utc::now() - chrono::Duration::hours(hours);
+
+    let rollups = repo.fetch_rollups_since(resolution, since).await?;
+
+    Ok((resolution, rollups))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::db::create_pool;
+    use crate::insights::FeeDataPoint;
+    use crate::rollup::roll_up_closed_buckets;
+
+    #[tokio::test]
+    async fn lists_rollups_of_closed_buckets() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = Arc::new(FeeRepository::new(pool));
+        let now = Utc::now();
+        let points: Vec<FeeDataPoint> = [(180, 100), (179, 300), (120, 500), (0, 900)]
+            .into_iter()
+            .map(|(minutes_ago, fee_amount)| FeeDataPoint {
+                fee_amount,
+                timestamp: now - chrono::Duration::minutes(minutes_ago),
+                transaction_hash: format!("tx_{}", minutes_ago),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        repo.insert_fee_points(&points).await.unwrap();
+        roll_up_closed_buckets(&repo, now).await.unwrap();
+
+        let app = Router::new()
+            .route("/fees/rollups", get(list_rollups))
+            .route("/fees/heatmap", get(fee_heatmap))
+            .route("/fees/stats", get(fee_stats))
+            .with_state(repo);
+        let get_json = |uri: &str| {
+            let app = app.clone();
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            async move {
+                let resp = app.oneshot(request).await.unwrap();
+                let status = resp.status();
+                let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&bytes).unwrap(),
+                )
+            }
+        };
+
+        // The point in the still-open minute is not rolled up yet
+        let (status, json) = get_json("/fees/rollups?resolution=1m").await;
+        assert_eq!(status, StatusCode::OK);
+        let minutes = json.as_array().unwrap();
+        assert_eq!(minutes.len(), 3);
+        assert_eq!(minutes[0]["resolution"], "1m");
+        assert_eq!(minutes[0]["max_fee"], 100);
+
+        let (_, json) = get_json("/fees/rollups?resolution=1d&hours=48").await;
+        let days = json.as_array().unwrap();
+        let sampled: u64 = days
+            .iter()
+            .map(|day| day["sample_count"].as_u64().unwrap())
+            .sum();
+        assert!(sampled <= 3);
+
+        let (status, _) = get_json("/fees/rollups?resolution=2h").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, json) = get_json("/fees/heatmap?resolution=1m&fee_buckets=2").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["time_buckets"].as_array().unwrap().len(), 3);
+        assert_eq!(json["fee_edges"], serde_json::json!([100, 224, 501]));
+        assert_eq!(json["counts"][0], serde_json::json!([1.0, 0.0]));
+        assert_eq!(json["counts"][2], serde_json::json!([0.0, 1.0]));
+
+        let (status, json) = get_json("/fees/stats?bucket=1m").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["resolution"], "1m");
+        let buckets = json["buckets"].as_array().unwrap();
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(buckets[0]["count"], 1);
+        assert_eq!(buckets[0]["min"], 100);
+        assert_eq!(buckets[0]["p50"], 100);
+
+        // Wider buckets are merged from the coarsest rollups that tile them
+        let (_, json) = get_json("/fees/stats?bucket=2h").await;
+        assert_eq!(json["resolution"], "1h");
+        let total: u64 = json["buckets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| bucket["count"].as_u64().unwrap())
+            .sum();
+        assert!(total <= 3);
+
+        for uri in [
+            "/fees/stats?bucket=5s",
+            "/fees/stats?bucket=0m",
+            "/fees/stats?bucket=1m&start=2024-01-02T00:00:00Z&end=2024-01-01T00:00:00Z",
+            "/fees/stats?bucket=1m&start=2024-01-01T00:00:00Z&end=2024-01-31T00:00:00Z",
+        ] {
+            let (status, _) = get_json(uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+}
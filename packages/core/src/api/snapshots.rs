@@ -0,0 +1,223 @@
+//! Insight snapshot history.
+//!
+//! Routes:
+//! - `GET /insights/snapshots?hours=24` — full insights captured on the
+//!   engine's snapshot cadence, oldest first; 304 when `If-None-Match`
+//!   holds the current ETag, which is checked before any snapshot is read
+//! - `GET /insights/congestion-sla` — share of the last 24h, 7d and 30d
+//!   spent in each congestion state, from the state at every snapshot
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::Response,
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use super::headers::{
+    cache_control, compute_etag, if_none_match_matches, json_with_etag, last_modified, not_modified,
+};
+use crate::error::AppError;
+use crate::repository::FeeRepository;
+use crate::sla::{congestion_sla, CongestionSla};
+
+/// Shared state for the snapshot routes.
+pub type SnapshotsState = Arc<FeeRepository>;
+
+/// Furthest back a snapshot query may look.
+const MAX_HOURS: i64 = 24 * 30;
+const SNAPSHOTS_MAX_AGE: u32 = 30;
+const SNAPSHOTS_SWR: u32 = 60;
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotQuery {
+    /// Hours to look back; defaults to 24 and is clamped to 1–720.
+    pub hours: Option<i64>,
+}
+
+/// `GET /insights/snapshots` — insight snapshots from the last `hours`.
+pub async fn list_snapshots(
+    State(repo): State<SnapshotsState>,
+    Query(params): Query<SnapshotQuery>,
+    request_headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let hours = params.hours.unwrap_or(24).clamp(1, MAX_HOURS);
+    let since = Utc::now() - chrono::Duration::hours(hours);
+
+    let span = repo.insight_snapshot_span_since(since).await?;
+    let etag = compute_etag(
+        format!(
+            "snapshots:{}:{:?}:{:?}",
+            span.count, span.first_id, span.last_id
+        )
+        .as_bytes(),
+    );
+    let cache_control = cache_control(SNAPSHOTS_MAX_AGE, SNAPSHOTS_SWR);
+    let last_modified_value = span.latest.map(last_modified);
+    if if_none_match_matches(&request_headers, &etag) {
+        return Ok(not_modified(&etag, cache_control, last_modified_value));
+    }
+
+    let snapshots = repo.fetch_insight_snapshots_since(since).await?;
+    let body = serde_json::to_vec(&snapshots)
+        .map_err(|e| AppError::Unknown(format!("Failed to serialize snapshots: {}", e)))?;
+
+    Ok(json_with_etag(
+        body,
+        &etag,
+        cache_control,
+        last_modified_value,
+    ))
+}
+
+/// `GET /insights/congestion-sla` — time in each congestion state over
+/// rolling 24h, 7d and 30d periods.
+pub async fn get_congestion_sla(
+    State(repo): State<SnapshotsState>,
+) -> Result<Json<Vec<CongestionSla>>, AppError> {
+    Ok(Json(congestion_sla(&repo, Utc::now()).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::db::create_pool;
+    use crate::insights::{CongestionState, FeeInsightsEngine, InsightsConfig};
+
+    #[tokio::test]
+    async fn lists_recent_snapshots_oldest_first() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = Arc::new(FeeRepository::new(pool));
+        let engine = FeeInsightsEngine::new(InsightsConfig::default());
+        for hours_ago in [3, 1] {
+            let mut insights = engine.get_current_insights();
+            insights.last_updated = Utc::now() - chrono::Duration::hours(hours_ago);
+            repo.insert_insight_snapshot(&insights).await.unwrap();
+        }
+
+        let app = Router::new()
+            .route("/insights/snapshots", get(list_snapshots))
+            .with_state(repo);
+        let request = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let resp = app
+            .clone()
+            .oneshot(request("/insights/snapshots"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let snapshots = json.as_array().unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert!(snapshots[0]["last_updated"].as_str() < snapshots[1]["last_updated"].as_str());
+        assert!(snapshots[0]["rolling_averages"]["short_term"].is_object());
+
+        let resp = app
+            .oneshot(request("/insights/snapshots?hours=2"))
+            .await
+            .unwrap();
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn revalidates_snapshots_with_etag() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = Arc::new(FeeRepository::new(pool));
+        let engine = FeeInsightsEngine::new(InsightsConfig::default());
+        let insights = engine.get_current_insights();
+        repo.insert_insight_snapshot(&insights).await.unwrap();
+
+        let app = Router::new()
+            .route("/insights/snapshots", get(list_snapshots))
+            .with_state(repo.clone());
+        let request = |etag: Option<&str>| {
+            let mut request = Request::builder().uri("/insights/snapshots");
+            if let Some(etag) = etag {
+                request = request.header("if-none-match", etag);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let resp = app.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().contains_key("last-modified"));
+        let etag = resp.headers()["etag"].to_str().unwrap().to_string();
+
+        let resp = app.clone().oneshot(request(Some(&etag))).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert!(resp
+            .into_body()
+            .collect()
+            .await
+            .unwrap()
+            .to_bytes()
+            .is_empty());
+
+        // A new snapshot changes the tag
+        repo.insert_insight_snapshot(&insights).await.unwrap();
+        let resp = app.oneshot(request(Some(&etag))).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_ne!(resp.headers()["etag"], etag.as_str());
+    }
+
+    #[tokio::test]
+    async fn reports_time_in_each_congestion_state() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = Arc::new(FeeRepository::new(pool));
+        let now = Utc::now();
+        // Congested for 10 of the last 40 minutes; normal two days ago
+        let samples = [
+            (60 * 48, CongestionState::Normal),
+            (40, CongestionState::Normal),
+            (30, CongestionState::Congested),
+            (20, CongestionState::Normal),
+            (5, CongestionState::Normal),
+        ];
+        for (minutes_ago, state) in samples {
+            let captured_at = now - chrono::Duration::minutes(minutes_ago);
+            repo.insert_congestion_sample(captured_at, state)
+                .await
+                .unwrap();
+        }
+
+        let app = Router::new()
+            .route("/insights/congestion-sla", get(get_congestion_sla))
+            .with_state(repo);
+        let request = Request::builder()
+            .uri("/insights/congestion-sla")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(request).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+        let day = &json[0];
+        assert_eq!(day["period"], "24h");
+        let observed = day["observed_seconds"].as_i64().unwrap();
+        assert!((40 * 60 - 5..=40 * 60).contains(&observed));
+        let congested = day["congested_share"].as_f64().unwrap();
+        assert!((congested - 600.0 / observed as f64).abs() < 1e-9);
+
+        let week = &json[1];
+        assert_eq!(week["period"], "7d");
+        assert!(week["observed_seconds"].as_i64().unwrap() > observed);
+        assert_eq!(json[2]["period"], "30d");
+    }
+}
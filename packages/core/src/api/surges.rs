@@ -0,0 +1,98 @@
+//! Surge episode history.
+//!
+//! Routes:
+//! - `GET /insights/surges?days=30` — completed surge episodes of the
+//!   network, newest first
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::error::AppError;
+use crate::insights::SurgeEpisode;
+use crate::repository::FeeRepository;
+
+/// Shared state for the surge routes.
+pub type SurgesState = Arc<FeeRepository>;
+
+/// Furthest back a surge query may look.
+const MAX_DAYS: i64 = 365;
+
+#[derive(Debug, Deserialize)]
+pub struct SurgeQuery {
+    /// Days to look back; defaults to 30 and is clamped to 1–365.
+    pub days: Option<i64>,
+}
+
+/// `GET /insights/surges` — surge episodes that started in the last `days`.
+pub async fn list_surges(
+    State(repo): State<SurgesState>,
+    Query(params): Query<SurgeQuery>,
+) -> Result<Json<Vec<SurgeEpisode>>, AppError> {
+    let days = params.days.unwrap_or(30).clamp(1, MAX_DAYS);
+    let since = Utc::now() - chrono::Duration::days(days);
+
+    Ok(Json(repo.fetch_surge_episodes_since(since).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::db::create_pool;
+
+    #[tokio::test]
+    async fn lists_recent_surges() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = Arc::new(FeeRepository::new(pool));
+        let start_time = Utc::now() - chrono::Duration::days(3);
+        repo.insert_surge_episodes(&[SurgeEpisode {
+            start_time,
+            end_time: start_time + chrono::Duration::minutes(5),
+            duration: chrono::Duration::minutes(5),
+            peak_fee: 2_500,
+            baseline_fee: 100.0,
+            sample_count: 40,
+        }])
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/insights/surges", get(list_surges))
+            .with_state(repo);
+        let request = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let resp = app
+            .clone()
+            .oneshot(request("/insights/surges"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["peak_fee"], 2_500);
+        assert_eq!(json[0]["sample_count"], 40);
+
+        let resp = app
+            .oneshot(request("/insights/surges?days=1"))
+            .await
+            .unwrap();
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert!(json.as_array().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! Timestamps in the reader's zone: `?tz=` on JSON responses.
+//!
+//! `?tz=Europe/Berlin`, or an `Accept-Timezone: Europe/Berlin` header,
+//! renders every RFC 3339 timestamp of a response in that IANA zone, e.g.
+//! `2024-03-01T13:00:00+01:00` instead of `2024-03-01T12:00:00Z`. The
+//! query parameter wins over the header. Storage, HTTP date headers and
+//! query parameters stay in UTC; only the rendering changes.
+//!
+//! Like sparse fieldsets, this works on the serialized JSON: any JSON
+//! route gets it by adding [`render_timezone`] as a layer, inside any
+//! compression layer.
+
+use axum::{
+    extract::{Query, Request},
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, SecondsFormat};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::fields::rewrite_json;
+use crate::error::AppError;
+
+/// Request header naming the zone when `tz` is not given.
+pub const ACCEPT_TIMEZONE: HeaderName = HeaderName::from_static("accept-timezone");
+
+#[derive(Debug, Deserialize)]
+struct TimezoneQuery {
+    tz: Option<String>,
+}
+
+/// The zone a request asked for: `tz`, else `Accept-Timezone`.
+fn requested_zone(request: &Request) -> Option<String> {
+    Query::<TimezoneQuery>::try_from_uri(request.uri())
+        .ok()
+        .and_then(|Query(query)| query.tz)
+        .or_else(|| {
+            request
+                .headers()
+                .get(&ACCEPT_TIMEZONE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        })
+}
+
+/// `value` with every RFC 3339 timestamp in it rendered in `zone`.
+pub fn localize(value: Value, zone: Tz) -> Value {
+    match value {
+        Value::String(text) => match DateTime::parse_from_rfc3339(&text) {
+            Ok(at) => Value::String(
+                at.with_timezone(&zone)
+                    .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            ),
+            Err(_) => Value::String(text),
+        },
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(|item| localize(item, zone)).collect())
+        }
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(name, field)| (name, localize(field, zone)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Render successful JSON responses' timestamps in the request's zone, if
+/// it names one. Responses vary on `Accept-Timezone` either way.
+pub async fn render_timezone(request: Request, next: Next) -> Response {
+    let Some(name) = requested_zone(&request) else {
+        return vary(next.run(request).await);
+    };
+    let zone = match name.trim().parse::<Tz>() {
+        Ok(zone) => zone,
+        Err(_) => {
+            return vary(
+                AppError::BadRequest(format!(
+                    "Unknown time zone '{}' (use an IANA name, e.g. Europe/Berlin)",
+                    name
+                ))
+                .into_response(),
+            )
+        }
+    };
+    let request_headers = request.headers().clone();
+
+    let response = next.run(request).await;
+    let response = if zone == Tz::UTC {
+        response
+    } else {
+        rewrite_json(&request_headers, response, |value| localize(value, zone)).await
+    };
+    vary(response)
+}
+
+fn vary(mut response: Response) -> Response {
+    response
+        .headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept-timezone"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get, Json, Router};
+    use http_body_util::BodyExt;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    fn sample() -> Value {
+        json!({
+            "last_updated": "2024-03-01T12:00:00Z",
+            "windows": [{ "name": "short_term", "start": "2024-07-01T12:00:00.250Z" }],
+            "transaction_hash": "abc123",
+            "bucket": "2024-03-01",
+            "count": 3,
+        })
+    }
+
+    #[test]
+    fn renders_timestamps_in_the_zone_and_leaves_the_rest() {
+        let localized = localize(sample(), "Europe/Berlin".parse().unwrap());
+        assert_eq!(localized["last_updated"], "2024-03-01T13:00:00+01:00");
+        // Daylight saving time applies per timestamp
+        assert_eq!(
+            localized["windows"][0]["start"],
+            "2024-07-01T14:00:00.250+02:00"
+        );
+        assert_eq!(localized["transaction_hash"], "abc123");
+        assert_eq!(localized["bucket"], "2024-03-01");
+        assert_eq!(localized["count"], 3);
+    }
+
+    async fn fetch(app: &Router, uri: &str, zone_header: Option<&str>) -> (StatusCode, Value) {
+        let mut request = Request::builder().uri(uri);
+        if let Some(zone) = zone_header {
+            request = request.header(&ACCEPT_TIMEZONE, zone);
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::VARY], "accept-timezone");
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn layer_takes_the_zone_from_the_query_or_header() {
+        let app = Router::new()
+            .route("/insights", get(|| async { Json(sample()) }))
+            .layer(axum::middleware::from_fn(render_timezone));
+
+        let (_, json) = fetch(&app, "/insights", None).await;
+        assert_eq!(json["last_updated"], "2024-03-01T12:00:00Z");
+
+        let (_, json) = fetch(&app, "/insights?tz=America/New_York", None).await;
+        assert_eq!(json["last_updated"], "2024-03-01T07:00:00-05:00");
+
+        let (_, json) = fetch(&app, "/insights", Some("Asia/Kolkata")).await;
+        assert_eq!(json["last_updated"], "2024-03-01T17:30:00+05:30");
+
+        let (_, json) = fetch(&app, "/insights?tz=UTC", Some("Asia/Kolkata")).await;
+        assert_eq!(json["last_updated"], "2024-03-01T12:00:00Z");
+
+        let (status, json) = fetch(&app, "/insights?tz=Mars/Olympus", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["code"], "invalid_request");
+        assert!(json["detail"].as_str().unwrap().contains("Mars/Olympus"));
+    }
+}
@@ -0,0 +1,379 @@
+//! Version 1 of the public API.
+//!
+//! Every business route is served under [`PREFIX`] as well as unprefixed.
+//! Most share their handler with the unprefixed route; the routes below
+//! answer with response types of their own, converted from the engine's
+//! types field by field so that changes to those do not reach v1 clients:
+//!
+//! Routes:
+//! - `GET /v1/insights` — [`InsightsResponse`], a summary of the current
+//!   insights
+//!
+//! A later version adds a module like this one and is nested under its own
+//! prefix next to `/v1`.
+
+use axum::{extract::State, http::HeaderMap, response::Response, routing::get, Router};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::fields::select_fields;
+use super::headers::{
+    cache_control, compute_etag, if_none_match_matches, json_with_etag, last_modified, not_modified,
+};
+use super::insights::{insights_etag, InsightsState, INSIGHTS_MAX_AGE, INSIGHTS_SWR};
+use super::timezone::render_timezone;
+use crate::error::AppError;
+use crate::insights::{
+    AverageResult, CongestionState, CurrentInsights, FeeDistribution, TrendDirection,
+    TrendIndicator,
+};
+
+/// Path prefix of version 1.
+pub const PREFIX: &str = "/v1";
+
+/// One network's v1 routes; requests without a v1 handler fall through to
+/// `unversioned`, the network's unprefixed routes.
+pub fn network_routes(insights_engine: InsightsState, unversioned: Router) -> Router {
+    Router::new()
+        .route("/insights", get(current_insights))
+        .route_layer(axum::middleware::from_fn(select_fields))
+        .route_layer(axum::middleware::from_fn(render_timezone))
+        .with_state(insights_engine)
+        .fallback_service(unversioned)
+}
+
+/// `GET /v1/insights` response
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InsightsResponse {
+    /// Rolling windows from shortest to longest.
+    pub windows: Vec<WindowSummary>,
+    /// Cheapest and dearest fee in the current extremes period.
+    pub extremes: FeeRange,
+    pub congestion: CongestionSummary,
+    /// `None` before any classic transaction has been seen.
+    pub surge_pricing: Option<SurgeSummary>,
+    /// `true` when the latest batch deviated from recent history.
+    pub anomaly_detected: bool,
+    /// Share (0.0–1.0) of expected data that was received.
+    pub data_completeness: f64,
+    pub last_updated: DateTime<Utc>,
+}
+
+/// Average and spread of one rolling window, in stroops
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WindowSummary {
+    /// `short_term`, `medium_term` or `long_term`.
+    pub name: &'static str,
+    pub duration_seconds: i64,
+    pub average_fee: f64,
+    pub sample_count: usize,
+    /// `true` while the window holds fewer samples than it needs.
+    pub is_partial: bool,
+    pub percentiles: Option<Percentiles>,
+    /// `None` with too few samples.
+    pub trend: Option<Trend>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Percentiles {
+    pub p10: u64,
+    pub p25: u64,
+    pub p50: u64,
+    pub p75: u64,
+    pub p90: u64,
+    pub p95: u64,
+    pub p99: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    Rising,
+    Falling,
+    Stable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeeRange {
+    pub min: u64,
+    pub max: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CongestionSummary {
+    pub level: CongestionLevel,
+    pub trend: CongestionTrend,
+    /// Congestion pressure (0.0–1.0); `None` when ledgers are not reported.
+    pub score: Option<f64>,
+    /// Share (0.0–1.0) of ledger capacity used; `None` when ledgers are not
+    /// reported.
+    pub capacity_utilization: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CongestionLevel {
+    Normal,
+    Elevated,
+    Congested,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CongestionTrend {
+    Normal,
+    Rising,
+    Congested,
+    Declining,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SurgeSummary {
+    pub active: bool,
+    /// Effective fee per operation over the base fee.
+    pub multiplier: f64,
+    pub base_fee: u64,
+}
+
+impl From<&CurrentInsights> for InsightsResponse {
+    fn from(insights: &CurrentInsights) -> Self {
+        let averages = &insights.rolling_averages;
+        let congestion = &insights.congestion_trends;
+        Self {
+            windows: vec![
+                WindowSummary::new("short_term", &averages.short_term),
+                WindowSummary::new("medium_term", &averages.medium_term),
+                WindowSummary::new("long_term", &averages.long_term),
+            ],
+            extremes: FeeRange {
+                min: insights.extremes.current_min.value,
+                max: insights.extremes.current_max.value,
+            },
+            congestion: CongestionSummary {
+                level: match congestion.congestion_state {
+                    CongestionState::Normal => CongestionLevel::Normal,
+                    CongestionState::Elevated => CongestionLevel::Elevated,
+                    CongestionState::Congested => CongestionLevel::Congested,
+                },
+                trend: match congestion.current_trend {
+                    TrendIndicator::Normal => CongestionTrend::Normal,
+                    TrendIndicator::Rising => CongestionTrend::Rising,
+                    TrendIndicator::Congested => CongestionTrend::Congested,
+                    TrendIndicator::Declining => CongestionTrend::Declining,
+                },
+                score: congestion.congestion_score,
+                capacity_utilization: congestion.capacity_utilization,
+            },
+            surge_pricing: insights.surge_pricing.as_ref().map(|surge| SurgeSummary {
+                active: surge.active,
+                multiplier: surge.multiplier,
+                base_fee: surge.base_fee,
+            }),
+            anomaly_detected: insights.anomalies.is_anomaly,
+            data_completeness: insights.data_quality.completeness,
+            last_updated: insights.last_updated,
+        }
+    }
+}
+
+impl WindowSummary {
+    fn new(name: &'static str, average: &AverageResult) -> Self {
+        Self {
+            name,
+            duration_seconds: average.time_window.duration.num_seconds(),
+            average_fee: average.value,
+            sample_count: average.sample_count,
+            is_partial: average.is_partial,
+            percentiles: average.percentiles.as_ref().map(Percentiles::from),
+            trend: average.trend.map(|trend| match trend.direction {
+                TrendDirection::Rising => Trend::Rising,
+                TrendDirection::Falling => Trend::Falling,
+                TrendDirection::Stable => Trend::Stable,
+            }),
+        }
+    }
+}
+
+impl From<&FeeDistribution> for Percentiles {
+    fn from(distribution: &FeeDistribution) -> Self {
+        Self {
+            p10: distribution.p10,
+            p25: distribution.p25,
+            p50: distribution.p50,
+            p75: distribution.p75,
+            p90: distribution.p90,
+            p95: distribution.p95,
+            p99: distribution.p99,
+        }
+    }
+}
+
+/// `GET /v1/insights` — summary of the current insights; 304 when
+/// `If-None-Match` holds the current ETag.
+async fn current_insights(
+    State(engine): State<InsightsState>,
+    request_headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let engine = engine.read().await;
+    let cache_control = cache_control(INSIGHTS_MAX_AGE, INSIGHTS_SWR);
+    let etag = insights_etag(&engine, "v1/insights");
+    if let (Some(etag), Some(updated)) = (&etag, engine.get_last_update()) {
+        if if_none_match_matches(&request_headers, etag) {
+            return Ok(not_modified(
+                etag,
+                cache_control,
+                Some(last_modified(updated)),
+            ));
+        }
+    }
+
+    let insights = engine.get_current_insights();
+    drop(engine);
+    let body = serde_json::to_vec(&InsightsResponse::from(&insights))
+        .map_err(|err| AppError::Unknown(format!("Failed to serialize insights: {}", err)))?;
+    let etag = etag.unwrap_or_else(|| compute_etag(&body));
+    Ok(json_with_etag(
+        body,
+        &etag,
+        cache_control,
+        Some(last_modified(insights.last_updated)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use axum::{body::Body, http::Request, http::StatusCode};
+    use http_body_util::BodyExt;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    use crate::api::insights::create_insights_router;
+    use crate::insights::{FeeDataPoint, FeeInsightsEngine, InsightsConfig};
+
+    async fn get_json(app: &Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn v1_routes_override_and_fall_through() {
+        let engine = Arc::new(RwLock::new(FeeInsightsEngine::new(
+            InsightsConfig::default(),
+        )));
+        let points: Vec<FeeDataPoint> = (0..5)
+            .map(|i| FeeDataPoint {
+                fee_amount: 100 + i * 50,
+                timestamp: Utc::now(),
+                transaction_hash: format!("tx_{}", i),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        engine
+            .write()
+            .await
+            .process_fee_data(&points)
+            .await
+            .unwrap();
+
+        let unversioned = create_insights_router(engine.clone());
+        let app = Router::new().merge(unversioned.clone()).nest(
+            PREFIX,
+            Router::new()
+                .merge(network_routes(engine.clone(), unversioned.clone()))
+                .nest(
+                    "/networks/testnet",
+                    network_routes(engine.clone(), unversioned),
+                ),
+        );
+
+        let (status, v1) = get_json(&app, "/v1/insights").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(v1["windows"][0]["name"], "short_term");
+        assert_eq!(v1["windows"][0]["sample_count"], 5);
+        assert_eq!(
+            v1["extremes"],
+            serde_json::json!({ "min": 100, "max": 300 })
+        );
+        assert_eq!(v1["congestion"]["level"], "normal");
+
+        // The unprefixed route keeps the engine's own shape
+        let (_, unprefixed) = get_json(&app, "/insights").await;
+        assert!(unprefixed["rolling_averages"].is_object());
+
+        // Routes without a v1 handler are shared
+        let (status, averages) = get_json(&app, "/v1/insights/averages").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(averages["short_term"].is_object());
+
+        let (status, _) = get_json(&app, "/v1/networks/testnet/insights/averages").await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, network) = get_json(&app, "/v1/networks/testnet/insights").await;
+        assert_eq!(network["windows"], v1["windows"]);
+
+        // The summary is revalidated against its own ETag
+        let etag = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                response.headers()["etag"].to_str().unwrap().to_string()
+            }
+        };
+        let v1_etag = etag("/v1/insights").await;
+        assert_ne!(v1_etag, etag("/insights").await);
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/v1/insights")
+                    .header("if-none-match", &v1_etag)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()["etag"], v1_etag.as_str());
+
+        // New fees change the tag
+        engine
+            .write()
+            .await
+            .process_fee_data(&points)
+            .await
+            .unwrap();
+        assert_ne!(etag("/v1/insights").await, v1_etag);
+
+        // Sparse fieldsets, on both versions
+        let (status, sparse) = get_json(
+            &app,
+            "/v1/insights?fields=windows.percentiles.p90,congestion",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(sparse.as_object().unwrap().len(), 2);
+        assert_eq!(sparse["windows"][0].as_object().unwrap().len(), 1);
+        assert!(sparse["windows"][0]["percentiles"]["p90"].is_u64());
+        assert_eq!(sparse["congestion"]["level"], "normal");
+        let (_, sparse) = get_json(&app, "/insights/averages?fields=short_term.value").await;
+        assert_eq!(sparse["short_term"].as_object().unwrap().len(), 1);
+        let (status, _) = get_json(&app, "/insights?fields=,").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = get_json(&app, "/v1/unknown").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,361 @@
+//! Live fee stream over WebSocket.
+//!
+//! Routes:
+//! - `GET /ws/fees?topics=…&min_fee=…` — upgrades to a WebSocket that
+//!   pushes JSON messages as the engine processes fees
+//!
+//! Messages are tagged by `type`: `fees` carries a processed batch's
+//! points, `insights` the insights it produced and `event` an
+//! [`InsightEvent`] such as a congestion state change. `subscribed` confirms
+//! the connection's filter, first on connect and again after every change.
+//!
+//! `topics` is a comma-separated subset of `fees`, `insights`,
+//! `congestion`, `anomalies`, `surges` and `budgets` (default all);
+//! `min_fee` drops fee points charged less than it. A client changes its
+//! filter by sending `{"topics": [...], "min_fee": …}`; omitted fields are
+//! kept.
+
+use std::collections::BTreeSet;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use super::insights::InsightsState;
+use crate::error::AppError;
+use crate::insights::{CurrentInsights, FeeDataPoint, InsightEvent, ProcessedBatch};
+
+/// Kind of message a connection can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    Fees,
+    Insights,
+    Congestion,
+    Anomalies,
+    Surges,
+    Budgets,
+}
+
+impl Topic {
+    pub const ALL: [Topic; 6] = [
+        Self::Fees,
+        Self::Insights,
+        Self::Congestion,
+        Self::Anomalies,
+        Self::Surges,
+        Self::Budgets,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fees => "fees",
+            Self::Insights => "insights",
+            Self::Congestion => "congestion",
+            Self::Anomalies => "anomalies",
+            Self::Surges => "surges",
+            Self::Budgets => "budgets",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == value)
+    }
+
+    /// Topic an engine event is published under.
+    fn of_event(event: &InsightEvent) -> Self {
+        match event {
+            InsightEvent::CongestionEntered(_) | InsightEvent::CongestionCleared(_) => {
+                Self::Congestion
+            }
+            InsightEvent::AnomalyDetected(_) => Self::Anomalies,
+            InsightEvent::SurgeStarted { .. } => Self::Surges,
+            InsightEvent::BudgetExceeded(_) => Self::Budgets,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamQuery {
+    /// Comma-separated topics; defaults to all.
+    pub topics: Option<String>,
+    pub min_fee: Option<u64>,
+}
+
+/// A connection's filter change; omitted fields are kept
+#[derive(Debug, Deserialize)]
+struct SubscriptionUpdate {
+    topics: Option<BTreeSet<Topic>>,
+    min_fee: Option<u64>,
+}
+
+/// What one connection is sent
+#[derive(Debug, Clone, PartialEq)]
+struct Subscription {
+    topics: BTreeSet<Topic>,
+    min_fee: Option<u64>,
+}
+
+impl Subscription {
+    fn apply(&mut self, update: SubscriptionUpdate) {
+        if let Some(topics) = update.topics {
+            self.topics = topics;
+        }
+        if update.min_fee.is_some() {
+            self.min_fee = update.min_fee;
+        }
+    }
+
+    /// Messages for `batch` that pass the filter.
+    fn batch_messages<'a>(&self, batch: &'a ProcessedBatch) -> Vec<StreamMessage<'a>> {
+        let mut messages = Vec::new();
+        if self.topics.contains(&Topic::Fees) {
+            let points: Vec<&FeeDataPoint> = batch
+                .points
+                .iter()
+                .filter(|p| self.min_fee.is_none_or(|min| p.fee_amount >= min))
+                .collect();
+            if !points.is_empty() {
+                messages.push(StreamMessage::Fees { points });
+            }
+        }
+        if self.topics.contains(&Topic::Insights) {
+            messages.push(StreamMessage::Insights {
+                insights: &batch.insights,
+            });
+        }
+        messages
+    }
+}
+
+/// A message sent to the client
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamMessage<'a> {
+    Subscribed {
+        topics: &'a BTreeSet<Topic>,
+        min_fee: Option<u64>,
+    },
+    Fees {
+        points: Vec<&'a FeeDataPoint>,
+    },
+    Insights {
+        insights: &'a CurrentInsights,
+    },
+    Event {
+        event: &'a InsightEvent,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl StreamMessage<'_> {
+    fn encode(&self) -> Message {
+        Message::Text(serde_json::to_string(self).unwrap_or_default())
+    }
+}
+
+/// `GET /ws/fees` — upgrade to the live fee stream.
+pub async fn fee_stream(
+    State(engine): State<InsightsState>,
+    Query(params): Query<StreamQuery>,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    let topics = match params.topics.as_deref() {
+        None => Topic::ALL.into_iter().collect(),
+        Some(value) => value
+            .split(',')
+            .map(|topic| {
+                Topic::parse(topic.trim())
+                    .ok_or_else(|| AppError::BadRequest(format!("Unknown topic: {}", topic)))
+            })
+            .collect::<Result<_, _>>()?,
+    };
+    let subscription = Subscription {
+        topics,
+        min_fee: params.min_fee,
+    };
+
+    Ok(upgrade.on_upgrade(move |socket| stream_fees(socket, engine, subscription)))
+}
+
+async fn stream_fees(mut socket: WebSocket, engine: InsightsState, mut subscription: Subscription) {
+    let (mut batches, mut events) = {
+        let engine = engine.read().await;
+        (engine.subscribe_batches(), engine.subscribe_events())
+    };
+    let subscribed = |subscription: &Subscription| {
+        StreamMessage::Subscribed {
+            topics: &subscription.topics,
+            min_fee: subscription.min_fee,
+        }
+        .encode()
+    };
+    if socket.send(subscribed(&subscription)).await.is_err() {
+        return;
+    }
+
+    loop {
+        let outgoing: Vec<Message> = tokio::select! {
+            batch = batches.recv() => match batch {
+                Ok(batch) => subscription
+                    .batch_messages(&batch)
+                    .iter()
+                    .map(StreamMessage::encode)
+                    .collect(),
+                // A slow client skips what it missed
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            event = events.recv() => match event {
+                Ok(event) if subscription.topics.contains(&Topic::of_event(&event)) => {
+                    vec![StreamMessage::Event { event: &event }.encode()]
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    match serde_json::from_str::<SubscriptionUpdate>(&text) {
+                        Ok(update) => {
+                            subscription.apply(update);
+                            vec![subscribed(&subscription)]
+                        }
+                        Err(err) => vec![StreamMessage::Error {
+                            message: format!("Invalid subscription: {}", err),
+                        }
+                        .encode()],
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by axum
+                Some(Ok(_)) => continue,
+            },
+        };
+        for message in outgoing {
+            if socket.send(message).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use axum::{routing::get, Router};
+    use chrono::Utc;
+    use futures::{SinkExt, StreamExt};
+    use tokio::sync::RwLock;
+    use tokio_tungstenite::tungstenite;
+
+    use crate::insights::{FeeInsightsEngine, InsightsConfig};
+
+    fn batch(fees: &[u64]) -> Vec<FeeDataPoint> {
+        fees.iter()
+            .enumerate()
+            .map(|(i, &fee_amount)| FeeDataPoint {
+                fee_amount,
+                timestamp: Utc::now(),
+                transaction_hash: format!("tx_{}_{}", fee_amount, i),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect()
+    }
+
+    /// The next message from the server, parsed as JSON.
+    async fn next_json<S>(client: &mut S) -> serde_json::Value
+    where
+        S: StreamExt<Item = Result<tungstenite::Message, tungstenite::Error>> + Unpin,
+    {
+        match client.next().await.unwrap().unwrap() {
+            tungstenite::Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            other => panic!("expected text, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn pushes_batches_matching_the_subscription() {
+        let engine = Arc::new(RwLock::new(FeeInsightsEngine::new(
+            InsightsConfig::default(),
+        )));
+        let app = Router::new()
+            .route("/ws/fees", get(fee_stream))
+            .with_state(engine.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let (mut client, _) = tokio_tungstenite::connect_async(format!(
+            "ws://{}/ws/fees?topics=fees,congestion&min_fee=200",
+            addr
+        ))
+        .await
+        .unwrap();
+
+        let subscribed = next_json(&mut client).await;
+        assert_eq!(subscribed["type"], "subscribed");
+        assert_eq!(
+            subscribed["topics"],
+            serde_json::json!(["fees", "congestion"])
+        );
+
+        engine
+            .write()
+            .await
+            .process_fee_data(&batch(&[100, 300, 500]))
+            .await
+            .unwrap();
+        let fees = next_json(&mut client).await;
+        assert_eq!(fees["type"], "fees");
+        let charged: Vec<u64> = fees["points"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["fee_amount"].as_u64().unwrap())
+            .collect();
+        assert_eq!(charged, vec![300, 500]);
+
+        client
+            .send(tungstenite::Message::Text(
+                r#"{"topics": ["insights"]}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+        let resubscribed = next_json(&mut client).await;
+        assert_eq!(resubscribed["topics"], serde_json::json!(["insights"]));
+        assert_eq!(resubscribed["min_fee"], 200);
+
+        engine
+            .write()
+            .await
+            .process_fee_data(&batch(&[400]))
+            .await
+            .unwrap();
+        let insights = next_json(&mut client).await;
+        assert_eq!(insights["type"], "insights");
+        assert!(insights["insights"]["rolling_averages"].is_object());
+    }
+
+    #[test]
+    fn events_map_to_their_topics() {
+        let event = InsightEvent::SurgeStarted {
+            start_time: Utc::now(),
+            baseline_fee: 100.0,
+        };
+        assert_eq!(Topic::of_event(&event), Topic::Surges);
+        assert_eq!(Topic::parse("budgets"), Some(Topic::Budgets));
+        assert_eq!(Topic::parse("ledgers"), None);
+    }
+}
@@ -0,0 +1,170 @@
+//! Historical fee backfill.
+//!
+//! Bootstraps the database with past fee data by walking a ledger range in
+//! fixed-size chunks through `FeeDataProvider::fetch_fees_range`. Each chunk
+//! is persisted before the next is fetched, so memory use stays bounded no
+//! matter how long the range is.
+//!
+//! Backfilled rows are subject to the normal `STORAGE_RETENTION_DAYS` pruning,
+//! so raise the retention window before backfilling weeks of history.
+
+use crate::insights::error::ProviderError;
+use crate::insights::FeeDataProvider;
+use crate::repository::FeeRepository;
+
+/// Number of ledgers fetched per chunk (~10 minutes of ledgers).
+pub const DEFAULT_CHUNK_LEDGERS: u64 = 100;
+
+/// Errors that abort a backfill run.
+#[derive(Debug, thiserror::Error)]
+pub enum BackfillError {
+    #[error("provider error: {0}")]
+    Provider(#[from] ProviderError),
+
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Fetch and persist every ledger in `start_ledger..=end_ledger`.
+///
+/// Returns the total number of fee data points written.
+pub async fn run_backfill(
+    provider: &(dyn FeeDataProvider + Send + Sync),
+    repository: &FeeRepository,
+    start_ledger: u64,
+    end_ledger: u64,
+    chunk_ledgers: u64,
+) -> Result<usize, BackfillError> {
+    let chunk_ledgers = chunk_ledgers.max(1);
+    let mut total = 0;
+    let mut chunk_start = start_ledger;
+
+    tracing::info!(
+        "Backfill started: ledgers {}..={} via {}",
+        start_ledger,
+        end_ledger,
+        provider.provider_name()
+    );
+
+    while chunk_start <= end_ledger {
+        let chunk_end = chunk_start
+            .saturating_add(chunk_ledgers - 1)
+            .min(end_ledger);
+
+        let points = provider.fetch_fees_range(chunk_start, chunk_end).await?;
+        repository.insert_fee_points(&points).await?;
+        total += points.len();
+
+        tracing::info!(
+            "Backfilled ledgers {}..={} ({} points, {} total)",
+            chunk_start,
+            chunk_end,
+            points.len(),
+            total
+        );
+
+        if chunk_end == u64::MAX {
+            break;
+        }
+        chunk_start = chunk_end + 1;
+    }
+
+    tracing::info!("Backfill complete: {} fee data points stored", total);
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use chrono::{Duration, Utc};
+    use std::sync::Mutex;
+
+    use crate::db::create_pool;
+    use crate::insights::types::FeeDataPoint;
+
+    /// Provider that returns one point per ledger and records requested ranges.
+    struct LedgerRangeProvider {
+        requested: Mutex<Vec<(u64, u64)>>,
+    }
+
+    #[async_trait]
+    impl FeeDataProvider for LedgerRangeProvider {
+        async fn fetch_latest_fees(&self) -> Result<Vec<FeeDataPoint>, ProviderError> {
+            Ok(Vec::new())
+        }
+
+        async fn fetch_fees_range(
+            &self,
+            start_ledger: u64,
+            end_ledger: u64,
+        ) -> Result<Vec<FeeDataPoint>, ProviderError> {
+            self.requested
+                .lock()
+                .unwrap()
+                .push((start_ledger, end_ledger));
+            Ok((start_ledger..=end_ledger)
+                .map(|ledger| FeeDataPoint {
+                    fee_amount: 100,
+                    timestamp: Utc::now() - Duration::minutes(5),
+                    transaction_hash: format!("hash_{}", ledger),
+                    ledger_sequence: ledger,
+                    envelope: None,
+                    soroban: None,
+                })
+                .collect())
+        }
+
+        fn provider_name(&self) -> &str {
+            "LedgerRange"
+        }
+    }
+
+    struct NoHistoryProvider;
+
+    #[async_trait]
+    impl FeeDataProvider for NoHistoryProvider {
+        async fn fetch_latest_fees(&self) -> Result<Vec<FeeDataPoint>, ProviderError> {
+            Ok(Vec::new())
+        }
+
+        fn provider_name(&self) -> &str {
+            "NoHistory"
+        }
+    }
+
+    async fn make_repo() -> FeeRepository {
+        FeeRepository::new(create_pool("sqlite::memory:").await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn backfill_walks_range_in_chunks_and_persists() {
+        let provider = LedgerRangeProvider {
+            requested: Mutex::new(Vec::new()),
+        };
+        let repo = make_repo().await;
+
+        let total = run_backfill(&provider, &repo, 100, 124, 10).await.unwrap();
+
+        assert_eq!(total, 25);
+        assert_eq!(
+            *provider.requested.lock().unwrap(),
+            vec![(100, 109), (110, 119), (120, 124)]
+        );
+        let stored = repo
+            .fetch_since(Utc::now() - Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 25);
+    }
+
+    #[tokio::test]
+    async fn backfill_with_unsupported_provider_fails() {
+        let repo = make_repo().await;
+        let result = run_backfill(&NoHistoryProvider, &repo, 1, 10, 10).await;
+        assert!(matches!(
+            result,
+            Err(BackfillError::Provider(ProviderError::Unsupported { .. }))
+        ));
+    }
+}
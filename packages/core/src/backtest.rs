@@ -0,0 +1,256 @@
+//! Fee strategy backtesting.
+//!
+//! Replays stored fee history ledger by ledger against a bidding strategy.
+//! Before each ledger the strategy bids from what it has seen so far; the
+//! bid is included in the first of the next `max_wait_ledgers` ledgers whose
+//! cheapest classic fee it meets — the same bar `InclusionEstimator` uses.
+//!
+//! Spend counts the full bid of every included transaction, so it is an
+//! upper bound on what the strategy would have paid.
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::insights::{FeeDataPoint, FeeInsightsEngine, InsightsConfig, Urgency};
+use crate::repository::FeeRepository;
+
+/// A fee-bidding strategy under test
+#[async_trait]
+pub trait FeeStrategy: Send {
+    /// Name the report is labelled with.
+    fn name(&self) -> String;
+
+    /// Bid for a transaction submitted before the next ledger, in stroops.
+    fn bid(&mut self) -> u64;
+
+    /// Learn from a ledger's fees once it has closed.
+    async fn observe(&mut self, ledger: &[FeeDataPoint]);
+}
+
+/// Always bids the same fee
+pub struct FixedBid(pub u64);
+
+#[async_trait]
+impl FeeStrategy for FixedBid {
+    fn name(&self) -> String {
+        format!("fixed_{}", self.0)
+    }
+
+    fn bid(&mut self) -> u64 {
+        self.0
+    }
+
+    async fn observe(&mut self, _ledger: &[FeeDataPoint]) {}
+}
+
+/// Bids what the built-in recommendation engine suggests
+pub struct RecommendationStrategy {
+    engine: FeeInsightsEngine,
+    urgency: Urgency,
+}
+
+impl RecommendationStrategy {
+    /// Replay with a fresh engine built from `config`.
+    pub fn new(config: InsightsConfig, urgency: Urgency) -> Self {
+        Self {
+            engine: FeeInsightsEngine::new(config),
+            urgency,
+        }
+    }
+}
+
+#[async_trait]
+impl FeeStrategy for RecommendationStrategy {
+    fn name(&self) -> String {
+        format!("recommendation_{:?}", self.urgency).to_lowercase()
+    }
+
+    fn bid(&mut self) -> u64 {
+        self.urgency.pick(&self.engine.get_fee_recommendation())
+    }
+
+    async fn observe(&mut self, ledger: &[FeeDataPoint]) {
+        if let Err(err) = self.engine.process_fee_data(ledger).await {
+            tracing::debug!("Backtest engine skipped a ledger: {}", err);
+        }
+    }
+}
+
+/// Replay settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestConfig {
+    /// Ledgers a transaction may wait before it counts as not included.
+    pub max_wait_ledgers: usize,
+    /// Ledgers the strategy observes before it starts bidding.
+    pub warmup_ledgers: usize,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            max_wait_ledgers: 5,
+            warmup_ledgers: 20,
+        }
+    }
+}
+
+/// Outcome of replaying one strategy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub strategy: String,
+    pub ledgers_replayed: usize,
+    /// One transaction is submitted before each ledger after the warmup.
+    pub submitted: usize,
+    pub included: usize,
+    /// `None` when nothing was submitted.
+    pub inclusion_rate: Option<f64>,
+    /// Sum of the bids of included transactions, in stroops.
+    pub total_fee_spend: u64,
+    /// Mean bid of included transactions; `None` when none were.
+    pub average_fee: Option<f64>,
+    /// Mean ledgers until inclusion, counting the first as 1.
+    pub average_wait_ledgers: Option<f64>,
+}
+
+/// Replay `points` ledger by ledger against `strategy`.
+pub async fn run_backtest(
+    strategy: &mut dyn FeeStrategy,
+    points: &[FeeDataPoint],
+    config: &BacktestConfig,
+) -> BacktestReport {
+    let mut by_ledger: BTreeMap<u64, Vec<FeeDataPoint>> = BTreeMap::new();
+    for point in points {
+        by_ledger
+            .entry(point.ledger_sequence)
+            .or_default()
+            .push(point.clone());
+    }
+    let ledgers: Vec<Vec<FeeDataPoint>> = by_ledger.into_values().collect();
+    // Soroban resource fees say nothing about inclusion pricing
+    let minimums: Vec<Option<u64>> = ledgers
+        .iter()
+        .map(|ledger| {
+            ledger
+                .iter()
+                .filter(|p| p.soroban.is_none())
+                .map(|p| p.fee_amount)
+                .min()
+        })
+        .collect();
+
+    let max_wait = config.max_wait_ledgers.max(1);
+    let (mut submitted, mut included, mut spend, mut waited) = (0, 0, 0u64, 0);
+    for (i, ledger) in ledgers.iter().enumerate() {
+        // Only bid where every ledger the transaction may wait for is known
+        if i >= config.warmup_ledgers && i + max_wait <= ledgers.len() {
+            let bid = strategy.bid();
+            submitted += 1;
+            let wait = minimums[i..i + max_wait]
+                .iter()
+                .position(|min_fee| min_fee.is_some_and(|min_fee| min_fee <= bid));
+            if let Some(wait) = wait {
+                included += 1;
+                spend += bid;
+                waited += wait + 1;
+            }
+        }
+        strategy.observe(ledger).await;
+    }
+
+    BacktestReport {
+        strategy: strategy.name(),
+        ledgers_replayed: ledgers.len(),
+        submitted,
+        included,
+        inclusion_rate: (submitted > 0).then(|| included as f64 / submitted as f64),
+        total_fee_spend: spend,
+        average_fee: (included > 0).then(|| spend as f64 / included as f64),
+        average_wait_ledgers: (included > 0).then(|| waited as f64 / included as f64),
+    }
+}
+
+/// Replay every stored point since `since` against `strategy`.
+pub async fn backtest_since(
+    repo: &FeeRepository,
+    since: DateTime<Utc>,
+    strategy: &mut dyn FeeStrategy,
+    config: &BacktestConfig,
+) -> Result<BacktestReport, sqlx::Error> {
+    let points = repo.fetch_since(since).await?;
+    Ok(run_backtest(strategy, &points, config).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+
+    /// Ledgers 1..=`count`; every third clears at 100, the rest at 400.
+    fn history(count: u64) -> Vec<FeeDataPoint> {
+        let start = Utc::now() - chrono::Duration::minutes(30);
+        (1..=count)
+            .flat_map(|sequence| {
+                let min_fee = if sequence % 3 == 0 { 100 } else { 400 };
+                [min_fee, min_fee * 2].map(|fee_amount| FeeDataPoint {
+                    fee_amount,
+                    timestamp: start + chrono::Duration::seconds(5 * sequence as i64),
+                    transaction_hash: format!("tx_{}_{}", sequence, fee_amount),
+                    ledger_sequence: sequence,
+                    envelope: None,
+                    soroban: None,
+                })
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn fixed_bids_trade_spend_for_inclusion() {
+        let config = BacktestConfig {
+            max_wait_ledgers: 1,
+            warmup_ledgers: 0,
+        };
+        let points = history(30);
+
+        let cheap = run_backtest(&mut FixedBid(100), &points, &config).await;
+        assert_eq!(cheap.strategy, "fixed_100");
+        assert_eq!((cheap.submitted, cheap.included), (30, 10));
+        assert_eq!(cheap.total_fee_spend, 1_000);
+
+        let generous = run_backtest(&mut FixedBid(400), &points, &config).await;
+        assert_eq!(generous.inclusion_rate, Some(1.0));
+        assert_eq!(generous.total_fee_spend, 12_000);
+        assert_eq!(generous.average_wait_ledgers, Some(1.0));
+
+        // Waiting up to three ledgers lets the cheap bid always clear
+        let patient = BacktestConfig {
+            max_wait_ledgers: 3,
+            warmup_ledgers: 0,
+        };
+        let cheap = run_backtest(&mut FixedBid(100), &points, &patient).await;
+        assert_eq!((cheap.submitted, cheap.included), (28, 28));
+        assert_eq!(cheap.average_wait_ledgers, Some(57.0 / 28.0));
+    }
+
+    #[tokio::test]
+    async fn replays_stored_history_through_the_recommendation_engine() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = FeeRepository::new(pool);
+        repo.insert_fee_points(&history(60)).await.unwrap();
+
+        let mut strategy =
+            RecommendationStrategy::new(InsightsConfig::default(), Urgency::Priority);
+        let since = Utc::now() - chrono::Duration::hours(1);
+        let report = backtest_since(&repo, since, &mut strategy, &BacktestConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(report.strategy, "recommendation_priority");
+        assert_eq!(report.ledgers_replayed, 60);
+        assert_eq!(report.submitted, 36);
+        assert_eq!(report.inclusion_rate, Some(1.0));
+        assert!(report.average_fee.unwrap() >= 400.0);
+    }
+}
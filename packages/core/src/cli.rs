@@ -21,4 +21,12 @@ pub struct Cli {
     /// Fee polling interval in seconds
     #[arg(long)]
     pub poll_interval: Option<u64>,
+
+    /// First ledger to backfill into the database before polling starts
+    #[arg(long, requires = "backfill_to_ledger")]
+    pub backfill_from_ledger: Option<u64>,
+
+    /// Last ledger (inclusive) to backfill
+    #[arg(long, requires = "backfill_from_ledger")]
+    pub backfill_to_ledger: Option<u64>,
 }
@@ -0,0 +1,145 @@
+//! Fee comparisons between two time ranges.
+//!
+//! Each range is summarised from stored rollups, so week-long ranges stay
+//! cheap: the average is exact, while p90 is estimated by merging the
+//! rollups' percentiles. Congestion time is the part of the range covered
+//! by recorded surge episodes.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::repository::FeeRepository;
+use crate::rollup::{merged_quantile, Resolution};
+
+/// Most rollups summarised per range; longer ranges use coarser buckets.
+const MAX_BUCKETS: i64 = 2_000;
+
+/// Fees over one time range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangeStats {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Rollup resolution the stats were computed from.
+    pub resolution: Resolution,
+    pub sample_count: u64,
+    /// `None` when the range holds no samples.
+    pub avg_fee: Option<f64>,
+    pub p90_fee: Option<f64>,
+    /// Time within the range spent in surge episodes, in seconds.
+    pub congestion_seconds: i64,
+    /// `congestion_seconds` over the length of the range.
+    pub congestion_share: f64,
+}
+
+/// How `current` differs from `baseline`, in percent of the baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangeComparison {
+    pub baseline: RangeStats,
+    pub current: RangeStats,
+    /// `None` when either range lacks the value or the baseline is zero.
+    pub avg_fee_change_pct: Option<f64>,
+    pub p90_fee_change_pct: Option<f64>,
+    pub congestion_change_pct: Option<f64>,
+}
+
+/// Summarise fees between `from` and `to`.
+pub async fn range_stats(
+    repo: &FeeRepository,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<RangeStats, sqlx::Error> {
+    let resolution = resolution_for(to - from);
+    let rollups = repo.fetch_rollups_between(resolution, from, to).await?;
+    let episodes = repo.fetch_surge_episodes_between(from, to).await?;
+
+    let sample_count: u64 = rollups.iter().map(|r| r.sample_count).sum();
+    let fee_total: f64 = rollups
+        .iter()
+        .map(|r| r.avg_fee * r.sample_count as f64)
+        .sum();
+    let congestion_seconds: i64 = episodes
+        .iter()
+        .map(|episode| {
+            (episode.end_time.min(to) - episode.start_time.max(from))
+                .num_seconds()
+                .max(0)
+        })
+        .sum();
+    let length = (to - from).num_seconds();
+
+    Ok(RangeStats {
+        from,
+        to,
+        resolution,
+        sample_count,
+        avg_fee: (sample_count > 0).then(|| fee_total / sample_count as f64),
+        p90_fee: merged_quantile(&rollups, 0.9),
+        congestion_seconds,
+        congestion_share: if length > 0 {
+            congestion_seconds as f64 / length as f64
+        } else {
+            0.0
+        },
+    })
+}
+
+/// Compare fees in `current` against `baseline`, each a `(from, to)` range.
+pub async fn compare_ranges(
+    repo: &FeeRepository,
+    baseline: (DateTime<Utc>, DateTime<Utc>),
+    current: (DateTime<Utc>, DateTime<Utc>),
+) -> Result<RangeComparison, sqlx::Error> {
+    let baseline = range_stats(repo, baseline.0, baseline.1).await?;
+    let current = range_stats(repo, current.0, current.1).await?;
+
+    Ok(RangeComparison {
+        avg_fee_change_pct: percent_change(baseline.avg_fee, current.avg_fee),
+        p90_fee_change_pct: percent_change(baseline.p90_fee, current.p90_fee),
+        congestion_change_pct: percent_change(
+            Some(baseline.congestion_seconds as f64),
+            Some(current.congestion_seconds as f64),
+        ),
+        baseline,
+        current,
+    })
+}
+
+/// Finest resolution that covers `length` in at most `MAX_BUCKETS` rollups.
+fn resolution_for(length: Duration) -> Resolution {
+    Resolution::ALL
+        .into_iter()
+        .find(|resolution| {
+            length.num_seconds() <= resolution.duration().num_seconds() * MAX_BUCKETS
+        })
+        .unwrap_or(Resolution::OneDay)
+}
+
+fn percent_change(baseline: Option<f64>, current: Option<f64>) -> Option<f64> {
+    match (baseline, current) {
+        (Some(baseline), Some(current)) if baseline != 0.0 => {
+            Some((current - baseline) / baseline * 100.0)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longer_ranges_use_coarser_rollups() {
+        assert_eq!(resolution_for(Duration::hours(6)), Resolution::OneMinute);
+        assert_eq!(resolution_for(Duration::days(3)), Resolution::FiveMinutes);
+        assert_eq!(resolution_for(Duration::days(7)), Resolution::OneHour);
+        assert_eq!(resolution_for(Duration::days(60)), Resolution::OneHour);
+        assert_eq!(resolution_for(Duration::days(365)), Resolution::OneDay);
+    }
+
+    #[test]
+    fn percent_change_needs_a_nonzero_baseline() {
+        assert_eq!(percent_change(Some(200.0), Some(300.0)), Some(50.0));
+        assert_eq!(percent_change(Some(0.0), Some(300.0)), None);
+        assert_eq!(percent_change(None, Some(300.0)), None);
+    }
+}
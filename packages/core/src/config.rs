@@ -1,39 +1,164 @@
+use serde::{Deserialize, Serialize};
 use std::env;
 
 use crate::cli::Cli;
-use crate::insights::SpikeSeverity;
+use crate::insights::config::{
+    BasicAuthConfig, HorizonAuthConfig, HorizonTimeoutConfig, HorizonTransportConfig,
+    DEFAULT_AUTH_HEADER,
+};
+use crate::insights::providers::simulated::{NoiseDistribution, SimulationConfig};
+use crate::insights::{AveragingMethod, InsightsConfig, SpikeSeverity};
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub stellar_network: StellarNetwork,
+    /// Networks tracked in addition to `stellar_network`, using their public Horizon URLs.
+    pub additional_networks: Vec<StellarNetwork>,
     pub horizon_url: String,
+    /// Additional Horizon mirrors tried in order when `horizon_url` is unreachable.
+    pub horizon_fallback_urls: Vec<String>,
+    /// Credentials for a private `horizon_url`; never sent to fallbacks.
+    pub horizon_auth: Option<HorizonAuthConfig>,
+    /// Connect, read and per-call limits for Horizon requests.
+    pub horizon_timeouts: HorizonTimeoutConfig,
+    /// Outbound proxy and TLS trust for Horizon requests.
+    pub horizon_transport: HorizonTransportConfig,
+    pub fee_provider: FeeProviderKind,
+    /// Providers whose data is merged with `fee_provider`'s on every poll.
+    pub merge_providers: Vec<FeeProviderKind>,
+    /// Provider used for `--backfill-*` runs; defaults to `fee_provider`.
+    pub backfill_provider: Option<FeeProviderKind>,
+    pub soroban_rpc_url: Option<String>,
+    /// Path to captive stellar-core's `METADATA_OUTPUT_STREAM` (usually a named pipe).
+    #[cfg_attr(not(feature = "captive-core"), allow(dead_code))]
+    pub captive_core_meta_path: Option<String>,
+    /// Base URL of the remote fee collector for `FEE_PROVIDER=grpc`.
+    #[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+    pub grpc_collector_url: Option<String>,
+    /// CSV or JSONL file replayed by `FEE_PROVIDER=file`.
+    pub replay_path: Option<String>,
+    /// Replay speed multiplier; `0` releases the whole file at once.
+    pub replay_speed: f64,
+    /// Traffic shape for `FEE_PROVIDER=simulated`.
+    pub simulation: SimulationConfig,
+    pub ingestion_mode: IngestionMode,
     pub poll_interval_seconds: u64,
     pub cache_ttl_seconds: u64,
+    /// TTL for cached provider responses; `0` disables provider caching.
+    pub provider_cache_ttl_seconds: u64,
+    /// How long polled transaction hashes are remembered to drop repeats;
+    /// `0` disables deduplication.
+    pub dedup_window_seconds: u64,
+    /// Consecutive failures that open the provider circuit breaker; `0` disables it.
+    pub circuit_breaker_threshold: u32,
+    pub circuit_breaker_open_seconds: u64,
+    /// GCP project billed for Hubble queries.
+    #[cfg_attr(not(feature = "hubble"), allow(dead_code))]
+    pub hubble_project_id: Option<String>,
+    /// Service account key for BigQuery; workload identity is used when unset.
+    #[cfg_attr(not(feature = "hubble"), allow(dead_code))]
+    pub hubble_credentials_path: Option<String>,
+    /// Overrides the public Hubble transaction table.
+    #[cfg_attr(not(feature = "hubble"), allow(dead_code))]
+    pub hubble_table: Option<String>,
+    /// Ledger segments fetched concurrently during historical backfill.
+    pub backfill_parallelism: usize,
     pub api_key: Option<String>,
+    /// Bearer token validation; `None` unless `JWT_JWKS_URL` is set.
+    pub jwt: Option<JwtConfig>,
     pub rate_limit_per_minute: u32,
+    /// Limits for paths under given prefixes, overriding
+    /// `rate_limit_per_minute` there.
+    pub route_rate_limits: Vec<RouteRateLimit>,
     pub webhook_url: Option<String>,
     pub alert_threshold: SpikeSeverity,
     pub api_port: u16,
+    /// Port of the gRPC API; off while `None`. Requires the `grpc-server`
+    /// cargo feature.
+    #[cfg_attr(not(feature = "grpc-server"), allow(dead_code))]
+    pub grpc_port: Option<u16>,
+    /// Origins browsers may call the API from; `*` allows any.
     pub allowed_origins: Vec<String>,
+    /// Methods browsers may use cross-origin.
+    pub cors_allowed_methods: Vec<String>,
+    /// Request headers browsers may send cross-origin.
+    pub cors_allowed_headers: Vec<String>,
+    /// Let browsers send cookies and HTTP authentication cross-origin.
+    pub cors_allow_credentials: bool,
+    /// How long browsers may cache a preflight response, in seconds.
+    pub cors_max_age_seconds: u64,
     pub retry_attempts: u32,
     pub base_retry_delay_ms: u64,
     pub database_url: String,
     pub storage_retention_days: u64,
+    /// Averaging for the named insight windows; windows not listed keep
+    /// `AveragingMethod::Simple`.
+    pub window_averaging: Vec<(String, AveragingMethod)>,
+    /// Store the account that paid each fee, for per-account spend reports.
+    pub track_fee_accounts: bool,
 }
 
-#[derive(Debug, Clone)]
+/// Requests per minute each client may make to the routes under a prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteRateLimit {
+    /// Path prefix, as served unprefixed on the primary network, e.g.
+    /// `/fees/history`.
+    pub prefix: String,
+    pub per_minute: u32,
+}
+
+/// Validation of JWT bearer tokens issued by an identity provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JwtConfig {
+    /// Required `iss` claim.
+    pub issuer: String,
+    /// Where the issuer publishes its signing keys.
+    pub jwks_url: String,
+    /// Required `aud` claim, when set.
+    pub audience: Option<String>,
+    /// Scope that grants the read-only routes.
+    pub read_scope: String,
+    /// Scope that grants every route, including admin operations.
+    pub admin_scope: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum StellarNetwork {
     Testnet,
+    #[serde(alias = "pubnet")]
     Mainnet,
+    Futurenet,
 }
 
 impl StellarNetwork {
+    /// Parses a network name as accepted by `STELLAR_NETWORK`.
+    /// `pubnet` is accepted as an alias for `mainnet`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "testnet" => Some(StellarNetwork::Testnet),
+            "mainnet" | "pubnet" => Some(StellarNetwork::Mainnet),
+            "futurenet" => Some(StellarNetwork::Futurenet),
+            _ => None,
+        }
+    }
+
     /// Returns the well-known public Horizon URL for this network.
     /// Used as the default when `HORIZON_URL` is not explicitly configured.
     pub fn default_horizon_url(&self) -> &'static str {
         match self {
             StellarNetwork::Testnet => "https://horizon-testnet.stellar.org",
             StellarNetwork::Mainnet => "https://horizon.stellar.org",
+            StellarNetwork::Futurenet => "https://horizon-futurenet.stellar.org",
+        }
+    }
+
+    /// Returns the public soroban-rpc URL for this network, if SDF runs one.
+    pub fn default_soroban_rpc_url(&self) -> Option<&'static str> {
+        match self {
+            StellarNetwork::Testnet => Some("https://soroban-testnet.stellar.org"),
+            StellarNetwork::Mainnet => None,
+            StellarNetwork::Futurenet => Some("https://rpc-futurenet.stellar.org"),
         }
     }
 
@@ -41,6 +166,48 @@ impl StellarNetwork {
         match self {
             StellarNetwork::Testnet => "testnet",
             StellarNetwork::Mainnet => "mainnet",
+            StellarNetwork::Futurenet => "futurenet",
+        }
+    }
+}
+
+/// How fee data reaches the insights engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IngestionMode {
+    /// Fetch on a fixed interval (`POLL_INTERVAL_SECONDS`).
+    Poll,
+    /// Consume Horizon's SSE transaction stream as ledgers close.
+    Stream,
+}
+
+/// Which `FeeDataProvider` feeds the polling scheduler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeeProviderKind {
+    Horizon,
+    SorobanRpc,
+    /// Requires the `captive-core` cargo feature.
+    CaptiveCore,
+    /// Replays a recorded CSV/JSONL file.
+    File,
+    /// Historical-only; requires the `hubble` cargo feature.
+    Hubble,
+    /// Synthetic traffic for demos and load tests.
+    Simulated,
+    /// Remote collector over gRPC; requires the `grpc` cargo feature.
+    Grpc,
+}
+
+impl FeeProviderKind {
+    /// `FEE_PROVIDER` value and provider registry key.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeeProviderKind::Horizon => "horizon",
+            FeeProviderKind::SorobanRpc => "soroban",
+            FeeProviderKind::CaptiveCore => "captive-core",
+            FeeProviderKind::File => "file",
+            FeeProviderKind::Hubble => "hubble",
+            FeeProviderKind::Simulated => "simulated",
+            FeeProviderKind::Grpc => "grpc",
         }
     }
 }
@@ -82,11 +249,24 @@ impl Config {
             .or_else(|| get("STELLAR_NETWORK"))
             .ok_or("STELLAR_NETWORK is required")?;
 
-        let stellar_network = match network_raw.as_str() {
-            "testnet" => StellarNetwork::Testnet,
-            "mainnet" => StellarNetwork::Mainnet,
-            other => return Err(format!("Invalid STELLAR_NETWORK: {}", other)),
-        };
+        let stellar_network = StellarNetwork::parse(&network_raw)
+            .ok_or_else(|| format!("Invalid STELLAR_NETWORK: {}", network_raw))?;
+
+        // Extra networks tracked alongside the primary one, each with its own
+        // Horizon client, storage scope and `/networks/{name}` routes.
+        let mut additional_networks: Vec<StellarNetwork> = Vec::new();
+        for raw in get("ADDITIONAL_NETWORKS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+        {
+            let network = StellarNetwork::parse(raw)
+                .ok_or_else(|| format!("Invalid ADDITIONAL_NETWORKS entry: {}", raw))?;
+            if network != stellar_network && !additional_networks.contains(&network) {
+                additional_networks.push(network);
+            }
+        }
 
         // -------- Horizon URL --------
         let horizon_url = cli
@@ -95,6 +275,155 @@ impl Config {
             .or_else(|| get("HORIZON_URL"))
             .unwrap_or_else(|| stellar_network.default_horizon_url().to_string());
 
+        let horizon_fallback_urls = get("HORIZON_FALLBACK_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty() && *s != horizon_url)
+            .collect();
+
+        let horizon_auth = parse_horizon_auth(&get)?;
+
+        // 0 disables a timeout.
+        let timeout_seconds = |key: &str, default: chrono::Duration| {
+            get(key)
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|v| *v >= 0)
+                .map(chrono::Duration::seconds)
+                .unwrap_or(default)
+        };
+        let timeout_defaults = HorizonTimeoutConfig::default();
+        let horizon_timeouts = HorizonTimeoutConfig {
+            connect_timeout: timeout_seconds(
+                "HORIZON_CONNECT_TIMEOUT_SECONDS",
+                timeout_defaults.connect_timeout,
+            ),
+            read_timeout: timeout_seconds(
+                "HORIZON_READ_TIMEOUT_SECONDS",
+                timeout_defaults.read_timeout,
+            ),
+            request_deadline: timeout_seconds(
+                "HORIZON_REQUEST_DEADLINE_SECONDS",
+                timeout_defaults.request_deadline,
+            ),
+        };
+
+        let horizon_transport = HorizonTransportConfig {
+            proxy_url: get("HORIZON_PROXY_URL").filter(|v| !v.trim().is_empty()),
+            ca_bundle_path: get("HORIZON_CA_BUNDLE_PATH").filter(|v| !v.trim().is_empty()),
+            accept_invalid_certs: get("HORIZON_TLS_INSECURE")
+                .map(|v| v.trim().eq_ignore_ascii_case("true") || v.trim() == "1")
+                .unwrap_or(false),
+        };
+
+        // -------- Fee data provider --------
+        let fee_provider = match get("FEE_PROVIDER") {
+            None => FeeProviderKind::Horizon,
+            Some(raw) => parse_fee_provider("FEE_PROVIDER", &raw)?,
+        };
+        if fee_provider == FeeProviderKind::Hubble {
+            return Err(
+                "FEE_PROVIDER=hubble only serves historical data; use BACKFILL_PROVIDER=hubble"
+                    .to_string(),
+            );
+        }
+
+        let mut merge_providers: Vec<FeeProviderKind> = Vec::new();
+        for raw in get("MERGE_PROVIDERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+        {
+            let kind = parse_fee_provider("MERGE_PROVIDERS", raw)?;
+            if kind == FeeProviderKind::Hubble {
+                return Err("MERGE_PROVIDERS cannot include hubble (historical only)".to_string());
+            }
+            if kind != fee_provider && !merge_providers.contains(&kind) {
+                merge_providers.push(kind);
+            }
+        }
+
+        let backfill_provider = get("BACKFILL_PROVIDER")
+            .filter(|v| !v.trim().is_empty())
+            .map(|raw| parse_fee_provider("BACKFILL_PROVIDER", &raw))
+            .transpose()?;
+
+        let hubble_project_id = get("HUBBLE_PROJECT_ID").filter(|v| !v.trim().is_empty());
+        let hubble_credentials_path =
+            get("HUBBLE_CREDENTIALS_PATH").filter(|v| !v.trim().is_empty());
+        let hubble_table = get("HUBBLE_TABLE").filter(|v| !v.trim().is_empty());
+
+        if backfill_provider == Some(FeeProviderKind::Hubble) && hubble_project_id.is_none() {
+            return Err("HUBBLE_PROJECT_ID is required when BACKFILL_PROVIDER=hubble".to_string());
+        }
+
+        let soroban_rpc_url = get("SOROBAN_RPC_URL")
+            .filter(|v| !v.trim().is_empty())
+            .or_else(|| {
+                stellar_network
+                    .default_soroban_rpc_url()
+                    .map(str::to_string)
+            });
+
+        if fee_provider == FeeProviderKind::SorobanRpc && soroban_rpc_url.is_none() {
+            return Err(format!(
+                "SOROBAN_RPC_URL is required when FEE_PROVIDER=soroban on {}",
+                stellar_network.as_str()
+            ));
+        }
+
+        let captive_core_meta_path = get("CAPTIVE_CORE_META_PATH").filter(|v| !v.trim().is_empty());
+
+        if fee_provider == FeeProviderKind::CaptiveCore && captive_core_meta_path.is_none() {
+            return Err(
+                "CAPTIVE_CORE_META_PATH is required when FEE_PROVIDER=captive-core".to_string(),
+            );
+        }
+
+        let grpc_collector_url = get("GRPC_COLLECTOR_URL").filter(|v| !v.trim().is_empty());
+
+        let uses_grpc = fee_provider == FeeProviderKind::Grpc
+            || merge_providers.contains(&FeeProviderKind::Grpc)
+            || backfill_provider == Some(FeeProviderKind::Grpc);
+        if uses_grpc && grpc_collector_url.is_none() {
+            return Err("GRPC_COLLECTOR_URL is required when using the grpc provider".to_string());
+        }
+
+        let replay_path = get("FEE_REPLAY_PATH").filter(|v| !v.trim().is_empty());
+
+        if fee_provider == FeeProviderKind::File && replay_path.is_none() {
+            return Err("FEE_REPLAY_PATH is required when FEE_PROVIDER=file".to_string());
+        }
+
+        let replay_speed = match get("FEE_REPLAY_SPEED") {
+            None => 1.0,
+            Some(raw) => raw
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite() && *v >= 0.0)
+                .ok_or_else(|| format!("Invalid FEE_REPLAY_SPEED: {}", raw))?,
+        };
+
+        let simulation = parse_simulation(&get)?;
+
+        // -------- Ingestion mode --------
+        let ingestion_mode = match get("INGESTION_MODE").as_deref().map(str::trim) {
+            None | Some("") | Some("poll") => IngestionMode::Poll,
+            Some("stream") => IngestionMode::Stream,
+            Some(other) => return Err(format!("Invalid INGESTION_MODE: {}", other)),
+        };
+
+        if ingestion_mode == IngestionMode::Stream && fee_provider != FeeProviderKind::Horizon {
+            return Err("INGESTION_MODE=stream requires FEE_PROVIDER=horizon".to_string());
+        }
+        if ingestion_mode == IngestionMode::Stream && !merge_providers.is_empty() {
+            return Err(
+                "INGESTION_MODE=stream cannot be combined with MERGE_PROVIDERS".to_string(),
+            );
+        }
+
         // -------- Poll Interval --------
         let poll_interval_seconds = cli
             .poll_interval
@@ -109,19 +438,63 @@ impl Config {
             .and_then(|v| v.parse::<u16>().ok())
             .unwrap_or(8080);
 
+        let grpc_port = match get("GRPC_PORT").filter(|v| !v.trim().is_empty()) {
+            None => None,
+            Some(_) if !cfg!(feature = "grpc-server") => {
+                return Err("GRPC_PORT requires building with the `grpc-server` feature".to_string())
+            }
+            Some(v) => match v.trim().parse::<u16>() {
+                Ok(port) if port != api_port => Some(port),
+                Ok(_) => return Err("GRPC_PORT must differ from the API port".to_string()),
+                Err(_) => return Err(format!("Invalid GRPC_PORT: {}", v)),
+            },
+        };
+
         // -------- Cache TTL --------
         let cache_ttl_seconds = get("CACHE_TTL_SECONDS")
             .and_then(|v| v.parse::<u64>().ok())
             .unwrap_or(5);
 
+        let provider_cache_ttl_seconds = get("PROVIDER_CACHE_TTL_SECONDS")
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0);
+
+        // -------- Deduplication --------
+        let dedup_window_seconds = get("DEDUP_WINDOW_SECONDS")
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(600);
+
+        // -------- Circuit breaker --------
+        let circuit_breaker_threshold = get("CIRCUIT_BREAKER_THRESHOLD")
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(5);
+
+        let circuit_breaker_open_seconds = get("CIRCUIT_BREAKER_OPEN_SECONDS")
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(30);
+
+        // -------- Backfill --------
+        let backfill_parallelism = get("BACKFILL_PARALLELISM")
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(4);
+
         // -------- API key --------
         let api_key = get("API_KEY").filter(|v| !v.trim().is_empty());
 
+        // -------- JWT --------
+        let jwt = parse_jwt(&get)?;
+
         // -------- Rate limiting --------
         let rate_limit_per_minute = get("RATE_LIMIT_PER_MINUTE")
             .and_then(|v| v.parse::<u32>().ok())
             .filter(|v| *v > 0)
             .unwrap_or(60);
+        let route_rate_limits = get("RATE_LIMIT_ROUTES")
+            .map(|v| parse_route_rate_limits(&v))
+            .transpose()?
+            .unwrap_or_default();
 
         // -------- Alerts --------
         let webhook_url = get("WEBHOOK_URL").filter(|v| !v.trim().is_empty());
@@ -136,7 +509,50 @@ impl Config {
             .split(',')
             .map(|s| s.trim().to_string())
             .filter(|s| !s.is_empty())
-            .collect();
+            .collect::<Vec<String>>();
+
+        // -------- CORS --------
+        let list = |key: &str, default: &str| -> Vec<String> {
+            get(key)
+                .filter(|v| !v.trim().is_empty())
+                .unwrap_or_else(|| default.to_string())
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        };
+        let cors_allowed_methods: Vec<String> =
+            list("CORS_ALLOWED_METHODS", "GET,POST,PUT,PATCH,DELETE,OPTIONS")
+                .into_iter()
+                .map(|method| method.to_ascii_uppercase())
+                .collect();
+        if let Some(invalid) = cors_allowed_methods
+            .iter()
+            .find(|m| reqwest::Method::from_bytes(m.as_bytes()).is_err())
+        {
+            return Err(format!("Invalid CORS_ALLOWED_METHODS entry: {}", invalid));
+        }
+        let cors_allowed_headers = list(
+            "CORS_ALLOWED_HEADERS",
+            "content-type,x-api-key,authorization,x-request-id,traceparent",
+        );
+        if let Some(invalid) = cors_allowed_headers
+            .iter()
+            .find(|h| reqwest::header::HeaderName::from_bytes(h.as_bytes()).is_err())
+        {
+            return Err(format!("Invalid CORS_ALLOWED_HEADERS entry: {}", invalid));
+        }
+        let cors_allow_credentials = get("CORS_ALLOW_CREDENTIALS")
+            .map(|v| v.trim().eq_ignore_ascii_case("true") || v.trim() == "1")
+            .unwrap_or(false);
+        if cors_allow_credentials && allowed_origins.iter().any(|o| o == "*") {
+            return Err(
+                "CORS_ALLOW_CREDENTIALS cannot be combined with ALLOWED_ORIGINS=*".to_string(),
+            );
+        }
+        let cors_max_age_seconds = get("CORS_MAX_AGE_SECONDS")
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .unwrap_or(3600);
 
         // -------- Retry config --------
         let retry_attempts = get("RETRY_ATTEMPTS")
@@ -156,25 +572,265 @@ impl Config {
             .and_then(|v| v.parse::<u64>().ok())
             .unwrap_or(7);
 
+        // -------- Insight windows --------
+        let window_averaging = get("WINDOW_AVERAGING")
+            .map(|v| parse_window_averaging(&v))
+            .transpose()?
+            .unwrap_or_default();
+
+        // -------- Per-account fees --------
+        let track_fee_accounts = get("TRACK_FEE_ACCOUNTS")
+            .map(|v| v.trim().eq_ignore_ascii_case("true") || v.trim() == "1")
+            .unwrap_or(false);
+
         Ok(Self {
             stellar_network,
+            additional_networks,
             horizon_url,
+            horizon_fallback_urls,
+            horizon_auth,
+            horizon_timeouts,
+            horizon_transport,
+            fee_provider,
+            merge_providers,
+            backfill_provider,
+            soroban_rpc_url,
+            captive_core_meta_path,
+            grpc_collector_url,
+            replay_path,
+            replay_speed,
+            simulation,
+            ingestion_mode,
             poll_interval_seconds,
             cache_ttl_seconds,
+            provider_cache_ttl_seconds,
+            dedup_window_seconds,
+            circuit_breaker_threshold,
+            circuit_breaker_open_seconds,
+            hubble_project_id,
+            hubble_credentials_path,
+            hubble_table,
+            backfill_parallelism,
             api_key,
+            jwt,
             rate_limit_per_minute,
+            route_rate_limits,
             webhook_url,
             alert_threshold,
             api_port,
+            grpc_port,
             allowed_origins,
+            cors_allowed_methods,
+            cors_allowed_headers,
+            cors_allow_credentials,
+            cors_max_age_seconds,
             retry_attempts,
             base_retry_delay_ms,
             database_url,
             storage_retention_days,
+            window_averaging,
+            track_fee_accounts,
         })
     }
 }
 
+/// Parse a provider name as accepted by `FEE_PROVIDER` / `BACKFILL_PROVIDER`.
+/// `var` names the variable in error messages.
+fn parse_fee_provider(var: &str, raw: &str) -> Result<FeeProviderKind, String> {
+    let requires_feature = |feature: &str| {
+        Err(format!(
+            "{}={} requires building with the `{}` feature",
+            var, feature, feature
+        ))
+    };
+    match raw.trim() {
+        "" | "horizon" => Ok(FeeProviderKind::Horizon),
+        "soroban" => Ok(FeeProviderKind::SorobanRpc),
+        "file" => Ok(FeeProviderKind::File),
+        "simulated" => Ok(FeeProviderKind::Simulated),
+        "captive-core" if cfg!(feature = "captive-core") => Ok(FeeProviderKind::CaptiveCore),
+        "captive-core" => requires_feature("captive-core"),
+        "hubble" if cfg!(feature = "hubble") => Ok(FeeProviderKind::Hubble),
+        "hubble" => requires_feature("hubble"),
+        "grpc" if cfg!(feature = "grpc") => Ok(FeeProviderKind::Grpc),
+        "grpc" => requires_feature("grpc"),
+        other => Err(format!("Invalid {}: {}", var, other)),
+    }
+}
+
+/// Build the simulated provider's traffic shape from `SIM_*` variables,
+/// falling back to `SimulationConfig::default()` for anything unset.
+fn parse_simulation(get: &impl Fn(&str) -> Option<String>) -> Result<SimulationConfig, String> {
+    fn parse<T: std::str::FromStr>(
+        get: &impl Fn(&str) -> Option<String>,
+        key: &str,
+    ) -> Result<Option<T>, String> {
+        get(key)
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| {
+                v.trim()
+                    .parse::<T>()
+                    .map_err(|_| format!("Invalid {}: {}", key, v))
+            })
+            .transpose()
+    }
+
+    let defaults = SimulationConfig::default();
+    let noise = match get("SIM_NOISE").filter(|v| !v.trim().is_empty()) {
+        Some(raw) => {
+            NoiseDistribution::parse(&raw).ok_or_else(|| format!("Invalid SIM_NOISE: {}", raw))?
+        }
+        None => defaults.noise,
+    };
+    let surge_probability =
+        parse::<f64>(get, "SIM_SURGE_PROBABILITY")?.unwrap_or(defaults.surge_probability);
+    if !(0.0..=1.0).contains(&surge_probability) {
+        return Err(format!(
+            "Invalid SIM_SURGE_PROBABILITY: {} (expected 0.0-1.0)",
+            surge_probability
+        ));
+    }
+
+    Ok(SimulationConfig {
+        base_fee: parse(get, "SIM_BASE_FEE")?.unwrap_or(defaults.base_fee),
+        transactions_per_ledger: parse(get, "SIM_TX_PER_LEDGER")?
+            .unwrap_or(defaults.transactions_per_ledger),
+        noise,
+        noise_factor: parse(get, "SIM_NOISE_FACTOR")?.unwrap_or(defaults.noise_factor),
+        surge_probability,
+        surge_multiplier: parse(get, "SIM_SURGE_MULTIPLIER")?.unwrap_or(defaults.surge_multiplier),
+        surge_duration_ledgers: parse(get, "SIM_SURGE_DURATION_LEDGERS")?
+            .unwrap_or(defaults.surge_duration_ledgers),
+        seed: parse(get, "SIM_SEED")?,
+        ..defaults
+    })
+}
+
+/// Build Horizon credentials from `HORIZON_AUTH_*` / `HORIZON_BASIC_AUTH_*`.
+/// Returns `None` when neither a token nor a basic-auth user is set.
+fn parse_horizon_auth(
+    get: &impl Fn(&str) -> Option<String>,
+) -> Result<Option<HorizonAuthConfig>, String> {
+    let non_empty = |key: &str| get(key).filter(|v| !v.trim().is_empty());
+
+    let token = non_empty("HORIZON_AUTH_TOKEN");
+    let basic = non_empty("HORIZON_BASIC_AUTH_USERNAME").map(|username| BasicAuthConfig {
+        username,
+        password: non_empty("HORIZON_BASIC_AUTH_PASSWORD"),
+    });
+    if token.is_none() && basic.is_none() {
+        return Ok(None);
+    }
+
+    let header_name = non_empty("HORIZON_AUTH_HEADER")
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|| DEFAULT_AUTH_HEADER.to_string());
+    if reqwest::header::HeaderName::from_bytes(header_name.as_bytes()).is_err() {
+        return Err(format!("Invalid HORIZON_AUTH_HEADER: {}", header_name));
+    }
+
+    let auth = HorizonAuthConfig {
+        header_name,
+        token,
+        basic,
+    };
+    if auth.token.is_some() && auth.basic.is_some() && auth.uses_authorization_header() {
+        return Err(
+            "HORIZON_AUTH_TOKEN and basic auth both use the Authorization header; \
+             set HORIZON_AUTH_HEADER to send the token elsewhere"
+                .to_string(),
+        );
+    }
+    Ok(Some(auth))
+}
+
+/// Parse `RATE_LIMIT_ROUTES`, a comma-separated list of `prefix=limit`
+/// pairs such as `/admin=10,/fees/history=20`.
+fn parse_route_rate_limits(raw: &str) -> Result<Vec<RouteRateLimit>, String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let invalid = || format!("Invalid RATE_LIMIT_ROUTES entry: {}", entry);
+            let (prefix, limit) = entry.split_once('=').ok_or_else(invalid)?;
+            let prefix = prefix.trim().trim_end_matches('/');
+            if !prefix.starts_with('/') {
+                return Err(invalid());
+            }
+            let per_minute = limit
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|v| *v > 0)
+                .ok_or_else(invalid)?;
+            Ok(RouteRateLimit {
+                prefix: prefix.to_string(),
+                per_minute,
+            })
+        })
+        .collect()
+}
+
+/// Parse `WINDOW_AVERAGING`, a comma-separated list of `window=method`
+/// pairs such as `medium_term=operation_weighted,long_term=exponential:0.2`.
+/// Methods are `simple`, `operation_weighted` and `exponential:<factor>`.
+fn parse_window_averaging(raw: &str) -> Result<Vec<(String, AveragingMethod)>, String> {
+    let windows = InsightsConfig::default().time_windows;
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let invalid = || format!("Invalid WINDOW_AVERAGING entry: {}", entry);
+            let (window, method) = entry.split_once('=').ok_or_else(invalid)?;
+            let window = window.trim();
+            if !windows.iter().any(|w| w.name == window) {
+                return Err(format!("Unknown WINDOW_AVERAGING window: {}", window));
+            }
+            let method = match method.trim().split_once(':') {
+                None if method.trim() == "simple" => AveragingMethod::Simple,
+                None if method.trim() == "operation_weighted" => AveragingMethod::OperationWeighted,
+                Some(("exponential", factor)) => AveragingMethod::Exponential {
+                    smoothing_factor: factor
+                        .trim()
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|f| *f > 0.0 && *f <= 1.0)
+                        .ok_or_else(invalid)?,
+                },
+                _ => return Err(invalid()),
+            };
+            Ok((window.to_string(), method))
+        })
+        .collect()
+}
+
+/// Build bearer token validation from `JWT_*`. Returns `None` when
+/// `JWT_JWKS_URL` is unset.
+fn parse_jwt(get: &impl Fn(&str) -> Option<String>) -> Result<Option<JwtConfig>, String> {
+    let non_empty = |key: &str| {
+        get(key)
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    let Some(jwks_url) = non_empty("JWT_JWKS_URL") else {
+        return Ok(None);
+    };
+    if !jwks_url.starts_with("http://") && !jwks_url.starts_with("https://") {
+        return Err(format!("Invalid JWT_JWKS_URL: {}", jwks_url));
+    }
+    let issuer = non_empty("JWT_ISSUER")
+        .ok_or_else(|| "JWT_ISSUER is required when JWT_JWKS_URL is set".to_string())?;
+
+    Ok(Some(JwtConfig {
+        issuer,
+        jwks_url,
+        audience: non_empty("JWT_AUDIENCE"),
+        read_scope: non_empty("JWT_READ_SCOPE").unwrap_or_else(|| "fees:read".to_string()),
+        admin_scope: non_empty("JWT_ADMIN_SCOPE").unwrap_or_else(|| "fees:admin".to_string()),
+    }))
+}
+
 fn parse_spike_severity(value: &str) -> Result<SpikeSeverity, String> {
     match value.trim().to_ascii_lowercase().as_str() {
         "minor" => Ok(SpikeSeverity::Minor),
@@ -195,6 +851,8 @@ mod tests {
             network: Some(network.to_string()),
             horizon_url: horizon_url.map(str::to_string),
             poll_interval: Some(30),
+            backfill_from_ledger: None,
+            backfill_to_ledger: None,
         }
     }
 
@@ -252,6 +910,71 @@ mod tests {
         assert!(result.unwrap_err().contains("Invalid STELLAR_NETWORK"));
     }
 
+    #[test]
+    fn futurenet_and_pubnet_alias_are_accepted() {
+        let config =
+            Config::from_sources_with_overrides(&make_cli("futurenet", None), &no_env()).unwrap();
+        assert_eq!(config.stellar_network, StellarNetwork::Futurenet);
+        assert_eq!(config.horizon_url, "https://horizon-futurenet.stellar.org");
+
+        let config =
+            Config::from_sources_with_overrides(&make_cli("pubnet", None), &no_env()).unwrap();
+        assert_eq!(config.stellar_network, StellarNetwork::Mainnet);
+    }
+
+    #[test]
+    fn additional_networks_default_to_empty() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert!(config.additional_networks.is_empty());
+    }
+
+    #[test]
+    fn additional_networks_skip_primary_and_duplicates() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("ADDITIONAL_NETWORKS", "pubnet, testnet,futurenet,mainnet")]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(
+            config.additional_networks,
+            vec![StellarNetwork::Mainnet, StellarNetwork::Futurenet]
+        );
+    }
+
+    #[test]
+    fn invalid_additional_network_returns_error() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("ADDITIONAL_NETWORKS", "devnet")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("ADDITIONAL_NETWORKS"));
+    }
+
+    #[test]
+    fn circuit_breaker_defaults() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert_eq!(config.circuit_breaker_threshold, 5);
+        assert_eq!(config.circuit_breaker_open_seconds, 30);
+    }
+
+    #[test]
+    fn circuit_breaker_can_be_disabled() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("CIRCUIT_BREAKER_THRESHOLD", "0")]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(config.circuit_breaker_threshold, 0);
+    }
+
+    #[test]
+    fn backfill_parallelism_defaults_to_four_and_rejects_zero() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert_eq!(config.backfill_parallelism, 4);
+
+        let env = HashMap::from([("BACKFILL_PARALLELISM", "0")]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(config.backfill_parallelism, 4);
+    }
+
     #[test]
     fn api_port_defaults_to_8080() {
         let cli = make_cli("testnet", None);
@@ -259,6 +982,32 @@ mod tests {
         assert_eq!(config.api_port, 8080);
     }
 
+    #[test]
+    #[cfg(not(feature = "grpc-server"))]
+    fn grpc_port_requires_feature() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert_eq!(config.grpc_port, None);
+
+        let env = HashMap::from([("GRPC_PORT", "50051")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("`grpc-server` feature"));
+    }
+
+    #[test]
+    #[cfg(feature = "grpc-server")]
+    fn grpc_port_must_be_a_free_port() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("GRPC_PORT", "50051")]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(config.grpc_port, Some(50051));
+
+        for port in ["8080", "grpc"] {
+            let env = HashMap::from([("GRPC_PORT", port)]);
+            assert!(Config::from_sources_with_overrides(&cli, &env).is_err());
+        }
+    }
+
     #[test]
     fn cache_ttl_defaults_to_five_seconds() {
         let cli = make_cli("testnet", None);
@@ -304,6 +1053,75 @@ mod tests {
         assert_eq!(config.rate_limit_per_minute, 60);
     }
 
+    #[test]
+    fn route_rate_limits_parse_prefix_limit_pairs() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert!(config.route_rate_limits.is_empty());
+
+        let env = HashMap::from([("RATE_LIMIT_ROUTES", "/admin/=10, /fees/history=20")]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(
+            config.route_rate_limits,
+            vec![
+                RouteRateLimit {
+                    prefix: "/admin".to_string(),
+                    per_minute: 10
+                },
+                RouteRateLimit {
+                    prefix: "/fees/history".to_string(),
+                    per_minute: 20
+                },
+            ]
+        );
+
+        for invalid in ["/admin", "admin=10", "/admin=0"] {
+            let env = HashMap::from([("RATE_LIMIT_ROUTES", invalid)]);
+            assert!(Config::from_sources_with_overrides(&cli, &env).is_err());
+        }
+    }
+
+    #[test]
+    fn window_averaging_parses_window_method_pairs() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert!(config.window_averaging.is_empty());
+
+        let env = HashMap::from([(
+            "WINDOW_AVERAGING",
+            "medium_term=operation_weighted, long_term = exponential:0.2,short_term=simple",
+        )]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(
+            config.window_averaging,
+            vec![
+                (
+                    "medium_term".to_string(),
+                    AveragingMethod::OperationWeighted
+                ),
+                (
+                    "long_term".to_string(),
+                    AveragingMethod::Exponential {
+                        smoothing_factor: 0.2
+                    }
+                ),
+                ("short_term".to_string(), AveragingMethod::Simple),
+            ]
+        );
+
+        for invalid in [
+            "medium_term",
+            "weekly=simple",
+            "long_term=median",
+            "long_term=exponential",
+            "long_term=exponential:0",
+            "long_term=exponential:1.5",
+        ] {
+            let env = HashMap::from([("WINDOW_AVERAGING", invalid)]);
+            assert!(Config::from_sources_with_overrides(&cli, &env).is_err());
+        }
+    }
+
     #[test]
     fn api_key_reads_from_env() {
         let cli = make_cli("testnet", None);
@@ -320,6 +1138,32 @@ mod tests {
         assert!(config.api_key.is_none());
     }
 
+    #[test]
+    fn jwt_is_configured_from_jwks_url_and_issuer() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert!(config.jwt.is_none());
+
+        let env = HashMap::from([
+            (
+                "JWT_JWKS_URL",
+                "https://auth.example.com/.well-known/jwks.json",
+            ),
+            ("JWT_ISSUER", "https://auth.example.com/"),
+        ]);
+        let jwt = Config::from_sources_with_overrides(&cli, &env)
+            .unwrap()
+            .jwt
+            .unwrap();
+        assert_eq!(jwt.issuer, "https://auth.example.com/");
+        assert_eq!(jwt.audience, None);
+        assert_eq!(jwt.read_scope, "fees:read");
+        assert_eq!(jwt.admin_scope, "fees:admin");
+
+        let env = HashMap::from([("JWT_JWKS_URL", "https://auth.example.com/jwks")]);
+        assert!(Config::from_sources_with_overrides(&cli, &env).is_err());
+    }
+
     #[test]
     fn webhook_url_defaults_to_none() {
         let cli = make_cli("testnet", None);
@@ -362,6 +1206,229 @@ mod tests {
         assert!(result.unwrap_err().contains("Invalid ALERT_THRESHOLD"));
     }
 
+    #[test]
+    fn horizon_fallback_urls_default_to_empty() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert!(config.horizon_fallback_urls.is_empty());
+    }
+
+    #[test]
+    fn horizon_fallback_urls_are_parsed_and_skip_primary() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([(
+            "HORIZON_FALLBACK_URLS",
+            "https://mirror-a.example.com, https://horizon-testnet.stellar.org,,https://mirror-b.example.com",
+        )]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(
+            config.horizon_fallback_urls,
+            vec![
+                "https://mirror-a.example.com".to_string(),
+                "https://mirror-b.example.com".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn provider_cache_ttl_defaults_to_disabled() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert_eq!(config.provider_cache_ttl_seconds, 0);
+    }
+
+    #[test]
+    fn provider_cache_ttl_is_parsed() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("PROVIDER_CACHE_TTL_SECONDS", "15")]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(config.provider_cache_ttl_seconds, 15);
+    }
+
+    #[test]
+    fn horizon_timeouts_default_and_parse() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert_eq!(
+            config.horizon_timeouts.request_deadline,
+            chrono::Duration::seconds(30)
+        );
+
+        let env = HashMap::from([
+            ("HORIZON_CONNECT_TIMEOUT_SECONDS", "2"),
+            ("HORIZON_READ_TIMEOUT_SECONDS", "0"),
+            ("HORIZON_REQUEST_DEADLINE_SECONDS", "-1"),
+        ]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(
+            config.horizon_timeouts.connect_timeout,
+            chrono::Duration::seconds(2)
+        );
+        assert_eq!(
+            config.horizon_timeouts.read_timeout,
+            chrono::Duration::zero()
+        );
+        // Invalid values fall back to the default.
+        assert_eq!(
+            config.horizon_timeouts.request_deadline,
+            chrono::Duration::seconds(30)
+        );
+    }
+
+    #[test]
+    fn horizon_transport_is_parsed() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert_eq!(config.horizon_transport, HorizonTransportConfig::default());
+
+        let env = HashMap::from([
+            ("HORIZON_PROXY_URL", "http://proxy.corp:3128"),
+            ("HORIZON_CA_BUNDLE_PATH", "/etc/ssl/corp-ca.pem"),
+            ("HORIZON_TLS_INSECURE", "true"),
+        ]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(
+            config.horizon_transport.proxy_url.as_deref(),
+            Some("http://proxy.corp:3128")
+        );
+        assert_eq!(
+            config.horizon_transport.ca_bundle_path.as_deref(),
+            Some("/etc/ssl/corp-ca.pem")
+        );
+        assert!(config.horizon_transport.accept_invalid_certs);
+    }
+
+    #[test]
+    fn dedup_window_defaults_to_ten_minutes() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert_eq!(config.dedup_window_seconds, 600);
+
+        let env = HashMap::from([("DEDUP_WINDOW_SECONDS", "0")]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(config.dedup_window_seconds, 0);
+    }
+
+    #[test]
+    fn fee_accounts_are_tracked_only_when_enabled() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert!(!config.track_fee_accounts);
+
+        let env = HashMap::from([("TRACK_FEE_ACCOUNTS", "true")]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert!(config.track_fee_accounts);
+    }
+
+    #[test]
+    fn fee_provider_defaults_to_horizon() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert_eq!(config.fee_provider, FeeProviderKind::Horizon);
+    }
+
+    #[test]
+    fn soroban_provider_on_testnet_uses_default_rpc_url() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("FEE_PROVIDER", "soroban")]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(config.fee_provider, FeeProviderKind::SorobanRpc);
+        assert_eq!(
+            config.soroban_rpc_url.as_deref(),
+            Some("https://soroban-testnet.stellar.org")
+        );
+    }
+
+    #[test]
+    fn soroban_provider_on_mainnet_requires_rpc_url() {
+        let cli = make_cli("mainnet", None);
+        let env = HashMap::from([("FEE_PROVIDER", "soroban")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("SOROBAN_RPC_URL is required"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "captive-core"))]
+    fn captive_core_provider_requires_feature() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([
+            ("FEE_PROVIDER", "captive-core"),
+            ("CAPTIVE_CORE_META_PATH", "/tmp/meta.pipe"),
+        ]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("`captive-core` feature"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "grpc"))]
+    fn grpc_provider_requires_feature() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([
+            ("FEE_PROVIDER", "grpc"),
+            ("GRPC_COLLECTOR_URL", "http://collector:50051"),
+        ]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("`grpc` feature"));
+    }
+
+    #[test]
+    #[cfg(feature = "grpc")]
+    fn grpc_provider_requires_collector_url() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("BACKFILL_PROVIDER", "grpc")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("GRPC_COLLECTOR_URL"));
+
+        let env = HashMap::from([
+            ("FEE_PROVIDER", "grpc"),
+            ("GRPC_COLLECTOR_URL", "http://collector:50051"),
+        ]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(config.fee_provider, FeeProviderKind::Grpc);
+    }
+
+    #[test]
+    #[cfg(feature = "captive-core")]
+    fn captive_core_provider_requires_meta_path() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("FEE_PROVIDER", "captive-core")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("CAPTIVE_CORE_META_PATH"));
+    }
+
+    #[test]
+    fn invalid_fee_provider_returns_error() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("FEE_PROVIDER", "bigquery")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("Invalid FEE_PROVIDER"));
+    }
+
+    #[test]
+    fn ingestion_mode_defaults_to_poll() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert_eq!(config.ingestion_mode, IngestionMode::Poll);
+    }
+
+    #[test]
+    fn ingestion_mode_stream_is_parsed() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("INGESTION_MODE", "stream")]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(config.ingestion_mode, IngestionMode::Stream);
+    }
+
+    #[test]
+    fn stream_mode_with_soroban_provider_returns_error() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("INGESTION_MODE", "stream"), ("FEE_PROVIDER", "soroban")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result
+            .unwrap_err()
+            .contains("requires FEE_PROVIDER=horizon"));
+    }
+
     #[test]
     fn allowed_origins_defaults_to_localhost_3000() {
         let cli = make_cli("testnet", None);
@@ -396,4 +1463,240 @@ mod tests {
             vec!["http://localhost:3000", "https://app.example.com"]
         );
     }
+
+    #[test]
+    fn cors_settings_default_and_override() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert_eq!(
+            config.cors_allowed_methods,
+            vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"]
+        );
+        assert_eq!(
+            config.cors_allowed_headers,
+            vec![
+                "content-type",
+                "x-api-key",
+                "authorization",
+                "x-request-id",
+                "traceparent"
+            ]
+        );
+        assert!(!config.cors_allow_credentials);
+        assert_eq!(config.cors_max_age_seconds, 3600);
+
+        let env = HashMap::from([
+            ("CORS_ALLOWED_METHODS", "get, post"),
+            ("CORS_ALLOWED_HEADERS", "x-api-key"),
+            ("CORS_ALLOW_CREDENTIALS", "true"),
+            ("CORS_MAX_AGE_SECONDS", "60"),
+        ]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(config.cors_allowed_methods, vec!["GET", "POST"]);
+        assert_eq!(config.cors_allowed_headers, vec!["x-api-key"]);
+        assert!(config.cors_allow_credentials);
+        assert_eq!(config.cors_max_age_seconds, 60);
+    }
+
+    #[test]
+    fn cors_credentials_reject_any_origin() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("ALLOWED_ORIGINS", "*"), ("CORS_ALLOW_CREDENTIALS", "true")]);
+        assert!(Config::from_sources_with_overrides(&cli, &env).is_err());
+
+        let env = HashMap::from([("CORS_ALLOWED_HEADERS", "bad header")]);
+        assert!(Config::from_sources_with_overrides(&cli, &env).is_err());
+    }
+
+    #[test]
+    fn horizon_auth_is_disabled_by_default() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert!(config.horizon_auth.is_none());
+    }
+
+    #[test]
+    fn horizon_auth_token_defaults_to_authorization_header() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("HORIZON_AUTH_TOKEN", "s3cret")]);
+        let auth = Config::from_sources_with_overrides(&cli, &env)
+            .unwrap()
+            .horizon_auth
+            .unwrap();
+        assert_eq!(auth.header_name, "Authorization");
+        assert_eq!(auth.token.as_deref(), Some("s3cret"));
+        assert!(auth.basic.is_none());
+    }
+
+    #[test]
+    fn horizon_basic_auth_with_custom_token_header() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([
+            ("HORIZON_AUTH_HEADER", "X-Api-Key"),
+            ("HORIZON_AUTH_TOKEN", "s3cret"),
+            ("HORIZON_BASIC_AUTH_USERNAME", "user"),
+            ("HORIZON_BASIC_AUTH_PASSWORD", "pass"),
+        ]);
+        let auth = Config::from_sources_with_overrides(&cli, &env)
+            .unwrap()
+            .horizon_auth
+            .unwrap();
+        assert_eq!(auth.header_name, "X-Api-Key");
+        let basic = auth.basic.unwrap();
+        assert_eq!(basic.username, "user");
+        assert_eq!(basic.password.as_deref(), Some("pass"));
+    }
+
+    #[test]
+    fn horizon_auth_rejects_conflicting_authorization_credentials() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([
+            ("HORIZON_AUTH_TOKEN", "s3cret"),
+            ("HORIZON_BASIC_AUTH_USERNAME", "user"),
+        ]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("Authorization"));
+    }
+
+    #[test]
+    fn horizon_auth_rejects_invalid_header_name() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([
+            ("HORIZON_AUTH_HEADER", "bad header"),
+            ("HORIZON_AUTH_TOKEN", "s3cret"),
+        ]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("Invalid HORIZON_AUTH_HEADER"));
+    }
+
+    #[test]
+    fn file_provider_requires_replay_path() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("FEE_PROVIDER", "file")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("FEE_REPLAY_PATH"));
+    }
+
+    #[test]
+    fn file_provider_reads_path_and_speed() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([
+            ("FEE_PROVIDER", "file"),
+            ("FEE_REPLAY_PATH", "fixtures/fees.csv"),
+            ("FEE_REPLAY_SPEED", "60"),
+        ]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(config.fee_provider, FeeProviderKind::File);
+        assert_eq!(config.replay_path.as_deref(), Some("fixtures/fees.csv"));
+        assert_eq!(config.replay_speed, 60.0);
+    }
+
+    #[test]
+    fn negative_replay_speed_is_rejected() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("FEE_REPLAY_SPEED", "-1")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("Invalid FEE_REPLAY_SPEED"));
+    }
+
+    #[test]
+    fn backfill_provider_defaults_to_fee_provider() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert!(config.backfill_provider.is_none());
+    }
+
+    #[test]
+    fn invalid_backfill_provider_returns_error() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("BACKFILL_PROVIDER", "bigquery")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("Invalid BACKFILL_PROVIDER"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "hubble"))]
+    fn hubble_backfill_requires_feature() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("BACKFILL_PROVIDER", "hubble")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("`hubble` feature"));
+    }
+
+    #[test]
+    #[cfg(feature = "hubble")]
+    fn hubble_backfill_requires_project_id() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("BACKFILL_PROVIDER", "hubble")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("HUBBLE_PROJECT_ID"));
+    }
+
+    #[test]
+    #[cfg(feature = "hubble")]
+    fn hubble_cannot_serve_live_fees() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("FEE_PROVIDER", "hubble")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("BACKFILL_PROVIDER=hubble"));
+    }
+
+    #[test]
+    fn merge_providers_skip_primary_and_duplicates() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("MERGE_PROVIDERS", "soroban, horizon,soroban")]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(config.merge_providers, vec![FeeProviderKind::SorobanRpc]);
+    }
+
+    #[test]
+    fn merge_providers_reject_stream_mode() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([("MERGE_PROVIDERS", "soroban"), ("INGESTION_MODE", "stream")]);
+        let result = Config::from_sources_with_overrides(&cli, &env);
+        assert!(result.unwrap_err().contains("MERGE_PROVIDERS"));
+    }
+
+    #[test]
+    fn simulation_defaults_apply_when_unset() {
+        let cli = make_cli("testnet", None);
+        let config = Config::from_sources_with_overrides(&cli, &no_env()).unwrap();
+        assert_eq!(config.simulation, SimulationConfig::default());
+    }
+
+    #[test]
+    fn simulated_provider_reads_sim_variables() {
+        let cli = make_cli("testnet", None);
+        let env = HashMap::from([
+            ("FEE_PROVIDER", "simulated"),
+            ("SIM_BASE_FEE", "200"),
+            ("SIM_NOISE", "uniform"),
+            ("SIM_SURGE_PROBABILITY", "0.5"),
+            ("SIM_SEED", "7"),
+        ]);
+        let config = Config::from_sources_with_overrides(&cli, &env).unwrap();
+        assert_eq!(config.fee_provider, FeeProviderKind::Simulated);
+        assert_eq!(config.simulation.base_fee, 200);
+        assert_eq!(config.simulation.noise, NoiseDistribution::Uniform);
+        assert_eq!(config.simulation.surge_probability, 0.5);
+        assert_eq!(config.simulation.seed, Some(7));
+    }
+
+    #[test]
+    fn invalid_simulation_values_return_error() {
+        let cli = make_cli("testnet", None);
+        for (key, value) in [
+            ("SIM_NOISE", "gamma"),
+            ("SIM_BASE_FEE", "cheap"),
+            ("SIM_SURGE_PROBABILITY", "1.5"),
+        ] {
+            let env = HashMap::from([(key, value)]);
+            let result = Config::from_sources_with_overrides(&cli, &env);
+            assert!(
+                result.unwrap_err().contains(key),
+                "{} should be rejected",
+                key
+            );
+        }
+    }
 }
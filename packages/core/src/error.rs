@@ -2,11 +2,15 @@ use std::error::Error;
 use std::fmt;
 
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Media type of the RFC 7807 problem documents errors are answered with.
+pub const PROBLEM_JSON: &str = "application/problem+json";
 
 /// Unified application error.
 ///
@@ -17,7 +21,22 @@ pub enum AppError {
     Config(String),
     Network(String),
     Parse(String),
-    #[allow(dead_code)]
+    /// A request the client has to change before retrying.
+    BadRequest(String),
+    /// Missing or unknown credentials.
+    Unauthorized(String),
+    /// Valid credentials that may not perform the request.
+    Forbidden(String),
+    /// The requested resource does not exist.
+    NotFound(String),
+    /// A well-formed request with values that cannot be applied.
+    Unprocessable(String),
+    /// The client exceeded its rate limit.
+    TooManyRequests(String),
+    /// Temporarily unable to answer, e.g. before enough data has arrived.
+    Unavailable(String),
+    /// The database failed.
+    Storage(String),
     Unknown(String),
 }
 
@@ -27,6 +46,14 @@ impl fmt::Display for AppError {
             AppError::Config(msg) => write!(f, "Config error: {}", msg),
             AppError::Network(msg) => write!(f, "Network error: {}", msg),
             AppError::Parse(msg) => write!(f, "Parse error: {}", msg),
+            AppError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
+            AppError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            AppError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
+            AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            AppError::Unprocessable(msg) => write!(f, "Unprocessable: {}", msg),
+            AppError::TooManyRequests(msg) => write!(f, "Too many requests: {}", msg),
+            AppError::Unavailable(msg) => write!(f, "Unavailable: {}", msg),
+            AppError::Storage(msg) => write!(f, "Storage error: {}", msg),
             AppError::Unknown(msg) => write!(f, "Unknown error: {}", msg),
         }
     }
@@ -34,18 +61,167 @@ impl fmt::Display for AppError {
 
 impl Error for AppError {}
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let status = match &self {
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        AppError::Storage(err.to_string())
+    }
+}
+
+/// Stable, machine-readable error codes.
+///
+/// Clients branch on these rather than on the human-readable `detail`;
+/// a code is never renamed once released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    ConfigError,
+    UpstreamUnavailable,
+    InvalidUpstreamResponse,
+    InvalidRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    UnprocessableRequest,
+    TooManyRequests,
+    ServiceUnavailable,
+    StorageError,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::ConfigError => "config_error",
+            ErrorCode::UpstreamUnavailable => "upstream_unavailable",
+            ErrorCode::InvalidUpstreamResponse => "invalid_upstream_response",
+            ErrorCode::InvalidRequest => "invalid_request",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::UnprocessableRequest => "unprocessable_request",
+            ErrorCode::TooManyRequests => "too_many_requests",
+            ErrorCode::ServiceUnavailable => "service_unavailable",
+            ErrorCode::StorageError => "storage_error",
+            ErrorCode::InternalError => "internal_error",
+        }
+    }
+
+    /// Short summary that does not change between occurrences.
+    pub fn title(self) -> &'static str {
+        match self {
+            ErrorCode::ConfigError => "Server misconfigured",
+            ErrorCode::UpstreamUnavailable => "Upstream service unavailable",
+            ErrorCode::InvalidUpstreamResponse => "Invalid upstream response",
+            ErrorCode::InvalidRequest => "Invalid request",
+            ErrorCode::Unauthorized => "Unauthorized",
+            ErrorCode::Forbidden => "Forbidden",
+            ErrorCode::NotFound => "Not found",
+            ErrorCode::UnprocessableRequest => "Unprocessable request",
+            ErrorCode::TooManyRequests => "Too many requests",
+            ErrorCode::ServiceUnavailable => "Service unavailable",
+            ErrorCode::StorageError => "Storage error",
+            ErrorCode::InternalError => "Internal error",
+        }
+    }
+
+    /// The problem `type` URI; a URN, so it is not expected to resolve.
+    pub fn type_uri(self) -> String {
+        format!("urn:stellar-fee-tracker:problem:{}", self.as_str())
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AppError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::Config(_) => ErrorCode::ConfigError,
+            AppError::Network(_) => ErrorCode::UpstreamUnavailable,
+            AppError::Parse(_) => ErrorCode::InvalidUpstreamResponse,
+            AppError::BadRequest(_) => ErrorCode::InvalidRequest,
+            AppError::Unauthorized(_) => ErrorCode::Unauthorized,
+            AppError::Forbidden(_) => ErrorCode::Forbidden,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::Unprocessable(_) => ErrorCode::UnprocessableRequest,
+            AppError::TooManyRequests(_) => ErrorCode::TooManyRequests,
+            AppError::Unavailable(_) => ErrorCode::ServiceUnavailable,
+            AppError::Storage(_) => ErrorCode::StorageError,
+            AppError::Unknown(_) => ErrorCode::InternalError,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
             AppError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Network(_) => StatusCode::BAD_GATEWAY,
             AppError::Parse(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        }
+    }
 
-        let body = Json(json!({ "error": self.to_string() }));
+    fn detail(&self) -> &str {
+        match self {
+            AppError::Config(msg)
+            | AppError::Network(msg)
+            | AppError::Parse(msg)
+            | AppError::BadRequest(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::NotFound(msg)
+            | AppError::Unprocessable(msg)
+            | AppError::TooManyRequests(msg)
+            | AppError::Unavailable(msg)
+            | AppError::Storage(msg)
+            | AppError::Unknown(msg) => msg,
+        }
+    }
+}
+
+impl AppError {
+    /// The RFC 7807 problem document describing this error.
+    pub fn problem(&self) -> Value {
+        let code = self.code();
+        json!({
+            "type": code.type_uri(),
+            "title": code.title(),
+            "status": self.status().as_u16(),
+            "detail": self.detail(),
+            "code": code,
+        })
+    }
+
+    /// The problem document, with the members of `extensions` added to it.
+    pub fn into_response_with(self, extensions: Value) -> Response {
+        let mut body = self.problem();
+        if let (Some(body), Value::Object(extensions)) = (body.as_object_mut(), extensions) {
+            for (name, value) in extensions {
+                body.entry(name).or_insert(value);
+            }
+        }
 
-        (status, body).into_response()
+        let mut response = (self.status(), Json(body)).into_response();
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        response
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        self.into_response_with(Value::Null)
     }
 }
 
@@ -83,6 +259,110 @@ mod tests {
         );
     }
 
+    #[test]
+    fn bad_request_error_returns_400() {
+        assert_eq!(
+            status_of(AppError::BadRequest("no such urgency".into())),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn unauthorized_error_returns_401() {
+        assert_eq!(
+            status_of(AppError::Unauthorized("no key".into())),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[test]
+    fn forbidden_error_returns_403() {
+        assert_eq!(
+            status_of(AppError::Forbidden("read-only key".into())),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[test]
+    fn not_found_error_returns_404() {
+        assert_eq!(
+            status_of(AppError::NotFound("no such job".into())),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn too_many_requests_error_returns_429() {
+        assert_eq!(
+            status_of(AppError::TooManyRequests("slow down".into())),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[test]
+    fn unavailable_error_returns_503() {
+        assert_eq!(
+            status_of(AppError::Unavailable("not enough data".into())),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[tokio::test]
+    async fn extensions_are_added_without_replacing_standard_members() {
+        let response = AppError::TooManyRequests("slow down".into())
+            .into_response_with(json!({ "retry_after_seconds": 3, "status": 200 }));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["code"], "too_many_requests");
+        assert_eq!(problem["status"], 429);
+        assert_eq!(problem["retry_after_seconds"], 3);
+    }
+
+    #[tokio::test]
+    async fn responses_are_problem_documents() {
+        let response = AppError::Network("timeout".into()).into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            problem,
+            json!({
+                "type": "urn:stellar-fee-tracker:problem:upstream_unavailable",
+                "title": "Upstream service unavailable",
+                "status": 502,
+                "detail": "timeout",
+                "code": "upstream_unavailable",
+            })
+        );
+    }
+
+    #[test]
+    fn codes_serialize_as_their_string_form() {
+        for code in [
+            ErrorCode::ConfigError,
+            ErrorCode::UpstreamUnavailable,
+            ErrorCode::InvalidUpstreamResponse,
+            ErrorCode::InvalidRequest,
+            ErrorCode::Unauthorized,
+            ErrorCode::Forbidden,
+            ErrorCode::NotFound,
+            ErrorCode::UnprocessableRequest,
+            ErrorCode::TooManyRequests,
+            ErrorCode::ServiceUnavailable,
+            ErrorCode::StorageError,
+            ErrorCode::InternalError,
+        ] {
+            assert_eq!(serde_json::to_value(code).unwrap(), code.as_str());
+        }
+    }
+
     #[test]
     fn unknown_error_returns_500() {
         assert_eq!(
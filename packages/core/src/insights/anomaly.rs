@@ -0,0 +1,160 @@
+//! Statistical anomaly detection on the fee stream
+//!
+//! Scores fees with a robust z-score: distance from the median of recent
+//! fees in units of the scaled median absolute deviation (MAD). Anomalies
+//! are reported on their own and never feed congestion detection, so a
+//! single odd transaction cannot raise a congestion alarm.
+
+use std::collections::VecDeque;
+
+use crate::insights::{config::AnomalyConfig, types::*};
+
+/// Makes the MAD a consistent estimator of the standard deviation for
+/// normally distributed data.
+const MAD_SCALE: f64 = 1.4826;
+
+/// Flags fees and batches that deviate from recent history
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    /// Most recent fees, oldest first.
+    history: VecDeque<u64>,
+}
+
+impl AnomalyDetector {
+    /// Create a new anomaly detector
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            history: VecDeque::with_capacity(config.history_size),
+            config,
+        }
+    }
+
+    /// Score a batch against the history seen before it, then add it to the
+    /// history. Nothing is flagged until `min_samples` fees have been seen.
+    pub fn observe(&mut self, fees: &[FeeDataPoint]) -> AnomalyReport {
+        let report = self.score(fees).unwrap_or_default();
+
+        for point in fees {
+            if self.history.len() >= self.config.history_size {
+                self.history.pop_front();
+            }
+            self.history.push_back(point.fee_amount);
+        }
+
+        report
+    }
+
+    fn score(&self, fees: &[FeeDataPoint]) -> Option<AnomalyReport> {
+        if fees.is_empty() || self.history.len() < self.config.min_samples {
+            return None;
+        }
+
+        let mut history: Vec<f64> = self.history.iter().map(|&fee| fee as f64).collect();
+        let median_fee = median(&mut history);
+        let mut deviations: Vec<f64> = history.iter().map(|fee| (fee - median_fee).abs()).collect();
+        // Flat histories (every fee at the base fee) have no spread; the
+        // floor keeps tiny deviations from scoring as anomalies.
+        let scale = (median(&mut deviations) * MAD_SCALE)
+            .max(median_fee * self.config.min_relative_scale)
+            .max(f64::EPSILON);
+        let robust_z = |fee: f64| (fee - median_fee) / scale;
+
+        let anomalies = fees
+            .iter()
+            .filter_map(|point| {
+                let score = robust_z(point.fee_amount as f64);
+                (score.abs() > self.config.score_threshold).then(|| FeeAnomaly {
+                    transaction_hash: point.transaction_hash.clone(),
+                    fee_amount: point.fee_amount,
+                    timestamp: point.timestamp,
+                    score,
+                    median_fee,
+                })
+            })
+            .collect();
+
+        let mut batch: Vec<f64> = fees.iter().map(|point| point.fee_amount as f64).collect();
+        let window_score = robust_z(median(&mut batch));
+
+        Some(AnomalyReport {
+            is_anomaly: window_score.abs() > self.config.score_threshold,
+            window_score: Some(window_score),
+            anomalies,
+        })
+    }
+}
+
+/// Median of `values`, which must not be empty. Sorts in place.
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn points(fees: &[u64]) -> Vec<FeeDataPoint> {
+        fees.iter()
+            .enumerate()
+            .map(|(i, &fee_amount)| FeeDataPoint {
+                fee_amount,
+                timestamp: Utc::now(),
+                transaction_hash: format!("hash_{}_{}", i, fee_amount),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect()
+    }
+
+    fn warmed_up() -> AnomalyDetector {
+        let mut detector = AnomalyDetector::new(AnomalyConfig::default());
+        let history: Vec<u64> = (0..50).map(|i| 100 + (i % 5) * 10).collect();
+        assert_eq!(
+            detector.observe(&points(&history)),
+            AnomalyReport::default()
+        );
+        detector
+    }
+
+    #[test]
+    fn single_outlier_is_flagged_without_flagging_the_window() {
+        let mut detector = warmed_up();
+
+        let report = detector.observe(&points(&[110, 120, 5_000, 100, 130]));
+
+        assert_eq!(report.anomalies.len(), 1);
+        assert_eq!(report.anomalies[0].fee_amount, 5_000);
+        assert_eq!(report.anomalies[0].median_fee, 120.0);
+        assert!(report.anomalies[0].score > 3.5);
+        assert!(!report.is_anomaly);
+    }
+
+    #[test]
+    fn shifted_batch_flags_the_window() {
+        let mut detector = warmed_up();
+
+        let report = detector.observe(&points(&[900, 1_000, 1_100]));
+
+        assert!(report.is_anomaly);
+        assert_eq!(report.anomalies.len(), 3);
+    }
+
+    #[test]
+    fn flat_history_tolerates_small_deviations() {
+        let mut detector = AnomalyDetector::new(AnomalyConfig::default());
+        detector.observe(&points(&[100; 40]));
+
+        let report = detector.observe(&points(&[101, 110, 1_000]));
+
+        assert_eq!(report.anomalies.len(), 1);
+        assert_eq!(report.anomalies[0].fee_amount, 1_000);
+    }
+}
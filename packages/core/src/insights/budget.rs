@@ -0,0 +1,218 @@
+//! Per-account fee budgets
+//!
+//! Accounts registered with a daily or weekly budget have their fees summed
+//! as batches arrive, keyed by the account that paid them. Spend resets when
+//! a new period starts, and crossing the budget is reported once per period.
+
+use chrono::{DateTime, Utc};
+
+use crate::insights::types::*;
+
+/// A budget and what its account spent in the current period
+#[derive(Debug, Clone)]
+struct TrackedBudget {
+    budget: FeeBudget,
+    period_start: DateTime<Utc>,
+    spent: u64,
+    /// Fees before this were counted when the budget was registered.
+    counted_from: DateTime<Utc>,
+    alerted: bool,
+}
+
+impl TrackedBudget {
+    fn status(&self, period_start: DateTime<Utc>, spent: u64) -> BudgetStatus {
+        let limit = self.budget.limit;
+        BudgetStatus {
+            account: self.budget.account.clone(),
+            period: self.budget.period,
+            period_start,
+            limit,
+            spent,
+            remaining: limit.saturating_sub(spent),
+            utilization: if limit > 0 {
+                spent as f64 / limit as f64
+            } else {
+                0.0
+            },
+            exceeded: spent > limit,
+        }
+    }
+}
+
+/// Tracks spend against every registered fee budget
+#[derive(Debug, Clone, Default)]
+pub struct BudgetTracker {
+    budgets: Vec<TrackedBudget>,
+}
+
+impl BudgetTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `budget`, replacing any with the same account and period.
+    /// `spent` is what the account already paid in the period containing
+    /// `as_of`; only fees from `as_of` on are counted from then.
+    pub fn set(&mut self, budget: FeeBudget, spent: u64, as_of: DateTime<Utc>) {
+        self.remove(&budget.account, budget.period);
+        self.budgets.push(TrackedBudget {
+            period_start: budget.period.start(as_of),
+            alerted: spent > budget.limit,
+            budget,
+            spent,
+            counted_from: as_of,
+        });
+    }
+
+    /// Stop tracking a budget. Returns `false` when none was registered.
+    pub fn remove(&mut self, account: &str, period: BudgetPeriod) -> bool {
+        let before = self.budgets.len();
+        self.budgets
+            .retain(|b| !(b.budget.account == account && b.budget.period == period));
+        self.budgets.len() != before
+    }
+
+    /// Count each point's fee against its account's budgets and return the
+    /// budgets this batch pushed over their limit.
+    pub fn observe(&mut self, data: &[FeeDataPoint]) -> Vec<BudgetStatus> {
+        if self.budgets.is_empty() {
+            return Vec::new();
+        }
+
+        for point in data {
+            let Some(account) = point
+                .envelope
+                .as_ref()
+                .and_then(|e| e.fee_account.as_deref())
+            else {
+                continue;
+            };
+            for tracked in self
+                .budgets
+                .iter_mut()
+                .filter(|b| b.budget.account == account && point.timestamp >= b.counted_from)
+            {
+                let period_start = tracked.budget.period.start(point.timestamp);
+                if period_start > tracked.period_start {
+                    tracked.period_start = period_start;
+                    tracked.spent = 0;
+                    tracked.alerted = false;
+                } else if period_start < tracked.period_start {
+                    continue;
+                }
+                tracked.spent += point.fee_amount;
+            }
+        }
+
+        self.budgets
+            .iter_mut()
+            .filter(|b| b.spent > b.budget.limit && !b.alerted)
+            .map(|b| {
+                b.alerted = true;
+                b.status(b.period_start, b.spent)
+            })
+            .collect()
+    }
+
+    /// Spend against every budget as of `now`, by account then period.
+    pub fn statuses(&self, now: DateTime<Utc>) -> Vec<BudgetStatus> {
+        let mut statuses: Vec<BudgetStatus> = self
+            .budgets
+            .iter()
+            .map(|b| {
+                let period_start = b.budget.period.start(now);
+                if period_start > b.period_start {
+                    b.status(period_start, 0)
+                } else {
+                    b.status(b.period_start, b.spent)
+                }
+            })
+            .collect();
+        statuses.sort_by(|a, b| {
+            a.account
+                .cmp(&b.account)
+                .then_with(|| a.period.as_str().cmp(b.period.as_str()))
+        });
+        statuses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        // 2024-01-15 is a Monday
+        Utc.with_ymd_and_hms(2024, 1, day, hour, 0, 0).unwrap()
+    }
+
+    fn paid_by(account: &str, fee_amount: u64, timestamp: DateTime<Utc>) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp,
+            transaction_hash: format!("tx_{}_{}", account, timestamp.timestamp()),
+            ledger_sequence: 1,
+            envelope: Some(EnvelopeDetails {
+                fee_account: Some(account.to_string()),
+                ..Default::default()
+            }),
+            soroban: None,
+        }
+    }
+
+    fn budget(account: &str, period: BudgetPeriod, limit: u64) -> FeeBudget {
+        FeeBudget {
+            account: account.to_string(),
+            period,
+            limit,
+        }
+    }
+
+    #[test]
+    fn periods_start_at_midnight_and_on_mondays() {
+        assert_eq!(BudgetPeriod::Daily.start(at(17, 13)), at(17, 0));
+        assert_eq!(BudgetPeriod::Weekly.start(at(17, 13)), at(15, 0));
+        assert_eq!(BudgetPeriod::Weekly.start(at(15, 0)), at(15, 0));
+        assert_eq!(BudgetPeriod::parse("weekly"), Some(BudgetPeriod::Weekly));
+        assert_eq!(BudgetPeriod::parse("monthly"), None);
+    }
+
+    #[test]
+    fn reports_each_budget_once_per_period_it_is_exceeded() {
+        let mut tracker = BudgetTracker::new();
+        tracker.set(budget("GA", BudgetPeriod::Daily, 1_000), 400, at(15, 8));
+        tracker.set(budget("GA", BudgetPeriod::Weekly, 5_000), 400, at(15, 8));
+
+        // Fees before registration were already counted; other payers never are
+        let crossed = tracker.observe(&[
+            paid_by("GA", 300, at(15, 7)),
+            paid_by("GA", 500, at(15, 9)),
+            paid_by("GB", 9_000, at(15, 9)),
+        ]);
+        assert!(crossed.is_empty());
+
+        let crossed = tracker.observe(&[paid_by("GA", 200, at(15, 10))]);
+        assert_eq!(crossed.len(), 1);
+        assert_eq!(crossed[0].period, BudgetPeriod::Daily);
+        assert_eq!((crossed[0].spent, crossed[0].remaining), (1_100, 0));
+        assert!(crossed[0].exceeded);
+        assert!(tracker
+            .observe(&[paid_by("GA", 100, at(15, 11))])
+            .is_empty());
+
+        // A new day resets the daily budget but not the weekly one
+        tracker.observe(&[paid_by("GA", 100, at(16, 1))]);
+        let statuses = tracker.statuses(at(16, 2));
+        assert_eq!(statuses[0].period, BudgetPeriod::Daily);
+        assert_eq!((statuses[0].spent, statuses[0].exceeded), (100, false));
+        assert_eq!(statuses[1].spent, 1_300);
+        assert!((statuses[1].utilization - 0.26).abs() < 1e-9);
+
+        // Periods with no fees yet read as unspent
+        assert_eq!(tracker.statuses(at(22, 0))[1].spent, 0);
+        assert!(tracker.remove("GA", BudgetPeriod::Daily));
+        assert!(!tracker.remove("GA", BudgetPeriod::Daily));
+        assert_eq!(tracker.statuses(at(16, 2)).len(), 1);
+    }
+}
@@ -0,0 +1,194 @@
+//! Caching Fee Data Provider
+//!
+//! Decorator that serves `fetch_latest_fees` from memory for a fixed TTL, so
+//! several consumers polling the same provider share one upstream request.
+
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::insights::{
+    provider::{FeeDataProvider, ProviderMetadata, ProviderResult, ProviderStatus},
+    types::{FeeDataPoint, FeeStatsSnapshot, LedgerInfo},
+};
+
+/// Last successful response and when it was fetched.
+struct CacheEntry {
+    fetched_at: Instant,
+    points: Vec<FeeDataPoint>,
+}
+
+/// Wraps a provider and caches successful `fetch_latest_fees` results.
+///
+/// The cache lock is held across the upstream call, so concurrent callers
+/// that miss the cache wait for the in-flight request instead of issuing
+/// their own. Errors are never cached: the next caller retries upstream.
+pub struct CachedProvider<P: FeeDataProvider> {
+    inner: P,
+    ttl: Duration,
+    entry: Mutex<Option<CacheEntry>>,
+}
+
+impl<P: FeeDataProvider> CachedProvider<P> {
+    pub fn new(inner: P, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            entry: Mutex::new(None),
+        }
+    }
+
+    /// Drop the cached response so the next fetch goes upstream.
+    #[allow(dead_code)]
+    pub async fn invalidate(&self) {
+        *self.entry.lock().await = None;
+    }
+}
+
+#[async_trait]
+impl<P: FeeDataProvider + Send + Sync> FeeDataProvider for CachedProvider<P> {
+    async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        let mut entry = self.entry.lock().await;
+
+        if let Some(cached) = entry.as_ref() {
+            if cached.fetched_at.elapsed() < self.ttl {
+                return Ok(cached.points.clone());
+            }
+        }
+
+        let points = self.inner.fetch_latest_fees().await?;
+        *entry = Some(CacheEntry {
+            fetched_at: Instant::now(),
+            points: points.clone(),
+        });
+        Ok(points)
+    }
+
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        self.inner.fetch_fees_range(start_ledger, end_ledger).await
+    }
+
+    async fn fetch_fee_stats(&self) -> ProviderResult<FeeStatsSnapshot> {
+        self.inner.fetch_fee_stats().await
+    }
+
+    async fn fetch_recent_ledgers(&self) -> ProviderResult<Vec<LedgerInfo>> {
+        self.inner.fetch_recent_ledgers().await
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    async fn health_check(&self) -> ProviderResult<()> {
+        self.inner.health_check().await
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        self.inner.get_metadata()
+    }
+
+    async fn refresh_metadata(&self) -> ProviderResult<ProviderMetadata> {
+        self.inner.refresh_metadata().await
+    }
+
+    fn provider_status(&self) -> ProviderStatus {
+        self.inner.provider_status()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::insights::error::ProviderError;
+    use crate::services::mock_horizon::MockHorizonClient;
+    use chrono::Utc;
+    use std::sync::Arc;
+
+    fn make_fee_point(fee_amount: u64) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp: Utc::now(),
+            transaction_hash: format!("hash_{}", fee_amount),
+            ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn serves_cached_response_within_ttl() {
+        let mock = MockHorizonClient::new().with_fees(vec![make_fee_point(100)]);
+        let calls = mock.call_count.clone();
+        let cached = CachedProvider::new(mock, Duration::from_secs(10));
+
+        cached.fetch_latest_fees().await.unwrap();
+        tokio::time::advance(Duration::from_secs(5)).await;
+        let points = cached.fetch_latest_fees().await.unwrap();
+
+        assert_eq!(points[0].fee_amount, 100);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refetches_after_ttl_expires() {
+        let mock = MockHorizonClient::new().with_fees(vec![make_fee_point(100)]);
+        let calls = mock.call_count.clone();
+        let cached = CachedProvider::new(mock, Duration::from_secs(10));
+
+        cached.fetch_latest_fees().await.unwrap();
+        tokio::time::advance(Duration::from_secs(11)).await;
+        cached.fetch_latest_fees().await.unwrap();
+
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_callers_share_one_upstream_request() {
+        let mock = MockHorizonClient::new().with_fees(vec![make_fee_point(100)]);
+        let calls = mock.call_count.clone();
+        let cached = Arc::new(CachedProvider::new(mock, Duration::from_secs(10)));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let cached = cached.clone();
+                tokio::spawn(async move { cached.fetch_latest_fees().await })
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.await.unwrap().is_ok());
+        }
+
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn errors_are_not_cached() {
+        let mock = MockHorizonClient::new().with_error(ProviderError::ServiceUnavailable);
+        let calls = mock.call_count.clone();
+        let cached = CachedProvider::new(mock, Duration::from_secs(10));
+
+        assert!(cached.fetch_latest_fees().await.is_err());
+        assert!(cached.fetch_latest_fees().await.is_err());
+
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn invalidate_forces_refetch() {
+        let mock = MockHorizonClient::new().with_fees(vec![make_fee_point(100)]);
+        let calls = mock.call_count.clone();
+        let cached = CachedProvider::new(mock, Duration::from_secs(10));
+
+        cached.fetch_latest_fees().await.unwrap();
+        cached.invalidate().await;
+        cached.fetch_latest_fees().await.unwrap();
+
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+}
@@ -1,9 +1,27 @@
 //! Rolling Average Calculator
+//!
+//! Each window keeps running aggregates that are updated as points enter
+//! and leave it: sums for means, spreads, bids, Soroban resources and the
+//! trend fit, its fees counted by value overall and per category, and its
+//! exponential average. Reading a window back costs time in the number of
+//! distinct fees, or of sketch buckets with `PercentileMethod::Sketch`,
+//! never in the number of points it holds.
+//!
+//! Exactly-counted windows buffer their points to take each back out as
+//! it expires. Sketched windows keep no points: they hold aggregates per
+//! slice of their length and forget a whole slice at a time, so memory
+//! stays flat however many fees arrive.
 
 use chrono::{DateTime, Utc};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
-use crate::insights::{config::AverageConfig, error::InsightsError, types::*};
+use crate::insights::{
+    config::{AverageConfig, HistogramBins, OutlierFilter, PercentileMethod},
+    error::InsightsError,
+    sketch::FeeSketch,
+    trend::{FeeTrendAnalyzer, TrendSums},
+    types::*,
+};
 
 /// Circular buffer for efficient storage of fee data points
 #[derive(Debug, Clone)]
@@ -20,32 +38,845 @@ impl<T> CircularBuffer<T> {
         }
     }
 
-    fn push(&mut self, item: T) {
-        if self.data.len() >= self.max_size {
-            self.data.pop_front();
-        }
+    /// Append `item`, returning the oldest item if it had to make room.
+    fn push(&mut self, item: T) -> Option<T> {
+        let evicted = if self.data.len() >= self.max_size {
+            self.data.pop_front()
+        } else {
+            None
+        };
         self.data.push_back(item);
+        evicted
+    }
+
+    fn front(&self) -> Option<&T> {
+        self.data.front()
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        self.data.pop_front()
     }
 
     fn iter(&self) -> impl Iterator<Item = &T> {
         self.data.iter()
     }
+}
+
+/// A window's fees in ascending order
+#[derive(Debug, Clone)]
+enum FeeOrder {
+    /// How often each distinct fee occurs, and how many fees there are.
+    Exact {
+        counts: BTreeMap<u64, u64>,
+        len: u64,
+    },
+    Sketch(FeeSketch),
+}
+
+impl FeeOrder {
+    fn new(method: &PercentileMethod) -> Self {
+        match method {
+            PercentileMethod::Exact => Self::Exact {
+                counts: BTreeMap::new(),
+                len: 0,
+            },
+            PercentileMethod::Sketch { relative_accuracy } => {
+                Self::Sketch(FeeSketch::new(*relative_accuracy))
+            }
+        }
+    }
+
+    fn insert(&mut self, fee: u64) {
+        match self {
+            Self::Sketch(sketch) => sketch.insert(fee),
+            _ => self.insert_many(fee, 1),
+        }
+    }
+
+    /// Record `fee` `times` times over.
+    fn insert_many(&mut self, fee: u64, times: u64) {
+        match self {
+            Self::Exact { counts, len } => {
+                *counts.entry(fee).or_default() += times;
+                *len += times;
+            }
+            Self::Sketch(sketch) => sketch.insert_many(fee, times),
+        }
+    }
+
+    fn remove(&mut self, fee: u64) {
+        match self {
+            Self::Sketch(sketch) => sketch.remove(fee),
+            _ => self.remove_many(fee, 1),
+        }
+    }
+
+    /// Forget `times` earlier inserts of `fee`.
+    fn remove_many(&mut self, fee: u64, times: u64) {
+        match self {
+            Self::Exact { counts, len } => {
+                if let Some(count) = counts.get_mut(&fee) {
+                    let times = times.min(*count);
+                    *count -= times;
+                    *len -= times;
+                    if *count == 0 {
+                        counts.remove(&fee);
+                    }
+                }
+            }
+            Self::Sketch(sketch) => sketch.remove_many(fee, times),
+        }
+    }
 
     fn len(&self) -> usize {
-        self.data.len()
+        match self {
+            Self::Exact { len, .. } => *len as usize,
+            Self::Sketch(sketch) => sketch.count(),
+        }
+    }
+
+    /// Fee at 0-based `rank`; `rank` must be below `len`.
+    fn nth(&self, rank: usize) -> u64 {
+        match self {
+            Self::Exact { counts, .. } => counts
+                .iter()
+                .scan(0u64, |seen, (&fee, &count)| {
+                    *seen += count;
+                    Some((fee, *seen))
+                })
+                .find(|&(_, seen)| seen > rank as u64)
+                .map_or(0, |(fee, _)| fee),
+            Self::Sketch(sketch) => sketch.nth(rank).unwrap_or_default(),
+        }
+    }
+
+    /// Forget every fee recorded in `other`, kept the same way.
+    fn subtract(&mut self, other: &Self) {
+        match (self, other) {
+            (Self::Sketch(sketch), Self::Sketch(other)) => sketch.subtract(other),
+            (order, other) => {
+                for (fee, count) in other.runs() {
+                    order.remove_many(fee, count);
+                }
+            }
+        }
+    }
+
+    /// `(fee, count)` runs, ascending.
+    fn runs(&self) -> Vec<(u64, u64)> {
+        match self {
+            Self::Exact { counts, .. } => {
+                counts.iter().map(|(&fee, &count)| (fee, count)).collect()
+            }
+            Self::Sketch(sketch) => sketch.counts().collect(),
+        }
+    }
+
+    /// Nearest-rank percentiles, or `None` when empty.
+    fn distribution(&self) -> Option<FeeDistribution> {
+        distribution_by(self.len(), |rank| self.nth(rank))
+    }
+}
+
+/// Fee stats of the points of one operation category in a window
+#[derive(Debug, Clone)]
+struct CategorySums {
+    count: usize,
+    sum: u128,
+    per_operation_count: usize,
+    per_operation_sum: f64,
+    fees: FeeOrder,
+}
+
+impl CategorySums {
+    fn new(method: &PercentileMethod) -> Self {
+        Self {
+            count: 0,
+            sum: 0,
+            per_operation_count: 0,
+            per_operation_sum: 0.0,
+            fees: FeeOrder::new(method),
+        }
+    }
+
+    fn insert(&mut self, point: &FeeDataPoint) {
+        self.count += 1;
+        self.sum += point.fee_amount as u128;
+        if let Some(per_operation) = point.fee_per_operation() {
+            self.per_operation_count += 1;
+            self.per_operation_sum += per_operation;
+        }
+        self.fees.insert(point.fee_amount);
+    }
+
+    fn remove(&mut self, point: &FeeDataPoint) {
+        self.count -= 1;
+        self.sum -= point.fee_amount as u128;
+        if let Some(per_operation) = point.fee_per_operation() {
+            self.per_operation_count -= 1;
+            self.per_operation_sum -= per_operation;
+        }
+        self.fees.remove(point.fee_amount);
+    }
+
+    fn subtract(&mut self, other: &Self) {
+        self.count -= other.count;
+        self.sum -= other.sum;
+        self.per_operation_count -= other.per_operation_count;
+        self.per_operation_sum -= other.per_operation_sum;
+        self.fees.subtract(&other.fees);
+    }
+
+    fn stats(&self, category: OperationCategory) -> Option<CategoryFeeStats> {
+        Some(CategoryFeeStats {
+            category,
+            sample_count: self.count,
+            average_fee: self.sum as f64 / self.count as f64,
+            avg_fee_per_operation: (self.per_operation_count > 0)
+                .then(|| self.per_operation_sum / self.per_operation_count as f64),
+            percentiles: self.fees.distribution()?,
+        })
+    }
+}
+
+/// Bids against charged fees of the points in a window that carry a bid
+#[derive(Debug, Clone)]
+struct BidSums {
+    count: usize,
+    bid_sum: u128,
+    charged_sum: u128,
+    /// Total of bid minus charged fee, never below zero per point.
+    waste: u128,
+    overbids: usize,
+    spreads: FeeOrder,
+}
+
+impl BidSums {
+    fn new(method: &PercentileMethod) -> Self {
+        Self {
+            count: 0,
+            bid_sum: 0,
+            charged_sum: 0,
+            waste: 0,
+            overbids: 0,
+            spreads: FeeOrder::new(method),
+        }
+    }
+
+    fn insert(&mut self, bid: u64, charged: u64) {
+        let spread = bid.saturating_sub(charged);
+        self.count += 1;
+        self.bid_sum += bid as u128;
+        self.charged_sum += charged as u128;
+        self.waste += spread as u128;
+        self.overbids += usize::from(spread > 0);
+        self.spreads.insert(spread);
+    }
+
+    fn remove(&mut self, bid: u64, charged: u64) {
+        let spread = bid.saturating_sub(charged);
+        self.count -= 1;
+        self.bid_sum -= bid as u128;
+        self.charged_sum -= charged as u128;
+        self.waste -= spread as u128;
+        self.overbids -= usize::from(spread > 0);
+        self.spreads.remove(spread);
     }
 
-    fn is_empty(&self) -> bool {
-        self.data.is_empty()
+    fn subtract(&mut self, other: &Self) {
+        self.count -= other.count;
+        self.bid_sum -= other.bid_sum;
+        self.charged_sum -= other.charged_sum;
+        self.waste -= other.waste;
+        self.overbids -= other.overbids;
+        self.spreads.subtract(&other.spreads);
+    }
+
+    /// The spread, or `None` when no point carries a bid.
+    fn spread(&self) -> Option<BidSpread> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let count = self.count as f64;
+        Some(BidSpread {
+            sample_count: self.count,
+            average_bid: self.bid_sum as f64 / count,
+            average_charged: self.charged_sum as f64 / count,
+            mean_spread: self.waste as f64 / count,
+            median_spread: self.spreads.nth((self.count - 1) / 2),
+            bid_to_charged_ratio: if self.charged_sum > 0 {
+                self.bid_sum as f64 / self.charged_sum as f64
+            } else {
+                0.0
+            },
+            overbidding_rate: self.overbids as f64 / count,
+            total_waste: self.waste as u64,
+            waste_share: if self.bid_sum > 0 {
+                self.waste as f64 / self.bid_sum as f64
+            } else {
+                0.0
+            },
+        })
+    }
+}
+
+/// Resource usage and fees of the Soroban points in a window
+#[derive(Debug, Clone, Default)]
+struct SorobanSums {
+    count: usize,
+    instructions: u128,
+    disk_read_bytes: u128,
+    write_bytes: u128,
+    resource_fee: u128,
+    rent_count: usize,
+    rent_fee: u128,
+}
+
+impl SorobanSums {
+    fn insert(&mut self, detail: &SorobanFeeDetail) {
+        self.count += 1;
+        self.instructions += detail.instructions as u128;
+        self.disk_read_bytes += detail.disk_read_bytes as u128;
+        self.write_bytes += detail.write_bytes as u128;
+        self.resource_fee += detail.resource_fee() as u128;
+        if let Some(rent_fee) = detail.rent_fee {
+            self.rent_count += 1;
+            self.rent_fee += rent_fee as u128;
+        }
+    }
+
+    fn remove(&mut self, detail: &SorobanFeeDetail) {
+        self.count -= 1;
+        self.instructions -= detail.instructions as u128;
+        self.disk_read_bytes -= detail.disk_read_bytes as u128;
+        self.write_bytes -= detail.write_bytes as u128;
+        self.resource_fee -= detail.resource_fee() as u128;
+        if let Some(rent_fee) = detail.rent_fee {
+            self.rent_count -= 1;
+            self.rent_fee -= rent_fee as u128;
+        }
+    }
+
+    fn subtract(&mut self, other: &Self) {
+        self.count -= other.count;
+        self.instructions -= other.instructions;
+        self.disk_read_bytes -= other.disk_read_bytes;
+        self.write_bytes -= other.write_bytes;
+        self.resource_fee -= other.resource_fee;
+        self.rent_count -= other.rent_count;
+        self.rent_fee -= other.rent_fee;
+    }
+
+    /// Means over the Soroban points, or `None` when there are none.
+    fn averages(&self) -> Option<SorobanResourceAverages> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let mean = |sum: u128| sum as f64 / self.count as f64;
+        Some(SorobanResourceAverages {
+            sample_count: self.count,
+            instructions: mean(self.instructions),
+            disk_read_bytes: mean(self.disk_read_bytes),
+            write_bytes: mean(self.write_bytes),
+            resource_fee: mean(self.resource_fee),
+            rent_fee: (self.rent_count > 0).then(|| self.rent_fee as f64 / self.rent_count as f64),
+        })
+    }
+}
+
+/// Aggregates of the fees in a window, or in a slice of one, kept in step
+/// with the points it holds. Integer sums stay exact however many points
+/// come and go.
+#[derive(Debug, Clone)]
+struct RunningAggregates {
+    count: u128,
+    sum: u128,
+    sum_of_squares: u128,
+    fee_bump_count: usize,
+    fee_bump_sum: u128,
+    per_operation_count: usize,
+    per_operation_sum: f64,
+    /// Operations across the window, counting one where unknown.
+    operation_total: u128,
+    /// Every fee in the window.
+    fees: FeeOrder,
+    /// Every fee in the window, once per operation it paid for.
+    operations_by_fee: FeeOrder,
+    categories: HashMap<OperationCategory, CategorySums>,
+    bids: BidSums,
+    soroban: SorobanSums,
+    trend: TrendSums,
+}
+
+impl RunningAggregates {
+    fn new(method: &PercentileMethod, origin: DateTime<Utc>) -> Self {
+        Self {
+            count: 0,
+            sum: 0,
+            sum_of_squares: 0,
+            fee_bump_count: 0,
+            fee_bump_sum: 0,
+            per_operation_count: 0,
+            per_operation_sum: 0.0,
+            operation_total: 0,
+            fees: FeeOrder::new(method),
+            operations_by_fee: FeeOrder::new(method),
+            categories: HashMap::new(),
+            bids: BidSums::new(method),
+            soroban: SorobanSums::default(),
+            trend: TrendSums::new(origin),
+        }
+    }
+
+    fn insert(&mut self, point: &FeeDataPoint, method: &PercentileMethod) {
+        let fee = point.fee_amount as u128;
+        self.count += 1;
+        self.sum += fee;
+        self.sum_of_squares += fee * fee;
+        if point.is_fee_bump() {
+            self.fee_bump_count += 1;
+            self.fee_bump_sum += fee;
+        }
+        if let Some(per_operation) = point.fee_per_operation() {
+            self.per_operation_count += 1;
+            self.per_operation_sum += per_operation;
+        }
+        self.operation_total += operations(point);
+        self.fees.insert(point.fee_amount);
+        self.operations_by_fee
+            .insert_many(point.fee_amount, operations(point) as u64);
+        if let Some(category) = point.operation_category() {
+            self.categories
+                .entry(category)
+                .or_insert_with(|| CategorySums::new(method))
+                .insert(point);
+        }
+        if let Some(bid) = point.max_fee() {
+            self.bids.insert(bid, point.fee_amount);
+        }
+        if let Some(detail) = &point.soroban {
+            self.soroban.insert(detail);
+        }
+        self.trend.insert(point);
+    }
+
+    fn remove(&mut self, point: &FeeDataPoint) {
+        let fee = point.fee_amount as u128;
+        self.count -= 1;
+        self.sum -= fee;
+        self.sum_of_squares -= fee * fee;
+        if point.is_fee_bump() {
+            self.fee_bump_count -= 1;
+            self.fee_bump_sum -= fee;
+        }
+        if let Some(per_operation) = point.fee_per_operation() {
+            self.per_operation_count -= 1;
+            self.per_operation_sum -= per_operation;
+            if self.per_operation_count == 0 {
+                // Drop accumulated rounding error
+                self.per_operation_sum = 0.0;
+            }
+        }
+        self.operation_total -= operations(point);
+        self.fees.remove(point.fee_amount);
+        self.operations_by_fee
+            .remove_many(point.fee_amount, operations(point) as u64);
+        if let Some(category) = point.operation_category() {
+            if let Some(sums) = self.categories.get_mut(&category) {
+                sums.remove(point);
+                if sums.count == 0 {
+                    self.categories.remove(&category);
+                }
+            }
+        }
+        if let Some(bid) = point.max_fee() {
+            self.bids.remove(bid, point.fee_amount);
+        }
+        if let Some(detail) = &point.soroban {
+            self.soroban.remove(detail);
+        }
+        self.trend.remove(point);
+    }
+
+    /// Forget every point inserted into `other`, a part of this window
+    /// sharing its trend origin.
+    fn subtract(&mut self, other: &Self) {
+        self.count -= other.count;
+        self.sum -= other.sum;
+        self.sum_of_squares -= other.sum_of_squares;
+        self.fee_bump_count -= other.fee_bump_count;
+        self.fee_bump_sum -= other.fee_bump_sum;
+        self.per_operation_count -= other.per_operation_count;
+        self.per_operation_sum -= other.per_operation_sum;
+        if self.per_operation_count == 0 {
+            self.per_operation_sum = 0.0;
+        }
+        self.operation_total -= other.operation_total;
+        self.fees.subtract(&other.fees);
+        self.operations_by_fee.subtract(&other.operations_by_fee);
+        for (category, removed) in &other.categories {
+            if let Some(sums) = self.categories.get_mut(category) {
+                sums.subtract(removed);
+                if sums.count == 0 {
+                    self.categories.remove(category);
+                }
+            }
+        }
+        self.bids.subtract(&other.bids);
+        self.soroban.subtract(&other.soroban);
+        self.trend.subtract(&other.trend);
+    }
+
+    fn mean(&self) -> f64 {
+        self.sum as f64 / self.count as f64
+    }
+
+    /// Fees over operations; see `AveragingMethod::OperationWeighted`.
+    fn operation_weighted_mean(&self) -> f64 {
+        self.sum as f64 / self.operation_total as f64
+    }
+
+    /// Fees over operations after `filter`, cutting on the whole fee as
+    /// `filter_fee` does before each fee is weighted.
+    fn filtered_operation_weighted_mean(&self, filter: &OutlierFilter) -> f64 {
+        let bounds = outlier_bounds(&self.fees, filter);
+        let fees: u128 = self
+            .fees
+            .runs()
+            .into_iter()
+            .filter_map(|(fee, count)| {
+                Some(filter_fee(fee, bounds, filter)? as u128 * count as u128)
+            })
+            .sum();
+        let operation_total: u128 = self
+            .operations_by_fee
+            .runs()
+            .into_iter()
+            .filter(|&(fee, _)| filter_fee(fee, bounds, filter).is_some())
+            .map(|(_, operations)| operations as u128)
+            .sum();
+        fees as f64 / operation_total as f64
+    }
+
+    /// Standard deviation, coefficient of variation, median and median
+    /// absolute deviation, or `None` for an empty window.
+    fn volatility(&self) -> Option<FeeVolatility> {
+        if self.count == 0 {
+            return None;
+        }
+        // n·Σx² − (Σx)² is exact in integers and never negative
+        let spread = self.count * self.sum_of_squares - self.sum * self.sum;
+        let std_dev = (spread as f64).sqrt() / self.count as f64;
+        let mean = self.mean();
+        let runs: Vec<(f64, u64)> = self
+            .fees
+            .runs()
+            .into_iter()
+            .map(|(fee, count)| (fee as f64, count))
+            .collect();
+        let middle = median(&runs);
+        let mut deviations: Vec<(f64, u64)> = runs
+            .iter()
+            .map(|&(fee, count)| ((fee - middle).abs(), count))
+            .collect();
+        deviations.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+        Some(FeeVolatility {
+            std_dev,
+            coefficient_of_variation: (mean > 0.0).then(|| std_dev / mean),
+            median: middle,
+            median_absolute_deviation: median(&deviations),
+        })
+    }
+
+    fn fee_bump_volume_share(&self) -> f64 {
+        if self.sum > 0 {
+            self.fee_bump_sum as f64 / self.sum as f64
+        } else {
+            0.0
+        }
+    }
+
+    fn avg_fee_per_operation(&self) -> Option<f64> {
+        (self.per_operation_count > 0)
+            .then(|| self.per_operation_sum / self.per_operation_count as f64)
+    }
+
+    /// Fee stats per operation category, in `OperationCategory::ALL` order.
+    fn category_stats(&self) -> Vec<CategoryFeeStats> {
+        OperationCategory::ALL
+            .into_iter()
+            .filter_map(|category| self.categories.get(&category)?.stats(category))
+            .collect()
+    }
+}
+
+/// EWMA of a window's fees in arrival order, seeded with the oldest
+#[derive(Debug, Clone)]
+struct ExponentialAverage {
+    smoothing_factor: f64,
+    value: f64,
+    /// The seed: the oldest fee averaged.
+    first: u64,
+    /// Fees averaged, oldest first in the window's buffer.
+    inputs: usize,
+}
+
+impl ExponentialAverage {
+    fn new(smoothing_factor: f64) -> Self {
+        Self {
+            smoothing_factor,
+            value: 0.0,
+            first: 0,
+            inputs: 0,
+        }
+    }
+
+    fn push(&mut self, fee: u64) {
+        self.value = if self.inputs == 0 {
+            self.first = fee;
+            fee as f64
+        } else {
+            self.smoothing_factor * fee as f64 + (1.0 - self.smoothing_factor) * self.value
+        };
+        self.inputs += 1;
+    }
+
+    /// The average of this one's fees followed by `later`'s.
+    fn then(&self, later: &Self) -> Self {
+        if self.inputs == 0 {
+            return later.clone();
+        }
+        if later.inputs == 0 {
+            return self.clone();
+        }
+        // Seeded here instead, `later`'s first fee weighs (1 − α)^n less
+        // and this average takes its place.
+        let decay = (1.0 - self.smoothing_factor).powi(later.inputs as i32);
+        Self {
+            smoothing_factor: self.smoothing_factor,
+            value: later.value + decay * (self.value - later.first as f64),
+            first: self.first,
+            inputs: self.inputs + later.inputs,
+        }
+    }
+
+    /// Drop the oldest fee, `oldest`, reseeding on the one after it.
+    fn evict(&mut self, oldest: u64, next: Option<u64>) {
+        match next.filter(|_| self.inputs > 1) {
+            // The oldest fee weighs (1 − α)^(n−1); its successor moves
+            // from α(1 − α)^(n−2) to that seed weight.
+            Some(next) => {
+                let seed_weight = (1.0 - self.smoothing_factor).powi(self.inputs as i32 - 1);
+                self.value += seed_weight * (next as f64 - oldest as f64);
+                self.first = next;
+                self.inputs -= 1;
+            }
+            None => *self = Self::new(self.smoothing_factor),
+        }
+    }
+}
+
+/// A point in a window's buffer
+#[derive(Debug, Clone)]
+struct Buffered {
+    point: FeeDataPoint,
+    /// Fee fed to the window's `ExponentialAverage`, if any: the point's
+    /// fee after the outlier filter as it stood when the point arrived.
+    smoothed: Option<u64>,
+}
+
+/// Slices each sketched window is split into; up to one slice's length of
+/// expired points lingers until its slice is forgotten.
+const SLICES_PER_WINDOW: i64 = 60;
+
+/// Aggregates of the points stamped within one slice of a sketched window
+#[derive(Debug, Clone)]
+struct Slice {
+    /// Slice number counted from the Unix epoch.
+    index: i64,
+    aggregates: RunningAggregates,
+    exponential: Option<ExponentialAverage>,
+}
+
+/// What a window keeps to forget its points again
+#[derive(Debug, Clone)]
+enum WindowStore {
+    /// Each point, oldest first, up to `AverageConfig::max_buffer_size`.
+    Points {
+        points: CircularBuffer<Buffered>,
+        exponential: Option<ExponentialAverage>,
+    },
+    /// Aggregates per slice of `width_ms`, ordered by slice.
+    Slices {
+        width_ms: i64,
+        slices: VecDeque<Slice>,
+    },
+}
+
+/// One time window: its running aggregates and what it keeps to forget
+/// points from them
+#[derive(Debug, Clone)]
+struct WindowState {
+    store: WindowStore,
+    aggregates: RunningAggregates,
+    percentile_method: PercentileMethod,
+    origin: DateTime<Utc>,
+    /// Set for `AveragingMethod::Exponential` windows only.
+    smoothing_factor: Option<f64>,
+}
+
+impl WindowState {
+    fn new(window: &TimeWindow, config: &AverageConfig, origin: DateTime<Utc>) -> Self {
+        let smoothing_factor = match window.averaging {
+            AveragingMethod::Exponential { smoothing_factor } => Some(smoothing_factor),
+            _ => None,
+        };
+        let store = match config.percentile_method {
+            PercentileMethod::Exact => WindowStore::Points {
+                points: CircularBuffer::new(config.max_buffer_size),
+                exponential: smoothing_factor.map(ExponentialAverage::new),
+            },
+            PercentileMethod::Sketch { .. } => WindowStore::Slices {
+                width_ms: (window.duration.num_milliseconds() / SLICES_PER_WINDOW).max(1),
+                slices: VecDeque::new(),
+            },
+        };
+        Self {
+            store,
+            aggregates: RunningAggregates::new(&config.percentile_method, origin),
+            percentile_method: config.percentile_method.clone(),
+            origin,
+            smoothing_factor,
+        }
+    }
+
+    fn push(&mut self, point: FeeDataPoint, filter: &OutlierFilter) {
+        self.aggregates.insert(&point, &self.percentile_method);
+        let smoothed = self.smoothing_factor.and_then(|_| {
+            let bounds = outlier_bounds(&self.aggregates.fees, filter);
+            filter_fee(point.fee_amount, bounds, filter)
+        });
+
+        match &mut self.store {
+            WindowStore::Points {
+                points,
+                exponential,
+            } => {
+                if let (Some(average), Some(fee)) = (exponential.as_mut(), smoothed) {
+                    average.push(fee);
+                }
+                if let Some(evicted) = points.push(Buffered { point, smoothed }) {
+                    forget(&mut self.aggregates, points, exponential, evicted);
+                }
+            }
+            WindowStore::Slices { width_ms, slices } => {
+                let index = point.timestamp.timestamp_millis().div_euclid(*width_ms);
+                let position = match slices.back() {
+                    Some(last) if last.index < index => Err(slices.len()),
+                    None => Err(0),
+                    _ => slices.binary_search_by_key(&index, |slice| slice.index),
+                };
+                let position = position.unwrap_or_else(|position| {
+                    slices.insert(
+                        position,
+                        Slice {
+                            index,
+                            aggregates: RunningAggregates::new(
+                                &self.percentile_method,
+                                self.origin,
+                            ),
+                            exponential: None,
+                        },
+                    );
+                    position
+                });
+                let slice = &mut slices[position];
+                slice.aggregates.insert(&point, &self.percentile_method);
+                if let (Some(smoothing_factor), Some(fee)) = (self.smoothing_factor, smoothed) {
+                    slice
+                        .exponential
+                        .get_or_insert_with(|| ExponentialAverage::new(smoothing_factor))
+                        .push(fee);
+                }
+            }
+        }
+    }
+
+    /// Drop points stamped before `window_start`; sketched windows drop
+    /// each slice once it has ended by then.
+    fn evict_before(&mut self, window_start: DateTime<Utc>) {
+        match &mut self.store {
+            WindowStore::Points {
+                points,
+                exponential,
+            } => {
+                while points
+                    .front()
+                    .is_some_and(|front| front.point.timestamp < window_start)
+                {
+                    if let Some(evicted) = points.pop_front() {
+                        forget(&mut self.aggregates, points, exponential, evicted);
+                    }
+                }
+            }
+            WindowStore::Slices { width_ms, slices } => {
+                let start_ms = window_start.timestamp_millis();
+                while slices
+                    .front()
+                    .is_some_and(|front| (front.index + 1) * *width_ms <= start_ms)
+                {
+                    if let Some(expired) = slices.pop_front() {
+                        self.aggregates.subtract(&expired.aggregates);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The window's exponential average, 0 when it has averaged nothing.
+    fn exponential_value(&self) -> f64 {
+        match &self.store {
+            WindowStore::Points { exponential, .. } => {
+                exponential.as_ref().map_or(0.0, |average| average.value)
+            }
+            WindowStore::Slices { slices, .. } => slices
+                .iter()
+                .filter_map(|slice| slice.exponential.as_ref())
+                .fold(None, |average: Option<ExponentialAverage>, later| {
+                    Some(match average {
+                        Some(average) => average.then(later),
+                        None => later.clone(),
+                    })
+                })
+                .map_or(0.0, |average| average.value),
+        }
+    }
+}
+
+/// Take `evicted`, which has left the front of `points`, out of
+/// `aggregates` and `exponential`.
+fn forget(
+    aggregates: &mut RunningAggregates,
+    points: &CircularBuffer<Buffered>,
+    exponential: &mut Option<ExponentialAverage>,
+    evicted: Buffered,
+) {
+    aggregates.remove(&evicted.point);
+    if let (Some(average), Some(oldest)) = (exponential.as_mut(), evicted.smoothed) {
+        let next = points.iter().find_map(|buffered| buffered.smoothed);
+        average.evict(oldest, next);
     }
 }
 
 /// Calculator for rolling averages across multiple time windows
 pub struct RollingAverageCalculator {
-    #[allow(dead_code)]
     config: AverageConfig,
-    windows: HashMap<TimeWindow, CircularBuffer<FeeDataPoint>>,
+    windows: HashMap<TimeWindow, WindowState>,
     time_windows: Vec<TimeWindow>,
+    trend_analyzer: FeeTrendAnalyzer,
 }
 
 impl RollingAverageCalculator {
@@ -54,11 +885,13 @@ impl RollingAverageCalculator {
         let mut windows = HashMap::new();
 
         // Initialize circular buffers for each time window
+        let origin = Utc::now();
         for window in &time_windows {
-            windows.insert(window.clone(), CircularBuffer::new(config.max_buffer_size));
+            windows.insert(window.clone(), WindowState::new(window, &config, origin));
         }
 
         Self {
+            trend_analyzer: FeeTrendAnalyzer::new(config.trend.clone()),
             config,
             windows,
             time_windows,
@@ -71,11 +904,11 @@ impl RollingAverageCalculator {
 
         // Add to each time window if the point is within the window duration
         for window in &self.time_windows {
-            if let Some(buffer) = self.windows.get_mut(window) {
+            if let Some(state) = self.windows.get_mut(window) {
                 // Check if the data point is within the time window
                 let window_start = now - window.duration;
                 if point.timestamp >= window_start {
-                    buffer.push(point.clone());
+                    state.push(point.clone(), &self.config.outlier_filter);
                 }
             }
         }
@@ -86,17 +919,8 @@ impl RollingAverageCalculator {
 
     /// Clean old data points that are outside their respective time windows
     fn clean_old_data(&mut self, current_time: DateTime<Utc>) {
-        for (window, buffer) in &mut self.windows {
-            let window_start = current_time - window.duration;
-
-            // Remove data points that are too old
-            while let Some(front) = buffer.data.front() {
-                if front.timestamp < window_start {
-                    buffer.data.pop_front();
-                } else {
-                    break;
-                }
-            }
+        for (window, state) in &mut self.windows {
+            state.evict_before(current_time - window.duration);
         }
     }
 
@@ -116,12 +940,8 @@ impl RollingAverageCalculator {
         })
     }
 
-    /// Calculate average for a specific time window by name
-    fn calculate_average_for_window(
-        &self,
-        window_name: &str,
-        calculated_at: DateTime<Utc>,
-    ) -> Result<AverageResult, InsightsError> {
+    /// The time window named `window_name` and its state.
+    fn window(&self, window_name: &str) -> Result<(&TimeWindow, &WindowState), InsightsError> {
         // Find the time window by name
         let time_window = self
             .time_windows
@@ -131,24 +951,75 @@ impl RollingAverageCalculator {
                 InsightsError::config_error(format!("Time window '{}' not found", window_name))
             })?;
 
-        let buffer = self.windows.get(time_window).ok_or_else(|| {
+        let state = self.windows.get(time_window).ok_or_else(|| {
             InsightsError::config_error(format!("Buffer for window '{}' not found", window_name))
         })?;
+        Ok((time_window, state))
+    }
 
-        if buffer.is_empty() {
+    /// Mean fee per transaction in the named window after the outlier
+    /// filter, whatever the window is averaged by; 0 when it is empty.
+    /// Batches of transactions are compared against this.
+    pub(crate) fn transaction_mean(&self, window_name: &str) -> Result<f64, InsightsError> {
+        let aggregates = &self.window(window_name)?.1.aggregates;
+        Ok(match &self.config.outlier_filter {
+            _ if aggregates.count == 0 => 0.0,
+            OutlierFilter::None => aggregates.mean(),
+            filter => filtered_mean(&aggregates.fees, filter),
+        })
+    }
+
+    /// Calculate average for a specific time window by name
+    pub(crate) fn calculate_average_for_window(
+        &self,
+        window_name: &str,
+        calculated_at: DateTime<Utc>,
+    ) -> Result<AverageResult, InsightsError> {
+        let (time_window, state) = self.window(window_name)?;
+        let aggregates = &state.aggregates;
+
+        if aggregates.count == 0 {
             return Ok(AverageResult {
                 value: 0.0,
                 sample_count: 0,
                 is_partial: true,
                 calculated_at,
                 time_window: time_window.clone(),
+                fee_bump_count: 0,
+                fee_bump_volume_share: 0.0,
+                avg_fee_per_operation: None,
+                soroban: None,
+                percentiles: None,
+                histogram: None,
+                by_category: Vec::new(),
+                volatility: None,
+                trend: None,
+                bid_spread: None,
             });
         }
 
         // Calculate the average fee
-        let total_fee: u64 = buffer.iter().map(|point| point.fee_amount).sum();
-        let sample_count = buffer.len();
-        let average = total_fee as f64 / sample_count as f64;
+        let sample_count = aggregates.count as usize;
+        let filter = &self.config.outlier_filter;
+        let average = match time_window.averaging {
+            AveragingMethod::Simple if *filter == OutlierFilter::None => aggregates.mean(),
+            AveragingMethod::Simple => filtered_mean(&aggregates.fees, filter),
+            AveragingMethod::OperationWeighted if *filter == OutlierFilter::None => {
+                aggregates.operation_weighted_mean()
+            }
+            AveragingMethod::OperationWeighted => {
+                aggregates.filtered_operation_weighted_mean(filter)
+            }
+            AveragingMethod::Exponential { smoothing_factor } => {
+                if !(smoothing_factor > 0.0 && smoothing_factor <= 1.0) {
+                    return Err(InsightsError::config_error(format!(
+                        "Smoothing factor {} for window '{}' must be in (0, 1]",
+                        smoothing_factor, window_name
+                    )));
+                }
+                state.exponential_value()
+            }
+        };
 
         // Determine if this is a partial result (insufficient samples)
         let is_partial = sample_count < time_window.min_samples;
@@ -159,6 +1030,231 @@ impl RollingAverageCalculator {
             is_partial,
             calculated_at,
             time_window: time_window.clone(),
+            fee_bump_count: aggregates.fee_bump_count,
+            fee_bump_volume_share: aggregates.fee_bump_volume_share(),
+            avg_fee_per_operation: aggregates.avg_fee_per_operation(),
+            soroban: aggregates.soroban.averages(),
+            percentiles: aggregates.fees.distribution(),
+            histogram: fee_histogram(&aggregates.fees.runs(), &self.config.histogram),
+            by_category: aggregates.category_stats(),
+            volatility: aggregates.volatility(),
+            trend: self
+                .trend_analyzer
+                .classify(&aggregates.trend, time_window.duration),
+            bid_spread: aggregates.bids.spread(),
+        })
+    }
+}
+
+/// Operations `point` was charged for, or one when unknown.
+fn operations(point: &FeeDataPoint) -> u128 {
+    point.operation_count().map_or(1, u128::from)
+}
+
+/// Middle of the non-empty ascending `(value, count)` runs.
+fn median(runs: &[(f64, u64)]) -> f64 {
+    let len: u64 = runs.iter().map(|&(_, count)| count).sum();
+    let nth = |rank: u64| {
+        let mut seen = 0;
+        for &(value, count) in runs {
+            seen += count;
+            if seen > rank {
+                return value;
+            }
+        }
+        runs[runs.len() - 1].0
+    };
+    if len.is_multiple_of(2) {
+        (nth(len / 2 - 1) + nth(len / 2)) / 2.0
+    } else {
+        nth(len / 2)
+    }
+}
+
+/// Fees `filter` drops or clamps from each end of `len` sorted fees.
+/// At least one fee is always kept.
+fn tail_len(len: usize, filter: &OutlierFilter) -> usize {
+    let fraction = match filter {
+        OutlierFilter::None => return 0,
+        OutlierFilter::Trim { fraction } | OutlierFilter::Winsorize { fraction } => *fraction,
+    };
+    ((len as f64 * fraction.max(0.0)).floor() as usize).min(len.saturating_sub(1) / 2)
+}
+
+/// Mean of the non-empty `fees` after `filter`.
+fn filtered_mean(fees: &FeeOrder, filter: &OutlierFilter) -> f64 {
+    let len = fees.len();
+    let tail = tail_len(len, filter);
+    let (kept_start, kept_end) = (tail as u64, (len - tail) as u64);
+    let mut kept_sum: u128 = 0;
+    let mut start = 0;
+    for (fee, count) in fees.runs() {
+        let end = start + count;
+        let kept = end.min(kept_end).saturating_sub(start.max(kept_start));
+        kept_sum += fee as u128 * kept as u128;
+        start = end;
+    }
+
+    match filter {
+        OutlierFilter::Winsorize { .. } => {
+            let clamped =
+                tail as u128 * (fees.nth(tail) as u128 + fees.nth(len - 1 - tail) as u128);
+            (kept_sum + clamped) as f64 / len as f64
+        }
+        _ => kept_sum as f64 / (len - 2 * tail) as f64,
+    }
+}
+
+/// `fee` after `filter` with the cut-offs `bounds`, or `None` when it is
+/// trimmed. Ties at a cut-off are all kept.
+fn filter_fee(fee: u64, (lowest, highest): (u64, u64), filter: &OutlierFilter) -> Option<u64> {
+    match filter {
+        // A sketch's lowest and highest fees are only approximate
+        OutlierFilter::None => Some(fee),
+        OutlierFilter::Winsorize { .. } => Some(fee.clamp(lowest, highest)),
+        _ => (lowest..=highest).contains(&fee).then_some(fee),
+    }
+}
+
+/// Lowest and highest fee `filter` keeps of those in `order`.
+fn outlier_bounds(order: &FeeOrder, filter: &OutlierFilter) -> (u64, u64) {
+    let len = order.len();
+    let tail = tail_len(len, filter);
+    match len {
+        0 => (0, u64::MAX),
+        _ => (order.nth(tail), order.nth(len - 1 - tail)),
+    }
+}
+
+/// Nearest-rank percentiles of the ascending `fees`, or `None` when there
+/// are none.
+pub(crate) fn fee_distribution(fees: &[u64]) -> Option<FeeDistribution> {
+    distribution_by(fees.len(), |rank| fees[rank])
+}
+
+/// Nearest-rank percentiles of `len` fees read by 0-based rank through
+/// `nth`, or `None` when there are none.
+fn distribution_by(len: usize, nth: impl Fn(usize) -> u64) -> Option<FeeDistribution> {
+    if len == 0 {
+        return None;
+    }
+
+    let percentile = |p: f64| {
+        let rank = (p / 100.0 * len as f64).ceil() as usize;
+        nth(rank.clamp(1, len) - 1)
+    };
+
+    Some(FeeDistribution {
+        p10: percentile(10.0),
+        p25: percentile(25.0),
+        p50: percentile(50.0),
+        p75: percentile(75.0),
+        p90: percentile(90.0),
+        p95: percentile(95.0),
+        p99: percentile(99.0),
+    })
+}
+
+/// Bucket counts of the ascending `(fee, count)` runs, or `None` when
+/// there are none.
+fn fee_histogram(runs: &[(u64, u64)], bins: &HistogramBins) -> Option<FeeHistogram> {
+    let (Some(&(first, _)), Some(&(last, _))) = (runs.first(), runs.last()) else {
+        return None;
+    };
+
+    // Lower edge of every bucket; the first always starts at zero.
+    let mut lowers = vec![0];
+    match bins {
+        HistogramBins::Fixed { edges } => lowers.extend(edges.iter().copied()),
+        HistogramBins::Logarithmic { bins } => {
+            let min = first.max(1) as f64;
+            let max = last.max(1) as f64;
+            let step = (max / min).powf(1.0 / (*bins).max(1) as f64);
+            lowers.extend((1..*bins).map(|i| (min * step.powi(i as i32)).round() as u64));
+        }
+    }
+    lowers.sort_unstable();
+    lowers.dedup();
+
+    let buckets = lowers
+        .iter()
+        .enumerate()
+        .map(|(i, &lower)| {
+            let upper = lowers.get(i + 1).copied();
+            let count: u64 = runs
+                .iter()
+                .filter(|&&(fee, _)| fee >= lower && upper.is_none_or(|upper| fee < upper))
+                .map(|&(_, count)| count)
+                .sum();
+            HistogramBucket {
+                lower,
+                upper,
+                count: count as usize,
+            }
         })
+        .collect();
+
+    Some(FeeHistogram { buckets })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::insights::config::InsightsConfig;
+    use chrono::Duration;
+
+    fn exponential(fees: &[u64]) -> f64 {
+        fees.iter()
+            .map(|&fee| fee as f64)
+            .reduce(|average, fee| 0.3 * fee + 0.7 * average)
+            .unwrap()
+    }
+
+    #[test]
+    fn sketched_windows_keep_every_point_and_expire_whole_slices() {
+        let config = AverageConfig {
+            max_buffer_size: 4,
+            percentile_method: PercentileMethod::Sketch {
+                relative_accuracy: 0.01,
+            },
+            ..AverageConfig::default()
+        };
+        // Five minutes, so five-second slices
+        let mut window = InsightsConfig::default().time_windows.remove(0);
+        window.averaging = AveragingMethod::Exponential {
+            smoothing_factor: 0.3,
+        };
+        let start = DateTime::from_timestamp_millis(1_700_000_000_000).unwrap();
+        let mut state = WindowState::new(&window, &config, start);
+
+        let fees: Vec<u64> = (0..20).map(|i| 100 + 10 * i).collect();
+        for (i, &fee_amount) in fees.iter().enumerate() {
+            state.push(
+                FeeDataPoint {
+                    fee_amount,
+                    timestamp: start + Duration::seconds(i as i64),
+                    transaction_hash: format!("hash_{}", i),
+                    ledger_sequence: i as u64,
+                    envelope: None,
+                    soroban: None,
+                },
+                &OutlierFilter::None,
+            );
+        }
+
+        // Not capped at `max_buffer_size`, and averaged across four slices
+        assert_eq!(state.aggregates.count, 20);
+        assert!((state.exponential_value() - exponential(&fees)).abs() < 1e-9);
+
+        // The first two slices have ended ten seconds in
+        state.evict_before(start + Duration::seconds(10));
+        assert_eq!(state.aggregates.count, 10);
+        assert_eq!(state.aggregates.fees.len(), 10);
+        assert_eq!(state.aggregates.sum, fees[10..].iter().sum::<u64>() as u128);
+        assert!((state.exponential_value() - exponential(&fees[10..])).abs() < 1e-9);
+
+        // A slice still running by then is kept whole
+        state.evict_before(start + Duration::seconds(12));
+        assert_eq!(state.aggregates.count, 10);
     }
 }
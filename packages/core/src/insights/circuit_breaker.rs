@@ -0,0 +1,383 @@
+//! Circuit Breaker Fee Data Provider
+//!
+//! Decorator that stops calling a failing provider after a run of
+//! consecutive errors, serves the last good response while the circuit is
+//! open, and periodically lets one trial call through to detect recovery.
+
+use async_trait::async_trait;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::insights::{
+    config::CircuitBreakerConfig,
+    error::ProviderError,
+    provider::{
+        CircuitBreakerStatus, CircuitState, FeeDataProvider, ProviderMetadata, ProviderResult,
+        ProviderStatus,
+    },
+    types::{FeeDataPoint, FeeStatsSnapshot, LedgerInfo},
+};
+
+/// Mutable breaker state, guarded by a short-lived std mutex (never held
+/// across an await).
+struct BreakerState {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// A half-open trial call is in flight; other callers get stale data.
+    trial_in_flight: bool,
+    last_good: Option<Vec<FeeDataPoint>>,
+    serving_stale: bool,
+}
+
+/// Whether a call may go upstream.
+enum Admission {
+    Allow,
+    Reject,
+}
+
+/// Wraps a provider with a closed → open → half-open circuit breaker.
+///
+/// `Unsupported` errors do not count as failures — they say nothing about
+/// the health of the upstream service.
+pub struct CircuitBreakerProvider<P: FeeDataProvider> {
+    inner: P,
+    config: CircuitBreakerConfig,
+    state: Mutex<BreakerState>,
+}
+
+impl<P: FeeDataProvider> CircuitBreakerProvider<P> {
+    pub fn new(inner: P, config: CircuitBreakerConfig) -> Self {
+        Self {
+            inner,
+            config,
+            state: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                trial_in_flight: false,
+                last_good: None,
+                serving_stale: false,
+            }),
+        }
+    }
+
+    /// Current breaker state, moving from open to half-open once the open
+    /// period has elapsed.
+    pub fn state(&self) -> CircuitState {
+        let mut state = self.lock();
+        self.refresh(&mut state);
+        state.state
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn open_duration(&self) -> Duration {
+        self.config.open_duration.to_std().unwrap_or(Duration::ZERO)
+    }
+
+    fn refresh(&self, state: &mut BreakerState) {
+        if state.state == CircuitState::Open
+            && state
+                .opened_at
+                .is_some_and(|at| at.elapsed() >= self.open_duration())
+        {
+            state.state = CircuitState::HalfOpen;
+            state.trial_in_flight = false;
+        }
+    }
+
+    fn admit(&self) -> Admission {
+        let mut state = self.lock();
+        self.refresh(&mut state);
+        match state.state {
+            CircuitState::Closed => Admission::Allow,
+            CircuitState::HalfOpen if !state.trial_in_flight => {
+                state.trial_in_flight = true;
+                Admission::Allow
+            }
+            _ => Admission::Reject,
+        }
+    }
+
+    fn record_success(&self) {
+        let mut state = self.lock();
+        if state.state != CircuitState::Closed {
+            tracing::info!(
+                "{} recovered — closing circuit breaker",
+                self.inner.provider_name()
+            );
+        }
+        state.state = CircuitState::Closed;
+        state.consecutive_failures = 0;
+        state.opened_at = None;
+        state.trial_in_flight = false;
+        state.serving_stale = false;
+    }
+
+    fn record_failure(&self, err: &ProviderError) {
+        if matches!(err, ProviderError::Unsupported { .. }) {
+            return;
+        }
+
+        let mut state = self.lock();
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        state.trial_in_flight = false;
+
+        let should_open = state.state == CircuitState::HalfOpen
+            || state.consecutive_failures >= self.config.failure_threshold.max(1);
+        if should_open {
+            if state.state != CircuitState::Open {
+                tracing::warn!(
+                    "{} failed {} time(s) in a row — opening circuit breaker for {}s: {}",
+                    self.inner.provider_name(),
+                    state.consecutive_failures,
+                    self.open_duration().as_secs(),
+                    err,
+                );
+            }
+            state.state = CircuitState::Open;
+            state.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Last good response, or `ServiceUnavailable` if there is none yet.
+    fn serve_stale(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        let mut state = self.lock();
+        match state.last_good.clone() {
+            Some(points) => {
+                state.serving_stale = true;
+                Ok(points)
+            }
+            None => Err(ProviderError::ServiceUnavailable),
+        }
+    }
+
+    fn status(&self) -> CircuitBreakerStatus {
+        let mut state = self.lock();
+        self.refresh(&mut state);
+        let retry_in_seconds = match (state.state, state.opened_at) {
+            (CircuitState::Open, Some(at)) => {
+                let remaining = self.open_duration().saturating_sub(at.elapsed());
+                Some(remaining.as_millis().div_ceil(1000) as u64)
+            }
+            _ => None,
+        };
+        CircuitBreakerStatus {
+            state: state.state,
+            consecutive_failures: state.consecutive_failures,
+            retry_in_seconds,
+            serving_stale: state.serving_stale,
+        }
+    }
+}
+
+#[async_trait]
+impl<P: FeeDataProvider + Send + Sync> FeeDataProvider for CircuitBreakerProvider<P> {
+    async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        if let Admission::Reject = self.admit() {
+            return self.serve_stale();
+        }
+
+        match self.inner.fetch_latest_fees().await {
+            Ok(points) => {
+                self.record_success();
+                self.lock().last_good = Some(points.clone());
+                Ok(points)
+            }
+            Err(err) => {
+                self.record_failure(&err);
+                if self.state() == CircuitState::Open {
+                    self.serve_stale().map_err(|_| err)
+                } else {
+                    Err(err)
+                }
+            }
+        }
+    }
+
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        if let Admission::Reject = self.admit() {
+            return Err(ProviderError::ServiceUnavailable);
+        }
+
+        let result = self.inner.fetch_fees_range(start_ledger, end_ledger).await;
+        match &result {
+            Ok(_) => self.record_success(),
+            Err(err) => self.record_failure(err),
+        }
+        result
+    }
+
+    async fn fetch_fee_stats(&self) -> ProviderResult<FeeStatsSnapshot> {
+        if self.state() == CircuitState::Open {
+            return Err(ProviderError::ServiceUnavailable);
+        }
+        self.inner.fetch_fee_stats().await
+    }
+
+    async fn fetch_recent_ledgers(&self) -> ProviderResult<Vec<LedgerInfo>> {
+        if self.state() == CircuitState::Open {
+            return Err(ProviderError::ServiceUnavailable);
+        }
+        self.inner.fetch_recent_ledgers().await
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    async fn health_check(&self) -> ProviderResult<()> {
+        if self.state() == CircuitState::Open {
+            return Err(ProviderError::ServiceUnavailable);
+        }
+        self.inner.health_check().await
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        self.inner.get_metadata()
+    }
+
+    async fn refresh_metadata(&self) -> ProviderResult<ProviderMetadata> {
+        self.inner.refresh_metadata().await
+    }
+
+    fn provider_status(&self) -> ProviderStatus {
+        ProviderStatus {
+            circuit_breaker: Some(self.status()),
+            ..self.inner.provider_status()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::mock_horizon::MockHorizonClient;
+    use chrono::Utc;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Provider whose failures can be switched on and off mid-test.
+    #[derive(Clone, Default)]
+    struct FlakyProvider {
+        failing: Arc<AtomicBool>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl FlakyProvider {
+        fn set_failing(&self, failing: bool) {
+            self.failing.store(failing, Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait]
+    impl FeeDataProvider for FlakyProvider {
+        async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(ProviderError::ServiceUnavailable);
+            }
+            Ok(vec![FeeDataPoint {
+                fee_amount: 100,
+                timestamp: Utc::now(),
+                transaction_hash: "hash_100".into(),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            }])
+        }
+
+        fn provider_name(&self) -> &str {
+            "Flaky"
+        }
+    }
+
+    fn config(failure_threshold: u32) -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            failure_threshold,
+            open_duration: chrono::Duration::seconds(30),
+        }
+    }
+
+    #[tokio::test]
+    async fn opens_after_threshold_and_stops_calling_upstream() {
+        let mock = MockHorizonClient::new().with_error(ProviderError::ServiceUnavailable);
+        let calls = mock.call_count.clone();
+        let breaker = CircuitBreakerProvider::new(mock, config(3));
+
+        for _ in 0..5 {
+            assert!(breaker.fetch_latest_fees().await.is_err());
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn serves_last_good_response_while_open() {
+        let flaky = FlakyProvider::default();
+        let breaker = CircuitBreakerProvider::new(flaky.clone(), config(1));
+
+        breaker.fetch_latest_fees().await.unwrap();
+        flaky.set_failing(true);
+
+        let points = breaker.fetch_latest_fees().await.unwrap();
+        assert_eq!(points[0].fee_amount, 100);
+
+        let status = breaker.provider_status().circuit_breaker.unwrap();
+        assert_eq!(status.state, CircuitState::Open);
+        assert!(status.serving_stale);
+        assert_eq!(status.retry_in_seconds, Some(30));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn half_open_trial_closes_circuit_on_success() {
+        let flaky = FlakyProvider::default();
+        let breaker = CircuitBreakerProvider::new(flaky.clone(), config(1));
+
+        flaky.set_failing(true);
+        assert!(breaker.fetch_latest_fees().await.is_err());
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        tokio::time::advance(Duration::from_secs(31)).await;
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        flaky.set_failing(false);
+        breaker.fetch_latest_fees().await.unwrap();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_half_open_trial_reopens_circuit() {
+        let mock = MockHorizonClient::new().with_error(ProviderError::ServiceUnavailable);
+        let calls = mock.call_count.clone();
+        let breaker = CircuitBreakerProvider::new(mock, config(2));
+
+        breaker.fetch_latest_fees().await.ok();
+        breaker.fetch_latest_fees().await.ok();
+        tokio::time::advance(Duration::from_secs(31)).await;
+
+        assert!(breaker.fetch_latest_fees().await.is_err());
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn unsupported_errors_do_not_trip_the_breaker() {
+        let breaker = CircuitBreakerProvider::new(MockHorizonClient::new(), config(1));
+
+        assert!(breaker.fetch_fees_range(1, 10).await.is_err());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
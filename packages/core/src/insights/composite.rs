@@ -0,0 +1,391 @@
+//! Composite Fee Data Provider
+//!
+//! Queries several providers concurrently and merges their results into one
+//! stream, e.g. Horizon for classic transactions plus Soroban RPC for
+//! contract invocations.
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::insights::{
+    error::ProviderError,
+    provider::{FeeDataProvider, ProviderMetadata, ProviderResult, ProviderStatus},
+    types::{FeeDataPoint, FeeStatsSnapshot, LedgerInfo},
+};
+
+/// A single source merged by the composite.
+struct CompositeSource {
+    label: String,
+    provider: Arc<dyn FeeDataProvider + Send + Sync>,
+}
+
+/// Provider that merges the fee data of every source.
+///
+/// Points are deduplicated on `transaction_hash`, keeping the copy from the
+/// earliest-added source and filling in envelope details from later ones.
+/// A fetch succeeds as long as one source does; failing sources are logged
+/// and skipped, and `Unsupported` errors are ignored silently.
+pub struct CompositeFeeDataProvider {
+    sources: Vec<CompositeSource>,
+}
+
+impl CompositeFeeDataProvider {
+    /// Create an empty composite. Add sources with `with_source`.
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+        }
+    }
+
+    /// Add a source; earlier sources win when the same transaction is seen twice.
+    pub fn with_source(
+        mut self,
+        label: impl Into<String>,
+        provider: Arc<dyn FeeDataProvider + Send + Sync>,
+    ) -> Self {
+        self.sources.push(CompositeSource {
+            label: label.into(),
+            provider,
+        });
+        self
+    }
+
+    /// Labels of all configured sources, in priority order.
+    #[allow(dead_code)]
+    pub fn source_labels(&self) -> Vec<&str> {
+        self.sources.iter().map(|s| s.label.as_str()).collect()
+    }
+
+    /// Run `op` against every source concurrently and merge what succeeds.
+    async fn merge_sources<'a, F, Fut>(&'a self, op: F) -> ProviderResult<Vec<FeeDataPoint>>
+    where
+        F: Fn(&'a Arc<dyn FeeDataProvider + Send + Sync>) -> Fut,
+        Fut: Future<Output = ProviderResult<Vec<FeeDataPoint>>>,
+    {
+        let results = join_all(self.sources.iter().map(|source| op(&source.provider))).await;
+
+        let mut batches = Vec::with_capacity(results.len());
+        let mut last_error = None;
+        for (source, result) in self.sources.iter().zip(results) {
+            match result {
+                Ok(points) => batches.push(points),
+                Err(err @ ProviderError::Unsupported { .. }) => last_error = Some(err),
+                Err(err) => {
+                    tracing::warn!("Composite source '{}' failed: {}", source.label, err);
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        if batches.is_empty() {
+            return Err(last_error.unwrap_or(ProviderError::ServiceUnavailable));
+        }
+        Ok(merge_points(batches))
+    }
+}
+
+impl Default for CompositeFeeDataProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Concatenate `batches` in priority order, dropping repeated transactions,
+/// and sort the result by ledger then timestamp.
+fn merge_points(batches: Vec<Vec<FeeDataPoint>>) -> Vec<FeeDataPoint> {
+    let mut merged: Vec<FeeDataPoint> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for point in batches.into_iter().flatten() {
+        match seen.get(&point.transaction_hash) {
+            Some(&index) => {
+                if merged[index].envelope.is_none() {
+                    merged[index].envelope = point.envelope;
+                }
+            }
+            None => {
+                seen.insert(point.transaction_hash.clone(), merged.len());
+                merged.push(point);
+            }
+        }
+    }
+
+    merged.sort_by_key(|point| (point.ledger_sequence, point.timestamp));
+    merged
+}
+
+/// Combined capabilities: historical if any source is, the freshest
+/// source's freshness and the tightest rate limit. Network and ledger info
+/// come from the first source that reports them, except the latest ledger,
+/// which is the highest any source has seen.
+fn combine_metadata(metadata: Vec<ProviderMetadata>) -> ProviderMetadata {
+    if metadata.is_empty() {
+        return ProviderMetadata::default();
+    }
+    ProviderMetadata {
+        supports_historical: metadata.iter().any(|m| m.supports_historical),
+        max_batch_size: metadata.iter().map(|m| m.max_batch_size).max().unwrap_or(0),
+        rate_limit_per_minute: metadata
+            .iter()
+            .filter_map(|m| m.rate_limit_per_minute)
+            .min(),
+        data_freshness_seconds: metadata
+            .iter()
+            .map(|m| m.data_freshness_seconds)
+            .min()
+            .unwrap_or(0),
+        network_passphrase: metadata.iter().find_map(|m| m.network_passphrase.clone()),
+        latest_ledger: metadata.iter().filter_map(|m| m.latest_ledger).max(),
+        horizon_version: metadata.iter().find_map(|m| m.horizon_version.clone()),
+        refreshed_at: metadata.iter().filter_map(|m| m.refreshed_at).min(),
+    }
+}
+
+#[async_trait]
+impl FeeDataProvider for CompositeFeeDataProvider {
+    async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        self.merge_sources(|provider| provider.fetch_latest_fees())
+            .await
+    }
+
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        self.merge_sources(|provider| provider.fetch_fees_range(start_ledger, end_ledger))
+            .await
+    }
+
+    /// Network-wide stats don't merge; the first source that has them wins.
+    async fn fetch_fee_stats(&self) -> ProviderResult<FeeStatsSnapshot> {
+        let mut last_error = ProviderError::ServiceUnavailable;
+        for source in &self.sources {
+            match source.provider.fetch_fee_stats().await {
+                Ok(stats) => return Ok(stats),
+                Err(err) => last_error = err,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Ledgers are network-wide, so the first source that reports them wins.
+    async fn fetch_recent_ledgers(&self) -> ProviderResult<Vec<LedgerInfo>> {
+        let mut last_error = ProviderError::ServiceUnavailable;
+        for source in &self.sources {
+            match source.provider.fetch_recent_ledgers().await {
+                Ok(ledgers) => return Ok(ledgers),
+                Err(err) => last_error = err,
+            }
+        }
+        Err(last_error)
+    }
+
+    fn provider_name(&self) -> &str {
+        "Composite"
+    }
+
+    /// Succeeds when at least one source is healthy.
+    async fn health_check(&self) -> ProviderResult<()> {
+        let results = join_all(self.sources.iter().map(|s| s.provider.health_check())).await;
+        let mut last_error = ProviderError::ServiceUnavailable;
+        let mut healthy = false;
+        for (source, result) in self.sources.iter().zip(results) {
+            match result {
+                Ok(()) => healthy = true,
+                Err(err) => {
+                    tracing::warn!(
+                        "Composite source '{}' failed health check: {}",
+                        source.label,
+                        err
+                    );
+                    last_error = err;
+                }
+            }
+        }
+        if healthy {
+            Ok(())
+        } else {
+            Err(last_error)
+        }
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        combine_metadata(
+            self.sources
+                .iter()
+                .map(|s| s.provider.get_metadata())
+                .collect(),
+        )
+    }
+
+    /// Refreshes every source; a failing source keeps its cached metadata.
+    async fn refresh_metadata(&self) -> ProviderResult<ProviderMetadata> {
+        let results = join_all(self.sources.iter().map(|s| s.provider.refresh_metadata())).await;
+        let metadata = self
+            .sources
+            .iter()
+            .zip(results)
+            .map(|(source, result)| {
+                result.unwrap_or_else(|err| {
+                    tracing::warn!(
+                        "Composite source '{}' failed to refresh metadata: {}",
+                        source.label,
+                        err
+                    );
+                    source.provider.get_metadata()
+                })
+            })
+            .collect();
+        Ok(combine_metadata(metadata))
+    }
+
+    /// Status of the primary source.
+    fn provider_status(&self) -> ProviderStatus {
+        self.sources
+            .first()
+            .map(|s| s.provider.provider_status())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::insights::types::EnvelopeDetails;
+    use crate::services::mock_horizon::MockHorizonClient;
+    use chrono::Utc;
+
+    fn point(hash: &str, ledger: u64, fee_amount: u64) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp: Utc::now(),
+            transaction_hash: hash.to_string(),
+            ledger_sequence: ledger,
+            envelope: None,
+            soroban: None,
+        }
+    }
+
+    fn hashes(points: &[FeeDataPoint]) -> Vec<&str> {
+        points.iter().map(|p| p.transaction_hash.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn merges_sources_and_deduplicates_by_hash() {
+        let classic = Arc::new(
+            MockHorizonClient::new()
+                .with_fees(vec![point("tx_a", 10, 100), point("tx_c", 12, 100)]),
+        );
+        let soroban = Arc::new(
+            MockHorizonClient::new()
+                .with_fees(vec![point("tx_b", 11, 5000), point("tx_a", 10, 999)]),
+        );
+        let provider = CompositeFeeDataProvider::new()
+            .with_source("horizon", classic.clone())
+            .with_source("soroban", soroban.clone());
+
+        let points = provider.fetch_latest_fees().await.unwrap();
+
+        assert_eq!(hashes(&points), vec!["tx_a", "tx_b", "tx_c"]);
+        // The earlier source's copy wins.
+        assert_eq!(points[0].fee_amount, 100);
+        assert_eq!(classic.calls(), 1);
+        assert_eq!(soroban.calls(), 1);
+    }
+
+    #[test]
+    fn duplicates_contribute_missing_envelope_details() {
+        let mut enriched = point("tx_a", 10, 100);
+        enriched.envelope = Some(EnvelopeDetails {
+            operation_count: 3,
+            fee_bump: false,
+            max_fee: 300,
+            inner_fee: None,
+            category: None,
+            fee_account: None,
+        });
+
+        let merged = merge_points(vec![vec![point("tx_a", 10, 100)], vec![enriched]]);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].envelope.as_ref().unwrap().operation_count, 3);
+    }
+
+    #[tokio::test]
+    async fn failing_source_is_skipped() {
+        let provider = CompositeFeeDataProvider::new()
+            .with_source(
+                "horizon",
+                Arc::new(MockHorizonClient::new().with_error(ProviderError::ServiceUnavailable)),
+            )
+            .with_source(
+                "soroban",
+                Arc::new(MockHorizonClient::new().with_fees(vec![point("tx_b", 11, 5000)])),
+            );
+
+        let points = provider.fetch_latest_fees().await.unwrap();
+        assert_eq!(hashes(&points), vec!["tx_b"]);
+    }
+
+    #[tokio::test]
+    async fn all_sources_failing_returns_an_error() {
+        let provider = CompositeFeeDataProvider::new()
+            .with_source(
+                "horizon",
+                Arc::new(MockHorizonClient::new().with_error(ProviderError::ServiceUnavailable)),
+            )
+            .with_source(
+                "soroban",
+                Arc::new(MockHorizonClient::new().with_error(ProviderError::RateLimitExceeded)),
+            );
+
+        assert!(provider.fetch_latest_fees().await.is_err());
+        assert!(CompositeFeeDataProvider::new()
+            .fetch_latest_fees()
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn healthy_while_any_source_is_up() {
+        let provider = CompositeFeeDataProvider::new()
+            .with_source(
+                "horizon",
+                Arc::new(MockHorizonClient::new().with_healthy(false)),
+            )
+            .with_source(
+                "soroban",
+                Arc::new(MockHorizonClient::new().with_healthy(true)),
+            );
+
+        assert!(provider.health_check().await.is_ok());
+        assert_eq!(provider.source_labels(), vec!["horizon", "soroban"]);
+    }
+
+    #[test]
+    fn combined_metadata_takes_highest_ledger_and_first_passphrase() {
+        let horizon = ProviderMetadata {
+            network_passphrase: Some("Public Global Stellar Network ; September 2015".into()),
+            latest_ledger: Some(100),
+            horizon_version: Some("2.32.0".into()),
+            ..ProviderMetadata::default()
+        };
+        let soroban = ProviderMetadata {
+            network_passphrase: Some("other".into()),
+            latest_ledger: Some(102),
+            ..ProviderMetadata::default()
+        };
+
+        let combined = combine_metadata(vec![horizon, soroban]);
+        assert_eq!(combined.latest_ledger, Some(102));
+        assert_eq!(
+            combined.network_passphrase.as_deref(),
+            Some("Public Global Stellar Network ; September 2015")
+        );
+        assert_eq!(combined.horizon_version.as_deref(), Some("2.32.0"));
+    }
+}
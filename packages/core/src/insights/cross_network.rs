@@ -0,0 +1,154 @@
+//! Side-by-side insights across tracked networks
+//!
+//! Each network runs its own engine; this lines their headline numbers up
+//! in one structure so pubnet and testnet can be read together. Fees are
+//! also given relative to the primary network, and an anomaly that only
+//! one network shows is flagged as isolated — usually a testnet quirk
+//! rather than a market-wide move.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::StellarNetwork;
+use crate::insights::{engine::FeeInsightsEngine, types::*};
+
+/// Headline insights for one network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkInsights {
+    pub network: StellarNetwork,
+    pub primary: bool,
+    /// 1-hour rolling average fee, in stroops.
+    pub short_term_avg: f64,
+    /// 24-hour rolling average fee, in stroops.
+    pub long_term_avg: f64,
+    /// `short_term_avg` over the primary network's; `None` when the
+    /// primary has no recent fees.
+    pub fee_ratio_to_primary: Option<f64>,
+    pub congestion_state: CongestionState,
+    pub recommendation: FeeRecommendation,
+    /// `false` also before any classic transaction has been seen.
+    pub surge_pricing_active: bool,
+    /// The latest batch deviated from this network's own history.
+    pub anomaly: bool,
+    /// Anomalous while no other network is.
+    pub isolated_anomaly: bool,
+    /// `None` before the engine has processed any fees.
+    pub last_updated: Option<DateTime<Utc>>,
+}
+
+/// Insights for every tracked network, primary first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossNetworkComparison {
+    pub networks: Vec<NetworkInsights>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Line up `engines` side by side; the first is the primary network.
+pub fn compare_networks(engines: &[&FeeInsightsEngine]) -> CrossNetworkComparison {
+    let mut networks: Vec<NetworkInsights> = engines
+        .iter()
+        .enumerate()
+        .map(|(i, engine)| {
+            let averages = engine.get_rolling_averages();
+            NetworkInsights {
+                network: engine.get_config().network,
+                primary: i == 0,
+                short_term_avg: averages.short_term.value,
+                long_term_avg: averages.long_term.value,
+                fee_ratio_to_primary: None,
+                congestion_state: engine.get_congestion_trends().congestion_state,
+                recommendation: engine.get_fee_recommendation(),
+                surge_pricing_active: engine.get_surge_pricing().is_some_and(|s| s.active),
+                anomaly: engine.get_current_insights().anomalies.is_anomaly,
+                isolated_anomaly: false,
+                last_updated: engine.get_last_update(),
+            }
+        })
+        .collect();
+
+    let primary_avg = networks.first().map_or(0.0, |n| n.short_term_avg);
+    let anomalous = networks.iter().filter(|n| n.anomaly).count();
+    for network in &mut networks {
+        network.fee_ratio_to_primary =
+            (primary_avg > 0.0).then(|| network.short_term_avg / primary_avg);
+        network.isolated_anomaly = network.anomaly && anomalous == 1 && engines.len() > 1;
+    }
+
+    CrossNetworkComparison {
+        networks,
+        generated_at: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::insights::InsightsConfig;
+
+    fn feed(engine: &mut FeeInsightsEngine, fee_amount: u64, count: u64) {
+        let now = Utc::now();
+        let points: Vec<FeeDataPoint> = (0..count)
+            .map(|i| FeeDataPoint {
+                fee_amount,
+                timestamp: now - chrono::Duration::seconds(5 * (count - i) as i64),
+                transaction_hash: format!("{}_{}", fee_amount, i),
+                ledger_sequence: i + 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        tokio_test::block_on(engine.process_fee_data(&points)).unwrap();
+    }
+
+    fn engine_with(network: StellarNetwork, fee_amount: u64) -> FeeInsightsEngine {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig {
+            network,
+            ..InsightsConfig::default()
+        });
+        feed(&mut engine, fee_amount, 40);
+        engine
+    }
+
+    #[test]
+    fn lines_networks_up_against_the_primary() {
+        let mainnet = engine_with(StellarNetwork::Mainnet, 400);
+        let testnet = engine_with(StellarNetwork::Testnet, 100);
+
+        let comparison = compare_networks(&[&mainnet, &testnet]);
+        let [primary, other] = comparison.networks.as_slice() else {
+            panic!("expected two networks");
+        };
+        assert_eq!(primary.network, StellarNetwork::Mainnet);
+        assert!(primary.primary && !other.primary);
+        assert_eq!(primary.fee_ratio_to_primary, Some(1.0));
+        assert_eq!(other.fee_ratio_to_primary, Some(0.25));
+        assert!(other.last_updated.is_some());
+    }
+
+    #[test]
+    fn no_ratio_without_primary_fees() {
+        let mainnet = FeeInsightsEngine::new(InsightsConfig::default());
+        let testnet = engine_with(StellarNetwork::Testnet, 100);
+
+        let comparison = compare_networks(&[&mainnet, &testnet]);
+        assert_eq!(comparison.networks[1].fee_ratio_to_primary, None);
+        assert!(comparison.networks[0].last_updated.is_none());
+        assert!(!comparison.networks[1].isolated_anomaly);
+    }
+
+    #[test]
+    fn flags_an_anomaly_only_one_network_shows() {
+        let mainnet = engine_with(StellarNetwork::Mainnet, 100);
+        let mut testnet = engine_with(StellarNetwork::Testnet, 100);
+        feed(&mut testnet, 50_000, 10);
+
+        let comparison = compare_networks(&[&mainnet, &testnet]);
+        assert!(!comparison.networks[0].anomaly);
+        assert!(comparison.networks[1].anomaly);
+        assert!(comparison.networks[1].isolated_anomaly);
+
+        // Alone, a network has nothing to be isolated from
+        let comparison = compare_networks(&[&testnet]);
+        assert!(!comparison.networks[0].isolated_anomaly);
+    }
+}
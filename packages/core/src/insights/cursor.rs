@@ -0,0 +1,32 @@
+//! Ingestion Cursor Persistence
+//!
+//! Lets a provider remember how far it has read across restarts. The
+//! storage backend (the SQLite repository in production) implements
+//! [`CursorStore`]; providers only see the trait.
+
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::insights::error::InsightsError;
+
+/// Position in the upstream transaction stream
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PagingCursor {
+    /// Opaque paging token of the last processed transaction.
+    pub paging_token: String,
+    /// Ledger that transaction closed in.
+    pub ledger_sequence: u64,
+}
+
+/// Durable storage for a provider's `PagingCursor`
+#[async_trait]
+pub trait CursorStore {
+    /// The last saved cursor, or `None` if nothing was ever saved.
+    async fn load_cursor(&self) -> Result<Option<PagingCursor>, InsightsError>;
+
+    /// Replace the saved cursor.
+    async fn save_cursor(&self, cursor: &PagingCursor) -> Result<(), InsightsError>;
+}
+
+/// Shared, thread-safe handle to a cursor store
+pub type SharedCursorStore = std::sync::Arc<dyn CursorStore + Send + Sync>;
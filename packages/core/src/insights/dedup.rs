@@ -0,0 +1,210 @@
+//! Deduplicating Fee Data Provider
+//!
+//! Decorator that drops transactions already returned by an earlier
+//! `fetch_latest_fees` call. Consecutive polls of "the latest N transactions"
+//! overlap whenever fewer than N transactions close between them, and a
+//! cached or stale response repeats a whole batch; without this layer those
+//! repeats are ingested twice and skew counts and averages.
+
+use async_trait::async_trait;
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::insights::{
+    provider::{FeeDataProvider, ProviderMetadata, ProviderResult, ProviderStatus},
+    types::{FeeDataPoint, FeeStatsSnapshot, LedgerInfo},
+};
+
+/// Transaction hashes seen within the window, oldest first.
+#[derive(Default)]
+struct SeenHashes {
+    hashes: HashSet<String>,
+    order: VecDeque<(Instant, String)>,
+}
+
+impl SeenHashes {
+    /// Forget hashes first seen more than `window` ago.
+    fn evict_older_than(&mut self, window: Duration, now: Instant) {
+        while let Some((seen_at, _)) = self.order.front() {
+            if now.duration_since(*seen_at) <= window {
+                break;
+            }
+            if let Some((_, hash)) = self.order.pop_front() {
+                self.hashes.remove(&hash);
+            }
+        }
+    }
+
+    /// Record `hash`; returns `false` if it was already seen.
+    fn insert(&mut self, hash: &str, now: Instant) -> bool {
+        if !self.hashes.insert(hash.to_string()) {
+            return false;
+        }
+        self.order.push_back((now, hash.to_string()));
+        true
+    }
+}
+
+/// Wraps a provider and filters `fetch_latest_fees` down to transactions
+/// not returned within the last `window`.
+///
+/// A hash is remembered from the first time it is seen, so the window should
+/// comfortably exceed the time a transaction can stay in the upstream's
+/// "latest" page. Range fetches pass through untouched: backfills ask for
+/// explicit ledgers and are deduplicated by the storage layer.
+pub struct DedupProvider<P: FeeDataProvider> {
+    inner: P,
+    window: Duration,
+    seen: Mutex<SeenHashes>,
+    duplicates_dropped: AtomicU64,
+}
+
+impl<P: FeeDataProvider> DedupProvider<P> {
+    pub fn new(inner: P, window: Duration) -> Self {
+        Self {
+            inner,
+            window,
+            seen: Mutex::new(SeenHashes::default()),
+            duplicates_dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Total points dropped as duplicates since construction.
+    #[allow(dead_code)]
+    pub fn duplicates_dropped(&self) -> u64 {
+        self.duplicates_dropped.load(Ordering::Relaxed)
+    }
+
+    /// Number of hashes currently remembered.
+    #[allow(dead_code)]
+    pub fn tracked(&self) -> usize {
+        self.seen.lock().expect("dedup lock poisoned").order.len()
+    }
+
+    /// Keep the points of `points` not seen within the window, dropping
+    /// repeats inside the batch too.
+    fn filter_new(&self, points: Vec<FeeDataPoint>) -> Vec<FeeDataPoint> {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().expect("dedup lock poisoned");
+        seen.evict_older_than(self.window, now);
+
+        let total = points.len();
+        let fresh: Vec<FeeDataPoint> = points
+            .into_iter()
+            .filter(|point| seen.insert(&point.transaction_hash, now))
+            .collect();
+
+        let dropped = (total - fresh.len()) as u64;
+        if dropped > 0 {
+            tracing::debug!("Dropped {} duplicate fee data point(s)", dropped);
+            self.duplicates_dropped
+                .fetch_add(dropped, Ordering::Relaxed);
+        }
+        fresh
+    }
+}
+
+#[async_trait]
+impl<P: FeeDataProvider + Send + Sync> FeeDataProvider for DedupProvider<P> {
+    async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        let points = self.inner.fetch_latest_fees().await?;
+        Ok(self.filter_new(points))
+    }
+
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        self.inner.fetch_fees_range(start_ledger, end_ledger).await
+    }
+
+    async fn fetch_fee_stats(&self) -> ProviderResult<FeeStatsSnapshot> {
+        self.inner.fetch_fee_stats().await
+    }
+
+    async fn fetch_recent_ledgers(&self) -> ProviderResult<Vec<LedgerInfo>> {
+        self.inner.fetch_recent_ledgers().await
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    async fn health_check(&self) -> ProviderResult<()> {
+        self.inner.health_check().await
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        self.inner.get_metadata()
+    }
+
+    async fn refresh_metadata(&self) -> ProviderResult<ProviderMetadata> {
+        self.inner.refresh_metadata().await
+    }
+
+    fn provider_status(&self) -> ProviderStatus {
+        self.inner.provider_status()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::mock_horizon::MockHorizonClient;
+    use chrono::Utc;
+
+    fn point(hash: &str) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount: 100,
+            timestamp: Utc::now(),
+            transaction_hash: hash.to_string(),
+            ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
+        }
+    }
+
+    fn hashes(points: &[FeeDataPoint]) -> Vec<&str> {
+        points.iter().map(|p| p.transaction_hash.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn repeated_fetches_only_return_new_transactions() {
+        let mock = MockHorizonClient::new().with_fees(vec![point("a"), point("b")]);
+        let provider = DedupProvider::new(mock, Duration::from_secs(60));
+
+        let first = provider.fetch_latest_fees().await.unwrap();
+        assert_eq!(hashes(&first), vec!["a", "b"]);
+
+        let second = provider.fetch_latest_fees().await.unwrap();
+        assert!(second.is_empty());
+        assert_eq!(provider.duplicates_dropped(), 2);
+    }
+
+    #[test]
+    fn duplicates_within_one_batch_are_dropped() {
+        let provider = DedupProvider::new(MockHorizonClient::new(), Duration::from_secs(60));
+        let fresh = provider.filter_new(vec![point("a"), point("a"), point("b")]);
+        assert_eq!(hashes(&fresh), vec!["a", "b"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hashes_are_forgotten_after_the_window() {
+        let provider = DedupProvider::new(MockHorizonClient::new(), Duration::from_secs(60));
+        assert_eq!(provider.filter_new(vec![point("a")]).len(), 1);
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert!(provider.filter_new(vec![point("a")]).is_empty());
+        assert_eq!(provider.filter_new(vec![point("b")]).len(), 1);
+
+        // "a" was first seen 61s ago; "b" only 31s ago.
+        tokio::time::advance(Duration::from_secs(31)).await;
+        assert_eq!(provider.filter_new(vec![point("a")]).len(), 1);
+        assert!(provider.filter_new(vec![point("b")]).is_empty());
+        assert_eq!(provider.tracked(), 2);
+    }
+}
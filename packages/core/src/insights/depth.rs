@@ -0,0 +1,239 @@
+//! Fee market depth
+//!
+//! Under surge pricing a ledger takes the highest per-operation bids first,
+//! so a bid is priced out when enough of the others outbid it. Counting how
+//! many recent bids sit at or above each fee level gives an order-book-like
+//! view of that demand.
+
+use chrono::Utc;
+use std::collections::VecDeque;
+
+use crate::insights::{config::MarketDepthConfig, types::*};
+
+/// Per-operation bids seen in one ledger
+#[derive(Debug, Clone)]
+struct LedgerBids {
+    sequence: u64,
+    bids: Vec<u64>,
+}
+
+/// Tracks the spread of fee bids over recent ledgers
+pub struct MarketDepthTracker {
+    config: MarketDepthConfig,
+    ledgers: VecDeque<LedgerBids>,
+}
+
+impl MarketDepthTracker {
+    /// Create a new market depth tracker
+    pub fn new(config: MarketDepthConfig) -> Self {
+        Self {
+            config,
+            ledgers: VecDeque::new(),
+        }
+    }
+
+    /// Record each transaction's bid in its ledger. Soroban bids are skipped
+    /// since their max fee includes the resource fee.
+    pub fn add_data_points(&mut self, points: &[FeeDataPoint]) {
+        for point in points.iter().filter(|p| p.soroban.is_none()) {
+            if let Some(bid) = bid_per_operation(point) {
+                self.add_bid(point.ledger_sequence, bid);
+            }
+        }
+    }
+
+    fn add_bid(&mut self, sequence: u64, bid: u64) {
+        let position = self
+            .ledgers
+            .iter()
+            .rposition(|ledger| ledger.sequence <= sequence);
+
+        match position {
+            Some(i) if self.ledgers[i].sequence == sequence => self.ledgers[i].bids.push(bid),
+            _ => {
+                // Too old to matter once the buffer is full
+                if position.is_none() && self.ledgers.len() >= self.config.max_ledgers {
+                    return;
+                }
+                let insert_at = position.map_or(0, |i| i + 1);
+                self.ledgers.insert(
+                    insert_at,
+                    LedgerBids {
+                        sequence,
+                        bids: vec![bid],
+                    },
+                );
+                while self.ledgers.len() > self.config.max_ledgers {
+                    self.ledgers.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Bids at or above log-spaced levels between the lowest and highest
+    /// bid, plus where `fee` would have stood among them.
+    pub fn depth(&self, fee: Option<u64>) -> MarketDepth {
+        let mut bids: Vec<u64> = self
+            .ledgers
+            .iter()
+            .flat_map(|ledger| ledger.bids.iter().copied())
+            .collect();
+        bids.sort_unstable();
+
+        let ledgers = self.ledgers.len();
+        let per_ledger = |count: usize| {
+            if ledgers > 0 {
+                count as f64 / ledgers as f64
+            } else {
+                0.0
+            }
+        };
+        let share = |count: usize| {
+            if bids.is_empty() {
+                0.0
+            } else {
+                count as f64 / bids.len() as f64
+            }
+        };
+        let at_or_above = |fee: u64| bids.len() - bids.partition_point(|&bid| bid < fee);
+
+        let levels = level_fees(&bids, self.config.levels)
+            .into_iter()
+            .map(|bid_at_least| {
+                let count = at_or_above(bid_at_least);
+                DepthLevel {
+                    bid_at_least,
+                    count,
+                    per_ledger: per_ledger(count),
+                    share: share(count),
+                }
+            })
+            .collect();
+
+        let position = fee.map(|fee| {
+            let outbid_by = bids.len() - bids.partition_point(|&bid| bid <= fee);
+            BidPosition {
+                fee,
+                outbid_by,
+                outbid_per_ledger: per_ledger(outbid_by),
+                share_outbid: share(outbid_by),
+            }
+        });
+
+        MarketDepth {
+            ledgers,
+            bid_count: bids.len(),
+            levels,
+            position,
+            generated_at: Utc::now(),
+        }
+    }
+}
+
+/// Bid per operation, since that is what surge pricing ranks by.
+fn bid_per_operation(point: &FeeDataPoint) -> Option<u64> {
+    let max_fee = point.max_fee()?;
+    Some(max_fee / u64::from(point.operation_count().unwrap_or(1)))
+}
+
+/// Up to `levels` distinct fees, log-spaced from the lowest to the highest
+/// of the sorted `bids`.
+fn level_fees(bids: &[u64], levels: usize) -> Vec<u64> {
+    let (Some(&low), Some(&high)) = (bids.first(), bids.last()) else {
+        return Vec::new();
+    };
+    if levels <= 1 || low == high {
+        return vec![low];
+    }
+
+    let (low_ln, high_ln) = ((low.max(1) as f64).ln(), (high as f64).ln());
+    let step = (high_ln - low_ln) / (levels - 1) as f64;
+    let mut fees: Vec<u64> = (0..levels)
+        .map(|i| (low_ln + step * i as f64).exp().round() as u64)
+        .map(|fee| fee.clamp(low, high))
+        .collect();
+    fees.dedup();
+    fees
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(sequence: u64, max_fee: u64, operation_count: u32) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount: 100,
+            timestamp: Utc::now(),
+            transaction_hash: format!("hash_{}_{}", sequence, max_fee),
+            ledger_sequence: sequence,
+            envelope: Some(EnvelopeDetails {
+                operation_count,
+                fee_bump: false,
+                max_fee,
+                inner_fee: None,
+                category: None,
+                fee_account: None,
+            }),
+            soroban: None,
+        }
+    }
+
+    #[test]
+    fn counts_bids_at_or_above_each_level() {
+        let mut tracker = MarketDepthTracker::new(MarketDepthConfig {
+            max_ledgers: 10,
+            levels: 3,
+        });
+        tracker.add_data_points(&[
+            point(1, 100, 1),
+            point(1, 1_000, 1),
+            point(2, 10_000, 1),
+            // 5_000 over five operations bids 1_000 each
+            point(2, 5_000, 5),
+        ]);
+
+        let depth = tracker.depth(Some(999));
+        assert_eq!(depth.ledgers, 2);
+        assert_eq!(depth.bid_count, 4);
+        let levels: Vec<(u64, usize)> = depth
+            .levels
+            .iter()
+            .map(|level| (level.bid_at_least, level.count))
+            .collect();
+        assert_eq!(levels, vec![(100, 4), (1_000, 3), (10_000, 1)]);
+        assert_eq!(depth.levels[1].per_ledger, 1.5);
+        assert_eq!(depth.levels[1].share, 0.75);
+
+        let position = depth.position.unwrap();
+        assert_eq!(position.outbid_by, 3);
+        assert_eq!(position.outbid_per_ledger, 1.5);
+        assert_eq!(tracker.depth(Some(1_000)).position.unwrap().outbid_by, 1);
+    }
+
+    #[test]
+    fn bids_without_an_envelope_are_skipped() {
+        let mut tracker = MarketDepthTracker::new(MarketDepthConfig::default());
+        let mut unknown = point(1, 500, 1);
+        unknown.envelope = None;
+        tracker.add_data_points(&[unknown]);
+
+        let depth = tracker.depth(Some(100));
+        assert_eq!(depth.bid_count, 0);
+        assert!(depth.levels.is_empty());
+        assert_eq!(depth.position.unwrap().share_outbid, 0.0);
+    }
+
+    #[test]
+    fn oldest_ledgers_are_dropped_past_capacity() {
+        let mut tracker = MarketDepthTracker::new(MarketDepthConfig {
+            max_ledgers: 2,
+            levels: 20,
+        });
+        for sequence in [3, 1, 2, 1] {
+            tracker.add_bid(sequence, 100);
+        }
+
+        let sequences: Vec<u64> = tracker.ledgers.iter().map(|l| l.sequence).collect();
+        assert_eq!(sequences, vec![2, 3]);
+    }
+}
@@ -1,9 +1,17 @@
 //! Congestion Detection System
 
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use std::collections::VecDeque;
+use tokio::sync::broadcast;
 
-use crate::insights::{config::SpikeConfig, error::InsightsError, types::*};
+use crate::insights::{
+    config::{CongestionStateConfig, SpikeConfig},
+    error::InsightsError,
+    types::*,
+};
+
+/// Ledgers averaged for capacity utilization (about 50 seconds of closes).
+const CAPACITY_SAMPLE_LEDGERS: usize = 10;
 
 /// Analyzer for trend patterns
 #[derive(Debug, Clone)]
@@ -130,11 +138,96 @@ impl TrendAnalyzer {
     }
 }
 
+/// Normal → Elevated → Congested state machine with hysteresis
+#[derive(Debug, Clone)]
+pub struct CongestionStateMachine {
+    config: CongestionStateConfig,
+    state: CongestionState,
+    /// When `state` was entered; `None` until the first transition.
+    entered_at: Option<DateTime<Utc>>,
+}
+
+impl CongestionStateMachine {
+    /// Create a state machine starting in `Normal`
+    pub fn new(config: CongestionStateConfig) -> Self {
+        Self {
+            config,
+            state: CongestionState::Normal,
+            entered_at: None,
+        }
+    }
+
+    /// Current state
+    pub fn state(&self) -> CongestionState {
+        self.state
+    }
+
+    /// Feed the latest fee-to-baseline ratio. `capacity_congested` forces
+    /// `Congested` whatever the ratio. Returns the transition, if one happened.
+    pub fn observe(
+        &mut self,
+        fee_ratio: f64,
+        capacity_congested: bool,
+        at: DateTime<Utc>,
+    ) -> Option<CongestionTransition> {
+        let config = &self.config;
+        let target = match self.state {
+            _ if capacity_congested => CongestionState::Congested,
+            CongestionState::Normal | CongestionState::Elevated
+                if fee_ratio >= config.congested_enter_ratio =>
+            {
+                CongestionState::Congested
+            }
+            CongestionState::Normal if fee_ratio >= config.elevated_enter_ratio => {
+                CongestionState::Elevated
+            }
+            CongestionState::Elevated | CongestionState::Congested
+                if fee_ratio < config.elevated_exit_ratio =>
+            {
+                CongestionState::Normal
+            }
+            CongestionState::Congested if fee_ratio < config.congested_exit_ratio => {
+                CongestionState::Elevated
+            }
+            state => state,
+        };
+
+        if target == self.state {
+            return None;
+        }
+        if let Some(entered_at) = self.entered_at {
+            if at - entered_at < config.min_dwell {
+                return None;
+            }
+        }
+
+        let transition = CongestionTransition {
+            from: self.state,
+            to: target,
+            at,
+            fee_ratio,
+            capacity_congested,
+        };
+        self.state = target;
+        self.entered_at = Some(at);
+        Some(transition)
+    }
+}
+
 /// Detector for network congestion through fee spike analysis
 pub struct CongestionDetector {
     config: SpikeConfig,
     trend_analyzer: TrendAnalyzer,
     historical_spikes: VecDeque<FeeSpike>,
+    /// Most recent ledgers, oldest first.
+    recent_ledgers: VecDeque<LedgerInfo>,
+    state_machine: CongestionStateMachine,
+    /// Transitions not yet collected by `take_state_transitions`.
+    pending_transitions: Vec<CongestionTransition>,
+    /// Mean fee over baseline of the latest batch.
+    last_fee_ratio: Option<f64>,
+    /// Where state changes are published as they happen.
+    events: Option<broadcast::Sender<InsightEvent>>,
 }
 
 impl CongestionDetector {
@@ -142,19 +235,124 @@ impl CongestionDetector {
     pub fn new(config: SpikeConfig) -> Self {
         Self {
             trend_analyzer: TrendAnalyzer::new(config.congestion_window),
+            state_machine: CongestionStateMachine::new(config.states.clone()),
             config,
             historical_spikes: VecDeque::new(),
+            recent_ledgers: VecDeque::new(),
+            pending_transitions: Vec::new(),
+            last_fee_ratio: None,
+            events: None,
         }
     }
 
+    /// Publish congestion state changes on `events`.
+    pub fn with_events(mut self, events: broadcast::Sender<InsightEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Swap in new thresholds, keeping spike history and the current state.
+    pub fn set_config(&mut self, config: SpikeConfig) {
+        self.trend_analyzer.congestion_window = config.congestion_window;
+        self.state_machine.config = config.states.clone();
+        self.config = config;
+    }
+
+    /// Current debounced congestion state.
+    pub fn congestion_state(&self) -> CongestionState {
+        self.state_machine.state()
+    }
+
+    /// Mean fee over baseline of the latest batch; `None` before one had a
+    /// baseline.
+    pub fn last_fee_ratio(&self) -> Option<f64> {
+        self.last_fee_ratio
+    }
+
+    /// Drain the state transitions observed since the last call.
+    pub fn take_state_transitions(&mut self) -> Vec<CongestionTransition> {
+        std::mem::take(&mut self.pending_transitions)
+    }
+
+    /// Track closed ledgers for capacity-based congestion detection. Ledgers
+    /// may arrive in any order and overlap earlier calls.
+    pub fn record_ledgers(&mut self, ledgers: &[LedgerInfo]) {
+        for ledger in ledgers {
+            self.recent_ledgers
+                .retain(|known| known.sequence != ledger.sequence);
+            self.recent_ledgers.push_back(ledger.clone());
+        }
+        self.recent_ledgers
+            .make_contiguous()
+            .sort_by_key(|ledger| ledger.sequence);
+
+        while self.recent_ledgers.len() > CAPACITY_SAMPLE_LEDGERS {
+            self.recent_ledgers.pop_front();
+        }
+    }
+
+    /// Average capacity utilization of the tracked ledgers, or `None` when no
+    /// ledger with a known capacity has been recorded.
+    pub fn capacity_utilization(&self) -> Option<f64> {
+        let utilizations: Vec<f64> = self
+            .recent_ledgers
+            .iter()
+            .filter_map(LedgerInfo::capacity_utilization)
+            .collect();
+        if utilizations.is_empty() {
+            return None;
+        }
+        Some(utilizations.iter().sum::<f64>() / utilizations.len() as f64)
+    }
+
+    /// Capacity and price pressure weighted per `CongestionSignalConfig`, or
+    /// `None` until a ledger with a known capacity has been recorded.
+    pub fn congestion_score(&self) -> Option<f64> {
+        let capacity_threshold = self.config.capacity_congestion_threshold;
+        let capacity_pressure = self
+            .capacity_utilization()
+            .map(|u| (u / capacity_threshold.max(f64::EPSILON)).clamp(0.0, 1.0))?;
+
+        // Ratio 1.0 (fees at baseline) is no pressure; the congested enter
+        // ratio is full pressure.
+        let congested_ratio = self.config.states.congested_enter_ratio;
+        let price_pressure = self.last_fee_ratio.map_or(0.0, |ratio| {
+            ((ratio - 1.0) / (congested_ratio - 1.0).max(f64::EPSILON)).clamp(0.0, 1.0)
+        });
+
+        let signals = &self.config.signals;
+        let total_weight = signals.capacity_weight + signals.price_weight;
+        if total_weight <= 0.0 {
+            return None;
+        }
+        Some(
+            (signals.capacity_weight * capacity_pressure + signals.price_weight * price_pressure)
+                / total_weight,
+        )
+    }
+
+    /// Whether capacity and price pressure together call the network
+    /// congested.
+    pub fn is_congested(&self) -> bool {
+        self.congestion_score()
+            .is_some_and(|score| score >= self.config.signals.score_threshold)
+    }
+
     /// Analyze congestion patterns
     pub fn analyze_congestion(
         &mut self,
         current_fees: &[FeeDataPoint],
         baseline: f64,
     ) -> Result<CongestionTrends, InsightsError> {
+        // Too small a batch says nothing about spikes or state
+        let judged = current_fees.len() >= self.config.min_samples;
+
         // Detect new spikes in the current fee data
-        let new_spikes = self.detect_spikes(current_fees, baseline)?;
+        let new_spikes = if judged {
+            self.detect_spikes(current_fees, baseline)?
+        } else {
+            Vec::new()
+        };
 
         // Add new spikes to the trend analyzer
         for spike in &new_spikes {
@@ -170,8 +368,42 @@ impl CongestionDetector {
         // Clean old spikes from trend analyzer
         self.trend_analyzer.clean_old_spikes();
 
-        // Calculate current trend indicators
-        let current_trend = self.trend_analyzer.determine_trend_indicator();
+        // Advance the congestion state machine on the batch's mean fee
+        if let Some(latest) = current_fees.iter().map(|p| p.timestamp).max() {
+            if judged && baseline > 0.0 {
+                let mean_fee = current_fees
+                    .iter()
+                    .map(|p| p.fee_amount as f64)
+                    .sum::<f64>()
+                    / current_fees.len() as f64;
+                let fee_ratio = mean_fee / baseline;
+                self.last_fee_ratio = Some(fee_ratio);
+                let capacity_congested = self.is_congested();
+                if let Some(transition) =
+                    self.state_machine
+                        .observe(fee_ratio, capacity_congested, latest)
+                {
+                    if let Some(events) = &self.events {
+                        let event = if transition.to > transition.from {
+                            InsightEvent::CongestionEntered(transition.clone())
+                        } else {
+                            InsightEvent::CongestionCleared(transition.clone())
+                        };
+                        // Nobody listening is fine
+                        let _ = events.send(event);
+                    }
+                    self.pending_transitions.push(transition);
+                }
+            }
+        }
+
+        // Calculate current trend indicators; capacity pressure backed by
+        // surge pricing means congestion even without fee spikes.
+        let current_trend = if self.is_congested() {
+            TrendIndicator::Congested
+        } else {
+            self.trend_analyzer.determine_trend_indicator()
+        };
         let trend_strength = self.trend_analyzer.calculate_trend_strength();
         let predicted_duration = self.trend_analyzer.predict_duration();
 
@@ -184,6 +416,10 @@ impl CongestionDetector {
             recent_spikes,
             trend_strength,
             predicted_duration,
+            capacity_utilization: self.capacity_utilization(),
+            congestion_score: self.congestion_score(),
+            congestion_state: self.congestion_state(),
+            seasonal_ratio: None,
         })
     }
 
@@ -1,16 +1,69 @@
 //! Fee Insights Engine - Central orchestrator for fee analysis
 
 use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use std::time::Instant;
+use tokio::sync::broadcast;
 
 use crate::insights::{
+    anomaly::AnomalyDetector,
+    budget::BudgetTracker,
     calculator::RollingAverageCalculator,
-    config::{AverageConfig, ExtremesConfig, InsightsConfig},
+    config::{AverageConfig, ExtremesConfig, InsightsConfig, SpikeConfig},
+    depth::MarketDepthTracker,
     detector::CongestionDetector,
     error::InsightsError,
+    forecaster::FeeForecaster,
+    inclusion::InclusionEstimator,
+    seasonality::SeasonalityAnalyzer,
+    soroban_pricing::resource_fee,
+    surge::SurgeTracker,
+    surge_pricing::SurgePricingTracker,
     tracker::ExtremesTracker,
     types::*,
 };
+use crate::repository::{FeeRepository, PointFilter};
+
+/// Largest relative gap between our short-term average and Horizon's p50
+/// that still counts as consistent. Means and medians of skewed fee
+/// distributions differ, so this is deliberately loose.
+const FEE_STATS_DIVERGENCE_TOLERANCE: f64 = 0.5;
+
+/// Network minimum fee per operation, in stroops.
+const MIN_BASE_FEE: u64 = 100;
+
+/// Events buffered per subscriber before the slowest starts missing some.
+const EVENT_CAPACITY: usize = 256;
+
+/// Per-window calculator settings taken from `config`.
+fn average_config(config: &InsightsConfig) -> AverageConfig {
+    AverageConfig {
+        histogram: config.histogram.clone(),
+        trend: config.trend.clone(),
+        outlier_filter: config.outlier_filter.clone(),
+        percentile_method: config.percentile_method.clone(),
+        ..AverageConfig::default()
+    }
+}
+
+/// A custom metric computed in the engine's pipeline
+///
+/// Registered calculators run after the built-in analysis of every batch,
+/// in registration order. Their output appears in
+/// [`CurrentInsights::custom`] under their name.
+pub trait InsightCalculator: Send + Sync {
+    /// Key the metric is published under; unique within an engine.
+    fn name(&self) -> &str;
+
+    /// Fold in a validated batch and return the metric's latest value.
+    /// `insights` holds the built-in results for the same batch.
+    fn process(
+        &mut self,
+        data: &[FeeDataPoint],
+        insights: &CurrentInsights,
+    ) -> Result<serde_json::Value, InsightsError>;
+}
 
 /// Central fee insights engine that orchestrates all analysis operations
 pub struct FeeInsightsEngine {
@@ -18,30 +71,112 @@ pub struct FeeInsightsEngine {
     calculator: RollingAverageCalculator,
     tracker: ExtremesTracker,
     detector: CongestionDetector,
+    forecaster: FeeForecaster,
+    surge_tracker: SurgeTracker,
+    anomaly_detector: AnomalyDetector,
+    seasonality: SeasonalityAnalyzer,
+    surge_pricing: SurgePricingTracker,
+    inclusion: InclusionEstimator,
+    market_depth: MarketDepthTracker,
+    budgets: BudgetTracker,
+    calculators: Vec<Box<dyn InsightCalculator>>,
+    events: broadcast::Sender<InsightEvent>,
+    batches: broadcast::Sender<Arc<ProcessedBatch>>,
     last_update: Option<DateTime<Utc>>,
     last_insights: Option<CurrentInsights>,
+    last_snapshot_at: Option<DateTime<Utc>>,
+    fee_stats: Option<FeeStatsSnapshot>,
 }
 
 impl FeeInsightsEngine {
     /// Create a new fee insights engine with the given configuration
     pub fn new(config: InsightsConfig) -> Self {
         // Create component configurations
-        let average_config = AverageConfig::default();
+        let average_config = average_config(&config);
         let extremes_config = ExtremesConfig::default();
 
         // Initialize components
         let calculator = RollingAverageCalculator::new(average_config, config.time_windows.clone());
-        let tracker = ExtremesTracker::new(extremes_config);
-        let detector = CongestionDetector::new(config.spike_detection.clone());
+        let tracker = ExtremesTracker::new(extremes_config).with_windows(&config.time_windows);
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let (batches, _) = broadcast::channel(EVENT_CAPACITY);
+        let detector =
+            CongestionDetector::new(config.spike_detection.clone()).with_events(events.clone());
+        let forecaster = FeeForecaster::new(config.forecast.clone());
+        let surge_tracker = SurgeTracker::new(config.surges.clone());
+        let anomaly_detector = AnomalyDetector::new(config.anomaly.clone());
+        let seasonality = SeasonalityAnalyzer::new(config.seasonality.clone());
+        let inclusion = InclusionEstimator::new(config.inclusion.clone());
+        let market_depth = MarketDepthTracker::new(config.market_depth.clone());
 
         Self {
             config,
             calculator,
             tracker,
             detector,
+            forecaster,
+            surge_tracker,
+            anomaly_detector,
+            seasonality,
+            surge_pricing: SurgePricingTracker::new(),
+            inclusion,
+            market_depth,
+            budgets: BudgetTracker::new(),
+            calculators: Vec::new(),
+            events,
+            batches,
             last_update: None,
             last_insights: None,
+            last_snapshot_at: None,
+            fee_stats: None,
+        }
+    }
+
+    /// Forget every fee, ledger and fee stats snapshot seen so history can
+    /// be replayed. The configuration, custom calculators and event
+    /// subscribers stay; budgets are dropped and must be tracked again.
+    pub fn reset(&mut self) {
+        let fresh = Self::new(self.config.clone());
+        self.calculator = fresh.calculator;
+        self.tracker = fresh.tracker;
+        self.detector = CongestionDetector::new(self.config.spike_detection.clone())
+            .with_events(self.events.clone());
+        self.forecaster = fresh.forecaster;
+        self.surge_tracker = fresh.surge_tracker;
+        self.anomaly_detector = fresh.anomaly_detector;
+        self.seasonality = fresh.seasonality;
+        self.surge_pricing = fresh.surge_pricing;
+        self.inclusion = fresh.inclusion;
+        self.market_depth = fresh.market_depth;
+        self.budgets = fresh.budgets;
+        self.last_update = None;
+        self.last_insights = None;
+        self.last_snapshot_at = None;
+        self.fee_stats = None;
+    }
+
+    /// Add a custom calculator to the pipeline. Fails when its name is
+    /// empty or already registered.
+    #[allow(dead_code)]
+    pub fn register_calculator(
+        &mut self,
+        calculator: Box<dyn InsightCalculator>,
+    ) -> Result<(), InsightsError> {
+        let name = calculator.name();
+        if name.is_empty() {
+            return Err(InsightsError::config_error(
+                "Insight calculator name must not be empty",
+            ));
         }
+        if self.calculators.iter().any(|c| c.name() == name) {
+            return Err(InsightsError::config_error(format!(
+                "Insight calculator {} is already registered",
+                name
+            )));
+        }
+
+        self.calculators.push(calculator);
+        Ok(())
     }
 
     /// Process new fee data and update insights
@@ -59,20 +194,46 @@ impl FeeInsightsEngine {
         // Validate fee data
         self.validate_fee_data(data)?;
 
-        // Update rolling averages
+        // Update rolling averages and the forecast model
         for fee_point in data {
             self.calculator.add_data_point(fee_point.clone());
+            self.forecaster.add_data_point(fee_point);
         }
 
         // Update extremes tracking
         self.tracker.update_with_fees(data)?;
 
-        // Calculate rolling averages to get baseline for congestion detection
         let rolling_averages = self.calculator.calculate_averages()?;
-        let baseline = rolling_averages.medium_term.value; // Use medium-term as baseline
+        // Batches are compared per transaction, so the baseline is too, even
+        // where `WINDOW_AVERAGING` weights the medium-term average otherwise
+        let baseline = self.calculator.transaction_mean("medium_term")?;
 
         // Update congestion detection
-        let congestion_trends = self.detector.analyze_congestion(data, baseline)?;
+        let mut congestion_trends = self.detector.analyze_congestion(data, baseline)?;
+        // Compare against history from before this batch
+        congestion_trends.seasonal_ratio = self.seasonal_ratio(
+            self.calculator.transaction_mean("short_term")?,
+            processing_start,
+        );
+        self.seasonality.add_data_points(data);
+        let state_transitions = self.detector.take_state_transitions();
+        let open_surge = self.surge_tracker.current_surge_start();
+        let completed_surges = self.surge_tracker.observe(data, baseline);
+        self.publish_surge_starts(open_surge, &completed_surges, baseline);
+
+        // Outliers are scored separately so they never count as congestion
+        let anomalies = self.anomaly_detector.observe(data);
+        if !anomalies.anomalies.is_empty() || anomalies.is_anomaly {
+            let _ = self
+                .events
+                .send(InsightEvent::AnomalyDetected(anomalies.clone()));
+        }
+        let surge_pricing = self.surge_pricing.observe(data);
+        self.inclusion.add_data_points(data);
+        self.market_depth.add_data_points(data);
+        for status in self.budgets.observe(data) {
+            let _ = self.events.send(InsightEvent::BudgetExceeded(status));
+        }
 
         // Get current extremes
         let extremes = self
@@ -84,17 +245,40 @@ impl FeeInsightsEngine {
         let data_quality = self.calculate_data_quality(data, processing_start);
 
         // Create current insights
-        let insights = CurrentInsights {
+        let mut insights = CurrentInsights {
             rolling_averages,
             extremes,
             congestion_trends,
             last_updated: processing_start,
             data_quality,
+            anomalies,
+            surge_pricing,
+            custom: BTreeMap::new(),
         };
 
+        // A failing custom metric is left out rather than failing the batch
+        let mut custom = BTreeMap::new();
+        for calculator in &mut self.calculators {
+            match calculator.process(data, &insights) {
+                Ok(value) => {
+                    custom.insert(calculator.name().to_string(), value);
+                }
+                Err(err) => {
+                    tracing::warn!("Insight calculator {} failed: {}", calculator.name(), err)
+                }
+            }
+        }
+        insights.custom = custom;
+
         // Update last update time
         self.last_update = Some(processing_start);
         self.last_insights = Some(insights.clone());
+        if self.batches.receiver_count() > 0 {
+            let _ = self.batches.send(Arc::new(ProcessedBatch {
+                points: data.to_vec(),
+                insights: insights.clone(),
+            }));
+        }
 
         // Calculate processing time
         let processing_time = chrono::Duration::from_std(start_time.elapsed())
@@ -104,9 +288,67 @@ impl FeeInsightsEngine {
             insights,
             processing_time,
             data_points_processed: data.len(),
+            state_transitions,
+            fee_ratio: self.detector.last_fee_ratio(),
+            completed_surges,
         })
     }
 
+    /// Track `budget`, replacing any for the same account and period.
+    /// `spent` is what the account already paid this period, up to
+    /// `as_of`; fees processed from then on are added to it.
+    pub fn set_fee_budget(&mut self, budget: FeeBudget, spent: u64, as_of: DateTime<Utc>) {
+        self.budgets.set(budget, spent, as_of);
+    }
+
+    /// Stop tracking a fee budget. Returns `false` when none was set.
+    pub fn remove_fee_budget(&mut self, account: &str, period: BudgetPeriod) -> bool {
+        self.budgets.remove(account, period)
+    }
+
+    /// Spend against every tracked fee budget in its current period.
+    pub fn get_budget_statuses(&self) -> Vec<BudgetStatus> {
+        self.budgets.statuses(Utc::now())
+    }
+
+    /// Receive events (congestion changes, anomalies, surge starts) as
+    /// batches are processed. Each subscriber gets every event sent after
+    /// it subscribed; one that falls far behind misses the oldest.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<InsightEvent> {
+        self.events.subscribe()
+    }
+
+    /// Receive every processed batch with the insights it produced. Like
+    /// [`Self::subscribe_events`], a lagging subscriber misses the oldest.
+    pub fn subscribe_batches(&self) -> broadcast::Receiver<Arc<ProcessedBatch>> {
+        self.batches.subscribe()
+    }
+
+    /// Publish a `SurgeStarted` for every surge that opened in the latest
+    /// batch, given the one open before it (`open_before`).
+    fn publish_surge_starts(
+        &self,
+        open_before: Option<DateTime<Utc>>,
+        completed: &[SurgeEpisode],
+        baseline: f64,
+    ) {
+        let started = completed
+            .iter()
+            .map(|episode| (episode.start_time, episode.baseline_fee))
+            .chain(
+                self.surge_tracker
+                    .current_surge_start()
+                    .map(|start| (start, baseline)),
+            )
+            .filter(|(start, _)| Some(*start) != open_before);
+        for (start_time, baseline_fee) in started {
+            let _ = self.events.send(InsightEvent::SurgeStarted {
+                start_time,
+                baseline_fee,
+            });
+        }
+    }
+
     /// Validate fee data for basic correctness
     pub fn validate_fee_data(&self, data: &[FeeDataPoint]) -> Result<(), InsightsError> {
         for (i, fee_point) in data.iter().enumerate() {
@@ -212,6 +454,7 @@ impl FeeInsightsEngine {
             value: 100, // Default Stellar base fee in stroops
             timestamp: now,
             transaction_hash: "unknown".to_string(),
+            ledger_sequence: 0,
         };
 
         FeeExtremes {
@@ -219,6 +462,9 @@ impl FeeInsightsEngine {
             current_max: default_extreme,
             period_start: now,
             period_end: now,
+            all_time: self.tracker.all_time(),
+            last_24h: None,
+            by_window: Vec::new(),
         }
     }
 
@@ -239,10 +485,14 @@ impl FeeInsightsEngine {
             .unwrap_or_else(|_| self.create_default_extremes());
 
         let congestion_trends = CongestionTrends {
-            current_trend: TrendIndicator::Normal,
+            current_trend: self.capacity_trend(),
             recent_spikes: self.detector.get_recent_spikes(),
             trend_strength: self.detector.calculate_trend_strength(),
             predicted_duration: None,
+            capacity_utilization: self.detector.capacity_utilization(),
+            congestion_score: self.detector.congestion_score(),
+            congestion_state: self.detector.congestion_state(),
+            seasonal_ratio: None,
         };
 
         let data_quality = DataQuality {
@@ -258,6 +508,9 @@ impl FeeInsightsEngine {
             congestion_trends,
             last_updated: self.last_update.unwrap_or_else(Utc::now),
             data_quality,
+            anomalies: AnomalyReport::default(),
+            surge_pricing: self.surge_pricing.current(),
+            custom: BTreeMap::new(),
         }
     }
 
@@ -273,7 +526,18 @@ impl FeeInsightsEngine {
                 name: "default".to_string(),
                 duration: chrono::Duration::hours(1),
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
+            fee_bump_count: 0,
+            fee_bump_volume_share: 0.0,
+            avg_fee_per_operation: None,
+            soroban: None,
+            percentiles: None,
+            histogram: None,
+            by_category: Vec::new(),
+            volatility: None,
+            trend: None,
+            bid_spread: None,
         };
 
         RollingAverages {
@@ -297,16 +561,273 @@ impl FeeInsightsEngine {
             .unwrap_or_else(|_| self.create_default_extremes())
     }
 
+    /// Extremes since tracking began, for persisting across restarts.
+    pub fn get_all_time_extremes(&self) -> Option<ExtremeRange> {
+        self.tracker.all_time()
+    }
+
+    /// Restore all-time extremes persisted by an earlier run.
+    pub fn restore_all_time_extremes(&mut self, extremes: &ExtremeRange) {
+        self.tracker.restore_all_time(extremes);
+    }
+
     /// Get congestion trends
     pub fn get_congestion_trends(&self) -> CongestionTrends {
+        let short_term = self.get_rolling_averages().short_term;
         CongestionTrends {
-            current_trend: TrendIndicator::Normal,
+            current_trend: self.capacity_trend(),
             recent_spikes: self.detector.get_recent_spikes(),
             trend_strength: self.detector.calculate_trend_strength(),
             predicted_duration: None,
+            capacity_utilization: self.detector.capacity_utilization(),
+            congestion_score: self.detector.congestion_score(),
+            congestion_state: self.detector.congestion_state(),
+            seasonal_ratio: (short_term.sample_count > 0)
+                .then(|| {
+                    let fee = self.calculator.transaction_mean("short_term").ok()?;
+                    self.seasonal_ratio(fee, Utc::now())
+                })
+                .flatten(),
+        }
+    }
+
+    /// Forecast the mean fee `horizon` from now.
+    pub fn get_forecast(&self, horizon: chrono::Duration) -> Result<FeeForecast, InsightsError> {
+        let mut forecast = self.forecaster.forecast(horizon)?;
+        forecast.seasonal_baseline = self.seasonality.typical_fee(forecast.target_time);
+        Ok(forecast)
+    }
+
+    /// Seed the seasonality baselines with pre-aggregated history.
+    pub fn seed_seasonality(&mut self, slots: &[SeasonalSlot]) {
+        for slot in slots {
+            self.seasonality
+                .add_slot(slot.weekday, slot.hour, slot.count, slot.total_fee);
+        }
+    }
+
+    /// Typical fees by hour of day and day of week.
+    pub fn get_seasonality_profile(&self) -> SeasonalityProfile {
+        self.seasonality.profile()
+    }
+
+    /// How `fee` compares with the typical fee at `at`.
+    fn seasonal_ratio(&self, fee: f64, at: DateTime<Utc>) -> Option<f64> {
+        self.seasonality
+            .typical_fee(at)
+            .filter(|typical| *typical > 0.0)
+            .map(|typical| fee / typical)
+    }
+
+    /// Mean fee over baseline of the latest batch, which congestion states
+    /// are entered and left on.
+    pub fn get_fee_ratio(&self) -> Option<f64> {
+        self.detector.last_fee_ratio()
+    }
+
+    /// Suggest economy/standard/priority bids from the most recent fee
+    /// distribution, bidding higher up it as congestion builds.
+    pub fn get_fee_recommendation(&self) -> FeeRecommendation {
+        let congestion = self
+            .last_insights
+            .as_ref()
+            .map(|insights| insights.congestion_trends.current_trend.clone())
+            .unwrap_or_else(|| self.capacity_trend());
+
+        let averages = self.get_rolling_averages();
+        let sampled = [
+            averages.short_term,
+            averages.medium_term,
+            averages.long_term,
+        ]
+        .into_iter()
+        .find_map(|average| Some((average.time_window.name, average.percentiles?)));
+
+        let bid = |urgency: Urgency| {
+            sampled
+                .as_ref()
+                .and_then(|(_, p)| p.percentile(urgency.percentile(&congestion)))
+                .unwrap_or(MIN_BASE_FEE)
+                .max(MIN_BASE_FEE)
+        };
+
+        FeeRecommendation {
+            economy: bid(Urgency::Economy),
+            standard: bid(Urgency::Standard),
+            priority: bid(Urgency::Priority),
+            congestion,
+            time_window: sampled.map(|(window, _)| window),
+            generated_at: Utc::now(),
+        }
+    }
+
+    /// Summarise every stored fee in `[from, to)` as one window, averaged
+    /// with `averaging`. Reads the database rather than the engine's own
+    /// windows, so any range still in storage can be queried; it takes an
+    /// engine's `config` rather than the engine so that need not stay
+    /// locked meanwhile. Fails when the range holds over `max_points` fees.
+    pub async fn compute_range_insights(
+        config: &InsightsConfig,
+        repository: &FeeRepository,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        averaging: AveragingMethod,
+        max_points: usize,
+    ) -> Result<RangeInsights, InsightsError> {
+        if from >= to {
+            return Err(InsightsError::invalid_data(
+                "Range must start before it ends",
+            ));
+        }
+        // One past the cap tells a full range from one that overflows
+        let points: Vec<FeeDataPoint> = repository
+            .fetch_page_between(from, to, &PointFilter::default(), None, max_points + 1, 0)
+            .await
+            .map_err(|e| InsightsError::storage_error(e.to_string()))?
+            .into_iter()
+            .map(|(_, point)| point)
+            .collect();
+        if points.len() > max_points {
+            return Err(InsightsError::invalid_data(format!(
+                "Range holds more than {} fees; narrow it",
+                max_points
+            )));
+        }
+
+        // Points are filtered against the clock as they are added, so the
+        // window reaches a little past `from` to keep all of them.
+        let window = TimeWindow {
+            name: "range".to_string(),
+            duration: Utc::now() - from + chrono::Duration::minutes(1),
+            min_samples: config.time_windows.first().map_or(1, |w| w.min_samples),
+            averaging,
+        };
+        let mut calculator = RollingAverageCalculator::new(
+            AverageConfig {
+                max_buffer_size: points.len().max(1),
+                ..average_config(config)
+            },
+            vec![window],
+        );
+        let (min_fee, max_fee) = (
+            points.iter().map(|p| p.fee_amount).min(),
+            points.iter().map(|p| p.fee_amount).max(),
+        );
+        for point in points {
+            calculator.add_data_point(point);
+        }
+
+        let mut summary = calculator.calculate_average_for_window("range", Utc::now())?;
+        summary.time_window.duration = to - from;
+        Ok(RangeInsights {
+            from,
+            to,
+            summary,
+            min_fee,
+            max_fee,
+            generated_at: Utc::now(),
+        })
+    }
+
+    /// Historical chance that a bid of `fee` stroops would have been
+    /// included within 1, 3 and 5 ledgers.
+    pub fn estimate_inclusion(&self, fee: u64) -> InclusionEstimate {
+        self.inclusion.estimate(fee)
+    }
+
+    /// How many recent bids sit at or above each fee level, and where a
+    /// bid of `fee` stroops per operation would have stood among them.
+    pub fn get_market_depth(&self, fee: Option<u64>) -> MarketDepth {
+        self.market_depth.depth(fee)
+    }
+
+    /// Price a Soroban invocation using `resources`: its resource fee plus
+    /// the current inclusion bid at each urgency.
+    pub fn estimate_soroban_fee(&self, resources: &SorobanResources) -> SorobanFeeEstimate {
+        let resource_fee = resource_fee(resources, &self.config.soroban_pricing);
+        let inclusion_fee = self.get_fee_recommendation();
+
+        SorobanFeeEstimate {
+            resources: resources.clone(),
+            economy_total: inclusion_fee.economy + resource_fee.total,
+            standard_total: inclusion_fee.standard + resource_fee.total,
+            priority_total: inclusion_fee.priority + resource_fee.total,
+            resource_fee,
+            inclusion_fee,
         }
     }
 
+    /// The latest insights, if a snapshot interval has passed since the
+    /// last one was taken. Marks them as taken.
+    pub fn take_due_snapshot(&mut self) -> Option<CurrentInsights> {
+        let insights = self.last_insights.as_ref()?;
+        let due = self
+            .last_snapshot_at
+            .is_none_or(|taken| insights.last_updated - taken >= self.config.snapshots.interval);
+        if !due {
+            return None;
+        }
+
+        self.last_snapshot_at = Some(insights.last_updated);
+        Some(insights.clone())
+    }
+
+    /// Track recently closed ledgers so congestion reflects how full they are.
+    pub fn record_ledgers(&mut self, ledgers: &[LedgerInfo]) {
+        self.detector.record_ledgers(ledgers);
+        self.surge_pricing.record_ledgers(ledgers);
+    }
+
+    /// Whether surge pricing is active, from the latest ledger seen.
+    pub fn get_surge_pricing(&self) -> Option<SurgePricing> {
+        self.surge_pricing.current()
+    }
+
+    /// Trend implied by the detector's congestion score, for views computed
+    /// without fresh fee data.
+    fn capacity_trend(&self) -> TrendIndicator {
+        if self.detector.is_congested() {
+            TrendIndicator::Congested
+        } else {
+            TrendIndicator::Normal
+        }
+    }
+
+    /// Store the latest Horizon `/fee_stats` snapshot for cross-checking.
+    pub fn record_fee_stats(&mut self, snapshot: FeeStatsSnapshot) {
+        self.fee_stats = Some(snapshot);
+    }
+
+    /// Compare our short-term rolling average with the latest `/fee_stats`
+    /// median. `None` until a snapshot has been recorded.
+    pub fn get_fee_stats_cross_check(&self) -> Option<FeeStatsCrossCheck> {
+        let fee_stats = self.fee_stats.clone()?;
+        let local_short_term_average = self.get_rolling_averages().short_term.value;
+
+        let horizon_p50 = fee_stats.fee_charged.p50 as f64;
+        let divergence = if horizon_p50 > 0.0 {
+            (local_short_term_average - horizon_p50).abs() / horizon_p50
+        } else {
+            0.0
+        };
+
+        Some(FeeStatsCrossCheck {
+            fee_stats,
+            local_short_term_average,
+            divergence,
+            consistent: divergence <= FEE_STATS_DIVERGENCE_TOLERANCE,
+        })
+    }
+
+    /// Replace the congestion detector's thresholds without restarting.
+    /// Spike history and the current congestion state are kept.
+    pub fn update_spike_config(&mut self, config: SpikeConfig) -> Result<(), InsightsError> {
+        config.validate()?;
+        self.detector.set_config(config.clone());
+        self.config.spike_detection = config;
+        Ok(())
+    }
+
     /// Get engine configuration
     pub fn get_config(&self) -> &InsightsConfig {
         &self.config
@@ -0,0 +1,196 @@
+//! Transaction Envelope Decoding
+//!
+//! Decodes base64 `TransactionEnvelope` XDR so fee data can carry details
+//! that are only reliable in the envelope itself: operation count and type,
+//! fee-bump wrapping, the max fee bids and the account paying the fee.
+
+use stellar_xdr::curr::{
+    FeeBumpTransactionInnerTx, Limits, MuxedAccount, Operation, OperationBody, ReadXdr,
+    SorobanTransactionMetaExt, TransactionEnvelope, TransactionExt, TransactionMeta,
+};
+
+use crate::insights::{
+    error::ProviderError,
+    provider::ProviderResult,
+    types::{EnvelopeDetails, OperationCategory, SorobanFeeDetail},
+};
+
+/// Decode a base64 `TransactionEnvelope`.
+pub fn decode_envelope(envelope_xdr: &str) -> ProviderResult<TransactionEnvelope> {
+    TransactionEnvelope::from_xdr_base64(envelope_xdr, Limits::none()).map_err(|e| {
+        ProviderError::FormatError {
+            message: format!("Invalid envelope XDR: {}", e),
+        }
+    })
+}
+
+/// Extract the fee-relevant details of an envelope.
+pub fn envelope_details(envelope: &TransactionEnvelope) -> EnvelopeDetails {
+    match envelope {
+        TransactionEnvelope::TxV0(env) => EnvelopeDetails {
+            operation_count: env.tx.operations.len() as u32,
+            fee_bump: false,
+            max_fee: env.tx.fee as u64,
+            inner_fee: None,
+            category: dominant_category(&env.tx.operations),
+            fee_account: Some(account_address(&MuxedAccount::Ed25519(
+                env.tx.source_account_ed25519.clone(),
+            ))),
+        },
+        TransactionEnvelope::Tx(env) => EnvelopeDetails {
+            operation_count: env.tx.operations.len() as u32,
+            fee_bump: false,
+            max_fee: env.tx.fee as u64,
+            inner_fee: None,
+            category: dominant_category(&env.tx.operations),
+            fee_account: Some(account_address(&env.tx.source_account)),
+        },
+        TransactionEnvelope::TxFeeBump(env) => {
+            let FeeBumpTransactionInnerTx::Tx(inner) = &env.tx.inner_tx;
+            EnvelopeDetails {
+                operation_count: inner.tx.operations.len() as u32,
+                fee_bump: true,
+                max_fee: env.tx.fee.max(0) as u64,
+                inner_fee: Some(inner.tx.fee as u64),
+                category: dominant_category(&inner.tx.operations),
+                fee_account: Some(account_address(&env.tx.fee_source)),
+            }
+        }
+    }
+}
+
+/// `G…` address of `account`; muxed accounts pay from their base account.
+fn account_address(account: &MuxedAccount) -> String {
+    match account {
+        MuxedAccount::Ed25519(_) => account.to_string(),
+        MuxedAccount::MuxedEd25519(muxed) => {
+            MuxedAccount::Ed25519(muxed.ed25519.clone()).to_string()
+        }
+    }
+}
+
+/// Category of a single operation.
+fn operation_category(operation: &Operation) -> OperationCategory {
+    match operation.body {
+        OperationBody::Payment(_) => OperationCategory::Payment,
+        OperationBody::PathPaymentStrictReceive(_) | OperationBody::PathPaymentStrictSend(_) => {
+            OperationCategory::PathPayment
+        }
+        OperationBody::ManageSellOffer(_)
+        | OperationBody::ManageBuyOffer(_)
+        | OperationBody::CreatePassiveSellOffer(_) => OperationCategory::OfferManagement,
+        OperationBody::InvokeHostFunction(_) => OperationCategory::ContractInvoke,
+        _ => OperationCategory::Other,
+    }
+}
+
+/// Most common category among `operations`; ties go to the one that
+/// appears first. `None` for a transaction without operations.
+fn dominant_category(operations: &[Operation]) -> Option<OperationCategory> {
+    let categories: Vec<OperationCategory> = operations.iter().map(operation_category).collect();
+    let count = |category: &OperationCategory| categories.iter().filter(|c| *c == category).count();
+    categories
+        .iter()
+        .rev()
+        .max_by_key(|category| count(category))
+        .copied()
+}
+
+/// Extension of the (inner) transaction, where Soroban resources live.
+fn transaction_ext(envelope: &TransactionEnvelope) -> Option<&TransactionExt> {
+    match envelope {
+        TransactionEnvelope::TxV0(_) => None,
+        TransactionEnvelope::Tx(env) => Some(&env.tx.ext),
+        TransactionEnvelope::TxFeeBump(env) => match &env.tx.inner_tx {
+            FeeBumpTransactionInnerTx::Tx(inner) => Some(&inner.tx.ext),
+        },
+    }
+}
+
+/// `true` when the (inner) transaction carries Soroban resource data.
+pub fn is_soroban_envelope(envelope: &TransactionEnvelope) -> bool {
+    matches!(transaction_ext(envelope), Some(TransactionExt::V1(_)))
+}
+
+/// Declared resources and resource fee bid of a Soroban transaction.
+///
+/// The charged fee split is left unset; see [`apply_soroban_meta`].
+pub fn soroban_fee_detail(envelope: &TransactionEnvelope) -> Option<SorobanFeeDetail> {
+    let Some(TransactionExt::V1(data)) = transaction_ext(envelope) else {
+        return None;
+    };
+    let footprint = &data.resources.footprint;
+    Some(SorobanFeeDetail {
+        instructions: data.resources.instructions,
+        disk_read_bytes: data.resources.disk_read_bytes,
+        write_bytes: data.resources.write_bytes,
+        read_entries: (footprint.read_only.len() + footprint.read_write.len()) as u32,
+        write_entries: footprint.read_write.len() as u32,
+        resource_fee_bid: data.resource_fee.max(0) as u64,
+        ..SorobanFeeDetail::default()
+    })
+}
+
+/// Fill in the charged resource fees from a base64 `TransactionMeta`.
+///
+/// Leaves `detail` untouched when the meta cannot be decoded or predates
+/// the fee breakdown (protocol 20 and earlier).
+pub fn apply_soroban_meta(detail: &mut SorobanFeeDetail, meta_xdr: &str) {
+    let Ok(meta) = TransactionMeta::from_xdr_base64(meta_xdr, Limits::none()) else {
+        return;
+    };
+    let ext = match &meta {
+        TransactionMeta::V3(v3) => v3.soroban_meta.as_ref().map(|m| &m.ext),
+        TransactionMeta::V4(v4) => v4.soroban_meta.as_ref().map(|m| &m.ext),
+        _ => None,
+    };
+    if let Some(SorobanTransactionMetaExt::V1(fees)) = ext {
+        detail.non_refundable_fee =
+            Some(fees.total_non_refundable_resource_fee_charged.max(0) as u64);
+        detail.refundable_fee = Some(fees.total_refundable_resource_fee_charged.max(0) as u64);
+        detail.rent_fee = Some(fees.rent_fee_charged.max(0) as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stellar_xdr::curr::{Asset, ManageBuyOfferOp, PaymentOp, Price, Uint256};
+
+    fn payment() -> Operation {
+        Operation {
+            source_account: None,
+            body: OperationBody::Payment(PaymentOp {
+                destination: MuxedAccount::Ed25519(Uint256([0; 32])),
+                asset: Asset::Native,
+                amount: 10,
+            }),
+        }
+    }
+
+    fn buy_offer() -> Operation {
+        Operation {
+            source_account: None,
+            body: OperationBody::ManageBuyOffer(ManageBuyOfferOp {
+                selling: Asset::Native,
+                buying: Asset::Native,
+                buy_amount: 10,
+                price: Price { n: 1, d: 1 },
+                offer_id: 0,
+            }),
+        }
+    }
+
+    #[test]
+    fn dominant_category_is_most_common_then_first() {
+        assert_eq!(
+            dominant_category(&[payment(), buy_offer(), buy_offer()]),
+            Some(OperationCategory::OfferManagement)
+        );
+        assert_eq!(
+            dominant_category(&[buy_offer(), payment()]),
+            Some(OperationCategory::OfferManagement)
+        );
+        assert_eq!(dominant_category(&[]), None);
+    }
+}
@@ -34,8 +34,12 @@ pub enum InsightsError {
 /// Errors from fee data providers
 #[derive(Error, Debug)]
 pub enum ProviderError {
-    #[error("Network error: {message}")]
-    NetworkError { message: String },
+    #[error("Network error{}: {message}", if *timeout { " (timeout)" } else { "" })]
+    NetworkError {
+        message: String,
+        /// The request exceeded a connect, read or per-call deadline.
+        timeout: bool,
+    },
 
     #[error("Data format error: {message}")]
     FormatError { message: String },
@@ -48,6 +52,16 @@ pub enum ProviderError {
 
     #[error("Service unavailable")]
     ServiceUnavailable,
+
+    #[error("Operation not supported: {operation}")]
+    Unsupported { operation: String },
+}
+
+impl ProviderError {
+    /// Whether this is a `NetworkError` caused by a timeout.
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::NetworkError { timeout: true, .. })
+    }
 }
 
 impl InsightsError {
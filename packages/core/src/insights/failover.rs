@@ -0,0 +1,471 @@
+//! Failover Fee Data Provider
+//!
+//! Wraps an ordered list of providers (e.g. several Horizon mirrors) and
+//! falls through to the next backend when one is unreachable.
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+
+use crate::insights::{
+    error::ProviderError,
+    provider::{FailoverStatus, FeeDataProvider, ProviderMetadata, ProviderResult, ProviderStatus},
+    types::{FeeDataPoint, FeeStatsSnapshot, LedgerInfo},
+};
+
+/// A single backend in the failover chain.
+struct FailoverBackend {
+    label: String,
+    provider: Arc<dyn FeeDataProvider + Send + Sync>,
+}
+
+/// Health of one backend as observed by `FailoverFeeDataProvider::health_report`
+/// or by the last fetch that reached it.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendHealth {
+    pub label: String,
+    pub healthy: bool,
+    pub error: Option<String>,
+}
+
+/// Aggregate status across all backends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateHealth {
+    /// Every backend passed its health check.
+    Healthy,
+    /// At least one backend is up, but not all of them.
+    Degraded,
+    /// No backend passed its health check.
+    Unavailable,
+}
+
+/// Provider that tries each backend in order until one returns data.
+///
+/// Only `NetworkError` and `ServiceUnavailable` trigger failover; other errors
+/// (bad data, auth, rate limiting) are returned from the backend that raised
+/// them, since a mirror is unlikely to fix them.
+pub struct FailoverFeeDataProvider {
+    backends: Vec<FailoverBackend>,
+    last_served_by: RwLock<Option<String>>,
+    /// Last seen health of each backend, in failover order.
+    last_health: RwLock<Vec<Option<BackendHealth>>>,
+}
+
+impl FailoverFeeDataProvider {
+    /// Create an empty failover chain. Add backends with `with_backend`.
+    pub fn new() -> Self {
+        Self {
+            backends: Vec::new(),
+            last_served_by: RwLock::new(None),
+            last_health: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Append a backend; backends are tried in the order they were added.
+    pub fn with_backend(
+        mut self,
+        label: impl Into<String>,
+        provider: Arc<dyn FeeDataProvider + Send + Sync>,
+    ) -> Self {
+        self.backends.push(FailoverBackend {
+            label: label.into(),
+            provider,
+        });
+        self.last_health
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(None);
+        self
+    }
+
+    /// Labels of all configured backends, in failover order.
+    pub fn backend_labels(&self) -> Vec<&str> {
+        self.backends.iter().map(|b| b.label.as_str()).collect()
+    }
+
+    /// Label of the backend that served the most recent successful fetch.
+    pub fn last_served_by(&self) -> Option<String> {
+        self.last_served_by
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Probe every backend and report its health individually.
+    pub async fn health_report(&self) -> Vec<BackendHealth> {
+        let mut report = Vec::with_capacity(self.backends.len());
+        for (index, backend) in self.backends.iter().enumerate() {
+            let result = backend.provider.health_check().await;
+            self.record_health(index, result.as_ref().err());
+            report.push(BackendHealth {
+                label: backend.label.clone(),
+                healthy: result.is_ok(),
+                error: result.err().map(|e| e.to_string()),
+            });
+        }
+        report
+    }
+
+    /// The backend that served the last fetch and the last seen health of
+    /// each backend, without probing any of them.
+    pub fn failover_status(&self) -> FailoverStatus {
+        let backends: Vec<BackendHealth> = self
+            .last_health
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .flatten()
+            .cloned()
+            .collect();
+        FailoverStatus {
+            serving: self.last_served_by(),
+            health: (!backends.is_empty()).then(|| Self::aggregate(&backends)),
+            backends,
+        }
+    }
+
+    /// Summarise a health report into a single status.
+    pub fn aggregate(report: &[BackendHealth]) -> AggregateHealth {
+        let healthy = report.iter().filter(|b| b.healthy).count();
+        if healthy == 0 {
+            AggregateHealth::Unavailable
+        } else if healthy == report.len() {
+            AggregateHealth::Healthy
+        } else {
+            AggregateHealth::Degraded
+        }
+    }
+
+    /// Run `op` against each backend in order, failing over on connectivity errors.
+    async fn try_backends<'a, F, Fut, T>(&'a self, op: F) -> ProviderResult<T>
+    where
+        F: Fn(&'a Arc<dyn FeeDataProvider + Send + Sync>) -> Fut,
+        Fut: Future<Output = ProviderResult<T>>,
+    {
+        let mut last_error = ProviderError::ServiceUnavailable;
+
+        for (index, backend) in self.backends.iter().enumerate() {
+            match op(&backend.provider).await {
+                Ok(value) => {
+                    if index > 0 {
+                        tracing::warn!(
+                            "Fee data served by failover backend '{}' ({} of {})",
+                            backend.label,
+                            index + 1,
+                            self.backends.len()
+                        );
+                    }
+                    self.set_last_served_by(&backend.label);
+                    self.record_health(index, None);
+                    return Ok(value);
+                }
+                Err(err) if should_fail_over(&err) => {
+                    tracing::warn!("Backend '{}' unavailable: {}", backend.label, err);
+                    self.record_health(index, Some(&err));
+                    last_error = err;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(last_error)
+    }
+
+    fn set_last_served_by(&self, label: &str) {
+        *self
+            .last_served_by
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = Some(label.to_string());
+    }
+
+    /// Note the backend at `index` as healthy, or down with `error`.
+    fn record_health(&self, index: usize, error: Option<&ProviderError>) {
+        let mut last_health = self.last_health.write().unwrap_or_else(|e| e.into_inner());
+        if let (Some(slot), Some(backend)) = (last_health.get_mut(index), self.backends.get(index))
+        {
+            *slot = Some(BackendHealth {
+                label: backend.label.clone(),
+                healthy: error.is_none(),
+                error: error.map(|e| e.to_string()),
+            });
+        }
+    }
+
+    /// The backend that served the last fetch, else the primary.
+    fn serving_backend(&self) -> Option<&FailoverBackend> {
+        let served_by = self.last_served_by();
+        self.backends
+            .iter()
+            .find(|b| Some(&b.label) == served_by.as_ref())
+            .or_else(|| self.backends.first())
+    }
+}
+
+impl Default for FailoverFeeDataProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `true` when the error indicates the backend itself is unreachable.
+fn should_fail_over(err: &ProviderError) -> bool {
+    matches!(
+        err,
+        ProviderError::NetworkError { .. } | ProviderError::ServiceUnavailable
+    )
+}
+
+#[async_trait]
+impl FeeDataProvider for FailoverFeeDataProvider {
+    async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        self.try_backends(|provider| provider.fetch_latest_fees())
+            .await
+    }
+
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        self.try_backends(|provider| provider.fetch_fees_range(start_ledger, end_ledger))
+            .await
+    }
+
+    async fn fetch_fee_stats(&self) -> ProviderResult<FeeStatsSnapshot> {
+        self.try_backends(|provider| provider.fetch_fee_stats())
+            .await
+    }
+
+    async fn fetch_recent_ledgers(&self) -> ProviderResult<Vec<LedgerInfo>> {
+        self.try_backends(|provider| provider.fetch_recent_ledgers())
+            .await
+    }
+
+    fn provider_name(&self) -> &str {
+        "Failover"
+    }
+
+    /// Succeeds when at least one backend is healthy; whether any is down
+    /// shows in `provider_status().failover`.
+    async fn health_check(&self) -> ProviderResult<()> {
+        let report = self.health_report().await;
+        match Self::aggregate(&report) {
+            AggregateHealth::Unavailable => Err(ProviderError::ServiceUnavailable),
+            AggregateHealth::Degraded => {
+                for backend in report.iter().filter(|b| !b.healthy) {
+                    tracing::warn!(
+                        "Backend '{}' failed health check: {}",
+                        backend.label,
+                        backend.error.as_deref().unwrap_or("unknown error")
+                    );
+                }
+                Ok(())
+            }
+            AggregateHealth::Healthy => Ok(()),
+        }
+    }
+
+    /// Status of the backend that served the last fetch, else the primary,
+    /// with the chain's own.
+    fn provider_status(&self) -> ProviderStatus {
+        ProviderStatus {
+            failover: Some(self.failover_status()),
+            ..self
+                .serving_backend()
+                .map(|b| b.provider.provider_status())
+                .unwrap_or_default()
+        }
+    }
+
+    /// Metadata of the backend that served the last fetch, else the primary.
+    fn get_metadata(&self) -> ProviderMetadata {
+        self.serving_backend()
+            .map(|b| b.provider.get_metadata())
+            .unwrap_or_default()
+    }
+
+    /// Refreshes the metadata `get_metadata` reports.
+    async fn refresh_metadata(&self) -> ProviderResult<ProviderMetadata> {
+        match self.serving_backend() {
+            Some(backend) => backend.provider.refresh_metadata().await,
+            None => Ok(ProviderMetadata::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::mock_horizon::MockHorizonClient;
+    use chrono::Utc;
+
+    fn make_fee_point(fee_amount: u64) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp: Utc::now(),
+            transaction_hash: format!("hash_{}", fee_amount),
+            ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
+        }
+    }
+
+    fn network_error() -> ProviderError {
+        ProviderError::NetworkError {
+            message: "connection refused".into(),
+            timeout: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn primary_serves_when_healthy() {
+        let primary = Arc::new(MockHorizonClient::new().with_fees(vec![make_fee_point(100)]));
+        let secondary = Arc::new(MockHorizonClient::new().with_fees(vec![make_fee_point(200)]));
+        let provider = FailoverFeeDataProvider::new()
+            .with_backend("primary", primary.clone())
+            .with_backend("secondary", secondary.clone());
+
+        let points = provider.fetch_latest_fees().await.unwrap();
+
+        assert_eq!(points[0].fee_amount, 100);
+        assert_eq!(provider.last_served_by().as_deref(), Some("primary"));
+        assert_eq!(secondary.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn fails_over_on_network_error() {
+        let primary = Arc::new(MockHorizonClient::new().with_error(network_error()));
+        let secondary = Arc::new(MockHorizonClient::new().with_fees(vec![make_fee_point(200)]));
+        let provider = FailoverFeeDataProvider::new()
+            .with_backend("primary", primary.clone())
+            .with_backend("secondary", secondary);
+
+        let points = provider.fetch_latest_fees().await.unwrap();
+
+        assert_eq!(points[0].fee_amount, 200);
+        assert_eq!(provider.last_served_by().as_deref(), Some("secondary"));
+        assert_eq!(primary.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn status_reports_the_serving_backend_and_each_backend_seen() {
+        let provider = FailoverFeeDataProvider::new()
+            .with_backend(
+                "primary",
+                Arc::new(MockHorizonClient::new().with_error(network_error())),
+            )
+            .with_backend(
+                "secondary",
+                Arc::new(MockHorizonClient::new().with_fees(vec![make_fee_point(200)])),
+            );
+        assert!(provider
+            .provider_status()
+            .failover
+            .unwrap()
+            .health
+            .is_none());
+
+        provider.fetch_latest_fees().await.unwrap();
+
+        let status = provider.provider_status().failover.unwrap();
+        assert_eq!(status.serving.as_deref(), Some("secondary"));
+        assert_eq!(status.health, Some(AggregateHealth::Degraded));
+        assert_eq!(status.backends.len(), 2);
+        assert!(!status.backends[0].healthy);
+        assert!(status.backends[1].healthy);
+    }
+
+    #[tokio::test]
+    async fn fails_over_on_service_unavailable() {
+        let primary =
+            Arc::new(MockHorizonClient::new().with_error(ProviderError::ServiceUnavailable));
+        let secondary = Arc::new(MockHorizonClient::new().with_fees(vec![make_fee_point(200)]));
+        let provider = FailoverFeeDataProvider::new()
+            .with_backend("primary", primary)
+            .with_backend("secondary", secondary);
+
+        assert!(provider.fetch_latest_fees().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn format_error_does_not_fail_over() {
+        let primary = Arc::new(
+            MockHorizonClient::new().with_error(ProviderError::FormatError {
+                message: "bad json".into(),
+            }),
+        );
+        let secondary = Arc::new(MockHorizonClient::new().with_fees(vec![make_fee_point(200)]));
+        let provider = FailoverFeeDataProvider::new()
+            .with_backend("primary", primary)
+            .with_backend("secondary", secondary.clone());
+
+        let result = provider.fetch_latest_fees().await;
+
+        assert!(matches!(result, Err(ProviderError::FormatError { .. })));
+        assert_eq!(secondary.calls(), 0);
+        assert!(provider.last_served_by().is_none());
+    }
+
+    #[tokio::test]
+    async fn all_backends_down_returns_last_error() {
+        let provider = FailoverFeeDataProvider::new()
+            .with_backend(
+                "primary",
+                Arc::new(MockHorizonClient::new().with_error(ProviderError::ServiceUnavailable)),
+            )
+            .with_backend(
+                "secondary",
+                Arc::new(MockHorizonClient::new().with_error(network_error())),
+            );
+
+        let result = provider.fetch_latest_fees().await;
+        assert!(matches!(result, Err(ProviderError::NetworkError { .. })));
+    }
+
+    #[tokio::test]
+    async fn health_check_reports_aggregate_status() {
+        let provider = FailoverFeeDataProvider::new()
+            .with_backend(
+                "primary",
+                Arc::new(MockHorizonClient::new().with_healthy(false)),
+            )
+            .with_backend(
+                "secondary",
+                Arc::new(MockHorizonClient::new().with_healthy(true)),
+            );
+
+        let report = provider.health_report().await;
+        assert_eq!(report.len(), 2);
+        assert!(!report[0].healthy);
+        assert!(report[0].error.is_some());
+        assert_eq!(
+            FailoverFeeDataProvider::aggregate(&report),
+            AggregateHealth::Degraded
+        );
+        assert!(provider.health_check().await.is_ok());
+        assert_eq!(
+            provider.provider_status().failover.unwrap().health,
+            Some(AggregateHealth::Degraded)
+        );
+    }
+
+    #[tokio::test]
+    async fn health_check_fails_when_every_backend_is_down() {
+        let provider = FailoverFeeDataProvider::new()
+            .with_backend(
+                "primary",
+                Arc::new(MockHorizonClient::new().with_healthy(false)),
+            )
+            .with_backend(
+                "secondary",
+                Arc::new(MockHorizonClient::new().with_healthy(false)),
+            );
+
+        assert!(matches!(
+            provider.health_check().await,
+            Err(ProviderError::ServiceUnavailable)
+        ));
+    }
+}
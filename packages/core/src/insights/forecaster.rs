@@ -0,0 +1,262 @@
+//! Short-term fee forecasting
+//!
+//! Fees are averaged per ledger and fed through Holt's linear exponential
+//! smoothing (Holt-Winters without a seasonal term). The spread of the
+//! one-step-ahead errors gives the forecast's confidence interval.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::VecDeque;
+
+use crate::insights::{config::ForecastConfig, error::InsightsError, types::*};
+
+/// Ledger close interval assumed until a spread of closes has been seen.
+const DEFAULT_LEDGER_INTERVAL_SECS: f64 = 5.0;
+
+/// Two-sided 95% normal quantile used for the confidence interval.
+const CONFIDENCE_Z: f64 = 1.96;
+const CONFIDENCE_LEVEL: f64 = 0.95;
+
+/// Fees charged in one ledger
+#[derive(Debug, Clone)]
+struct LedgerFees {
+    sequence: u64,
+    closed_at: DateTime<Utc>,
+    total_fee: u64,
+    count: u64,
+}
+
+impl LedgerFees {
+    fn mean_fee(&self) -> f64 {
+        self.total_fee as f64 / self.count as f64
+    }
+}
+
+/// Predicts mean fees a few minutes ahead from recent ledgers
+pub struct FeeForecaster {
+    config: ForecastConfig,
+    ledgers: VecDeque<LedgerFees>,
+}
+
+impl FeeForecaster {
+    /// Create a new forecaster
+    pub fn new(config: ForecastConfig) -> Self {
+        Self {
+            config,
+            ledgers: VecDeque::new(),
+        }
+    }
+
+    /// Add a fee to its ledger, keeping ledgers in sequence order.
+    pub fn add_data_point(&mut self, point: &FeeDataPoint) {
+        let position = self
+            .ledgers
+            .iter()
+            .rposition(|ledger| ledger.sequence <= point.ledger_sequence);
+
+        match position {
+            Some(i) if self.ledgers[i].sequence == point.ledger_sequence => {
+                let ledger = &mut self.ledgers[i];
+                ledger.total_fee += point.fee_amount;
+                ledger.count += 1;
+                ledger.closed_at = ledger.closed_at.max(point.timestamp);
+            }
+            _ => {
+                // Too old to matter once the buffer is full
+                if position.is_none() && self.ledgers.len() >= self.config.max_ledgers {
+                    return;
+                }
+                let insert_at = position.map_or(0, |i| i + 1);
+                self.ledgers.insert(
+                    insert_at,
+                    LedgerFees {
+                        sequence: point.ledger_sequence,
+                        closed_at: point.timestamp,
+                        total_fee: point.fee_amount,
+                        count: 1,
+                    },
+                );
+                while self.ledgers.len() > self.config.max_ledgers {
+                    self.ledgers.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Forecast the mean fee `horizon` from now.
+    pub fn forecast(&self, horizon: Duration) -> Result<FeeForecast, InsightsError> {
+        if horizon <= Duration::zero() || horizon > self.config.max_horizon {
+            return Err(InsightsError::invalid_data(format!(
+                "Forecast horizon must be between 0 and {} minutes",
+                self.config.max_horizon.num_minutes()
+            )));
+        }
+        if self.ledgers.len() < self.config.min_ledgers {
+            return Err(InsightsError::insufficient_data(format!(
+                "fee forecast needs {} ledgers, have {}",
+                self.config.min_ledgers,
+                self.ledgers.len()
+            )));
+        }
+
+        let alpha = self.config.level_smoothing;
+        let beta = self.config.trend_smoothing;
+
+        let mut fees = self.ledgers.iter().map(LedgerFees::mean_fee);
+        let mut level = fees.next().unwrap_or_default();
+        let mut trend = 0.0;
+        let mut squared_error = 0.0;
+        let mut error_count = 0usize;
+        for fee in fees {
+            let error = fee - (level + trend);
+            squared_error += error * error;
+            error_count += 1;
+
+            let previous_level = level;
+            level = alpha * fee + (1.0 - alpha) * (level + trend);
+            trend = beta * (level - previous_level) + (1.0 - beta) * trend;
+        }
+        let sigma = (squared_error / error_count.max(1) as f64).sqrt();
+
+        let ledgers_ahead =
+            (horizon.num_milliseconds() as f64 / 1000.0 / self.ledger_interval_secs()).ceil()
+                as u64;
+        let ledgers_ahead = ledgers_ahead.max(1);
+
+        // Variance of an h-step-ahead Holt forecast, relative to one step
+        let variance_factor = 1.0
+            + (1..ledgers_ahead)
+                .map(|j| (alpha * (1.0 + j as f64 * beta)).powi(2))
+                .sum::<f64>();
+        let half_width = CONFIDENCE_Z * sigma * variance_factor.sqrt();
+
+        let predicted_fee = (level + ledgers_ahead as f64 * trend).max(0.0);
+        let generated_at = Utc::now();
+
+        Ok(FeeForecast {
+            generated_at,
+            target_time: generated_at + horizon,
+            horizon_minutes: horizon.num_minutes(),
+            ledgers_ahead,
+            predicted_fee,
+            lower_bound: (predicted_fee - half_width).max(0.0),
+            upper_bound: predicted_fee + half_width,
+            confidence_level: CONFIDENCE_LEVEL,
+            seasonal_baseline: None,
+            sample_ledgers: self.ledgers.len(),
+        })
+    }
+
+    /// Mean time between the observed ledgers, in seconds.
+    fn ledger_interval_secs(&self) -> f64 {
+        match (self.ledgers.front(), self.ledgers.back()) {
+            (Some(first), Some(last)) if self.ledgers.len() > 1 => {
+                let span = (last.closed_at - first.closed_at).num_milliseconds() as f64 / 1000.0;
+                let interval = span / (self.ledgers.len() - 1) as f64;
+                if interval > 0.0 {
+                    interval
+                } else {
+                    DEFAULT_LEDGER_INTERVAL_SECS
+                }
+            }
+            _ => DEFAULT_LEDGER_INTERVAL_SECS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(ledger_sequence: u64, fee_amount: u64, timestamp: DateTime<Utc>) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp,
+            transaction_hash: format!("hash_{}_{}", ledger_sequence, fee_amount),
+            ledger_sequence,
+            envelope: None,
+            soroban: None,
+        }
+    }
+
+    fn forecaster_with(fees: impl IntoIterator<Item = u64>) -> FeeForecaster {
+        let mut forecaster = FeeForecaster::new(ForecastConfig::default());
+        let start = Utc::now() - Duration::minutes(10);
+        for (i, fee) in fees.into_iter().enumerate() {
+            let closed_at = start + Duration::seconds(5 * i as i64);
+            forecaster.add_data_point(&point(i as u64 + 1, fee, closed_at));
+        }
+        forecaster
+    }
+
+    #[test]
+    fn flat_fees_forecast_flat_with_tight_interval() {
+        let forecast = forecaster_with(std::iter::repeat_n(100, 20))
+            .forecast(Duration::minutes(1))
+            .unwrap();
+
+        assert_eq!(forecast.predicted_fee, 100.0);
+        assert_eq!(forecast.lower_bound, 100.0);
+        assert_eq!(forecast.upper_bound, 100.0);
+        assert_eq!(forecast.ledgers_ahead, 12);
+        assert_eq!(forecast.sample_ledgers, 20);
+    }
+
+    #[test]
+    fn rising_fees_forecast_higher_with_interval_around_prediction() {
+        let forecast = forecaster_with((0..30).map(|i| 100 + i * 10))
+            .forecast(Duration::minutes(1))
+            .unwrap();
+
+        assert!(forecast.predicted_fee > 390.0);
+        assert!(forecast.lower_bound < forecast.predicted_fee);
+        assert!(forecast.upper_bound > forecast.predicted_fee);
+    }
+
+    #[test]
+    fn fees_in_the_same_ledger_are_averaged() {
+        let mut forecaster = FeeForecaster::new(ForecastConfig {
+            min_ledgers: 1,
+            ..ForecastConfig::default()
+        });
+        let now = Utc::now();
+        forecaster.add_data_point(&point(7, 100, now));
+        forecaster.add_data_point(&point(7, 300, now));
+
+        let forecast = forecaster.forecast(Duration::minutes(1)).unwrap();
+        assert_eq!(forecast.predicted_fee, 200.0);
+        assert_eq!(forecast.sample_ledgers, 1);
+    }
+
+    #[test]
+    fn too_few_ledgers_is_insufficient_data() {
+        let result = forecaster_with([100, 200]).forecast(Duration::minutes(5));
+        assert!(matches!(
+            result,
+            Err(InsightsError::InsufficientData { .. })
+        ));
+    }
+
+    #[test]
+    fn horizon_outside_limits_is_rejected() {
+        let forecaster = forecaster_with(std::iter::repeat_n(100, 20));
+        assert!(forecaster.forecast(Duration::zero()).is_err());
+        assert!(forecaster.forecast(Duration::hours(2)).is_err());
+    }
+
+    #[test]
+    fn oldest_ledgers_are_dropped_past_capacity() {
+        let mut forecaster = FeeForecaster::new(ForecastConfig {
+            max_ledgers: 3,
+            min_ledgers: 1,
+            ..ForecastConfig::default()
+        });
+        let now = Utc::now();
+        for sequence in 1..=5 {
+            forecaster.add_data_point(&point(sequence, 100, now));
+        }
+        forecaster.add_data_point(&point(1, 100, now));
+
+        let sequences: Vec<u64> = forecaster.ledgers.iter().map(|l| l.sequence).collect();
+        assert_eq!(sequences, vec![3, 4, 5]);
+    }
+}
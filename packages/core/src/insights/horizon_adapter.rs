@@ -4,23 +4,68 @@
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
+use std::future::Future;
 use std::str::FromStr;
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::mpsc;
 
 // No direct reqwest import needed — we use the pooled client from HorizonClient.
 
+use crate::config::StellarNetwork;
 use crate::insights::{
+    config::{HorizonAuthConfig, HorizonTimeoutConfig},
+    cursor::{PagingCursor, SharedCursorStore},
+    envelope::{decode_envelope, envelope_details, soroban_fee_detail},
     error::ProviderError,
-    provider::{FeeDataProvider, ProviderMetadata, ProviderResult},
-    types::FeeDataPoint,
+    provider::{
+        FeeDataProvider, ProviderMetadata, ProviderResult, ProviderStatus, StreamingFeeDataProvider,
+    },
+    types::{EnvelopeDetails, FeeDataPoint, FeePercentiles, FeeStatsSnapshot, LedgerInfo},
 };
 use crate::services::horizon::HorizonClient;
+use crate::services::sse::SseDecoder;
+
+/// Page size used when walking historical transactions (Horizon's maximum).
+const HISTORY_PAGE_SIZE: u32 = 200;
+
+/// Upper bound on the delay between stream reconnect attempts.
+const MAX_STREAM_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Pages read per poll while catching up from a persisted cursor.
+const MAX_CATCH_UP_PAGES: usize = 10;
+
+/// Ledgers returned by `fetch_recent_ledgers` (about two minutes of closes).
+const RECENT_LEDGERS: u32 = 20;
 
 /// Adapter that implements FeeDataProvider for HorizonClient
 pub struct HorizonFeeDataProvider {
     client: HorizonClient,
     #[allow(dead_code)]
-    metadata: ProviderMetadata,
+    metadata: RwLock<ProviderMetadata>,
+    stream_reconnect_delay: Duration,
+    network: StellarNetwork,
+    fetch_parallelism: usize,
+    /// Persists the ingestion position; `None` polls the latest page instead.
+    cursor_store: Option<SharedCursorStore>,
+    cursor: tokio::sync::Mutex<CursorState>,
+    /// Limit on each wait for response headers or a body; `None` waits forever.
+    read_timeout: Option<Duration>,
+    /// Limit on a whole provider call; `None` waits forever.
+    request_deadline: Option<Duration>,
+}
+
+/// Polling position while a cursor store is attached.
+#[derive(Default)]
+struct CursorState {
+    /// Whether the saved cursor has been read from the store yet.
+    loaded: bool,
+    position: Option<PagingCursor>,
+    /// `position` is ahead of what the store holds.
+    unsaved: bool,
 }
 
 /// Horizon transaction response for fee data extraction
@@ -42,6 +87,206 @@ struct HorizonTransactionRecord {
     pub created_at: String,
     pub fee_charged: String,
     pub successful: bool,
+    #[serde(default)]
+    pub paging_token: String,
+    #[serde(default)]
+    pub envelope_xdr: Option<String>,
+    #[serde(default)]
+    pub operation_count: Option<u32>,
+    /// Outer bid for fee bumps, the transaction's own bid otherwise.
+    #[serde(default)]
+    pub max_fee: Option<String>,
+    /// Present only on fee-bump transactions.
+    #[serde(default)]
+    pub inner_transaction: Option<HorizonInnerTransaction>,
+    /// Account that paid the fee.
+    #[serde(default)]
+    pub fee_account: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HorizonInnerTransaction {
+    pub max_fee: String,
+}
+
+impl HorizonTransactionRecord {
+    /// Envelope details from the record's JSON fields, for when the XDR is
+    /// missing or cannot be decoded.
+    fn json_envelope_details(&self) -> Option<EnvelopeDetails> {
+        let inner_fee = match &self.inner_transaction {
+            Some(inner) => Some(inner.max_fee.parse().ok()?),
+            None => None,
+        };
+        Some(EnvelopeDetails {
+            operation_count: self.operation_count?,
+            fee_bump: inner_fee.is_some(),
+            max_fee: self.max_fee.as_deref()?.parse().ok()?,
+            inner_fee,
+            category: None,
+            fee_account: self.fee_account.clone(),
+        })
+    }
+}
+
+/// Horizon `/fee_stats` response. Every number is a decimal string.
+#[derive(Debug, Deserialize)]
+struct HorizonFeeStatsResponse {
+    last_ledger: String,
+    last_ledger_base_fee: String,
+    ledger_capacity_usage: String,
+    fee_charged: HorizonFeeDistribution,
+    max_fee: HorizonFeeDistribution,
+}
+
+#[derive(Debug, Deserialize)]
+struct HorizonFeeDistribution {
+    min: String,
+    max: String,
+    mode: String,
+    p10: String,
+    p50: String,
+    p90: String,
+    p95: String,
+    p99: String,
+}
+
+impl HorizonFeeStatsResponse {
+    fn into_snapshot(self) -> ProviderResult<FeeStatsSnapshot> {
+        Ok(FeeStatsSnapshot {
+            last_ledger: parse_stat("last_ledger", &self.last_ledger)?,
+            last_ledger_base_fee: parse_stat("last_ledger_base_fee", &self.last_ledger_base_fee)?,
+            ledger_capacity_usage: parse_stat(
+                "ledger_capacity_usage",
+                &self.ledger_capacity_usage,
+            )?,
+            fee_charged: self.fee_charged.into_percentiles()?,
+            max_fee: self.max_fee.into_percentiles()?,
+            captured_at: Utc::now(),
+        })
+    }
+}
+
+/// Horizon `/ledgers` page
+#[derive(Debug, Deserialize)]
+struct HorizonLedgersResponse {
+    #[serde(rename = "_embedded")]
+    embedded: HorizonLedgersEmbedded,
+}
+
+#[derive(Debug, Deserialize)]
+struct HorizonLedgersEmbedded {
+    records: Vec<HorizonLedgerRecord>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HorizonLedgerRecord {
+    sequence: u64,
+    closed_at: String,
+    successful_transaction_count: u32,
+    /// Null on ledgers ingested before Horizon tracked failures.
+    #[serde(default)]
+    failed_transaction_count: Option<u32>,
+    /// Operations of successful transactions only.
+    operation_count: u32,
+    /// Operations of every transaction in the set, failed ones included.
+    #[serde(default)]
+    tx_set_operation_count: Option<u32>,
+    base_fee_in_stroops: u64,
+    max_tx_set_size: u32,
+}
+
+impl HorizonLedgerRecord {
+    fn into_ledger_info(self) -> ProviderResult<LedgerInfo> {
+        let closed_at = DateTime::parse_from_rfc3339(&self.closed_at)
+            .map_err(|e| ProviderError::FormatError {
+                message: format!("Invalid ledger close time '{}': {}", self.closed_at, e),
+            })?
+            .with_timezone(&Utc);
+
+        Ok(LedgerInfo {
+            sequence: self.sequence,
+            closed_at,
+            transaction_count: self.successful_transaction_count
+                + self.failed_transaction_count.unwrap_or(0),
+            operation_count: self.tx_set_operation_count.unwrap_or(self.operation_count),
+            max_tx_set_size: self.max_tx_set_size,
+            base_fee: self.base_fee_in_stroops,
+        })
+    }
+}
+
+/// Horizon's root resource (`GET /`)
+#[derive(Debug, Deserialize)]
+struct HorizonRootResponse {
+    horizon_version: String,
+    network_passphrase: String,
+    /// Latest ledger ingested into Horizon's history database.
+    history_latest_ledger: u64,
+}
+
+impl HorizonFeeDistribution {
+    fn into_percentiles(self) -> ProviderResult<FeePercentiles> {
+        Ok(FeePercentiles {
+            min: parse_stat("min", &self.min)?,
+            max: parse_stat("max", &self.max)?,
+            mode: parse_stat("mode", &self.mode)?,
+            p10: parse_stat("p10", &self.p10)?,
+            p50: parse_stat("p50", &self.p50)?,
+            p90: parse_stat("p90", &self.p90)?,
+            p95: parse_stat("p95", &self.p95)?,
+            p99: parse_stat("p99", &self.p99)?,
+        })
+    }
+}
+
+/// Map a non-success Horizon status to the matching provider error.
+fn check_status(status: reqwest::StatusCode) -> ProviderResult<()> {
+    match status {
+        s if s.is_success() => Ok(()),
+        reqwest::StatusCode::TOO_MANY_REQUESTS => Err(ProviderError::RateLimitExceeded),
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+            Err(ProviderError::AuthError {
+                message: format!("Horizon rejected credentials (HTTP {})", status),
+            })
+        }
+        _ => Err(ProviderError::NetworkError {
+            message: format!("Horizon returned HTTP {}", status),
+            timeout: false,
+        }),
+    }
+}
+
+/// Map a failed request to a `NetworkError`, flagging reqwest's timeouts.
+fn request_error(context: &str, err: reqwest::Error) -> ProviderError {
+    ProviderError::NetworkError {
+        message: format!("{}: {}", context, err),
+        timeout: err.is_timeout(),
+    }
+}
+
+/// Await `future`, failing with a timeout `NetworkError` once `limit` passes.
+async fn with_limit<T>(
+    limit: Option<Duration>,
+    what: &str,
+    future: impl Future<Output = ProviderResult<T>>,
+) -> ProviderResult<T> {
+    let Some(limit) = limit else {
+        return future.await;
+    };
+    tokio::time::timeout(limit, future)
+        .await
+        .unwrap_or_else(|_| {
+            Err(ProviderError::NetworkError {
+                message: format!("{} timed out after {}ms", what, limit.as_millis()),
+                timeout: true,
+            })
+        })
+}
+
+fn parse_stat<T: FromStr>(field: &str, value: &str) -> ProviderResult<T> {
+    value.parse().map_err(|_| ProviderError::FormatError {
+        message: format!("Invalid fee_stats {}: {}", field, value),
+    })
 }
 
 impl HorizonFeeDataProvider {
@@ -52,9 +297,77 @@ impl HorizonFeeDataProvider {
             max_batch_size: 200,               // Horizon's default limit
             rate_limit_per_minute: Some(3600), // Horizon's rate limit
             data_freshness_seconds: 5,         // Stellar ledger close time
+            ..ProviderMetadata::default()
         };
 
-        Self { client, metadata }
+        Self {
+            client,
+            metadata: RwLock::new(metadata),
+            stream_reconnect_delay: Duration::from_secs(1),
+            network: StellarNetwork::Testnet,
+            fetch_parallelism: 1,
+            cursor_store: None,
+            cursor: tokio::sync::Mutex::new(CursorState::default()),
+            read_timeout: None,
+            request_deadline: None,
+        }
+    }
+
+    /// Bound connecting, each response read and each whole provider call, so
+    /// a slow Horizon node fails fast instead of stalling the scheduler.
+    /// Exceeding any of them yields a `NetworkError` with `timeout` set.
+    pub fn with_timeouts(mut self, timeouts: &HorizonTimeoutConfig) -> Self {
+        let to_std = |d: chrono::Duration| d.to_std().ok().filter(|d| !d.is_zero());
+        if let Some(connect) = to_std(timeouts.connect_timeout) {
+            self.client = self.client.with_connect_timeout(connect);
+        }
+        self.read_timeout = to_std(timeouts.read_timeout);
+        self.request_deadline = to_std(timeouts.request_deadline);
+        self
+    }
+
+    /// Persist the ingestion position in `store` and resume from it, so a
+    /// restart neither re-fetches nor skips transactions.
+    ///
+    /// Polls then walk `/transactions` forward from the cursor instead of
+    /// reading the latest page, and streams start from it instead of `now`.
+    pub fn with_cursor_store(mut self, store: SharedCursorStore) -> Self {
+        self.cursor_store = Some(store);
+        self
+    }
+
+    /// Split historical range fetches into up to `parallelism` ledger
+    /// segments that are paged concurrently. `1` keeps fetches sequential.
+    pub fn with_fetch_parallelism(mut self, parallelism: usize) -> Self {
+        self.fetch_parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Authenticate every request against a private Horizon deployment.
+    #[allow(dead_code)]
+    pub fn with_auth(mut self, auth: HorizonAuthConfig) -> Self {
+        self.client = self.client.with_auth(auth);
+        self
+    }
+
+    /// Tag this provider with the network its Horizon instance serves.
+    pub fn with_network(mut self, network: StellarNetwork) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// The network this provider reads fees from.
+    #[allow(dead_code)]
+    pub fn network(&self) -> StellarNetwork {
+        self.network
+    }
+
+    /// Set the initial delay before reconnecting a dropped stream.
+    /// The delay doubles on each consecutive failure, capped at 30s.
+    #[allow(dead_code)]
+    pub fn with_stream_reconnect_delay(mut self, delay: Duration) -> Self {
+        self.stream_reconnect_delay = delay;
+        self
     }
 
     /// Fetch recent transactions from Horizon using the shared pooled HTTP client.
@@ -67,34 +380,210 @@ impl HorizonFeeDataProvider {
             self.client.base_url(),
             limit
         );
+        self.fetch_transactions_page(&url).await
+    }
+
+    /// Fetch one page of transactions in ascending order, strictly after `cursor`.
+    async fn fetch_transactions_after(
+        &self,
+        cursor: &str,
+        limit: u32,
+    ) -> ProviderResult<Vec<HorizonTransactionRecord>> {
+        let url = format!(
+            "{}/transactions?order=asc&limit={}&cursor={}",
+            self.client.base_url(),
+            limit,
+            cursor
+        );
+        self.fetch_transactions_page(&url).await
+    }
+
+    async fn fetch_transactions_page(
+        &self,
+        url: &str,
+    ) -> ProviderResult<Vec<HorizonTransactionRecord>> {
+        let transaction_response: HorizonTransactionResponse =
+            self.fetch_json(url, "transactions").await?;
+        Ok(transaction_response.embedded.records)
+    }
+
+    /// GET `url` and decode the JSON body, honouring Horizon's rate limits.
+    async fn fetch_json<T: DeserializeOwned>(&self, url: &str, what: &str) -> ProviderResult<T> {
+        self.throttle().await?;
 
         // Use the pooled client from HorizonClient instead of spawning ephemeral
         // reqwest clients, so we get TCP connection reuse across poll ticks.
-        let response = self
-            .client
-            .http_client()
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| ProviderError::NetworkError {
-                message: format!("Failed to fetch transactions: {}", e),
-            })?;
+        let response = with_limit(self.read_timeout, &format!("Fetching {}", what), async {
+            self.client
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| request_error(&format!("Failed to fetch {}", what), e))
+        })
+        .await?;
+        self.client
+            .record_rate_limit(response.status(), response.headers());
+        check_status(response.status())?;
 
-        if !response.status().is_success() {
-            return Err(ProviderError::NetworkError {
-                message: format!("Horizon returned HTTP {}", response.status()),
-            });
+        with_limit(self.read_timeout, &format!("Reading {}", what), async {
+            response.json().await.map_err(|e| {
+                if e.is_timeout() {
+                    request_error(&format!("Failed to read {}", what), e)
+                } else {
+                    ProviderError::FormatError {
+                        message: format!("Failed to parse {} response: {}", what, e),
+                    }
+                }
+            })
+        })
+        .await
+    }
+
+    /// Page through every successful transaction in `start_ledger..=end_ledger`.
+    async fn fetch_ledger_segment(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        // Paging tokens are TOIDs (ledger << 32 | tx order << 12), so this
+        // cursor sits just before the first transaction of `start_ledger`.
+        let mut cursor = (start_ledger << 32).to_string();
+        let mut fee_data_points = Vec::new();
+
+        loop {
+            let records = self
+                .fetch_transactions_after(&cursor, HISTORY_PAGE_SIZE)
+                .await?;
+            let page_len = records.len();
+
+            for record in records {
+                if record.ledger > end_ledger {
+                    return Ok(fee_data_points);
+                }
+                cursor = record.paging_token.clone();
+                if record.ledger < start_ledger || !record.successful {
+                    continue;
+                }
+                match self.convert_to_fee_data_point(record) {
+                    Ok(fee_point) => fee_data_points.push(fee_point),
+                    Err(e) => {
+                        tracing::warn!("Failed to convert historical transaction: {}", e);
+                    }
+                }
+            }
+
+            if page_len < HISTORY_PAGE_SIZE as usize || cursor.is_empty() {
+                return Ok(fee_data_points);
+            }
         }
+    }
 
-        let transaction_response: HorizonTransactionResponse =
-            response
-                .json()
-                .await
-                .map_err(|e| ProviderError::FormatError {
-                    message: format!("Failed to parse transaction response: {}", e),
-                })?;
+    /// Hold off while Horizon's rate-limit window is exhausted.
+    async fn throttle(&self) -> ProviderResult<()> {
+        self.client.throttle().await.map_err(|wait| {
+            tracing::warn!(
+                "Horizon rate limit exhausted; next window in {}s",
+                wait.as_secs()
+            );
+            ProviderError::RateLimitExceeded
+        })
+    }
 
-        Ok(transaction_response.embedded.records)
+    /// Convert `records`, logging and skipping the ones that fail.
+    fn convert_records(&self, records: Vec<HorizonTransactionRecord>) -> Vec<FeeDataPoint> {
+        let mut fee_data_points = Vec::new();
+        for record in records {
+            match self.convert_to_fee_data_point(record) {
+                Ok(fee_point) => fee_data_points.push(fee_point),
+                Err(e) => {
+                    // Log the error but continue processing other transactions
+                    tracing::warn!("Failed to convert transaction to fee data point: {}", e);
+                }
+            }
+        }
+        fee_data_points
+    }
+
+    /// Poll forward from the persisted cursor.
+    ///
+    /// The position reached by one call is saved at the start of the next,
+    /// by which time the caller has stored the batch: a crash re-reads at
+    /// most one batch and never skips one. With nothing saved yet, polling
+    /// starts from the most recent transactions.
+    async fn fetch_from_cursor(
+        &self,
+        store: &SharedCursorStore,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        let mut state = self.cursor.lock().await;
+        if !state.loaded {
+            match store.load_cursor().await {
+                Ok(saved) => {
+                    if let Some(saved) = &saved {
+                        tracing::info!(
+                            "Resuming Horizon ingestion after ledger {} (cursor {})",
+                            saved.ledger_sequence,
+                            saved.paging_token
+                        );
+                    }
+                    state.position = saved;
+                }
+                Err(e) => tracing::warn!("Failed to load ingestion cursor: {}", e),
+            }
+            state.loaded = true;
+        }
+        if state.unsaved {
+            if let Some(position) = &state.position {
+                match store.save_cursor(position).await {
+                    Ok(()) => state.unsaved = false,
+                    Err(e) => tracing::warn!("Failed to save ingestion cursor: {}", e),
+                }
+            }
+        }
+
+        let records = match &state.position {
+            None => {
+                // Latest page comes newest first; walk it oldest first.
+                let mut records = self.fetch_recent_transactions(100).await?;
+                records.reverse();
+                records
+            }
+            Some(position) => {
+                let mut records = Vec::new();
+                let mut cursor = position.paging_token.clone();
+                for _ in 0..MAX_CATCH_UP_PAGES {
+                    let page = self
+                        .fetch_transactions_after(&cursor, HISTORY_PAGE_SIZE)
+                        .await?;
+                    let full_page = page.len() == HISTORY_PAGE_SIZE as usize;
+                    if let Some(last) = page.last() {
+                        cursor = last.paging_token.clone();
+                    }
+                    records.extend(page);
+                    if !full_page {
+                        break;
+                    }
+                }
+                records
+            }
+        };
+
+        if let Some(last) = records.iter().rev().find(|r| !r.paging_token.is_empty()) {
+            state.position = Some(PagingCursor {
+                paging_token: last.paging_token.clone(),
+                ledger_sequence: last.ledger,
+            });
+            state.unsaved = true;
+        }
+        Ok(self.convert_records(records))
+    }
+
+    /// Save `cursor` to the attached store, if any. Failures are logged.
+    async fn persist_cursor(&self, cursor: &PagingCursor) {
+        if let Some(store) = &self.cursor_store {
+            if let Err(e) = store.save_cursor(cursor).await {
+                tracing::warn!("Failed to save ingestion cursor: {}", e);
+            }
+        }
     }
 
     /// Convert Horizon transaction record to FeeDataPoint
@@ -122,11 +611,32 @@ impl HorizonFeeDataProvider {
             })?
             .with_timezone(&Utc);
 
+        // Envelope details are best-effort: a point without them is still useful.
+        let decoded = record
+            .envelope_xdr
+            .as_deref()
+            .and_then(|xdr| match decode_envelope(xdr) {
+                Ok(envelope) => Some(envelope),
+                Err(e) => {
+                    tracing::debug!("Skipping envelope details for '{}': {}", record.hash, e);
+                    None
+                }
+            });
+        let envelope = decoded
+            .as_ref()
+            .map(envelope_details)
+            .or_else(|| record.json_envelope_details());
+        // Horizon does not return the result meta, so only the declared
+        // resources and bid are known here.
+        let soroban = decoded.as_ref().and_then(soroban_fee_detail);
+
         Ok(FeeDataPoint {
             fee_amount,
             timestamp,
             transaction_hash: record.hash,
             ledger_sequence: record.ledger,
+            envelope,
+            soroban,
         })
     }
 }
@@ -134,28 +644,87 @@ impl HorizonFeeDataProvider {
 #[async_trait]
 impl FeeDataProvider for HorizonFeeDataProvider {
     async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
-        // Fetch recent transactions (last 100 by default)
-        let transactions = self.fetch_recent_transactions(100).await?;
+        with_limit(self.request_deadline, "Fetching latest fees", async {
+            if let Some(store) = &self.cursor_store {
+                return self.fetch_from_cursor(store).await;
+            }
 
-        // Convert to fee data points, filtering out failed conversions
-        let mut fee_data_points = Vec::new();
-        for transaction in transactions {
-            match self.convert_to_fee_data_point(transaction) {
-                Ok(fee_point) => fee_data_points.push(fee_point),
-                Err(e) => {
-                    // Log the error but continue processing other transactions
-                    tracing::warn!("Failed to convert transaction to fee data point: {}", e);
-                }
+            // Fetch recent transactions (last 100 by default)
+            let transactions = self.fetch_recent_transactions(100).await?;
+            let fee_data_points = self.convert_records(transactions);
+
+            if fee_data_points.is_empty() {
+                return Err(ProviderError::FormatError {
+                    message: "No valid fee data points found in recent transactions".to_string(),
+                });
             }
-        }
 
-        if fee_data_points.is_empty() {
+            Ok(fee_data_points)
+        })
+        .await
+    }
+
+    /// Walk `/transactions` in ascending order from the first transaction of
+    /// `start_ledger` until a record past `end_ledger` is seen.
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        if start_ledger > end_ledger {
             return Err(ProviderError::FormatError {
-                message: "No valid fee data points found in recent transactions".to_string(),
+                message: format!(
+                    "start_ledger {} is after end_ledger {}",
+                    start_ledger, end_ledger
+                ),
             });
         }
 
-        Ok(fee_data_points)
+        let segments = split_ledger_range(start_ledger, end_ledger, self.fetch_parallelism);
+        if segments.len() == 1 {
+            return self.fetch_ledger_segment(start_ledger, end_ledger).await;
+        }
+
+        // `buffered` keeps at most `fetch_parallelism` segments in flight and
+        // yields them in ledger order, so the merged result stays sorted.
+        let pages: Vec<Vec<FeeDataPoint>> = stream::iter(segments)
+            .map(|(start, end)| self.fetch_ledger_segment(start, end))
+            .buffered(self.fetch_parallelism)
+            .try_collect()
+            .await?;
+
+        Ok(pages.into_iter().flatten().collect())
+    }
+
+    async fn fetch_fee_stats(&self) -> ProviderResult<FeeStatsSnapshot> {
+        let url = format!("{}/fee_stats", self.client.base_url());
+        let response: HorizonFeeStatsResponse = with_limit(
+            self.request_deadline,
+            "Fetching fee_stats",
+            self.fetch_json(&url, "fee_stats"),
+        )
+        .await?;
+        response.into_snapshot()
+    }
+
+    async fn fetch_recent_ledgers(&self) -> ProviderResult<Vec<LedgerInfo>> {
+        let url = format!(
+            "{}/ledgers?order=desc&limit={}",
+            self.client.base_url(),
+            RECENT_LEDGERS
+        );
+        let response: HorizonLedgersResponse = with_limit(
+            self.request_deadline,
+            "Fetching ledgers",
+            self.fetch_json(&url, "ledgers"),
+        )
+        .await?;
+        response
+            .embedded
+            .records
+            .into_iter()
+            .map(HorizonLedgerRecord::into_ledger_info)
+            .collect()
     }
 
     fn provider_name(&self) -> &str {
@@ -164,17 +733,764 @@ impl FeeDataProvider for HorizonFeeDataProvider {
 
     async fn health_check(&self) -> ProviderResult<()> {
         // Use the existing fee_stats endpoint for health check
-        self.client
-            .fetch_fee_stats()
-            .await
-            .map_err(|e| ProviderError::NetworkError {
-                message: format!("Horizon health check failed: {}", e),
-            })?;
+        with_limit(self.request_deadline, "Horizon health check", async {
+            self.client
+                .fetch_fee_stats()
+                .await
+                .map_err(|e| ProviderError::NetworkError {
+                    message: format!("Horizon health check failed: {}", e),
+                    timeout: false,
+                })
+        })
+        .await?;
 
         Ok(())
     }
 
     fn get_metadata(&self) -> ProviderMetadata {
-        self.metadata.clone()
+        self.metadata
+            .read()
+            .expect("metadata lock poisoned")
+            .clone()
+    }
+
+    /// Reads the network passphrase, ingested ledger and version from
+    /// Horizon's root resource.
+    async fn refresh_metadata(&self) -> ProviderResult<ProviderMetadata> {
+        let url = format!("{}/", self.client.base_url());
+        let root: HorizonRootResponse = with_limit(
+            self.request_deadline,
+            "Fetching Horizon root",
+            self.fetch_json(&url, "root"),
+        )
+        .await?;
+
+        let mut metadata = self.metadata.write().expect("metadata lock poisoned");
+        metadata.network_passphrase = Some(root.network_passphrase);
+        metadata.latest_ledger = Some(root.history_latest_ledger);
+        metadata.horizon_version = Some(root.horizon_version);
+        metadata.refreshed_at = Some(Utc::now());
+        Ok(metadata.clone())
+    }
+
+    fn provider_status(&self) -> ProviderStatus {
+        ProviderStatus {
+            rate_limit: Some(self.client.rate_limit_status()),
+            ..ProviderStatus::default()
+        }
+    }
+}
+
+/// Split `start..=end` into at most `parts` contiguous, non-empty segments.
+fn split_ledger_range(start: u64, end: u64, parts: usize) -> Vec<(u64, u64)> {
+    let len = end - start + 1;
+    let segment_len = len.div_ceil(parts.max(1) as u64).max(1);
+
+    let mut segments = Vec::new();
+    let mut segment_start = start;
+    loop {
+        let segment_end = segment_start.saturating_add(segment_len - 1).min(end);
+        segments.push((segment_start, segment_end));
+        if segment_end >= end {
+            return segments;
+        }
+        segment_start = segment_end + 1;
+    }
+}
+
+impl HorizonFeeDataProvider {
+    /// Consume one SSE connection, forwarding fee points and advancing `cursor`.
+    ///
+    /// The cursor is persisted each time a new ledger starts, so a restart
+    /// resumes at the last ledger boundary.
+    ///
+    /// Returns `Ok(true)` when the receiver was dropped (stop streaming) and
+    /// `Ok(false)` when the server closed the stream (reconnect).
+    async fn stream_once(
+        &self,
+        cursor: &mut PagingCursor,
+        sender: &mpsc::Sender<FeeDataPoint>,
+        received_any: &mut bool,
+    ) -> ProviderResult<bool> {
+        let url = format!(
+            "{}/transactions?order=asc&cursor={}",
+            self.client.base_url(),
+            cursor.paging_token
+        );
+
+        self.throttle().await?;
+
+        let mut response = self
+            .client
+            .get(&url)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .await
+            .map_err(|e| request_error("Failed to open transaction stream", e))?;
+        self.client
+            .record_rate_limit(response.status(), response.headers());
+        check_status(response.status())?;
+
+        let mut decoder = SseDecoder::new();
+        loop {
+            let chunk = response
+                .chunk()
+                .await
+                .map_err(|e| request_error("Transaction stream interrupted", e))?;
+            let Some(chunk) = chunk else {
+                return Ok(false);
+            };
+
+            for event in decoder.push(&chunk) {
+                // Horizon opens with a `"hello"` event and closes with `"byebye"`;
+                // neither carries a record.
+                let record = match serde_json::from_str::<HorizonTransactionRecord>(&event.data) {
+                    Ok(record) => record,
+                    Err(_) => continue,
+                };
+                *received_any = true;
+
+                if record.ledger > cursor.ledger_sequence && cursor.ledger_sequence > 0 {
+                    self.persist_cursor(cursor).await;
+                }
+                if let Some(id) = event.id.filter(|id| !id.is_empty()) {
+                    cursor.paging_token = id;
+                } else if !record.paging_token.is_empty() {
+                    cursor.paging_token = record.paging_token.clone();
+                }
+                cursor.ledger_sequence = record.ledger;
+
+                if !record.successful {
+                    continue;
+                }
+                match self.convert_to_fee_data_point(record) {
+                    Ok(point) => {
+                        if sender.send(point).await.is_err() {
+                            return Ok(true);
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to convert streamed transaction: {}", e),
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl StreamingFeeDataProvider for HorizonFeeDataProvider {
+    async fn stream_fees(&self, sender: mpsc::Sender<FeeDataPoint>) -> ProviderResult<()> {
+        let saved = match &self.cursor_store {
+            Some(store) => store.load_cursor().await.unwrap_or_else(|e| {
+                tracing::warn!("Failed to load ingestion cursor: {}", e);
+                None
+            }),
+            None => None,
+        };
+        let mut cursor = saved.unwrap_or_else(|| PagingCursor {
+            paging_token: "now".to_string(),
+            ledger_sequence: 0,
+        });
+        let mut delay = self.stream_reconnect_delay;
+
+        loop {
+            let mut received_any = false;
+            match self
+                .stream_once(&mut cursor, &sender, &mut received_any)
+                .await
+            {
+                Ok(true) => return Ok(()),
+                Ok(false) => tracing::info!(
+                    "Horizon stream closed; resuming from {}",
+                    cursor.paging_token
+                ),
+                Err(e) => tracing::warn!(
+                    "Horizon stream error: {} — resuming from {}",
+                    e,
+                    cursor.paging_token
+                ),
+            }
+
+            if sender.is_closed() {
+                return Ok(());
+            }
+            if received_any {
+                delay = self.stream_reconnect_delay;
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_STREAM_RECONNECT_DELAY);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::insights::cursor::CursorStore;
+    use crate::insights::error::InsightsError;
+    use crate::insights::types::OperationCategory;
+    use serde_json::json;
+    use std::sync::Arc;
+    use wiremock::{
+        matchers::{header, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn record(ledger: u64, order: u64, successful: bool) -> serde_json::Value {
+        json!({
+            "hash": format!("tx_{}_{}", ledger, order),
+            "ledger": ledger,
+            "created_at": "2024-01-15T10:30:00Z",
+            "fee_charged": "100",
+            "successful": successful,
+            "paging_token": ((ledger << 32) | (order << 12)).to_string()
+        })
+    }
+
+    fn page(records: Vec<serde_json::Value>) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({ "_embedded": { "records": records } }))
+    }
+
+    #[test]
+    fn convert_decodes_fee_bump_envelope_details() {
+        use stellar_xdr::curr::{
+            FeeBumpTransaction, FeeBumpTransactionEnvelope, FeeBumpTransactionExt,
+            FeeBumpTransactionInnerTx, Limits, Memo, MuxedAccount, Operation, OperationBody,
+            Preconditions, SequenceNumber, Transaction, TransactionEnvelope, TransactionExt,
+            TransactionV1Envelope, Uint256, VecM, WriteXdr,
+        };
+
+        let inner = TransactionV1Envelope {
+            tx: Transaction {
+                source_account: MuxedAccount::Ed25519(Uint256([0; 32])),
+                fee: 200,
+                seq_num: SequenceNumber(1),
+                cond: Preconditions::None,
+                memo: Memo::None,
+                operations: vec![
+                    Operation {
+                        source_account: None,
+                        body: OperationBody::Inflation,
+                    };
+                    2
+                ]
+                .try_into()
+                .unwrap(),
+                ext: TransactionExt::V0,
+            },
+            signatures: VecM::default(),
+        };
+        let envelope = TransactionEnvelope::TxFeeBump(FeeBumpTransactionEnvelope {
+            tx: FeeBumpTransaction {
+                fee_source: MuxedAccount::Ed25519(Uint256([1; 32])),
+                fee: 5_000,
+                inner_tx: FeeBumpTransactionInnerTx::Tx(inner),
+                ext: FeeBumpTransactionExt::V0,
+            },
+            signatures: VecM::default(),
+        });
+
+        let mut value = record(10, 1, true);
+        value["envelope_xdr"] = json!(envelope.to_xdr_base64(Limits::none()).unwrap());
+        let record: HorizonTransactionRecord = serde_json::from_value(value).unwrap();
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new("http://localhost".into()));
+        let point = provider.convert_to_fee_data_point(record).unwrap();
+
+        assert_eq!(
+            point.envelope,
+            Some(EnvelopeDetails {
+                operation_count: 2,
+                fee_bump: true,
+                max_fee: 5_000,
+                inner_fee: Some(200),
+                category: Some(OperationCategory::Other),
+                fee_account: Some(MuxedAccount::Ed25519(Uint256([1; 32])).to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn convert_reads_fee_bump_fields_without_envelope_xdr() {
+        let mut value = record(10, 1, true);
+        value["operation_count"] = json!(3);
+        value["max_fee"] = json!("2000");
+        value["fee_bump_transaction"] = json!({ "hash": "outer", "signatures": [] });
+        value["inner_transaction"] = json!({ "hash": "inner", "max_fee": "300" });
+        value["fee_account"] = json!("GSPONSOR");
+        let record: HorizonTransactionRecord = serde_json::from_value(value).unwrap();
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new("http://localhost".into()));
+        let point = provider.convert_to_fee_data_point(record).unwrap();
+
+        assert!(point.is_fee_bump());
+        assert_eq!(point.inner_fee(), Some(300));
+        let envelope = point.envelope.unwrap();
+        assert_eq!(envelope.max_fee, 2000);
+        assert_eq!(envelope.fee_account.as_deref(), Some("GSPONSOR"));
+    }
+
+    #[test]
+    fn convert_tolerates_undecodable_envelope() {
+        let mut value = record(10, 1, true);
+        value["envelope_xdr"] = json!("not-xdr");
+        let record: HorizonTransactionRecord = serde_json::from_value(value).unwrap();
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new("http://localhost".into()));
+        let point = provider.convert_to_fee_data_point(record).unwrap();
+
+        assert_eq!(point.fee_amount, 100);
+        assert!(point.envelope.is_none());
+    }
+
+    #[tokio::test]
+    async fn fetch_fees_range_starts_at_ledger_cursor_and_stops_past_end() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/transactions"))
+            .and(query_param("order", "asc"))
+            .and(query_param("cursor", (10u64 << 32).to_string()))
+            .respond_with(page(vec![
+                record(10, 1, true),
+                record(10, 2, false),
+                record(11, 1, true),
+                record(13, 1, true),
+            ]))
+            .mount(&server)
+            .await;
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri()));
+        let points = provider.fetch_fees_range(10, 12).await.unwrap();
+
+        let ledgers: Vec<u64> = points.iter().map(|p| p.ledger_sequence).collect();
+        assert_eq!(ledgers, vec![10, 11]);
+    }
+
+    #[test]
+    fn split_ledger_range_covers_range_without_overlap() {
+        assert_eq!(split_ledger_range(10, 12, 1), vec![(10, 12)]);
+        assert_eq!(split_ledger_range(1, 10, 3), vec![(1, 4), (5, 8), (9, 10)]);
+        assert_eq!(split_ledger_range(5, 6, 8), vec![(5, 5), (6, 6)]);
+        assert_eq!(split_ledger_range(u64::MAX - 1, u64::MAX, 4).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn parallel_fetch_merges_segments_in_ledger_order() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/transactions"))
+            .and(query_param("cursor", (10u64 << 32).to_string()))
+            .respond_with(
+                page(vec![
+                    record(10, 1, true),
+                    record(11, 1, true),
+                    record(12, 1, true),
+                ])
+                .set_delay(Duration::from_millis(100)),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/transactions"))
+            .and(query_param("cursor", (12u64 << 32).to_string()))
+            .respond_with(page(vec![record(12, 1, true), record(13, 1, true)]))
+            .mount(&server)
+            .await;
+
+        let provider =
+            HorizonFeeDataProvider::new(HorizonClient::new(server.uri())).with_fetch_parallelism(2);
+        let points = provider.fetch_fees_range(10, 13).await.unwrap();
+
+        let ledgers: Vec<u64> = points.iter().map(|p| p.ledger_sequence).collect();
+        assert_eq!(ledgers, vec![10, 11, 12, 13]);
+    }
+
+    fn distribution(base: u64) -> serde_json::Value {
+        json!({
+            "min": base.to_string(), "max": (base * 10).to_string(), "mode": base.to_string(),
+            "p10": base.to_string(), "p20": base.to_string(), "p30": base.to_string(),
+            "p40": base.to_string(), "p50": (base * 2).to_string(), "p60": base.to_string(),
+            "p70": base.to_string(), "p80": base.to_string(), "p90": (base * 5).to_string(),
+            "p95": (base * 6).to_string(), "p99": (base * 9).to_string()
+        })
+    }
+
+    #[tokio::test]
+    async fn fetch_fee_stats_parses_capacity_and_percentiles() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/fee_stats"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "last_ledger": "52000000",
+                "last_ledger_base_fee": "100",
+                "ledger_capacity_usage": "0.97",
+                "fee_charged": distribution(100),
+                "max_fee": distribution(1000)
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri()));
+        let stats = provider.fetch_fee_stats().await.unwrap();
+
+        assert_eq!(stats.last_ledger, 52_000_000);
+        assert!((stats.ledger_capacity_usage - 0.97).abs() < f64::EPSILON);
+        assert_eq!(stats.fee_charged.p50, 200);
+        assert_eq!(stats.max_fee.p99, 9000);
+    }
+
+    #[tokio::test]
+    async fn fetch_recent_ledgers_counts_failed_transactions_toward_capacity() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ledgers"))
+            .and(query_param("order", "desc"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "_embedded": { "records": [
+                    {
+                        "sequence": 52000001,
+                        "closed_at": "2024-01-15T10:30:05Z",
+                        "successful_transaction_count": 180,
+                        "failed_transaction_count": 20,
+                        "operation_count": 850,
+                        "tx_set_operation_count": 950,
+                        "base_fee_in_stroops": 100,
+                        "max_tx_set_size": 1000
+                    },
+                    {
+                        "sequence": 52000000,
+                        "closed_at": "2024-01-15T10:30:00Z",
+                        "successful_transaction_count": 10,
+                        "failed_transaction_count": null,
+                        "operation_count": 12,
+                        "tx_set_operation_count": null,
+                        "base_fee_in_stroops": 100,
+                        "max_tx_set_size": 1000
+                    }
+                ]}
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri()));
+        let ledgers = provider.fetch_recent_ledgers().await.unwrap();
+
+        assert_eq!(ledgers.len(), 2);
+        assert_eq!(ledgers[0].sequence, 52_000_001);
+        assert_eq!(ledgers[0].transaction_count, 200);
+        assert_eq!(ledgers[0].operation_count, 950);
+        assert_eq!(ledgers[0].capacity_utilization(), Some(0.95));
+        assert_eq!(ledgers[0].closed_at.timestamp(), 1_705_314_605);
+        assert_eq!(ledgers[1].transaction_count, 10);
+        assert_eq!(ledgers[1].operation_count, 12);
+    }
+
+    fn timeouts(read_ms: i64, deadline_ms: i64) -> HorizonTimeoutConfig {
+        HorizonTimeoutConfig {
+            connect_timeout: chrono::Duration::seconds(1),
+            read_timeout: chrono::Duration::milliseconds(read_ms),
+            request_deadline: chrono::Duration::milliseconds(deadline_ms),
+        }
+    }
+
+    #[tokio::test]
+    async fn slow_response_hits_read_timeout() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/fee_stats"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri()))
+            .with_timeouts(&timeouts(50, 5_000));
+        let err = provider.fetch_fee_stats().await.unwrap_err();
+
+        assert!(err.is_timeout(), "expected a timeout, got {}", err);
+        assert!(err.to_string().contains("(timeout)"));
+    }
+
+    #[tokio::test]
+    async fn call_past_the_deadline_times_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/transactions"))
+            .respond_with(page(vec![record(40, 1, true)]).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri()))
+            .with_timeouts(&timeouts(5_000, 50));
+        let err = provider.fetch_latest_fees().await.unwrap_err();
+
+        assert!(err.is_timeout(), "expected a timeout, got {}", err);
+        assert!(err.to_string().contains("Fetching latest fees timed out"));
+    }
+
+    #[tokio::test]
+    async fn fetch_fee_stats_rejects_non_numeric_fields() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/fee_stats"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "last_ledger": "abc",
+                "last_ledger_base_fee": "100",
+                "ledger_capacity_usage": "0.5",
+                "fee_charged": distribution(100),
+                "max_fee": distribution(100)
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri()));
+        assert!(matches!(
+            provider.fetch_fee_stats().await,
+            Err(ProviderError::FormatError { .. })
+        ));
+    }
+
+    fn sse_body(records: &[serde_json::Value]) -> String {
+        let mut body = String::from("retry: 1000\nevent: open\ndata: \"hello\"\n\n");
+        for record in records {
+            body.push_str(&format!(
+                "id: {}\ndata: {}\n\n",
+                record["paging_token"].as_str().unwrap(),
+                record
+            ));
+        }
+        body
+    }
+
+    #[tokio::test]
+    async fn stream_fees_forwards_points_and_resumes_from_last_cursor() {
+        let server = MockServer::start().await;
+        let first = vec![record(20, 1, true), record(20, 2, false)];
+        let resumed = vec![record(21, 1, true)];
+
+        Mock::given(method("GET"))
+            .and(path("/transactions"))
+            .and(query_param("cursor", "now"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(sse_body(&first)),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/transactions"))
+            .and(query_param(
+                "cursor",
+                first[1]["paging_token"].as_str().unwrap(),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(sse_body(&resumed)),
+            )
+            .mount(&server)
+            .await;
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri()))
+            .with_stream_reconnect_delay(Duration::from_millis(10));
+        let (tx, mut rx) = mpsc::channel(8);
+        let handle = tokio::spawn(async move { provider.stream_fees(tx).await });
+
+        let a = rx.recv().await.unwrap();
+        let b = rx.recv().await.unwrap();
+        assert_eq!(a.ledger_sequence, 20);
+        assert_eq!(b.ledger_sequence, 21);
+
+        drop(rx);
+        let result = tokio::time::timeout(Duration::from_secs(5), handle).await;
+        assert!(result.unwrap().unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn rate_limited_response_maps_to_rate_limit_error_and_updates_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/transactions"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("x-ratelimit-limit", "3600")
+                    .insert_header("x-ratelimit-remaining", "0")
+                    .insert_header("retry-after", "600"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri()));
+        let first = provider.fetch_latest_fees().await;
+        // The second call is refused locally without reaching Horizon.
+        let second = provider.fetch_latest_fees().await;
+
+        assert!(matches!(first, Err(ProviderError::RateLimitExceeded)));
+        assert!(matches!(second, Err(ProviderError::RateLimitExceeded)));
+        let status = provider.provider_status().rate_limit.unwrap();
+        assert_eq!(status.limit, Some(3600));
+        assert_eq!(status.remaining, Some(0));
+        assert!(status.throttled);
+    }
+
+    #[tokio::test]
+    async fn fetch_fees_range_rejects_inverted_range() {
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new("http://localhost".into()));
+        let result = provider.fetch_fees_range(20, 10).await;
+        assert!(matches!(result, Err(ProviderError::FormatError { .. })));
+    }
+
+    #[tokio::test]
+    async fn auth_token_is_sent_with_requests() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/transactions"))
+            .and(header("x-api-key", "s3cret"))
+            .respond_with(page(vec![record(10, 1, true)]))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri())).with_auth(
+            HorizonAuthConfig {
+                header_name: "X-Api-Key".into(),
+                token: Some("s3cret".into()),
+                basic: None,
+            },
+        );
+
+        assert_eq!(provider.fetch_latest_fees().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejected_credentials_map_to_auth_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/transactions"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fee_stats"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri()));
+
+        assert!(matches!(
+            provider.fetch_latest_fees().await,
+            Err(ProviderError::AuthError { .. })
+        ));
+        assert!(matches!(
+            provider.fetch_fee_stats().await,
+            Err(ProviderError::AuthError { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn refresh_metadata_reads_horizon_root() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "horizon_version": "2.32.0",
+                "core_version": "stellar-core 22.0.0",
+                "history_latest_ledger": 52000000,
+                "core_latest_ledger": 52000001,
+                "network_passphrase": "Test SDF Network ; September 2015"
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri()));
+        assert!(provider.get_metadata().refreshed_at.is_none());
+
+        let metadata = provider.refresh_metadata().await.unwrap();
+        assert_eq!(metadata.horizon_version.as_deref(), Some("2.32.0"));
+        assert_eq!(metadata.latest_ledger, Some(52_000_000));
+        assert_eq!(
+            metadata.network_passphrase.as_deref(),
+            Some("Test SDF Network ; September 2015")
+        );
+        // Static capabilities are kept and the refresh is cached.
+        assert!(metadata.supports_historical);
+        assert_eq!(provider.get_metadata().latest_ledger, Some(52_000_000));
+    }
+
+    /// In-memory `CursorStore` recording every save.
+    #[derive(Default)]
+    struct MemoryCursorStore {
+        saved: std::sync::Mutex<Vec<PagingCursor>>,
+    }
+
+    #[async_trait]
+    impl CursorStore for MemoryCursorStore {
+        async fn load_cursor(&self) -> Result<Option<PagingCursor>, InsightsError> {
+            Ok(self.saved.lock().unwrap().last().cloned())
+        }
+
+        async fn save_cursor(&self, cursor: &PagingCursor) -> Result<(), InsightsError> {
+            self.saved.lock().unwrap().push(cursor.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn polling_resumes_from_saved_cursor_and_saves_on_next_fetch() {
+        let server = MockServer::start().await;
+        let batch = vec![record(30, 1, true), record(30, 2, true)];
+        let last_token = batch[1]["paging_token"].as_str().unwrap().to_string();
+        Mock::given(method("GET"))
+            .and(path("/transactions"))
+            .and(query_param("order", "asc"))
+            .and(query_param("cursor", "saved-token"))
+            .respond_with(page(batch))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/transactions"))
+            .and(query_param("cursor", last_token.as_str()))
+            .respond_with(page(vec![]))
+            .mount(&server)
+            .await;
+
+        let store = Arc::new(MemoryCursorStore::default());
+        store.saved.lock().unwrap().push(PagingCursor {
+            paging_token: "saved-token".to_string(),
+            ledger_sequence: 29,
+        });
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri()))
+            .with_cursor_store(store.clone());
+
+        let points = provider.fetch_latest_fees().await.unwrap();
+        assert_eq!(points.len(), 2);
+        // Not saved until the caller comes back for the next batch.
+        assert_eq!(store.saved.lock().unwrap().len(), 1);
+
+        assert!(provider.fetch_latest_fees().await.unwrap().is_empty());
+        let saved = store.saved.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(saved.paging_token, last_token);
+        assert_eq!(saved.ledger_sequence, 30);
+    }
+
+    #[tokio::test]
+    async fn polling_without_saved_cursor_starts_from_latest_page() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/transactions"))
+            .and(query_param("order", "desc"))
+            .respond_with(page(vec![record(41, 1, true), record(40, 1, true)]))
+            .mount(&server)
+            .await;
+
+        let provider = HorizonFeeDataProvider::new(HorizonClient::new(server.uri()))
+            .with_cursor_store(Arc::new(MemoryCursorStore::default()));
+        let points = provider.fetch_latest_fees().await.unwrap();
+
+        let ledgers: Vec<u64> = points.iter().map(|p| p.ledger_sequence).collect();
+        assert_eq!(ledgers, vec![40, 41]);
+        let state = provider.cursor.lock().await;
+        assert_eq!(state.position.as_ref().unwrap().ledger_sequence, 41);
     }
 }
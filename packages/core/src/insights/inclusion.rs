@@ -0,0 +1,169 @@
+//! Inclusion probability estimation
+//!
+//! A ledger's cheapest included fee is the bar every transaction in it
+//! cleared. Replaying a bid against each run of recent ledgers shows how
+//! often it would have cleared that bar within a given number of ledgers.
+
+use chrono::Utc;
+use std::collections::VecDeque;
+
+use crate::insights::{config::InclusionConfig, types::*};
+
+/// Cheapest classic fee charged in one ledger
+#[derive(Debug, Clone)]
+struct LedgerMinimum {
+    sequence: u64,
+    min_fee: u64,
+}
+
+/// Estimates how soon a fee bid would have been included
+pub struct InclusionEstimator {
+    config: InclusionConfig,
+    ledgers: VecDeque<LedgerMinimum>,
+}
+
+impl InclusionEstimator {
+    /// Create a new inclusion estimator
+    pub fn new(config: InclusionConfig) -> Self {
+        Self {
+            config,
+            ledgers: VecDeque::new(),
+        }
+    }
+
+    /// Fold fees into their ledgers' minimums. Soroban fees are skipped:
+    /// their resource fees say nothing about inclusion pricing.
+    pub fn add_data_points(&mut self, points: &[FeeDataPoint]) {
+        for point in points.iter().filter(|p| p.soroban.is_none()) {
+            self.add_fee(point.ledger_sequence, point.fee_amount);
+        }
+    }
+
+    fn add_fee(&mut self, sequence: u64, fee: u64) {
+        let position = self
+            .ledgers
+            .iter()
+            .rposition(|ledger| ledger.sequence <= sequence);
+
+        match position {
+            Some(i) if self.ledgers[i].sequence == sequence => {
+                let ledger = &mut self.ledgers[i];
+                ledger.min_fee = ledger.min_fee.min(fee);
+            }
+            _ => {
+                // Too old to matter once the buffer is full
+                if position.is_none() && self.ledgers.len() >= self.config.max_ledgers {
+                    return;
+                }
+                let insert_at = position.map_or(0, |i| i + 1);
+                self.ledgers.insert(
+                    insert_at,
+                    LedgerMinimum {
+                        sequence,
+                        min_fee: fee,
+                    },
+                );
+                while self.ledgers.len() > self.config.max_ledgers {
+                    self.ledgers.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Historical chance that `fee` would have been included within 1, 3
+    /// and 5 ledgers.
+    pub fn estimate(&self, fee: u64) -> InclusionEstimate {
+        InclusionEstimate {
+            fee,
+            within_1_ledger: self.probability_within(fee, 1),
+            within_3_ledgers: self.probability_within(fee, 3),
+            within_5_ledgers: self.probability_within(fee, 5),
+            sample_ledgers: self.ledgers.len(),
+            generated_at: Utc::now(),
+        }
+    }
+
+    /// Share of runs of `ledgers` consecutive observed ledgers in which at
+    /// least one ledger's cheapest fee was at or below `fee`.
+    fn probability_within(&self, fee: u64, ledgers: usize) -> Option<f64> {
+        if self.ledgers.len() < self.config.min_ledgers.max(ledgers) {
+            return None;
+        }
+
+        let minimums: Vec<u64> = self.ledgers.iter().map(|ledger| ledger.min_fee).collect();
+        let runs = minimums.windows(ledgers);
+        let total = runs.len();
+        let included = runs
+            .filter(|run| run.iter().any(|&min_fee| min_fee <= fee))
+            .count();
+        Some(included as f64 / total as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn estimator_with(minimums: &[u64]) -> InclusionEstimator {
+        let mut estimator = InclusionEstimator::new(InclusionConfig {
+            min_ledgers: 1,
+            ..InclusionConfig::default()
+        });
+        let points: Vec<FeeDataPoint> = minimums
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &min_fee)| {
+                [min_fee, min_fee * 3].map(|fee_amount| FeeDataPoint {
+                    fee_amount,
+                    timestamp: Utc::now(),
+                    transaction_hash: format!("hash_{}_{}", i, fee_amount),
+                    ledger_sequence: i as u64 + 1,
+                    envelope: None,
+                    soroban: None,
+                })
+            })
+            .collect();
+        estimator.add_data_points(&points);
+        estimator
+    }
+
+    #[test]
+    fn bids_clear_more_often_given_more_ledgers() {
+        let estimator = estimator_with(&[100, 500, 500, 100, 500, 500, 500, 500, 100, 500]);
+
+        let estimate = estimator.estimate(200);
+        assert_eq!(estimate.within_1_ledger, Some(0.3));
+        assert_eq!(estimate.within_3_ledgers, Some(6.0 / 8.0));
+        assert_eq!(estimate.within_5_ledgers, Some(1.0));
+        assert_eq!(estimate.sample_ledgers, 10);
+
+        assert_eq!(estimator.estimate(500).within_1_ledger, Some(1.0));
+        assert_eq!(estimator.estimate(99).within_5_ledgers, Some(0.0));
+    }
+
+    #[test]
+    fn too_few_ledgers_gives_no_estimate() {
+        let estimate = estimator_with(&[100, 100]).estimate(100);
+        assert_eq!(estimate.within_1_ledger, Some(1.0));
+        assert_eq!(estimate.within_3_ledgers, None);
+        assert_eq!(estimate.within_5_ledgers, None);
+
+        let empty = InclusionEstimator::new(InclusionConfig::default()).estimate(100);
+        assert_eq!(empty.within_1_ledger, None);
+        assert_eq!(empty.sample_ledgers, 0);
+    }
+
+    #[test]
+    fn oldest_ledgers_are_dropped_past_capacity() {
+        let mut estimator = InclusionEstimator::new(InclusionConfig {
+            max_ledgers: 2,
+            min_ledgers: 1,
+        });
+        for sequence in [3, 1, 2, 1] {
+            estimator.add_fee(sequence, 100);
+        }
+
+        let sequences: Vec<u64> = estimator.ledgers.iter().map(|l| l.sequence).collect();
+        assert_eq!(sequences, vec![2, 3]);
+    }
+}
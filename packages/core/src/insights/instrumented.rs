@@ -0,0 +1,241 @@
+//! Instrumented Fee Data Provider
+//!
+//! Decorator that records latency, success/error counts and payload size
+//! (fee data points returned) for every provider call, so slow upstreams can
+//! be told apart from slow parsing further down the pipeline.
+
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::insights::{
+    provider::{FeeDataProvider, ProviderMetadata, ProviderResult, ProviderStatus},
+    types::{FeeDataPoint, FeeStatsSnapshot, LedgerInfo},
+};
+use crate::metrics::AppMetrics;
+
+/// Per-operation call statistics
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct OperationStats {
+    pub calls: u64,
+    pub errors: u64,
+    pub total_latency_ms: f64,
+    pub average_latency_ms: f64,
+    pub max_latency_ms: f64,
+    pub last_latency_ms: f64,
+    /// Fee data points returned across all successful calls.
+    pub points_returned: u64,
+}
+
+/// Call statistics for one provider, keyed by operation name
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct ProviderStats {
+    pub provider: String,
+    pub operations: BTreeMap<String, OperationStats>,
+}
+
+/// Wraps a provider and records statistics for every call.
+pub struct InstrumentedProvider<P: FeeDataProvider> {
+    inner: P,
+    stats: Mutex<ProviderStats>,
+    metrics: Option<Arc<AppMetrics>>,
+}
+
+impl<P: FeeDataProvider> InstrumentedProvider<P> {
+    pub fn new(inner: P) -> Self {
+        let stats = ProviderStats {
+            provider: inner.provider_name().to_string(),
+            operations: BTreeMap::new(),
+        };
+        Self {
+            inner,
+            stats: Mutex::new(stats),
+            metrics: None,
+        }
+    }
+
+    /// Also export every call to the Prometheus registry.
+    pub fn with_metrics(mut self, metrics: Arc<AppMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Snapshot of the statistics recorded so far.
+    pub fn stats(&self) -> ProviderStats {
+        self.stats
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    async fn instrument<'a, F, Fut, T>(
+        &'a self,
+        operation: &str,
+        payload_size: impl Fn(&T) -> usize,
+        op: F,
+    ) -> ProviderResult<T>
+    where
+        F: FnOnce(&'a P) -> Fut,
+        Fut: Future<Output = ProviderResult<T>>,
+    {
+        let started = Instant::now();
+        let result = op(&self.inner).await;
+        let latency = started.elapsed();
+        let points = result.as_ref().map(&payload_size).unwrap_or(0);
+
+        {
+            let mut stats = self
+                .stats
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let entry = stats.operations.entry(operation.to_string()).or_default();
+            let latency_ms = latency.as_secs_f64() * 1000.0;
+            entry.calls += 1;
+            entry.total_latency_ms += latency_ms;
+            entry.average_latency_ms = entry.total_latency_ms / entry.calls as f64;
+            entry.max_latency_ms = entry.max_latency_ms.max(latency_ms);
+            entry.last_latency_ms = latency_ms;
+            match &result {
+                Ok(_) => entry.points_returned += points as u64,
+                Err(_) => entry.errors += 1,
+            }
+        }
+
+        if let Some(metrics) = &self.metrics {
+            metrics.record_provider_call(
+                self.inner.provider_name(),
+                operation,
+                result.is_ok(),
+                latency,
+                points,
+            );
+        }
+
+        result
+    }
+}
+
+#[async_trait]
+impl<P: FeeDataProvider + Send + Sync> FeeDataProvider for InstrumentedProvider<P> {
+    async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        self.instrument("fetch_latest_fees", Vec::len, |inner| {
+            inner.fetch_latest_fees()
+        })
+        .await
+    }
+
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        self.instrument("fetch_fees_range", Vec::len, |inner| {
+            inner.fetch_fees_range(start_ledger, end_ledger)
+        })
+        .await
+    }
+
+    async fn fetch_fee_stats(&self) -> ProviderResult<FeeStatsSnapshot> {
+        self.instrument("fetch_fee_stats", |_| 1, |inner| inner.fetch_fee_stats())
+            .await
+    }
+
+    async fn fetch_recent_ledgers(&self) -> ProviderResult<Vec<LedgerInfo>> {
+        self.instrument("fetch_recent_ledgers", Vec::len, |inner| {
+            inner.fetch_recent_ledgers()
+        })
+        .await
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    async fn health_check(&self) -> ProviderResult<()> {
+        self.instrument("health_check", |_| 0, |inner| inner.health_check())
+            .await
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        self.inner.get_metadata()
+    }
+
+    async fn refresh_metadata(&self) -> ProviderResult<ProviderMetadata> {
+        self.inner.refresh_metadata().await
+    }
+
+    fn provider_status(&self) -> ProviderStatus {
+        ProviderStatus {
+            stats: Some(self.stats()),
+            ..self.inner.provider_status()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::insights::error::ProviderError;
+    use crate::services::mock_horizon::MockHorizonClient;
+    use chrono::Utc;
+
+    fn make_fee_point(fee_amount: u64) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp: Utc::now(),
+            transaction_hash: format!("hash_{}", fee_amount),
+            ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn records_calls_and_points_returned() {
+        let mock =
+            MockHorizonClient::new().with_fees(vec![make_fee_point(100), make_fee_point(200)]);
+        let provider = InstrumentedProvider::new(mock);
+
+        provider.fetch_latest_fees().await.unwrap();
+        provider.fetch_latest_fees().await.unwrap();
+
+        let stats = provider.stats();
+        assert_eq!(stats.provider, "MockHorizon");
+        let fetch = &stats.operations["fetch_latest_fees"];
+        assert_eq!(fetch.calls, 2);
+        assert_eq!(fetch.errors, 0);
+        assert_eq!(fetch.points_returned, 4);
+    }
+
+    #[tokio::test]
+    async fn counts_errors_per_operation() {
+        let mock = MockHorizonClient::new().with_error(ProviderError::ServiceUnavailable);
+        let provider = InstrumentedProvider::new(mock);
+
+        assert!(provider.fetch_latest_fees().await.is_err());
+        assert!(provider.fetch_fees_range(1, 2).await.is_err());
+
+        let stats = provider.provider_status().stats.unwrap();
+        assert_eq!(stats.operations["fetch_latest_fees"].errors, 1);
+        assert_eq!(stats.operations["fetch_fees_range"].errors, 1);
+        assert_eq!(stats.operations["fetch_latest_fees"].points_returned, 0);
+    }
+
+    #[tokio::test]
+    async fn exports_to_prometheus_when_metrics_attached() {
+        let metrics = Arc::new(AppMetrics::new().unwrap());
+        let provider = InstrumentedProvider::new(
+            MockHorizonClient::new().with_fees(vec![make_fee_point(100)]),
+        )
+        .with_metrics(metrics.clone());
+
+        provider.fetch_latest_fees().await.unwrap();
+
+        let output = metrics.render().unwrap();
+        assert!(output.contains("stellar_fee_tracker_provider_requests_total"));
+        assert!(output.contains("outcome=\"success\""));
+        assert!(output.contains("stellar_fee_tracker_provider_request_duration_seconds"));
+    }
+}
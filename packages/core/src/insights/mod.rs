@@ -3,25 +3,75 @@
 //! This module provides analytical insights from raw blockchain fee data,
 //! including rolling averages, extremes tracking, and congestion detection.
 
+pub mod anomaly;
+pub mod budget;
+pub mod cached;
 pub mod calculator;
+pub mod circuit_breaker;
+pub mod composite;
 pub mod config;
+pub mod cross_network;
+pub mod cursor;
+pub mod dedup;
+pub mod depth;
 pub mod detector;
 pub mod engine;
+pub mod envelope;
 pub mod error;
+pub mod failover;
+pub mod forecaster;
 pub mod horizon_adapter;
+pub mod inclusion;
+pub mod instrumented;
 pub mod provider;
+pub mod providers;
+pub mod retry;
+pub mod seasonality;
+pub mod sketch;
+pub mod soroban_adapter;
+pub mod soroban_pricing;
+pub mod surge;
+pub mod surge_pricing;
 pub mod tracker;
+pub mod trend;
 pub mod types;
 
 #[cfg(test)]
 mod tests;
 
+#[allow(unused_imports)]
+pub use anomaly::AnomalyDetector;
+pub use cached::CachedProvider;
+pub use circuit_breaker::CircuitBreakerProvider;
+#[allow(unused_imports)]
+pub use composite::CompositeFeeDataProvider;
 pub use config::InsightsConfig;
+#[allow(unused_imports)]
+pub use cross_network::{compare_networks, CrossNetworkComparison};
+pub use dedup::DedupProvider;
+#[allow(unused_imports)]
+pub use depth::MarketDepthTracker;
 pub use engine::FeeInsightsEngine;
 #[allow(unused_imports)]
+pub use engine::InsightCalculator;
+#[allow(unused_imports)]
 pub use error::InsightsError;
+pub use failover::FailoverFeeDataProvider;
+#[allow(unused_imports)]
+pub use forecaster::FeeForecaster;
 pub use horizon_adapter::HorizonFeeDataProvider;
-pub use provider::FeeDataProvider;
+#[allow(unused_imports)]
+pub use inclusion::InclusionEstimator;
+pub use instrumented::InstrumentedProvider;
 #[allow(unused_imports)]
 pub use provider::ProviderMetadata;
+pub use provider::{FeeDataProvider, ProviderRegistry, StreamingFeeDataProvider};
+pub use retry::RetryingProvider;
+#[allow(unused_imports)]
+pub use seasonality::SeasonalityAnalyzer;
+pub use soroban_adapter::SorobanRpcFeeDataProvider;
+#[allow(unused_imports)]
+pub use surge::SurgeTracker;
+#[allow(unused_imports)]
+pub use surge_pricing::SurgePricingTracker;
 pub use types::*;
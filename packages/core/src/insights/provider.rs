@@ -4,8 +4,20 @@
 
 #![allow(dead_code)]
 
-use crate::insights::{error::ProviderError, types::FeeDataPoint};
+use crate::insights::instrumented::ProviderStats;
+use crate::insights::{
+    composite::CompositeFeeDataProvider,
+    config::{InsightsConfig, ProviderMode},
+    error::ProviderError,
+    failover::{AggregateHealth, BackendHealth, FailoverFeeDataProvider},
+    types::{FeeDataPoint, FeeStatsSnapshot, LedgerInfo},
+};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
 
 /// Trait for fee data providers to ensure data source independence
 #[async_trait]
@@ -13,6 +25,43 @@ pub trait FeeDataProvider {
     /// Fetch the latest fee data from the provider
     async fn fetch_latest_fees(&self) -> Result<Vec<FeeDataPoint>, ProviderError>;
 
+    /// Fetch fee data for every ledger in `start_ledger..=end_ledger`.
+    ///
+    /// Only providers whose metadata reports `supports_historical` implement
+    /// this; the default returns `ProviderError::Unsupported`.
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> Result<Vec<FeeDataPoint>, ProviderError> {
+        let _ = (start_ledger, end_ledger);
+        Err(ProviderError::Unsupported {
+            operation: format!(
+                "{} does not support historical fetches",
+                self.provider_name()
+            ),
+        })
+    }
+
+    /// Fetch network-wide fee statistics (percentiles, capacity usage).
+    ///
+    /// The default returns `ProviderError::Unsupported`.
+    async fn fetch_fee_stats(&self) -> Result<FeeStatsSnapshot, ProviderError> {
+        Err(ProviderError::Unsupported {
+            operation: format!("{} does not report fee stats", self.provider_name()),
+        })
+    }
+
+    /// Fetch the most recently closed ledgers (close time, operation count,
+    /// capacity), newest first.
+    ///
+    /// The default returns `ProviderError::Unsupported`.
+    async fn fetch_recent_ledgers(&self) -> Result<Vec<LedgerInfo>, ProviderError> {
+        Err(ProviderError::Unsupported {
+            operation: format!("{} does not report ledgers", self.provider_name()),
+        })
+    }
+
     /// Get the name of this provider for logging/debugging
     fn provider_name(&self) -> &str;
 
@@ -26,15 +75,100 @@ pub trait FeeDataProvider {
     fn get_metadata(&self) -> ProviderMetadata {
         ProviderMetadata::default()
     }
+
+    /// Re-query the data source for its network and ledger info and return
+    /// the updated metadata; later `get_metadata` calls reflect the result.
+    ///
+    /// The default returns `get_metadata()` unchanged.
+    async fn refresh_metadata(&self) -> Result<ProviderMetadata, ProviderError> {
+        Ok(self.get_metadata())
+    }
+
+    /// Live operational state, e.g. the upstream rate-limit window
+    fn provider_status(&self) -> ProviderStatus {
+        ProviderStatus::default()
+    }
+}
+
+/// Lets `Arc<dyn FeeDataProvider>` be used wherever a concrete provider is
+/// expected, e.g. as the inner provider of a decorator.
+#[async_trait]
+impl<P: FeeDataProvider + Send + Sync + ?Sized> FeeDataProvider for Arc<P> {
+    async fn fetch_latest_fees(&self) -> Result<Vec<FeeDataPoint>, ProviderError> {
+        (**self).fetch_latest_fees().await
+    }
+
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> Result<Vec<FeeDataPoint>, ProviderError> {
+        (**self).fetch_fees_range(start_ledger, end_ledger).await
+    }
+
+    async fn fetch_fee_stats(&self) -> Result<FeeStatsSnapshot, ProviderError> {
+        (**self).fetch_fee_stats().await
+    }
+
+    async fn fetch_recent_ledgers(&self) -> Result<Vec<LedgerInfo>, ProviderError> {
+        (**self).fetch_recent_ledgers().await
+    }
+
+    fn provider_name(&self) -> &str {
+        (**self).provider_name()
+    }
+
+    async fn health_check(&self) -> Result<(), ProviderError> {
+        (**self).health_check().await
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        (**self).get_metadata()
+    }
+
+    async fn refresh_metadata(&self) -> Result<ProviderMetadata, ProviderError> {
+        (**self).refresh_metadata().await
+    }
+
+    fn provider_status(&self) -> ProviderStatus {
+        (**self).provider_status()
+    }
+}
+
+/// Providers that push fee data as ledgers close instead of being polled
+#[async_trait]
+pub trait StreamingFeeDataProvider: FeeDataProvider {
+    /// Stream fee data points into `sender` until the receiver is dropped.
+    ///
+    /// Implementations reconnect on their own after transient failures and
+    /// resume from the last cursor they saw, so this only returns once the
+    /// receiving side has gone away.
+    async fn stream_fees(&self, sender: mpsc::Sender<FeeDataPoint>) -> ProviderResult<()>;
 }
 
 /// Metadata about a fee data provider
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ProviderMetadata {
     pub supports_historical: bool,
     pub max_batch_size: usize,
     pub rate_limit_per_minute: Option<u32>,
     pub data_freshness_seconds: u32,
+    /// Passphrase of the network the source serves, if it reports one.
+    pub network_passphrase: Option<String>,
+    /// Latest ledger the source had ingested at the last refresh.
+    pub latest_ledger: Option<u64>,
+    /// Horizon server version, for Horizon-backed sources.
+    pub horizon_version: Option<String>,
+    /// When the fields above were last fetched; `None` if never refreshed.
+    pub refreshed_at: Option<DateTime<Utc>>,
+}
+
+impl ProviderMetadata {
+    /// `true` if the metadata was never refreshed or is older than `max_age`.
+    pub fn is_stale(&self, max_age: chrono::Duration) -> bool {
+        self.refreshed_at
+            .is_none_or(|refreshed_at| Utc::now() - refreshed_at > max_age)
+    }
 }
 
 impl Default for ProviderMetadata {
@@ -44,9 +178,237 @@ impl Default for ProviderMetadata {
             max_batch_size: 100,
             rate_limit_per_minute: None,
             data_freshness_seconds: 60,
+            network_passphrase: None,
+            latest_ledger: None,
+            horizon_version: None,
+            refreshed_at: None,
         }
     }
 }
 
+/// Live operational state of a provider
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProviderStatus {
+    /// `None` when the provider does not report rate limits.
+    pub rate_limit: Option<RateLimitStatus>,
+    /// `None` when the provider is not behind a circuit breaker.
+    pub circuit_breaker: Option<CircuitBreakerStatus>,
+    /// `None` when the provider is not wrapped in an `InstrumentedProvider`.
+    pub stats: Option<ProviderStats>,
+    /// `None` when the provider is not a `FailoverFeeDataProvider`.
+    pub failover: Option<FailoverStatus>,
+}
+
+/// Upstream rate-limit window as last reported by the data source
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RateLimitStatus {
+    pub limit: Option<u32>,
+    pub remaining: Option<u32>,
+    pub reset_in_seconds: Option<u64>,
+    /// `true` while requests are being held back until the window resets.
+    pub throttled: bool,
+}
+
+/// State of a `CircuitBreakerProvider`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Calls pass through to the provider.
+    Closed,
+    /// Calls are short-circuited and served from the last good response.
+    Open,
+    /// One trial call is allowed through to probe for recovery.
+    HalfOpen,
+}
+
+impl CircuitState {
+    /// Numeric encoding used for the Prometheus gauge.
+    pub fn as_gauge(&self) -> f64 {
+        match self {
+            CircuitState::Closed => 0.0,
+            CircuitState::HalfOpen => 1.0,
+            CircuitState::Open => 2.0,
+        }
+    }
+}
+
+/// Circuit breaker snapshot as reported through `ProviderStatus`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CircuitBreakerStatus {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Seconds until the next half-open trial; set only while open.
+    pub retry_in_seconds: Option<u64>,
+    /// `true` if the last response was served from the stale cache.
+    pub serving_stale: bool,
+}
+
+/// Failover chain snapshot as reported through `ProviderStatus`
+#[derive(Debug, Clone, Serialize)]
+pub struct FailoverStatus {
+    /// Backend that served the last successful fetch; `None` before one.
+    pub serving: Option<String>,
+    /// Aggregate of `backends`; `None` before any backend has been seen.
+    pub health: Option<AggregateHealth>,
+    /// Each backend as last seen by a fetch or health check, in failover
+    /// order; backends never reached yet are left out.
+    pub backends: Vec<BackendHealth>,
+}
+
 /// Result type for provider operations
 pub type ProviderResult<T> = Result<T, ProviderError>;
+
+/// Shared, thread-safe handle to a provider
+pub type SharedFeeDataProvider = Arc<dyn FeeDataProvider + Send + Sync>;
+
+/// Constructor registered under a provider key
+pub type ProviderFactory = Box<dyn Fn() -> ProviderResult<SharedFeeDataProvider> + Send + Sync>;
+
+/// Named provider factories, selected at runtime by `InsightsConfig::providers`
+#[derive(Default)]
+pub struct ProviderRegistry {
+    factories: BTreeMap<String, ProviderFactory>,
+}
+
+impl ProviderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `factory` under `name`, replacing any previous registration.
+    pub fn register<F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        F: Fn() -> ProviderResult<SharedFeeDataProvider> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.into(), Box::new(factory));
+    }
+
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Registered keys in sorted order.
+    pub fn names(&self) -> Vec<&str> {
+        self.factories.keys().map(String::as_str).collect()
+    }
+
+    /// Build the provider registered under `name`.
+    pub fn build(&self, name: &str) -> ProviderResult<SharedFeeDataProvider> {
+        let factory = self
+            .factories
+            .get(name)
+            .ok_or_else(|| ProviderError::Unsupported {
+                operation: format!(
+                    "unknown provider '{}' (registered: {})",
+                    name,
+                    self.names().join(", ")
+                ),
+            })?;
+        factory()
+    }
+
+    /// Build the providers selected by `config`.
+    ///
+    /// A single key yields that provider directly; several keys are chained
+    /// into a `FailoverFeeDataProvider` or merged by a
+    /// `CompositeFeeDataProvider`, per `provider_mode`, in the order given.
+    pub fn select(&self, config: &InsightsConfig) -> ProviderResult<SharedFeeDataProvider> {
+        match (config.providers.as_slice(), config.provider_mode) {
+            ([], _) => Err(ProviderError::Unsupported {
+                operation: "no fee data provider selected".to_string(),
+            }),
+            ([name], _) => self.build(name),
+            (names, ProviderMode::Failover) => {
+                let mut failover = FailoverFeeDataProvider::new();
+                for name in names {
+                    failover = failover.with_backend(name.clone(), self.build(name)?);
+                }
+                Ok(Arc::new(failover))
+            }
+            (names, ProviderMode::Composite) => {
+                let mut composite = CompositeFeeDataProvider::new();
+                for name in names {
+                    composite = composite.with_source(name.clone(), self.build(name)?);
+                }
+                Ok(Arc::new(composite))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::mock_horizon::MockHorizonClient;
+
+    fn registry() -> ProviderRegistry {
+        let mut registry = ProviderRegistry::new();
+        registry.register("mock", || Ok(Arc::new(MockHorizonClient::new())));
+        registry.register("down", || {
+            Ok(Arc::new(
+                MockHorizonClient::new().with_error(ProviderError::ServiceUnavailable),
+            ))
+        });
+        registry
+    }
+
+    fn config_with(providers: &[&str]) -> InsightsConfig {
+        InsightsConfig {
+            providers: providers.iter().map(|p| p.to_string()).collect(),
+            ..InsightsConfig::default()
+        }
+    }
+
+    #[test]
+    fn names_are_sorted() {
+        assert_eq!(registry().names(), vec!["down", "mock"]);
+    }
+
+    #[test]
+    fn select_single_provider_by_key() {
+        let provider = registry().select(&config_with(&["mock"])).unwrap();
+        assert_eq!(provider.provider_name(), "MockHorizon");
+    }
+
+    #[tokio::test]
+    async fn select_multiple_providers_builds_failover_chain() {
+        let provider = registry().select(&config_with(&["down", "mock"])).unwrap();
+        assert_eq!(provider.provider_name(), "Failover");
+        assert!(provider.fetch_latest_fees().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn composite_mode_merges_every_provider() {
+        let config = InsightsConfig {
+            provider_mode: ProviderMode::Composite,
+            ..config_with(&["mock", "mock"])
+        };
+        let provider = registry().select(&config).unwrap();
+        assert_eq!(provider.provider_name(), "Composite");
+        assert!(provider.fetch_latest_fees().await.is_ok());
+    }
+
+    #[test]
+    fn unknown_provider_is_rejected() {
+        let result = registry().select(&config_with(&["bigquery"]));
+        assert!(matches!(result, Err(ProviderError::Unsupported { .. })));
+    }
+
+    #[test]
+    fn empty_selection_is_rejected() {
+        assert!(registry().select(&config_with(&[])).is_err());
+    }
+
+    #[test]
+    fn metadata_is_stale_until_refreshed_and_after_max_age() {
+        let max_age = chrono::Duration::seconds(60);
+        let mut metadata = ProviderMetadata::default();
+        assert!(metadata.is_stale(max_age));
+
+        metadata.refreshed_at = Some(Utc::now());
+        assert!(!metadata.is_stale(max_age));
+
+        metadata.refreshed_at = Some(Utc::now() - chrono::Duration::seconds(120));
+        assert!(metadata.is_stale(max_age));
+    }
+}
@@ -0,0 +1,390 @@
+//! Captive Core Fee Data Provider
+//!
+//! Reads `LedgerCloseMeta` records from a captive stellar-core's metadata
+//! output stream (`METADATA_OUTPUT_STREAM`, usually a named pipe) and
+//! extracts fee data directly, bypassing Horizon's rate limits.
+//!
+//! Records are XDR-encoded and framed with RFC 5531 record marking: a 4-byte
+//! big-endian header whose high bit flags the last fragment of a record and
+//! whose low 31 bits give the fragment length.
+//!
+//! Enabled with the `captive-core` cargo feature.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use stellar_xdr::curr::{
+    LedgerCloseMeta, Limits, ReadXdr, TransactionResultPair, TransactionResultResult,
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::insights::{
+    error::ProviderError,
+    provider::{FeeDataProvider, ProviderMetadata, ProviderResult},
+    types::FeeDataPoint,
+};
+
+/// Points retained between fetches before the oldest are dropped.
+pub const DEFAULT_BUFFER_CAPACITY: usize = 10_000;
+
+/// Health check fails when no ledger has closed for this long.
+const STALE_AFTER: Duration = Duration::from_secs(60);
+
+/// Delay before reopening the meta stream after EOF or a read error.
+const REOPEN_DELAY: Duration = Duration::from_secs(1);
+
+/// Largest frame accepted; ledger metas are a few MB at most.
+const MAX_FRAME_BYTES: usize = 256 * 1024 * 1024;
+
+#[derive(Default)]
+struct IngestState {
+    points: VecDeque<FeeDataPoint>,
+    last_ledger: Option<u64>,
+    last_ledger_at: Option<Instant>,
+}
+
+/// Provider fed by a background task that tails a ledger meta stream
+pub struct CaptiveCoreFeeDataProvider {
+    meta_path: PathBuf,
+    capacity: usize,
+    state: Arc<Mutex<IngestState>>,
+    reader: JoinHandle<()>,
+}
+
+impl CaptiveCoreFeeDataProvider {
+    /// Start tailing `meta_path` in the background.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn spawn(meta_path: impl Into<PathBuf>, capacity: usize) -> Self {
+        let meta_path = meta_path.into();
+        let capacity = capacity.max(1);
+        let state = Arc::new(Mutex::new(IngestState::default()));
+        let reader = tokio::spawn(tail_meta_stream(meta_path.clone(), capacity, state.clone()));
+
+        Self {
+            meta_path,
+            capacity,
+            state,
+            reader,
+        }
+    }
+
+    /// Highest ledger sequence ingested so far.
+    pub fn last_ledger(&self) -> Option<u64> {
+        self.lock_state().last_ledger
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, IngestState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for CaptiveCoreFeeDataProvider {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Reopen and read the meta stream forever, buffering extracted fee points.
+async fn tail_meta_stream(path: PathBuf, capacity: usize, state: Arc<Mutex<IngestState>>) {
+    loop {
+        match tokio::fs::File::open(&path).await {
+            Ok(mut file) => {
+                tracing::info!("Reading ledger close metas from {}", path.display());
+                if let Err(err) = ingest_stream(&mut file, capacity, &state).await {
+                    tracing::warn!("Ledger meta stream error: {}", err);
+                }
+            }
+            Err(err) => {
+                tracing::warn!("Cannot open ledger meta stream {}: {}", path.display(), err);
+            }
+        }
+        tokio::time::sleep(REOPEN_DELAY).await;
+    }
+}
+
+/// Read framed `LedgerCloseMeta` records until EOF.
+async fn ingest_stream<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    capacity: usize,
+    state: &Mutex<IngestState>,
+) -> io::Result<()> {
+    while let Some(frame) = read_frame(reader).await? {
+        let meta = LedgerCloseMeta::from_xdr(&frame, Limits::none())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let (ledger, points) = extract_fee_points(&meta);
+
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        // Reopening a stream (or a replayed file) can repeat ledgers already seen.
+        if state.last_ledger.is_some_and(|last| ledger <= last) {
+            continue;
+        }
+        state.last_ledger = Some(ledger);
+        state.last_ledger_at = Some(Instant::now());
+        state.points.extend(points);
+        let overflow = state.points.len().saturating_sub(capacity);
+        state.points.drain(..overflow);
+    }
+    Ok(())
+}
+
+/// Read one RFC 5531 record, joining fragments. `None` on clean EOF.
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut record = Vec::new();
+    loop {
+        let header = match reader.read_u32().await {
+            Ok(header) => header,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof && record.is_empty() => {
+                return Ok(None);
+            }
+            Err(err) => return Err(err),
+        };
+        let last_fragment = header & 0x8000_0000 != 0;
+        let len = (header & 0x7fff_ffff) as usize;
+        if record.len() + len > MAX_FRAME_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("ledger meta frame exceeds {} bytes", MAX_FRAME_BYTES),
+            ));
+        }
+
+        let start = record.len();
+        record.resize(start + len, 0);
+        reader.read_exact(&mut record[start..]).await?;
+
+        if last_fragment {
+            return Ok(Some(record));
+        }
+    }
+}
+
+/// Extract one fee point per successful transaction in a closed ledger.
+///
+/// Returns the ledger sequence alongside the points.
+pub fn extract_fee_points(meta: &LedgerCloseMeta) -> (u64, Vec<FeeDataPoint>) {
+    let (header, results): (_, Vec<&TransactionResultPair>) = match meta {
+        LedgerCloseMeta::V0(v0) => (
+            &v0.ledger_header.header,
+            v0.tx_processing.iter().map(|tx| &tx.result).collect(),
+        ),
+        LedgerCloseMeta::V1(v1) => (
+            &v1.ledger_header.header,
+            v1.tx_processing.iter().map(|tx| &tx.result).collect(),
+        ),
+        LedgerCloseMeta::V2(v2) => (
+            &v2.ledger_header.header,
+            v2.tx_processing.iter().map(|tx| &tx.result).collect(),
+        ),
+    };
+
+    let ledger = u64::from(header.ledger_seq);
+    let timestamp = DateTime::<Utc>::from_timestamp(header.scp_value.close_time.0 as i64, 0)
+        .unwrap_or_else(Utc::now);
+
+    let points = results
+        .into_iter()
+        .filter(|pair| {
+            matches!(
+                pair.result.result,
+                TransactionResultResult::TxSuccess(_)
+                    | TransactionResultResult::TxFeeBumpInnerSuccess(_)
+            )
+        })
+        .filter_map(|pair| {
+            let fee_amount = u64::try_from(pair.result.fee_charged).ok()?;
+            Some(FeeDataPoint {
+                fee_amount,
+                timestamp,
+                transaction_hash: to_hex(&pair.transaction_hash.0),
+                ledger_sequence: ledger,
+                envelope: None,
+                soroban: None,
+            })
+        })
+        .collect();
+
+    (ledger, points)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[async_trait]
+impl FeeDataProvider for CaptiveCoreFeeDataProvider {
+    /// Drain every point ingested since the previous fetch.
+    async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        Ok(self.lock_state().points.drain(..).collect())
+    }
+
+    fn provider_name(&self) -> &str {
+        "CaptiveCore"
+    }
+
+    async fn health_check(&self) -> ProviderResult<()> {
+        let last_ledger_at = self.lock_state().last_ledger_at;
+        match last_ledger_at {
+            Some(at) if at.elapsed() <= STALE_AFTER => Ok(()),
+            Some(_) => Err(ProviderError::ServiceUnavailable),
+            None => Err(ProviderError::NetworkError {
+                message: format!(
+                    "no ledger close meta read from {} yet",
+                    self.meta_path.display()
+                ),
+                timeout: false,
+            }),
+        }
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        ProviderMetadata {
+            supports_historical: false,
+            max_batch_size: self.capacity,
+            rate_limit_per_minute: None,
+            data_freshness_seconds: 5, // Stellar ledger close time
+            // Ingested in-process, so always current.
+            latest_ledger: self.last_ledger(),
+            refreshed_at: Some(Utc::now()),
+            ..ProviderMetadata::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stellar_xdr::curr::{
+        Hash, LedgerCloseMetaV0, TransactionResult, TransactionResultExt, TransactionResultMeta,
+        VecM, WriteXdr,
+    };
+
+    fn result_meta(hash_byte: u8, fee_charged: i64, success: bool) -> TransactionResultMeta {
+        TransactionResultMeta {
+            result: TransactionResultPair {
+                transaction_hash: Hash([hash_byte; 32]),
+                result: TransactionResult {
+                    fee_charged,
+                    result: if success {
+                        TransactionResultResult::TxSuccess(VecM::default())
+                    } else {
+                        TransactionResultResult::TxFailed(VecM::default())
+                    },
+                    ext: TransactionResultExt::V0,
+                },
+            },
+            ..TransactionResultMeta::default()
+        }
+    }
+
+    fn ledger_meta(ledger_seq: u32, txs: Vec<TransactionResultMeta>) -> LedgerCloseMeta {
+        let mut v0 = LedgerCloseMetaV0::default();
+        v0.ledger_header.header.ledger_seq = ledger_seq;
+        v0.ledger_header.header.scp_value.close_time.0 = 1_736_851_500;
+        v0.tx_processing = txs.try_into().unwrap();
+        LedgerCloseMeta::V0(v0)
+    }
+
+    fn framed(meta: &LedgerCloseMeta) -> Vec<u8> {
+        let body = meta.to_xdr(Limits::none()).unwrap();
+        let mut out = (0x8000_0000u32 | body.len() as u32).to_be_bytes().to_vec();
+        out.extend(body);
+        out
+    }
+
+    #[test]
+    fn extracts_successful_transactions_only() {
+        let meta = ledger_meta(
+            500,
+            vec![result_meta(0xab, 150, true), result_meta(0xcd, 100, false)],
+        );
+
+        let (ledger, points) = extract_fee_points(&meta);
+
+        assert_eq!(ledger, 500);
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].fee_amount, 150);
+        assert_eq!(points[0].ledger_sequence, 500);
+        assert_eq!(points[0].transaction_hash, "ab".repeat(32));
+        assert_eq!(points[0].timestamp.timestamp(), 1_736_851_500);
+    }
+
+    #[tokio::test]
+    async fn read_frame_joins_fragments() {
+        let mut bytes = 2u32.to_be_bytes().to_vec();
+        bytes.extend([1, 2]);
+        bytes.extend((0x8000_0000u32 | 1).to_be_bytes());
+        bytes.push(3);
+
+        let mut reader = bytes.as_slice();
+        assert_eq!(read_frame(&mut reader).await.unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(read_frame(&mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn ingest_stream_buffers_points_up_to_capacity() {
+        let mut bytes = framed(&ledger_meta(1, vec![result_meta(1, 100, true)]));
+        bytes.extend(framed(&ledger_meta(
+            2,
+            vec![result_meta(2, 200, true), result_meta(3, 300, true)],
+        )));
+        let state = Mutex::new(IngestState::default());
+
+        ingest_stream(&mut bytes.as_slice(), 2, &state)
+            .await
+            .unwrap();
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.last_ledger, Some(2));
+        let fees: Vec<u64> = state.points.iter().map(|p| p.fee_amount).collect();
+        assert_eq!(fees, vec![200, 300]);
+    }
+
+    #[tokio::test]
+    async fn ingest_stream_skips_ledgers_already_seen() {
+        let bytes = framed(&ledger_meta(5, vec![result_meta(1, 100, true)]));
+        let state = Mutex::new(IngestState::default());
+
+        ingest_stream(&mut bytes.as_slice(), 10, &state)
+            .await
+            .unwrap();
+        ingest_stream(&mut bytes.as_slice(), 10, &state)
+            .await
+            .unwrap();
+
+        assert_eq!(state.lock().unwrap().points.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn provider_drains_points_read_from_meta_file() {
+        let path =
+            std::env::temp_dir().join(format!("captive-core-meta-{}.xdr", std::process::id()));
+        std::fs::write(
+            &path,
+            framed(&ledger_meta(7, vec![result_meta(9, 120, true)])),
+        )
+        .unwrap();
+
+        let provider = CaptiveCoreFeeDataProvider::spawn(&path, DEFAULT_BUFFER_CAPACITY);
+        for _ in 0..50 {
+            if provider.last_ledger().is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let points = provider.fetch_latest_fees().await.unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].ledger_sequence, 7);
+        assert!(provider.health_check().await.is_ok());
+        assert!(provider.fetch_latest_fees().await.unwrap().is_empty());
+
+        drop(provider);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,384 @@
+//! File Replay Fee Data Provider
+//!
+//! Replays fee data points recorded in a CSV or JSONL file, paced by their
+//! recorded timestamps, so insights can be reproduced without network access.
+//!
+//! CSV files need a header row with `fee_amount`, `timestamp` (RFC 3339),
+//! `transaction_hash` and `ledger_sequence` columns; extra columns are
+//! ignored. JSONL files hold one serialised `FeeDataPoint` per line.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::insights::{
+    error::ProviderError,
+    provider::{FeeDataProvider, ProviderMetadata, ProviderResult},
+    types::FeeDataPoint,
+};
+
+/// On-disk layout of a replay file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayFormat {
+    Csv,
+    Jsonl,
+}
+
+impl ReplayFormat {
+    /// `.csv` files are read as CSV, everything else as JSONL.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => ReplayFormat::Csv,
+            _ => ReplayFormat::Jsonl,
+        }
+    }
+}
+
+/// One CSV row; envelope details are only available from JSONL.
+#[derive(Debug, Deserialize)]
+struct CsvRecord {
+    fee_amount: u64,
+    timestamp: DateTime<Utc>,
+    transaction_hash: String,
+    ledger_sequence: u64,
+}
+
+/// Where the replay is, anchored at the first `fetch_latest_fees` call.
+#[derive(Default)]
+struct ReplayState {
+    started: Option<(Instant, DateTime<Utc>)>,
+    next: usize,
+}
+
+/// Provider that replays a recorded fee history
+pub struct FileFeeDataProvider {
+    /// Sorted by timestamp.
+    points: Vec<FeeDataPoint>,
+    speed: f64,
+    rebase_timestamps: bool,
+    state: Mutex<ReplayState>,
+}
+
+impl FileFeeDataProvider {
+    /// Load every point from `path`, picking the format from its extension.
+    pub fn open(path: impl AsRef<Path>) -> ProviderResult<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|e| ProviderError::FormatError {
+            message: format!("Cannot read replay file {}: {}", path.display(), e),
+        })?;
+        Self::parse(&contents, ReplayFormat::from_path(path))
+    }
+
+    /// Build a provider from already-loaded file contents.
+    pub fn parse(contents: &str, format: ReplayFormat) -> ProviderResult<Self> {
+        let mut points = match format {
+            ReplayFormat::Csv => parse_csv(contents)?,
+            ReplayFormat::Jsonl => parse_jsonl(contents)?,
+        };
+        if points.is_empty() {
+            return Err(ProviderError::FormatError {
+                message: "Replay file contains no fee data points".to_string(),
+            });
+        }
+        points.sort_by_key(|point| point.timestamp);
+
+        Ok(Self {
+            points,
+            speed: 1.0,
+            rebase_timestamps: true,
+            state: Mutex::new(ReplayState::default()),
+        })
+    }
+
+    /// Replay `speed` times faster than recorded; `0` releases every point
+    /// on the first fetch.
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.speed = if speed.is_finite() {
+            speed.max(0.0)
+        } else {
+            1.0
+        };
+        self
+    }
+
+    /// Keep recorded timestamps instead of shifting the replay to the present.
+    ///
+    /// Time-windowed insights only see rebased points, so keep this for
+    /// offline analysis of the points themselves.
+    #[allow(dead_code)]
+    pub fn with_original_timestamps(mut self) -> Self {
+        self.rebase_timestamps = false;
+        self
+    }
+
+    /// Points not yet released by `fetch_latest_fees`.
+    #[allow(dead_code)]
+    pub fn remaining(&self) -> usize {
+        self.points.len() - self.lock_state().next
+    }
+
+    /// Where `point` lands on the wall clock when the replay began at `started`.
+    fn rebase(&self, point: &FeeDataPoint, started: DateTime<Utc>) -> DateTime<Utc> {
+        let first = self.points[0].timestamp;
+        if self.speed == 0.0 {
+            // Everything arrives at once: end the recording at the start time.
+            let last = self.points[self.points.len() - 1].timestamp;
+            return started - (last - point.timestamp);
+        }
+        let offset_ms = (point.timestamp - first).num_milliseconds() as f64 / self.speed;
+        started + chrono::Duration::milliseconds(offset_ms as i64)
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, ReplayState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn parse_csv(contents: &str) -> ProviderResult<Vec<FeeDataPoint>> {
+    csv::Reader::from_reader(contents.as_bytes())
+        .deserialize::<CsvRecord>()
+        .map(|record| {
+            let record = record.map_err(|e| ProviderError::FormatError {
+                message: format!("Invalid replay CSV: {}", e),
+            })?;
+            Ok(FeeDataPoint {
+                fee_amount: record.fee_amount,
+                timestamp: record.timestamp,
+                transaction_hash: record.transaction_hash,
+                ledger_sequence: record.ledger_sequence,
+                envelope: None,
+                soroban: None,
+            })
+        })
+        .collect()
+}
+
+fn parse_jsonl(contents: &str) -> ProviderResult<Vec<FeeDataPoint>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| ProviderError::FormatError {
+                message: format!("Invalid replay JSONL on line {}: {}", index + 1, e),
+            })
+        })
+        .collect()
+}
+
+#[async_trait]
+impl FeeDataProvider for FileFeeDataProvider {
+    /// Return the points whose recorded time has come since the last call.
+    async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        let mut state = self.lock_state();
+        let now = Instant::now();
+        let (started_at, started_utc) = *state.started.get_or_insert_with(|| (now, Utc::now()));
+
+        let first = self.points[0].timestamp;
+        let replayed_ms = (now - started_at).as_secs_f64() * self.speed * 1000.0;
+        let due = self.points[state.next..]
+            .iter()
+            .take_while(|point| {
+                self.speed == 0.0
+                    || (point.timestamp - first).num_milliseconds() as f64 <= replayed_ms
+            })
+            .count();
+
+        let released = &self.points[state.next..state.next + due];
+        state.next += due;
+
+        Ok(released
+            .iter()
+            .map(|point| {
+                let mut point = point.clone();
+                if self.rebase_timestamps {
+                    point.timestamp = self.rebase(&point, started_utc);
+                }
+                point
+            })
+            .collect())
+    }
+
+    /// Recorded points in the ledger range, with their original timestamps.
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        if start_ledger > end_ledger {
+            return Err(ProviderError::FormatError {
+                message: format!(
+                    "start_ledger {} is after end_ledger {}",
+                    start_ledger, end_ledger
+                ),
+            });
+        }
+        Ok(self
+            .points
+            .iter()
+            .filter(|point| (start_ledger..=end_ledger).contains(&point.ledger_sequence))
+            .cloned()
+            .collect())
+    }
+
+    fn provider_name(&self) -> &str {
+        "File"
+    }
+
+    async fn health_check(&self) -> ProviderResult<()> {
+        Ok(())
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        ProviderMetadata {
+            supports_historical: true,
+            max_batch_size: self.points.len(),
+            rate_limit_per_minute: None,
+            data_freshness_seconds: 0,
+            ..ProviderMetadata::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const CSV: &str = "\
+fee_amount,timestamp,transaction_hash,ledger_sequence,note
+200,2024-01-15T10:00:10Z,tx_b,101,late
+100,2024-01-15T10:00:00Z,tx_a,100,
+300,2024-01-15T10:01:00Z,tx_c,112,
+";
+
+    fn csv_provider() -> FileFeeDataProvider {
+        FileFeeDataProvider::parse(CSV, ReplayFormat::Csv).unwrap()
+    }
+
+    fn hashes(points: &[FeeDataPoint]) -> Vec<&str> {
+        points.iter().map(|p| p.transaction_hash.as_str()).collect()
+    }
+
+    #[test]
+    fn format_follows_extension() {
+        assert_eq!(
+            ReplayFormat::from_path(Path::new("fees.CSV")),
+            ReplayFormat::Csv
+        );
+        assert_eq!(
+            ReplayFormat::from_path(Path::new("fees.jsonl")),
+            ReplayFormat::Jsonl
+        );
+        assert_eq!(
+            ReplayFormat::from_path(Path::new("fees")),
+            ReplayFormat::Jsonl
+        );
+    }
+
+    #[test]
+    fn jsonl_keeps_envelope_details_and_skips_blank_lines() {
+        let jsonl = r#"{"fee_amount":100,"timestamp":"2024-01-15T10:00:00Z","transaction_hash":"tx_a","ledger_sequence":100,"envelope":{"operation_count":2,"fee_bump":true,"max_fee":500}}
+
+{"fee_amount":200,"timestamp":"2024-01-15T10:00:05Z","transaction_hash":"tx_b","ledger_sequence":101}
+"#;
+        let provider = FileFeeDataProvider::parse(jsonl, ReplayFormat::Jsonl).unwrap();
+
+        assert_eq!(provider.remaining(), 2);
+        assert_eq!(
+            provider.points[0]
+                .envelope
+                .as_ref()
+                .unwrap()
+                .operation_count,
+            2
+        );
+        assert!(provider.points[1].envelope.is_none());
+    }
+
+    #[test]
+    fn invalid_lines_are_reported_with_line_number() {
+        let jsonl = "{\"fee_amount\":1,\"timestamp\":\"2024-01-15T10:00:00Z\",\"transaction_hash\":\"a\",\"ledger_sequence\":1}\nnot json\n";
+        let result = FileFeeDataProvider::parse(jsonl, ReplayFormat::Jsonl);
+        assert!(
+            matches!(result, Err(ProviderError::FormatError { message }) if message.contains("line 2"))
+        );
+    }
+
+    #[test]
+    fn empty_file_is_rejected() {
+        let result = FileFeeDataProvider::parse(
+            "fee_amount,timestamp,transaction_hash,ledger_sequence\n",
+            ReplayFormat::Csv,
+        );
+        assert!(matches!(result, Err(ProviderError::FormatError { .. })));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn replays_points_at_recorded_pace() {
+        let provider = csv_provider().with_original_timestamps();
+
+        assert_eq!(
+            hashes(&provider.fetch_latest_fees().await.unwrap()),
+            vec!["tx_a"]
+        );
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(
+            hashes(&provider.fetch_latest_fees().await.unwrap()),
+            vec!["tx_b"]
+        );
+        assert!(provider.fetch_latest_fees().await.unwrap().is_empty());
+        tokio::time::advance(Duration::from_secs(50)).await;
+        assert_eq!(
+            hashes(&provider.fetch_latest_fees().await.unwrap()),
+            vec!["tx_c"]
+        );
+        assert_eq!(provider.remaining(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn accelerated_replay_compresses_gaps_and_rebases_timestamps() {
+        let provider = csv_provider().with_speed(10.0);
+
+        let first = provider.fetch_latest_fees().await.unwrap();
+        tokio::time::advance(Duration::from_secs(6)).await;
+        let rest = provider.fetch_latest_fees().await.unwrap();
+
+        assert_eq!(hashes(&rest), vec!["tx_b", "tx_c"]);
+        // 60s of recording replayed at 10x lands 6s after the first point.
+        assert_eq!((rest[1].timestamp - first[0].timestamp).num_seconds(), 6);
+        assert!(first[0].timestamp > Utc::now() - chrono::Duration::minutes(1));
+    }
+
+    #[tokio::test]
+    async fn unpaced_replay_releases_everything_ending_now() {
+        let provider = csv_provider().with_speed(0.0);
+        let before = Utc::now();
+
+        let points = provider.fetch_latest_fees().await.unwrap();
+
+        assert_eq!(hashes(&points), vec!["tx_a", "tx_b", "tx_c"]);
+        assert!(points[2].timestamp >= before);
+        assert_eq!(
+            (points[2].timestamp - points[0].timestamp).num_seconds(),
+            60
+        );
+    }
+
+    #[tokio::test]
+    async fn range_fetch_filters_by_ledger_with_original_timestamps() {
+        let provider = csv_provider();
+
+        let points = provider.fetch_fees_range(100, 110).await.unwrap();
+
+        assert_eq!(hashes(&points), vec!["tx_a", "tx_b"]);
+        assert_eq!(
+            points[0].timestamp.to_rfc3339(),
+            "2024-01-15T10:00:00+00:00"
+        );
+        assert!(provider.fetch_fees_range(110, 100).await.is_err());
+    }
+}
@@ -0,0 +1,344 @@
+//! gRPC Fee Data Provider
+//!
+//! Pulls fee data from a remote collector speaking the `FeeCollector`
+//! service in `proto/fee_collector.proto`. This lets lightweight edge
+//! collectors run next to Horizon while the insights engine runs elsewhere.
+//!
+//! Calls are unary gRPC over HTTP/2 without TLS (`http://` endpoints);
+//! terminate TLS at a proxy in front of the collector. Error statuses are
+//! read from the response headers (gRPC "trailers-only" responses); a
+//! response carrying no message is treated as a failed call.
+//!
+//! Enabled with the `grpc` cargo feature.
+
+pub mod proto;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, CONTENT_TYPE, TE};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::config::StellarNetwork;
+use crate::insights::{
+    error::ProviderError,
+    provider::{FeeDataProvider, ProviderMetadata, ProviderResult},
+    types::{EnvelopeDetails, FeeDataPoint},
+};
+use proto::Message;
+
+/// Fully qualified name of the collector service.
+const SERVICE: &str = "stellar.fees.v1.FeeCollector";
+
+/// Limit on a single call, including the response body.
+const CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Provider backed by a remote fee collector
+pub struct GrpcFeeDataProvider {
+    endpoint: String,
+    /// Sent with every request; `None` accepts whatever the collector tracks.
+    network: Option<StellarNetwork>,
+    http: reqwest::Client,
+    metadata: RwLock<ProviderMetadata>,
+}
+
+impl GrpcFeeDataProvider {
+    /// `endpoint` is the collector's base URL, e.g. `http://collector:50051`.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .no_proxy()
+            .http2_prior_knowledge()
+            .timeout(CALL_TIMEOUT)
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+        let metadata = ProviderMetadata {
+            supports_historical: true,
+            max_batch_size: 1000,
+            rate_limit_per_minute: None,
+            data_freshness_seconds: 5,
+            ..ProviderMetadata::default()
+        };
+
+        Self {
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+            network: None,
+            http,
+            metadata: RwLock::new(metadata),
+        }
+    }
+
+    /// Ask the collector for `network`'s fees only.
+    pub fn with_network(mut self, network: StellarNetwork) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    fn network_name(&self) -> String {
+        self.network
+            .map(|n| n.as_str().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Make one unary call to `method` on the collector service.
+    async fn call<Req: Message, Resp: Message>(
+        &self,
+        method: &str,
+        request: &Req,
+    ) -> ProviderResult<Resp> {
+        let body = request.encode().map_err(|e| ProviderError::FormatError {
+            message: format!("Failed to encode {} request: {}", method, e),
+        })?;
+
+        let response = self
+            .http
+            .post(format!("{}/{}/{}", self.endpoint, SERVICE, method))
+            .header(CONTENT_TYPE, "application/grpc")
+            .header(TE, "trailers")
+            .body(proto::frame(&body))
+            .send()
+            .await
+            .map_err(|e| ProviderError::NetworkError {
+                message: format!("gRPC {} failed: {}", method, e),
+                timeout: e.is_timeout(),
+            })?;
+
+        if !response.status().is_success() {
+            return Err(ProviderError::NetworkError {
+                message: format!("gRPC {} returned HTTP {}", method, response.status()),
+                timeout: false,
+            });
+        }
+        check_grpc_status(method, response.headers())?;
+
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| ProviderError::NetworkError {
+                message: format!("gRPC {} response interrupted: {}", method, e),
+                timeout: e.is_timeout(),
+            })?;
+        let frames = proto::unframe(&bytes).map_err(|message| ProviderError::FormatError {
+            message: format!("gRPC {}: {}", method, message),
+        })?;
+        let Some(message) = frames.first() else {
+            // Success statuses travel in trailers, which we cannot read; a call
+            // that produced no message failed.
+            return Err(ProviderError::NetworkError {
+                message: format!("gRPC {} returned no message", method),
+                timeout: false,
+            });
+        };
+
+        Resp::decode(message).map_err(|e| ProviderError::FormatError {
+            message: format!("Invalid {} response: {}", method, e),
+        })
+    }
+
+    /// Convert a batch and record the collector's latest ledger.
+    fn convert_batch(&self, batch: proto::FeeDataPointBatch) -> ProviderResult<Vec<FeeDataPoint>> {
+        if batch.latest_ledger > 0 {
+            let mut metadata = self.metadata.write().expect("metadata lock poisoned");
+            metadata.latest_ledger = Some(batch.latest_ledger);
+            metadata.refreshed_at = Some(Utc::now());
+        }
+        batch.points.into_iter().map(convert_point).collect()
+    }
+}
+
+fn convert_point(point: proto::FeeDataPoint) -> ProviderResult<FeeDataPoint> {
+    let timestamp =
+        DateTime::<Utc>::from_timestamp_millis(point.timestamp_ms).ok_or_else(|| {
+            ProviderError::FormatError {
+                message: format!("Invalid timestamp {}ms", point.timestamp_ms),
+            }
+        })?;
+
+    Ok(FeeDataPoint {
+        fee_amount: point.fee_amount,
+        timestamp,
+        transaction_hash: point.transaction_hash,
+        ledger_sequence: point.ledger_sequence,
+        envelope: point.envelope.map(|envelope| EnvelopeDetails {
+            operation_count: envelope.operation_count,
+            fee_bump: envelope.fee_bump,
+            max_fee: envelope.max_fee,
+            inner_fee: envelope.inner_fee,
+            category: None,
+            fee_account: None,
+        }),
+        soroban: None,
+    })
+}
+
+/// Map a non-OK `grpc-status` header to the matching provider error.
+fn check_grpc_status(method: &str, headers: &HeaderMap) -> ProviderResult<()> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let Some(code) = header("grpc-status").and_then(|v| v.parse::<u32>().ok()) else {
+        return Ok(());
+    };
+    let message = format!(
+        "gRPC {} failed with status {}: {}",
+        method,
+        code,
+        header("grpc-message").unwrap_or("")
+    );
+
+    // Codes from https://grpc.github.io/grpc/core/md_doc_statuscodes.html
+    match code {
+        0 => Ok(()),
+        4 => Err(ProviderError::NetworkError {
+            message,
+            timeout: true,
+        }),
+        7 | 16 => Err(ProviderError::AuthError { message }),
+        8 => Err(ProviderError::RateLimitExceeded),
+        12 => Err(ProviderError::Unsupported {
+            operation: format!("{}/{}", SERVICE, method),
+        }),
+        14 => Err(ProviderError::ServiceUnavailable),
+        3 | 11 => Err(ProviderError::FormatError { message }),
+        _ => Err(ProviderError::NetworkError {
+            message,
+            timeout: false,
+        }),
+    }
+}
+
+#[async_trait]
+impl FeeDataProvider for GrpcFeeDataProvider {
+    async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        let request = proto::FetchLatestFeesRequest {
+            network: self.network_name(),
+        };
+        let batch = self.call("FetchLatestFees", &request).await?;
+        self.convert_batch(batch)
+    }
+
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        let request = proto::FetchFeesRangeRequest {
+            network: self.network_name(),
+            start_ledger,
+            end_ledger,
+        };
+        let batch = self.call("FetchFeesRange", &request).await?;
+        self.convert_batch(batch)
+    }
+
+    fn provider_name(&self) -> &str {
+        "gRPC Collector"
+    }
+
+    async fn health_check(&self) -> ProviderResult<()> {
+        let health: proto::HealthResponse = self.call("Health", &proto::HealthRequest).await?;
+        if health.serving {
+            Ok(())
+        } else {
+            Err(ProviderError::ServiceUnavailable)
+        }
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        self.metadata
+            .read()
+            .expect("metadata lock poisoned")
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn grpc_response(message: &impl Message) -> ResponseTemplate {
+        ResponseTemplate::new(200)
+            .insert_header("content-type", "application/grpc")
+            .set_body_bytes(proto::frame(&message.encode().unwrap()))
+    }
+
+    #[tokio::test]
+    async fn fetch_latest_fees_decodes_batch() {
+        let server = MockServer::start().await;
+        let batch = proto::FeeDataPointBatch {
+            points: vec![proto::FeeDataPoint {
+                fee_amount: 250,
+                timestamp_ms: 1_736_851_500_000,
+                transaction_hash: "abc".to_string(),
+                ledger_sequence: 42,
+                envelope: Some(proto::EnvelopeDetails {
+                    operation_count: 1,
+                    fee_bump: false,
+                    max_fee: 500,
+                    inner_fee: None,
+                }),
+            }],
+            latest_ledger: 42,
+        };
+        Mock::given(method("POST"))
+            .and(path("/stellar.fees.v1.FeeCollector/FetchLatestFees"))
+            .and(header("content-type", "application/grpc"))
+            .respond_with(grpc_response(&batch))
+            .mount(&server)
+            .await;
+
+        let provider = GrpcFeeDataProvider::new(server.uri()).with_network(StellarNetwork::Testnet);
+        let points = provider.fetch_latest_fees().await.unwrap();
+
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].transaction_hash, "abc");
+        assert_eq!(points[0].fee_amount, 250);
+        assert_eq!(points[0].timestamp.timestamp(), 1_736_851_500);
+        assert_eq!(points[0].envelope.as_ref().unwrap().max_fee, 500);
+        assert_eq!(provider.get_metadata().latest_ledger, Some(42));
+    }
+
+    #[tokio::test]
+    async fn error_status_maps_to_provider_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "application/grpc")
+                    .insert_header("grpc-status", "14")
+                    .insert_header("grpc-message", "collector draining"),
+            )
+            .mount(&server)
+            .await;
+
+        let provider = GrpcFeeDataProvider::new(server.uri());
+        let result = provider.fetch_fees_range(1, 10).await;
+
+        assert!(matches!(result, Err(ProviderError::ServiceUnavailable)));
+    }
+
+    #[tokio::test]
+    async fn health_check_reports_not_serving() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/stellar.fees.v1.FeeCollector/Health"))
+            .respond_with(grpc_response(&proto::HealthResponse { serving: false }))
+            .mount(&server)
+            .await;
+
+        let provider = GrpcFeeDataProvider::new(server.uri());
+        assert!(matches!(
+            provider.health_check().await,
+            Err(ProviderError::ServiceUnavailable)
+        ));
+    }
+
+    #[test]
+    fn deadline_status_is_a_timeout() {
+        let mut headers = HeaderMap::new();
+        headers.insert("grpc-status", "4".parse().unwrap());
+        let err = check_grpc_status("FetchLatestFees", &headers).unwrap_err();
+        assert!(err.is_timeout());
+    }
+}
@@ -0,0 +1,335 @@
+//! Wire Format of `proto/fee_collector.proto`
+//!
+//! The few messages are encoded by hand on top of `protobuf`'s coded streams
+//! instead of being generated, which keeps `protoc` out of the build. Field
+//! numbers must match the schema; unknown fields are skipped so collectors
+//! can add fields without breaking older engines.
+
+use protobuf::{wire_format::WireType, CodedInputStream, CodedOutputStream, ProtobufResult};
+
+/// A protobuf message with hand-written field handling
+pub trait Message: Default {
+    /// Write every field of `self` to `os`.
+    fn write_fields(&self, os: &mut CodedOutputStream) -> ProtobufResult<()>;
+
+    /// Consume one field from `is`; unknown fields must be skipped.
+    fn merge_field(
+        &mut self,
+        field: u32,
+        wire_type: WireType,
+        is: &mut CodedInputStream,
+    ) -> ProtobufResult<()>;
+
+    fn encode(&self) -> ProtobufResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let mut os = CodedOutputStream::vec(&mut bytes);
+        self.write_fields(&mut os)?;
+        os.flush()?;
+        drop(os);
+        Ok(bytes)
+    }
+
+    fn decode(bytes: &[u8]) -> ProtobufResult<Self> {
+        let mut message = Self::default();
+        let mut is = CodedInputStream::from_bytes(bytes);
+        while !is.eof()? {
+            let (field, wire_type) = is.read_tag_unpack()?;
+            message.merge_field(field, wire_type, &mut is)?;
+        }
+        Ok(message)
+    }
+}
+
+use WireType::{WireTypeLengthDelimited as LEN, WireTypeVarint as VARINT};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FetchLatestFeesRequest {
+    pub network: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FetchFeesRangeRequest {
+    pub network: String,
+    pub start_ledger: u64,
+    pub end_ledger: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FeeDataPointBatch {
+    pub points: Vec<FeeDataPoint>,
+    pub latest_ledger: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FeeDataPoint {
+    pub fee_amount: u64,
+    pub timestamp_ms: i64,
+    pub transaction_hash: String,
+    pub ledger_sequence: u64,
+    pub envelope: Option<EnvelopeDetails>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EnvelopeDetails {
+    pub operation_count: u32,
+    pub fee_bump: bool,
+    pub max_fee: u64,
+    pub inner_fee: Option<u64>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HealthRequest;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HealthResponse {
+    pub serving: bool,
+}
+
+impl Message for FetchLatestFeesRequest {
+    fn write_fields(&self, os: &mut CodedOutputStream) -> ProtobufResult<()> {
+        os.write_string(1, &self.network)
+    }
+
+    fn merge_field(
+        &mut self,
+        field: u32,
+        wire_type: WireType,
+        is: &mut CodedInputStream,
+    ) -> ProtobufResult<()> {
+        match (field, wire_type) {
+            (1, LEN) => self.network = is.read_string()?,
+            _ => is.skip_field(wire_type)?,
+        }
+        Ok(())
+    }
+}
+
+impl Message for FetchFeesRangeRequest {
+    fn write_fields(&self, os: &mut CodedOutputStream) -> ProtobufResult<()> {
+        os.write_string(1, &self.network)?;
+        os.write_uint64(2, self.start_ledger)?;
+        os.write_uint64(3, self.end_ledger)
+    }
+
+    fn merge_field(
+        &mut self,
+        field: u32,
+        wire_type: WireType,
+        is: &mut CodedInputStream,
+    ) -> ProtobufResult<()> {
+        match (field, wire_type) {
+            (1, LEN) => self.network = is.read_string()?,
+            (2, VARINT) => self.start_ledger = is.read_uint64()?,
+            (3, VARINT) => self.end_ledger = is.read_uint64()?,
+            _ => is.skip_field(wire_type)?,
+        }
+        Ok(())
+    }
+}
+
+impl Message for FeeDataPointBatch {
+    fn write_fields(&self, os: &mut CodedOutputStream) -> ProtobufResult<()> {
+        for point in &self.points {
+            os.write_bytes(1, &point.encode()?)?;
+        }
+        os.write_uint64(2, self.latest_ledger)
+    }
+
+    fn merge_field(
+        &mut self,
+        field: u32,
+        wire_type: WireType,
+        is: &mut CodedInputStream,
+    ) -> ProtobufResult<()> {
+        match (field, wire_type) {
+            (1, LEN) => self.points.push(FeeDataPoint::decode(&is.read_bytes()?)?),
+            (2, VARINT) => self.latest_ledger = is.read_uint64()?,
+            _ => is.skip_field(wire_type)?,
+        }
+        Ok(())
+    }
+}
+
+impl Message for FeeDataPoint {
+    fn write_fields(&self, os: &mut CodedOutputStream) -> ProtobufResult<()> {
+        os.write_uint64(1, self.fee_amount)?;
+        os.write_int64(2, self.timestamp_ms)?;
+        os.write_string(3, &self.transaction_hash)?;
+        os.write_uint64(4, self.ledger_sequence)?;
+        if let Some(envelope) = &self.envelope {
+            os.write_bytes(5, &envelope.encode()?)?;
+        }
+        Ok(())
+    }
+
+    fn merge_field(
+        &mut self,
+        field: u32,
+        wire_type: WireType,
+        is: &mut CodedInputStream,
+    ) -> ProtobufResult<()> {
+        match (field, wire_type) {
+            (1, VARINT) => self.fee_amount = is.read_uint64()?,
+            (2, VARINT) => self.timestamp_ms = is.read_int64()?,
+            (3, LEN) => self.transaction_hash = is.read_string()?,
+            (4, VARINT) => self.ledger_sequence = is.read_uint64()?,
+            (5, LEN) => self.envelope = Some(EnvelopeDetails::decode(&is.read_bytes()?)?),
+            _ => is.skip_field(wire_type)?,
+        }
+        Ok(())
+    }
+}
+
+impl Message for EnvelopeDetails {
+    fn write_fields(&self, os: &mut CodedOutputStream) -> ProtobufResult<()> {
+        os.write_uint32(1, self.operation_count)?;
+        os.write_bool(2, self.fee_bump)?;
+        os.write_uint64(3, self.max_fee)?;
+        if let Some(inner_fee) = self.inner_fee {
+            os.write_uint64(4, inner_fee)?;
+        }
+        Ok(())
+    }
+
+    fn merge_field(
+        &mut self,
+        field: u32,
+        wire_type: WireType,
+        is: &mut CodedInputStream,
+    ) -> ProtobufResult<()> {
+        match (field, wire_type) {
+            (1, VARINT) => self.operation_count = is.read_uint32()?,
+            (2, VARINT) => self.fee_bump = is.read_bool()?,
+            (3, VARINT) => self.max_fee = is.read_uint64()?,
+            (4, VARINT) => self.inner_fee = Some(is.read_uint64()?),
+            _ => is.skip_field(wire_type)?,
+        }
+        Ok(())
+    }
+}
+
+impl Message for HealthRequest {
+    fn write_fields(&self, _os: &mut CodedOutputStream) -> ProtobufResult<()> {
+        Ok(())
+    }
+
+    fn merge_field(
+        &mut self,
+        _field: u32,
+        wire_type: WireType,
+        is: &mut CodedInputStream,
+    ) -> ProtobufResult<()> {
+        is.skip_field(wire_type)
+    }
+}
+
+impl Message for HealthResponse {
+    fn write_fields(&self, os: &mut CodedOutputStream) -> ProtobufResult<()> {
+        os.write_bool(1, self.serving)
+    }
+
+    fn merge_field(
+        &mut self,
+        field: u32,
+        wire_type: WireType,
+        is: &mut CodedInputStream,
+    ) -> ProtobufResult<()> {
+        match (field, wire_type) {
+            (1, VARINT) => self.serving = is.read_bool()?,
+            _ => is.skip_field(wire_type)?,
+        }
+        Ok(())
+    }
+}
+
+/// Wrap an encoded message in a gRPC length-prefixed frame (uncompressed).
+pub fn frame(message: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(message.len() + 5);
+    framed.push(0);
+    framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    framed.extend_from_slice(message);
+    framed
+}
+
+/// Split a gRPC response body into its message payloads.
+pub fn unframe(mut body: &[u8]) -> Result<Vec<&[u8]>, String> {
+    let mut messages = Vec::new();
+    while !body.is_empty() {
+        if body.len() < 5 {
+            return Err("truncated gRPC frame header".to_string());
+        }
+        if body[0] != 0 {
+            return Err("compressed gRPC frames are not supported".to_string());
+        }
+        let len = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+        let end = 5 + len;
+        if body.len() < end {
+            return Err(format!(
+                "gRPC frame declares {} bytes but only {} remain",
+                len,
+                body.len() - 5
+            ));
+        }
+        messages.push(&body[5..end]);
+        body = &body[end..];
+    }
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_round_trips() {
+        let batch = FeeDataPointBatch {
+            points: vec![
+                FeeDataPoint {
+                    fee_amount: 100,
+                    timestamp_ms: 1_736_851_500_000,
+                    transaction_hash: "a".to_string(),
+                    ledger_sequence: 7,
+                    envelope: None,
+                },
+                FeeDataPoint {
+                    fee_amount: 400,
+                    timestamp_ms: 1_736_851_505_000,
+                    transaction_hash: "b".to_string(),
+                    ledger_sequence: 8,
+                    envelope: Some(EnvelopeDetails {
+                        operation_count: 2,
+                        fee_bump: true,
+                        max_fee: 1_000,
+                        inner_fee: Some(300),
+                    }),
+                },
+            ],
+            latest_ledger: 8,
+        };
+
+        let decoded = FeeDataPointBatch::decode(&batch.encode().unwrap()).unwrap();
+        assert_eq!(decoded, batch);
+    }
+
+    #[test]
+    fn unknown_fields_are_skipped() {
+        let mut bytes = Vec::new();
+        let mut os = CodedOutputStream::vec(&mut bytes);
+        os.write_string(99, "from a newer collector").unwrap();
+        os.write_bool(1, true).unwrap();
+        os.flush().unwrap();
+        drop(os);
+
+        assert!(HealthResponse::decode(&bytes).unwrap().serving);
+    }
+
+    #[test]
+    fn frames_round_trip_and_reject_truncation() {
+        let mut body = frame(b"one");
+        body.extend(frame(b""));
+        assert_eq!(unframe(&body).unwrap(), vec![&b"one"[..], &b""[..]]);
+
+        assert!(unframe(&body[..6]).is_err());
+        assert!(unframe(&[1, 0, 0, 0, 0]).is_err());
+    }
+}
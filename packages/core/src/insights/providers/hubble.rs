@@ -0,0 +1,328 @@
+//! Hubble Fee Data Provider
+//!
+//! Reads historical transactions from Stellar's Hubble dataset on BigQuery,
+//! for backfills reaching further back than Horizon's retention window.
+//! Hubble is loaded in batches, so this provider only serves ledger ranges —
+//! live polling stays with Horizon or another real-time provider.
+//!
+//! Queries are billed to the configured GCP project. Hubble's tables are
+//! partitioned by close time, not ledger, so every range query scans the
+//! transaction table; keep backfill ranges coarse.
+//!
+//! Enabled with the `hubble` cargo feature.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use gcp_bigquery_client::{
+    error::BQError,
+    model::{
+        get_query_results_parameters::GetQueryResultsParameters,
+        query_request::QueryRequest,
+        query_response::{QueryResponse, ResultSet},
+    },
+    Client,
+};
+use std::path::PathBuf;
+use tokio::sync::OnceCell;
+
+use crate::insights::{
+    error::ProviderError,
+    provider::{FeeDataProvider, ProviderMetadata, ProviderResult},
+    types::FeeDataPoint,
+};
+
+/// Public Hubble transaction table.
+pub const DEFAULT_TABLE: &str = "crypto-stellar.crypto_stellar.history_transactions";
+
+/// Rows requested per result page.
+const PAGE_ROWS: i32 = 10_000;
+
+/// How long each BigQuery call waits for the query job to finish.
+const QUERY_TIMEOUT_MS: i32 = 30_000;
+
+/// Provider backed by the Hubble BigQuery dataset
+pub struct HubbleFeeDataProvider {
+    /// GCP project billed for the queries.
+    project_id: String,
+    table: String,
+    /// Service account key; workload identity is used when unset.
+    credentials_path: Option<PathBuf>,
+    client: OnceCell<Client>,
+}
+
+impl HubbleFeeDataProvider {
+    /// The BigQuery client is created on first use, so construction never
+    /// touches the network.
+    pub fn new(project_id: impl Into<String>) -> Self {
+        Self {
+            project_id: project_id.into(),
+            table: DEFAULT_TABLE.to_string(),
+            credentials_path: None,
+            client: OnceCell::new(),
+        }
+    }
+
+    /// Query `table` instead of the public Hubble transaction table.
+    pub fn with_table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Authenticate with a service account key file.
+    pub fn with_credentials_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.credentials_path = Some(path.into());
+        self
+    }
+
+    async fn client(&self) -> ProviderResult<&Client> {
+        self.client
+            .get_or_try_init(|| async {
+                let client = match &self.credentials_path {
+                    Some(path) => {
+                        let key =
+                            yup_oauth2::read_service_account_key(path)
+                                .await
+                                .map_err(|e| ProviderError::AuthError {
+                                    message: format!(
+                                        "Cannot read service account key {}: {}",
+                                        path.display(),
+                                        e
+                                    ),
+                                })?;
+                        Client::from_service_account_key(key, true).await
+                    }
+                    None => Client::with_workload_identity(true).await,
+                };
+                client.map_err(|e| ProviderError::AuthError {
+                    message: format!("BigQuery authentication failed: {}", e),
+                })
+            })
+            .await
+    }
+
+    /// SQL selecting successful transactions in `start_ledger..=end_ledger`.
+    fn range_query(&self, start_ledger: u64, end_ledger: u64) -> String {
+        format!(
+            "SELECT transaction_hash, ledger_sequence, fee_charged, \
+             UNIX_MILLIS(closed_at) AS closed_at_ms \
+             FROM `{}` \
+             WHERE ledger_sequence BETWEEN {} AND {} AND successful \
+             ORDER BY ledger_sequence",
+            self.table, start_ledger, end_ledger
+        )
+    }
+}
+
+fn query_error(err: BQError) -> ProviderError {
+    match err {
+        BQError::AuthError(_) | BQError::YupAuthError(_) => ProviderError::AuthError {
+            message: err.to_string(),
+        },
+        _ => ProviderError::NetworkError {
+            message: format!("BigQuery query failed: {}", err),
+            timeout: false,
+        },
+    }
+}
+
+/// Convert one completed result page into fee data points.
+fn rows_to_points(response: QueryResponse) -> ProviderResult<Vec<FeeDataPoint>> {
+    let mut rows = ResultSet::new(response);
+    let mut points = Vec::with_capacity(rows.row_count());
+
+    while rows.next_row() {
+        let int = |column: &str| -> ProviderResult<i64> {
+            rows.get_i64_by_name(column)
+                .ok()
+                .flatten()
+                .ok_or_else(|| ProviderError::FormatError {
+                    message: format!("Hubble row has no integer {}", column),
+                })
+        };
+        let closed_at_ms = int("closed_at_ms")?;
+        let timestamp = DateTime::<Utc>::from_timestamp_millis(closed_at_ms).ok_or_else(|| {
+            ProviderError::FormatError {
+                message: format!("Invalid Hubble closed_at: {}", closed_at_ms),
+            }
+        })?;
+        let transaction_hash = rows
+            .get_string_by_name("transaction_hash")
+            .ok()
+            .flatten()
+            .ok_or_else(|| ProviderError::FormatError {
+                message: "Hubble row has no transaction_hash".to_string(),
+            })?;
+
+        points.push(FeeDataPoint {
+            fee_amount: int("fee_charged")? as u64,
+            timestamp,
+            transaction_hash,
+            ledger_sequence: int("ledger_sequence")? as u64,
+            envelope: None,
+            soroban: None,
+        });
+    }
+    Ok(points)
+}
+
+#[async_trait]
+impl FeeDataProvider for HubbleFeeDataProvider {
+    async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        Err(ProviderError::Unsupported {
+            operation: "fetch_latest_fees (Hubble only serves historical ranges)".to_string(),
+        })
+    }
+
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        if start_ledger > end_ledger {
+            return Err(ProviderError::FormatError {
+                message: format!(
+                    "start_ledger {} is after end_ledger {}",
+                    start_ledger, end_ledger
+                ),
+            });
+        }
+
+        let client = self.client().await?;
+        let mut request = QueryRequest::new(self.range_query(start_ledger, end_ledger));
+        request.max_results = Some(PAGE_ROWS);
+        request.timeout_ms = Some(QUERY_TIMEOUT_MS);
+
+        let mut response = client
+            .job()
+            .query(&self.project_id, request)
+            .await
+            .map_err(query_error)?
+            .query_response()
+            .clone();
+        let job = response.job_reference.clone().unwrap_or_default();
+        let mut points = Vec::new();
+
+        // Poll until the job completes, then walk every result page.
+        loop {
+            let page_token = response.page_token.clone();
+            if response.job_complete.unwrap_or(false) {
+                points.extend(rows_to_points(response)?);
+                if page_token.is_none() {
+                    return Ok(points);
+                }
+            }
+
+            let job_id = job
+                .job_id
+                .as_deref()
+                .ok_or_else(|| ProviderError::FormatError {
+                    message: "BigQuery response has no job reference".to_string(),
+                })?;
+            response = client
+                .job()
+                .get_query_results(
+                    &self.project_id,
+                    job_id,
+                    GetQueryResultsParameters {
+                        location: job.location.clone(),
+                        max_results: Some(PAGE_ROWS),
+                        page_token,
+                        timeout_ms: Some(QUERY_TIMEOUT_MS),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(query_error)?
+                .into();
+        }
+    }
+
+    fn provider_name(&self) -> &str {
+        "Hubble"
+    }
+
+    async fn health_check(&self) -> ProviderResult<()> {
+        self.client().await.map(|_| ())
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        ProviderMetadata {
+            supports_historical: true,
+            max_batch_size: PAGE_ROWS as usize,
+            rate_limit_per_minute: None,
+            // Hubble is refreshed in batches, typically within a day.
+            data_freshness_seconds: 24 * 60 * 60,
+            ..ProviderMetadata::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response(rows: Vec<serde_json::Value>) -> QueryResponse {
+        serde_json::from_value(json!({
+            "jobComplete": true,
+            "schema": { "fields": [
+                { "name": "transaction_hash", "type": "STRING" },
+                { "name": "ledger_sequence", "type": "INTEGER" },
+                { "name": "fee_charged", "type": "INTEGER" },
+                { "name": "closed_at_ms", "type": "INTEGER" }
+            ]},
+            "rows": rows
+        }))
+        .unwrap()
+    }
+
+    fn row(hash: &str, ledger: &str, fee: &str, closed_at_ms: &str) -> serde_json::Value {
+        json!({ "f": [{ "v": hash }, { "v": ledger }, { "v": fee }, { "v": closed_at_ms }] })
+    }
+
+    #[test]
+    fn range_query_targets_configured_table_and_ledgers() {
+        let sql = HubbleFeeDataProvider::new("billing-project")
+            .with_table("my-project.stellar.transactions")
+            .range_query(100, 200);
+
+        assert!(sql.contains("FROM `my-project.stellar.transactions`"));
+        assert!(sql.contains("ledger_sequence BETWEEN 100 AND 200 AND successful"));
+    }
+
+    #[test]
+    fn rows_convert_to_fee_data_points() {
+        let points = rows_to_points(response(vec![
+            row("tx_a", "100", "150", "1705312800000"),
+            row("tx_b", "101", "300", "1705312805000"),
+        ]))
+        .unwrap();
+
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].transaction_hash, "tx_a");
+        assert_eq!(points[1].fee_amount, 300);
+        assert_eq!(points[1].ledger_sequence, 101);
+        assert_eq!(
+            points[0].timestamp.to_rfc3339(),
+            "2024-01-15T10:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn missing_values_are_format_errors() {
+        let result = rows_to_points(response(vec![json!({
+            "f": [{ "v": "tx_a" }, { "v": null }, { "v": "150" }, { "v": "1705312800000" }]
+        })]));
+        assert!(matches!(result, Err(ProviderError::FormatError { .. })));
+    }
+
+    #[tokio::test]
+    async fn latest_fees_are_unsupported() {
+        let provider = HubbleFeeDataProvider::new("billing-project");
+        assert!(matches!(
+            provider.fetch_latest_fees().await,
+            Err(ProviderError::Unsupported { .. })
+        ));
+        assert!(provider.get_metadata().supports_historical);
+    }
+}
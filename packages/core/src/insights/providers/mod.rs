@@ -0,0 +1,17 @@
+//! Additional fee data sources
+//!
+//! Providers that read from somewhere other than a Stellar API live here.
+//! Those needing optional dependencies or infrastructure are gated behind
+//! their own cargo feature.
+
+pub mod file;
+pub mod simulated;
+
+#[cfg(feature = "captive-core")]
+pub mod captive_core;
+
+#[cfg(feature = "hubble")]
+pub mod hubble;
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
@@ -0,0 +1,200 @@
+//! Retrying Fee Data Provider
+//!
+//! Decorator that retries transient provider failures with exponential
+//! backoff and jitter, following `InsightsConfig::retry`.
+
+use async_trait::async_trait;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::insights::{
+    config::RetryConfig,
+    error::ProviderError,
+    provider::{FeeDataProvider, ProviderMetadata, ProviderResult, ProviderStatus},
+    types::{FeeDataPoint, FeeStatsSnapshot, LedgerInfo},
+};
+
+/// Wraps a provider and retries `NetworkError`, `ServiceUnavailable` and
+/// `RateLimitExceeded`. Format and auth errors are returned immediately —
+/// repeating the same request will not fix them.
+pub struct RetryingProvider<P: FeeDataProvider> {
+    inner: P,
+    config: RetryConfig,
+}
+
+impl<P: FeeDataProvider> RetryingProvider<P> {
+    pub fn new(inner: P, config: RetryConfig) -> Self {
+        Self { inner, config }
+    }
+
+    /// Delay before retry number `attempt` (0-based), or `None` if `err`
+    /// should not be retried.
+    fn delay_for(&self, err: &ProviderError, attempt: u32) -> Option<Duration> {
+        let max_delay = to_std(self.config.max_delay);
+        match err {
+            ProviderError::RateLimitExceeded => Some(to_std(self.config.rate_limit_cooldown)),
+            ProviderError::NetworkError { .. } | ProviderError::ServiceUnavailable => {
+                let base_ms = to_std(self.config.base_delay).as_millis() as u64;
+                let exponential = base_ms.saturating_mul(1u64 << attempt.min(31));
+                let jitter = rand::random::<u64>() % base_ms.max(1);
+                Some(Duration::from_millis(exponential.saturating_add(jitter)).min(max_delay))
+            }
+            _ => None,
+        }
+    }
+
+    async fn with_retry<'a, F, Fut, T>(&'a self, op: F) -> ProviderResult<T>
+    where
+        F: Fn(&'a P) -> Fut,
+        Fut: Future<Output = ProviderResult<T>>,
+    {
+        let max_attempts = self.config.max_attempts.max(1);
+        let mut attempt = 0;
+
+        loop {
+            let err = match op(&self.inner).await {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+
+            attempt += 1;
+            let delay = match self.delay_for(&err, attempt - 1) {
+                Some(delay) if attempt < max_attempts => delay,
+                _ => return Err(err),
+            };
+
+            tracing::warn!(
+                "{} attempt {}/{} failed: {} — retrying in {}ms",
+                self.inner.provider_name(),
+                attempt,
+                max_attempts,
+                err,
+                delay.as_millis(),
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+fn to_std(duration: chrono::Duration) -> Duration {
+    duration.to_std().unwrap_or(Duration::ZERO)
+}
+
+#[async_trait]
+impl<P: FeeDataProvider + Send + Sync> FeeDataProvider for RetryingProvider<P> {
+    async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        self.with_retry(|inner| inner.fetch_latest_fees()).await
+    }
+
+    async fn fetch_fees_range(
+        &self,
+        start_ledger: u64,
+        end_ledger: u64,
+    ) -> ProviderResult<Vec<FeeDataPoint>> {
+        self.with_retry(|inner| inner.fetch_fees_range(start_ledger, end_ledger))
+            .await
+    }
+
+    async fn fetch_fee_stats(&self) -> ProviderResult<FeeStatsSnapshot> {
+        self.with_retry(|inner| inner.fetch_fee_stats()).await
+    }
+
+    async fn fetch_recent_ledgers(&self) -> ProviderResult<Vec<LedgerInfo>> {
+        self.with_retry(|inner| inner.fetch_recent_ledgers()).await
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    async fn health_check(&self) -> ProviderResult<()> {
+        self.inner.health_check().await
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        self.inner.get_metadata()
+    }
+
+    async fn refresh_metadata(&self) -> ProviderResult<ProviderMetadata> {
+        self.inner.refresh_metadata().await
+    }
+
+    fn provider_status(&self) -> ProviderStatus {
+        self.inner.provider_status()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::mock_horizon::MockHorizonClient;
+    use std::sync::atomic::Ordering;
+
+    fn fast_config(max_attempts: u32) -> RetryConfig {
+        RetryConfig {
+            max_attempts,
+            base_delay: chrono::Duration::milliseconds(1),
+            max_delay: chrono::Duration::milliseconds(5),
+            rate_limit_cooldown: chrono::Duration::seconds(60),
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_network_errors_up_to_max_attempts() {
+        let mock = MockHorizonClient::new().with_error(ProviderError::NetworkError {
+            message: "timeout".into(),
+            timeout: true,
+        });
+        let calls = mock.call_count.clone();
+        let provider = RetryingProvider::new(mock, fast_config(3));
+
+        let result = provider.fetch_latest_fees().await;
+
+        assert!(matches!(result, Err(ProviderError::NetworkError { .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_format_errors() {
+        let mock = MockHorizonClient::new().with_error(ProviderError::FormatError {
+            message: "bad json".into(),
+        });
+        let calls = mock.call_count.clone();
+        let provider = RetryingProvider::new(mock, fast_config(3));
+
+        assert!(provider.fetch_latest_fees().await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn success_is_returned_without_retry() {
+        let mock = MockHorizonClient::new();
+        let calls = mock.call_count.clone();
+        let provider = RetryingProvider::new(mock, fast_config(3));
+
+        assert!(provider.fetch_latest_fees().await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limit_waits_for_cooldown() {
+        let mock = MockHorizonClient::new().with_error(ProviderError::RateLimitExceeded);
+        let calls = mock.call_count.clone();
+        let provider = RetryingProvider::new(mock, fast_config(2));
+
+        let started = tokio::time::Instant::now();
+        assert!(provider.fetch_latest_fees().await.is_err());
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(started.elapsed() >= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn backoff_is_capped_at_max_delay() {
+        let provider = RetryingProvider::new(MockHorizonClient::new(), fast_config(10));
+        let delay = provider
+            .delay_for(&ProviderError::ServiceUnavailable, 8)
+            .unwrap();
+        assert!(delay <= Duration::from_millis(5));
+    }
+}
@@ -0,0 +1,177 @@
+//! Time-of-day and day-of-week fee seasonality
+//!
+//! Keeps the mean fee of every (weekday, hour) slot in UTC so current fees
+//! can be compared with what is typical at this time of the week.
+
+use chrono::{DateTime, Datelike, Timelike, Utc, Weekday};
+
+use crate::insights::{config::SeasonalityConfig, types::*};
+
+const DAYS: usize = 7;
+const HOURS: usize = 24;
+
+/// Fees seen in one slot
+#[derive(Debug, Clone, Copy, Default)]
+struct SlotStats {
+    count: u64,
+    total_fee: u64,
+}
+
+impl SlotStats {
+    fn add(&mut self, other: SlotStats) {
+        self.count += other.count;
+        self.total_fee = self.total_fee.saturating_add(other.total_fee);
+    }
+
+    fn mean_fee(&self) -> f64 {
+        self.total_fee as f64 / self.count as f64
+    }
+}
+
+/// Aggregates fees by hour of day and day of week
+pub struct SeasonalityAnalyzer {
+    config: SeasonalityConfig,
+    /// Indexed by days from Monday, then UTC hour.
+    slots: [[SlotStats; HOURS]; DAYS],
+}
+
+impl SeasonalityAnalyzer {
+    /// Create an empty analyzer
+    pub fn new(config: SeasonalityConfig) -> Self {
+        Self {
+            config,
+            slots: [[SlotStats::default(); HOURS]; DAYS],
+        }
+    }
+
+    /// Add fees to their slots.
+    pub fn add_data_points(&mut self, points: &[FeeDataPoint]) {
+        for point in points {
+            self.add_slot(
+                point.timestamp.weekday(),
+                point.timestamp.hour(),
+                1,
+                point.fee_amount,
+            );
+        }
+    }
+
+    /// Add pre-aggregated history, e.g. grouped from the database.
+    pub fn add_slot(&mut self, weekday: Weekday, hour: u32, count: u64, total_fee: u64) {
+        if let Some(slot) =
+            self.slots[weekday.num_days_from_monday() as usize].get_mut(hour as usize)
+        {
+            slot.add(SlotStats { count, total_fee });
+        }
+    }
+
+    /// Typical fee at `at`: the mean of its weekday and hour, falling back to
+    /// the hour across all weekdays while that slot has too few samples.
+    pub fn typical_fee(&self, at: DateTime<Utc>) -> Option<f64> {
+        let hour = at.hour() as usize;
+        let slot = self.slots[at.weekday().num_days_from_monday() as usize][hour];
+        if slot.count >= self.config.min_samples {
+            return Some(slot.mean_fee());
+        }
+        let across_days = self.hour_stats(hour);
+        (across_days.count >= self.config.min_samples).then(|| across_days.mean_fee())
+    }
+
+    /// Baselines of every slot with enough samples.
+    pub fn profile(&self) -> SeasonalityProfile {
+        let by_hour = (0..HOURS)
+            .filter_map(|hour| self.baseline(None, hour, self.hour_stats(hour)))
+            .collect();
+        let by_weekday_hour = (0..DAYS)
+            .flat_map(|day| (0..HOURS).map(move |hour| (day, hour)))
+            .filter_map(|(day, hour)| {
+                let weekday = Weekday::try_from(day as u8).ok()?;
+                self.baseline(Some(weekday), hour, self.slots[day][hour])
+            })
+            .collect();
+
+        SeasonalityProfile {
+            by_hour,
+            by_weekday_hour,
+        }
+    }
+
+    fn hour_stats(&self, hour: usize) -> SlotStats {
+        let mut stats = SlotStats::default();
+        for day in &self.slots {
+            stats.add(day[hour]);
+        }
+        stats
+    }
+
+    fn baseline(
+        &self,
+        weekday: Option<Weekday>,
+        hour: usize,
+        stats: SlotStats,
+    ) -> Option<SeasonalBaseline> {
+        (stats.count >= self.config.min_samples).then(|| SeasonalBaseline {
+            weekday,
+            hour: hour as u32,
+            mean_fee: stats.mean_fee(),
+            sample_count: stats.count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn point(at: DateTime<Utc>, fee_amount: u64) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp: at,
+            transaction_hash: format!("hash_{}_{}", at.timestamp(), fee_amount),
+            ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
+        }
+    }
+
+    fn config() -> SeasonalityConfig {
+        SeasonalityConfig { min_samples: 2 }
+    }
+
+    #[test]
+    fn typical_fee_uses_weekday_hour_then_hour_fallback() {
+        let mut analyzer = SeasonalityAnalyzer::new(config());
+        // Monday 14:xx UTC is busy; Tuesday 14:xx has a single sample.
+        let monday = Utc.with_ymd_and_hms(2024, 1, 1, 14, 10, 0).unwrap();
+        let tuesday = monday + chrono::Duration::days(1);
+        analyzer.add_data_points(&[
+            point(monday, 400),
+            point(monday + chrono::Duration::minutes(20), 600),
+            point(tuesday, 200),
+        ]);
+
+        assert_eq!(analyzer.typical_fee(monday), Some(500.0));
+        assert_eq!(analyzer.typical_fee(tuesday), Some(400.0));
+        assert_eq!(
+            analyzer.typical_fee(monday + chrono::Duration::hours(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn profile_lists_slots_with_enough_samples() {
+        let mut analyzer = SeasonalityAnalyzer::new(config());
+        analyzer.add_slot(Weekday::Sun, 23, 10, 1_500);
+        analyzer.add_slot(Weekday::Mon, 23, 1, 100);
+
+        let profile = analyzer.profile();
+
+        assert_eq!(profile.by_hour.len(), 1);
+        assert_eq!(profile.by_hour[0].hour, 23);
+        assert_eq!(profile.by_hour[0].sample_count, 11);
+        assert_eq!(profile.by_weekday_hour.len(), 1);
+        assert_eq!(profile.by_weekday_hour[0].weekday, Some(Weekday::Sun));
+        assert_eq!(profile.by_weekday_hour[0].mean_fee, 150.0);
+    }
+}
@@ -0,0 +1,185 @@
+//! Relative-error fee sketch
+//!
+//! A DDSketch: fees fall into logarithmic buckets whose width grows with
+//! the fee, so any quantile read back is within `relative_accuracy` of an
+//! actual fee. Memory depends on the spread of fees rather than on how
+//! many there are, and — unlike a t-digest — fees can be removed again,
+//! which a sliding window needs.
+
+use std::collections::BTreeMap;
+
+/// Bucketed counts of fees
+#[derive(Debug, Clone)]
+pub struct FeeSketch {
+    /// Ratio between the bounds of consecutive buckets.
+    gamma: f64,
+    ln_gamma: f64,
+    /// Fees of zero, which no logarithmic bucket covers.
+    zero_count: u64,
+    buckets: BTreeMap<i32, u64>,
+    count: u64,
+}
+
+impl FeeSketch {
+    /// Sketch whose quantiles are within `relative_accuracy` (0.0–1.0,
+    /// exclusive) of a recorded fee.
+    pub fn new(relative_accuracy: f64) -> Self {
+        let gamma = (1.0 + relative_accuracy) / (1.0 - relative_accuracy);
+        Self {
+            gamma,
+            ln_gamma: gamma.ln(),
+            zero_count: 0,
+            buckets: BTreeMap::new(),
+            count: 0,
+        }
+    }
+
+    fn bucket(&self, fee: u64) -> i32 {
+        ((fee as f64).ln() / self.ln_gamma).ceil() as i32
+    }
+
+    /// Fee every member of bucket `index` is read back as.
+    fn value(&self, index: i32) -> u64 {
+        (2.0 * self.gamma.powi(index) / (self.gamma + 1.0)).round() as u64
+    }
+
+    pub fn insert(&mut self, fee: u64) {
+        self.insert_many(fee, 1);
+    }
+
+    /// Record `fee` `times` times over.
+    pub fn insert_many(&mut self, fee: u64, times: u64) {
+        self.count += times;
+        if fee == 0 {
+            self.zero_count += times;
+        } else {
+            *self.buckets.entry(self.bucket(fee)).or_default() += times;
+        }
+    }
+
+    /// Forget one earlier `insert` of `fee`; unknown fees are ignored.
+    pub fn remove(&mut self, fee: u64) {
+        self.remove_many(fee, 1);
+    }
+
+    /// Forget `times` earlier inserts of `fee`, as far as there were any.
+    pub fn remove_many(&mut self, fee: u64, times: u64) {
+        let count = if fee == 0 {
+            &mut self.zero_count
+        } else {
+            let index = self.bucket(fee);
+            match self.buckets.get_mut(&index) {
+                Some(count) => count,
+                None => return,
+            }
+        };
+        let times = times.min(*count);
+        *count -= times;
+        self.count -= times;
+        if fee != 0 && *count == 0 {
+            let index = self.bucket(fee);
+            self.buckets.remove(&index);
+        }
+    }
+
+    /// Forget every fee recorded in `other`, a sketch of the same accuracy
+    /// whose fees were all recorded here too.
+    pub fn subtract(&mut self, other: &Self) {
+        let zeros = other.zero_count.min(self.zero_count);
+        self.zero_count -= zeros;
+        self.count -= zeros;
+        for (index, &times) in &other.buckets {
+            if let Some(count) = self.buckets.get_mut(index) {
+                let times = times.min(*count);
+                *count -= times;
+                self.count -= times;
+                if *count == 0 {
+                    self.buckets.remove(index);
+                }
+            }
+        }
+    }
+
+    /// Fees currently recorded.
+    pub fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    /// Buckets in use; memory grows with this, not with `count`.
+    #[allow(dead_code)]
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len() + usize::from(self.zero_count > 0)
+    }
+
+    /// Approximate fee at 0-based `rank` in ascending order, or `None` past
+    /// the end.
+    pub fn nth(&self, rank: usize) -> Option<u64> {
+        self.counts()
+            .scan(0u64, |seen, (fee, count)| {
+                *seen += count;
+                Some((fee, *seen))
+            })
+            .find(|&(_, seen)| seen > rank as u64)
+            .map(|(fee, _)| fee)
+    }
+
+    /// `(fee, count)` per bucket, ascending.
+    pub fn counts(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        let zeros = (self.zero_count > 0).then_some((0, self.zero_count));
+        zeros.into_iter().chain(
+            self.buckets
+                .iter()
+                .map(|(&index, &count)| (self.value(index), count)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantiles_stay_within_the_relative_accuracy() {
+        let mut sketch = FeeSketch::new(0.01);
+        for fee in 1..=10_000u64 {
+            sketch.insert(fee * 100);
+        }
+
+        for (rank, exact) in [(0, 100u64), (4_999, 500_000), (9_899, 990_000)] {
+            let fee = sketch.nth(rank).unwrap() as f64;
+            assert!((fee - exact as f64).abs() <= exact as f64 * 0.01 + 1.0);
+        }
+        assert_eq!(sketch.nth(10_000), None);
+        assert!(sketch.bucket_count() < 500);
+    }
+
+    #[test]
+    fn subtracting_a_sketch_forgets_its_fees() {
+        let (mut all, mut part) = (FeeSketch::new(0.01), FeeSketch::new(0.01));
+        for fee in [0, 100, 100, 5_000, 70_000] {
+            all.insert(fee);
+        }
+        for fee in [0, 100, 5_000] {
+            part.insert(fee);
+        }
+
+        all.subtract(&part);
+        assert_eq!(all.count(), 2);
+        assert_eq!(all.bucket_count(), 2);
+        assert!(all.nth(0).unwrap().abs_diff(100) <= 1);
+    }
+
+    #[test]
+    fn removed_fees_no_longer_count() {
+        let mut sketch = FeeSketch::new(0.01);
+        for fee in [0, 100, 100, 5_000] {
+            sketch.insert(fee);
+        }
+        sketch.remove(5_000);
+        sketch.remove(0);
+        sketch.remove(7);
+
+        assert_eq!(sketch.count(), 2);
+        assert_eq!(sketch.counts().collect::<Vec<_>>(), vec![(100, 2)]);
+    }
+}
@@ -0,0 +1,532 @@
+//! Soroban RPC Fee Data Provider Adapter
+//!
+//! Adapts the SorobanRpcClient to implement the FeeDataProvider trait.
+//! Only Soroban (contract) transactions are reported; classic transactions
+//! returned by `getTransactions` are skipped.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::RwLock;
+use stellar_xdr::curr::{Limits, ReadXdr, TransactionResult};
+
+use crate::error::AppError;
+use crate::insights::{
+    envelope::{
+        apply_soroban_meta, decode_envelope, envelope_details, is_soroban_envelope,
+        soroban_fee_detail,
+    },
+    error::ProviderError,
+    provider::{FeeDataProvider, ProviderMetadata, ProviderResult},
+    types::FeeDataPoint,
+};
+use crate::services::soroban_rpc::{SorobanRpcClient, SorobanTransactionInfo};
+
+/// Number of most recent ledgers scanned on each fetch (~1 minute of ledgers).
+const DEFAULT_LEDGER_LOOKBACK: u64 = 12;
+
+/// Page size requested from `getTransactions`.
+const FETCH_LIMIT: u32 = 200;
+
+/// Pages followed per fetch before giving up on reaching `latestLedger`.
+const MAX_PAGES: usize = 50;
+
+/// Adapter that implements FeeDataProvider for a soroban-rpc endpoint
+pub struct SorobanRpcFeeDataProvider {
+    client: SorobanRpcClient,
+    metadata: RwLock<ProviderMetadata>,
+    ledger_lookback: u64,
+}
+
+impl SorobanRpcFeeDataProvider {
+    /// Create a new Soroban RPC fee data provider
+    pub fn new(client: SorobanRpcClient) -> Self {
+        let metadata = ProviderMetadata {
+            supports_historical: false,
+            max_batch_size: FETCH_LIMIT as usize,
+            rate_limit_per_minute: None,
+            data_freshness_seconds: 6, // Stellar ledger close time
+            ..ProviderMetadata::default()
+        };
+
+        Self {
+            client,
+            metadata: RwLock::new(metadata),
+            ledger_lookback: DEFAULT_LEDGER_LOOKBACK,
+        }
+    }
+
+    /// Convert a soroban-rpc transaction into a FeeDataPoint.
+    ///
+    /// Returns `Ok(None)` for classic (non-Soroban) transactions.
+    fn convert_to_fee_data_point(
+        &self,
+        tx: SorobanTransactionInfo,
+    ) -> ProviderResult<Option<FeeDataPoint>> {
+        // Only include successful transactions
+        if tx.status != "SUCCESS" {
+            return Err(ProviderError::FormatError {
+                message: format!("Transaction status was {}", tx.status),
+            });
+        }
+
+        let envelope =
+            decode_envelope(&tx.envelope_xdr).map_err(|e| ProviderError::FormatError {
+                message: format!("{} for '{}'", e, tx.tx_hash),
+            })?;
+
+        if !is_soroban_envelope(&envelope) {
+            return Ok(None);
+        }
+
+        // fee_charged covers both the inclusion fee and the (refunded) resource fee.
+        let result =
+            TransactionResult::from_xdr_base64(&tx.result_xdr, Limits::none()).map_err(|e| {
+                ProviderError::FormatError {
+                    message: format!("Invalid result XDR for '{}': {}", tx.tx_hash, e),
+                }
+            })?;
+
+        let fee_amount =
+            u64::try_from(result.fee_charged).map_err(|_| ProviderError::FormatError {
+                message: format!("Negative fee charged: {}", result.fee_charged),
+            })?;
+
+        let timestamp = DateTime::<Utc>::from_timestamp(tx.created_at, 0).ok_or_else(|| {
+            ProviderError::FormatError {
+                message: format!("Invalid timestamp '{}'", tx.created_at),
+            }
+        })?;
+
+        let mut soroban = soroban_fee_detail(&envelope);
+        if let (Some(detail), Some(meta)) = (soroban.as_mut(), tx.result_meta_xdr.as_deref()) {
+            apply_soroban_meta(detail, meta);
+        }
+
+        Ok(Some(FeeDataPoint {
+            fee_amount,
+            timestamp,
+            transaction_hash: tx.tx_hash,
+            ledger_sequence: tx.ledger,
+            envelope: Some(envelope_details(&envelope)),
+            soroban,
+        }))
+    }
+}
+
+fn map_app_error(err: AppError) -> ProviderError {
+    match err {
+        AppError::Parse(message) => ProviderError::FormatError { message },
+        other => ProviderError::NetworkError {
+            message: other.to_string(),
+            timeout: false,
+        },
+    }
+}
+
+#[async_trait]
+impl FeeDataProvider for SorobanRpcFeeDataProvider {
+    async fn fetch_latest_fees(&self) -> ProviderResult<Vec<FeeDataPoint>> {
+        let latest = self
+            .client
+            .get_latest_ledger()
+            .await
+            .map_err(map_app_error)?;
+        let start_ledger = latest.sequence.saturating_sub(self.ledger_lookback).max(1);
+
+        // Pages come oldest first, so follow the cursor up to the tip
+        let mut page = self
+            .client
+            .get_transactions(start_ledger, FETCH_LIMIT)
+            .await
+            .map_err(map_app_error)?;
+        let mut fee_data_points = Vec::new();
+        for pages in 1.. {
+            let full = page.transactions.len() == FETCH_LIMIT as usize;
+            let reached_tip = page
+                .transactions
+                .last()
+                .is_none_or(|tx| tx.ledger >= page.latest_ledger);
+            let cursor = page.cursor.take();
+
+            for transaction in page.transactions {
+                match self.convert_to_fee_data_point(transaction) {
+                    Ok(Some(fee_point)) => fee_data_points.push(fee_point),
+                    Ok(None) => {}
+                    Err(e) => {
+                        tracing::warn!("Failed to convert Soroban transaction: {}", e);
+                    }
+                }
+            }
+
+            let Some(cursor) = cursor.filter(|_| full && !reached_tip) else {
+                break;
+            };
+            if pages == MAX_PAGES {
+                tracing::warn!(
+                    "Stopped following getTransactions after {} pages short of ledger {}",
+                    MAX_PAGES,
+                    page.latest_ledger
+                );
+                break;
+            }
+            page = self
+                .client
+                .get_transactions_after(&cursor, FETCH_LIMIT)
+                .await
+                .map_err(map_app_error)?;
+        }
+
+        // Ledgers without contract calls are normal, not a provider fault
+        Ok(fee_data_points)
+    }
+
+    fn provider_name(&self) -> &str {
+        "SorobanRpc"
+    }
+
+    async fn health_check(&self) -> ProviderResult<()> {
+        let health = self
+            .client
+            .get_health()
+            .await
+            .map_err(|e| ProviderError::NetworkError {
+                message: format!("soroban-rpc health check failed: {}", e),
+                timeout: false,
+            })?;
+
+        if health.status != "healthy" {
+            return Err(ProviderError::ServiceUnavailable);
+        }
+
+        Ok(())
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        self.metadata
+            .read()
+            .expect("metadata lock poisoned")
+            .clone()
+    }
+
+    async fn refresh_metadata(&self) -> ProviderResult<ProviderMetadata> {
+        let rpc_error = |e: AppError| ProviderError::NetworkError {
+            message: format!("soroban-rpc metadata refresh failed: {}", e),
+            timeout: false,
+        };
+        let network = self.client.get_network().await.map_err(rpc_error)?;
+        let latest = self.client.get_latest_ledger().await.map_err(rpc_error)?;
+
+        let mut metadata = self.metadata.write().expect("metadata lock poisoned");
+        metadata.network_passphrase = Some(network.passphrase);
+        metadata.latest_ledger = Some(latest.sequence);
+        metadata.refreshed_at = Some(Utc::now());
+        Ok(metadata.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::insights::types::{EnvelopeDetails, SorobanFeeDetail};
+    use serde_json::json;
+    use stellar_xdr::curr::{
+        ExtensionPoint, LedgerEntryChanges, LedgerFootprint, Limits, Memo, MuxedAccount,
+        Preconditions, ScVal, SequenceNumber, SorobanResources, SorobanTransactionData,
+        SorobanTransactionDataExt, SorobanTransactionMeta, SorobanTransactionMetaExt,
+        SorobanTransactionMetaExtV1, Transaction, TransactionEnvelope, TransactionExt,
+        TransactionMeta, TransactionMetaV3, TransactionResultExt, TransactionResultResult,
+        TransactionV1Envelope, Uint256, VecM, WriteXdr,
+    };
+    use wiremock::{
+        matchers::{body_partial_json, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn envelope_xdr(soroban: bool) -> String {
+        let ext = if soroban {
+            TransactionExt::V1(SorobanTransactionData {
+                ext: SorobanTransactionDataExt::V0,
+                resources: SorobanResources {
+                    footprint: LedgerFootprint {
+                        read_only: VecM::default(),
+                        read_write: VecM::default(),
+                    },
+                    instructions: 1_000_000,
+                    disk_read_bytes: 2_000,
+                    write_bytes: 500,
+                },
+                resource_fee: 80_000,
+            })
+        } else {
+            TransactionExt::V0
+        };
+        TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx: Transaction {
+                source_account: MuxedAccount::Ed25519(Uint256([0; 32])),
+                fee: 100_100,
+                seq_num: SequenceNumber(1),
+                cond: Preconditions::None,
+                memo: Memo::None,
+                operations: VecM::default(),
+                ext,
+            },
+            signatures: VecM::default(),
+        })
+        .to_xdr_base64(Limits::none())
+        .unwrap()
+    }
+
+    fn result_xdr(fee_charged: i64) -> String {
+        TransactionResult {
+            fee_charged,
+            result: TransactionResultResult::TxSuccess(VecM::default()),
+            ext: TransactionResultExt::V0,
+        }
+        .to_xdr_base64(Limits::none())
+        .unwrap()
+    }
+
+    fn result_meta_xdr(non_refundable: i64, refundable: i64, rent: i64) -> String {
+        TransactionMeta::V3(TransactionMetaV3 {
+            ext: ExtensionPoint::V0,
+            tx_changes_before: LedgerEntryChanges(VecM::default()),
+            operations: VecM::default(),
+            tx_changes_after: LedgerEntryChanges(VecM::default()),
+            soroban_meta: Some(SorobanTransactionMeta {
+                ext: SorobanTransactionMetaExt::V1(SorobanTransactionMetaExtV1 {
+                    ext: ExtensionPoint::V0,
+                    total_non_refundable_resource_fee_charged: non_refundable,
+                    total_refundable_resource_fee_charged: refundable,
+                    rent_fee_charged: rent,
+                }),
+                events: VecM::default(),
+                return_value: ScVal::Void,
+                diagnostic_events: VecM::default(),
+            }),
+        })
+        .to_xdr_base64(Limits::none())
+        .unwrap()
+    }
+
+    fn rpc_tx(hash: &str, soroban: bool, fee_charged: i64) -> serde_json::Value {
+        json!({
+            "status": "SUCCESS",
+            "txHash": hash,
+            "feeBump": false,
+            "envelopeXdr": envelope_xdr(soroban),
+            "resultXdr": result_xdr(fee_charged),
+            "ledger": 995,
+            "createdAt": 1736851500
+        })
+    }
+
+    async fn mount_rpc(server: &MockServer, transactions: Vec<serde_json::Value>) {
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "method": "getLatestLedger" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": { "id": "abc", "protocolVersion": 23, "sequence": 1000 }
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "method": "getTransactions",
+                "params": { "startLedger": 988 }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": { "transactions": transactions, "latestLedger": 1000 }
+            })))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn fetch_latest_fees_returns_only_soroban_transactions() {
+        let server = MockServer::start().await;
+        mount_rpc(
+            &server,
+            vec![
+                rpc_tx("soroban1", true, 65_000),
+                rpc_tx("classic1", false, 100),
+            ],
+        )
+        .await;
+
+        let provider = SorobanRpcFeeDataProvider::new(SorobanRpcClient::new(server.uri()));
+        let points = provider.fetch_latest_fees().await.unwrap();
+
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].transaction_hash, "soroban1");
+        assert_eq!(points[0].fee_amount, 65_000);
+        assert_eq!(points[0].ledger_sequence, 995);
+        assert_eq!(points[0].timestamp.timestamp(), 1736851500);
+        assert_eq!(
+            points[0].envelope,
+            Some(EnvelopeDetails {
+                operation_count: 0,
+                fee_bump: false,
+                max_fee: 100_100,
+                inner_fee: None,
+                category: None,
+                fee_account: Some(MuxedAccount::Ed25519(Uint256([0; 32])).to_string()),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_latest_fees_captures_resource_fee_breakdown() {
+        let server = MockServer::start().await;
+        let mut tx = rpc_tx("soroban1", true, 65_000);
+        tx["resultMetaXdr"] = json!(result_meta_xdr(40_000, 24_900, 20_000));
+        mount_rpc(&server, vec![tx, rpc_tx("soroban2", true, 90_000)]).await;
+
+        let provider = SorobanRpcFeeDataProvider::new(SorobanRpcClient::new(server.uri()));
+        let points = provider.fetch_latest_fees().await.unwrap();
+
+        let declared = SorobanFeeDetail {
+            instructions: 1_000_000,
+            disk_read_bytes: 2_000,
+            write_bytes: 500,
+            read_entries: 0,
+            write_entries: 0,
+            resource_fee_bid: 80_000,
+            non_refundable_fee: None,
+            refundable_fee: None,
+            rent_fee: None,
+        };
+        assert_eq!(
+            points[0].soroban,
+            Some(SorobanFeeDetail {
+                non_refundable_fee: Some(40_000),
+                refundable_fee: Some(24_900),
+                rent_fee: Some(20_000),
+                ..declared.clone()
+            })
+        );
+        assert_eq!(points[0].soroban.as_ref().unwrap().resource_fee(), 64_900);
+        // Without result meta only the declared resources are known.
+        assert_eq!(points[1].soroban, Some(declared));
+    }
+
+    #[tokio::test]
+    async fn fetch_latest_fees_without_soroban_transactions_is_empty() {
+        let server = MockServer::start().await;
+        mount_rpc(&server, vec![rpc_tx("classic1", false, 100)]).await;
+
+        let provider = SorobanRpcFeeDataProvider::new(SorobanRpcClient::new(server.uri()));
+        let points = provider.fetch_latest_fees().await.unwrap();
+
+        assert!(points.is_empty());
+    }
+
+    #[tokio::test]
+    async fn fetch_latest_fees_follows_the_cursor_to_the_latest_ledger() {
+        let server = MockServer::start().await;
+        mount_rpc(&server, vec![]).await;
+        // A full first page of classic transactions, then the newest one
+        let classic = rpc_tx("classic", false, 100);
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "method": "getTransactions",
+                "params": { "startLedger": 988 }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "transactions": vec![classic; FETCH_LIMIT as usize],
+                    "latestLedger": 1000,
+                    "cursor": "page-1"
+                }
+            })))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        let mut newest = rpc_tx("soroban-latest", true, 65_000);
+        newest["ledger"] = json!(1000);
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "method": "getTransactions",
+                "params": { "pagination": { "cursor": "page-1" } }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "transactions": [newest],
+                    "latestLedger": 1000,
+                    "cursor": "page-2"
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = SorobanRpcFeeDataProvider::new(SorobanRpcClient::new(server.uri()));
+        let points = provider.fetch_latest_fees().await.unwrap();
+
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].transaction_hash, "soroban-latest");
+        assert_eq!(points[0].ledger_sequence, 1000);
+    }
+
+    #[tokio::test]
+    async fn rpc_error_maps_to_network_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": { "code": -32603, "message": "internal error" }
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = SorobanRpcFeeDataProvider::new(SorobanRpcClient::new(server.uri()));
+        let result = provider.fetch_latest_fees().await;
+
+        assert!(matches!(result, Err(ProviderError::NetworkError { .. })));
+    }
+
+    #[test]
+    fn metadata_reports_soroban_batch_size() {
+        let provider =
+            SorobanRpcFeeDataProvider::new(SorobanRpcClient::new("http://localhost".into()));
+        assert_eq!(provider.provider_name(), "SorobanRpc");
+        assert_eq!(provider.get_metadata().max_batch_size, 200);
+    }
+
+    #[tokio::test]
+    async fn refresh_metadata_reads_network_and_latest_ledger() {
+        let server = MockServer::start().await;
+        mount_rpc(&server, vec![]).await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "method": "getNetwork" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "passphrase": "Test SDF Network ; September 2015",
+                    "protocolVersion": 23
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = SorobanRpcFeeDataProvider::new(SorobanRpcClient::new(server.uri()));
+        let metadata = provider.refresh_metadata().await.unwrap();
+
+        assert_eq!(metadata.latest_ledger, Some(1000));
+        assert_eq!(
+            metadata.network_passphrase.as_deref(),
+            Some("Test SDF Network ; September 2015")
+        );
+        assert!(metadata.horizon_version.is_none());
+        assert!(provider.get_metadata().refreshed_at.is_some());
+    }
+}
@@ -0,0 +1,94 @@
+//! Soroban resource fee pricing
+//!
+//! Prices an invocation's declared resources with the network's contract
+//! cost settings, following the resource fee formula stellar-core applies.
+
+use crate::insights::{config::SorobanPricingConfig, types::*};
+
+/// Fixed bytes added to the transaction size for its history entry.
+const HISTORICAL_ENTRY_OVERHEAD_BYTES: u64 = 300;
+
+/// Resource fee of an invocation using `resources`.
+pub fn resource_fee(
+    resources: &SorobanResources,
+    pricing: &SorobanPricingConfig,
+) -> SorobanResourceFee {
+    let per_kb = |bytes: u64, rate: u64| (bytes * rate).div_ceil(1024);
+    let transaction_size = resources.transaction_size_bytes as u64;
+
+    let compute =
+        (resources.instructions as u64 * pricing.fee_per_instructions_increment).div_ceil(10_000);
+    let read_entries = resources.read_entries as u64 * pricing.fee_per_read_entry;
+    let write_entries = resources.write_entries as u64 * pricing.fee_per_write_entry;
+    let read_bytes = per_kb(resources.disk_read_bytes as u64, pricing.fee_per_read_1kb);
+    let write_bytes = per_kb(resources.write_bytes as u64, pricing.fee_per_write_1kb);
+    let historical = per_kb(
+        transaction_size + HISTORICAL_ENTRY_OVERHEAD_BYTES,
+        pricing.fee_per_historical_1kb,
+    );
+    let bandwidth = per_kb(transaction_size, pricing.fee_per_transaction_size_1kb);
+    let contract_events = per_kb(
+        resources.contract_events_bytes as u64,
+        pricing.fee_per_contract_events_1kb,
+    );
+
+    let non_refundable =
+        compute + read_entries + write_entries + read_bytes + write_bytes + historical + bandwidth;
+    let refundable = contract_events + resources.rent_fee;
+
+    SorobanResourceFee {
+        compute,
+        read_entries,
+        write_entries,
+        read_bytes,
+        write_bytes,
+        historical,
+        bandwidth,
+        contract_events,
+        rent: resources.rent_fee,
+        non_refundable,
+        refundable,
+        total: non_refundable + refundable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prices_each_resource_at_its_rate() {
+        let resources = SorobanResources {
+            instructions: 1_000_001,
+            read_entries: 3,
+            write_entries: 1,
+            disk_read_bytes: 2_048,
+            write_bytes: 512,
+            transaction_size_bytes: 724,
+            contract_events_bytes: 1_024,
+            rent_fee: 500,
+        };
+
+        let fee = resource_fee(&resources, &SorobanPricingConfig::default());
+        assert_eq!(fee.compute, 2_501);
+        assert_eq!(fee.read_entries, 18_750);
+        assert_eq!(fee.write_entries, 10_000);
+        assert_eq!(fee.read_bytes, 3_572);
+        assert_eq!(fee.write_bytes, 5_900);
+        assert_eq!(fee.historical, 16_235);
+        assert_eq!(fee.bandwidth, 1_149);
+        assert_eq!(fee.non_refundable, 58_107);
+        assert_eq!(fee.refundable, 10_500);
+        assert_eq!(fee.total, 68_607);
+    }
+
+    #[test]
+    fn empty_footprint_still_pays_for_history() {
+        let fee = resource_fee(
+            &SorobanResources::default(),
+            &SorobanPricingConfig::default(),
+        );
+        assert_eq!(fee.historical, (300 * 16_235_u64).div_ceil(1024));
+        assert_eq!(fee.total, fee.historical);
+    }
+}
@@ -0,0 +1,148 @@
+//! Surge episode detection
+//!
+//! Unlike `FeeSpike`s, which are found within a single batch, a surge episode
+//! may span many polls: it opens on the first fee at or above the threshold
+//! and closes on the first fee back below it.
+
+use chrono::{DateTime, Utc};
+
+use crate::insights::{config::SurgeConfig, types::*};
+
+/// A surge that has not ended yet
+#[derive(Debug, Clone)]
+struct OpenSurge {
+    start_time: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+    peak_fee: u64,
+    baseline_fee: f64,
+    sample_count: usize,
+}
+
+/// Tracks surge episodes across successive fee batches
+pub struct SurgeTracker {
+    config: SurgeConfig,
+    open: Option<OpenSurge>,
+}
+
+impl SurgeTracker {
+    /// Create a new surge tracker
+    pub fn new(config: SurgeConfig) -> Self {
+        Self { config, open: None }
+    }
+
+    /// Start time of the surge in progress, if any.
+    pub fn current_surge_start(&self) -> Option<DateTime<Utc>> {
+        self.open.as_ref().map(|surge| surge.start_time)
+    }
+
+    /// Feed a batch of fees and return the episodes it completed. A new
+    /// surge is measured against `baseline`; an open one keeps the baseline
+    /// it started with, since the surge itself drags averages up.
+    pub fn observe(&mut self, fees: &[FeeDataPoint], baseline: f64) -> Vec<SurgeEpisode> {
+        let mut sorted: Vec<&FeeDataPoint> = fees.iter().collect();
+        sorted.sort_by_key(|point| point.timestamp);
+
+        let mut completed = Vec::new();
+        for point in sorted {
+            let reference = self
+                .open
+                .as_ref()
+                .map_or(baseline, |surge| surge.baseline_fee);
+            if reference <= 0.0 {
+                continue;
+            }
+            let surging = point.fee_amount as f64 >= reference * self.config.baseline_multiple;
+
+            match (&mut self.open, surging) {
+                (Some(surge), true) => {
+                    surge.last_seen = surge.last_seen.max(point.timestamp);
+                    surge.peak_fee = surge.peak_fee.max(point.fee_amount);
+                    surge.sample_count += 1;
+                }
+                (None, true) => {
+                    self.open = Some(OpenSurge {
+                        start_time: point.timestamp,
+                        last_seen: point.timestamp,
+                        peak_fee: point.fee_amount,
+                        baseline_fee: baseline,
+                        sample_count: 1,
+                    });
+                }
+                (Some(_), false) => {
+                    if let Some(surge) = self.open.take() {
+                        let end_time = point.timestamp.max(surge.last_seen);
+                        let duration = end_time - surge.start_time;
+                        if duration >= self.config.min_duration {
+                            completed.push(SurgeEpisode {
+                                start_time: surge.start_time,
+                                end_time,
+                                duration,
+                                peak_fee: surge.peak_fee,
+                                baseline_fee: surge.baseline_fee,
+                                sample_count: surge.sample_count,
+                            });
+                        }
+                    }
+                }
+                (None, false) => {}
+            }
+        }
+
+        completed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn batch(start: DateTime<Utc>, fees: &[u64]) -> Vec<FeeDataPoint> {
+        fees.iter()
+            .enumerate()
+            .map(|(i, &fee_amount)| FeeDataPoint {
+                fee_amount,
+                timestamp: start + Duration::seconds(30 * i as i64),
+                transaction_hash: format!("hash_{}_{}", start.timestamp(), i),
+                ledger_sequence: i as u64,
+                envelope: None,
+                soroban: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn surge_spanning_batches_is_reported_once_it_ends() {
+        let mut tracker = SurgeTracker::new(SurgeConfig::default());
+        let t0 = Utc::now() - Duration::minutes(30);
+
+        assert!(tracker
+            .observe(&batch(t0, &[100, 250, 400]), 100.0)
+            .is_empty());
+        assert_eq!(
+            tracker.current_surge_start(),
+            Some(t0 + Duration::seconds(30))
+        );
+
+        // The baseline has risen, but the episode keeps its own.
+        let t1 = t0 + Duration::minutes(2);
+        let completed = tracker.observe(&batch(t1, &[300, 150]), 250.0);
+
+        assert_eq!(completed.len(), 1);
+        let episode = &completed[0];
+        assert_eq!(episode.start_time, t0 + Duration::seconds(30));
+        assert_eq!(episode.end_time, t1 + Duration::seconds(30));
+        assert_eq!(episode.peak_fee, 400);
+        assert_eq!(episode.baseline_fee, 100.0);
+        assert_eq!(episode.sample_count, 3);
+        assert!(tracker.current_surge_start().is_none());
+    }
+
+    #[test]
+    fn short_surges_are_discarded() {
+        let mut tracker = SurgeTracker::new(SurgeConfig::default());
+        let completed = tracker.observe(&batch(Utc::now(), &[100, 500, 100]), 100.0);
+        assert!(completed.is_empty());
+        assert!(tracker.current_surge_start().is_none());
+    }
+}
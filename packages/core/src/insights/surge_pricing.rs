@@ -0,0 +1,160 @@
+//! Surge pricing phase detection
+//!
+//! While ledgers have room, every transaction is charged the base fee per
+//! operation. Once demand exceeds capacity the network charges everyone in
+//! a ledger the lowest included bid instead, so the cheapest fee per
+//! operation in the latest ledger reveals the effective base fee.
+
+use chrono::{DateTime, Utc};
+
+use crate::insights::types::*;
+
+/// Protocol base fee per operation, in stroops, until a ledger reports one.
+const DEFAULT_BASE_FEE: u64 = 100;
+
+/// Tracks whether surge pricing is active and since when
+pub struct SurgePricingTracker {
+    base_fee: u64,
+    current: Option<SurgePricing>,
+}
+
+impl SurgePricingTracker {
+    /// Create a tracker assuming the default base fee
+    pub fn new() -> Self {
+        Self {
+            base_fee: DEFAULT_BASE_FEE,
+            current: None,
+        }
+    }
+
+    /// Latest surge pricing status, if any classic fee has been seen.
+    pub fn current(&self) -> Option<SurgePricing> {
+        self.current.clone()
+    }
+
+    /// Take the base fee from the most recent of `ledgers`.
+    pub fn record_ledgers(&mut self, ledgers: &[LedgerInfo]) {
+        if let Some(ledger) = ledgers
+            .iter()
+            .filter(|ledger| ledger.base_fee > 0)
+            .max_by_key(|ledger| ledger.sequence)
+        {
+            self.base_fee = ledger.base_fee;
+        }
+    }
+
+    /// Update the status from the latest ledger in `fees`. Soroban fees are
+    /// skipped: their resource fees say nothing about inclusion pricing.
+    pub fn observe(&mut self, fees: &[FeeDataPoint]) -> Option<SurgePricing> {
+        let classic: Vec<&FeeDataPoint> = fees.iter().filter(|p| p.soroban.is_none()).collect();
+        let ledger_sequence = classic.iter().map(|p| p.ledger_sequence).max()?;
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|current| current.ledger_sequence > ledger_sequence)
+        {
+            return self.current();
+        }
+
+        let in_ledger = || {
+            classic
+                .iter()
+                .filter(move |p| p.ledger_sequence == ledger_sequence)
+        };
+        let effective_fee_per_operation = in_ledger()
+            .map(|p| p.fee_per_operation().unwrap_or(p.fee_amount as f64))
+            .fold(f64::INFINITY, f64::min);
+        let observed_at = in_ledger().map(|p| p.timestamp).max()?;
+
+        let multiplier = effective_fee_per_operation / self.base_fee as f64;
+        let active = multiplier > 1.0;
+        let active_since: Option<DateTime<Utc>> = match &self.current {
+            _ if !active => None,
+            Some(previous) if previous.active => previous.active_since,
+            _ => Some(observed_at),
+        };
+
+        self.current = Some(SurgePricing {
+            active,
+            multiplier,
+            effective_fee_per_operation,
+            base_fee: self.base_fee,
+            ledger_sequence,
+            active_since,
+        });
+        self.current()
+    }
+}
+
+impl Default for SurgePricingTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn point(ledger_sequence: u64, fee_amount: u64, operation_count: u32) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp: Utc::now() + Duration::seconds(5 * ledger_sequence as i64),
+            transaction_hash: format!("hash_{}_{}", ledger_sequence, fee_amount),
+            ledger_sequence,
+            envelope: Some(EnvelopeDetails {
+                operation_count,
+                fee_bump: false,
+                max_fee: fee_amount,
+                inner_fee: None,
+                category: None,
+                fee_account: None,
+            }),
+            soroban: None,
+        }
+    }
+
+    #[test]
+    fn base_fee_ledgers_are_not_surging() {
+        let mut tracker = SurgePricingTracker::new();
+
+        let status = tracker
+            .observe(&[point(1, 100, 1), point(1, 300, 3)])
+            .unwrap();
+
+        assert!(!status.active);
+        assert_eq!(status.multiplier, 1.0);
+        assert_eq!(status.active_since, None);
+    }
+
+    #[test]
+    fn surge_is_priced_from_the_cheapest_operation_in_the_latest_ledger() {
+        let mut tracker = SurgePricingTracker::new();
+        tracker.record_ledgers(&[LedgerInfo {
+            sequence: 2,
+            closed_at: Utc::now(),
+            transaction_count: 2,
+            operation_count: 2,
+            max_tx_set_size: 2,
+            base_fee: 100,
+        }]);
+
+        // Ledger 1 is older and ignored; in ledger 2 everyone paid 250/op.
+        let first = tracker
+            .observe(&[point(1, 100, 1), point(2, 500, 2), point(2, 1_000, 4)])
+            .unwrap();
+        assert!(first.active);
+        assert_eq!(first.multiplier, 2.5);
+        assert_eq!(first.ledger_sequence, 2);
+        let since = first.active_since.unwrap();
+
+        let second = tracker.observe(&[point(3, 400, 1)]).unwrap();
+        assert_eq!(second.multiplier, 4.0);
+        assert_eq!(second.active_since, Some(since));
+
+        let ended = tracker.observe(&[point(4, 100, 1)]).unwrap();
+        assert!(!ended.active);
+        assert_eq!(ended.active_since, None);
+    }
+}
@@ -8,9 +8,14 @@
 mod tests {
     use crate::insights::{
         calculator::RollingAverageCalculator,
-        config::{AverageConfig, ExtremesConfig, InsightsConfig, SpikeConfig},
-        detector::CongestionDetector,
-        engine::FeeInsightsEngine,
+        config::{
+            AverageConfig, CongestionSignalConfig, CongestionStateConfig, ExtremesConfig,
+            HistogramBins, InsightsConfig, OutlierFilter, PercentileMethod, SpikeConfig,
+            TrendConfig,
+        },
+        detector::{CongestionDetector, CongestionStateMachine},
+        engine::{FeeInsightsEngine, InsightCalculator},
+        error::InsightsError,
         tracker::ExtremesTracker,
         types::*,
     };
@@ -33,6 +38,8 @@ mod tests {
                     timestamp,
                     transaction_hash,
                     ledger_sequence,
+                    envelope: None,
+                    soroban: None,
                 },
             )
     }
@@ -48,6 +55,7 @@ mod tests {
                 name,
                 duration: Duration::seconds(duration_secs),
                 min_samples,
+                averaging: AveragingMethod::Simple,
             })
     }
 
@@ -62,6 +70,7 @@ mod tests {
             name: "test".to_string(),
             duration: Duration::hours(1),
             min_samples: 5,
+            averaging: AveragingMethod::Simple,
         }];
 
         let _calculator = RollingAverageCalculator::new(config, time_windows);
@@ -77,16 +86,19 @@ mod tests {
                 name: "short_term".to_string(),
                 duration: Duration::hours(1),
                 min_samples: 2,
+                averaging: AveragingMethod::Simple,
             },
             TimeWindow {
                 name: "medium_term".to_string(),
                 duration: Duration::hours(6),
                 min_samples: 2,
+                averaging: AveragingMethod::Simple,
             },
             TimeWindow {
                 name: "long_term".to_string(),
                 duration: Duration::hours(24),
                 min_samples: 2,
+                averaging: AveragingMethod::Simple,
             },
         ];
 
@@ -100,12 +112,16 @@ mod tests {
                 timestamp: now - Duration::minutes(30),
                 transaction_hash: "hash1".to_string(),
                 ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 200,
                 timestamp: now - Duration::minutes(15),
                 transaction_hash: "hash2".to_string(),
                 ledger_sequence: 2,
+                envelope: None,
+                soroban: None,
             },
         ];
 
@@ -119,6 +135,673 @@ mod tests {
         assert_eq!(averages.short_term.value, 150.0);
         assert_eq!(averages.short_term.sample_count, 2);
         assert!(!averages.short_term.is_partial);
+        assert_eq!(averages.short_term.fee_bump_count, 0);
+        assert_eq!(averages.short_term.fee_bump_volume_share, 0.0);
+        assert!(averages.short_term.avg_fee_per_operation.is_none());
+    }
+
+    #[test]
+    fn test_rolling_average_reports_fee_bump_volume_share() {
+        let mut calculator = RollingAverageCalculator::new(
+            AverageConfig::default(),
+            InsightsConfig::default().time_windows,
+        );
+
+        let now = Utc::now();
+        let point = |fee_amount, fee_bump| FeeDataPoint {
+            fee_amount,
+            timestamp: now - Duration::minutes(1),
+            transaction_hash: format!("hash_{}", fee_amount),
+            ledger_sequence: 1,
+            envelope: Some(EnvelopeDetails {
+                operation_count: 1,
+                fee_bump,
+                max_fee: fee_amount * 2,
+                inner_fee: fee_bump.then_some(100),
+                category: None,
+                fee_account: None,
+            }),
+            soroban: None,
+        };
+        calculator.add_data_point(point(100, false));
+        calculator.add_data_point(point(300, true));
+
+        let averages = calculator.calculate_averages().unwrap();
+
+        assert_eq!(averages.short_term.fee_bump_count, 1);
+        assert_eq!(averages.short_term.fee_bump_volume_share, 0.75);
+    }
+
+    #[test]
+    fn test_rolling_average_reports_fee_per_operation() {
+        let mut calculator = RollingAverageCalculator::new(
+            AverageConfig::default(),
+            InsightsConfig::default().time_windows,
+        );
+
+        let now = Utc::now();
+        let point = |fee_amount, operation_count: Option<u32>| FeeDataPoint {
+            fee_amount,
+            timestamp: now - Duration::minutes(1),
+            transaction_hash: format!("hash_{}", fee_amount),
+            ledger_sequence: 1,
+            envelope: operation_count.map(|operation_count| EnvelopeDetails {
+                operation_count,
+                fee_bump: false,
+                max_fee: fee_amount,
+                inner_fee: None,
+                category: None,
+                fee_account: None,
+            }),
+            soroban: None,
+        };
+        // 100 stroops for 1 op and 1000 stroops for 10 ops both cost 100/op;
+        // the point without an envelope is left out.
+        calculator.add_data_point(point(100, Some(1)));
+        calculator.add_data_point(point(1_000, Some(10)));
+        calculator.add_data_point(point(5_000, None));
+
+        let averages = calculator.calculate_averages().unwrap();
+
+        assert_eq!(averages.short_term.avg_fee_per_operation, Some(100.0));
+        assert_eq!(averages.short_term.sample_count, 3);
+    }
+
+    #[test]
+    fn test_rolling_average_reports_fee_percentiles() {
+        let mut calculator = RollingAverageCalculator::new(
+            AverageConfig::default(),
+            InsightsConfig::default().time_windows,
+        );
+
+        let now = Utc::now();
+        // Fees 100, 200, ..., 2000, added out of order.
+        for i in (1..=20u64).rev() {
+            calculator.add_data_point(FeeDataPoint {
+                fee_amount: i * 100,
+                timestamp: now - Duration::minutes(1),
+                transaction_hash: format!("hash_{}", i),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            });
+        }
+
+        let averages = calculator.calculate_averages().unwrap();
+        let percentiles = averages.short_term.percentiles.unwrap();
+
+        assert_eq!(percentiles.p10, 200);
+        assert_eq!(percentiles.p25, 500);
+        assert_eq!(percentiles.p50, 1_000);
+        assert_eq!(percentiles.p75, 1_500);
+        assert_eq!(percentiles.p90, 1_800);
+        assert_eq!(percentiles.p95, 1_900);
+        assert_eq!(percentiles.p99, 2_000);
+        assert_eq!(averages.long_term.percentiles, Some(percentiles));
+    }
+
+    #[test]
+    fn test_fee_volatility_separates_stable_from_whipsawing_fees() {
+        let volatility = |fees: &[u64]| {
+            let mut calculator = RollingAverageCalculator::new(
+                AverageConfig::default(),
+                InsightsConfig::default().time_windows,
+            );
+            let now = Utc::now();
+            for (i, &fee_amount) in fees.iter().enumerate() {
+                calculator.add_data_point(FeeDataPoint {
+                    fee_amount,
+                    timestamp: now - Duration::minutes(1),
+                    transaction_hash: format!("hash_{}", i),
+                    ledger_sequence: 1,
+                    envelope: None,
+                    soroban: None,
+                });
+            }
+            calculator
+                .calculate_averages()
+                .unwrap()
+                .short_term
+                .volatility
+                .unwrap()
+        };
+
+        // Both average 1000 stroops.
+        let stable = volatility(&[1_000, 1_000, 1_000, 1_000]);
+        assert_eq!(stable.std_dev, 0.0);
+        assert_eq!(stable.coefficient_of_variation, Some(0.0));
+
+        let whipsawing = volatility(&[500, 1_500, 500, 1_500]);
+        assert_eq!(whipsawing.std_dev, 500.0);
+        assert_eq!(whipsawing.coefficient_of_variation, Some(0.5));
+        assert_eq!(whipsawing.median, 1_000.0);
+        assert_eq!(whipsawing.median_absolute_deviation, 500.0);
+    }
+
+    #[test]
+    fn test_median_and_mad_shrug_off_a_heavy_tail() {
+        let mut calculator = RollingAverageCalculator::new(
+            AverageConfig::default(),
+            InsightsConfig::default().time_windows,
+        );
+        let now = Utc::now();
+        for (i, fee_amount) in [300, 100, 10_000_000, 200, 100].into_iter().enumerate() {
+            calculator.add_data_point(FeeDataPoint {
+                fee_amount,
+                timestamp: now - Duration::minutes(1),
+                transaction_hash: format!("hash_{}", i),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            });
+        }
+
+        let short_term = calculator.calculate_averages().unwrap().short_term;
+        let volatility = short_term.volatility.unwrap();
+        assert!(short_term.value > 2_000_000.0);
+        assert!(volatility.std_dev > 3_000_000.0);
+        assert_eq!(volatility.median, 200.0);
+        // Deviations 0, 100, 100, 100 and 9,999,800
+        assert_eq!(volatility.median_absolute_deviation, 100.0);
+    }
+
+    #[test]
+    fn test_bid_spread_compares_bids_with_charged_fees() {
+        let mut calculator = RollingAverageCalculator::new(
+            AverageConfig::default(),
+            InsightsConfig::default().time_windows,
+        );
+        let now = Utc::now();
+        // (bid, charged); the last point has no decoded envelope.
+        let samples = [
+            (Some(100), 100),
+            (Some(1_000), 100),
+            (Some(500), 200),
+            (None, 300),
+        ];
+        for (i, (max_fee, fee_amount)) in samples.into_iter().enumerate() {
+            calculator.add_data_point(FeeDataPoint {
+                fee_amount,
+                timestamp: now - Duration::minutes(1),
+                transaction_hash: format!("hash_{}", i),
+                ledger_sequence: 1,
+                envelope: max_fee.map(|max_fee| EnvelopeDetails {
+                    operation_count: 1,
+                    max_fee,
+                    ..EnvelopeDetails::default()
+                }),
+                soroban: None,
+            });
+        }
+
+        let spread = calculator
+            .calculate_averages()
+            .unwrap()
+            .short_term
+            .bid_spread
+            .unwrap();
+        assert_eq!(spread.sample_count, 3);
+        assert_eq!(spread.average_bid, 1_600.0 / 3.0);
+        assert_eq!(spread.mean_spread, 1_200.0 / 3.0);
+        assert_eq!(spread.median_spread, 300);
+        assert_eq!(spread.bid_to_charged_ratio, 4.0);
+        assert_eq!(spread.overbidding_rate, 2.0 / 3.0);
+        assert_eq!(spread.total_waste, 1_200);
+        assert_eq!(spread.waste_share, 1_200.0 / 1_600.0);
+    }
+
+    #[test]
+    fn test_fee_histogram_buckets_by_configured_bins() {
+        let points = |calculator: &mut RollingAverageCalculator| {
+            let now = Utc::now();
+            for (i, fee) in [100u64, 100, 150, 400, 1_000, 10_000]
+                .into_iter()
+                .enumerate()
+            {
+                calculator.add_data_point(FeeDataPoint {
+                    fee_amount: fee,
+                    timestamp: now - Duration::minutes(1),
+                    transaction_hash: format!("hash_{}", i),
+                    ledger_sequence: 1,
+                    envelope: None,
+                    soroban: None,
+                });
+            }
+        };
+        let counts = |histogram: FeeHistogram| -> Vec<(u64, Option<u64>, usize)> {
+            histogram
+                .buckets
+                .into_iter()
+                .map(|b| (b.lower, b.upper, b.count))
+                .collect()
+        };
+
+        let mut fixed = RollingAverageCalculator::new(
+            AverageConfig {
+                histogram: HistogramBins::Fixed {
+                    edges: vec![1_000, 200],
+                },
+                ..AverageConfig::default()
+            },
+            InsightsConfig::default().time_windows,
+        );
+        points(&mut fixed);
+        let histogram = fixed.calculate_averages().unwrap().short_term.histogram;
+        assert_eq!(
+            counts(histogram.unwrap()),
+            vec![(0, Some(200), 3), (200, Some(1_000), 1), (1_000, None, 2)]
+        );
+
+        // 100..10000 in two log-scaled bins splits at 1000.
+        let mut log = RollingAverageCalculator::new(
+            AverageConfig {
+                histogram: HistogramBins::Logarithmic { bins: 2 },
+                ..AverageConfig::default()
+            },
+            InsightsConfig::default().time_windows,
+        );
+        points(&mut log);
+        let histogram = log.calculate_averages().unwrap().long_term.histogram;
+        assert_eq!(
+            counts(histogram.unwrap()),
+            vec![(0, Some(1_000), 4), (1_000, None, 2)]
+        );
+    }
+
+    #[test]
+    fn test_rolling_average_segments_fees_by_operation_category() {
+        let mut calculator = RollingAverageCalculator::new(
+            AverageConfig::default(),
+            InsightsConfig::default().time_windows,
+        );
+
+        let now = Utc::now();
+        let point = |i: u64, fee_amount, category: Option<OperationCategory>| FeeDataPoint {
+            fee_amount,
+            timestamp: now - Duration::minutes(1),
+            transaction_hash: format!("hash_{}", i),
+            ledger_sequence: 1,
+            envelope: category.map(|category| EnvelopeDetails {
+                operation_count: 2,
+                fee_bump: false,
+                max_fee: fee_amount,
+                inner_fee: None,
+                category: Some(category),
+                fee_account: None,
+            }),
+            soroban: None,
+        };
+        // DEX traffic bids far above plain payments.
+        calculator.add_data_point(point(1, 200, Some(OperationCategory::Payment)));
+        calculator.add_data_point(point(2, 400, Some(OperationCategory::Payment)));
+        calculator.add_data_point(point(3, 50_000, Some(OperationCategory::OfferManagement)));
+        calculator.add_data_point(point(4, 9_000, None));
+
+        let by_category = calculator
+            .calculate_averages()
+            .unwrap()
+            .short_term
+            .by_category;
+
+        assert_eq!(by_category.len(), 2);
+        let payments = &by_category[0];
+        assert_eq!(payments.category, OperationCategory::Payment);
+        assert_eq!(payments.sample_count, 2);
+        assert_eq!(payments.average_fee, 300.0);
+        assert_eq!(payments.avg_fee_per_operation, Some(150.0));
+        assert_eq!(payments.percentiles.p50, 200);
+        let offers = &by_category[1];
+        assert_eq!(offers.category, OperationCategory::OfferManagement);
+        assert_eq!(offers.average_fee, 50_000.0);
+    }
+
+    #[test]
+    fn test_exponential_average_weights_recent_fees() {
+        let window = |averaging| TimeWindow {
+            name: "short_term".to_string(),
+            duration: Duration::hours(1),
+            min_samples: 1,
+            averaging,
+        };
+        let mut calculator = RollingAverageCalculator::new(
+            AverageConfig::default(),
+            vec![
+                window(AveragingMethod::Simple),
+                TimeWindow {
+                    name: "medium_term".to_string(),
+                    ..window(AveragingMethod::Exponential {
+                        smoothing_factor: 0.5,
+                    })
+                },
+                TimeWindow {
+                    name: "long_term".to_string(),
+                    ..window(AveragingMethod::Simple)
+                },
+            ],
+        );
+
+        let now = Utc::now();
+        for (i, fee) in [100u64, 100, 100, 500].into_iter().enumerate() {
+            calculator.add_data_point(FeeDataPoint {
+                fee_amount: fee,
+                timestamp: now - Duration::minutes(4 - i as i64),
+                transaction_hash: format!("hash_{}", i),
+                ledger_sequence: i as u64,
+                envelope: None,
+                soroban: None,
+            });
+        }
+
+        let averages = calculator.calculate_averages().unwrap();
+        assert_eq!(averages.short_term.value, 200.0);
+        // 100, 100, 100, then 0.5 * 500 + 0.5 * 100
+        assert_eq!(averages.medium_term.value, 300.0);
+    }
+
+    #[test]
+    fn test_operation_weighted_average_charges_per_operation() {
+        let window = |name: &str, averaging| TimeWindow {
+            name: name.to_string(),
+            duration: Duration::hours(1),
+            min_samples: 1,
+            averaging,
+        };
+        let mut calculator = RollingAverageCalculator::new(
+            AverageConfig::default(),
+            vec![
+                window("short_term", AveragingMethod::Simple),
+                window("medium_term", AveragingMethod::OperationWeighted),
+                window("long_term", AveragingMethod::Simple),
+            ],
+        );
+
+        // Three one-operation payments at 100 and one ten-operation batch
+        // at 1_000; the last fee has no envelope and counts as one.
+        let now = Utc::now();
+        let fees = [
+            (100u64, Some(1)),
+            (100, Some(1)),
+            (1_000, Some(10)),
+            (100, None),
+        ];
+        for (i, (fee, operation_count)) in fees.into_iter().enumerate() {
+            calculator.add_data_point(FeeDataPoint {
+                fee_amount: fee,
+                timestamp: now - Duration::minutes(4 - i as i64),
+                transaction_hash: format!("hash_{}", i),
+                ledger_sequence: i as u64,
+                envelope: operation_count.map(|operation_count| EnvelopeDetails {
+                    operation_count,
+                    fee_bump: false,
+                    max_fee: fee,
+                    inner_fee: None,
+                    category: None,
+                    fee_account: None,
+                }),
+                soroban: None,
+            });
+        }
+
+        let averages = calculator.calculate_averages().unwrap();
+        assert_eq!(averages.short_term.value, 325.0);
+        assert_eq!(averages.medium_term.value, 1_300.0 / 13.0);
+        assert_ne!(AveragingMethod::OperationWeighted, AveragingMethod::Simple);
+    }
+
+    #[test]
+    fn test_operation_weighted_average_filters_outliers_before_weighting() {
+        let average_with = |outlier_filter| {
+            let mut windows = InsightsConfig::default().time_windows;
+            windows[0].averaging = AveragingMethod::OperationWeighted;
+            let mut calculator = RollingAverageCalculator::new(
+                AverageConfig {
+                    outlier_filter,
+                    ..AverageConfig::default()
+                },
+                windows,
+            );
+            let now = Utc::now();
+            let fees = [
+                (50u64, 1u32),
+                (100, 1),
+                (100, 1),
+                (1_000, 10),
+                (10_000_000, 1),
+            ];
+            for (i, (fee, operation_count)) in fees.into_iter().enumerate() {
+                calculator.add_data_point(FeeDataPoint {
+                    fee_amount: fee,
+                    timestamp: now - Duration::seconds(60 - i as i64),
+                    transaction_hash: format!("hash_{}", i),
+                    ledger_sequence: i as u64,
+                    envelope: Some(EnvelopeDetails {
+                        operation_count,
+                        fee_bump: false,
+                        max_fee: fee,
+                        inner_fee: None,
+                        category: None,
+                        fee_account: None,
+                    }),
+                    soroban: None,
+                });
+            }
+            calculator.calculate_averages().unwrap().short_term.value
+        };
+
+        assert_eq!(average_with(OutlierFilter::None), 10_001_250.0 / 14.0);
+        // Trimming 20% drops the 50 and the 10,000,000 with their operations
+        assert_eq!(average_with(OutlierFilter::Trim { fraction: 0.2 }), 100.0);
+        // Winsorizing clamps them to 100 and 1,000, still one operation each
+        assert_eq!(
+            average_with(OutlierFilter::Winsorize { fraction: 0.2 }),
+            2_300.0 / 14.0
+        );
+    }
+
+    #[test]
+    fn test_outlier_filter_keeps_a_pathological_fee_out_of_the_average() {
+        let average_with = |outlier_filter| {
+            let mut windows = InsightsConfig::default().time_windows;
+            windows[1].averaging = AveragingMethod::Exponential {
+                smoothing_factor: 0.5,
+            };
+            let mut calculator = RollingAverageCalculator::new(
+                AverageConfig {
+                    outlier_filter,
+                    ..AverageConfig::default()
+                },
+                windows,
+            );
+            let now = Utc::now();
+            // Nine ordinary fees, then one absurd bid
+            let fees = (1..=9u64).map(|i| i * 100).chain([10_000_000]);
+            for (i, fee) in fees.enumerate() {
+                calculator.add_data_point(FeeDataPoint {
+                    fee_amount: fee,
+                    timestamp: now - Duration::seconds(60 - i as i64),
+                    transaction_hash: format!("hash_{}", i),
+                    ledger_sequence: i as u64,
+                    envelope: None,
+                    soroban: None,
+                });
+            }
+            let averages = calculator.calculate_averages().unwrap();
+            (averages.short_term, averages.medium_term)
+        };
+
+        let (unfiltered, _) = average_with(OutlierFilter::None);
+        assert_eq!(unfiltered.value, 1_000_450.0);
+
+        // Trimming 10% drops 100 and 10,000,000
+        let (trimmed, trimmed_ewma) = average_with(OutlierFilter::Trim { fraction: 0.1 });
+        assert_eq!(trimmed.value, 4_400.0 / 8.0);
+        assert_eq!(trimmed.sample_count, 10);
+        assert!(trimmed_ewma.value < 900.0);
+        assert_eq!(trimmed.percentiles.unwrap().p99, 10_000_000);
+
+        // Winsorizing clamps them to 200 and 900 instead, so they still count
+        let (winsorized, winsorized_ewma) =
+            average_with(OutlierFilter::Winsorize { fraction: 0.1 });
+        assert_eq!(winsorized.value, (200.0 + 4_400.0 + 900.0) / 10.0);
+        assert!(winsorized_ewma.value <= 900.0);
+
+        assert!(OutlierFilter::Trim { fraction: 0.5 }.validate().is_err());
+        assert!(OutlierFilter::Winsorize { fraction: 0.01 }
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_sketched_percentiles_track_exact_ones() {
+        let short_term_with = |percentile_method| {
+            let mut calculator = RollingAverageCalculator::new(
+                AverageConfig {
+                    percentile_method,
+                    ..AverageConfig::default()
+                },
+                InsightsConfig::default().time_windows,
+            );
+            let now = Utc::now();
+            for i in 0..2_000u64 {
+                calculator.add_data_point(FeeDataPoint {
+                    fee_amount: 100 + (i * 7_919) % 50_000,
+                    timestamp: now - Duration::seconds(600 - (i % 600) as i64),
+                    transaction_hash: format!("hash_{}", i),
+                    ledger_sequence: i,
+                    envelope: None,
+                    soroban: None,
+                });
+            }
+            calculator.calculate_averages().unwrap().short_term
+        };
+
+        let exact = short_term_with(PercentileMethod::Exact);
+        let sketched = short_term_with(PercentileMethod::Sketch {
+            relative_accuracy: 0.01,
+        });
+        assert_eq!(sketched.value, exact.value);
+
+        let (exact_p, sketched_p) = (exact.percentiles.unwrap(), sketched.percentiles.unwrap());
+        for (exact, sketched) in [
+            (exact_p.p10, sketched_p.p10),
+            (exact_p.p50, sketched_p.p50),
+            (exact_p.p99, sketched_p.p99),
+        ] {
+            assert!((sketched as f64 - exact as f64).abs() <= exact as f64 * 0.01 + 1.0);
+        }
+        let (exact_v, sketched_v) = (exact.volatility.unwrap(), sketched.volatility.unwrap());
+        assert!((sketched_v.median - exact_v.median).abs() <= exact_v.median * 0.01 + 1.0);
+        let bucketed: usize = sketched
+            .histogram
+            .unwrap()
+            .buckets
+            .iter()
+            .map(|b| b.count)
+            .sum();
+        assert_eq!(bucketed, sketched.sample_count);
+
+        assert!(PercentileMethod::Sketch {
+            relative_accuracy: 0.0
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_exponential_average_rejects_invalid_smoothing_factor() {
+        let mut windows = InsightsConfig::default().time_windows;
+        windows[0].averaging = AveragingMethod::Exponential {
+            smoothing_factor: 1.5,
+        };
+        let mut calculator = RollingAverageCalculator::new(AverageConfig::default(), windows);
+        calculator.add_data_point(FeeDataPoint {
+            fee_amount: 100,
+            timestamp: Utc::now(),
+            transaction_hash: "hash".to_string(),
+            ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
+        });
+
+        assert!(calculator.calculate_averages().is_err());
+    }
+
+    #[test]
+    fn test_fee_per_operation_counts_fee_bump_as_extra_operation() {
+        let point = FeeDataPoint {
+            fee_amount: 300,
+            timestamp: Utc::now(),
+            transaction_hash: "bump".to_string(),
+            ledger_sequence: 1,
+            envelope: Some(EnvelopeDetails {
+                operation_count: 2,
+                fee_bump: true,
+                max_fee: 1_000,
+                inner_fee: Some(200),
+                category: None,
+                fee_account: None,
+            }),
+            soroban: None,
+        };
+        assert_eq!(point.operation_count(), Some(3));
+        assert_eq!(point.fee_per_operation(), Some(100.0));
+    }
+
+    #[test]
+    fn test_rolling_average_reports_soroban_resource_averages() {
+        let mut calculator = RollingAverageCalculator::new(
+            AverageConfig::default(),
+            InsightsConfig::default().time_windows,
+        );
+
+        let now = Utc::now();
+        let point = |hash: &str, soroban: Option<SorobanFeeDetail>| FeeDataPoint {
+            fee_amount: 10_000,
+            timestamp: now - Duration::minutes(1),
+            transaction_hash: hash.to_string(),
+            ledger_sequence: 1,
+            envelope: None,
+            soroban,
+        };
+        calculator.add_data_point(point(
+            "charged",
+            Some(SorobanFeeDetail {
+                instructions: 2_000_000,
+                disk_read_bytes: 1_000,
+                write_bytes: 200,
+                resource_fee_bid: 9_000,
+                non_refundable_fee: Some(4_000),
+                refundable_fee: Some(2_000),
+                rent_fee: Some(1_500),
+                ..SorobanFeeDetail::default()
+            }),
+        ));
+        // No charged split known: the bid stands in for the resource fee.
+        calculator.add_data_point(point(
+            "bid_only",
+            Some(SorobanFeeDetail {
+                instructions: 1_000_000,
+                disk_read_bytes: 3_000,
+                write_bytes: 0,
+                resource_fee_bid: 8_000,
+                ..SorobanFeeDetail::default()
+            }),
+        ));
+        calculator.add_data_point(point("classic", None));
+
+        let soroban = calculator
+            .calculate_averages()
+            .unwrap()
+            .short_term
+            .soroban
+            .unwrap();
+
+        assert_eq!(soroban.sample_count, 2);
+        assert_eq!(soroban.instructions, 1_500_000.0);
+        assert_eq!(soroban.disk_read_bytes, 2_000.0);
+        assert_eq!(soroban.write_bytes, 100.0);
+        assert_eq!(soroban.resource_fee, 7_000.0);
+        assert_eq!(soroban.rent_fee, Some(1_500.0));
     }
 
     #[test]
@@ -129,16 +812,19 @@ mod tests {
                 name: "short_term".to_string(),
                 duration: Duration::hours(1),
                 min_samples: 5, // Require 5 samples
+                averaging: AveragingMethod::Simple,
             },
             TimeWindow {
                 name: "medium_term".to_string(),
                 duration: Duration::hours(6),
                 min_samples: 5,
+                averaging: AveragingMethod::Simple,
             },
             TimeWindow {
                 name: "long_term".to_string(),
                 duration: Duration::hours(24),
                 min_samples: 5,
+                averaging: AveragingMethod::Simple,
             },
         ];
 
@@ -151,12 +837,16 @@ mod tests {
             timestamp: now - Duration::minutes(30),
             transaction_hash: "hash1".to_string(),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         });
         calculator.add_data_point(FeeDataPoint {
             fee_amount: 200,
             timestamp: now - Duration::minutes(15),
             transaction_hash: "hash2".to_string(),
             ledger_sequence: 2,
+            envelope: None,
+            soroban: None,
         });
 
         let averages = calculator.calculate_averages().unwrap();
@@ -174,16 +864,19 @@ mod tests {
                 name: "short_term".to_string(),
                 duration: Duration::minutes(30), // 30-minute window
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
             TimeWindow {
                 name: "medium_term".to_string(),
                 duration: Duration::hours(6),
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
             TimeWindow {
                 name: "long_term".to_string(),
                 duration: Duration::hours(24),
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
         ];
 
@@ -197,6 +890,8 @@ mod tests {
             timestamp: now - Duration::hours(2), // 2 hours ago (outside 30-min window)
             transaction_hash: "hash1".to_string(),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         });
 
         // Add recent data point (inside window)
@@ -205,6 +900,8 @@ mod tests {
             timestamp: now - Duration::minutes(15), // 15 minutes ago (inside window)
             transaction_hash: "hash2".to_string(),
             ledger_sequence: 2,
+            envelope: None,
+            soroban: None,
         });
 
         let averages = calculator.calculate_averages().unwrap();
@@ -219,22 +916,29 @@ mod tests {
         let config = AverageConfig {
             max_buffer_size: 3, // Small buffer for testing
             min_samples_for_calculation: 1,
+            histogram: HistogramBins::default(),
+            trend: Default::default(),
+            outlier_filter: OutlierFilter::None,
+            percentile_method: Default::default(),
         };
         let time_windows = vec![
             TimeWindow {
                 name: "short_term".to_string(),
                 duration: Duration::hours(1),
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
             TimeWindow {
                 name: "medium_term".to_string(),
                 duration: Duration::hours(6),
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
             TimeWindow {
                 name: "long_term".to_string(),
                 duration: Duration::hours(24),
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
         ];
 
@@ -249,6 +953,8 @@ mod tests {
                 timestamp: now - Duration::minutes(i as i64 * 5),
                 transaction_hash: format!("hash{}", i),
                 ledger_sequence: i + 1,
+                envelope: None,
+                soroban: None,
             });
         }
 
@@ -262,6 +968,141 @@ mod tests {
         assert_eq!(averages.short_term.value, 400.0);
     }
 
+    #[test]
+    fn test_running_aggregates_follow_evicted_points() {
+        let config = AverageConfig {
+            max_buffer_size: 3,
+            ..AverageConfig::default()
+        };
+        let mut calculator =
+            RollingAverageCalculator::new(config, InsightsConfig::default().time_windows);
+
+        let now = Utc::now();
+        for (i, fee_amount) in [100u64, 200, 300, 400, 1_000].into_iter().enumerate() {
+            let fee_bump = fee_amount == 100 || fee_amount == 400;
+            calculator.add_data_point(FeeDataPoint {
+                fee_amount,
+                timestamp: now - Duration::minutes(1),
+                transaction_hash: format!("hash{}", i),
+                ledger_sequence: i as u64 + 1,
+                envelope: fee_bump.then(|| EnvelopeDetails {
+                    operation_count: 1,
+                    fee_bump: true,
+                    max_fee: fee_amount,
+                    ..EnvelopeDetails::default()
+                }),
+                soroban: None,
+            });
+        }
+
+        // Only 300, 400 and 1000 remain
+        let short_term = calculator.calculate_averages().unwrap().short_term;
+        assert_eq!(short_term.value, 1_700.0 / 3.0);
+        assert_eq!(short_term.fee_bump_count, 1);
+        assert_eq!(short_term.fee_bump_volume_share, 400.0 / 1_700.0);
+        assert_eq!(short_term.avg_fee_per_operation, Some(200.0));
+
+        let percentiles = short_term.percentiles.unwrap();
+        assert_eq!(
+            (percentiles.p10, percentiles.p50, percentiles.p99),
+            (300, 400, 1_000)
+        );
+
+        let mean = 1_700.0 / 3.0;
+        let variance = [300.0, 400.0, 1_000.0]
+            .iter()
+            .map(|fee: &f64| (fee - mean).powi(2))
+            .sum::<f64>()
+            / 3.0;
+        let std_dev = short_term.volatility.unwrap().std_dev;
+        assert!((std_dev - variance.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_incremental_window_stats_match_the_points_left_after_eviction() {
+        let config = AverageConfig {
+            max_buffer_size: 4,
+            outlier_filter: OutlierFilter::Winsorize { fraction: 0.25 },
+            trend: TrendConfig {
+                min_samples: 4,
+                ..TrendConfig::default()
+            },
+            ..AverageConfig::default()
+        };
+        let mut windows = InsightsConfig::default().time_windows;
+        windows[0].averaging = AveragingMethod::Exponential {
+            smoothing_factor: 0.3,
+        };
+        let mut calculator = RollingAverageCalculator::new(config, windows);
+
+        let now = Utc::now();
+        let fees = [900u64, 100, 400, 250, 100, 5_000, 300, 200, 700];
+        for (i, fee_amount) in fees.into_iter().enumerate() {
+            let category = if i % 2 == 0 {
+                OperationCategory::Payment
+            } else {
+                OperationCategory::ContractInvoke
+            };
+            calculator.add_data_point(FeeDataPoint {
+                fee_amount,
+                timestamp: now - Duration::seconds(60 - i as i64),
+                transaction_hash: format!("hash_{}", i),
+                ledger_sequence: i as u64,
+                envelope: Some(EnvelopeDetails {
+                    operation_count: 1,
+                    max_fee: 1_000,
+                    category: Some(category),
+                    ..EnvelopeDetails::default()
+                }),
+                soroban: (category == OperationCategory::ContractInvoke).then(|| {
+                    SorobanFeeDetail {
+                        instructions: fee_amount as u32,
+                        resource_fee_bid: fee_amount,
+                        ..SorobanFeeDetail::default()
+                    }
+                }),
+            });
+        }
+
+        // Only 5,000, 300, 200 and 700 are left
+        let short_term = calculator.calculate_averages().unwrap().short_term;
+        assert_eq!(short_term.sample_count, 4);
+
+        // Each fee was clamped to the cut-offs as it arrived: 5,000 came in
+        // alongside 100, 100, 250 and 400, so was clamped to 400
+        let expected = [400.0, 300.0, 200.0, 700.0]
+            .into_iter()
+            .reduce(|average, fee| 0.3 * fee + 0.7 * average)
+            .unwrap();
+        assert!((short_term.value - expected).abs() < 1e-9);
+
+        let by_category: Vec<(OperationCategory, usize, f64)> = short_term
+            .by_category
+            .iter()
+            .map(|stats| (stats.category, stats.sample_count, stats.average_fee))
+            .collect();
+        assert_eq!(
+            by_category,
+            vec![
+                (OperationCategory::Payment, 2, 500.0),
+                (OperationCategory::ContractInvoke, 2, 2_600.0),
+            ]
+        );
+        let soroban = short_term.soroban.unwrap();
+        assert_eq!(soroban.sample_count, 2);
+        assert_eq!(soroban.resource_fee, 2_600.0);
+
+        let spread = short_term.bid_spread.unwrap();
+        assert_eq!(spread.sample_count, 4);
+        // Spreads 0, 700, 800 and 300
+        assert_eq!(spread.median_spread, 300);
+        assert_eq!(spread.total_waste, 1_800);
+        assert_eq!(
+            short_term.trend.map(|trend| trend.direction),
+            Some(TrendDirection::Falling)
+        );
+    }
+
     #[test]
     fn test_rolling_average_empty_dataset() {
         let config = AverageConfig::default();
@@ -270,16 +1111,19 @@ mod tests {
                 name: "short_term".to_string(),
                 duration: Duration::hours(1),
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
             TimeWindow {
                 name: "medium_term".to_string(),
                 duration: Duration::hours(6),
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
             TimeWindow {
                 name: "long_term".to_string(),
                 duration: Duration::hours(24),
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
         ];
 
@@ -317,18 +1161,24 @@ mod tests {
                 timestamp: now, // Use current time
                 transaction_hash: "hash1".to_string(),
                 ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 50, // Minimum
                 timestamp: now, // Use current time
                 transaction_hash: "hash2".to_string(),
                 ledger_sequence: 2,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 300, // Maximum
                 timestamp: now,  // Use current time
                 transaction_hash: "hash3".to_string(),
                 ledger_sequence: 3,
+                envelope: None,
+                soroban: None,
             },
         ];
 
@@ -354,12 +1204,16 @@ mod tests {
                 timestamp: now - Duration::seconds(1), // Slightly earlier
                 transaction_hash: "hash1".to_string(),
                 ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 100, // Second occurrence of min (more recent)
                 timestamp: now,  // More recent
                 transaction_hash: "hash2".to_string(),
                 ledger_sequence: 2,
+                envelope: None,
+                soroban: None,
             },
         ];
 
@@ -382,6 +1236,8 @@ mod tests {
             timestamp: now,
             transaction_hash: "test_hash_123".to_string(),
             ledger_sequence: 12345,
+            envelope: None,
+            soroban: None,
         }];
 
         tracker.update_with_fees(&fee_data).unwrap();
@@ -394,6 +1250,64 @@ mod tests {
         assert_eq!(extremes.current_max.timestamp, now);
     }
 
+    #[test]
+    fn test_extremes_over_all_time_last_day_and_windows() {
+        let mut tracker = ExtremesTracker::new(ExtremesConfig::default())
+            .with_windows(&InsightsConfig::default().time_windows);
+
+        let now = Utc::now();
+        let point = |fee_amount: u64, minutes_ago: i64, ledger_sequence: u64| FeeDataPoint {
+            fee_amount,
+            timestamp: now - Duration::minutes(minutes_ago),
+            transaction_hash: format!("hash_{}", fee_amount),
+            ledger_sequence,
+            envelope: None,
+            soroban: None,
+        };
+        // Out of order: the cheapest fee is two days old and the dearest is
+        // 30 minutes old, outside the 5-minute short-term window.
+        tracker
+            .update_with_fees(&[
+                point(400, 1, 40),
+                point(90, 2 * 24 * 60, 10),
+                point(9_000, 30, 30),
+                point(150, 2, 35),
+            ])
+            .unwrap();
+        tracker.update_with_fees(&[point(300, 0, 41)]).unwrap();
+
+        let all_time = tracker.all_time().unwrap();
+        assert_eq!(all_time.min.value, 90);
+        assert_eq!(all_time.min.ledger_sequence, 10);
+        assert_eq!(all_time.max.value, 9_000);
+
+        // The current period starts now, so only fees since then count.
+        let extremes = tracker.get_current_extremes().unwrap();
+        assert_eq!(extremes.current_min.value, 300);
+
+        let last_24h = extremes.last_24h.unwrap();
+        assert_eq!(last_24h.min.transaction_hash, "hash_150");
+        assert_eq!(last_24h.max.value, 9_000);
+
+        assert_eq!(extremes.by_window[0].window, "short_term");
+        let short_term = &extremes.by_window[0].extremes;
+        assert_eq!((short_term.min.value, short_term.max.value), (150, 400));
+
+        // Restored extremes only win when more extreme.
+        tracker.restore_all_time(&ExtremeRange {
+            min: ExtremeValue {
+                value: 50,
+                ..all_time.min.clone()
+            },
+            max: ExtremeValue {
+                value: 5_000,
+                ..all_time.max.clone()
+            },
+        });
+        let restored = tracker.all_time().unwrap();
+        assert_eq!((restored.min.value, restored.max.value), (50, 9_000));
+    }
+
     // =============================================================================
     // UNIT TESTS - Congestion Detector
     // =============================================================================
@@ -413,6 +1327,10 @@ mod tests {
             threshold_multiplier: 2.0,
             minimum_spike_duration: Duration::minutes(1),
             congestion_window: Duration::hours(1),
+            capacity_congestion_threshold: 0.9,
+            states: Default::default(),
+            signals: Default::default(),
+            min_samples: 1,
         };
         let detector = CongestionDetector::new(config);
 
@@ -425,24 +1343,32 @@ mod tests {
                 timestamp: now - Duration::minutes(30),
                 transaction_hash: "hash1".to_string(),
                 ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 250, // Spike (2.5x baseline)
                 timestamp: now - Duration::minutes(20),
                 transaction_hash: "hash2".to_string(),
                 ledger_sequence: 2,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 300, // Higher spike
                 timestamp: now - Duration::minutes(15),
                 transaction_hash: "hash3".to_string(),
                 ledger_sequence: 3,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 100, // Back to normal
                 timestamp: now - Duration::minutes(10),
                 transaction_hash: "hash4".to_string(),
                 ledger_sequence: 4,
+                envelope: None,
+                soroban: None,
             },
         ];
 
@@ -479,6 +1405,10 @@ mod tests {
             threshold_multiplier: 2.0,
             minimum_spike_duration: Duration::seconds(1), // Very short duration
             congestion_window: Duration::hours(1),
+            capacity_congestion_threshold: 0.9,
+            states: Default::default(),
+            signals: Default::default(),
+            min_samples: 1,
         };
         let detector = CongestionDetector::new(config);
 
@@ -491,12 +1421,16 @@ mod tests {
                 timestamp: now,
                 transaction_hash: "hash1".to_string(),
                 ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 100, // Back to normal to end the spike
                 timestamp: now + Duration::seconds(2),
                 transaction_hash: "hash2".to_string(),
                 ledger_sequence: 2,
+                envelope: None,
+                soroban: None,
             },
         ];
 
@@ -522,6 +1456,8 @@ mod tests {
             timestamp: Utc::now(),
             transaction_hash: "hash1".to_string(),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         }];
 
         let result = engine.validate_fee_data(&invalid_data);
@@ -534,6 +1470,8 @@ mod tests {
             timestamp: Utc::now(),
             transaction_hash: "hash1".to_string(),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         }];
 
         let result = engine.validate_fee_data(&invalid_data);
@@ -549,6 +1487,8 @@ mod tests {
             timestamp: Utc::now(),
             transaction_hash: "hash1".to_string(),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         }];
 
         let result = engine.validate_fee_data(&valid_data);
@@ -566,6 +1506,8 @@ mod tests {
             timestamp: Utc::now() + Duration::hours(2), // 2 hours in future
             transaction_hash: "hash1".to_string(),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         }];
 
         let result = engine.validate_fee_data(&invalid_data);
@@ -578,6 +1520,8 @@ mod tests {
             timestamp: Utc::now() - Duration::minutes(30),
             transaction_hash: "hash1".to_string(),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         }];
 
         let result = engine.validate_fee_data(&valid_data);
@@ -595,6 +1539,8 @@ mod tests {
             timestamp: Utc::now(),
             transaction_hash: "".to_string(),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         }];
 
         let result = engine.validate_fee_data(&invalid_data);
@@ -610,6 +1556,8 @@ mod tests {
             timestamp: Utc::now(),
             transaction_hash: "valid_hash_123".to_string(),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         }];
 
         let result = engine.validate_fee_data(&valid_data);
@@ -628,16 +1576,19 @@ mod tests {
                 name: "short_term".to_string(),
                 duration: Duration::hours(1),
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
             TimeWindow {
                 name: "medium_term".to_string(),
                 duration: Duration::hours(6),
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
             TimeWindow {
                 name: "long_term".to_string(),
                 duration: Duration::hours(24),
                 min_samples: 1,
+                averaging: AveragingMethod::Simple,
             },
         ];
 
@@ -652,12 +1603,16 @@ mod tests {
                 timestamp: now - Duration::minutes(30),
                 transaction_hash: "hash1".to_string(),
                 ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 999_999_998,
                 timestamp: now - Duration::minutes(15),
                 transaction_hash: "hash2".to_string(),
                 ledger_sequence: 2,
+                envelope: None,
+                soroban: None,
             },
         ];
 
@@ -701,6 +1656,8 @@ mod tests {
             timestamp: now,
             transaction_hash: "hash1".to_string(),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         }];
 
         // Test with zero baseline (should return error)
@@ -733,6 +1690,8 @@ mod tests {
             timestamp: now,
             transaction_hash: "hash1".to_string(),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         }];
 
         let spikes = detector.detect_spikes(&fee_data, baseline).unwrap();
@@ -769,16 +1728,19 @@ mod tests {
                     name: "short_term".to_string(),
                     duration: Duration::hours(24), // Large window to include all points
                     min_samples: 1,
+                    averaging: AveragingMethod::Simple,
                 },
                 TimeWindow {
                     name: "medium_term".to_string(),
                     duration: Duration::hours(24),
                     min_samples: 1,
+                    averaging: AveragingMethod::Simple,
                 },
                 TimeWindow {
                     name: "long_term".to_string(),
                     duration: Duration::hours(24),
                     min_samples: 1,
+                    averaging: AveragingMethod::Simple,
                 },
             ];
 
@@ -831,6 +1793,10 @@ mod tests {
                 threshold_multiplier: 1.5, // Lower threshold to catch more spikes
                 minimum_spike_duration: Duration::seconds(1),
                 congestion_window: Duration::hours(1),
+                capacity_congestion_threshold: 0.9,
+                states: Default::default(),
+                signals: Default::default(),
+                min_samples: 1,
             };
             let detector = CongestionDetector::new(config);
 
@@ -858,6 +1824,8 @@ mod tests {
                     timestamp: Utc::now() - Duration::minutes(30),
                     transaction_hash: "valid_hash".to_string(),
                     ledger_sequence: 1,
+                    envelope: None,
+                    soroban: None,
                 }
             ];
 
@@ -896,24 +1864,32 @@ mod tests {
                 timestamp: now - Duration::minutes(60),
                 transaction_hash: "hash1".to_string(),
                 ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 150,
                 timestamp: now - Duration::minutes(45),
                 transaction_hash: "hash2".to_string(),
                 ledger_sequence: 2,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 500, // Spike
                 timestamp: now - Duration::minutes(30),
                 transaction_hash: "hash3".to_string(),
                 ledger_sequence: 3,
+                envelope: None,
+                soroban: None,
             },
             FeeDataPoint {
                 fee_amount: 120,
                 timestamp: now - Duration::minutes(15),
                 transaction_hash: "hash4".to_string(),
                 ledger_sequence: 4,
+                envelope: None,
+                soroban: None,
             },
             // Recent point within the 5-min short_term window
             FeeDataPoint {
@@ -921,6 +1897,8 @@ mod tests {
                 timestamp: now - Duration::minutes(2),
                 transaction_hash: "hash5".to_string(),
                 ledger_sequence: 5,
+                envelope: None,
+                soroban: None,
             },
         ];
 
@@ -938,6 +1916,42 @@ mod tests {
         assert_eq!(update.data_points_processed, 5);
     }
 
+    #[test]
+    fn test_congestion_baseline_stays_per_transaction_under_operation_weighting() {
+        let mut config = InsightsConfig::default();
+        for window in &mut config.time_windows {
+            window.averaging = AveragingMethod::OperationWeighted;
+        }
+        let mut engine = FeeInsightsEngine::new(config);
+
+        // Ten-operation transactions paying a steady 1,000, 100 per operation
+        let now = Utc::now();
+        let batch = |first: u64| -> Vec<FeeDataPoint> {
+            (first..first + 5)
+                .map(|i| FeeDataPoint {
+                    fee_amount: 1_000,
+                    timestamp: now - Duration::seconds(60 - i as i64),
+                    transaction_hash: format!("hash_{}", i),
+                    ledger_sequence: i,
+                    envelope: Some(EnvelopeDetails {
+                        operation_count: 10,
+                        ..EnvelopeDetails::default()
+                    }),
+                    soroban: None,
+                })
+                .collect()
+        };
+        for first in [0, 5, 10] {
+            tokio_test::block_on(engine.process_fee_data(&batch(first))).unwrap();
+        }
+
+        let insights = engine.get_current_insights();
+        assert_eq!(insights.rolling_averages.medium_term.value, 100.0);
+        // Steady fees are no surge against a per-operation baseline
+        assert_eq!(engine.get_fee_ratio(), Some(1.0));
+        assert!(insights.congestion_trends.recent_spikes.is_empty());
+    }
+
     #[test]
     fn test_engine_processes_data_and_records_last_update() {
         // Replaces the deleted reset() test — verifies that process_fee_data
@@ -956,6 +1970,8 @@ mod tests {
             timestamp: now - Duration::minutes(30),
             transaction_hash: "hash1".to_string(),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         }];
 
         let _result = tokio_test::block_on(engine.process_fee_data(&fee_data));
@@ -964,4 +1980,605 @@ mod tests {
             "last_update set after processing"
         );
     }
+
+    fn fee_stats_with_p50(p50: u64) -> FeeStatsSnapshot {
+        FeeStatsSnapshot {
+            last_ledger: 1,
+            last_ledger_base_fee: 100,
+            ledger_capacity_usage: 0.5,
+            fee_charged: FeePercentiles {
+                p50,
+                ..FeePercentiles::default()
+            },
+            max_fee: FeePercentiles::default(),
+            captured_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_fee_stats_cross_check_flags_divergence() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        assert!(engine.get_fee_stats_cross_check().is_none());
+
+        let now = Utc::now();
+        let fee_data: Vec<FeeDataPoint> = (0..5)
+            .map(|i| FeeDataPoint {
+                fee_amount: 100,
+                timestamp: now - Duration::seconds(10 * (i + 1)),
+                transaction_hash: format!("hash{}", i),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        tokio_test::block_on(engine.process_fee_data(&fee_data)).unwrap();
+
+        engine.record_fee_stats(fee_stats_with_p50(110));
+        let check = engine.get_fee_stats_cross_check().unwrap();
+        assert!(check.consistent);
+        assert!((check.local_short_term_average - 100.0).abs() < 1e-9);
+
+        engine.record_fee_stats(fee_stats_with_p50(1000));
+        let check = engine.get_fee_stats_cross_check().unwrap();
+        assert!(!check.consistent);
+        assert!((check.divergence - 0.9).abs() < 1e-9);
+    }
+
+    fn ledger(sequence: u64, operation_count: u32) -> LedgerInfo {
+        LedgerInfo {
+            sequence,
+            closed_at: Utc::now(),
+            transaction_count: operation_count,
+            operation_count,
+            max_tx_set_size: 1000,
+            base_fee: 100,
+        }
+    }
+
+    #[test]
+    fn test_capacity_utilization_averages_recent_ledgers() {
+        let mut detector = CongestionDetector::new(SpikeConfig::default());
+        assert_eq!(detector.capacity_utilization(), None);
+
+        // Out of order and overlapping, as successive polls return them.
+        detector.record_ledgers(&[ledger(3, 900), ledger(2, 700)]);
+        detector.record_ledgers(&[ledger(3, 1000), ledger(1, 0)]);
+        assert!((detector.capacity_utilization().unwrap() - (1700.0 / 3000.0)).abs() < 1e-9);
+
+        // Only the most recent ledgers count.
+        let full: Vec<LedgerInfo> = (10..30).map(|seq| ledger(seq, 950)).collect();
+        detector.record_ledgers(&full);
+        assert!((detector.capacity_utilization().unwrap() - 0.95).abs() < 1e-9);
+        assert!(detector.is_congested());
+    }
+
+    #[test]
+    fn test_congestion_score_weighs_capacity_against_surge_pricing() {
+        let batch = |fee: u64| -> Vec<FeeDataPoint> {
+            (0..5)
+                .map(|i| FeeDataPoint {
+                    fee_amount: fee,
+                    timestamp: Utc::now() - Duration::seconds(i),
+                    transaction_hash: format!("hash{}_{}", fee, i),
+                    ledger_sequence: 1,
+                    envelope: None,
+                    soroban: None,
+                })
+                .collect()
+        };
+        let half_full = [ledger(1, 600), ledger(2, 600)];
+
+        // Two-thirds of the capacity threshold: 0.4 from capacity alone.
+        let mut detector = CongestionDetector::new(SpikeConfig::default());
+        assert_eq!(detector.congestion_score(), None);
+        detector.record_ledgers(&half_full);
+        detector.analyze_congestion(&batch(100), 100.0).unwrap();
+        assert!((detector.congestion_score().unwrap() - 0.4).abs() < 1e-9);
+        assert!(!detector.is_congested());
+
+        // Surge pricing on top of it tips the balance.
+        let trends = detector.analyze_congestion(&batch(250), 100.0).unwrap();
+        assert!((trends.congestion_score.unwrap() - 0.8).abs() < 1e-9);
+        assert!(matches!(trends.current_trend, TrendIndicator::Congested));
+
+        // Weighting capacity alone ignores the surge.
+        let mut capacity_only = CongestionDetector::new(SpikeConfig {
+            signals: CongestionSignalConfig {
+                capacity_weight: 1.0,
+                price_weight: 0.0,
+                score_threshold: 0.9,
+            },
+            ..SpikeConfig::default()
+        });
+        capacity_only.record_ledgers(&half_full);
+        capacity_only
+            .analyze_congestion(&batch(250), 100.0)
+            .unwrap();
+        assert!(!capacity_only.is_congested());
+    }
+
+    #[test]
+    fn test_congestion_state_machine_uses_hysteresis_and_dwell() {
+        let mut machine = CongestionStateMachine::new(CongestionStateConfig::default());
+        let t0 = Utc::now();
+
+        let entered = machine.observe(1.6, false, t0).unwrap();
+        assert_eq!(
+            (entered.from, entered.to),
+            (CongestionState::Normal, CongestionState::Elevated)
+        );
+        assert_eq!(entered.at, t0);
+
+        // Between the exit and enter thresholds: stays elevated.
+        assert!(machine
+            .observe(1.3, false, t0 + Duration::minutes(5))
+            .is_none());
+        assert_eq!(machine.state(), CongestionState::Elevated);
+
+        // Full ledgers force congestion, once the dwell time has passed.
+        let t1 = t0 + Duration::minutes(6);
+        let congested = machine.observe(1.0, true, t1).unwrap();
+        assert_eq!(congested.to, CongestionState::Congested);
+        assert!(congested.capacity_congested);
+
+        // Fees drop straight away, but the minimum dwell holds the state.
+        assert!(machine
+            .observe(1.0, false, t1 + Duration::minutes(1))
+            .is_none());
+        assert_eq!(machine.state(), CongestionState::Congested);
+
+        let recovered = machine
+            .observe(1.0, false, t1 + Duration::minutes(2))
+            .unwrap();
+        assert_eq!(
+            (recovered.from, recovered.to),
+            (CongestionState::Congested, CongestionState::Normal)
+        );
+    }
+
+    #[test]
+    fn test_engine_reports_congestion_state_transitions() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        let now = Utc::now();
+        let batch = |fee: u64, offset: i64| -> Vec<FeeDataPoint> {
+            (0..5)
+                .map(|i| FeeDataPoint {
+                    fee_amount: fee,
+                    timestamp: now - Duration::seconds(offset + i),
+                    transaction_hash: format!("hash{}_{}", offset, i),
+                    ledger_sequence: 1,
+                    envelope: None,
+                    soroban: None,
+                })
+                .collect()
+        };
+
+        let update = tokio_test::block_on(engine.process_fee_data(&batch(100, 600))).unwrap();
+        assert!(update.state_transitions.is_empty());
+
+        engine.record_ledgers(&[ledger(1, 980), ledger(2, 1000)]);
+        let update = tokio_test::block_on(engine.process_fee_data(&batch(100, 300))).unwrap();
+        assert_eq!(update.state_transitions.len(), 1);
+        assert_eq!(update.state_transitions[0].to, CongestionState::Congested);
+        assert_eq!(
+            update.insights.congestion_trends.congestion_state,
+            CongestionState::Congested
+        );
+    }
+
+    #[test]
+    fn test_engine_publishes_events_to_subscribers() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        let mut events = engine.subscribe_events();
+        let now = Utc::now();
+        let batch = |fee: u64, offset: i64| -> Vec<FeeDataPoint> {
+            (0..5)
+                .map(|i| FeeDataPoint {
+                    fee_amount: fee,
+                    timestamp: now - Duration::seconds(offset + i),
+                    transaction_hash: format!("hash{}_{}", offset, i),
+                    ledger_sequence: 1,
+                    envelope: None,
+                    soroban: None,
+                })
+                .collect()
+        };
+
+        tokio_test::block_on(engine.process_fee_data(&batch(100, 600))).unwrap();
+        assert!(events.try_recv().is_err());
+
+        engine.record_ledgers(&[ledger(1, 980), ledger(2, 1000)]);
+        tokio_test::block_on(engine.process_fee_data(&batch(100, 300))).unwrap();
+        match events.try_recv().unwrap() {
+            InsightEvent::CongestionEntered(transition) => {
+                assert_eq!(transition.to, CongestionState::Congested)
+            }
+            other => panic!("expected congestion to be entered, got {:?}", other),
+        }
+
+        tokio_test::block_on(engine.process_fee_data(&batch(5_000, 60))).unwrap();
+        let mut surges = std::iter::from_fn(|| events.try_recv().ok())
+            .filter(|event| matches!(event, InsightEvent::SurgeStarted { .. }));
+        assert!(surges.next().is_some());
+        assert!(surges.next().is_none());
+    }
+
+    #[test]
+    fn test_full_ledgers_mark_congestion_without_fee_spikes() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        let now = Utc::now();
+        let fee_data: Vec<FeeDataPoint> = (0..5)
+            .map(|i| FeeDataPoint {
+                fee_amount: 100,
+                timestamp: now - Duration::seconds(10 * (i + 1)),
+                transaction_hash: format!("hash{}", i),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+
+        let update = tokio_test::block_on(engine.process_fee_data(&fee_data)).unwrap();
+        assert!(matches!(
+            update.insights.congestion_trends.current_trend,
+            TrendIndicator::Normal
+        ));
+
+        engine.record_ledgers(&[ledger(1, 980), ledger(2, 1000)]);
+        let update = tokio_test::block_on(engine.process_fee_data(&fee_data)).unwrap();
+        let trends = update.insights.congestion_trends;
+        assert!(matches!(trends.current_trend, TrendIndicator::Congested));
+        assert!(trends.recent_spikes.is_empty());
+        assert!((trends.capacity_utilization.unwrap() - 0.99).abs() < 1e-9);
+        assert!(matches!(
+            engine.get_congestion_trends().current_trend,
+            TrendIndicator::Congested
+        ));
+    }
+
+    #[test]
+    fn test_fee_recommendation_bids_higher_under_congestion() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+
+        let empty = engine.get_fee_recommendation();
+        assert_eq!(
+            (empty.economy, empty.standard, empty.priority),
+            (100, 100, 100)
+        );
+        assert!(empty.time_window.is_none());
+
+        // Fees 1000, 1050, ..., 1950: spread out, but no spikes.
+        let now = Utc::now();
+        let fee_data: Vec<FeeDataPoint> = (0..20u64)
+            .map(|i| FeeDataPoint {
+                fee_amount: 1000 + i * 50,
+                timestamp: now - Duration::seconds(10 * (i as i64 + 1)),
+                transaction_hash: format!("hash{}", i),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        tokio_test::block_on(engine.process_fee_data(&fee_data)).unwrap();
+
+        let calm = engine.get_fee_recommendation();
+        assert!(matches!(calm.congestion, TrendIndicator::Normal));
+        assert_eq!(
+            (calm.economy, calm.standard, calm.priority),
+            (1200, 1450, 1850)
+        );
+        assert_eq!(calm.time_window.as_deref(), Some("short_term"));
+
+        engine.record_ledgers(&[ledger(1, 980), ledger(2, 1000)]);
+        tokio_test::block_on(engine.process_fee_data(&fee_data)).unwrap();
+
+        let congested = engine.get_fee_recommendation();
+        assert!(matches!(congested.congestion, TrendIndicator::Congested));
+        assert_eq!(
+            (congested.economy, congested.standard, congested.priority),
+            (1450, 1850, 1950)
+        );
+    }
+
+    #[test]
+    fn test_seeded_seasonality_sets_seasonal_ratio() {
+        use chrono::{Datelike, Timelike};
+
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        let now = Utc::now();
+        let fee_data: Vec<FeeDataPoint> = (0..10u64)
+            .map(|i| FeeDataPoint {
+                fee_amount: 300,
+                timestamp: now - Duration::seconds(i as i64),
+                transaction_hash: format!("hash{}", i),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+
+        // No history for this hour yet
+        assert!(FeeInsightsEngine::new(InsightsConfig::default())
+            .get_congestion_trends()
+            .seasonal_ratio
+            .is_none());
+
+        engine.seed_seasonality(&[SeasonalSlot {
+            weekday: now.weekday(),
+            hour: now.hour(),
+            count: 100,
+            total_fee: 10_000,
+        }]);
+        let update = tokio_test::block_on(engine.process_fee_data(&fee_data)).unwrap();
+
+        assert_eq!(update.insights.congestion_trends.seasonal_ratio, Some(3.0));
+        assert!(!engine.get_seasonality_profile().by_weekday_hour.is_empty());
+    }
+
+    #[test]
+    fn test_inclusion_estimate_replays_bid_against_recent_ledgers() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        assert!(engine.estimate_inclusion(100).within_1_ledger.is_none());
+
+        // Every fourth ledger clears at 100; the rest need 400.
+        let now = Utc::now();
+        let fee_data: Vec<FeeDataPoint> = (0..40u64)
+            .map(|i| FeeDataPoint {
+                fee_amount: if i % 4 == 0 { 100 } else { 400 },
+                timestamp: now - Duration::seconds(5 * (40 - i as i64)),
+                transaction_hash: format!("hash{}", i),
+                ledger_sequence: i + 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        tokio_test::block_on(engine.process_fee_data(&fee_data)).unwrap();
+
+        let cheap = engine.estimate_inclusion(150);
+        assert_eq!(cheap.within_1_ledger, Some(0.25));
+        assert!(cheap.within_3_ledgers.unwrap() > 0.25);
+        assert_eq!(cheap.within_5_ledgers, Some(1.0));
+        assert_eq!(cheap.sample_ledgers, 40);
+
+        assert_eq!(engine.estimate_inclusion(400).within_1_ledger, Some(1.0));
+    }
+
+    #[test]
+    fn test_market_depth_counts_recent_bids_per_operation() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        let now = Utc::now();
+        // Ten ledgers of two bids each: 200 and 2_000 per operation
+        let fee_data: Vec<FeeDataPoint> = (0..20u64)
+            .map(|i| FeeDataPoint {
+                fee_amount: 100,
+                timestamp: now - Duration::seconds(5 * (20 - i as i64)),
+                transaction_hash: format!("hash{}", i),
+                ledger_sequence: i / 2 + 1,
+                envelope: Some(EnvelopeDetails {
+                    operation_count: 2,
+                    fee_bump: false,
+                    max_fee: if i % 2 == 0 { 400 } else { 4_000 },
+                    inner_fee: None,
+                    category: None,
+                    fee_account: None,
+                }),
+                soroban: None,
+            })
+            .collect();
+        tokio_test::block_on(engine.process_fee_data(&fee_data)).unwrap();
+
+        let depth = engine.get_market_depth(Some(500));
+        assert_eq!(depth.ledgers, 10);
+        assert_eq!(depth.bid_count, 20);
+        assert_eq!(depth.levels.first().unwrap().bid_at_least, 200);
+        assert_eq!(depth.levels.last().unwrap().bid_at_least, 2_000);
+        assert_eq!(depth.levels.last().unwrap().per_ledger, 1.0);
+        let position = depth.position.unwrap();
+        assert_eq!(position.outbid_by, 10);
+        assert_eq!(position.share_outbid, 0.5);
+    }
+
+    #[test]
+    fn test_soroban_estimate_adds_resource_fee_to_inclusion_bids() {
+        let engine = FeeInsightsEngine::new(InsightsConfig::default());
+        let resources = SorobanResources {
+            instructions: 40_000,
+            read_entries: 1,
+            transaction_size_bytes: 1_024,
+            ..SorobanResources::default()
+        };
+
+        let estimate = engine.estimate_soroban_fee(&resources);
+        // compute 100 + read entry 6,250 + history 20,992 + bandwidth 1,624
+        assert_eq!(estimate.resource_fee.total, 28_966);
+        assert_eq!(estimate.inclusion_fee.economy, 100);
+        assert_eq!(estimate.economy_total, 29_066);
+        assert_eq!(
+            estimate.priority_total,
+            estimate.inclusion_fee.priority + 28_966
+        );
+    }
+
+    /// Counts fees at or above a threshold; fails on batches marked "poison".
+    struct HighFeeCounter {
+        threshold: u64,
+        seen: u64,
+    }
+
+    impl InsightCalculator for HighFeeCounter {
+        fn name(&self) -> &str {
+            "acme.high_fees"
+        }
+
+        fn process(
+            &mut self,
+            data: &[FeeDataPoint],
+            insights: &CurrentInsights,
+        ) -> Result<serde_json::Value, InsightsError> {
+            if data.iter().any(|p| p.transaction_hash == "poison") {
+                return Err(InsightsError::calculation_error("poisoned batch"));
+            }
+            self.seen += data
+                .iter()
+                .filter(|p| p.fee_amount >= self.threshold)
+                .count() as u64;
+            Ok(serde_json::json!({
+                "seen": self.seen,
+                "samples": insights.rolling_averages.short_term.sample_count,
+            }))
+        }
+    }
+
+    #[test]
+    fn test_custom_calculators_publish_under_their_name() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        let counter = || {
+            Box::new(HighFeeCounter {
+                threshold: 500,
+                seen: 0,
+            })
+        };
+        engine.register_calculator(counter()).unwrap();
+        assert!(matches!(
+            engine.register_calculator(counter()),
+            Err(InsightsError::ConfigError { .. })
+        ));
+
+        let point = |fee_amount, hash: &str| FeeDataPoint {
+            fee_amount,
+            timestamp: Utc::now(),
+            transaction_hash: hash.to_string(),
+            ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
+        };
+        let update = tokio_test::block_on(engine.process_fee_data(&[
+            point(100, "a"),
+            point(600, "b"),
+            point(900, "c"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            update.insights.custom["acme.high_fees"],
+            serde_json::json!({ "seen": 2, "samples": 3 })
+        );
+        let json = serde_json::to_value(engine.get_current_insights()).unwrap();
+        assert_eq!(json["custom"]["acme.high_fees"]["seen"], 2);
+
+        // A failing calculator is dropped from the output, not the batch
+        let update =
+            tokio_test::block_on(engine.process_fee_data(&[point(700, "poison")])).unwrap();
+        assert!(update.insights.custom.is_empty());
+    }
+
+    #[test]
+    fn test_snapshots_are_due_once_per_interval() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        assert!(engine.take_due_snapshot().is_none());
+
+        let batch = |timestamp: chrono::DateTime<Utc>| {
+            vec![FeeDataPoint {
+                fee_amount: 100,
+                timestamp,
+                transaction_hash: timestamp.to_rfc3339(),
+                ledger_sequence: 1,
+                envelope: None,
+                soroban: None,
+            }]
+        };
+        tokio_test::block_on(engine.process_fee_data(&batch(Utc::now()))).unwrap();
+        let first = engine.take_due_snapshot().unwrap();
+        assert_eq!(Some(first.last_updated), engine.get_last_update());
+
+        // Within the interval nothing new is due
+        tokio_test::block_on(engine.process_fee_data(&batch(Utc::now()))).unwrap();
+        assert!(engine.take_due_snapshot().is_none());
+
+        let mut config = InsightsConfig::default();
+        config.snapshots.interval = Duration::zero();
+        let mut engine = FeeInsightsEngine::new(config);
+        tokio_test::block_on(engine.process_fee_data(&batch(Utc::now()))).unwrap();
+        assert!(engine.take_due_snapshot().is_some());
+        assert!(engine.take_due_snapshot().is_some());
+    }
+
+    #[test]
+    fn test_spike_config_updates_are_validated_and_applied() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        assert!(SpikeConfig::default().validate().is_ok());
+
+        let mut inverted = SpikeConfig::default();
+        inverted.states.elevated_exit_ratio = inverted.states.elevated_enter_ratio + 1.0;
+        assert!(matches!(
+            engine.update_spike_config(inverted),
+            Err(InsightsError::ConfigError { .. })
+        ));
+        assert_eq!(
+            engine
+                .get_config()
+                .spike_detection
+                .states
+                .elevated_exit_ratio,
+            1.2
+        );
+
+        // Batches below min_samples are not judged
+        let config = SpikeConfig {
+            min_samples: 5,
+            ..SpikeConfig::default()
+        };
+        engine.update_spike_config(config).unwrap();
+        let now = Utc::now();
+        let batch: Vec<FeeDataPoint> = (0..3u64)
+            .map(|i| FeeDataPoint {
+                fee_amount: 10_000,
+                timestamp: now - Duration::minutes(10 - i as i64 * 5),
+                transaction_hash: format!("hash{}", i),
+                ledger_sequence: i + 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        let mut detector = CongestionDetector::new(engine.get_config().spike_detection.clone());
+        let trends = detector.analyze_congestion(&batch, 100.0).unwrap();
+        assert!(trends.recent_spikes.is_empty());
+        assert_eq!(trends.congestion_state, CongestionState::Normal);
+
+        detector.set_config(SpikeConfig::default());
+        let trends = detector.analyze_congestion(&batch, 100.0).unwrap();
+        assert_eq!(trends.recent_spikes.len(), 1);
+    }
+
+    #[test]
+    fn test_reset_forgets_fees_but_keeps_config_and_subscribers() {
+        let mut engine = FeeInsightsEngine::new(InsightsConfig::default());
+        let config = SpikeConfig {
+            min_samples: 5,
+            ..SpikeConfig::default()
+        };
+        engine.update_spike_config(config).unwrap();
+        let mut batches = engine.subscribe_batches();
+        let batch: Vec<FeeDataPoint> = (0..3u64)
+            .map(|i| FeeDataPoint {
+                fee_amount: 100 * (i + 1),
+                timestamp: Utc::now(),
+                transaction_hash: format!("hash{}", i),
+                ledger_sequence: i + 1,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        tokio_test::block_on(engine.process_fee_data(&batch)).unwrap();
+        assert!(engine.get_last_update().is_some());
+
+        engine.reset();
+        assert!(engine.get_last_update().is_none());
+        assert_eq!(engine.get_rolling_averages().short_term.sample_count, 0);
+        assert_eq!(engine.get_config().spike_detection.min_samples, 5);
+
+        tokio_test::block_on(engine.process_fee_data(&batch[..1])).unwrap();
+        assert_eq!(engine.get_rolling_averages().short_term.sample_count, 1);
+        assert!(batches.try_recv().is_ok());
+        assert!(batches.try_recv().is_ok());
+    }
 }
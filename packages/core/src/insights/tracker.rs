@@ -1,6 +1,9 @@
 //! Extremes Tracker for min/max fee values
+//!
+//! Besides the fixed tracking period, extremes are kept since tracking began
+//! (all-time), over the last 24 hours and over each rolling time window.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use std::collections::VecDeque;
 
 use crate::insights::{config::ExtremesConfig, error::InsightsError, types::*};
@@ -25,11 +28,7 @@ impl ExtremePeriod {
     }
 
     fn update_with_fee(&mut self, fee_point: &FeeDataPoint) {
-        let extreme_value = ExtremeValue {
-            value: fee_point.fee_amount,
-            timestamp: fee_point.timestamp,
-            transaction_hash: fee_point.transaction_hash.clone(),
-        };
+        let extreme_value = ExtremeValue::from_point(fee_point);
 
         // Update minimum
         match &self.min_value {
@@ -59,17 +58,109 @@ impl ExtremePeriod {
                 current_max: max.clone(),
                 period_start: self.period_start,
                 period_end: self.period_end,
+                all_time: None,
+                last_24h: None,
+                by_window: Vec::new(),
             }),
             _ => None,
         }
     }
 }
 
+/// Min and max over a sliding time span.
+///
+/// Each side is a monotonic queue ordered by time: an entry is dropped once
+/// a later one is at least as extreme, since it can never be the extreme
+/// again. The front of each queue is the current extreme.
+#[derive(Debug, Clone)]
+struct RollingExtremes {
+    span: Duration,
+    min: VecDeque<ExtremeValue>,
+    max: VecDeque<ExtremeValue>,
+}
+
+impl RollingExtremes {
+    fn new(span: Duration) -> Self {
+        Self {
+            span,
+            min: VecDeque::new(),
+            max: VecDeque::new(),
+        }
+    }
+
+    fn update_with_fee(&mut self, fee_point: &FeeDataPoint) {
+        let value = ExtremeValue::from_point(fee_point);
+        push_monotonic(&mut self.min, value.clone(), |a, b| a <= b);
+        push_monotonic(&mut self.max, value, |a, b| a >= b);
+    }
+
+    fn expire(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - self.span;
+        for queue in [&mut self.min, &mut self.max] {
+            while queue.front().is_some_and(|e| e.timestamp < cutoff) {
+                queue.pop_front();
+            }
+        }
+    }
+
+    fn range(&self, now: DateTime<Utc>) -> Option<ExtremeRange> {
+        let cutoff = now - self.span;
+        let current =
+            |queue: &VecDeque<ExtremeValue>| queue.iter().find(|e| e.timestamp >= cutoff).cloned();
+        Some(ExtremeRange {
+            min: current(&self.min)?,
+            max: current(&self.max)?,
+        })
+    }
+}
+
+/// Insert `value` into a time-ordered monotonic queue, where
+/// `at_least_as_extreme(a, b)` compares fees.
+fn push_monotonic(
+    queue: &mut VecDeque<ExtremeValue>,
+    value: ExtremeValue,
+    at_least_as_extreme: impl Fn(u64, u64) -> bool,
+) {
+    // Points may arrive out of order; a later, more extreme entry already
+    // outlives this one.
+    if queue
+        .iter()
+        .any(|e| e.timestamp >= value.timestamp && at_least_as_extreme(e.value, value.value))
+    {
+        return;
+    }
+    queue
+        .retain(|e| !(e.timestamp <= value.timestamp && at_least_as_extreme(value.value, e.value)));
+    let position = queue.partition_point(|e| e.timestamp <= value.timestamp);
+    queue.insert(position, value);
+}
+
+/// Keep whichever of `current` and `candidate` is more extreme, preferring
+/// the most recent on ties.
+fn keep_extreme(
+    current: &mut Option<ExtremeValue>,
+    candidate: &ExtremeValue,
+    more_extreme: impl Fn(u64, u64) -> bool,
+) {
+    let replace = current.as_ref().is_none_or(|e| {
+        more_extreme(candidate.value, e.value)
+            || (candidate.value == e.value && candidate.timestamp >= e.timestamp)
+    });
+    if replace {
+        *current = Some(candidate.clone());
+    }
+}
+
 /// Tracker for minimum and maximum fee values
 pub struct ExtremesTracker {
     config: ExtremesConfig,
     current_period: ExtremePeriod,
     historical_periods: VecDeque<ExtremePeriod>,
+    all_time_min: Option<ExtremeValue>,
+    all_time_max: Option<ExtremeValue>,
+    last_24h: RollingExtremes,
+    /// Extremes per rolling window, by window name.
+    windows: Vec<(String, RollingExtremes)>,
 }
 
 impl ExtremesTracker {
@@ -83,9 +174,36 @@ impl ExtremesTracker {
             config,
             current_period: ExtremePeriod::new(period_start, period_end),
             historical_periods: VecDeque::new(),
+            all_time_min: None,
+            all_time_max: None,
+            last_24h: RollingExtremes::new(Duration::hours(24)),
+            windows: Vec::new(),
         }
     }
 
+    /// Also track extremes over each of `time_windows`.
+    pub fn with_windows(mut self, time_windows: &[TimeWindow]) -> Self {
+        self.windows = time_windows
+            .iter()
+            .map(|window| (window.name.clone(), RollingExtremes::new(window.duration)))
+            .collect();
+        self
+    }
+
+    /// Extremes since tracking began, or `None` before any fee was seen.
+    pub fn all_time(&self) -> Option<ExtremeRange> {
+        Some(ExtremeRange {
+            min: self.all_time_min.clone()?,
+            max: self.all_time_max.clone()?,
+        })
+    }
+
+    /// Merge all-time extremes persisted by an earlier run.
+    pub fn restore_all_time(&mut self, extremes: &ExtremeRange) {
+        keep_extreme(&mut self.all_time_min, &extremes.min, |a, b| a < b);
+        keep_extreme(&mut self.all_time_max, &extremes.max, |a, b| a > b);
+    }
+
     /// Update with new fee data
     pub fn update_with_fees(&mut self, fees: &[FeeDataPoint]) -> Result<(), InsightsError> {
         let now = Utc::now();
@@ -95,6 +213,20 @@ impl ExtremesTracker {
             self.rotate_period(now)?;
         }
 
+        for fee_point in fees {
+            let value = ExtremeValue::from_point(fee_point);
+            keep_extreme(&mut self.all_time_min, &value, |a, b| a < b);
+            keep_extreme(&mut self.all_time_max, &value, |a, b| a > b);
+            self.last_24h.update_with_fee(fee_point);
+            for (_, window) in &mut self.windows {
+                window.update_with_fee(fee_point);
+            }
+        }
+        self.last_24h.expire(now);
+        for (_, window) in &mut self.windows {
+            window.expire(now);
+        }
+
         // Update current period with new fees
         for fee_point in fees {
             // Only process fees that are within the current tracking period
@@ -128,8 +260,23 @@ impl ExtremesTracker {
 
     /// Get current extremes
     pub fn get_current_extremes(&self) -> Result<FeeExtremes, InsightsError> {
-        self.current_period.to_fee_extremes().ok_or_else(|| {
+        let mut extremes = self.current_period.to_fee_extremes().ok_or_else(|| {
             InsightsError::insufficient_data("No fee data available for current period")
-        })
+        })?;
+
+        let now = Utc::now();
+        extremes.all_time = self.all_time();
+        extremes.last_24h = self.last_24h.range(now);
+        extremes.by_window = self
+            .windows
+            .iter()
+            .filter_map(|(name, window)| {
+                Some(WindowExtremes {
+                    window: name.clone(),
+                    extremes: window.range(now)?,
+                })
+            })
+            .collect();
+        Ok(extremes)
     }
 }
@@ -0,0 +1,198 @@
+//! Fee trend direction
+//!
+//! Fits a least-squares line through a window's fees over time. The slope,
+//! projected across the window and taken relative to the mean fee, decides
+//! whether fees are rising, falling or stable. The fit needs only running
+//! sums, which `TrendSums` keeps as points enter and leave a window.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::insights::{config::TrendConfig, types::*};
+
+/// Least-squares sums of fees over time, in milliseconds from `origin`
+///
+/// Integer sums stay exact however many points come and go, so the fit
+/// never drifts. Sums sharing an origin can be added and subtracted.
+#[derive(Debug, Clone)]
+pub struct TrendSums {
+    origin: DateTime<Utc>,
+    count: i128,
+    sum_x: i128,
+    sum_xx: i128,
+    sum_y: i128,
+    sum_xy: i128,
+}
+
+impl TrendSums {
+    /// Empty sums measuring time from `origin`.
+    pub fn new(origin: DateTime<Utc>) -> Self {
+        Self {
+            origin,
+            count: 0,
+            sum_x: 0,
+            sum_xx: 0,
+            sum_y: 0,
+            sum_xy: 0,
+        }
+    }
+
+    fn sample(&self, point: &FeeDataPoint) -> (i128, i128) {
+        let x = (point.timestamp - self.origin).num_milliseconds() as i128;
+        (x, point.fee_amount as i128)
+    }
+
+    pub fn insert(&mut self, point: &FeeDataPoint) {
+        let (x, y) = self.sample(point);
+        self.count += 1;
+        self.sum_x += x;
+        self.sum_xx += x * x;
+        self.sum_y += y;
+        self.sum_xy += x * y;
+    }
+
+    /// Forget one earlier `insert` of `point`.
+    pub fn remove(&mut self, point: &FeeDataPoint) {
+        let (x, y) = self.sample(point);
+        self.count -= 1;
+        self.sum_x -= x;
+        self.sum_xx -= x * x;
+        self.sum_y -= y;
+        self.sum_xy -= x * y;
+    }
+
+    /// Forget every point summed in `other`, which shares this origin.
+    pub fn subtract(&mut self, other: &Self) {
+        self.count -= other.count;
+        self.sum_x -= other.sum_x;
+        self.sum_xx -= other.sum_xx;
+        self.sum_y -= other.sum_y;
+        self.sum_xy -= other.sum_xy;
+    }
+}
+
+/// Labels windows of fees as rising, falling or stable
+#[derive(Debug, Clone)]
+pub struct FeeTrendAnalyzer {
+    config: TrendConfig,
+}
+
+impl FeeTrendAnalyzer {
+    /// Create a new trend analyzer
+    pub fn new(config: TrendConfig) -> Self {
+        Self { config }
+    }
+
+    /// Trend of the points summed in `sums` across a window of `window`
+    /// length, or `None` with too few samples or when they all share one
+    /// timestamp.
+    pub fn classify(&self, sums: &TrendSums, window: Duration) -> Option<FeeTrend> {
+        if sums.count < self.config.min_samples.max(2) as i128 {
+            return None;
+        }
+
+        // n·Σxy − Σx·Σy and n·Σx² − (Σx)², both exact in integers
+        let covariance = sums.count * sums.sum_xy - sums.sum_x * sums.sum_y;
+        let variance = sums.count * sums.sum_xx - sums.sum_x * sums.sum_x;
+        if variance <= 0 {
+            return None;
+        }
+
+        let slope_per_minute = covariance as f64 / variance as f64 * 60_000.0;
+        let mean_y = sums.sum_y as f64 / sums.count as f64;
+        let window_minutes = window.num_milliseconds() as f64 / 60_000.0;
+        let relative_change = if mean_y > 0.0 {
+            slope_per_minute * window_minutes / mean_y
+        } else {
+            0.0
+        };
+
+        let direction = if relative_change >= self.config.min_relative_change {
+            TrendDirection::Rising
+        } else if relative_change <= -self.config.min_relative_change {
+            TrendDirection::Falling
+        } else {
+            TrendDirection::Stable
+        };
+
+        Some(FeeTrend {
+            direction,
+            slope_per_minute,
+            relative_change,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn points(fees: &[u64]) -> Vec<FeeDataPoint> {
+        let start = Utc::now() - Duration::minutes(fees.len() as i64);
+        fees.iter()
+            .enumerate()
+            .map(|(i, &fee_amount)| FeeDataPoint {
+                fee_amount,
+                timestamp: start + Duration::minutes(i as i64),
+                transaction_hash: format!("hash_{}", i),
+                ledger_sequence: i as u64,
+                envelope: None,
+                soroban: None,
+            })
+            .collect()
+    }
+
+    fn analyze(config: TrendConfig, fees: &[u64]) -> Option<FeeTrend> {
+        let points = points(fees);
+        let mut sums = TrendSums::new(Utc::now());
+        points.iter().for_each(|point| sums.insert(point));
+        FeeTrendAnalyzer::new(config).classify(&sums, Duration::minutes(10))
+    }
+
+    fn direction(config: TrendConfig, fees: &[u64]) -> Option<TrendDirection> {
+        analyze(config, fees).map(|trend| trend.direction)
+    }
+
+    #[test]
+    fn slope_over_the_window_sets_the_direction() {
+        let config = TrendConfig::default();
+        assert_eq!(
+            direction(config.clone(), &[100, 120, 140, 160, 180]),
+            Some(TrendDirection::Rising)
+        );
+        assert_eq!(
+            direction(config.clone(), &[180, 160, 140, 120, 100]),
+            Some(TrendDirection::Falling)
+        );
+        assert_eq!(
+            direction(config.clone(), &[100, 101, 99, 100, 101]),
+            Some(TrendDirection::Stable)
+        );
+        assert_eq!(direction(config, &[100, 200]), None);
+    }
+
+    #[test]
+    fn sensitivity_is_configurable() {
+        // 1 stroop a minute on 100 is 10% over the 10-minute window.
+        let fees = [100, 101, 102, 103, 104];
+        let strict = TrendConfig {
+            min_relative_change: 0.5,
+            ..TrendConfig::default()
+        };
+        let sensitive = TrendConfig {
+            min_relative_change: 0.05,
+            ..TrendConfig::default()
+        };
+
+        assert_eq!(direction(strict, &fees), Some(TrendDirection::Stable));
+        assert_eq!(direction(sensitive, &fees), Some(TrendDirection::Rising));
+    }
+
+    #[test]
+    fn trend_reports_slope_and_relative_change() {
+        let trend = analyze(TrendConfig::default(), &[100, 110, 120, 130, 140]).unwrap();
+
+        assert!((trend.slope_per_minute - 10.0).abs() < 1e-9);
+        assert!((trend.relative_change - 10.0 * 10.0 / 120.0).abs() < 1e-9);
+    }
+}
@@ -1,7 +1,8 @@
 //! Core data types for fee insights
 
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Datelike, Duration, Utc, Weekday};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// A single fee data point from the blockchain
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,6 +11,177 @@ pub struct FeeDataPoint {
     pub timestamp: DateTime<Utc>,
     pub transaction_hash: String,
     pub ledger_sequence: u64,
+    /// Decoded from the transaction envelope XDR when the source provides it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub envelope: Option<EnvelopeDetails>,
+    /// Resource usage and fee split; set only for Soroban transactions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soroban: Option<SorobanFeeDetail>,
+}
+
+impl FeeDataPoint {
+    /// `true` when the transaction is known to be wrapped in a fee bump.
+    pub fn is_fee_bump(&self) -> bool {
+        self.envelope.as_ref().is_some_and(|e| e.fee_bump)
+    }
+
+    /// Fee bid of the inner transaction, for fee bumps.
+    #[allow(dead_code)]
+    pub fn inner_fee(&self) -> Option<u64> {
+        self.envelope.as_ref().and_then(|e| e.inner_fee)
+    }
+
+    /// Operations the fee was charged for. A fee bump counts as one extra
+    /// operation, as it does in the protocol's fee calculation.
+    pub fn operation_count(&self) -> Option<u32> {
+        self.envelope
+            .as_ref()
+            .map(|e| e.operation_count + u32::from(e.fee_bump))
+            .filter(|&count| count > 0)
+    }
+
+    /// Fee charged per operation, when the operation count is known.
+    pub fn fee_per_operation(&self) -> Option<f64> {
+        self.operation_count()
+            .map(|count| self.fee_amount as f64 / count as f64)
+    }
+
+    /// Maximum fee bid, when the envelope was decoded.
+    pub fn max_fee(&self) -> Option<u64> {
+        self.envelope
+            .as_ref()
+            .map(|e| e.max_fee)
+            .filter(|&max_fee| max_fee > 0)
+    }
+
+    /// Dominant operation type, when the envelope was decoded.
+    pub fn operation_category(&self) -> Option<OperationCategory> {
+        self.envelope.as_ref().and_then(|e| e.category)
+    }
+}
+
+/// Transaction details parsed from the envelope XDR
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeDetails {
+    /// Operations in the (inner) transaction.
+    pub operation_count: u32,
+    /// `true` when the transaction was wrapped in a fee-bump envelope.
+    pub fee_bump: bool,
+    /// Maximum fee bid in stroops (the outer, sponsoring bid for fee bumps).
+    pub max_fee: u64,
+    /// Fee bid of the inner transaction; set only for fee bumps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inner_fee: Option<u64>,
+    /// Most common operation type; only known from the envelope XDR.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<OperationCategory>,
+    /// Account that paid the fee — the fee source for fee bumps — as a
+    /// `G…` address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_account: Option<String>,
+}
+
+/// Kind of traffic a transaction belongs to, by its operations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationCategory {
+    Payment,
+    PathPayment,
+    /// Creating, updating or deleting DEX offers.
+    OfferManagement,
+    ContractInvoke,
+    Other,
+}
+
+impl OperationCategory {
+    /// Every category, in reporting order.
+    pub const ALL: [OperationCategory; 5] = [
+        Self::Payment,
+        Self::PathPayment,
+        Self::OfferManagement,
+        Self::ContractInvoke,
+        Self::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Payment => "payment",
+            Self::PathPayment => "path_payment",
+            Self::OfferManagement => "offer_management",
+            Self::ContractInvoke => "contract_invoke",
+            Self::Other => "other",
+        }
+    }
+
+    /// Parse the value stored by [`OperationCategory::as_str`].
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "payment" => Some(Self::Payment),
+            "path_payment" => Some(Self::PathPayment),
+            "offer_management" => Some(Self::OfferManagement),
+            "contract_invoke" => Some(Self::ContractInvoke),
+            "other" => Some(Self::Other),
+            _ => None,
+        }
+    }
+}
+
+/// Resources and resource fees of a Soroban transaction, in stroops
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SorobanFeeDetail {
+    /// CPU instructions declared in the transaction's resources.
+    pub instructions: u32,
+    pub disk_read_bytes: u32,
+    pub write_bytes: u32,
+    /// Ledger entries in the footprint, read-only and read-write.
+    pub read_entries: u32,
+    /// Read-write ledger entries in the footprint.
+    pub write_entries: u32,
+    /// Resource fee bid in the envelope.
+    pub resource_fee_bid: u64,
+    /// Charged fee for instructions, bytes and entries. The charged
+    /// amounts are only known when the source provides the result meta.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub non_refundable_fee: Option<u64>,
+    /// Charged fee for events, return value and rent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refundable_fee: Option<u64>,
+    /// Rent (entry TTL extension) part of the refundable fee.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rent_fee: Option<u64>,
+}
+
+impl SorobanFeeDetail {
+    /// Charged resource fee, falling back to the bid when the split is unknown.
+    pub fn resource_fee(&self) -> u64 {
+        match (self.non_refundable_fee, self.refundable_fee) {
+            (Some(non_refundable), Some(refundable)) => non_refundable + refundable,
+            _ => self.resource_fee_bid,
+        }
+    }
+}
+
+/// A closed ledger and how full it was
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerInfo {
+    pub sequence: u64,
+    pub closed_at: DateTime<Utc>,
+    /// Successful plus failed transactions in the ledger.
+    pub transaction_count: u32,
+    /// Operations in the ledger's transaction set, including failed ones.
+    pub operation_count: u32,
+    /// Maximum operations the transaction set may hold.
+    pub max_tx_set_size: u32,
+    pub base_fee: u64,
+}
+
+impl LedgerInfo {
+    /// Fraction of the ledger's operation capacity that was used, or `None`
+    /// when the capacity is unknown.
+    pub fn capacity_utilization(&self) -> Option<f64> {
+        (self.max_tx_set_size > 0)
+            .then(|| self.operation_count as f64 / self.max_tx_set_size as f64)
+    }
 }
 
 /// Complete insights data structure
@@ -20,6 +192,54 @@ pub struct CurrentInsights {
     pub congestion_trends: CongestionTrends,
     pub last_updated: DateTime<Utc>,
     pub data_quality: DataQuality,
+    /// Statistical outliers in the latest batch; independent of congestion.
+    #[serde(default)]
+    pub anomalies: AnomalyReport,
+    /// Surge pricing status of the latest ledger; `None` before any
+    /// classic transaction has been seen.
+    #[serde(default)]
+    pub surge_pricing: Option<SurgePricing>,
+    /// Output of registered `InsightCalculator`s, keyed by their names.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, serde_json::Value>,
+}
+
+/// Whether the network charges more than the base fee
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SurgePricing {
+    /// `true` while the effective fee exceeds the base fee.
+    pub active: bool,
+    /// Effective fee per operation over the base fee.
+    pub multiplier: f64,
+    /// Cheapest fee per operation charged in the ledger, in stroops.
+    pub effective_fee_per_operation: f64,
+    pub base_fee: u64,
+    /// Ledger the status was read from.
+    pub ledger_sequence: u64,
+    /// Close of the first ledger of the current surge; `None` when inactive.
+    pub active_since: Option<DateTime<Utc>>,
+}
+
+/// Outliers found in one batch of fees
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyReport {
+    /// `true` when the batch as a whole deviates from recent history.
+    pub is_anomaly: bool,
+    /// Robust z-score of the batch median; `None` until enough history.
+    pub window_score: Option<f64>,
+    /// Individual fees that deviate from recent history.
+    pub anomalies: Vec<FeeAnomaly>,
+}
+
+/// A fee that deviated abnormally from recent history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeAnomaly {
+    pub transaction_hash: String,
+    pub fee_amount: u64,
+    pub timestamp: DateTime<Utc>,
+    /// Robust z-score: distance from `median_fee` in scaled MADs.
+    pub score: f64,
+    pub median_fee: f64,
 }
 
 /// Rolling averages across different time windows
@@ -38,6 +258,388 @@ pub struct AverageResult {
     pub is_partial: bool,
     pub calculated_at: DateTime<Utc>,
     pub time_window: TimeWindow,
+    /// Fee-bump transactions among the samples.
+    #[serde(default)]
+    pub fee_bump_count: usize,
+    /// Share (0.0–1.0) of the window's total fees charged on fee bumps.
+    #[serde(default)]
+    pub fee_bump_volume_share: f64,
+    /// Mean fee per operation over samples with a known operation count;
+    /// `None` when no sample has one.
+    #[serde(default)]
+    pub avg_fee_per_operation: Option<f64>,
+    /// Resource averages over the Soroban samples; `None` when there are none.
+    #[serde(default)]
+    pub soroban: Option<SorobanResourceAverages>,
+    /// Distribution of charged fees in the window; `None` when it is empty.
+    #[serde(default)]
+    pub percentiles: Option<FeeDistribution>,
+    /// Fee counts per bucket; `None` when the window is empty.
+    #[serde(default)]
+    pub histogram: Option<FeeHistogram>,
+    /// Stats per operation category, for samples whose category is known.
+    #[serde(default)]
+    pub by_category: Vec<CategoryFeeStats>,
+    /// Spread of the window's fees; `None` when it is empty.
+    #[serde(default)]
+    pub volatility: Option<FeeVolatility>,
+    /// Direction fees are moving in; `None` with too few samples.
+    #[serde(default)]
+    pub trend: Option<FeeTrend>,
+    /// How far bids exceed charged fees; `None` when no sample has a bid.
+    #[serde(default)]
+    pub bid_spread: Option<BidSpread>,
+}
+
+/// Fitted direction of fees within a window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeeTrend {
+    pub direction: TrendDirection,
+    /// Least-squares slope, in stroops per minute.
+    pub slope_per_minute: f64,
+    /// Slope projected across the window, relative to the mean fee.
+    pub relative_change: f64,
+}
+
+/// Whether fees are going up, down or nowhere
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrendDirection {
+    Rising,
+    Falling,
+    Stable,
+}
+
+/// How much fees in a window move around their centre
+///
+/// Fee distributions are heavy-tailed, so the median and its absolute
+/// deviation are given alongside the mean-based spread.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeeVolatility {
+    /// Population standard deviation, in stroops.
+    pub std_dev: f64,
+    /// Standard deviation over the mean, comparable across fee levels;
+    /// `None` when the mean fee is zero.
+    pub coefficient_of_variation: Option<f64>,
+    /// Middle fee, averaging the two middle fees of an even count.
+    #[serde(default)]
+    pub median: f64,
+    /// Median distance of the fees from `median`, in stroops.
+    #[serde(default)]
+    pub median_absolute_deviation: f64,
+}
+
+/// Gap between fee bids (`max_fee`) and the fees actually charged
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BidSpread {
+    /// Samples with a known bid.
+    pub sample_count: usize,
+    pub average_bid: f64,
+    pub average_charged: f64,
+    /// Mean of bid minus charged fee, in stroops.
+    pub mean_spread: f64,
+    pub median_spread: u64,
+    /// Total bids over total charged fees; 1.0 means bids were spent in full.
+    pub bid_to_charged_ratio: f64,
+    /// Share (0.0–1.0) of samples that bid more than they were charged.
+    pub overbidding_rate: f64,
+    /// Fee waste: bids minus charged fees summed over the samples, in
+    /// stroops. What users authorised beyond what the network took.
+    #[serde(default)]
+    pub total_waste: u64,
+    /// Share (0.0–1.0) of the total bid that went unspent.
+    #[serde(default)]
+    pub waste_share: f64,
+}
+
+/// Fees of one operation category within a window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryFeeStats {
+    pub category: OperationCategory,
+    pub sample_count: usize,
+    pub average_fee: f64,
+    /// Mean fee per operation; `None` when no sample has an operation count.
+    pub avg_fee_per_operation: Option<f64>,
+    pub percentiles: FeeDistribution,
+}
+
+/// Fees charged within a window, bucketed for charting
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeHistogram {
+    /// Buckets in ascending fee order.
+    pub buckets: Vec<HistogramBucket>,
+}
+
+/// Fees in `[lower, upper)`, in stroops
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    pub lower: u64,
+    /// `None` for the last, unbounded bucket.
+    pub upper: Option<u64>,
+    pub count: usize,
+}
+
+/// Percentiles of the fees charged within a window, in stroops
+///
+/// Nearest-rank: each value is a fee that was actually charged.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeDistribution {
+    pub p10: u64,
+    pub p25: u64,
+    pub p50: u64,
+    pub p75: u64,
+    pub p90: u64,
+    pub p95: u64,
+    pub p99: u64,
+}
+
+impl FeeDistribution {
+    /// The `p`th percentile, for the percentiles kept.
+    pub fn percentile(&self, p: u8) -> Option<u64> {
+        match p {
+            10 => Some(self.p10),
+            25 => Some(self.p25),
+            50 => Some(self.p50),
+            75 => Some(self.p75),
+            90 => Some(self.p90),
+            95 => Some(self.p95),
+            99 => Some(self.p99),
+            _ => None,
+        }
+    }
+}
+
+/// How soon a transaction needs to be included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Urgency {
+    Economy,
+    Standard,
+    Priority,
+}
+
+impl Urgency {
+    /// Percentile of recent fees bid at this urgency; higher up the
+    /// distribution as congestion builds.
+    pub fn percentile(self, congestion: &TrendIndicator) -> u8 {
+        match (congestion, self) {
+            (TrendIndicator::Normal | TrendIndicator::Declining, Urgency::Economy) => 25,
+            (TrendIndicator::Normal | TrendIndicator::Declining, Urgency::Standard) => 50,
+            (TrendIndicator::Normal | TrendIndicator::Declining, Urgency::Priority) => 90,
+            (TrendIndicator::Rising, Urgency::Economy) => 50,
+            (TrendIndicator::Rising, Urgency::Standard) => 75,
+            (TrendIndicator::Rising, Urgency::Priority) => 95,
+            (TrendIndicator::Congested, Urgency::Economy) => 50,
+            (TrendIndicator::Congested, Urgency::Standard) => 90,
+            (TrendIndicator::Congested, Urgency::Priority) => 99,
+        }
+    }
+
+    /// This urgency's bid in `recommendation`.
+    pub fn pick(self, recommendation: &FeeRecommendation) -> u64 {
+        match self {
+            Urgency::Economy => recommendation.economy,
+            Urgency::Standard => recommendation.standard,
+            Urgency::Priority => recommendation.priority,
+        }
+    }
+}
+
+/// Suggested fee bids for three inclusion urgencies, in stroops
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeRecommendation {
+    /// Likely included within a few ledgers while the network is calm.
+    pub economy: u64,
+    /// Likely included in the next ledger or two.
+    pub standard: u64,
+    /// Outbids nearly all recent transactions.
+    pub priority: u64,
+    /// Congestion state the bids were adjusted for.
+    pub congestion: TrendIndicator,
+    /// Window whose fee distribution the bids were taken from; `None` when
+    /// there were no samples and the network minimum was used.
+    pub time_window: Option<String>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Historical chance a fee bid would have been included within a few ledgers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InclusionEstimate {
+    /// Bid the estimate is for, in stroops.
+    pub fee: u64,
+    /// `None` until enough ledgers have been seen for that horizon.
+    pub within_1_ledger: Option<f64>,
+    pub within_3_ledgers: Option<f64>,
+    pub within_5_ledgers: Option<f64>,
+    /// Recent ledgers the estimate was replayed against.
+    pub sample_ledgers: usize,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Insights computed on demand over a stored time range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RangeInsights {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Every fee in the range as one window named `range`.
+    pub summary: AverageResult,
+    /// `None` when the range holds no fees.
+    pub min_fee: Option<u64>,
+    pub max_fee: Option<u64>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// How many recent bids sit at or above a fee level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DepthLevel {
+    /// Per-operation bid, in stroops.
+    pub bid_at_least: u64,
+    pub count: usize,
+    /// Mean count per observed ledger.
+    pub per_ledger: f64,
+    /// Share of all recent bids.
+    pub share: f64,
+}
+
+/// Where a given bid stands among recent bids
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BidPosition {
+    /// Per-operation bid, in stroops.
+    pub fee: u64,
+    /// Recent bids strictly higher than `fee`.
+    pub outbid_by: usize,
+    /// Mean higher bids per ledger; once this exceeds a ledger's capacity,
+    /// surge pricing leaves `fee` out.
+    pub outbid_per_ledger: f64,
+    pub share_outbid: f64,
+}
+
+/// Distribution of max fee bids over recent ledgers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketDepth {
+    /// Recent ledgers the bids were taken from.
+    pub ledgers: usize,
+    pub bid_count: usize,
+    /// Log-spaced from the lowest to the highest bid.
+    pub levels: Vec<DepthLevel>,
+    /// Set when a fee was asked about.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<BidPosition>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Resources a Soroban invocation is expected to use
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SorobanResources {
+    pub instructions: u32,
+    /// Footprint entries, read-only and read-write.
+    pub read_entries: u32,
+    /// Read-write footprint entries.
+    pub write_entries: u32,
+    pub disk_read_bytes: u32,
+    pub write_bytes: u32,
+    /// Size of the signed transaction envelope.
+    pub transaction_size_bytes: u32,
+    #[serde(default)]
+    pub contract_events_bytes: u32,
+    /// Rent to extend entry TTLs, when the caller has simulated it.
+    #[serde(default)]
+    pub rent_fee: u64,
+}
+
+/// Resource fee of a Soroban invocation, by component, in stroops
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SorobanResourceFee {
+    pub compute: u64,
+    pub read_entries: u64,
+    pub write_entries: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    pub historical: u64,
+    pub bandwidth: u64,
+    pub contract_events: u64,
+    pub rent: u64,
+    /// Charged whatever the invocation ends up using.
+    pub non_refundable: u64,
+    /// Events and rent; the unused part is refunded.
+    pub refundable: u64,
+    pub total: u64,
+}
+
+/// End-to-end cost of a Soroban invocation at each inclusion urgency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SorobanFeeEstimate {
+    pub resources: SorobanResources,
+    pub resource_fee: SorobanResourceFee,
+    /// Suggested inclusion bids from current fee insights.
+    pub inclusion_fee: FeeRecommendation,
+    /// Inclusion bid plus resource fee for each urgency.
+    pub economy_total: u64,
+    pub standard_total: u64,
+    pub priority_total: u64,
+}
+
+/// Typical fees by UTC hour and by weekday and hour
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeasonalityProfile {
+    /// One baseline per hour of day, across all weekdays.
+    pub by_hour: Vec<SeasonalBaseline>,
+    pub by_weekday_hour: Vec<SeasonalBaseline>,
+}
+
+/// Fees aggregated into one weekday and hour, e.g. from the database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeasonalSlot {
+    pub weekday: Weekday,
+    /// Hour of day, UTC.
+    pub hour: u32,
+    pub count: u64,
+    pub total_fee: u64,
+}
+
+/// Mean fee observed in one seasonal slot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeasonalBaseline {
+    /// `None` for baselines across all weekdays.
+    pub weekday: Option<Weekday>,
+    /// Hour of day, UTC.
+    pub hour: u32,
+    pub mean_fee: f64,
+    pub sample_count: u64,
+}
+
+/// Predicted mean fee at a point ahead, with a confidence interval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeForecast {
+    pub generated_at: DateTime<Utc>,
+    pub target_time: DateTime<Utc>,
+    pub horizon_minutes: i64,
+    /// Ledger closes between now and `target_time`, at the observed pace.
+    pub ledgers_ahead: u64,
+    /// Mean fee per transaction expected at `target_time`, in stroops.
+    pub predicted_fee: f64,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    /// Probability (0.0–1.0) the bounds are meant to cover.
+    pub confidence_level: f64,
+    /// Typical fee at `target_time` from seasonality, when known.
+    #[serde(default)]
+    pub seasonal_baseline: Option<f64>,
+    /// Ledgers the model was fitted on.
+    pub sample_ledgers: usize,
+}
+
+/// Mean Soroban resource usage and fees within a window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SorobanResourceAverages {
+    pub sample_count: usize,
+    pub instructions: f64,
+    pub disk_read_bytes: f64,
+    pub write_bytes: f64,
+    /// Charged resource fee (the bid where the charge is unknown).
+    pub resource_fee: f64,
+    /// Mean rent fee over the samples that report one.
+    pub rent_fee: Option<f64>,
 }
 
 /// Time window configuration
@@ -46,6 +648,59 @@ pub struct TimeWindow {
     pub name: String,
     pub duration: Duration,
     pub min_samples: usize,
+    /// How the window's `AverageResult::value` is computed.
+    #[serde(default)]
+    pub averaging: AveragingMethod,
+}
+
+/// Averaging applied to the fees within a time window
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum AveragingMethod {
+    /// Arithmetic mean of every sample in the window.
+    #[default]
+    Simple,
+    /// Exponentially weighted moving average in arrival order. Each new
+    /// sample gets weight `smoothing_factor` (0.0 < α ≤ 1.0); higher values
+    /// follow fee spikes more closely.
+    Exponential { smoothing_factor: f64 },
+    /// Total fees over total operations: each transaction's per-operation
+    /// fee weighted by its operation count, so multi-operation transactions
+    /// do not inflate what a simple payment appears to cost. Transactions
+    /// without a decoded envelope count as one operation. `OutlierFilter`
+    /// cuts on the whole fee before the rest are weighted.
+    OperationWeighted,
+}
+
+// `TimeWindow` keys the calculator's buffers, so the smoothing factor is
+// compared and hashed by its bit pattern.
+impl PartialEq for AveragingMethod {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (AveragingMethod::Simple, AveragingMethod::Simple)
+            | (AveragingMethod::OperationWeighted, AveragingMethod::OperationWeighted) => true,
+            (
+                AveragingMethod::Exponential {
+                    smoothing_factor: a,
+                },
+                AveragingMethod::Exponential {
+                    smoothing_factor: b,
+                },
+            ) => a.to_bits() == b.to_bits(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for AveragingMethod {}
+
+impl std::hash::Hash for AveragingMethod {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        if let AveragingMethod::Exponential { smoothing_factor } = self {
+            smoothing_factor.to_bits().hash(state);
+        }
+    }
 }
 
 /// Fee extremes (min/max) tracking
@@ -55,14 +710,52 @@ pub struct FeeExtremes {
     pub current_max: ExtremeValue,
     pub period_start: DateTime<Utc>,
     pub period_end: DateTime<Utc>,
+    /// Since tracking began, including extremes restored on startup.
+    #[serde(default)]
+    pub all_time: Option<ExtremeRange>,
+    /// Over the last 24 hours.
+    #[serde(default)]
+    pub last_24h: Option<ExtremeRange>,
+    /// Over each configured rolling window, in configuration order.
+    #[serde(default)]
+    pub by_window: Vec<WindowExtremes>,
 }
 
 /// An extreme fee value with metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtremeValue {
     pub value: u64,
     pub timestamp: DateTime<Utc>,
     pub transaction_hash: String,
+    #[serde(default)]
+    pub ledger_sequence: u64,
+}
+
+impl ExtremeValue {
+    /// The fee of `point`, with where and when it was charged.
+    pub fn from_point(point: &FeeDataPoint) -> Self {
+        Self {
+            value: point.fee_amount,
+            timestamp: point.timestamp,
+            transaction_hash: point.transaction_hash.clone(),
+            ledger_sequence: point.ledger_sequence,
+        }
+    }
+}
+
+/// Lowest and highest fee over some span
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtremeRange {
+    pub min: ExtremeValue,
+    pub max: ExtremeValue,
+}
+
+/// Extremes of one rolling window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowExtremes {
+    /// Name of the `TimeWindow`.
+    pub window: String,
+    pub extremes: ExtremeRange,
 }
 
 /// Congestion trend analysis results
@@ -72,6 +765,144 @@ pub struct CongestionTrends {
     pub recent_spikes: Vec<FeeSpike>,
     pub trend_strength: TrendStrength,
     pub predicted_duration: Option<Duration>,
+    /// Average capacity utilization of the most recent ledgers; `None` when
+    /// the provider does not report ledgers.
+    #[serde(default)]
+    pub capacity_utilization: Option<f64>,
+    /// Capacity and price pressure weighted per `CongestionSignalConfig`
+    /// (0.0–1.0); `None` when the provider does not report ledgers.
+    #[serde(default)]
+    pub congestion_score: Option<f64>,
+    /// Debounced congestion level; see `CongestionStateConfig`.
+    #[serde(default)]
+    pub congestion_state: CongestionState,
+    /// Short-term average over the typical fee for this hour and weekday;
+    /// `None` until enough history has been seen.
+    #[serde(default)]
+    pub seasonal_ratio: Option<f64>,
+}
+
+/// Congestion level tracked with hysteresis and a minimum dwell time
+///
+/// Ordered from calmest to most congested.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CongestionState {
+    #[default]
+    Normal,
+    Elevated,
+    Congested,
+}
+
+impl CongestionState {
+    /// Every state, from calmest to most congested.
+    pub const ALL: [CongestionState; 3] = [Self::Normal, Self::Elevated, Self::Congested];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Elevated => "elevated",
+            Self::Congested => "congested",
+        }
+    }
+
+    /// Parse the value stored by [`CongestionState::as_str`].
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|state| state.as_str() == value)
+    }
+}
+
+/// A change of `CongestionState`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CongestionTransition {
+    pub from: CongestionState,
+    pub to: CongestionState,
+    pub at: DateTime<Utc>,
+    /// Mean fee over baseline that triggered the change.
+    pub fee_ratio: f64,
+    /// `true` when the combined capacity and price score, rather than the
+    /// fee ratio alone, drove the change.
+    pub capacity_congested: bool,
+}
+
+/// Length of a fee budget's period. Periods start at UTC midnight; weeks
+/// start on Monday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetPeriod {
+    Daily,
+    Weekly,
+}
+
+impl BudgetPeriod {
+    pub const ALL: [BudgetPeriod; 2] = [Self::Daily, Self::Weekly];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+
+    /// Parse the value stored by [`BudgetPeriod::as_str`].
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|period| period.as_str() == value)
+    }
+
+    /// Start of the period containing `at`.
+    pub fn start(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let midnight = at.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default();
+        let day = DateTime::from_naive_utc_and_offset(midnight, Utc);
+        match self {
+            Self::Daily => day,
+            Self::Weekly => day - Duration::days(at.weekday().num_days_from_monday() as i64),
+        }
+    }
+}
+
+/// Fees an account may spend per period, in stroops
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeBudget {
+    pub account: String,
+    pub period: BudgetPeriod,
+    pub limit: u64,
+}
+
+/// An account's spend against its budget in the current period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub account: String,
+    pub period: BudgetPeriod,
+    pub period_start: DateTime<Utc>,
+    /// In stroops.
+    pub limit: u64,
+    pub spent: u64,
+    pub remaining: u64,
+    /// `spent` over `limit`.
+    pub utilization: f64,
+    pub exceeded: bool,
+}
+
+/// Something the engine noticed while processing fees, published to
+/// subscribers as it happens
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum InsightEvent {
+    /// Congestion moved to a more severe state.
+    CongestionEntered(CongestionTransition),
+    /// Congestion eased to a calmer state.
+    CongestionCleared(CongestionTransition),
+    /// A batch held statistical outliers.
+    AnomalyDetected(AnomalyReport),
+    /// Fees rose past the surge threshold.
+    SurgeStarted {
+        start_time: DateTime<Utc>,
+        baseline_fee: f64,
+    },
+    /// An account spent more than its fee budget for the period.
+    BudgetExceeded(BudgetStatus),
 }
 
 /// A detected fee spike
@@ -85,6 +916,33 @@ pub struct FeeSpike {
     pub severity: SpikeSeverity,
 }
 
+/// A period spent above `CongestionState::Normal`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CongestionEpisode {
+    pub id: i64,
+    pub start_time: DateTime<Utc>,
+    /// `None` while the episode is ongoing.
+    pub end_time: Option<DateTime<Utc>>,
+    /// Most congested state reached.
+    pub peak_state: CongestionState,
+    /// Highest mean fee over baseline seen during the episode.
+    pub peak_multiplier: f64,
+}
+
+/// A completed period of fees at or above a multiple of the baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SurgeEpisode {
+    pub start_time: DateTime<Utc>,
+    /// Time of the first fee back below the surge threshold.
+    pub end_time: DateTime<Utc>,
+    pub duration: Duration,
+    pub peak_fee: u64,
+    /// Baseline when the surge began; it is held for the whole episode.
+    pub baseline_fee: f64,
+    /// Fees at or above the threshold during the episode.
+    pub sample_count: usize,
+}
+
 /// Trend indicator for congestion
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TrendIndicator {
@@ -120,6 +978,52 @@ pub struct DataQuality {
     pub last_gap: Option<DateTime<Utc>>,
 }
 
+/// Fee distribution as reported by Horizon's `/fee_stats`, in stroops
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeePercentiles {
+    pub min: u64,
+    pub max: u64,
+    pub mode: u64,
+    pub p10: u64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p95: u64,
+    pub p99: u64,
+}
+
+/// Network-wide fee statistics for the most recent ledgers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeStatsSnapshot {
+    pub last_ledger: u64,
+    pub last_ledger_base_fee: u64,
+    /// Fraction of ledger capacity used, 0.0 to 1.0.
+    pub ledger_capacity_usage: f64,
+    /// Fees actually charged.
+    pub fee_charged: FeePercentiles,
+    /// Maximum fees bid.
+    pub max_fee: FeePercentiles,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Comparison of our rolling average against Horizon's `/fee_stats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeStatsCrossCheck {
+    pub fee_stats: FeeStatsSnapshot,
+    pub local_short_term_average: f64,
+    /// `|local - horizon p50| / horizon p50`.
+    pub divergence: f64,
+    /// `true` when `divergence` is within the configured tolerance.
+    pub consistent: bool,
+}
+
+/// A processed batch and the insights it produced, published to stream
+/// subscribers
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessedBatch {
+    pub points: Vec<FeeDataPoint>,
+    pub insights: CurrentInsights,
+}
+
 /// Update result from processing fee data
 #[derive(Debug, Clone)]
 pub struct InsightsUpdate {
@@ -127,4 +1031,11 @@ pub struct InsightsUpdate {
     #[allow(dead_code)]
     pub processing_time: Duration,
     pub data_points_processed: usize,
+    /// Congestion state changes caused by this update, oldest first.
+    pub state_transitions: Vec<CongestionTransition>,
+    /// Mean fee over baseline of this update's batch; `None` without a
+    /// baseline.
+    pub fee_ratio: Option<f64>,
+    /// Surge episodes that ended in this update, oldest first.
+    pub completed_surges: Vec<SurgeEpisode>,
 }
@@ -0,0 +1,417 @@
+//! Operational commands run on demand by administrators.
+//!
+//! Each command starts a [`Job`] that runs in the background; its id is
+//! returned straight away and its status polled from the [`JobRegistry`].
+//! Jobs live in memory only and the oldest finished ones are forgotten once
+//! [`JOB_HISTORY`] are kept.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+use crate::accounts::restore_budgets;
+use crate::alerts::AlertManager;
+use crate::backfill::{run_backfill, DEFAULT_CHUNK_LEDGERS};
+use crate::insights::{FeeDataProvider, FeeInsightsEngine};
+use crate::metrics::AppMetrics;
+use crate::repository::FeeRepository;
+use crate::rollup::roll_up_closed_buckets;
+use crate::scheduler::poll_once;
+use crate::store::FeeHistoryStore;
+
+/// Jobs kept for polling, finished or not.
+pub const JOB_HISTORY: usize = 100;
+
+/// History replayed into the engine when insights are recomputed.
+const RECOMPUTE_WINDOW_HOURS: i64 = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Backfill,
+    Fetch,
+    RecomputeInsights,
+    Purge,
+    RotateApiKey,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// One run of an operational command
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: u64,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// What the command did, once it has succeeded.
+    pub result: Option<Value>,
+    /// Why the command failed.
+    pub error: Option<String>,
+}
+
+/// The most recent jobs, newest last
+pub struct JobRegistry {
+    next_id: AtomicU64,
+    jobs: Mutex<VecDeque<Job>>,
+}
+
+impl Default for JobRegistry {
+    fn default() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            jobs: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start `kind` as a job running `work` in the background.
+    pub fn spawn<F>(self: &Arc<Self>, kind: JobKind, work: F) -> Job
+    where
+        F: Future<Output = Result<Value, String>> + Send + 'static,
+    {
+        let job = self.start(kind);
+        let registry = self.clone();
+        let id = job.id;
+        tokio::spawn(async move {
+            let outcome = work.await;
+            registry.finish(id, outcome);
+        });
+        job
+    }
+
+    /// Record `kind` as a job that has already ended with `outcome`.
+    pub fn record(&self, kind: JobKind, outcome: Result<Value, String>) -> Job {
+        let id = self.start(kind).id;
+        self.finish(id, outcome);
+        self.get(id).expect("a job just recorded is kept")
+    }
+
+    pub fn get(&self, id: u64) -> Option<Job> {
+        self.lock().iter().find(|job| job.id == id).cloned()
+    }
+
+    /// Every kept job, newest first.
+    pub fn list(&self) -> Vec<Job> {
+        self.lock().iter().rev().cloned().collect()
+    }
+
+    fn start(&self, kind: JobKind) -> Job {
+        let job = Job {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            kind,
+            status: JobStatus::Running,
+            started_at: Utc::now(),
+            finished_at: None,
+            result: None,
+            error: None,
+        };
+        let mut jobs = self.lock();
+        jobs.push_back(job.clone());
+        // Drop the oldest finished jobs first; running ones are still polled
+        while jobs.len() > JOB_HISTORY {
+            match jobs.iter().position(|j| j.status != JobStatus::Running) {
+                Some(oldest) => jobs.remove(oldest),
+                None => jobs.pop_front(),
+            };
+        }
+        job
+    }
+
+    fn finish(&self, id: u64, outcome: Result<Value, String>) {
+        let mut jobs = self.lock();
+        let Some(job) = jobs.iter_mut().find(|job| job.id == id) else {
+            return;
+        };
+        job.finished_at = Some(Utc::now());
+        match outcome {
+            Ok(result) => {
+                job.status = JobStatus::Succeeded;
+                job.result = Some(result);
+            }
+            Err(error) => {
+                tracing::warn!("Admin job {} ({:?}) failed: {}", id, job.kind, error);
+                job.status = JobStatus::Failed;
+                job.error = Some(error);
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<Job>> {
+        // A panic while holding the lock cannot leave the queue inconsistent
+        self.jobs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Everything the primary network's operational commands act on
+pub struct JobRunner {
+    pub jobs: Arc<JobRegistry>,
+    pub provider: Arc<dyn FeeDataProvider + Send + Sync>,
+    /// Provider for historical ledgers; `None` when none supports them.
+    pub backfill_provider: Option<Arc<dyn FeeDataProvider + Send + Sync>>,
+    pub fee_store: Arc<RwLock<FeeHistoryStore>>,
+    pub insights_engine: Arc<RwLock<FeeInsightsEngine>>,
+    pub repository: Arc<FeeRepository>,
+    pub retry_attempts: u32,
+    pub base_retry_delay_ms: u64,
+    pub storage_retention_days: u64,
+    pub metrics: Option<Arc<AppMetrics>>,
+    pub alert_manager: Option<Arc<AlertManager>>,
+}
+
+impl JobRunner {
+    /// Store every fee of `from_ledger..=to_ledger`.
+    pub fn backfill(&self, from_ledger: u64, to_ledger: u64) -> Job {
+        let provider = self.backfill_provider.clone();
+        let repository = self.repository.clone();
+        self.jobs.spawn(JobKind::Backfill, async move {
+            let provider = provider.ok_or("No provider supports historical ledgers")?;
+            let points = run_backfill(
+                provider.as_ref(),
+                &repository,
+                from_ledger,
+                to_ledger,
+                DEFAULT_CHUNK_LEDGERS,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            Ok(json!({ "points": points }))
+        })
+    }
+
+    /// Poll the provider now rather than on the next tick.
+    pub fn fetch(&self) -> Job {
+        let provider = self.provider.clone();
+        let fee_store = self.fee_store.clone();
+        let insights_engine = self.insights_engine.clone();
+        let repository = self.repository.clone();
+        let metrics = self.metrics.clone();
+        let alert_manager = self.alert_manager.clone();
+        let (retry_attempts, base_retry_delay_ms, retention_days) = (
+            self.retry_attempts,
+            self.base_retry_delay_ms,
+            self.storage_retention_days,
+        );
+        self.jobs.spawn(JobKind::Fetch, async move {
+            let points = poll_once(
+                &provider,
+                &fee_store,
+                &insights_engine,
+                retry_attempts,
+                base_retry_delay_ms,
+                Some(&repository),
+                retention_days,
+                metrics.as_deref(),
+                alert_manager.as_deref(),
+            )
+            .await;
+            Ok(json!({ "points": points }))
+        })
+    }
+
+    /// Rebuild the store and the engine's state from stored history.
+    pub fn recompute_insights(&self) -> Job {
+        let fee_store = self.fee_store.clone();
+        let insights_engine = self.insights_engine.clone();
+        let repository = self.repository.clone();
+        self.jobs.spawn(JobKind::RecomputeInsights, async move {
+            let points = replay_history(&repository, &fee_store, &insights_engine)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(json!({ "points": points }))
+        })
+    }
+
+    /// Delete fees, ledgers and insight snapshots older than `days`.
+    pub fn purge(&self, days: u64) -> Job {
+        let repository = self.repository.clone();
+        self.jobs.spawn(JobKind::Purge, async move {
+            let cutoff = Utc::now() - chrono::Duration::days(days as i64);
+            purge_before(&repository, cutoff)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// Reset the engine and replay the last day of stored fees into it and
+/// the store, as at startup. Returns the number of points replayed.
+pub async fn replay_history(
+    repository: &FeeRepository,
+    fee_store: &RwLock<FeeHistoryStore>,
+    insights_engine: &RwLock<FeeInsightsEngine>,
+) -> Result<usize, sqlx::Error> {
+    let window_start = Utc::now() - chrono::Duration::hours(RECOMPUTE_WINDOW_HOURS);
+    let extremes = repository.load_all_time_extremes().await?;
+    let slots = repository.fetch_seasonal_slots(window_start).await?;
+    let points = repository.fetch_since(window_start).await?;
+
+    {
+        // Both locks are held so ingestion cannot interleave with the replay
+        let mut store = fee_store.write().await;
+        let mut engine = insights_engine.write().await;
+        engine.reset();
+        if let Some(extremes) = &extremes {
+            engine.restore_all_time_extremes(extremes);
+        }
+        engine.seed_seasonality(&slots);
+        store.clear();
+        for point in &points {
+            store.push(point.clone());
+        }
+        if !points.is_empty() {
+            if let Err(err) = engine.process_fee_data(&points).await {
+                tracing::warn!("Insights engine error during replay: {}", err);
+            }
+        }
+    }
+    restore_budgets(repository, insights_engine).await?;
+    Ok(points.len())
+}
+
+/// Roll up, then delete, everything stored before `cutoff`.
+async fn purge_before(
+    repository: &FeeRepository,
+    cutoff: DateTime<Utc>,
+) -> Result<Value, sqlx::Error> {
+    // Roll up first so no point leaves unsummarised
+    roll_up_closed_buckets(repository, Utc::now()).await?;
+    Ok(json!({
+        "cutoff": cutoff,
+        "fee_points": repository.prune_older_than(cutoff).await?,
+        "ledgers": repository.prune_ledgers_older_than(cutoff).await?,
+        "insight_snapshots": repository.prune_insight_snapshots_older_than(cutoff).await?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use crate::db::create_pool;
+    use crate::insights::{FeeDataPoint, InsightsConfig};
+
+    async fn wait_for(jobs: &JobRegistry, id: u64) -> Job {
+        for _ in 0..100 {
+            let job = jobs.get(id).unwrap();
+            if job.status != JobStatus::Running {
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {} did not finish", id);
+    }
+
+    #[tokio::test]
+    async fn jobs_report_their_outcome() {
+        let jobs = Arc::new(JobRegistry::new());
+        let ok = jobs.spawn(JobKind::Fetch, async { Ok(json!({ "points": 3 })) });
+        let failed = jobs.spawn(JobKind::Purge, async { Err("disk full".to_string()) });
+        assert_eq!(ok.status, JobStatus::Running);
+        assert_ne!(ok.id, failed.id);
+
+        let ok = wait_for(&jobs, ok.id).await;
+        assert_eq!(ok.status, JobStatus::Succeeded);
+        assert_eq!(ok.result, Some(json!({ "points": 3 })));
+        assert!(ok.finished_at.is_some());
+        let failed = wait_for(&jobs, failed.id).await;
+        assert_eq!(failed.status, JobStatus::Failed);
+        assert_eq!(failed.error.as_deref(), Some("disk full"));
+
+        for _ in 0..JOB_HISTORY {
+            jobs.record(JobKind::RotateApiKey, Ok(Value::Null));
+        }
+        assert_eq!(jobs.list().len(), JOB_HISTORY);
+        assert!(jobs.get(ok.id).is_none());
+    }
+
+    #[tokio::test]
+    async fn replay_rebuilds_store_and_engine_from_storage() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repository = FeeRepository::new(pool);
+        let points: Vec<FeeDataPoint> = (0..4)
+            .map(|idx| FeeDataPoint {
+                fee_amount: 100 + idx * 100,
+                timestamp: Utc::now() - chrono::Duration::seconds(40 - idx as i64 * 10),
+                transaction_hash: format!("tx-{}", idx),
+                ledger_sequence: 100 + idx,
+                envelope: None,
+                soroban: None,
+            })
+            .collect();
+        repository.insert_fee_points(&points).await.unwrap();
+
+        // The store and engine have drifted from what is stored
+        let fee_store = RwLock::new(FeeHistoryStore::new(100));
+        let engine = RwLock::new(FeeInsightsEngine::new(InsightsConfig::default()));
+        let stray = FeeDataPoint {
+            fee_amount: 90_000,
+            ..points[0].clone()
+        };
+        fee_store.write().await.push(stray.clone());
+        engine
+            .write()
+            .await
+            .process_fee_data(&[stray])
+            .await
+            .unwrap();
+
+        let replayed = replay_history(&repository, &fee_store, &engine)
+            .await
+            .unwrap();
+        assert_eq!(replayed, 4);
+        assert_eq!(fee_store.read().await.len(), 4);
+        let insights = engine.read().await.get_current_insights();
+        assert_eq!(insights.rolling_averages.short_term.sample_count, 4);
+        assert_eq!(insights.rolling_averages.short_term.value, 250.0);
+    }
+
+    #[tokio::test]
+    async fn purge_deletes_what_is_older_than_the_cutoff() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repository = FeeRepository::new(pool);
+        let point = |days_ago: i64| FeeDataPoint {
+            fee_amount: 100,
+            timestamp: Utc::now() - chrono::Duration::days(days_ago),
+            transaction_hash: format!("tx-{}", days_ago),
+            ledger_sequence: 100,
+            envelope: None,
+            soroban: None,
+        };
+        repository
+            .insert_fee_points(&[point(10), point(1)])
+            .await
+            .unwrap();
+
+        let result = purge_before(&repository, Utc::now() - chrono::Duration::days(5))
+            .await
+            .unwrap();
+        assert_eq!(result["fee_points"], 1);
+        let left = repository
+            .fetch_since(Utc::now() - chrono::Duration::days(30))
+            .await
+            .unwrap();
+        assert_eq!(left.len(), 1);
+    }
+}
@@ -0,0 +1,124 @@
+//! Fee leaderboard.
+//!
+//! The accounts that paid the most in fees over a range and the most
+//! expensive transactions within it. Both are ranked and cut to the top N
+//! in SQL, so a busy week costs one indexed scan rather than loading every
+//! point. Payers are only known with `TRACK_FEE_ACCOUNTS` enabled.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::repository::FeeRepository;
+
+/// An account ranked by the fees it paid
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopFeePayer {
+    pub account: String,
+    pub transaction_count: u64,
+    /// In stroops.
+    pub total_fee: u64,
+    pub average_fee: f64,
+    pub max_fee: u64,
+}
+
+/// A transaction ranked by the fee it paid
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopFeeTransaction {
+    pub transaction_hash: String,
+    pub ledger_sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    /// In stroops.
+    pub fee_amount: u64,
+    /// `None` when the envelope was not decoded.
+    pub operation_count: Option<u32>,
+    pub fee_account: Option<String>,
+}
+
+/// Biggest fee payers and most expensive transactions in a range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeLeaderboard {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Biggest total first.
+    pub payers: Vec<TopFeePayer>,
+    /// Highest fee first.
+    pub transactions: Vec<TopFeeTransaction>,
+}
+
+/// The top `limit` payers and transactions in `[from, to)`.
+pub async fn fee_leaderboard(
+    repo: &FeeRepository,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: usize,
+) -> Result<FeeLeaderboard, sqlx::Error> {
+    Ok(FeeLeaderboard {
+        from,
+        to,
+        payers: repo.fetch_top_fee_payers(from, to, limit).await?,
+        transactions: repo.fetch_top_fee_transactions(from, to, limit).await?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::insights::{EnvelopeDetails, FeeDataPoint};
+
+    fn paid_by(account: &str, fee_amount: u64, minutes_ago: i64) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            transaction_hash: format!("{}_{}", account, fee_amount),
+            ledger_sequence: minutes_ago as u64,
+            envelope: Some(EnvelopeDetails {
+                operation_count: 2,
+                max_fee: fee_amount,
+                fee_account: Some(account.to_string()),
+                ..EnvelopeDetails::default()
+            }),
+            soroban: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn ranks_payers_and_transactions_within_the_range() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repo = FeeRepository::new(pool).with_fee_accounts(true);
+        repo.insert_fee_points(&[
+            paid_by("GEXCHANGE", 300, 10),
+            paid_by("GEXCHANGE", 400, 20),
+            paid_by("GANCHOR", 600, 30),
+            paid_by("GBOT", 100, 40),
+            // Outside the range
+            paid_by("GWHALE", 50_000, 120),
+        ])
+        .await
+        .unwrap();
+
+        let now = Utc::now();
+        let board = fee_leaderboard(&repo, now - chrono::Duration::hours(1), now, 2)
+            .await
+            .unwrap();
+
+        assert_eq!(board.payers.len(), 2);
+        assert_eq!(board.payers[0].account, "GEXCHANGE");
+        assert_eq!(
+            (board.payers[0].transaction_count, board.payers[0].total_fee),
+            (2, 700)
+        );
+        assert_eq!(board.payers[0].average_fee, 350.0);
+        assert_eq!(board.payers[0].max_fee, 400);
+        assert_eq!(board.payers[1].account, "GANCHOR");
+
+        let fees: Vec<u64> = board.transactions.iter().map(|t| t.fee_amount).collect();
+        assert_eq!(fees, vec![600, 400]);
+        assert_eq!(board.transactions[0].transaction_hash, "GANCHOR_600");
+        assert_eq!(board.transactions[0].operation_count, Some(2));
+        assert_eq!(
+            board.transactions[0].fee_account.as_deref(),
+            Some("GANCHOR")
+        );
+    }
+}
@@ -1,16 +1,24 @@
 // Library root — exposes internal modules for integration tests in `tests/`.
 // Production entry point remains `src/main.rs`.
 
+pub mod accounts;
 pub mod alerts;
 pub mod api;
+pub mod backfill;
+pub mod backtest;
 pub mod cache;
+pub mod comparison;
 pub mod db;
 pub mod error;
 pub mod insights;
+pub mod jobs;
+pub mod leaderboard;
 pub mod metrics;
 pub mod repository;
+pub mod rollup;
 pub mod scheduler;
 pub mod services;
+pub mod sla;
 pub mod store;
 
 // These modules are only needed by the binary.
@@ -1,44 +1,78 @@
+mod accounts;
 mod alerts;
 mod api;
+mod backfill;
 mod cache;
 mod cli;
+mod comparison;
 mod config;
 mod db;
 mod error;
 mod insights;
+mod jobs;
+mod leaderboard;
 mod logging;
 mod metrics;
 mod middleware;
 mod repository;
+mod rollup;
 mod scheduler;
 mod services;
+mod sla;
 mod store;
 
 use std::sync::Arc;
 
-use axum::http::{HeaderName, Method};
 use axum::{routing::get, Router};
 use clap::Parser;
 use dotenvy::dotenv;
+use std::convert::Infallible;
 use std::time::Duration;
 use tokio::sync::{Mutex, RwLock};
-use tower_http::cors::{AllowOrigin, CorsLayer};
 
 use crate::alerts::AlertManager;
+use crate::api::caching::cache_headers;
+use crate::api::fields::select_fields;
+use crate::api::timezone::render_timezone;
 use crate::cache::ResponseCache;
 use crate::cli::Cli;
-use crate::config::Config;
+use crate::config::{Config, FeeProviderKind, IngestionMode, StellarNetwork};
 use crate::error::AppError;
-use crate::insights::{FeeInsightsEngine, HorizonFeeDataProvider, InsightsConfig};
+use crate::insights::config::{CircuitBreakerConfig, ProviderMode, RetryConfig};
+use crate::insights::error::ProviderError;
+use crate::insights::providers::file::FileFeeDataProvider;
+use crate::insights::providers::simulated::SimulatedFeeDataProvider;
+use crate::insights::{
+    CachedProvider, CircuitBreakerProvider, DedupProvider, FailoverFeeDataProvider,
+    FeeDataProvider, FeeInsightsEngine, HorizonFeeDataProvider, InsightsConfig,
+    InstrumentedProvider, ProviderRegistry, RetryingProvider, SorobanRpcFeeDataProvider,
+    StreamingFeeDataProvider,
+};
+use crate::jobs::{JobRegistry, JobRunner};
 use crate::logging::init_logging;
 use crate::metrics::AppMetrics;
-use crate::middleware::auth::require_api_key;
-use crate::middleware::rate_limit::{enforce_rate_limit, RateLimitState};
+use crate::middleware::auth::{require_admin_key, require_api_key, ApiKeyAuth};
+use crate::middleware::compression::compression_layer;
+use crate::middleware::cors::cors_layer;
+use crate::middleware::jwt::{require_bearer_token, require_scope, JwtAuth, JwtValidator, Scope};
+use crate::middleware::rate_limit::{enforce_rate_limit, limit_rejected_auth, RateLimitState};
+use crate::middleware::request_id::propagate_request_id;
 use crate::repository::FeeRepository;
-use crate::scheduler::run_fee_polling_with_retry;
+use crate::scheduler::{run_fee_polling_with_retry, run_fee_streaming, SchedulerHeartbeat};
 use crate::services::horizon::HorizonClient;
+use crate::services::soroban_rpc::SorobanRpcClient;
 use crate::store::{FeeHistoryStore, DEFAULT_CAPACITY};
 
+/// Idle time after which a partially received ledger is ingested in stream mode.
+const STREAM_FLUSH_AFTER_SECONDS: u64 = 7;
+
+/// Poll intervals without a scheduler tick or new fee data before health
+/// checks report the component down.
+const HEALTH_STALE_POLL_INTERVALS: u64 = 3;
+
+/// Shortest silence health checks tolerate, for short poll intervals.
+const HEALTH_MIN_STALE_SECONDS: u64 = 60;
+
 #[tokio::main]
 async fn main() {
     // Load .env file (if present)
@@ -59,9 +93,11 @@ async fn main() {
         });
 
     tracing::info!(
-        "Configuration loaded: network={:?}, horizon_url={}, poll_interval_seconds={}, cache_ttl_seconds={}, rate_limit_per_minute={}, api_port={}, allowed_origins={:?}, retry_attempts={}, base_retry_delay_ms={}, database_url={}, storage_retention_days={}, api_key_configured={}, webhook_configured={}, alert_threshold={:?}",
+        "Configuration loaded: network={:?}, horizon_url={}, fee_provider={:?}, ingestion_mode={:?}, poll_interval_seconds={}, cache_ttl_seconds={}, rate_limit_per_minute={}, api_port={}, allowed_origins={:?}, retry_attempts={}, base_retry_delay_ms={}, database_url={}, storage_retention_days={}, track_fee_accounts={}, api_key_configured={}, webhook_configured={}, alert_threshold={:?}",
         config.stellar_network,
         config.horizon_url,
+        config.fee_provider,
+        config.ingestion_mode,
         config.poll_interval_seconds,
         config.cache_ttl_seconds,
         config.rate_limit_per_minute,
@@ -71,6 +107,7 @@ async fn main() {
         config.base_retry_delay_ms,
         config.database_url,
         config.storage_retention_days,
+        config.track_fee_accounts,
         config.api_key.is_some(),
         config.webhook_url.is_some(),
         config.alert_threshold,
@@ -91,44 +128,185 @@ async fn main() {
         std::process::exit(1);
     }));
 
-    let repository = Arc::new(FeeRepository::new(db_pool));
+    let repository = Arc::new(
+        FeeRepository::new(db_pool)
+            .with_network(config.stellar_network)
+            .with_fee_accounts(config.track_fee_accounts),
+    );
+    match repository.claim_untagged_points().await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!(
+            "Tagged {} legacy fee data points as {}",
+            count,
+            config.stellar_network.as_str()
+        ),
+        Err(err) => tracing::warn!("Failed to tag legacy fee data points: {}", err),
+    }
 
     // ---- Shared state ----
-    let horizon_client = Arc::new(HorizonClient::new(config.horizon_url.clone()));
+    let mut insights_config = InsightsConfig {
+        network: config.stellar_network,
+        providers: std::iter::once(&config.fee_provider)
+            .chain(&config.merge_providers)
+            .map(|kind| kind.as_str().to_string())
+            .collect(),
+        provider_mode: ProviderMode::Composite,
+        retry: RetryConfig {
+            max_attempts: config.retry_attempts,
+            base_delay: chrono::Duration::milliseconds(config.base_retry_delay_ms as i64),
+            ..RetryConfig::default()
+        },
+        circuit_breaker: CircuitBreakerConfig {
+            failure_threshold: config.circuit_breaker_threshold,
+            open_duration: chrono::Duration::seconds(config.circuit_breaker_open_seconds as i64),
+        },
+        horizon_auth: config.horizon_auth.clone(),
+        horizon_timeouts: config.horizon_timeouts.clone(),
+        horizon_transport: config.horizon_transport.clone(),
+        ..InsightsConfig::default()
+    };
+    for (name, averaging) in &config.window_averaging {
+        for window in &mut insights_config.time_windows {
+            if window.name == *name {
+                window.averaging = *averaging;
+            }
+        }
+    }
+
+    if let Err(err) = insights_config
+        .spike_detection
+        .validate()
+        .and_then(|_| insights_config.outlier_filter.validate())
+        .and_then(|_| insights_config.percentile_method.validate())
+    {
+        tracing::error!("{}", err);
+        std::process::exit(1);
+    }
+
+    let mut horizon_client = HorizonClient::new(config.horizon_url.clone())
+        .with_transport(insights_config.horizon_transport.clone())
+        .unwrap_or_else(|err| {
+            tracing::error!("Failed to configure Horizon client: {}", err);
+            std::process::exit(1);
+        });
+    if insights_config.horizon_transport.proxy_url.is_some() {
+        tracing::info!("Horizon proxy enabled");
+    }
+    if let Some(auth) = insights_config.horizon_auth.clone() {
+        tracing::info!("Horizon authentication enabled");
+        horizon_client = horizon_client.with_auth(auth);
+    }
+    let horizon_client = Arc::new(horizon_client);
     tracing::info!("Horizon client initialized: {}", horizon_client.base_url());
 
     let fee_store = Arc::new(RwLock::new(FeeHistoryStore::new(DEFAULT_CAPACITY)));
-
-    let insights_engine = Arc::new(RwLock::new(FeeInsightsEngine::new(
-        InsightsConfig::default(),
-    )));
+    let insights_engine = Arc::new(RwLock::new(FeeInsightsEngine::new(insights_config.clone())));
     let current_fees_cache = Arc::new(Mutex::new(ResponseCache::new(Duration::from_secs(
         config.cache_ttl_seconds,
     ))));
 
-    // ---- Startup rehydration ----
-    let rehydration_window = chrono::Utc::now() - chrono::Duration::hours(24);
-    match repository.fetch_since(rehydration_window).await {
-        Ok(points) if !points.is_empty() => {
-            let count = points.len();
-            {
-                let mut store = fee_store.write().await;
-                for point in &points {
-                    store.push(point.clone());
-                }
-            }
-            {
-                let mut engine = insights_engine.write().await;
-                if let Err(err) = engine.process_fee_data(&points).await {
-                    tracing::warn!("Insights engine error during rehydration: {}", err);
+    let provider_registry = build_provider_registry(&config, &horizon_client, &repository);
+    let fee_data_provider = provider_registry
+        .select(&insights_config)
+        .unwrap_or_else(|err| {
+            tracing::error!("Failed to initialise fee data provider: {}", err);
+            std::process::exit(1);
+        });
+    // Instrument the raw provider so latency reflects upstream calls, not
+    // cache hits or breaker short-circuits.
+    let fee_data_provider: Arc<dyn FeeDataProvider + Send + Sync> =
+        Arc::new(InstrumentedProvider::new(fee_data_provider).with_metrics(app_metrics.clone()));
+    let fee_data_provider: Arc<dyn FeeDataProvider + Send + Sync> =
+        if config.provider_cache_ttl_seconds > 0 {
+            tracing::info!(
+                "Provider response caching enabled (ttl: {}s)",
+                config.provider_cache_ttl_seconds
+            );
+            Arc::new(CachedProvider::new(
+                fee_data_provider,
+                Duration::from_secs(config.provider_cache_ttl_seconds),
+            ))
+        } else {
+            fee_data_provider
+        };
+    let fee_data_provider: Arc<dyn FeeDataProvider + Send + Sync> =
+        if config.circuit_breaker_threshold > 0 {
+            Arc::new(CircuitBreakerProvider::new(
+                fee_data_provider,
+                insights_config.circuit_breaker.clone(),
+            ))
+        } else {
+            fee_data_provider
+        };
+    // Outermost, so repeats served from the cache or by an open breaker are
+    // dropped as well.
+    let fee_data_provider = with_dedup(fee_data_provider, config.dedup_window_seconds);
+    tracing::info!(
+        "Fee data provider initialized: {}",
+        fee_data_provider.provider_name()
+    );
+
+    // ---- Historical backfill ----
+    let backfill_requested = cli.backfill_from_ledger.is_some() && cli.backfill_to_ledger.is_some();
+    let backfill_provider = match &config.backfill_provider {
+        Some(kind) => match provider_registry.build(kind.as_str()) {
+            Ok(provider) => Some(provider),
+            Err(err) => {
+                tracing::error!("Failed to initialise backfill provider: {}", err);
+                if backfill_requested {
+                    std::process::exit(1);
                 }
+                None
             }
-            tracing::info!("Restored {} fee data points from database", count);
+        },
+        None => Some(fee_data_provider.clone()),
+    }
+    .filter(|provider| {
+        let supported = provider.get_metadata().supports_historical;
+        if !supported {
+            tracing::warn!(
+                "Provider {} does not support historical data — backfill unavailable",
+                provider.provider_name()
+            );
         }
-        Ok(_) => tracing::info!("No historical fee data found — starting cold"),
-        Err(err) => tracing::warn!("Failed to rehydrate store from database: {}", err),
+        supported
+    })
+    .map(|provider| {
+        Arc::new(RetryingProvider::new(
+            provider,
+            insights_config.retry.clone(),
+        )) as Arc<dyn FeeDataProvider + Send + Sync>
+    });
+    if let (Some(from), Some(to), Some(provider)) = (
+        cli.backfill_from_ledger,
+        cli.backfill_to_ledger,
+        &backfill_provider,
+    ) {
+        if let Err(err) = backfill::run_backfill(
+            provider.as_ref(),
+            &repository,
+            from,
+            to,
+            // Give each concurrent segment roughly a default-sized chunk.
+            backfill::DEFAULT_CHUNK_LEDGERS * config.backfill_parallelism as u64,
+        )
+        .await
+        {
+            tracing::error!("Backfill failed: {}", err);
+        }
+    }
+
+    // ---- Startup rehydration ----
+    rehydrate(&repository, &fee_store, &insights_engine).await;
+    let event_log = api::events::FeeEventLog::start(insights_engine.clone()).await;
+
+    // ---- Additional networks ----
+    let mut additional_networks = Vec::new();
+    for network in &config.additional_networks {
+        additional_networks
+            .push(NetworkRuntime::start(*network, &config, &insights_config, &repository).await);
     }
-    let horizon_provider = Arc::new(HorizonFeeDataProvider::new((*horizon_client).clone()));
+
     let fee_stats_provider: Arc<dyn api::fees::FeeStatsProvider + Send + Sync> =
         horizon_client.clone();
     let alert_manager = Arc::new(AlertManager::new(
@@ -136,75 +314,98 @@ async fn main() {
         config.alert_threshold.clone(),
         config.stellar_network.as_str().to_string(),
     ));
-    let rate_limit_state = Arc::new(RateLimitState::new(config.rate_limit_per_minute));
+    let job_runner = Arc::new(JobRunner {
+        jobs: Arc::new(JobRegistry::new()),
+        provider: fee_data_provider.clone(),
+        backfill_provider,
+        fee_store: fee_store.clone(),
+        insights_engine: insights_engine.clone(),
+        repository: repository.clone(),
+        retry_attempts: config.retry_attempts,
+        base_retry_delay_ms: config.base_retry_delay_ms,
+        storage_retention_days: config.storage_retention_days,
+        metrics: Some(app_metrics.clone()),
+        alert_manager: Some(alert_manager.clone()),
+    });
+    let rate_limit_state = Arc::new(
+        RateLimitState::new(config.rate_limit_per_minute)
+            .with_route_limits(&config.route_rate_limits),
+    );
 
     // ---- CORS policy ----
-    // Log and skip invalid origins rather than panicking at startup.
-    let origins: Vec<axum::http::HeaderValue> = config
-        .allowed_origins
-        .iter()
-        .filter_map(|o| match o.parse() {
-            Ok(v) => Some(v),
-            Err(err) => {
-                tracing::warn!("Skipping invalid ALLOWED_ORIGINS entry '{}': {}", o, err);
-                None
-            }
-        })
-        .collect();
-
-    let cors = CorsLayer::new()
-        .allow_origin(AllowOrigin::list(origins))
-        .allow_methods([
-            Method::GET,
-            Method::POST,
-            Method::PATCH,
-            Method::DELETE,
-            Method::OPTIONS,
-        ])
-        .allow_headers([
-            HeaderName::from_static("content-type"),
-            HeaderName::from_static("x-api-key"),
-        ])
-        .expose_headers([
-            HeaderName::from_static("etag"),
-            HeaderName::from_static("cache-control"),
-            HeaderName::from_static("last-modified"),
-            HeaderName::from_static("x-ratelimit-limit"),
-            HeaderName::from_static("x-ratelimit-remaining"),
-            HeaderName::from_static("x-ratelimit-reset"),
-            HeaderName::from_static("retry-after"),
-        ])
-        .max_age(Duration::from_secs(3600));
+    let cors = cors_layer(&config);
 
     // ---- Axum router ----
     //
     // Route tiers (from least to most restricted):
     //
-    //  /health   — no rate limit, no auth (must always respond for load-balancer probes)
+    //  /health, /health/live, /health/ready, /health/provider — no rate limit, no auth (must always
+    //            respond for load-balancer and orchestrator probes)
     //  /metrics  — rate limited, NO API-key auth (must be scrapeable by Prometheus agents)
-    //  all else  — rate limited + optional API-key auth
+    //  all else  — rate limited + optional API-key and/or JWT auth, unprefixed and under /v1;
+    //              admin, alert and budget changes need the admin scope with a JWT
+    //  /admin/*  — only the API_KEY itself or an admin-scoped JWT; not served
+    //              when neither is configured
     //
     // fees routes get shared state (Horizon client, store, insights engine)
     // insights routes get Arc<RwLock<FeeInsightsEngine>> as their own state
     // Both sub-routers are Router<()> after with_state, so merge works fine
 
-    let fees_router = Router::new()
-        .route("/fees/current", get(api::fees::current_fees))
-        .route("/fees/history", get(api::fees::fee_history))
-        .route("/fees/trend", get(api::fees::fee_trend))
-        .with_state(Arc::new(api::fees::FeesApiState {
-            fee_stats_provider: Some(fee_stats_provider),
-            fee_cache: current_fees_cache,
-            fee_store: fee_store.clone(),
-            insights_engine: Some(insights_engine.clone()),
-        }));
+    //
+    // Every tracked network, the primary included, is also served under
+    // /networks/{name}; unprefixed routes always refer to the primary network.
 
-    // Business routes that require optional API-key auth.
-    let api_routes = Router::new()
-        .merge(fees_router)
-        .merge(api::insights::create_insights_router(
-            insights_engine.clone(),
-        ))
+    let primary_routes = network_routes(
+        fee_stats_provider,
+        current_fees_cache,
+        fee_store.clone(),
+        insights_engine.clone(),
+        repository.clone(),
+        event_log,
+    );
+    let primary_v1_routes =
+        api::v1::network_routes(insights_engine.clone(), primary_routes.clone());
+    let mut network_routers = Router::new().nest(
+        &api::networks::network_path(config.stellar_network),
+        primary_routes.clone(),
+    );
+    let mut v1_network_routers = Router::new().nest(
+        &api::networks::network_path(config.stellar_network),
+        primary_v1_routes.clone(),
+    );
+    for runtime in &additional_networks {
+        let path = api::networks::network_path(runtime.network);
+        let routes = runtime.routes(config.cache_ttl_seconds);
+        network_routers = network_routers.nest(&path, routes.clone());
+        v1_network_routers = v1_network_routers.nest(
+            &path,
+            api::v1::network_routes(runtime.insights_engine.clone(), routes),
+        );
+    }
+
+    // Routes whose handlers do not depend on the API version.
+    let shared_routes = Router::new()
+        .route(
+            "/networks",
+            get(api::networks::list_networks).with_state(Arc::new(
+                api::networks::NetworksResponse::new(
+                    config.stellar_network,
+                    &config.additional_networks,
+                ),
+            )),
+        )
+        .route(
+            "/networks/compare",
+            get(api::networks::compare_tracked_networks).with_state(Arc::new(
+                std::iter::once(insights_engine.clone())
+                    .chain(
+                        additional_networks
+                            .iter()
+                            .map(|n| n.insights_engine.clone()),
+                    )
+                    .collect(),
+            )),
+        )
         .merge(
             Router::new()
                 .route(
@@ -227,19 +428,100 @@ async fn main() {
                     "/alerts/history",
                     axum::routing::get(api::alerts::get_alert_history),
                 )
-                .with_state(repository.clone()),
+                .with_state(repository.clone())
+                .route_layer(axum::middleware::from_fn_with_state(
+                    Scope::Admin,
+                    require_scope,
+                )),
+        )
+        .merge(admin_only(
+            Router::new()
+                .route(
+                    "/admin/api-keys",
+                    get(api::admin::list_api_keys).post(api::admin::create_api_key),
+                )
+                .route(
+                    "/admin/api-keys/:id",
+                    axum::routing::delete(api::admin::revoke_api_key),
+                )
+                .route("/admin/cursors", get(api::admin::list_cursors))
+                .with_state(repository.clone())
+                .route(
+                    "/admin/api-keys/:id/rotate",
+                    axum::routing::post(api::admin::rotate_api_key).with_state(job_runner.clone()),
+                )
+                .merge(
+                    Router::new()
+                        .route(
+                            "/admin/jobs/backfill",
+                            axum::routing::post(api::admin::start_backfill),
+                        )
+                        .route(
+                            "/admin/jobs/fetch",
+                            axum::routing::post(api::admin::start_fetch),
+                        )
+                        .route(
+                            "/admin/jobs/recompute-insights",
+                            axum::routing::post(api::admin::start_recompute_insights),
+                        )
+                        .route(
+                            "/admin/jobs/purge",
+                            axum::routing::post(api::admin::start_purge),
+                        )
+                        .route("/admin/jobs", get(api::admin::list_jobs))
+                        .route("/admin/jobs/:id", get(api::admin::get_job))
+                        .with_state(job_runner),
+                )
+                .route(
+                    "/admin/config",
+                    get(api::admin::get_runtime_config).with_state(api::admin::AdminConfigState {
+                        config: Arc::new(config.clone()),
+                        insights_engine: insights_engine.clone(),
+                    }),
+                )
+                .route(
+                    "/admin/congestion-thresholds",
+                    get(api::admin::get_congestion_thresholds)
+                        .patch(api::admin::update_congestion_thresholds)
+                        .with_state(insights_engine.clone()),
+                ),
+            &config,
+        ))
+        .layer(axum::middleware::from_fn(cache_headers));
+
+    // Business routes that require optional API-key auth. Unprefixed routes
+    // stay as they were for existing clients; each API version is nested
+    // under its own prefix, with the v1 routers falling through to the
+    // unprefixed ones for routes it does not version.
+    let api_routes = Router::new()
+        .merge(primary_routes)
+        .merge(network_routers)
+        .merge(shared_routes.clone())
+        .nest(
+            api::v1::PREFIX,
+            Router::new()
+                .merge(primary_v1_routes)
+                .merge(v1_network_routers)
+                .merge(shared_routes),
         );
 
-    let api_routes = match config.api_key.clone() {
-        Some(expected_key) => {
-            tracing::info!("API key authentication is enabled for protected routes");
-            api_routes.layer(axum::middleware::from_fn_with_state(
-                Some(expected_key),
-                require_api_key,
-            ))
-        }
-        None => api_routes,
-    };
+    if config.api_key.is_some() {
+        tracing::info!("API key authentication is enabled for protected routes");
+    }
+    if let Some(jwt) = &config.jwt {
+        tracing::info!("JWT authentication is enabled for issuer {}", jwt.issuer);
+    }
+    let jwt_validator = config
+        .jwt
+        .clone()
+        .map(|jwt| Arc::new(JwtValidator::new(jwt)));
+    let api_routes = authenticate(
+        api_routes,
+        &config,
+        &repository,
+        jwt_validator.clone(),
+        rate_limit_state.clone(),
+    );
 
     // /metrics: rate limited but NOT behind API-key auth (Prometheus scrapers
     // should not need to know the API key).
@@ -277,16 +559,38 @@ async fn main() {
         }),
     );
 
-    // Rate-limited tier: metrics + business API routes.
-    let rate_limited = Router::new().merge(metrics_route).merge(api_routes).layer(
-        axum::middleware::from_fn_with_state(rate_limit_state, enforce_rate_limit),
-    );
+    // Rate-limited tier: metrics, by client IP, + business API routes,
+    // limited inside `authenticate`.
+    let rate_limited = Router::new()
+        .merge(metrics_route.layer(axum::middleware::from_fn_with_state(
+            rate_limit_state.clone(),
+            enforce_rate_limit,
+        )))
+        .merge(api_routes);
 
     // Final app: /health bypasses the rate limiter entirely.
+    let scheduler_heartbeat = Arc::new(SchedulerHeartbeat::new());
+    let health_state = api::health::HealthState {
+        repository: repository.clone(),
+        provider: fee_data_provider.clone(),
+        insights_engine: insights_engine.clone(),
+        heartbeat: scheduler_heartbeat.clone(),
+        stale_after: chrono::Duration::seconds(
+            (config.poll_interval_seconds * HEALTH_STALE_POLL_INTERVALS)
+                .max(HEALTH_MIN_STALE_SECONDS) as i64,
+        ),
+        provider_probe: Arc::new(api::health::ProviderProbe::default()),
+    };
     let app = Router::new()
         .route("/health", get(api::health::health))
+        .route("/health/live", get(api::health::live))
+        .route("/health/ready", get(api::health::ready))
+        .route("/health/provider", get(api::health::provider_health))
+        .with_state(health_state)
         .merge(rate_limited)
-        .layer(cors);
+        .layer(cors)
+        // Outermost, so every response — including rejections — carries the IDs.
+        .layer(axum::middleware::from_fn(propagate_request_id));
 
     // ---- TCP listener ----
     let addr = format!("0.0.0.0:{}", config.api_port);
@@ -299,7 +603,44 @@ async fn main() {
 
     tracing::info!("API server listening on {}", addr);
 
+    #[cfg(feature = "grpc-server")]
+    if let Some(port) = config.grpc_port {
+        let grpc = api::grpc::router(api::grpc::GrpcState {
+            insights_engine: insights_engine.clone(),
+            repository: repository.clone(),
+        });
+        let grpc = authenticate(
+            grpc,
+            &config,
+            &repository,
+            jwt_validator.clone(),
+            rate_limit_state.clone(),
+        )
+        .layer(axum::middleware::from_fn(propagate_request_id));
+        let addr = format!("0.0.0.0:{}", port);
+        match tokio::net::TcpListener::bind(&addr).await {
+            Ok(listener) => {
+                tracing::info!("gRPC server listening on {}", addr);
+                tokio::spawn(async move {
+                    let grpc = grpc.into_make_service_with_connect_info::<std::net::SocketAddr>();
+                    if let Err(err) = axum::serve(listener, grpc).await {
+                        tracing::error!("gRPC server error: {}", err);
+                    }
+                });
+            }
+            Err(err) => {
+                tracing::error!("Failed to bind to {}: {}", addr, err);
+                std::process::exit(1);
+            }
+        }
+    }
+
     // ---- Run server + scheduler concurrently ----
+    // Additional networks always poll; streaming applies to the primary only.
+    for runtime in additional_networks {
+        tokio::spawn(runtime.run_polling(&config));
+    }
+
     tokio::join!(
         async {
             axum::serve(
@@ -309,19 +650,574 @@ async fn main() {
             .await
             .unwrap_or_else(|err| tracing::error!("Server error: {}", err));
         },
-        run_fee_polling_with_retry(
-            horizon_provider,
+        async {
+            match config.ingestion_mode {
+                IngestionMode::Poll => {
+                    run_fee_polling_with_retry(
+                        fee_data_provider,
+                        fee_store,
+                        insights_engine,
+                        config.poll_interval_seconds,
+                        config.retry_attempts,
+                        config.base_retry_delay_ms,
+                        Some(repository),
+                        config.storage_retention_days,
+                        Some(app_metrics),
+                        Some(alert_manager),
+                        Some(scheduler_heartbeat),
+                    )
+                    .await
+                }
+                IngestionMode::Stream => {
+                    // Streaming always follows the primary Horizon; fallbacks
+                    // only apply to polled requests.
+                    let streaming_provider: Arc<dyn StreamingFeeDataProvider + Send + Sync> =
+                        Arc::new(
+                            HorizonFeeDataProvider::new((*horizon_client).clone())
+                                .with_network(config.stellar_network)
+                                .with_timeouts(&config.horizon_timeouts)
+                                .with_cursor_store(repository.clone()),
+                        );
+                    run_fee_streaming(
+                        streaming_provider,
+                        fee_store,
+                        insights_engine,
+                        Duration::from_secs(STREAM_FLUSH_AFTER_SECONDS),
+                        Some(repository),
+                        config.storage_retention_days,
+                        Some(app_metrics),
+                        Some(alert_manager),
+                        Some(scheduler_heartbeat),
+                    )
+                    .await
+                }
+            }
+        },
+    );
+
+    tracing::info!("Application shut down cleanly");
+}
+
+/// Restrict operational `routes` to the configured `API_KEY` itself or a
+/// bearer token with the admin scope; keys created over the API never
+/// pass. With neither credential configured they would be open to anyone,
+/// so they are not served at all.
+fn admin_only(routes: Router, config: &Config) -> Router {
+    if config.api_key.is_none() && config.jwt.is_none() {
+        tracing::warn!("Admin routes are disabled: set API_KEY or JWT_JWKS_URL to enable them");
+        return Router::new();
+    }
+    routes
+        .route_layer(axum::middleware::from_fn_with_state(
+            Scope::Admin,
+            require_scope,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            config.api_key.clone(),
+            require_admin_key,
+        ))
+}
+
+/// Put `routes` behind the configured API key and bearer token checks,
+/// then the rate limit. Bearer tokens are checked first; without one, a
+/// request falls back to the API key when one is configured. The limiter
+/// runs last so that it can key on the client authentication identified,
+/// while rejected credentials are limited by IP ahead of authentication.
+fn authenticate(
+    routes: Router,
+    config: &Config,
+    repository: &Arc<FeeRepository>,
+    jwt_validator: Option<Arc<JwtValidator>>,
+    rate_limit_state: Arc<RateLimitState>,
+) -> Router {
+    let routes = routes.layer(axum::middleware::from_fn_with_state(
+        rate_limit_state.clone(),
+        enforce_rate_limit,
+    ));
+    let routes = match config.api_key.clone() {
+        Some(admin_key) => routes.layer(axum::middleware::from_fn_with_state(
+            ApiKeyAuth {
+                admin_key: Some(admin_key),
+                repository: Some(repository.clone()),
+            },
+            require_api_key,
+        )),
+        None => routes,
+    };
+    let routes = match jwt_validator {
+        Some(validator) => routes.layer(axum::middleware::from_fn_with_state(
+            JwtAuth {
+                validator,
+                api_key_fallback: config.api_key.is_some(),
+            },
+            require_bearer_token,
+        )),
+        None => routes,
+    };
+    routes.layer(axum::middleware::from_fn_with_state(
+        rate_limit_state,
+        limit_rejected_auth,
+    ))
+}
+
+/// Fee and insights routes for one network's store, engine and storage.
+///
+/// History queries and exports can return multi-megabyte bodies and are
+/// compressed when the client accepts it. Every response gets the caching
+/// headers of its route's `CachePolicy`.
+fn network_routes(
+    fee_stats_provider: Arc<dyn api::fees::FeeStatsProvider + Send + Sync>,
+    fee_cache: Arc<Mutex<ResponseCache<api::fees::CurrentFeeResponse>>>,
+    fee_store: Arc<RwLock<FeeHistoryStore>>,
+    insights_engine: Arc<RwLock<FeeInsightsEngine>>,
+    repository: Arc<FeeRepository>,
+    event_log: Arc<api::events::FeeEventLog>,
+) -> Router {
+    #[cfg(feature = "graphql")]
+    let graphql = Router::new().route(
+        "/graphql",
+        axum::routing::post(api::graphql::graphql).with_state(api::graphql::build_schema(
+            insights_engine.clone(),
+            repository.clone(),
+        )),
+    );
+    #[cfg(not(feature = "graphql"))]
+    let graphql = Router::new();
+    #[cfg(feature = "parquet")]
+    let parquet_export = Router::new().route(
+        "/fees/export.parquet",
+        get(api::export::export_parquet).with_state(repository.clone()),
+    );
+    #[cfg(not(feature = "parquet"))]
+    let parquet_export = Router::new();
+
+    Router::new()
+        .route("/fees/current", get(api::fees::current_fees))
+        .route(
+            "/fees/history",
+            get(api::fees::fee_history).layer(compression_layer()),
+        )
+        .route("/fees/trend", get(api::fees::fee_trend))
+        .route(
+            "/fees/recommendation",
+            get(api::recommendation::fee_recommendation),
+        )
+        .route(
+            "/fees/query",
+            axum::routing::post(api::batch::batch_query).layer(compression_layer()),
+        )
+        .with_state(Arc::new(api::fees::FeesApiState {
+            fee_stats_provider: Some(fee_stats_provider),
+            fee_cache,
+            fee_store,
+            insights_engine: Some(insights_engine.clone()),
+            repository: Some(repository.clone()),
+        }))
+        .merge(api::insights::create_insights_router(
+            insights_engine.clone(),
+        ))
+        .route(
+            "/ws/fees",
+            get(api::ws::fee_stream).with_state(insights_engine.clone()),
+        )
+        .route(
+            "/events/fees",
+            get(api::events::stream_fee_events).with_state(event_log),
+        )
+        .route(
+            "/insights/range",
+            get(api::range::range_insights)
+                .with_state(Arc::new(api::range::RangeState {
+                    insights_engine: insights_engine.clone(),
+                    repository: repository.clone(),
+                }))
+                .layer::<_, Infallible>(axum::middleware::from_fn(select_fields))
+                .layer::<_, Infallible>(axum::middleware::from_fn(render_timezone))
+                .layer(compression_layer()),
+        )
+        .merge(
+            Router::new()
+                .route("/congestion", get(api::congestion::current_congestion))
+                .route(
+                    "/congestion/history",
+                    get(api::congestion::congestion_history),
+                )
+                .route_layer(axum::middleware::from_fn(render_timezone))
+                .with_state(Arc::new(api::congestion::CongestionApiState {
+                    insights_engine: insights_engine.clone(),
+                    repository: repository.clone(),
+                })),
+        )
+        .route(
+            "/insights/surges",
+            get(api::surges::list_surges)
+                .with_state(repository.clone())
+                .layer::<_, Infallible>(axum::middleware::from_fn(select_fields))
+                .layer::<_, Infallible>(axum::middleware::from_fn(render_timezone))
+                .layer(compression_layer()),
+        )
+        .route(
+            "/insights/snapshots",
+            get(api::snapshots::list_snapshots)
+                .with_state(repository.clone())
+                .layer::<_, Infallible>(axum::middleware::from_fn(select_fields))
+                .layer::<_, Infallible>(axum::middleware::from_fn(render_timezone))
+                .layer(compression_layer()),
+        )
+        .route(
+            "/insights/congestion-sla",
+            get(api::snapshots::get_congestion_sla)
+                .with_state(repository.clone())
+                .layer(axum::middleware::from_fn(select_fields))
+                .layer(axum::middleware::from_fn(render_timezone)),
+        )
+        .route(
+            "/fees/rollups",
+            get(api::rollups::list_rollups)
+                .with_state(repository.clone())
+                .layer(compression_layer()),
+        )
+        .route(
+            "/fees/export.csv",
+            get(api::export::export_csv)
+                .with_state(repository.clone())
+                .layer(compression_layer()),
+        )
+        .merge(parquet_export)
+        .route(
+            "/fees/heatmap",
+            get(api::rollups::fee_heatmap)
+                .with_state(repository.clone())
+                .layer(compression_layer()),
+        )
+        .route(
+            "/fees/stats",
+            get(api::rollups::fee_stats)
+                .with_state(repository.clone())
+                .layer(compression_layer()),
+        )
+        .route(
+            "/ledgers/:sequence/fees",
+            get(api::ledgers::ledger_fees)
+                .with_state(repository.clone())
+                .layer(compression_layer()),
+        )
+        .route(
+            "/insights/compare",
+            get(api::compare::compare)
+                .with_state(repository.clone())
+                .layer(axum::middleware::from_fn(select_fields))
+                .layer(axum::middleware::from_fn(render_timezone)),
+        )
+        .route(
+            "/insights/leaderboard",
+            get(api::leaderboard::leaderboard)
+                .with_state(repository.clone())
+                .layer(axum::middleware::from_fn(select_fields))
+                .layer(axum::middleware::from_fn(render_timezone)),
+        )
+        .route(
+            "/fees/accounts",
+            get(api::accounts::list_account_spend).with_state(repository.clone()),
+        )
+        .route(
+            "/fees/accounts/:account",
+            get(api::accounts::get_account_spend).with_state(repository.clone()),
+        )
+        .merge(
+            Router::new()
+                .route("/fees/budgets", get(api::budgets::list_budgets))
+                .route(
+                    "/fees/budgets/:account/:period",
+                    axum::routing::put(api::budgets::set_budget)
+                        .delete(api::budgets::delete_budget)
+                        .layer(axum::middleware::from_fn_with_state(
+                            Scope::Admin,
+                            require_scope,
+                        )),
+                )
+                .with_state(Arc::new(api::budgets::BudgetsState {
+                    insights_engine,
+                    repository,
+                })),
+        )
+        .merge(graphql)
+        .layer(axum::middleware::from_fn(cache_headers))
+}
+
+/// Restore the last 24 hours of persisted fee data into `fee_store` and the
+/// insights engine, seed seasonality from everything older and restore the
+/// all-time fee extremes and fee budgets.
+async fn rehydrate(
+    repository: &FeeRepository,
+    fee_store: &RwLock<FeeHistoryStore>,
+    insights_engine: &RwLock<FeeInsightsEngine>,
+) {
+    let rehydration_window = chrono::Utc::now() - chrono::Duration::hours(24);
+    match repository.load_all_time_extremes().await {
+        Ok(Some(extremes)) => insights_engine
+            .write()
+            .await
+            .restore_all_time_extremes(&extremes),
+        Ok(None) => {}
+        Err(err) => tracing::warn!("Failed to load all-time fee extremes: {}", err),
+    }
+    // Older history only seeds seasonality; the window itself is replayed below.
+    match repository.fetch_seasonal_slots(rehydration_window).await {
+        Ok(slots) => insights_engine.write().await.seed_seasonality(&slots),
+        Err(err) => tracing::warn!("Failed to load seasonality history: {}", err),
+    }
+    match repository.fetch_since(rehydration_window).await {
+        Ok(points) if !points.is_empty() => {
+            let count = points.len();
+            {
+                let mut store = fee_store.write().await;
+                for point in &points {
+                    store.push(point.clone());
+                }
+            }
+            {
+                let mut engine = insights_engine.write().await;
+                if let Err(err) = engine.process_fee_data(&points).await {
+                    tracing::warn!("Insights engine error during rehydration: {}", err);
+                }
+            }
+            tracing::info!("Restored {} fee data points from database", count);
+        }
+        Ok(_) => tracing::info!("No historical fee data found — starting cold"),
+        Err(err) => tracing::warn!("Failed to rehydrate store from database: {}", err),
+    }
+    match accounts::restore_budgets(repository, insights_engine).await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("Tracking {} fee budgets", count),
+        Err(err) => tracing::warn!("Failed to restore fee budgets: {}", err),
+    }
+}
+
+/// Ingestion state for a network tracked alongside the primary one. Each has
+/// its own Horizon client, history store, insights engine and storage scope.
+struct NetworkRuntime {
+    network: StellarNetwork,
+    horizon_client: Arc<HorizonClient>,
+    fee_store: Arc<RwLock<FeeHistoryStore>>,
+    insights_engine: Arc<RwLock<FeeInsightsEngine>>,
+    repository: Arc<FeeRepository>,
+    event_log: Arc<api::events::FeeEventLog>,
+}
+
+impl NetworkRuntime {
+    async fn start(
+        network: StellarNetwork,
+        config: &Config,
+        insights_config: &InsightsConfig,
+        repository: &FeeRepository,
+    ) -> Self {
+        let horizon_client = HorizonClient::new(network.default_horizon_url().to_string())
+            .with_transport(insights_config.horizon_transport.clone())
+            .unwrap_or_else(|err| {
+                tracing::error!("Failed to configure Horizon client: {}", err);
+                std::process::exit(1);
+            });
+        let horizon_client = Arc::new(horizon_client);
+        let fee_store = Arc::new(RwLock::new(FeeHistoryStore::new(DEFAULT_CAPACITY)));
+        let insights_engine = Arc::new(RwLock::new(FeeInsightsEngine::new(InsightsConfig {
+            network,
+            // Extra networks always use public Horizon.
+            horizon_auth: None,
+            ..insights_config.clone()
+        })));
+        let repository = Arc::new(repository.for_network(network));
+        rehydrate(&repository, &fee_store, &insights_engine).await;
+        let runtime = Self {
+            network,
+            horizon_client,
             fee_store,
+            event_log: api::events::FeeEventLog::start(insights_engine.clone()).await,
             insights_engine,
+            repository,
+        };
+        tracing::info!(
+            "Tracking additional network {} via {} (poll interval: {}s)",
+            network.as_str(),
+            runtime.horizon_client.base_url(),
+            config.poll_interval_seconds
+        );
+        runtime
+    }
+
+    fn routes(&self, cache_ttl_seconds: u64) -> Router {
+        network_routes(
+            self.horizon_client.clone(),
+            Arc::new(Mutex::new(ResponseCache::new(Duration::from_secs(
+                cache_ttl_seconds,
+            )))),
+            self.fee_store.clone(),
+            self.insights_engine.clone(),
+            self.repository.clone(),
+            self.event_log.clone(),
+        )
+    }
+
+    fn run_polling(self, config: &Config) -> impl std::future::Future<Output = ()> + Send {
+        let provider: Arc<dyn FeeDataProvider + Send + Sync> = Arc::new(
+            HorizonFeeDataProvider::new((*self.horizon_client).clone())
+                .with_network(self.network)
+                .with_timeouts(&config.horizon_timeouts)
+                .with_cursor_store(self.repository.clone()),
+        );
+        let provider = with_dedup(provider, config.dedup_window_seconds);
+        let alert_manager = Arc::new(AlertManager::new(
+            config.webhook_url.clone(),
+            config.alert_threshold.clone(),
+            self.network.as_str().to_string(),
+        ));
+        run_fee_polling_with_retry(
+            provider,
+            self.fee_store,
+            self.insights_engine,
             config.poll_interval_seconds,
             config.retry_attempts,
             config.base_retry_delay_ms,
-            Some(repository),
+            Some(self.repository),
             config.storage_retention_days,
-            Some(app_metrics),
+            None,
             Some(alert_manager),
-        ),
-    );
+            None,
+        )
+    }
+}
 
-    tracing::info!("Application shut down cleanly");
+/// Wrap `provider` in a `DedupProvider` unless `window_seconds` is 0.
+fn with_dedup(
+    provider: Arc<dyn FeeDataProvider + Send + Sync>,
+    window_seconds: u64,
+) -> Arc<dyn FeeDataProvider + Send + Sync> {
+    if window_seconds == 0 {
+        return provider;
+    }
+    Arc::new(DedupProvider::new(
+        provider,
+        Duration::from_secs(window_seconds),
+    ))
+}
+
+/// Register every fee data provider this binary can run, keyed by the names
+/// accepted in `FEE_PROVIDER`.
+fn build_provider_registry(
+    config: &Config,
+    horizon_client: &HorizonClient,
+    repository: &Arc<FeeRepository>,
+) -> ProviderRegistry {
+    let mut registry = ProviderRegistry::new();
+
+    let network = config.stellar_network;
+    let parallelism = config.backfill_parallelism;
+    let primary = horizon_client.clone();
+    let primary_url = config.horizon_url.clone();
+    let fallback_urls = config.horizon_fallback_urls.clone();
+    let cursor_store = repository.clone();
+    let timeouts = config.horizon_timeouts.clone();
+    let transport = config.horizon_transport.clone();
+    registry.register(FeeProviderKind::Horizon.as_str(), move || {
+        // Only the primary persists its cursor; fallbacks poll the latest page.
+        let horizon: Arc<dyn FeeDataProvider + Send + Sync> = Arc::new(
+            HorizonFeeDataProvider::new(primary.clone())
+                .with_network(network)
+                .with_fetch_parallelism(parallelism)
+                .with_timeouts(&timeouts)
+                .with_cursor_store(cursor_store.clone()),
+        );
+        if fallback_urls.is_empty() {
+            return Ok(horizon);
+        }
+
+        let mut failover =
+            FailoverFeeDataProvider::new().with_backend(primary_url.clone(), horizon);
+        for url in &fallback_urls {
+            let client = HorizonClient::new(url.clone())
+                .with_transport(transport.clone())
+                .map_err(|e| ProviderError::NetworkError {
+                    message: format!("Cannot configure Horizon mirror {}: {}", url, e),
+                    timeout: false,
+                })?;
+            failover = failover.with_backend(
+                url.clone(),
+                Arc::new(
+                    HorizonFeeDataProvider::new(client)
+                        .with_network(network)
+                        .with_fetch_parallelism(parallelism)
+                        .with_timeouts(&timeouts),
+                ),
+            );
+        }
+        tracing::info!(
+            "Horizon failover enabled: {}",
+            failover.backend_labels().join(" -> ")
+        );
+        Ok(Arc::new(failover))
+    });
+
+    if let Some(rpc_url) = config.soroban_rpc_url.clone() {
+        registry.register(FeeProviderKind::SorobanRpc.as_str(), move || {
+            Ok(Arc::new(SorobanRpcFeeDataProvider::new(
+                SorobanRpcClient::new(rpc_url.clone()),
+            )))
+        });
+    }
+
+    let simulation = config.simulation.clone();
+    registry.register(FeeProviderKind::Simulated.as_str(), move || {
+        Ok(Arc::new(SimulatedFeeDataProvider::new(simulation.clone())))
+    });
+
+    if let Some(replay_path) = config.replay_path.clone() {
+        let speed = config.replay_speed;
+        registry.register(FeeProviderKind::File.as_str(), move || {
+            Ok(Arc::new(
+                FileFeeDataProvider::open(&replay_path)?.with_speed(speed),
+            ))
+        });
+    }
+
+    #[cfg(feature = "hubble")]
+    if let Some(project_id) = config.hubble_project_id.clone() {
+        use crate::insights::providers::hubble::HubbleFeeDataProvider;
+        let credentials_path = config.hubble_credentials_path.clone();
+        let table = config.hubble_table.clone();
+        registry.register(FeeProviderKind::Hubble.as_str(), move || {
+            let mut hubble = HubbleFeeDataProvider::new(project_id.clone());
+            if let Some(path) = &credentials_path {
+                hubble = hubble.with_credentials_path(path);
+            }
+            if let Some(table) = &table {
+                hubble = hubble.with_table(table.clone());
+            }
+            Ok(Arc::new(hubble))
+        });
+    }
+
+    #[cfg(feature = "grpc")]
+    if let Some(collector_url) = config.grpc_collector_url.clone() {
+        use crate::insights::providers::grpc::GrpcFeeDataProvider;
+        registry.register(FeeProviderKind::Grpc.as_str(), move || {
+            Ok(Arc::new(
+                GrpcFeeDataProvider::new(collector_url.clone()).with_network(network),
+            ))
+        });
+    }
+
+    #[cfg(feature = "captive-core")]
+    if let Some(meta_path) = config.captive_core_meta_path.clone() {
+        use crate::insights::providers::captive_core::{
+            CaptiveCoreFeeDataProvider, DEFAULT_BUFFER_CAPACITY,
+        };
+        registry.register(FeeProviderKind::CaptiveCore.as_str(), move || {
+            Ok(Arc::new(CaptiveCoreFeeDataProvider::spawn(
+                meta_path.clone(),
+                DEFAULT_BUFFER_CAPACITY,
+            )))
+        });
+    }
+
+    registry
 }
@@ -8,7 +8,10 @@
 //! (`text/plain; version=0.0.4`). The endpoint is intentionally excluded
 //! from API-key auth so it can be scraped by Prometheus / Grafana agents.
 
-use prometheus::{Counter, Gauge, Opts, Registry};
+use prometheus::{Counter, CounterVec, Gauge, HistogramOpts, HistogramVec, Opts, Registry};
+use std::time::Duration;
+
+use crate::insights::provider::ProviderStatus;
 
 /// All application-level Prometheus metrics.
 ///
@@ -26,6 +29,16 @@ pub struct AppMetrics {
     pub current_avg_fee: Gauge,
     /// Total number of fee spikes detected by the insights engine.
     pub spikes_detected_total: Counter,
+    /// Provider circuit breaker state: 0 = closed, 1 = half-open, 2 = open.
+    pub provider_circuit_state: Gauge,
+    /// Consecutive provider failures counted by the circuit breaker.
+    pub provider_consecutive_failures: Gauge,
+    /// Provider calls by provider, operation and outcome (success / error).
+    pub provider_requests_total: CounterVec,
+    /// Provider call latency by provider and operation.
+    pub provider_request_duration_seconds: HistogramVec,
+    /// Fee data points returned per successful provider call.
+    pub provider_payload_points: HistogramVec,
     /// The registry that owns all of the above metrics.
     pub registry: Registry,
 }
@@ -61,11 +74,54 @@ impl AppMetrics {
             "Total fee spikes detected",
         ))?;
 
+        let provider_circuit_state = Gauge::with_opts(Opts::new(
+            "stellar_fee_tracker_provider_circuit_state",
+            "Provider circuit breaker state (0 = closed, 1 = half-open, 2 = open)",
+        ))?;
+
+        let provider_consecutive_failures = Gauge::with_opts(Opts::new(
+            "stellar_fee_tracker_provider_consecutive_failures",
+            "Consecutive provider failures seen by the circuit breaker",
+        ))?;
+
+        let provider_requests_total = CounterVec::new(
+            Opts::new(
+                "stellar_fee_tracker_provider_requests_total",
+                "Fee data provider calls by outcome",
+            ),
+            &["provider", "operation", "outcome"],
+        )?;
+
+        let provider_request_duration_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "stellar_fee_tracker_provider_request_duration_seconds",
+                "Fee data provider call latency",
+            )
+            .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]),
+            &["provider", "operation"],
+        )?;
+
+        let provider_payload_points = HistogramVec::new(
+            HistogramOpts::new(
+                "stellar_fee_tracker_provider_payload_points",
+                "Fee data points returned per successful provider call",
+            )
+            .buckets(vec![
+                0.0, 1.0, 10.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 5000.0,
+            ]),
+            &["provider", "operation"],
+        )?;
+
         registry.register(Box::new(polls_total.clone()))?;
         registry.register(Box::new(poll_errors_total.clone()))?;
         registry.register(Box::new(fee_points_stored.clone()))?;
         registry.register(Box::new(current_avg_fee.clone()))?;
         registry.register(Box::new(spikes_detected_total.clone()))?;
+        registry.register(Box::new(provider_circuit_state.clone()))?;
+        registry.register(Box::new(provider_consecutive_failures.clone()))?;
+        registry.register(Box::new(provider_requests_total.clone()))?;
+        registry.register(Box::new(provider_request_duration_seconds.clone()))?;
+        registry.register(Box::new(provider_payload_points.clone()))?;
 
         Ok(Self {
             polls_total,
@@ -73,10 +129,48 @@ impl AppMetrics {
             fee_points_stored,
             current_avg_fee,
             spikes_detected_total,
+            provider_circuit_state,
+            provider_consecutive_failures,
+            provider_requests_total,
+            provider_request_duration_seconds,
+            provider_payload_points,
             registry,
         })
     }
 
+    /// Mirror the provider's circuit breaker state into the gauges.
+    /// Providers without a breaker leave the gauges untouched.
+    pub fn record_provider_status(&self, status: &ProviderStatus) {
+        if let Some(breaker) = &status.circuit_breaker {
+            self.provider_circuit_state.set(breaker.state.as_gauge());
+            self.provider_consecutive_failures
+                .set(breaker.consecutive_failures as f64);
+        }
+    }
+
+    /// Record one fee data provider call.
+    pub fn record_provider_call(
+        &self,
+        provider: &str,
+        operation: &str,
+        success: bool,
+        latency: Duration,
+        points: usize,
+    ) {
+        let outcome = if success { "success" } else { "error" };
+        self.provider_requests_total
+            .with_label_values(&[provider, operation, outcome])
+            .inc();
+        self.provider_request_duration_seconds
+            .with_label_values(&[provider, operation])
+            .observe(latency.as_secs_f64());
+        if success {
+            self.provider_payload_points
+                .with_label_values(&[provider, operation])
+                .observe(points as f64);
+        }
+    }
+
     /// Render all metrics as Prometheus text format (for the `/metrics` endpoint).
     pub fn render(&self) -> Result<String, prometheus::Error> {
         use prometheus::Encoder;
@@ -119,6 +213,25 @@ mod tests {
         assert!((metrics.poll_errors_total.get() - 1.0).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn record_provider_status_sets_circuit_gauges() {
+        use crate::insights::provider::{CircuitBreakerStatus, CircuitState};
+
+        let metrics = AppMetrics::new().unwrap();
+        metrics.record_provider_status(&ProviderStatus {
+            circuit_breaker: Some(CircuitBreakerStatus {
+                state: CircuitState::Open,
+                consecutive_failures: 4,
+                retry_in_seconds: Some(10),
+                serving_stale: true,
+            }),
+            ..ProviderStatus::default()
+        });
+
+        assert!((metrics.provider_circuit_state.get() - 2.0).abs() < f64::EPSILON);
+        assert!((metrics.provider_consecutive_failures.get() - 4.0).abs() < f64::EPSILON);
+    }
+
     #[test]
     fn gauge_set_and_get() {
         let metrics = AppMetrics::new().unwrap();
@@ -1,60 +1,134 @@
+//! `X-API-Key` authentication.
+//!
+//! Authentication is on while `API_KEY` is configured. Requests must then
+//! carry either that key, the admin key, or one created through
+//! `POST /admin/api-keys` and not yet revoked; only the admin key may manage
+//! keys. Requests already authenticated by a bearer token (see
+//! [`super::jwt`]) pass.
+
+use std::sync::Arc;
+
 use axum::{
     extract::{Request, State},
     http::StatusCode,
     middleware::Next,
     response::{IntoResponse, Response},
-    Json,
 };
-use serde_json::json;
+
+use super::jwt::BearerToken;
+use crate::api::admin::hash_api_key;
+use crate::error::AppError;
+use crate::repository::FeeRepository;
 
 const API_KEY_HEADER: &str = "x-api-key";
 
+/// Who an authenticated request came from, added to its extensions by
+/// [`require_api_key`] and [`super::jwt::require_bearer_token`] so that the
+/// rate limiter can give each client its own bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AuthenticatedClient(pub String);
+
+impl AuthenticatedClient {
+    /// A client identified by an API key; only its hash is kept.
+    pub fn api_key(key: &str) -> Self {
+        Self(format!("key:{}", hash_api_key(key)))
+    }
+}
+
+/// Keys [`require_api_key`] accepts
+#[derive(Clone)]
+pub struct ApiKeyAuth {
+    /// The configured `API_KEY`; authentication is off while `None`.
+    pub admin_key: Option<String>,
+    /// Where keys created over the API are stored.
+    pub repository: Option<Arc<FeeRepository>>,
+}
+
 pub async fn require_api_key(
-    State(expected_key): State<Option<String>>,
-    request: Request,
+    State(auth): State<ApiKeyAuth>,
+    mut request: Request,
     next: Next,
 ) -> Response {
-    let Some(expected_key) = expected_key else {
+    let Some(admin_key) = auth.admin_key else {
         return next.run(request).await;
     };
+    if request.extensions().get::<BearerToken>().is_some() {
+        return next.run(request).await;
+    }
 
-    let Some(provided_header) = request.headers().get(API_KEY_HEADER) else {
-        return unauthorized_response();
-    };
-
-    let Ok(provided_key) = provided_header.to_str() else {
-        return forbidden_response("Forbidden: invalid API key format");
+    let provided_key = match provided_key(&request) {
+        Ok(key) => key.to_string(),
+        Err(status) => return rejection(status),
     };
 
-    if provided_key.is_empty() {
-        return forbidden_response("Forbidden: invalid API key format");
+    let mut valid = constant_time_eq(provided_key.as_bytes(), admin_key.as_bytes());
+    if !valid {
+        if let Some(repository) = &auth.repository {
+            match repository
+                .is_active_api_key(&hash_api_key(&provided_key))
+                .await
+            {
+                Ok(active) => valid = active,
+                Err(err) => tracing::error!("Failed to look up API key: {}", err),
+            }
+        }
     }
-
-    if !constant_time_eq(provided_key.as_bytes(), expected_key.as_bytes()) {
+    if !valid {
         return unauthorized_response();
     }
 
+    request
+        .extensions_mut()
+        .insert(AuthenticatedClient::api_key(&provided_key));
     next.run(request).await
 }
 
-fn unauthorized_response() -> Response {
-    (
-        StatusCode::UNAUTHORIZED,
-        Json(json!({
-            "error": "Unauthorized: missing or invalid API key"
-        })),
-    )
-        .into_response()
+/// Like [`require_api_key`], but only the admin key is accepted.
+pub async fn require_admin_key(
+    State(admin_key): State<Option<String>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(admin_key) = admin_key else {
+        return next.run(request).await;
+    };
+    // A token's scope is checked by `require_scope`
+    if request.extensions().get::<BearerToken>().is_some() {
+        return next.run(request).await;
+    }
+
+    match provided_key(&request) {
+        Ok(key) if constant_time_eq(key.as_bytes(), admin_key.as_bytes()) => {
+            next.run(request).await
+        }
+        Ok(_) => unauthorized_response(),
+        Err(status) => rejection(status),
+    }
+}
+
+/// The request's API key, or the status rejecting it: 401 when missing
+/// and 403 when malformed.
+fn provided_key(request: &Request) -> Result<&str, StatusCode> {
+    let Some(provided_header) = request.headers().get(API_KEY_HEADER) else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    match provided_header.to_str() {
+        Ok(key) if !key.is_empty() => Ok(key),
+        _ => Err(StatusCode::FORBIDDEN),
+    }
 }
 
-fn forbidden_response(message: &str) -> Response {
-    (
-        StatusCode::FORBIDDEN,
-        Json(json!({
-            "error": message
-        })),
-    )
-        .into_response()
+fn rejection(status: StatusCode) -> Response {
+    if status == StatusCode::UNAUTHORIZED {
+        unauthorized_response()
+    } else {
+        AppError::Forbidden("invalid API key format".to_string()).into_response()
+    }
+}
+
+fn unauthorized_response() -> Response {
+    AppError::Unauthorized("missing or invalid API key".to_string()).into_response()
 }
 
 // Constant-time byte comparison to avoid timing leaks on key checks.
@@ -90,7 +164,13 @@ mod tests {
     fn build_test_app(api_key: Option<String>) -> Router {
         let protected = Router::new().route("/protected", get(ok_handler));
         let protected = if api_key.is_some() {
-            protected.layer(from_fn_with_state(api_key, require_api_key))
+            protected.layer(from_fn_with_state(
+                ApiKeyAuth {
+                    admin_key: api_key,
+                    repository: None,
+                },
+                require_api_key,
+            ))
         } else {
             protected
         };
@@ -133,6 +213,40 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn accepted_key_identifies_the_client() {
+        let app = Router::new()
+            .route(
+                "/whoami",
+                get(
+                    |axum::Extension(client): axum::Extension<AuthenticatedClient>| async move {
+                        client.0
+                    },
+                ),
+            )
+            .layer(from_fn_with_state(
+                ApiKeyAuth {
+                    admin_key: Some("secret".to_string()),
+                    repository: None,
+                },
+                require_api_key,
+            ));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/whoami")
+                    .header(API_KEY_HEADER, "secret")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, AuthenticatedClient::api_key("secret").0);
+    }
+
     #[tokio::test]
     async fn configured_key_with_missing_header_returns_401() {
         let app = build_test_app(Some("secret".to_string()));
@@ -149,7 +263,8 @@ mod tests {
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
         let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
         let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(payload["error"], "Unauthorized: missing or invalid API key");
+        assert_eq!(payload["code"], "unauthorized");
+        assert_eq!(payload["detail"], "missing or invalid API key");
     }
 
     #[tokio::test]
@@ -169,7 +284,8 @@ mod tests {
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
         let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
         let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(payload["error"], "Unauthorized: missing or invalid API key");
+        assert_eq!(payload["code"], "unauthorized");
+        assert_eq!(payload["detail"], "missing or invalid API key");
     }
 
     #[tokio::test]
@@ -188,7 +304,8 @@ mod tests {
         assert_eq!(response.status(), StatusCode::FORBIDDEN);
         let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
         let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(payload["error"], "Forbidden: invalid API key format");
+        assert_eq!(payload["code"], "forbidden");
+        assert_eq!(payload["detail"], "invalid API key format");
     }
 
     #[tokio::test]
@@ -207,6 +324,67 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    async fn status_with_key(app: &Router, uri: &str, key: &str) -> StatusCode {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .header(API_KEY_HEADER, key)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn stored_keys_are_accepted_until_revoked() {
+        let pool = crate::db::create_pool("sqlite::memory:").await.unwrap();
+        let repository = Arc::new(FeeRepository::new(pool));
+        let key = crate::api::admin::generate_api_key();
+        let id = repository
+            .insert_api_key("dashboard", &hash_api_key(&key), &key[..8])
+            .await
+            .unwrap()
+            .id;
+
+        let admin_key = Some("secret".to_string());
+        let app = Router::new()
+            .route("/protected", get(ok_handler))
+            .merge(
+                Router::new()
+                    .route("/admin", get(ok_handler))
+                    .layer(from_fn_with_state(admin_key.clone(), require_admin_key)),
+            )
+            .layer(from_fn_with_state(
+                ApiKeyAuth {
+                    admin_key,
+                    repository: Some(repository.clone()),
+                },
+                require_api_key,
+            ));
+
+        assert_eq!(
+            status_with_key(&app, "/protected", &key).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status_with_key(&app, "/admin", &key).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status_with_key(&app, "/admin", "secret").await,
+            StatusCode::OK
+        );
+
+        repository.revoke_api_key(id).await.unwrap();
+        assert_eq!(
+            status_with_key(&app, "/protected", &key).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
     #[test]
     fn constant_time_eq_checks_full_input() {
         assert!(constant_time_eq(b"abc", b"abc"));
@@ -0,0 +1,56 @@
+//! Response compression for routes that return large bodies.
+
+use tower_http::compression::CompressionLayer;
+
+/// gzip or brotli, whichever the client's `Accept-Encoding` prefers.
+///
+/// Bodies under 32 bytes, images and event streams are sent as they are.
+pub fn compression_layer() -> CompressionLayer {
+    CompressionLayer::new().gzip(true).br(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    async fn fetch(accept_encoding: Option<&str>) -> (Option<String>, Vec<u8>) {
+        let app = Router::new().route(
+            "/fees/history",
+            get(|| async { "[{\"fee\":100}]".repeat(100) }).layer(compression_layer()),
+        );
+        let mut request = Request::builder().uri("/fees/history");
+        if let Some(value) = accept_encoding {
+            request = request.header("accept-encoding", value);
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let encoding = response
+            .headers()
+            .get("content-encoding")
+            .map(|value| value.to_str().unwrap().to_string());
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (encoding, body.to_vec())
+    }
+
+    #[tokio::test]
+    async fn negotiates_encoding_from_accept_encoding() {
+        let (encoding, body) = fetch(None).await;
+        assert_eq!(encoding, None);
+        assert_eq!(body.len(), 1300);
+
+        let (encoding, body) = fetch(Some("gzip")).await;
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+        assert!(body.len() < 1300);
+
+        let (encoding, _) = fetch(Some("gzip;q=0.5, br")).await;
+        assert_eq!(encoding.as_deref(), Some("br"));
+
+        let (encoding, _) = fetch(Some("identity")).await;
+        assert_eq!(encoding, None);
+    }
+}
@@ -0,0 +1,144 @@
+//! CORS policy built from the server configuration.
+
+use std::time::Duration;
+
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::config::Config;
+
+/// Response headers browsers may read cross-origin.
+const EXPOSED_HEADERS: [&str; 9] = [
+    "etag",
+    "cache-control",
+    "last-modified",
+    "x-ratelimit-limit",
+    "x-ratelimit-remaining",
+    "x-ratelimit-reset",
+    "retry-after",
+    "x-request-id",
+    "traceparent",
+];
+
+/// The CORS layer for `config`'s origins, methods, headers and credentials.
+pub fn cors_layer(config: &Config) -> CorsLayer {
+    let allow_origin = if config.allowed_origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        // Log and skip invalid origins rather than panicking at startup.
+        let origins: Vec<HeaderValue> = config
+            .allowed_origins
+            .iter()
+            .filter_map(|o| match o.parse() {
+                Ok(v) => Some(v),
+                Err(err) => {
+                    tracing::warn!("Skipping invalid ALLOWED_ORIGINS entry '{}': {}", o, err);
+                    None
+                }
+            })
+            .collect();
+        AllowOrigin::list(origins)
+    };
+
+    // Methods and headers were validated when the configuration was loaded.
+    let methods: Vec<Method> = config
+        .cors_allowed_methods
+        .iter()
+        .filter_map(|m| Method::from_bytes(m.as_bytes()).ok())
+        .collect();
+    let headers: Vec<HeaderName> = config
+        .cors_allowed_headers
+        .iter()
+        .filter_map(|h| HeaderName::from_bytes(h.as_bytes()).ok())
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .allow_credentials(config.cors_allow_credentials)
+        .expose_headers(EXPOSED_HEADERS.map(HeaderName::from_static))
+        .max_age(Duration::from_secs(config.cors_max_age_seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, http::StatusCode, routing::get, Router};
+    use std::collections::HashMap;
+    use tower::ServiceExt;
+
+    use crate::cli::Cli;
+
+    fn config(env: &[(&str, &str)]) -> Config {
+        let cli = Cli {
+            network: Some("testnet".to_string()),
+            horizon_url: None,
+            poll_interval: Some(30),
+            backfill_from_ledger: None,
+            backfill_to_ledger: None,
+        };
+        Config::from_sources_with_overrides(&cli, &env.iter().copied().collect::<HashMap<_, _>>())
+            .unwrap()
+    }
+
+    async fn preflight(config: &Config, origin: &str) -> axum::response::Response {
+        Router::new()
+            .route("/fees/current", get(|| async { "ok" }))
+            .layer(cors_layer(config))
+            .oneshot(
+                Request::builder()
+                    .method("OPTIONS")
+                    .uri("/fees/current")
+                    .header("origin", origin)
+                    .header("access-control-request-method", "GET")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    fn header<'a>(response: &'a axum::response::Response, name: &str) -> Option<&'a str> {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    }
+
+    #[tokio::test]
+    async fn preflight_reflects_configured_policy() {
+        let config = config(&[
+            ("ALLOWED_ORIGINS", "https://dash.example.com"),
+            ("CORS_ALLOWED_METHODS", "GET"),
+            ("CORS_ALLOW_CREDENTIALS", "true"),
+            ("CORS_MAX_AGE_SECONDS", "600"),
+        ]);
+
+        let response = preflight(&config, "https://dash.example.com").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            header(&response, "access-control-allow-origin"),
+            Some("https://dash.example.com")
+        );
+        assert_eq!(
+            header(&response, "access-control-allow-methods"),
+            Some("GET")
+        );
+        assert_eq!(
+            header(&response, "access-control-allow-credentials"),
+            Some("true")
+        );
+        assert_eq!(header(&response, "access-control-max-age"), Some("600"));
+
+        let response = preflight(&config, "https://other.example.com").await;
+        assert_eq!(header(&response, "access-control-allow-origin"), None);
+    }
+
+    #[tokio::test]
+    async fn wildcard_origin_allows_any() {
+        let config = config(&[("ALLOWED_ORIGINS", "*")]);
+        let response = preflight(&config, "https://anywhere.example.com").await;
+        assert_eq!(header(&response, "access-control-allow-origin"), Some("*"));
+    }
+}
@@ -0,0 +1,581 @@
+//! JWT bearer token authentication.
+//!
+//! Enabled by `JWT_JWKS_URL` and `JWT_ISSUER`. A request carrying
+//! `Authorization: Bearer <token>` is accepted when the token is an RS256
+//! JWT signed with one of the issuer's published keys, issued by
+//! `JWT_ISSUER` (for `JWT_AUDIENCE`, when set), within its validity period
+//! and granting the read or the admin scope. Routes layered with
+//! [`require_scope`] for [`Scope::Admin`] take the admin scope.
+//!
+//! A request without a bearer token is left to API-key authentication when
+//! `API_KEY` is configured, and rejected otherwise.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rsa::{pkcs1v15, signature::Verifier, BigUint, RsaPublicKey};
+use serde::{de::DeserializeOwned, Deserialize};
+use sha2::Sha256;
+use tokio::sync::RwLock;
+
+use super::auth::AuthenticatedClient;
+use crate::config::JwtConfig;
+use crate::error::AppError;
+
+/// Leeway for clock skew when checking `exp` and `nbf`, in seconds.
+const CLOCK_SKEW_SECONDS: i64 = 60;
+
+/// Least time between two fetches of the issuer's keys.
+const MIN_JWKS_REFRESH: Duration = Duration::from_secs(60);
+
+/// Access a token grants, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scope {
+    Read,
+    Admin,
+}
+
+/// A validated bearer token; added to the request's extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BearerToken {
+    pub subject: Option<String>,
+    /// Highest scope granted; `None` when the token grants neither.
+    pub scope: Option<Scope>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenHeader {
+    alg: String,
+    kid: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    iss: String,
+    exp: i64,
+    nbf: Option<i64>,
+    aud: Option<Audience>,
+    sub: Option<String>,
+    /// Space-separated, as in OAuth 2.0.
+    scope: Option<String>,
+    /// List form used by some identity providers.
+    scp: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Jwk {
+    kty: String,
+    kid: Option<String>,
+    #[serde(rename = "use")]
+    key_use: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct KeyCache {
+    /// Keys without a `kid` are stored under "".
+    keys: HashMap<String, RsaPublicKey>,
+    fetched_at: Option<Instant>,
+}
+
+/// Checks bearer tokens against one issuer's published keys
+#[derive(Debug)]
+pub struct JwtValidator {
+    config: JwtConfig,
+    http: reqwest::Client,
+    cache: RwLock<KeyCache>,
+}
+
+impl JwtValidator {
+    pub fn new(config: JwtConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+        Self {
+            config,
+            http,
+            cache: RwLock::new(KeyCache::default()),
+        }
+    }
+
+    /// A validator that trusts `keys`, by `kid`, without fetching any.
+    #[cfg(test)]
+    fn with_keys(config: JwtConfig, keys: HashMap<String, RsaPublicKey>) -> Self {
+        let validator = Self::new(config);
+        *validator.cache.try_write().unwrap() = KeyCache {
+            keys,
+            fetched_at: Some(Instant::now()),
+        };
+        validator
+    }
+
+    /// Verify `token` and read what it grants.
+    pub async fn validate(&self, token: &str) -> Result<BearerToken, AppError> {
+        let invalid = |reason: &str| AppError::Unauthorized(format!("invalid token: {}", reason));
+
+        let mut segments = token.split('.');
+        let (Some(header), Some(payload), Some(signature), None) = (
+            segments.next(),
+            segments.next(),
+            segments.next(),
+            segments.next(),
+        ) else {
+            return Err(invalid("malformed"));
+        };
+
+        let token_header: TokenHeader = decode_json(header).ok_or_else(|| invalid("malformed"))?;
+        if token_header.alg != "RS256" {
+            return Err(invalid("unsupported algorithm"));
+        }
+        let key = self
+            .key(token_header.kid.as_deref().unwrap_or_default())
+            .await
+            .ok_or_else(|| invalid("unknown signing key"))?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .ok()
+            .and_then(|bytes| pkcs1v15::Signature::try_from(bytes.as_slice()).ok())
+            .ok_or_else(|| invalid("malformed"))?;
+        pkcs1v15::VerifyingKey::<Sha256>::new(key)
+            .verify(
+                &token.as_bytes()[..header.len() + 1 + payload.len()],
+                &signature,
+            )
+            .map_err(|_| invalid("bad signature"))?;
+
+        let claims: Claims = decode_json(payload).ok_or_else(|| invalid("malformed claims"))?;
+        let now = chrono::Utc::now().timestamp();
+        if claims.iss != self.config.issuer {
+            return Err(invalid("wrong issuer"));
+        }
+        if claims.exp + CLOCK_SKEW_SECONDS <= now {
+            return Err(invalid("expired"));
+        }
+        if claims.nbf.is_some_and(|nbf| nbf - CLOCK_SKEW_SECONDS > now) {
+            return Err(invalid("not yet valid"));
+        }
+        if let Some(audience) = &self.config.audience {
+            let matches = match &claims.aud {
+                Some(Audience::One(aud)) => aud == audience,
+                Some(Audience::Many(auds)) => auds.contains(audience),
+                None => false,
+            };
+            if !matches {
+                return Err(invalid("wrong audience"));
+            }
+        }
+
+        let granted: Vec<&str> = claims
+            .scope
+            .iter()
+            .flat_map(|scope| scope.split_whitespace())
+            .chain(claims.scp.iter().flatten().map(String::as_str))
+            .collect();
+        let scope = if granted.contains(&self.config.admin_scope.as_str()) {
+            Some(Scope::Admin)
+        } else if granted.contains(&self.config.read_scope.as_str()) {
+            Some(Scope::Read)
+        } else {
+            None
+        };
+
+        Ok(BearerToken {
+            subject: claims.sub,
+            scope,
+        })
+    }
+
+    /// The issuer's key `kid`, refetching the keys when it is unknown and
+    /// they were not fetched within [`MIN_JWKS_REFRESH`]. The fetch runs
+    /// without holding the cache, so other requests keep validating against
+    /// the cached keys meanwhile.
+    async fn key(&self, kid: &str) -> Option<RsaPublicKey> {
+        {
+            let cache = self.cache.read().await;
+            if let Some(key) = cache.keys.get(kid) {
+                return Some(key.clone());
+            }
+            if cache
+                .fetched_at
+                .is_some_and(|at| at.elapsed() < MIN_JWKS_REFRESH)
+            {
+                return None;
+            }
+        }
+
+        {
+            let mut cache = self.cache.write().await;
+            // Another request may have refreshed the keys, or started to
+            if cache
+                .fetched_at
+                .is_some_and(|at| at.elapsed() < MIN_JWKS_REFRESH)
+            {
+                return cache.keys.get(kid).cloned();
+            }
+            cache.fetched_at = Some(Instant::now());
+        }
+
+        match self.fetch_keys().await {
+            Ok(keys) => {
+                let key = keys.get(kid).cloned();
+                self.cache.write().await.keys = keys;
+                key
+            }
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to fetch signing keys from {}: {}",
+                    self.config.jwks_url,
+                    err
+                );
+                self.cache.read().await.keys.get(kid).cloned()
+            }
+        }
+    }
+
+    async fn fetch_keys(&self) -> Result<HashMap<String, RsaPublicKey>, String> {
+        let jwks: Jwks = self
+            .http
+            .get(&self.config.jwks_url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(parse_jwks(jwks))
+    }
+}
+
+/// RSA signing keys of a key set, by `kid`.
+fn parse_jwks(jwks: Jwks) -> HashMap<String, RsaPublicKey> {
+    jwks.keys
+        .into_iter()
+        .filter(|jwk| jwk.kty == "RSA" && jwk.key_use.as_deref().unwrap_or("sig") == "sig")
+        .filter_map(|jwk| {
+            let n = URL_SAFE_NO_PAD.decode(jwk.n?).ok()?;
+            let e = URL_SAFE_NO_PAD.decode(jwk.e?).ok()?;
+            let key =
+                RsaPublicKey::new(BigUint::from_bytes_be(&n), BigUint::from_bytes_be(&e)).ok()?;
+            Some((jwk.kid.unwrap_or_default(), key))
+        })
+        .collect()
+}
+
+fn decode_json<T: DeserializeOwned>(segment: &str) -> Option<T> {
+    let bytes = URL_SAFE_NO_PAD.decode(segment).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// State of [`require_bearer_token`]
+#[derive(Debug, Clone)]
+pub struct JwtAuth {
+    pub validator: Arc<JwtValidator>,
+    /// `true` when requests without a bearer token are left to API-key
+    /// authentication.
+    pub api_key_fallback: bool,
+}
+
+pub async fn require_bearer_token(
+    State(auth): State<JwtAuth>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
+    let Some(token) = token else {
+        if auth.api_key_fallback {
+            return next.run(request).await;
+        }
+        return challenge(AppError::Unauthorized("missing bearer token".to_string()));
+    };
+
+    match auth.validator.validate(&token).await {
+        Ok(token) if token.scope.is_some() => {
+            if let Some(subject) = &token.subject {
+                request
+                    .extensions_mut()
+                    .insert(AuthenticatedClient(format!("sub:{}", subject)));
+            }
+            request.extensions_mut().insert(token);
+            next.run(request).await
+        }
+        Ok(_) => insufficient_scope(),
+        Err(err) => challenge(err),
+    }
+}
+
+/// Reject requests whose bearer token grants less than the state's scope.
+/// Requests authenticated otherwise pass.
+pub async fn require_scope(
+    State(required): State<Scope>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match request.extensions().get::<BearerToken>() {
+        Some(token) if token.scope < Some(required) => insufficient_scope(),
+        _ => next.run(request).await,
+    }
+}
+
+fn challenge(err: AppError) -> Response {
+    let mut response = err.into_response();
+    response.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        header::HeaderValue::from_static("Bearer"),
+    );
+    response
+}
+
+fn insufficient_scope() -> Response {
+    let mut response =
+        AppError::Forbidden("token lacks the required scope".to_string()).into_response();
+    response.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        header::HeaderValue::from_static(r#"Bearer error="insufficient_scope""#),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body, http::StatusCode, middleware::from_fn_with_state, routing::get, Router,
+    };
+    use rsa::{
+        signature::{SignatureEncoding, Signer},
+        traits::PublicKeyParts,
+        RsaPrivateKey,
+    };
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    const ISSUER: &str = "https://auth.example.com/";
+
+    fn config() -> JwtConfig {
+        JwtConfig {
+            issuer: ISSUER.to_string(),
+            jwks_url: "https://auth.example.com/jwks".to_string(),
+            audience: Some("fee-tracker".to_string()),
+            read_scope: "fees:read".to_string(),
+            admin_scope: "fees:admin".to_string(),
+        }
+    }
+
+    fn sign(key: &RsaPrivateKey, header: serde_json::Value, claims: serde_json::Value) -> String {
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature =
+            pkcs1v15::SigningKey::<Sha256>::new(key.clone()).sign(signing_input.as_bytes());
+        format!(
+            "{}.{}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature.to_vec())
+        )
+    }
+
+    fn claims(scope: &str, expires_in: i64) -> serde_json::Value {
+        json!({
+            "iss": ISSUER,
+            "aud": ["fee-tracker"],
+            "sub": "dashboard",
+            "exp": chrono::Utc::now().timestamp() + expires_in,
+            "scope": scope,
+        })
+    }
+
+    async fn ok_handler() -> &'static str {
+        "ok"
+    }
+
+    async fn status(app: &Router, uri: &str, token: Option<&str>) -> StatusCode {
+        let mut request = Request::builder().uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        app.clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn tokens_are_checked_and_scoped_per_route() {
+        let key = RsaPrivateKey::new(&mut rand::rngs::OsRng, 1024).unwrap();
+        let other_key = RsaPrivateKey::new(&mut rand::rngs::OsRng, 1024).unwrap();
+        let validator = Arc::new(JwtValidator::with_keys(
+            config(),
+            HashMap::from([("k1".to_string(), key.to_public_key())]),
+        ));
+        let app = Router::new()
+            .route("/fees", get(ok_handler))
+            .merge(
+                Router::new()
+                    .route("/admin", get(ok_handler))
+                    .route_layer(from_fn_with_state(Scope::Admin, require_scope)),
+            )
+            .layer(from_fn_with_state(
+                JwtAuth {
+                    validator,
+                    api_key_fallback: false,
+                },
+                require_bearer_token,
+            ));
+        let header = json!({ "alg": "RS256", "kid": "k1" });
+
+        let reader = sign(&key, header.clone(), claims("openid fees:read", 300));
+        assert_eq!(status(&app, "/fees", Some(&reader)).await, StatusCode::OK);
+        assert_eq!(
+            status(&app, "/admin", Some(&reader)).await,
+            StatusCode::FORBIDDEN
+        );
+
+        let admin = sign(&key, header.clone(), claims("fees:admin", 300));
+        assert_eq!(status(&app, "/admin", Some(&admin)).await, StatusCode::OK);
+        assert_eq!(status(&app, "/fees", Some(&admin)).await, StatusCode::OK);
+
+        let unscoped = sign(&key, header.clone(), claims("openid", 300));
+        assert_eq!(
+            status(&app, "/fees", Some(&unscoped)).await,
+            StatusCode::FORBIDDEN
+        );
+
+        for rejected in [
+            sign(&key, header.clone(), claims("fees:read", -300)),
+            sign(&other_key, header.clone(), claims("fees:read", 300)),
+            sign(
+                &key,
+                header.clone(),
+                json!({ "iss": "https://evil.example.com/", "aud": "fee-tracker",
+                        "exp": chrono::Utc::now().timestamp() + 300, "scope": "fees:read" }),
+            ),
+            sign(
+                &key,
+                json!({ "alg": "none", "kid": "k1" }),
+                claims("fees:read", 300),
+            ),
+            "not.a.token".to_string(),
+        ] {
+            assert_eq!(
+                status(&app, "/fees", Some(&rejected)).await,
+                StatusCode::UNAUTHORIZED
+            );
+        }
+        assert_eq!(status(&app, "/fees", None).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn cached_keys_validate_while_the_key_set_is_refetched() {
+        use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
+
+        let key = RsaPrivateKey::new(&mut rand::rngs::OsRng, 1024).unwrap();
+        let rotated = RsaPrivateKey::new(&mut rand::rngs::OsRng, 1024)
+            .unwrap()
+            .to_public_key();
+        let server = MockServer::start().await;
+        Mock::given(path("/jwks"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "keys": [{
+                        "kty": "RSA",
+                        "kid": "k2",
+                        "n": URL_SAFE_NO_PAD.encode(rotated.n().to_bytes_be()),
+                        "e": URL_SAFE_NO_PAD.encode(rotated.e().to_bytes_be()),
+                    }] }))
+                    .set_delay(Duration::from_millis(500)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // k1 is cached, but the keys are due for a refresh
+        let validator = Arc::new(JwtValidator::new(JwtConfig {
+            jwks_url: format!("{}/jwks", server.uri()),
+            ..config()
+        }));
+        validator
+            .cache
+            .write()
+            .await
+            .keys
+            .insert("k1".to_string(), key.to_public_key());
+
+        let refreshing = tokio::spawn({
+            let validator = validator.clone();
+            async move { validator.key("k2").await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let token = sign(
+            &key,
+            json!({ "alg": "RS256", "kid": "k1" }),
+            claims("fees:read", 300),
+        );
+        let validated =
+            tokio::time::timeout(Duration::from_millis(200), validator.validate(&token))
+                .await
+                .expect("validation waited on the key fetch");
+        assert!(validated.is_ok());
+        // Unknown keys are not fetched again while the refresh is in flight
+        assert!(validator.key("k3").await.is_none());
+
+        assert_eq!(refreshing.await.unwrap(), Some(rotated));
+        assert!(validator.key("k1").await.is_none());
+    }
+
+    #[test]
+    fn jwks_keeps_rsa_signing_keys() {
+        let key = RsaPrivateKey::new(&mut rand::rngs::OsRng, 1024)
+            .unwrap()
+            .to_public_key();
+        let jwks: Jwks = serde_json::from_value(json!({
+            "keys": [
+                {
+                    "kty": "RSA",
+                    "kid": "k1",
+                    "use": "sig",
+                    "n": URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+                    "e": URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+                },
+                { "kty": "RSA", "kid": "k2", "use": "enc", "n": "AQAB", "e": "AQAB" },
+                { "kty": "EC", "kid": "k3", "crv": "P-256", "x": "", "y": "" },
+            ]
+        }))
+        .unwrap();
+
+        let keys = parse_jwks(jwks);
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys["k1"], key);
+    }
+}
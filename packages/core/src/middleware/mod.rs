@@ -1,2 +1,6 @@
 pub mod auth;
+pub mod compression;
+pub mod cors;
+pub mod jwt;
 pub mod rate_limit;
+pub mod request_id;
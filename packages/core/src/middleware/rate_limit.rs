@@ -6,12 +6,16 @@ use axum::{
     extract::{connect_info::ConnectInfo, Request, State},
     http::{header, HeaderValue, StatusCode},
     middleware::Next,
-    response::{IntoResponse, Response},
-    Json,
+    response::Response,
 };
 use dashmap::DashMap;
 use serde_json::json;
 
+use super::auth::AuthenticatedClient;
+use crate::api::networks::route_path;
+use crate::config::RouteRateLimit;
+use crate::error::AppError;
+
 const X_FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
 const X_RATE_LIMIT_LIMIT_HEADER: &str = "x-ratelimit-limit";
 const X_RATE_LIMIT_REMAINING_HEADER: &str = "x-ratelimit-remaining";
@@ -46,6 +50,16 @@ impl TokenBucket {
         Err(self.seconds_until_next_token())
     }
 
+    /// Whether a token is available, without taking it.
+    fn retry_after(&mut self) -> Result<(), u64> {
+        self.refill();
+        if self.tokens >= 1.0 {
+            Ok(())
+        } else {
+            Err(self.seconds_until_next_token())
+        }
+    }
+
     fn refill(&mut self) {
         let now = Instant::now();
         let elapsed = now.duration_since(self.last_refill).as_secs_f64();
@@ -67,8 +81,41 @@ impl TokenBucket {
     }
 }
 
+/// Who a bucket belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ClientKey {
+    /// Identity established by authentication, which runs first. Keys or
+    /// tokens the request merely carries are never trusted here, so
+    /// rotating made-up keys cannot buy fresh buckets.
+    Authenticated(AuthenticatedClient),
+    Ip(IpAddr),
+}
+
+impl ClientKey {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Authenticated(_) => "authenticated",
+            Self::Ip(_) => "ip",
+        }
+    }
+}
+
+/// Paths under `prefix` limited separately from the rest
+struct RouteGroup {
+    prefix: String,
+    capacity: u32,
+    refill_rate: f64,
+}
+
 pub struct RateLimitState {
-    buckets: DashMap<IpAddr, TokenBucket>,
+    /// Buckets per route group index (`None` for the default limit) and
+    /// client.
+    buckets: DashMap<(Option<usize>, ClientKey), TokenBucket>,
+    /// Failed authentication attempts per IP, drawn from before
+    /// authentication runs; see `limit_rejected_auth`.
+    rejected: DashMap<IpAddr, TokenBucket>,
+    /// Longest prefix first, so the most specific group wins.
+    groups: Vec<RouteGroup>,
     capacity: u32,
     refill_rate: f64,
 }
@@ -88,10 +135,48 @@ impl RateLimitState {
     fn with_refill_rate(capacity: u32, refill_rate: f64) -> Self {
         Self {
             buckets: DashMap::new(),
+            rejected: DashMap::new(),
+            groups: Vec::new(),
             capacity,
             refill_rate,
         }
     }
+
+    /// Limit the routes under each prefix on their own. A client's requests
+    /// there do not count against its default limit.
+    pub fn with_route_limits(mut self, limits: &[RouteRateLimit]) -> Self {
+        self.groups = limits
+            .iter()
+            .map(|limit| {
+                let capacity = limit.per_minute.max(1);
+                RouteGroup {
+                    prefix: limit.prefix.clone(),
+                    capacity,
+                    refill_rate: f64::from(capacity) / 60.0,
+                }
+            })
+            .collect();
+        self.groups
+            .sort_by_key(|group| std::cmp::Reverse(group.prefix.len()));
+        self
+    }
+
+    /// Index of the group `path` belongs to, if any.
+    fn group_of(&self, path: &str) -> Option<usize> {
+        let path = route_path(path);
+        self.groups.iter().position(|group| {
+            path.strip_prefix(group.prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+
+    /// Capacity and refill rate of a group's buckets.
+    fn limits(&self, group: Option<usize>) -> (u32, f64) {
+        match group {
+            Some(index) => (self.groups[index].capacity, self.groups[index].refill_rate),
+            None => (self.capacity, self.refill_rate),
+        }
+    }
 }
 
 /// Remove token buckets that have not been refilled in the last 2 minutes.
@@ -102,6 +187,56 @@ fn evict_stale_buckets(state: &RateLimitState) {
     state
         .buckets
         .retain(|_, bucket| bucket.last_refill >= cutoff);
+    state
+        .rejected
+        .retain(|_, bucket| bucket.last_refill >= cutoff);
+}
+
+/// Throttle, by IP, clients whose credentials keep being rejected. Runs
+/// outside authentication, which `enforce_rate_limit` must run within to
+/// key on the client, so bad keys and tokens are limited too and stop
+/// costing key lookups once an IP has used up its default limit on them.
+pub async fn limit_rejected_auth(
+    State(state): State<Arc<RateLimitState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let ip = extract_client_ip(&request);
+    let capacity = state.capacity;
+
+    if state.rejected.len() > 10_000 {
+        evict_stale_buckets(&state);
+    }
+
+    let blocked = state
+        .rejected
+        .get_mut(&ip)
+        .and_then(|mut bucket| bucket.retry_after().err());
+    if let Some(retry_after) = blocked {
+        let mut response = AppError::TooManyRequests(format!(
+            "Too many rejected credentials. Try again in {} seconds.",
+            retry_after
+        ))
+        .into_response_with(json!({
+            "limit_per_minute": capacity,
+            "retry_after_seconds": retry_after,
+            "client": "ip",
+            "route_group": null,
+        }));
+        attach_rate_limit_headers(&mut response, capacity, 0, retry_after);
+        insert_number_header(&mut response, header::RETRY_AFTER.as_str(), retry_after);
+        return response;
+    }
+
+    let response = next.run(request).await;
+    if response.status() == StatusCode::UNAUTHORIZED {
+        let _ = state
+            .rejected
+            .entry(ip)
+            .or_insert_with(|| TokenBucket::new(f64::from(capacity), state.refill_rate))
+            .try_consume();
+    }
+    response
 }
 
 pub async fn enforce_rate_limit(
@@ -109,7 +244,9 @@ pub async fn enforce_rate_limit(
     request: Request,
     next: Next,
 ) -> Response {
-    let client_ip = extract_client_ip(&request);
+    let client = extract_client_key(&request);
+    let group = state.group_of(request.uri().path());
+    let (capacity, refill_rate) = state.limits(group);
 
     // Evict stale entries once the map grows large (probabilistic amortisation).
     if state.buckets.len() > 10_000 {
@@ -119,8 +256,8 @@ pub async fn enforce_rate_limit(
     let (allowed, remaining, reset_secs, retry_after_secs) = {
         let mut bucket = state
             .buckets
-            .entry(client_ip)
-            .or_insert_with(|| TokenBucket::new(f64::from(state.capacity), state.refill_rate));
+            .entry((group, client.clone()))
+            .or_insert_with(|| TokenBucket::new(f64::from(capacity), refill_rate));
 
         match bucket.try_consume() {
             Ok(remaining) => (true, remaining, bucket.seconds_until_next_token(), None),
@@ -130,24 +267,35 @@ pub async fn enforce_rate_limit(
 
     if !allowed {
         let retry_after = retry_after_secs.unwrap_or(1);
-        let mut response = (
-            StatusCode::TOO_MANY_REQUESTS,
-            Json(json!({
-                "error": format!("Rate limit exceeded. Try again in {} seconds.", retry_after)
-            })),
-        )
-            .into_response();
-
-        attach_rate_limit_headers(&mut response, state.capacity, remaining, reset_secs);
+        let mut response = AppError::TooManyRequests(format!(
+            "Rate limit exceeded. Try again in {} seconds.",
+            retry_after
+        ))
+        .into_response_with(json!({
+            "limit_per_minute": capacity,
+            "retry_after_seconds": retry_after,
+            "client": client.kind(),
+            "route_group": group.map(|index| state.groups[index].prefix.as_str()),
+        }));
+
+        attach_rate_limit_headers(&mut response, capacity, remaining, reset_secs);
         insert_number_header(&mut response, header::RETRY_AFTER.as_str(), retry_after);
         return response;
     }
 
     let mut response = next.run(request).await;
-    attach_rate_limit_headers(&mut response, state.capacity, remaining, reset_secs);
+    attach_rate_limit_headers(&mut response, capacity, remaining, reset_secs);
     response
 }
 
+/// The client authentication identified, its IP otherwise.
+fn extract_client_key(request: &Request) -> ClientKey {
+    match request.extensions().get::<AuthenticatedClient>() {
+        Some(client) => ClientKey::Authenticated(client.clone()),
+        None => ClientKey::Ip(extract_client_ip(request)),
+    }
+}
+
 fn extract_client_ip(request: &Request) -> IpAddr {
     // Determine the direct TCP peer address first.
     let peer_ip = request
@@ -204,7 +352,7 @@ mod tests {
     use axum::{
         body::{to_bytes, Body},
         extract::connect_info::ConnectInfo,
-        http::Request,
+        http::{Request, StatusCode},
         middleware::from_fn_with_state,
         routing::get,
         Router,
@@ -256,6 +404,73 @@ mod tests {
         assert!(headers.get(X_RATE_LIMIT_RESET_HEADER).is_some());
     }
 
+    #[tokio::test]
+    async fn rejected_credentials_are_limited_before_authentication() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Stands in for authentication: counts every key it checks
+        let checked = Arc::new(AtomicUsize::new(0));
+        let auth = {
+            let checked = checked.clone();
+            move |request: axum::extract::Request, next: Next| {
+                let checked = checked.clone();
+                async move {
+                    checked.fetch_add(1, Ordering::SeqCst);
+                    if request.headers().contains_key("x-api-key") {
+                        next.run(request).await
+                    } else {
+                        axum::response::IntoResponse::into_response(AppError::Unauthorized(
+                            "missing key".to_string(),
+                        ))
+                    }
+                }
+            }
+        };
+        let state = Arc::new(RateLimitState::new(2));
+        let app = Router::new()
+            .route("/test", get(ok_handler))
+            .layer(axum::middleware::from_fn(auth))
+            .layer(from_fn_with_state(state, limit_rejected_auth));
+        let addr: SocketAddr = "192.0.2.40:40000".parse().unwrap();
+        let request = |key: Option<&str>| {
+            let mut request = Request::builder().uri("/test");
+            if let Some(key) = key {
+                request = request.header("x-api-key", key);
+            }
+            let mut request = request.body(Body::empty()).unwrap();
+            request.extensions_mut().insert(ConnectInfo(addr));
+            app.clone().oneshot(request)
+        };
+
+        // Accepted requests do not count against the IP
+        for _ in 0..3 {
+            assert_eq!(
+                request(Some("good")).await.unwrap().status(),
+                StatusCode::OK
+            );
+        }
+        for _ in 0..2 {
+            assert_eq!(
+                request(None).await.unwrap().status(),
+                StatusCode::UNAUTHORIZED
+            );
+        }
+        let blocked = request(None).await.unwrap();
+        assert_eq!(blocked.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(blocked.headers().get(header::RETRY_AFTER).is_some());
+        assert_eq!(checked.load(Ordering::SeqCst), 5);
+
+        // Another IP is unaffected
+        let mut other = Request::builder().uri("/test").body(Body::empty()).unwrap();
+        other.extensions_mut().insert(ConnectInfo(
+            "192.0.2.41:40000".parse::<SocketAddr>().unwrap(),
+        ));
+        assert_eq!(
+            app.clone().oneshot(other).await.unwrap().status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
     #[tokio::test]
     async fn first_n_requests_within_limit_succeed() {
         let app = build_test_app(Arc::new(RateLimitState::new(3)));
@@ -287,10 +502,12 @@ mod tests {
 
         let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
         let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["code"], "too_many_requests");
         assert_eq!(
-            payload["error"],
+            payload["detail"],
             format!("Rate limit exceeded. Try again in {} seconds.", retry_after)
         );
+        assert_eq!(payload["retry_after_seconds"], retry_after);
     }
 
     #[tokio::test]
@@ -367,4 +584,112 @@ mod tests {
         let second = request_with_connect_info(&app, client_addr).await;
         assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
     }
+
+    const X_API_KEY_HEADER: &str = "x-api-key";
+
+    /// Stands in for authentication: takes any key starting with `valid-`.
+    async fn fake_auth(mut request: axum::extract::Request, next: Next) -> Response {
+        let key = request
+            .headers()
+            .get(X_API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|key| key.starts_with("valid-"))
+            .map(AuthenticatedClient::api_key);
+        if let Some(client) = key {
+            request.extensions_mut().insert(client);
+        }
+        next.run(request).await
+    }
+
+    fn build_authenticated_app(state: Arc<RateLimitState>) -> Router {
+        Router::new()
+            .route("/test", get(ok_handler))
+            .layer(from_fn_with_state(state, enforce_rate_limit))
+            .layer(axum::middleware::from_fn(fake_auth))
+    }
+
+    async fn request_with_key(app: &Router, uri: &str, key: &str) -> Response {
+        let mut request = Request::builder()
+            .uri(uri)
+            .header(X_API_KEY_HEADER, key)
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(ConnectInfo(
+            "192.0.2.50:40000".parse::<SocketAddr>().unwrap(),
+        ));
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn authenticated_clients_behind_one_ip_have_independent_buckets() {
+        let app = build_authenticated_app(Arc::new(RateLimitState::new(1)));
+
+        let first = request_with_key(&app, "/test", "valid-dashboard-a").await;
+        assert_eq!(first.status(), StatusCode::OK);
+        let second = request_with_key(&app, "/test", "valid-dashboard-a").await;
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = to_bytes(second.into_body(), usize::MAX).await.unwrap();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["client"], "authenticated");
+        assert_eq!(payload["limit_per_minute"], 1);
+        assert!(payload["route_group"].is_null());
+
+        let other = request_with_key(&app, "/test", "valid-dashboard-b").await;
+        assert_eq!(other.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn rotating_unverified_keys_stays_on_the_ip_bucket() {
+        let state = Arc::new(RateLimitState::new(2));
+        let app = build_authenticated_app(state.clone());
+
+        for key in ["bogus-1", "bogus-2"] {
+            let response = request_with_key(&app, "/test", key).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let blocked = request_with_key(&app, "/test", "bogus-3").await;
+        assert_eq!(blocked.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = to_bytes(blocked.into_body(), usize::MAX).await.unwrap();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["client"], "ip");
+        assert_eq!(state.buckets.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn route_groups_have_their_own_limits() {
+        let state = RateLimitState::new(5).with_route_limits(&[
+            RouteRateLimit {
+                prefix: "/admin".to_string(),
+                per_minute: 1,
+            },
+            RouteRateLimit {
+                prefix: "/admin/cursors".to_string(),
+                per_minute: 2,
+            },
+        ]);
+        let app = Router::new()
+            .route("/test", get(ok_handler))
+            .route("/admin/congestion", get(ok_handler))
+            .route("/v1/admin/cursors", get(ok_handler))
+            .layer(from_fn_with_state(Arc::new(state), enforce_rate_limit));
+
+        let admin = request_with_key(&app, "/admin/congestion", "ops").await;
+        assert_eq!(admin.status(), StatusCode::OK);
+        assert_rate_limit_headers(&admin, 1);
+        let blocked = request_with_key(&app, "/admin/congestion", "ops").await;
+        assert_eq!(blocked.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = to_bytes(blocked.into_body(), usize::MAX).await.unwrap();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["route_group"], "/admin");
+
+        // The longest matching prefix applies, with or without a version
+        for _ in 0..2 {
+            let cursors = request_with_key(&app, "/v1/admin/cursors", "ops").await;
+            assert_eq!(cursors.status(), StatusCode::OK);
+        }
+
+        let other = request_with_key(&app, "/test", "ops").await;
+        assert_eq!(other.status(), StatusCode::OK);
+        assert_rate_limit_headers(&other, 5);
+    }
 }
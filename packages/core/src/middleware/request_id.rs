@@ -0,0 +1,246 @@
+//! Request IDs and W3C trace context.
+//!
+//! Every request carries an `X-Request-Id` — the client's, when it sent a
+//! usable one, otherwise a generated one — and a trace ID, continued from
+//! its `traceparent` header when it had a valid one. Both are recorded on
+//! the request's tracing span, so every log line for the request carries
+//! them, and both are echoed in the response.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use rand::RngCore;
+use tracing::Instrument;
+
+pub const X_REQUEST_ID_HEADER: &str = "x-request-id";
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Longest client-supplied request ID that is kept.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// IDs of the request being served, for handlers that want them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestContext {
+    pub request_id: String,
+    /// 32 hex digits; shared by every service the trace passes through.
+    pub trace_id: String,
+    /// 16 hex digits identifying this server's part of the trace.
+    pub span_id: String,
+    pub trace_flags: String,
+}
+
+impl RequestContext {
+    fn from_request(request: &Request) -> Self {
+        let header = |name: &str| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        let request_id = header(X_REQUEST_ID_HEADER)
+            .filter(|id| is_valid_request_id(id))
+            .map(str::to_string)
+            .unwrap_or_else(|| random_hex(16));
+        let (trace_id, trace_flags) = header(TRACEPARENT_HEADER)
+            .and_then(parse_traceparent)
+            .unwrap_or_else(|| (random_hex(16), "00".to_string()));
+
+        Self {
+            request_id,
+            trace_id,
+            span_id: random_hex(8),
+            trace_flags,
+        }
+    }
+
+    /// The `traceparent` for calls made on behalf of this request.
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-{}", self.trace_id, self.span_id, self.trace_flags)
+    }
+}
+
+/// Attach a [`RequestContext`] to the request, run it inside a span that
+/// carries the IDs and echo them in the response.
+pub async fn propagate_request_id(mut request: Request, next: Next) -> Response {
+    let context = RequestContext::from_request(&request);
+    let span = tracing::info_span!(
+        "request",
+        request_id = %context.request_id,
+        trace_id = %context.trace_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+
+    let request_id = HeaderValue::from_str(&context.request_id).ok();
+    let traceparent = HeaderValue::from_str(&context.traceparent()).ok();
+    if let Some(value) = &request_id {
+        request
+            .headers_mut()
+            .insert(HeaderName::from_static(X_REQUEST_ID_HEADER), value.clone());
+    }
+    request.extensions_mut().insert(context);
+
+    let mut response = next.run(request).instrument(span).await;
+    if let Some(value) = request_id {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(X_REQUEST_ID_HEADER), value);
+    }
+    if let Some(value) = traceparent {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(TRACEPARENT_HEADER), value);
+    }
+    response
+}
+
+/// Printable ASCII without spaces, so it is safe to log and echo.
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// The trace ID and flags of a valid `traceparent`, per the W3C Trace
+/// Context spec: `version-traceid-parentid-flags`, all lowercase hex.
+fn parse_traceparent(value: &str) -> Option<(String, String)> {
+    let parts: Vec<&str> = value.trim().split('-').collect();
+    let [version, trace_id, parent_id, flags] = parts.get(..4)? else {
+        return None;
+    };
+    let is_hex = |s: &str, len: usize| {
+        s.len() == len && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    };
+    let is_zero = |s: &str| s.bytes().all(|b| b == b'0');
+    if !is_hex(version, 2) || *version == "ff" || (*version == "00" && parts.len() != 4) {
+        return None;
+    }
+    if !is_hex(trace_id, 32) || is_zero(trace_id) || !is_hex(parent_id, 16) || is_zero(parent_id) {
+        return None;
+    }
+    if !is_hex(flags, 2) {
+        return None;
+    }
+    Some((trace_id.to_string(), flags.to_string()))
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Extension, Router};
+    use tower::ServiceExt;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/fees",
+                get(|Extension(context): Extension<RequestContext>| async move {
+                    context.request_id
+                }),
+            )
+            .layer(axum::middleware::from_fn(propagate_request_id))
+    }
+
+    async fn send(headers: &[(&str, &str)]) -> (Option<String>, Option<String>, String) {
+        let mut request = Request::builder().uri("/fees");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let response = app()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .map(|v| v.to_str().unwrap().to_string())
+        };
+        let (request_id, traceparent) = (header(X_REQUEST_ID_HEADER), header(TRACEPARENT_HEADER));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (
+            request_id,
+            traceparent,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn echoes_a_client_request_id() {
+        let (request_id, _, seen) = send(&[(X_REQUEST_ID_HEADER, "client-42")]).await;
+        assert_eq!(request_id.as_deref(), Some("client-42"));
+        assert_eq!(seen, "client-42");
+    }
+
+    #[tokio::test]
+    async fn generates_a_request_id_when_missing_or_unusable() {
+        let (request_id, _, seen) = send(&[]).await;
+        let request_id = request_id.unwrap();
+        assert_eq!(request_id.len(), 32);
+        assert_eq!(seen, request_id);
+
+        let too_long = "a".repeat(MAX_REQUEST_ID_LEN + 1);
+        for bad in ["has space", too_long.as_str()] {
+            let (request_id, _, _) = send(&[(X_REQUEST_ID_HEADER, bad)]).await;
+            assert_ne!(request_id.as_deref(), Some(bad));
+        }
+    }
+
+    #[tokio::test]
+    async fn continues_an_incoming_trace() {
+        let (_, traceparent, _) = send(&[(TRACEPARENT_HEADER, TRACEPARENT)]).await;
+        let traceparent = traceparent.unwrap();
+        assert!(traceparent.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+        assert!(traceparent.ends_with("-01"));
+        assert_ne!(traceparent, TRACEPARENT, "this server gets its own span id");
+    }
+
+    #[tokio::test]
+    async fn starts_a_trace_when_traceparent_is_missing_or_invalid() {
+        let (_, traceparent, _) = send(&[]).await;
+        assert!(parse_traceparent(&traceparent.unwrap()).is_some());
+
+        let (_, traceparent, _) = send(&[(
+            TRACEPARENT_HEADER,
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        )])
+        .await;
+        let traceparent = traceparent.unwrap();
+        assert!(!traceparent.contains("-00000000000000000000000000000000-"));
+        assert!(traceparent.ends_with("-00"));
+    }
+
+    #[test]
+    fn parses_traceparent_per_spec() {
+        assert_eq!(
+            parse_traceparent(TRACEPARENT),
+            Some((
+                "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+                "01".to_string()
+            ))
+        );
+        // Later versions may append fields.
+        assert!(parse_traceparent(&format!("01{}-extra", &TRACEPARENT[2..])).is_some());
+        for invalid in [
+            "",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+        ] {
+            assert_eq!(parse_traceparent(invalid), None, "{}", invalid);
+        }
+    }
+}
@@ -7,11 +7,23 @@
 //! On startup, [`FeeRepository::fetch_since`] rehydrates the in-memory
 //! [`FeeHistoryStore`] from the last 24 hours of persisted data.
 
-use chrono::{DateTime, Utc};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::SqlitePool;
-
-use crate::insights::types::FeeDataPoint;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+
+use crate::accounts::DailyFeeSpend;
+use crate::config::StellarNetwork;
+use crate::insights::cursor::{CursorStore, PagingCursor};
+use crate::insights::error::InsightsError;
+use crate::insights::types::{
+    BudgetPeriod, CongestionEpisode, CongestionState, CongestionTransition, CurrentInsights,
+    EnvelopeDetails, ExtremeRange, ExtremeValue, FeeBudget, FeeDataPoint, FeeDistribution,
+    LedgerInfo, OperationCategory, SeasonalSlot, SurgeEpisode,
+};
+use crate::leaderboard::{TopFeePayer, TopFeeTransaction};
+use crate::rollup::{FeeRollup, Resolution};
 
 /// Valid threshold values for alert configurations.
 /// Must match the `SpikeSeverity` enum variants used by the insights engine.
@@ -41,14 +53,194 @@ pub struct AlertEvent {
     pub triggered_at: String,
 }
 
+/// Saved ingestion position of one network.
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestionCursor {
+    /// Empty for a cursor saved through an unscoped repository.
+    pub network: String,
+    pub paging_token: String,
+    pub ledger_sequence: u64,
+    pub updated_at: String,
+}
+
+/// A stored API key, without the key itself.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKey {
+    pub id: i64,
+    pub name: String,
+    /// First characters of the key, to tell keys apart.
+    pub prefix: String,
+    pub created_at: String,
+    /// `None` while the key is accepted.
+    pub revoked_at: Option<String>,
+}
+
+/// Position of a stored fee data point: points are ordered by timestamp,
+/// then by row id, which breaks ties between points of one ledger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PointKey {
+    pub timestamp: DateTime<Utc>,
+    pub id: i64,
+}
+
+/// Narrows fee data point queries; every unset field matches all points.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PointFilter {
+    pub min_fee: Option<u64>,
+    pub max_fee: Option<u64>,
+    pub min_ledger: Option<u64>,
+    pub max_ledger: Option<u64>,
+    /// Lowercase hex start of the transaction hash.
+    pub hash_prefix: Option<String>,
+    pub operation_category: Option<OperationCategory>,
+}
+
+impl PointFilter {
+    /// Append `AND ...` conditions for the set fields, with their values
+    /// bound as parameters.
+    fn push_conditions(&self, query: &mut QueryBuilder<'_, Sqlite>) {
+        if let Some(fee) = self.min_fee {
+            query.push(" AND fee_amount >= ").push_bind(fee as i64);
+        }
+        if let Some(fee) = self.max_fee {
+            query.push(" AND fee_amount <= ").push_bind(fee as i64);
+        }
+        if let Some(ledger) = self.min_ledger {
+            query
+                .push(" AND ledger_sequence >= ")
+                .push_bind(ledger as i64);
+        }
+        if let Some(ledger) = self.max_ledger {
+            query
+                .push(" AND ledger_sequence <= ")
+                .push_bind(ledger as i64);
+        }
+        if let Some(prefix) = &self.hash_prefix {
+            // A range rather than LIKE keeps the hash index usable; every
+            // hex digit sorts before 'g'
+            query
+                .push(" AND transaction_hash >= ")
+                .push_bind(prefix.clone())
+                .push(" AND transaction_hash < ")
+                .push_bind(format!("{}g", prefix));
+        }
+        if let Some(category) = self.operation_category {
+            query
+                .push(" AND operation_category = ")
+                .push_bind(category.as_str());
+        }
+    }
+}
+
+/// Which stored insight snapshots fall in a range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotSpan {
+    pub count: u64,
+    pub first_id: Option<i64>,
+    pub last_id: Option<i64>,
+    /// Capture time of the newest snapshot; `None` when there are none.
+    pub latest: Option<DateTime<Utc>>,
+}
+
+fn without_keys(points: Vec<(PointKey, FeeDataPoint)>) -> Vec<FeeDataPoint> {
+    points.into_iter().map(|(_, point)| point).collect()
+}
+
+fn ledger_from_row(row: &SqliteRow) -> Result<LedgerInfo, sqlx::Error> {
+    use sqlx::Row;
+    let closed_at: String = row.try_get("closed_at")?;
+    let closed_at = DateTime::parse_from_rfc3339(&closed_at)
+        .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+        .with_timezone(&Utc);
+    Ok(LedgerInfo {
+        sequence: row.try_get::<i64, _>("sequence")? as u64,
+        closed_at,
+        transaction_count: row.try_get::<i64, _>("transaction_count")? as u32,
+        operation_count: row.try_get::<i64, _>("operation_count")? as u32,
+        max_tx_set_size: row.try_get::<i64, _>("max_tx_set_size")? as u32,
+        base_fee: row.try_get::<i64, _>("base_fee")? as u64,
+    })
+}
+
+fn congestion_episode_from_row(row: &SqliteRow) -> Result<CongestionEpisode, sqlx::Error> {
+    use sqlx::Row;
+    let parse_time = |value: &str| {
+        DateTime::parse_from_rfc3339(value)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+    };
+    let started_at: String = row.try_get("started_at")?;
+    let ended_at: Option<String> = row.try_get("ended_at")?;
+    let peak_state: String = row.try_get("peak_state")?;
+
+    Ok(CongestionEpisode {
+        id: row.try_get("id")?,
+        start_time: parse_time(&started_at)?,
+        end_time: ended_at.as_deref().map(parse_time).transpose()?,
+        peak_state: CongestionState::parse(&peak_state).ok_or_else(|| {
+            sqlx::Error::Decode(format!("unknown congestion state: {}", peak_state).into())
+        })?,
+        peak_multiplier: row.try_get("peak_multiplier")?,
+    })
+}
+
 /// Repository for reading and writing fee data to SQLite.
+///
+/// Fee data point queries are scoped to a network when one is set via
+/// [`FeeRepository::with_network`]; an unscoped repository sees every row.
 pub struct FeeRepository {
     pool: SqlitePool,
+    network: Option<String>,
+    record_fee_accounts: bool,
 }
 
 impl FeeRepository {
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            network: None,
+            record_fee_accounts: false,
+        }
+    }
+
+    /// Scope fee data point reads, writes and pruning to `network`.
+    pub fn with_network(mut self, network: StellarNetwork) -> Self {
+        self.network = Some(network.as_str().to_string());
+        self
+    }
+
+    /// Store the account that paid each fee along with the point.
+    pub fn with_fee_accounts(mut self, enabled: bool) -> Self {
+        self.record_fee_accounts = enabled;
+        self
+    }
+
+    /// Repository over the same pool, scoped to a different network.
+    pub fn for_network(&self, network: StellarNetwork) -> Self {
+        Self::new(self.pool.clone())
+            .with_network(network)
+            .with_fee_accounts(self.record_fee_accounts)
+    }
+
+    /// Round-trip a trivial query to check the database is reachable.
+    pub async fn ping(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Assign rows stored before networks were tracked to this repository's
+    /// network. Returns the number of rows claimed; a no-op when unscoped.
+    pub async fn claim_untagged_points(&self) -> Result<u64, sqlx::Error> {
+        let Some(network) = &self.network else {
+            return Ok(0);
+        };
+
+        let result = sqlx::query("UPDATE fee_data_points SET network = ? WHERE network IS NULL")
+            .bind(network)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
     }
 
     /// Bulk-insert fee data points in a single transaction.
@@ -64,16 +256,30 @@ impl FeeRepository {
             let timestamp = point.timestamp.to_rfc3339();
             let fee_amount = point.fee_amount as i64;
             let ledger_sequence = point.ledger_sequence as i64;
+            let envelope = point.envelope.as_ref();
 
             sqlx::query(
                 "INSERT INTO fee_data_points
-                 (fee_amount, timestamp, transaction_hash, ledger_sequence)
-                 VALUES (?, ?, ?, ?)",
+                 (fee_amount, timestamp, transaction_hash, ledger_sequence, network,
+                  operation_count, fee_bump, max_fee, inner_fee, operation_category,
+                  fee_account)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             )
             .bind(fee_amount)
             .bind(&timestamp)
             .bind(&point.transaction_hash)
             .bind(ledger_sequence)
+            .bind(&self.network)
+            .bind(envelope.map(|e| e.operation_count as i64))
+            .bind(envelope.map(|e| e.fee_bump))
+            .bind(envelope.map(|e| e.max_fee as i64))
+            .bind(envelope.and_then(|e| e.inner_fee).map(|fee| fee as i64))
+            .bind(envelope.and_then(|e| e.category).map(|c| c.as_str()))
+            .bind(
+                envelope
+                    .and_then(|e| e.fee_account.as_deref())
+                    .filter(|_| self.record_fee_accounts),
+            )
             .execute(&mut *tx)
             .await?;
         }
@@ -87,17 +293,106 @@ impl FeeRepository {
         &self,
         since: DateTime<Utc>,
     ) -> Result<Vec<FeeDataPoint>, sqlx::Error> {
-        let since_str = since.to_rfc3339();
+        self.fetch_points(since, None, &PointFilter::default(), None, None)
+            .await
+            .map(without_keys)
+    }
 
-        let rows = sqlx::query(
-            "SELECT fee_amount, timestamp, transaction_hash, ledger_sequence
+    /// Fetch all fee data points in `[from, to)`, ordered ascending.
+    #[cfg_attr(not(feature = "graphql"), allow(dead_code))]
+    pub async fn fetch_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<FeeDataPoint>, sqlx::Error> {
+        self.fetch_points(from, Some(to), &PointFilter::default(), None, None)
+            .await
+            .map(without_keys)
+    }
+
+    /// Up to `limit` fee data points in `[from, to)` matching `filter`
+    /// that come after `after`, when given, and after skipping `offset`,
+    /// ordered ascending, each with its key. `after` is found through the
+    /// timestamp index, so deep pages cost no more than the first; an
+    /// offset steps over every row before the page.
+    pub async fn fetch_page_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        filter: &PointFilter,
+        after: Option<&PointKey>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<(PointKey, FeeDataPoint)>, sqlx::Error> {
+        self.fetch_points(from, Some(to), filter, after, Some((limit, offset)))
+            .await
+    }
+
+    /// Number of fee data points in `[from, to)` matching `filter`.
+    pub async fn count_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        filter: &PointFilter,
+    ) -> Result<u64, sqlx::Error> {
+        let mut query =
+            QueryBuilder::new("SELECT COUNT(*) FROM fee_data_points WHERE timestamp >= ");
+        query
+            .push_bind(from.to_rfc3339())
+            .push(" AND timestamp < ")
+            .push_bind(to.to_rfc3339());
+        if let Some(network) = &self.network {
+            query.push(" AND network = ").push_bind(network.clone());
+        }
+        filter.push_conditions(&mut query);
+
+        let count: i64 = query.build_query_scalar().fetch_one(&self.pool).await?;
+        Ok(count as u64)
+    }
+
+    /// Points from `from` (until `to`, when given) matching `filter` after
+    /// `after`, optionally cut to a `(limit, offset)` page.
+    async fn fetch_points(
+        &self,
+        from: DateTime<Utc>,
+        to: Option<DateTime<Utc>>,
+        filter: &PointFilter,
+        after: Option<&PointKey>,
+        page: Option<(usize, usize)>,
+    ) -> Result<Vec<(PointKey, FeeDataPoint)>, sqlx::Error> {
+        let mut query = QueryBuilder::new(
+            "SELECT id, fee_amount, timestamp, transaction_hash, ledger_sequence,
+                    operation_count, fee_bump, max_fee, inner_fee, operation_category,
+                    fee_account
              FROM fee_data_points
-             WHERE timestamp >= ?
-             ORDER BY timestamp ASC",
-        )
-        .bind(&since_str)
-        .fetch_all(&self.pool)
-        .await?;
+             WHERE timestamp >= ",
+        );
+        query.push_bind(from.to_rfc3339());
+        if let Some(to) = to {
+            query.push(" AND timestamp < ").push_bind(to.to_rfc3339());
+        }
+        if let Some(network) = &self.network {
+            query.push(" AND network = ").push_bind(network.clone());
+        }
+        if let Some(key) = after {
+            query
+                .push(" AND (timestamp, id) > (")
+                .push_bind(key.timestamp.to_rfc3339())
+                .push(", ")
+                .push_bind(key.id)
+                .push(")");
+        }
+        filter.push_conditions(&mut query);
+        query.push(" ORDER BY timestamp ASC, id ASC");
+        if let Some((limit, offset)) = page {
+            query
+                .push(" LIMIT ")
+                .push_bind(limit as i64)
+                .push(" OFFSET ")
+                .push_bind(offset as i64);
+        }
+
+        let rows = query.build().fetch_all(&self.pool).await?;
 
         let points = rows
             .into_iter()
@@ -119,10 +414,17 @@ impl FeeRepository {
                     };
                 }
 
+                let id: i64 = col!("id", i64);
                 let fee_amount: i64 = col!("fee_amount", i64);
                 let timestamp_str: String = col!("timestamp", String);
                 let transaction_hash: String = col!("transaction_hash", String);
                 let ledger_sequence: i64 = col!("ledger_sequence", i64);
+                let operation_count: Option<i64> = col!("operation_count", Option<i64>);
+                let fee_bump: Option<bool> = col!("fee_bump", Option<bool>);
+                let max_fee: Option<i64> = col!("max_fee", Option<i64>);
+                let inner_fee: Option<i64> = col!("inner_fee", Option<i64>);
+                let operation_category: Option<String> = col!("operation_category", Option<String>);
+                let fee_account: Option<String> = col!("fee_account", Option<String>);
 
                 let timestamp = match DateTime::parse_from_rfc3339(&timestamp_str) {
                     Ok(ts) => ts.with_timezone(&Utc),
@@ -136,12 +438,24 @@ impl FeeRepository {
                     }
                 };
 
-                Some(FeeDataPoint {
+                let point = FeeDataPoint {
                     fee_amount: fee_amount as u64,
                     timestamp,
                     transaction_hash,
                     ledger_sequence: ledger_sequence as u64,
-                })
+                    envelope: operation_count.map(|operation_count| EnvelopeDetails {
+                        operation_count: operation_count as u32,
+                        fee_bump: fee_bump.unwrap_or(false),
+                        max_fee: max_fee.unwrap_or(0) as u64,
+                        inner_fee: inner_fee.map(|fee| fee as u64),
+                        category: operation_category
+                            .as_deref()
+                            .and_then(OperationCategory::parse),
+                        fee_account,
+                    }),
+                    soroban: None,
+                };
+                Some((PointKey { timestamp, id }, point))
             })
             .collect();
 
@@ -153,10 +467,15 @@ impl FeeRepository {
     pub async fn prune_older_than(&self, cutoff: DateTime<Utc>) -> Result<u64, sqlx::Error> {
         let cutoff_str = cutoff.to_rfc3339();
 
-        let result = sqlx::query("DELETE FROM fee_data_points WHERE timestamp < ?")
-            .bind(&cutoff_str)
-            .execute(&self.pool)
-            .await?;
+        let result = sqlx::query(
+            "DELETE FROM fee_data_points
+             WHERE timestamp < ? AND (? IS NULL OR network = ?)",
+        )
+        .bind(&cutoff_str)
+        .bind(&self.network)
+        .bind(&self.network)
+        .execute(&self.pool)
+        .await?;
 
         Ok(result.rows_affected())
     }
@@ -419,148 +738,1693 @@ impl FeeRepository {
         })?;
         Ok(count)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::Duration;
 
-    use crate::db::create_pool;
+    // ---- Ingestion cursors ----
 
-    async fn make_repo() -> FeeRepository {
-        let pool = create_pool("sqlite::memory:").await.unwrap();
-        FeeRepository::new(pool)
+    /// Key of this repository's rows in `ingestion_cursors`, `ledgers`,
+    /// `surge_episodes`, `fee_extremes`, `insight_snapshots`,
+    /// `congestion_samples` and `fee_rollups`.
+    fn cursor_key(&self) -> &str {
+        self.network.as_deref().unwrap_or("")
     }
 
-    fn make_point(fee_amount: u64, seconds_ago: i64) -> FeeDataPoint {
-        FeeDataPoint {
-            fee_amount,
-            timestamp: Utc::now() - Duration::seconds(seconds_ago),
-            transaction_hash: format!("hash_{}", fee_amount),
-            ledger_sequence: 1,
-        }
-    }
+    /// The saved ingestion cursor for this repository's network.
+    pub async fn load_ingestion_cursor(&self) -> Result<Option<PagingCursor>, sqlx::Error> {
+        use sqlx::Row;
+        let row = sqlx::query(
+            "SELECT paging_token, ledger_sequence FROM ingestion_cursors WHERE network = ?",
+        )
+        .bind(self.cursor_key())
+        .fetch_optional(&self.pool)
+        .await?;
 
-    #[tokio::test]
-    async fn insert_and_fetch_roundtrip() {
-        let repo = make_repo().await;
-        let points = vec![
-            make_point(100, 300),
-            make_point(200, 200),
-            make_point(300, 100),
-        ];
+        row.map(|row| {
+            Ok(PagingCursor {
+                paging_token: row.try_get("paging_token")?,
+                ledger_sequence: row.try_get::<i64, _>("ledger_sequence")? as u64,
+            })
+        })
+        .transpose()
+    }
 
-        repo.insert_fee_points(&points).await.unwrap();
+    /// Insert or replace the ingestion cursor for this repository's network.
+    pub async fn save_ingestion_cursor(&self, cursor: &PagingCursor) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO ingestion_cursors (network, paging_token, ledger_sequence, updated_at)
+             VALUES (?, ?, ?, ?)
+             ON CONFLICT(network) DO UPDATE SET
+                 paging_token = excluded.paging_token,
+                 ledger_sequence = excluded.ledger_sequence,
+                 updated_at = excluded.updated_at",
+        )
+        .bind(self.cursor_key())
+        .bind(&cursor.paging_token)
+        .bind(cursor.ledger_sequence as i64)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
 
-        let since = Utc::now() - Duration::seconds(400);
-        let fetched = repo.fetch_since(since).await.unwrap();
+    /// Every saved ingestion cursor, across all networks.
+    pub async fn list_ingestion_cursors(&self) -> Result<Vec<IngestionCursor>, sqlx::Error> {
+        use sqlx::Row;
+        let rows = sqlx::query(
+            "SELECT network, paging_token, ledger_sequence, updated_at
+             FROM ingestion_cursors ORDER BY network ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        assert_eq!(fetched.len(), 3);
-        assert_eq!(fetched[0].fee_amount, 100);
-        assert_eq!(fetched[1].fee_amount, 200);
-        assert_eq!(fetched[2].fee_amount, 300);
+        rows.into_iter()
+            .map(|row| {
+                Ok(IngestionCursor {
+                    network: row.try_get("network")?,
+                    paging_token: row.try_get("paging_token")?,
+                    ledger_sequence: row.try_get::<i64, _>("ledger_sequence")? as u64,
+                    updated_at: row.try_get("updated_at")?,
+                })
+            })
+            .collect()
     }
 
-    #[tokio::test]
-    async fn fetch_since_filters_old_points() {
-        let repo = make_repo().await;
-        let points = vec![
-            make_point(100, 7200), // 2 hours ago — outside window
-            make_point(200, 1800), // 30 min ago — inside window
-            make_point(300, 600),  // 10 min ago — inside window
-        ];
+    // ---- Ledgers ----
 
-        repo.insert_fee_points(&points).await.unwrap();
+    /// Insert ledgers, replacing rows already stored for the same sequence.
+    pub async fn insert_ledgers(&self, ledgers: &[LedgerInfo]) -> Result<(), sqlx::Error> {
+        if ledgers.is_empty() {
+            return Ok(());
+        }
 
-        let since = Utc::now() - Duration::hours(1);
-        let fetched = repo.fetch_since(since).await.unwrap();
+        let mut tx = self.pool.begin().await?;
 
-        assert_eq!(fetched.len(), 2);
-        assert_eq!(fetched[0].fee_amount, 200);
-        assert_eq!(fetched[1].fee_amount, 300);
-    }
+        for ledger in ledgers {
+            sqlx::query(
+                "INSERT OR REPLACE INTO ledgers
+                 (network, sequence, closed_at, transaction_count, operation_count,
+                  max_tx_set_size, base_fee)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(self.cursor_key())
+            .bind(ledger.sequence as i64)
+            .bind(ledger.closed_at.to_rfc3339())
+            .bind(ledger.transaction_count as i64)
+            .bind(ledger.operation_count as i64)
+            .bind(ledger.max_tx_set_size as i64)
+            .bind(ledger.base_fee as i64)
+            .execute(&mut *tx)
+            .await?;
+        }
 
-    #[tokio::test]
-    async fn insert_empty_slice_is_ok() {
-        let repo = make_repo().await;
-        let result = repo.insert_fee_points(&[]).await;
-        assert!(result.is_ok());
+        tx.commit().await?;
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn prune_older_than_removes_old_rows() {
-        let repo = make_repo().await;
-        let points = vec![
-            make_point(100, 7200), // 2 hours ago — outside window, should be pruned
-            make_point(200, 1800), // 30 min ago — clearly inside window, kept
-            make_point(300, 600),  // 10 min ago — inside window, kept
-        ];
+    /// The `limit` most recent stored ledgers of this network, newest first.
+    #[allow(dead_code)]
+    pub async fn fetch_recent_ledgers(&self, limit: u32) -> Result<Vec<LedgerInfo>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT sequence, closed_at, transaction_count, operation_count,
+                    max_tx_set_size, base_fee
+             FROM ledgers WHERE network = ?
+             ORDER BY sequence DESC LIMIT ?",
+        )
+        .bind(self.cursor_key())
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
 
-        repo.insert_fee_points(&points).await.unwrap();
+        rows.iter().map(ledger_from_row).collect()
+    }
 
-        let cutoff = Utc::now() - Duration::hours(1);
-        let deleted = repo.prune_older_than(cutoff).await.unwrap();
+    /// This network's stored ledger `sequence`, if it is still kept.
+    pub async fn fetch_ledger(&self, sequence: u64) -> Result<Option<LedgerInfo>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT sequence, closed_at, transaction_count, operation_count,
+                    max_tx_set_size, base_fee
+             FROM ledgers WHERE network = ? AND sequence = ?",
+        )
+        .bind(self.cursor_key())
+        .bind(sequence as i64)
+        .fetch_optional(&self.pool)
+        .await?;
 
-        assert_eq!(deleted, 1);
+        row.as_ref().map(ledger_from_row).transpose()
+    }
 
-        let remaining = repo
-            .fetch_since(Utc::now() - Duration::days(1))
+    /// Every stored fee data point of ledger `sequence`, ordered ascending.
+    pub async fn fetch_ledger_fees(&self, sequence: u64) -> Result<Vec<FeeDataPoint>, sqlx::Error> {
+        let filter = PointFilter {
+            min_ledger: Some(sequence),
+            max_ledger: Some(sequence),
+            ..PointFilter::default()
+        };
+        self.fetch_points(DateTime::UNIX_EPOCH, None, &filter, None, None)
             .await
-            .unwrap();
-        assert_eq!(remaining.len(), 2);
+            .map(without_keys)
     }
 
-    #[tokio::test]
-    async fn prune_older_than_returns_zero_when_nothing_to_prune() {
-        let repo = make_repo().await;
-        let points = vec![make_point(100, 60)]; // 1 min ago
+    /// Delete this network's ledgers closed before `cutoff`.
+    /// Returns the number of rows deleted.
+    pub async fn prune_ledgers_older_than(
+        &self,
+        cutoff: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM ledgers WHERE closed_at < ? AND network = ?")
+            .bind(cutoff.to_rfc3339())
+            .bind(self.cursor_key())
+            .execute(&self.pool)
+            .await?;
 
-        repo.insert_fee_points(&points).await.unwrap();
+        Ok(result.rows_affected())
+    }
 
-        let cutoff = Utc::now() - Duration::hours(1);
-        let deleted = repo.prune_older_than(cutoff).await.unwrap();
+    // ---- All-time extremes ----
 
-        assert_eq!(deleted, 0);
-    }
+    /// Insert or replace this network's all-time fee extremes.
+    pub async fn save_all_time_extremes(&self, extremes: &ExtremeRange) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
 
-    #[tokio::test]
-    async fn fetch_since_returns_empty_when_no_data() {
-        let repo = make_repo().await;
-        let fetched = repo
-            .fetch_since(Utc::now() - Duration::hours(24))
-            .await
-            .unwrap();
-        assert!(fetched.is_empty());
-    }
-}
-#[cfg(test)]
-mod alert_tests {
-    use super::*;
-    use crate::db::create_pool;
+        for (kind, value) in [("min", &extremes.min), ("max", &extremes.max)] {
+            sqlx::query(
+                "INSERT INTO fee_extremes
+                 (network, kind, fee_amount, timestamp, transaction_hash, ledger_sequence)
+                 VALUES (?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(network, kind) DO UPDATE SET
+                     fee_amount = excluded.fee_amount,
+                     timestamp = excluded.timestamp,
+                     transaction_hash = excluded.transaction_hash,
+                     ledger_sequence = excluded.ledger_sequence",
+            )
+            .bind(self.cursor_key())
+            .bind(kind)
+            .bind(value.value as i64)
+            .bind(value.timestamp.to_rfc3339())
+            .bind(&value.transaction_hash)
+            .bind(value.ledger_sequence as i64)
+            .execute(&mut *tx)
+            .await?;
+        }
 
-    async fn make_repo() -> FeeRepository {
-        let pool = create_pool("sqlite::memory:").await.unwrap();
-        FeeRepository::new(pool)
+        tx.commit().await?;
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn insert_and_list_alert_config() {
-        let repo = make_repo().await;
-        let id = repo
-            .insert_alert_config("https://hooks.example.com/webhook", "Major")
-            .await
-            .unwrap();
-        assert!(id > 0);
-        let configs = repo.list_alert_configs().await.unwrap();
-        assert_eq!(configs.len(), 1);
-        assert_eq!(configs[0].webhook_url, "https://hooks.example.com/webhook");
-        assert_eq!(configs[0].threshold, "Major");
-        assert!(configs[0].enabled);
+    /// This network's saved all-time fee extremes, if both are known.
+    pub async fn load_all_time_extremes(&self) -> Result<Option<ExtremeRange>, sqlx::Error> {
+        use sqlx::Row;
+        let rows = sqlx::query(
+            "SELECT kind, fee_amount, timestamp, transaction_hash, ledger_sequence
+             FROM fee_extremes WHERE network = ?",
+        )
+        .bind(self.cursor_key())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let (mut min, mut max) = (None, None);
+        for row in rows {
+            let timestamp: String = row.try_get("timestamp")?;
+            let value = ExtremeValue {
+                value: row.try_get::<i64, _>("fee_amount")? as u64,
+                timestamp: DateTime::parse_from_rfc3339(&timestamp)
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+                    .with_timezone(&Utc),
+                transaction_hash: row.try_get("transaction_hash")?,
+                ledger_sequence: row.try_get::<i64, _>("ledger_sequence")? as u64,
+            };
+            match row.try_get::<String, _>("kind")?.as_str() {
+                "min" => min = Some(value),
+                "max" => max = Some(value),
+                _ => {}
+            }
+        }
+
+        Ok(min.zip(max).map(|(min, max)| ExtremeRange { min, max }))
     }
 
-    #[tokio::test]
-    async fn update_alert_config_changes_threshold_and_enabled() {
+    // ---- Surge episodes ----
+
+    /// Record completed surge episodes.
+    pub async fn insert_surge_episodes(
+        &self,
+        episodes: &[SurgeEpisode],
+    ) -> Result<(), sqlx::Error> {
+        if episodes.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        for episode in episodes {
+            sqlx::query(
+                "INSERT INTO surge_episodes
+                 (network, started_at, ended_at, duration_seconds, peak_fee, baseline_fee,
+                  sample_count)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(self.cursor_key())
+            .bind(episode.start_time.to_rfc3339())
+            .bind(episode.end_time.to_rfc3339())
+            .bind(episode.duration.num_seconds())
+            .bind(episode.peak_fee as i64)
+            .bind(episode.baseline_fee)
+            .bind(episode.sample_count as i64)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// This network's surge episodes that overlap `from..to`, oldest first.
+    pub async fn fetch_surge_episodes_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<SurgeEpisode>, sqlx::Error> {
+        self.query_surge_episodes(
+            "SELECT started_at, ended_at, duration_seconds, peak_fee, baseline_fee, sample_count
+             FROM surge_episodes WHERE network = ? AND ended_at > ? AND started_at < ?
+             ORDER BY started_at ASC",
+            &[from.to_rfc3339(), to.to_rfc3339()],
+        )
+        .await
+    }
+
+    /// This network's surge episodes that started at or after `since`,
+    /// newest first.
+    pub async fn fetch_surge_episodes_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<SurgeEpisode>, sqlx::Error> {
+        self.query_surge_episodes(
+            "SELECT started_at, ended_at, duration_seconds, peak_fee, baseline_fee, sample_count
+             FROM surge_episodes WHERE network = ? AND started_at >= ?
+             ORDER BY started_at DESC",
+            &[since.to_rfc3339()],
+        )
+        .await
+    }
+
+    /// Run a surge episode `sql` scoped to this network, binding `bounds`
+    /// after the network.
+    async fn query_surge_episodes(
+        &self,
+        sql: &str,
+        bounds: &[String],
+    ) -> Result<Vec<SurgeEpisode>, sqlx::Error> {
+        use sqlx::Row;
+        let mut query = sqlx::query(sql).bind(self.cursor_key());
+        for bound in bounds {
+            query = query.bind(bound);
+        }
+        let rows = query.fetch_all(&self.pool).await?;
+
+        let parse_time = |value: String| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        };
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(SurgeEpisode {
+                    start_time: parse_time(row.try_get("started_at")?)?,
+                    end_time: parse_time(row.try_get("ended_at")?)?,
+                    duration: chrono::Duration::seconds(row.try_get("duration_seconds")?),
+                    peak_fee: row.try_get::<i64, _>("peak_fee")? as u64,
+                    baseline_fee: row.try_get("baseline_fee")?,
+                    sample_count: row.try_get::<i64, _>("sample_count")? as usize,
+                })
+            })
+            .collect()
+    }
+
+    /// Fee count and total per UTC weekday and hour for points before
+    /// `before`, for seeding seasonality baselines.
+    pub async fn fetch_seasonal_slots(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<Vec<SeasonalSlot>, sqlx::Error> {
+        use sqlx::Row;
+        // strftime('%w') numbers days from Sunday = 0
+        let rows = sqlx::query(
+            "SELECT CAST(strftime('%w', timestamp) AS INTEGER) AS weekday,
+                    CAST(strftime('%H', timestamp) AS INTEGER) AS hour,
+                    COUNT(*) AS count,
+                    SUM(fee_amount) AS total_fee
+             FROM fee_data_points
+             WHERE timestamp < ? AND (? IS NULL OR network = ?)
+             GROUP BY weekday, hour",
+        )
+        .bind(before.to_rfc3339())
+        .bind(&self.network)
+        .bind(&self.network)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let days_from_sunday = row.try_get::<i64, _>("weekday")?;
+                let weekday = chrono::Weekday::try_from(((days_from_sunday + 6) % 7) as u8)
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+                Ok(SeasonalSlot {
+                    weekday,
+                    hour: row.try_get::<i64, _>("hour")? as u32,
+                    count: row.try_get::<i64, _>("count")? as u64,
+                    total_fee: row.try_get::<i64, _>("total_fee")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    // ---- Insight snapshots ----
+
+    /// Record a full insights snapshot, stamped with its `last_updated`.
+    pub async fn insert_insight_snapshot(
+        &self,
+        insights: &CurrentInsights,
+    ) -> Result<(), sqlx::Error> {
+        let json =
+            serde_json::to_string(insights).map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        sqlx::query(
+            "INSERT INTO insight_snapshots (network, captured_at, insights) VALUES (?, ?, ?)",
+        )
+        .bind(self.cursor_key())
+        .bind(insights.last_updated.to_rfc3339())
+        .bind(json)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// This network's insight snapshots captured at or after `since`,
+    /// oldest first.
+    pub async fn fetch_insight_snapshots_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<CurrentInsights>, sqlx::Error> {
+        use sqlx::Row;
+        let rows = sqlx::query(
+            "SELECT insights FROM insight_snapshots
+             WHERE network = ? AND captured_at >= ?
+             ORDER BY captured_at ASC",
+        )
+        .bind(self.cursor_key())
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let json: String = row.try_get("insights")?;
+                serde_json::from_str(&json).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+            })
+            .collect()
+    }
+
+    /// Identifies the snapshots [`FeeRepository::fetch_insight_snapshots_since`]
+    /// would return without reading them: snapshots are never updated, so
+    /// the set only changes when one enters or leaves the range.
+    pub async fn insight_snapshot_span_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<SnapshotSpan, sqlx::Error> {
+        use sqlx::Row;
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS count, MIN(id) AS first_id, MAX(id) AS last_id,
+                    MAX(captured_at) AS latest
+             FROM insight_snapshots
+             WHERE network = ? AND captured_at >= ?",
+        )
+        .bind(self.cursor_key())
+        .bind(since.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await?;
+
+        let latest: Option<String> = row.try_get("latest")?;
+        Ok(SnapshotSpan {
+            count: row.try_get::<i64, _>("count")? as u64,
+            first_id: row.try_get("first_id")?,
+            last_id: row.try_get("last_id")?,
+            latest: latest
+                .as_deref()
+                .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                .map(|at| at.with_timezone(&Utc)),
+        })
+    }
+
+    /// Delete this network's insight snapshots captured before `cutoff`.
+    pub async fn prune_insight_snapshots_older_than(
+        &self,
+        cutoff: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        let result =
+            sqlx::query("DELETE FROM insight_snapshots WHERE captured_at < ? AND network = ?")
+                .bind(cutoff.to_rfc3339())
+                .bind(self.cursor_key())
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ---- Congestion samples ----
+
+    /// Record the congestion `state` seen at `captured_at`.
+    pub async fn insert_congestion_sample(
+        &self,
+        captured_at: DateTime<Utc>,
+        state: CongestionState,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO congestion_samples (network, captured_at, state) VALUES (?, ?, ?)",
+        )
+        .bind(self.cursor_key())
+        .bind(captured_at.to_rfc3339())
+        .bind(state.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// This network's congestion samples captured at or after `since`,
+    /// oldest first. Rows with an unknown state are skipped.
+    pub async fn fetch_congestion_samples_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, CongestionState)>, sqlx::Error> {
+        use sqlx::Row;
+        let rows = sqlx::query(
+            "SELECT captured_at, state FROM congestion_samples
+             WHERE network = ? AND captured_at >= ?
+             ORDER BY captured_at ASC",
+        )
+        .bind(self.cursor_key())
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut samples = Vec::with_capacity(rows.len());
+        for row in rows {
+            let captured_at: String = row.try_get("captured_at")?;
+            let captured_at = DateTime::parse_from_rfc3339(&captured_at)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+                .with_timezone(&Utc);
+            let state: String = row.try_get("state")?;
+            if let Some(state) = CongestionState::parse(&state) {
+                samples.push((captured_at, state));
+            }
+        }
+
+        Ok(samples)
+    }
+
+    /// Delete this network's congestion samples captured before `cutoff`.
+    pub async fn prune_congestion_samples_older_than(
+        &self,
+        cutoff: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        let result =
+            sqlx::query("DELETE FROM congestion_samples WHERE captured_at < ? AND network = ?")
+                .bind(cutoff.to_rfc3339())
+                .bind(self.cursor_key())
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ---- Congestion episodes ----
+
+    /// Open, extend and close this network's congestion episodes from the
+    /// `transitions` of one batch, oldest first. `state` and `fee_ratio`
+    /// are the detector's after the batch, seen `at`: the open episode's
+    /// peak multiplier is raised to `fee_ratio`, and it is closed if the
+    /// detector is back to normal without a transition saying so, as after
+    /// a restart.
+    pub async fn record_congestion_episodes(
+        &self,
+        transitions: &[CongestionTransition],
+        state: CongestionState,
+        fee_ratio: Option<f64>,
+        at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        for transition in transitions {
+            let open = Self::open_episode(&mut tx, self.cursor_key()).await?;
+            match open {
+                Some(episode) => {
+                    let ended_at = (transition.to == CongestionState::Normal)
+                        .then(|| transition.at.to_rfc3339());
+                    sqlx::query(
+                        "UPDATE congestion_episodes
+                         SET peak_state = ?, peak_multiplier = MAX(peak_multiplier, ?),
+                             ended_at = ?
+                         WHERE id = ?",
+                    )
+                    .bind(episode.peak_state.max(transition.to).as_str())
+                    .bind(transition.fee_ratio)
+                    .bind(ended_at)
+                    .bind(episode.id)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                None if transition.to > CongestionState::Normal => {
+                    sqlx::query(
+                        "INSERT INTO congestion_episodes
+                         (network, started_at, peak_state, peak_multiplier)
+                         VALUES (?, ?, ?, ?)",
+                    )
+                    .bind(self.cursor_key())
+                    .bind(transition.at.to_rfc3339())
+                    .bind(transition.to.as_str())
+                    .bind(transition.fee_ratio)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                // Leaving a state entered before episodes were recorded
+                None => {}
+            }
+        }
+
+        if state == CongestionState::Normal {
+            sqlx::query(
+                "UPDATE congestion_episodes SET ended_at = ?
+                 WHERE network = ? AND ended_at IS NULL",
+            )
+            .bind(at.to_rfc3339())
+            .bind(self.cursor_key())
+            .execute(&mut *tx)
+            .await?;
+        } else if let Some(fee_ratio) = fee_ratio {
+            sqlx::query(
+                "UPDATE congestion_episodes SET peak_multiplier = MAX(peak_multiplier, ?)
+                 WHERE network = ? AND ended_at IS NULL",
+            )
+            .bind(fee_ratio)
+            .bind(self.cursor_key())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// This network's ongoing congestion episode, if there is one.
+    pub async fn open_congestion_episode(&self) -> Result<Option<CongestionEpisode>, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        Self::open_episode(&mut conn, self.cursor_key()).await
+    }
+
+    async fn open_episode(
+        conn: &mut sqlx::SqliteConnection,
+        network: &str,
+    ) -> Result<Option<CongestionEpisode>, sqlx::Error> {
+        sqlx::query(
+            "SELECT id, started_at, ended_at, peak_state, peak_multiplier
+             FROM congestion_episodes WHERE network = ? AND ended_at IS NULL
+             ORDER BY id DESC LIMIT 1",
+        )
+        .bind(network)
+        .fetch_optional(conn)
+        .await?
+        .map(|row| congestion_episode_from_row(&row))
+        .transpose()
+    }
+
+    /// Up to `limit` of this network's congestion episodes, newest first,
+    /// continuing after the episode with id `before` when given.
+    pub async fn fetch_congestion_episodes(
+        &self,
+        before: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<CongestionEpisode>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, started_at, ended_at, peak_state, peak_multiplier
+             FROM congestion_episodes WHERE network = ? AND id < ?
+             ORDER BY id DESC LIMIT ?",
+        )
+        .bind(self.cursor_key())
+        .bind(before.unwrap_or(i64::MAX))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(congestion_episode_from_row).collect()
+    }
+
+    // ---- Fee rollups ----
+
+    /// Timestamp of the first fee point at or after `since` (of any point
+    /// when `None`).
+    pub async fn first_point_time_since(
+        &self,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        use sqlx::Row;
+        let since = since.map(|since| since.to_rfc3339());
+        let row = sqlx::query(
+            "SELECT MIN(timestamp) AS first FROM fee_data_points
+             WHERE (? IS NULL OR timestamp >= ?) AND (? IS NULL OR network = ?)",
+        )
+        .bind(&since)
+        .bind(&since)
+        .bind(&self.network)
+        .bind(&self.network)
+        .fetch_one(&self.pool)
+        .await?;
+
+        row.try_get::<Option<String>, _>("first")?
+            .map(|first| {
+                DateTime::parse_from_rfc3339(&first)
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+            })
+            .transpose()
+    }
+
+    /// Timestamp and fee of every point in `[from, to)`, oldest first.
+    pub async fn fetch_fees_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, u64)>, sqlx::Error> {
+        use sqlx::Row;
+        let rows = sqlx::query(
+            "SELECT timestamp, fee_amount FROM fee_data_points
+             WHERE timestamp >= ? AND timestamp < ? AND (? IS NULL OR network = ?)
+             ORDER BY timestamp ASC",
+        )
+        .bind(from.to_rfc3339())
+        .bind(to.to_rfc3339())
+        .bind(&self.network)
+        .bind(&self.network)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let timestamp: String = row.try_get("timestamp")?;
+                let timestamp = DateTime::parse_from_rfc3339(&timestamp)
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+                    .with_timezone(&Utc);
+                Ok((timestamp, row.try_get::<i64, _>("fee_amount")? as u64))
+            })
+            .collect()
+    }
+
+    /// Fees paid per account and UTC day in `[from, to)`, restricted to
+    /// `account` when given. Only points stored with their fee account
+    /// count; see [`FeeRepository::with_fee_accounts`].
+    pub async fn fetch_daily_account_fees(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        account: Option<&str>,
+    ) -> Result<Vec<(String, DailyFeeSpend)>, sqlx::Error> {
+        use sqlx::Row;
+        let rows = sqlx::query(
+            "SELECT fee_account, substr(timestamp, 1, 10) AS day,
+                    COUNT(*) AS transactions, SUM(fee_amount) AS total_fee
+             FROM fee_data_points
+             WHERE fee_account IS NOT NULL AND timestamp >= ? AND timestamp < ?
+               AND (? IS NULL OR fee_account = ?) AND (? IS NULL OR network = ?)
+             GROUP BY fee_account, day
+             ORDER BY fee_account, day",
+        )
+        .bind(from.to_rfc3339())
+        .bind(to.to_rfc3339())
+        .bind(account)
+        .bind(account)
+        .bind(&self.network)
+        .bind(&self.network)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let day: String = row.try_get("day")?;
+                let day = NaiveDate::parse_from_str(&day, "%Y-%m-%d")
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+                Ok((
+                    row.try_get("fee_account")?,
+                    DailyFeeSpend {
+                        day,
+                        transaction_count: row.try_get::<i64, _>("transactions")? as u64,
+                        total_fee: row.try_get::<i64, _>("total_fee")? as u64,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// The `limit` accounts that paid the most in fees in `[from, to)`,
+    /// biggest total first. Only points stored with their fee account count.
+    pub async fn fetch_top_fee_payers(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<TopFeePayer>, sqlx::Error> {
+        use sqlx::Row;
+        let rows = sqlx::query(
+            "SELECT fee_account, COUNT(*) AS transactions, SUM(fee_amount) AS total_fee,
+                    MAX(fee_amount) AS max_fee
+             FROM fee_data_points
+             WHERE timestamp >= ? AND timestamp < ? AND fee_account IS NOT NULL
+               AND (? IS NULL OR network = ?)
+             GROUP BY fee_account
+             ORDER BY total_fee DESC, fee_account ASC
+             LIMIT ?",
+        )
+        .bind(from.to_rfc3339())
+        .bind(to.to_rfc3339())
+        .bind(&self.network)
+        .bind(&self.network)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let transaction_count = row.try_get::<i64, _>("transactions")? as u64;
+                let total_fee = row.try_get::<i64, _>("total_fee")? as u64;
+                Ok(TopFeePayer {
+                    account: row.try_get("fee_account")?,
+                    transaction_count,
+                    total_fee,
+                    average_fee: total_fee as f64 / transaction_count as f64,
+                    max_fee: row.try_get::<i64, _>("max_fee")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    /// The `limit` most expensive transactions in `[from, to)`, highest fee
+    /// first.
+    pub async fn fetch_top_fee_transactions(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<TopFeeTransaction>, sqlx::Error> {
+        use sqlx::Row;
+        let rows = sqlx::query(
+            "SELECT transaction_hash, ledger_sequence, timestamp, fee_amount,
+                    operation_count, fee_account
+             FROM fee_data_points
+             WHERE timestamp >= ? AND timestamp < ? AND (? IS NULL OR network = ?)
+             ORDER BY fee_amount DESC, timestamp ASC
+             LIMIT ?",
+        )
+        .bind(from.to_rfc3339())
+        .bind(to.to_rfc3339())
+        .bind(&self.network)
+        .bind(&self.network)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let timestamp: String = row.try_get("timestamp")?;
+                Ok(TopFeeTransaction {
+                    transaction_hash: row.try_get("transaction_hash")?,
+                    ledger_sequence: row.try_get::<i64, _>("ledger_sequence")? as u64,
+                    timestamp: DateTime::parse_from_rfc3339(&timestamp)
+                        .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+                        .with_timezone(&Utc),
+                    fee_amount: row.try_get::<i64, _>("fee_amount")? as u64,
+                    operation_count: row
+                        .try_get::<Option<i64>, _>("operation_count")?
+                        .map(|count| count as u32),
+                    fee_account: row.try_get("fee_account")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Fees `account` paid in `[from, to)`, in stroops.
+    pub async fn fetch_account_fee_total(
+        &self,
+        account: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        use sqlx::Row;
+        let row = sqlx::query(
+            "SELECT COALESCE(SUM(fee_amount), 0) AS total_fee FROM fee_data_points
+             WHERE fee_account = ? AND timestamp >= ? AND timestamp < ?
+               AND (? IS NULL OR network = ?)",
+        )
+        .bind(account)
+        .bind(from.to_rfc3339())
+        .bind(to.to_rfc3339())
+        .bind(&self.network)
+        .bind(&self.network)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.try_get::<i64, _>("total_fee")? as u64)
+    }
+
+    /// Start of this network's newest stored rollup at `resolution`.
+    pub async fn latest_rollup_start(
+        &self,
+        resolution: Resolution,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        use sqlx::Row;
+        let row = sqlx::query(
+            "SELECT MAX(bucket_start) AS latest FROM fee_rollups
+             WHERE network = ? AND resolution = ?",
+        )
+        .bind(self.cursor_key())
+        .bind(resolution.as_str())
+        .fetch_one(&self.pool)
+        .await?;
+
+        row.try_get::<Option<String>, _>("latest")?
+            .map(|latest| {
+                DateTime::parse_from_rfc3339(&latest)
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+            })
+            .transpose()
+    }
+
+    /// Insert or replace rollups for this network.
+    pub async fn upsert_rollups(&self, rollups: &[FeeRollup]) -> Result<(), sqlx::Error> {
+        if rollups.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        for rollup in rollups {
+            let p = &rollup.percentiles;
+            sqlx::query(
+                "INSERT INTO fee_rollups
+                 (network, resolution, bucket_start, sample_count, min_fee, max_fee, avg_fee,
+                  p10, p25, p50, p75, p90, p95, p99)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(network, resolution, bucket_start) DO UPDATE SET
+                     sample_count = excluded.sample_count,
+                     min_fee = excluded.min_fee,
+                     max_fee = excluded.max_fee,
+                     avg_fee = excluded.avg_fee,
+                     p10 = excluded.p10,
+                     p25 = excluded.p25,
+                     p50 = excluded.p50,
+                     p75 = excluded.p75,
+                     p90 = excluded.p90,
+                     p95 = excluded.p95,
+                     p99 = excluded.p99",
+            )
+            .bind(self.cursor_key())
+            .bind(rollup.resolution.as_str())
+            .bind(rollup.bucket_start.to_rfc3339())
+            .bind(rollup.sample_count as i64)
+            .bind(rollup.min_fee as i64)
+            .bind(rollup.max_fee as i64)
+            .bind(rollup.avg_fee)
+            .bind(p.p10 as i64)
+            .bind(p.p25 as i64)
+            .bind(p.p50 as i64)
+            .bind(p.p75 as i64)
+            .bind(p.p90 as i64)
+            .bind(p.p95 as i64)
+            .bind(p.p99 as i64)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// This network's rollups at `resolution` whose bucket starts at or
+    /// after `since`, oldest first.
+    pub async fn fetch_rollups_since(
+        &self,
+        resolution: Resolution,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<FeeRollup>, sqlx::Error> {
+        self.fetch_rollups_in(resolution, since, None, None, None)
+            .await
+    }
+
+    /// This network's rollups at `resolution` whose bucket starts within
+    /// `from..to`, oldest first.
+    pub async fn fetch_rollups_between(
+        &self,
+        resolution: Resolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<FeeRollup>, sqlx::Error> {
+        self.fetch_rollups_in(resolution, from, Some(to), None, None)
+            .await
+    }
+
+    /// Up to `limit` of this network's rollups at `resolution` starting
+    /// within `from..to` and after `after`, when given, after skipping
+    /// `offset`, oldest first.
+    pub async fn fetch_rollup_page_between(
+        &self,
+        resolution: Resolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        after: Option<DateTime<Utc>>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<FeeRollup>, sqlx::Error> {
+        self.fetch_rollups_in(resolution, from, Some(to), after, Some((limit, offset)))
+            .await
+    }
+
+    /// Number of this network's rollups at `resolution` starting within
+    /// `from..to`.
+    pub async fn count_rollups_between(
+        &self,
+        resolution: Resolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM fee_rollups
+             WHERE network = ? AND resolution = ? AND bucket_start >= ? AND bucket_start < ?",
+        )
+        .bind(self.cursor_key())
+        .bind(resolution.as_str())
+        .bind(from.to_rfc3339())
+        .bind(to.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count as u64)
+    }
+
+    async fn fetch_rollups_in(
+        &self,
+        resolution: Resolution,
+        from: DateTime<Utc>,
+        to: Option<DateTime<Utc>>,
+        after: Option<DateTime<Utc>>,
+        page: Option<(usize, usize)>,
+    ) -> Result<Vec<FeeRollup>, sqlx::Error> {
+        use sqlx::Row;
+        let to = to.map(|to| to.to_rfc3339());
+        let after = after.map(|after| after.to_rfc3339());
+        let (limit, offset) = page.map_or((-1, 0), |(limit, offset)| (limit as i64, offset as i64));
+        let rows = sqlx::query(
+            "SELECT bucket_start, sample_count, min_fee, max_fee, avg_fee,
+                    p10, p25, p50, p75, p90, p95, p99
+             FROM fee_rollups
+             WHERE network = ? AND resolution = ? AND bucket_start >= ?
+               AND (? IS NULL OR bucket_start < ?)
+               AND (? IS NULL OR bucket_start > ?)
+             ORDER BY bucket_start ASC
+             LIMIT ? OFFSET ?",
+        )
+        .bind(self.cursor_key())
+        .bind(resolution.as_str())
+        .bind(from.to_rfc3339())
+        .bind(&to)
+        .bind(&to)
+        .bind(&after)
+        .bind(&after)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let fee = |column: &str| row.try_get::<i64, _>(column).map(|v| v as u64);
+                let bucket_start: String = row.try_get("bucket_start")?;
+                Ok(FeeRollup {
+                    resolution,
+                    bucket_start: DateTime::parse_from_rfc3339(&bucket_start)
+                        .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+                        .with_timezone(&Utc),
+                    sample_count: fee("sample_count")?,
+                    min_fee: fee("min_fee")?,
+                    max_fee: fee("max_fee")?,
+                    avg_fee: row.try_get("avg_fee")?,
+                    percentiles: FeeDistribution {
+                        p10: fee("p10")?,
+                        p25: fee("p25")?,
+                        p50: fee("p50")?,
+                        p75: fee("p75")?,
+                        p90: fee("p90")?,
+                        p95: fee("p95")?,
+                        p99: fee("p99")?,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    // ---- Fee budgets ----
+
+    /// Insert or replace this network's budget for the account and period.
+    pub async fn upsert_fee_budget(&self, budget: &FeeBudget) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO fee_budgets (network, account, period, limit_fee, updated_at)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(network, account, period) DO UPDATE SET
+                 limit_fee = excluded.limit_fee,
+                 updated_at = excluded.updated_at",
+        )
+        .bind(self.cursor_key())
+        .bind(&budget.account)
+        .bind(budget.period.as_str())
+        .bind(budget.limit as i64)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Every budget set on this network, by account then period.
+    pub async fn list_fee_budgets(&self) -> Result<Vec<FeeBudget>, sqlx::Error> {
+        use sqlx::Row;
+        let rows = sqlx::query(
+            "SELECT account, period, limit_fee FROM fee_budgets
+             WHERE network = ? ORDER BY account ASC, period ASC",
+        )
+        .bind(self.cursor_key())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let period: String = row.try_get("period")?;
+                Ok(FeeBudget {
+                    account: row.try_get("account")?,
+                    period: BudgetPeriod::parse(&period).ok_or_else(|| {
+                        sqlx::Error::Decode(format!("unknown budget period {}", period).into())
+                    })?,
+                    limit: row.try_get::<i64, _>("limit_fee")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    /// Delete this network's budget for the account and period. Returns
+    /// `false` when none was set.
+    pub async fn delete_fee_budget(
+        &self,
+        account: &str,
+        period: BudgetPeriod,
+    ) -> Result<bool, sqlx::Error> {
+        let result =
+            sqlx::query("DELETE FROM fee_budgets WHERE network = ? AND account = ? AND period = ?")
+                .bind(self.cursor_key())
+                .bind(account)
+                .bind(period.as_str())
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // ---- API keys ----
+
+    /// Store a new API key by its hash.
+    pub async fn insert_api_key(
+        &self,
+        name: &str,
+        key_hash: &str,
+        prefix: &str,
+    ) -> Result<ApiKey, sqlx::Error> {
+        let created_at = Utc::now().to_rfc3339();
+        let result = sqlx::query(
+            "INSERT INTO api_keys (name, key_hash, prefix, created_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(name)
+        .bind(key_hash)
+        .bind(prefix)
+        .bind(&created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(ApiKey {
+            id: result.last_insert_rowid(),
+            name: name.to_string(),
+            prefix: prefix.to_string(),
+            created_at,
+            revoked_at: None,
+        })
+    }
+
+    /// Every API key, revoked ones included, oldest first.
+    pub async fn list_api_keys(&self) -> Result<Vec<ApiKey>, sqlx::Error> {
+        use sqlx::Row;
+        let rows = sqlx::query(
+            "SELECT id, name, prefix, created_at, revoked_at FROM api_keys ORDER BY id ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(ApiKey {
+                    id: row.try_get("id")?,
+                    name: row.try_get("name")?,
+                    prefix: row.try_get("prefix")?,
+                    created_at: row.try_get("created_at")?,
+                    revoked_at: row.try_get("revoked_at")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Stop accepting an API key. Returns `false` when there is no such key
+    /// or it was already revoked.
+    pub async fn revoke_api_key(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let result =
+            sqlx::query("UPDATE api_keys SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL")
+                .bind(Utc::now().to_rfc3339())
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Revoke API key `id` and store a replacement of the same name in one
+    /// transaction. Returns `None` when there is no such active key.
+    pub async fn rotate_api_key(
+        &self,
+        id: i64,
+        key_hash: &str,
+        prefix: &str,
+    ) -> Result<Option<ApiKey>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let name: Option<String> =
+            sqlx::query_scalar("SELECT name FROM api_keys WHERE id = ? AND revoked_at IS NULL")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let Some(name) = name else {
+            return Ok(None);
+        };
+
+        let now = Utc::now().to_rfc3339();
+        sqlx::query("UPDATE api_keys SET revoked_at = ? WHERE id = ?")
+            .bind(&now)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query(
+            "INSERT INTO api_keys (name, key_hash, prefix, created_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&name)
+        .bind(key_hash)
+        .bind(prefix)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some(ApiKey {
+            id: result.last_insert_rowid(),
+            name,
+            prefix: prefix.to_string(),
+            created_at: now,
+            revoked_at: None,
+        }))
+    }
+
+    /// `true` when a key with this hash exists and has not been revoked.
+    pub async fn is_active_api_key(&self, key_hash: &str) -> Result<bool, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM api_keys WHERE key_hash = ? AND revoked_at IS NULL",
+        )
+        .bind(key_hash)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count > 0)
+    }
+}
+
+#[async_trait]
+impl CursorStore for FeeRepository {
+    async fn load_cursor(&self) -> Result<Option<PagingCursor>, InsightsError> {
+        self.load_ingestion_cursor()
+            .await
+            .map_err(|e| InsightsError::storage_error(e.to_string()))
+    }
+
+    async fn save_cursor(&self, cursor: &PagingCursor) -> Result<(), InsightsError> {
+        self.save_ingestion_cursor(cursor)
+            .await
+            .map_err(|e| InsightsError::storage_error(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    use crate::db::create_pool;
+    use crate::insights::{FeeInsightsEngine, InsightsConfig};
+
+    async fn make_repo() -> FeeRepository {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        FeeRepository::new(pool)
+    }
+
+    fn make_point(fee_amount: u64, seconds_ago: i64) -> FeeDataPoint {
+        FeeDataPoint {
+            fee_amount,
+            timestamp: Utc::now() - Duration::seconds(seconds_ago),
+            transaction_hash: format!("hash_{}", fee_amount),
+            ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn insert_and_fetch_roundtrip() {
+        let repo = make_repo().await;
+        let points = vec![
+            make_point(100, 300),
+            make_point(200, 200),
+            make_point(300, 100),
+        ];
+
+        repo.insert_fee_points(&points).await.unwrap();
+
+        let since = Utc::now() - Duration::seconds(400);
+        let fetched = repo.fetch_since(since).await.unwrap();
+
+        assert_eq!(fetched.len(), 3);
+        assert_eq!(fetched[0].fee_amount, 100);
+        assert_eq!(fetched[1].fee_amount, 200);
+        assert_eq!(fetched[2].fee_amount, 300);
+    }
+
+    #[tokio::test]
+    async fn fetch_since_filters_old_points() {
+        let repo = make_repo().await;
+        let points = vec![
+            make_point(100, 7200), // 2 hours ago — outside window
+            make_point(200, 1800), // 30 min ago — inside window
+            make_point(300, 600),  // 10 min ago — inside window
+        ];
+
+        repo.insert_fee_points(&points).await.unwrap();
+
+        let since = Utc::now() - Duration::hours(1);
+        let fetched = repo.fetch_since(since).await.unwrap();
+
+        assert_eq!(fetched.len(), 2);
+        assert_eq!(fetched[0].fee_amount, 200);
+        assert_eq!(fetched[1].fee_amount, 300);
+    }
+
+    #[tokio::test]
+    async fn seasonal_slots_group_by_weekday_and_hour() {
+        use chrono::{TimeZone, Weekday};
+
+        let repo = make_repo().await;
+        // Sunday 2024-01-07, 13:xx UTC
+        let sunday = Utc.with_ymd_and_hms(2024, 1, 7, 13, 5, 0).unwrap();
+        let mut points = vec![make_point(100, 0), make_point(300, 0), make_point(1_000, 0)];
+        points[0].timestamp = sunday;
+        points[1].timestamp = sunday + Duration::minutes(30);
+        points[2].timestamp = sunday + Duration::days(1);
+        repo.insert_fee_points(&points).await.unwrap();
+
+        let mut slots = repo
+            .fetch_seasonal_slots(sunday + Duration::days(2))
+            .await
+            .unwrap();
+        slots.sort_by_key(|slot| slot.weekday.num_days_from_monday());
+
+        assert_eq!(
+            slots,
+            vec![
+                SeasonalSlot {
+                    weekday: Weekday::Mon,
+                    hour: 13,
+                    count: 1,
+                    total_fee: 1_000,
+                },
+                SeasonalSlot {
+                    weekday: Weekday::Sun,
+                    hour: 13,
+                    count: 2,
+                    total_fee: 400,
+                },
+            ]
+        );
+
+        let earlier = repo.fetch_seasonal_slots(sunday).await.unwrap();
+        assert!(earlier.is_empty());
+    }
+
+    #[tokio::test]
+    async fn all_time_extremes_roundtrip_per_network() {
+        let repo = make_repo().await.with_network(StellarNetwork::Testnet);
+        assert_eq!(repo.load_all_time_extremes().await.unwrap(), None);
+
+        let extreme = |value: u64, ledger_sequence: u64| ExtremeValue {
+            value,
+            timestamp: Utc::now() - Duration::days(90),
+            transaction_hash: format!("hash_{}", value),
+            ledger_sequence,
+        };
+        let first = ExtremeRange {
+            min: extreme(100, 7),
+            max: extreme(5_000, 8),
+        };
+        repo.save_all_time_extremes(&first).await.unwrap();
+        let updated = ExtremeRange {
+            max: extreme(90_000, 9),
+            ..first
+        };
+        repo.save_all_time_extremes(&updated).await.unwrap();
+
+        assert_eq!(repo.load_all_time_extremes().await.unwrap(), Some(updated));
+        let mainnet = repo.for_network(StellarNetwork::Mainnet);
+        assert_eq!(mainnet.load_all_time_extremes().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn insert_empty_slice_is_ok() {
+        let repo = make_repo().await;
+        let result = repo.insert_fee_points(&[]).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn prune_older_than_removes_old_rows() {
+        let repo = make_repo().await;
+        let points = vec![
+            make_point(100, 7200), // 2 hours ago — outside window, should be pruned
+            make_point(200, 1800), // 30 min ago — clearly inside window, kept
+            make_point(300, 600),  // 10 min ago — inside window, kept
+        ];
+
+        repo.insert_fee_points(&points).await.unwrap();
+
+        let cutoff = Utc::now() - Duration::hours(1);
+        let deleted = repo.prune_older_than(cutoff).await.unwrap();
+
+        assert_eq!(deleted, 1);
+
+        let remaining = repo
+            .fetch_since(Utc::now() - Duration::days(1))
+            .await
+            .unwrap();
+        assert_eq!(remaining.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn prune_older_than_returns_zero_when_nothing_to_prune() {
+        let repo = make_repo().await;
+        let points = vec![make_point(100, 60)]; // 1 min ago
+
+        repo.insert_fee_points(&points).await.unwrap();
+
+        let cutoff = Utc::now() - Duration::hours(1);
+        let deleted = repo.prune_older_than(cutoff).await.unwrap();
+
+        assert_eq!(deleted, 0);
+    }
+
+    #[tokio::test]
+    async fn fetch_since_returns_empty_when_no_data() {
+        let repo = make_repo().await;
+        let fetched = repo
+            .fetch_since(Utc::now() - Duration::hours(24))
+            .await
+            .unwrap();
+        assert!(fetched.is_empty());
+    }
+
+    #[tokio::test]
+    async fn envelope_details_roundtrip() {
+        let repo = make_repo().await;
+        let details = EnvelopeDetails {
+            operation_count: 3,
+            fee_bump: true,
+            max_fee: 1_500,
+            inner_fee: Some(400),
+            category: Some(OperationCategory::PathPayment),
+            fee_account: None,
+        };
+        let mut enriched = make_point(100, 60);
+        enriched.envelope = Some(details.clone());
+
+        repo.insert_fee_points(&[enriched, make_point(200, 30)])
+            .await
+            .unwrap();
+
+        let fetched = repo
+            .fetch_since(Utc::now() - Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(fetched[0].envelope, Some(details));
+        assert!(fetched[1].envelope.is_none());
+    }
+
+    #[tokio::test]
+    async fn network_scoped_repositories_do_not_see_each_other() {
+        let repo = make_repo().await;
+        let testnet = repo.for_network(StellarNetwork::Testnet);
+        let mainnet = repo.for_network(StellarNetwork::Mainnet);
+
+        testnet
+            .insert_fee_points(&[make_point(100, 60)])
+            .await
+            .unwrap();
+        mainnet
+            .insert_fee_points(&[make_point(200, 60)])
+            .await
+            .unwrap();
+
+        let since = Utc::now() - Duration::hours(1);
+        let fetched = testnet.fetch_since(since).await.unwrap();
+        assert_eq!(fetched.len(), 1);
+        assert_eq!(fetched[0].fee_amount, 100);
+
+        // The unscoped repository still sees every network.
+        assert_eq!(repo.fetch_since(since).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn prune_only_touches_own_network() {
+        let repo = make_repo().await;
+        let testnet = repo.for_network(StellarNetwork::Testnet);
+        let futurenet = repo.for_network(StellarNetwork::Futurenet);
+
+        testnet
+            .insert_fee_points(&[make_point(100, 7200)])
+            .await
+            .unwrap();
+        futurenet
+            .insert_fee_points(&[make_point(200, 7200)])
+            .await
+            .unwrap();
+
+        let deleted = testnet
+            .prune_older_than(Utc::now() - Duration::hours(1))
+            .await
+            .unwrap();
+
+        assert_eq!(deleted, 1);
+        let remaining = futurenet
+            .fetch_since(Utc::now() - Duration::days(1))
+            .await
+            .unwrap();
+        assert_eq!(remaining.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn claim_untagged_points_assigns_legacy_rows() {
+        let repo = make_repo().await;
+        repo.insert_fee_points(&[make_point(100, 60)])
+            .await
+            .unwrap();
+
+        let mainnet = repo.for_network(StellarNetwork::Mainnet);
+        assert_eq!(mainnet.claim_untagged_points().await.unwrap(), 1);
+
+        let since = Utc::now() - Duration::hours(1);
+        assert_eq!(mainnet.fetch_since(since).await.unwrap().len(), 1);
+        assert!(repo
+            .for_network(StellarNetwork::Testnet)
+            .fetch_since(since)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    fn make_ledger(sequence: u64, operation_count: u32, seconds_ago: i64) -> LedgerInfo {
+        LedgerInfo {
+            sequence,
+            closed_at: Utc::now() - Duration::seconds(seconds_ago),
+            transaction_count: operation_count,
+            operation_count,
+            max_tx_set_size: 1000,
+            base_fee: 100,
+        }
+    }
+
+    #[tokio::test]
+    async fn ledgers_upsert_and_read_newest_first() {
+        let repo = make_repo().await.with_network(StellarNetwork::Testnet);
+        repo.insert_ledgers(&[make_ledger(10, 100, 10), make_ledger(11, 200, 5)])
+            .await
+            .unwrap();
+        // A re-fetched ledger replaces the stored row.
+        repo.insert_ledgers(&[make_ledger(11, 900, 5)])
+            .await
+            .unwrap();
+
+        let ledgers = repo.fetch_recent_ledgers(10).await.unwrap();
+        assert_eq!(ledgers.len(), 2);
+        assert_eq!(ledgers[0].sequence, 11);
+        assert_eq!(ledgers[0].operation_count, 900);
+        assert_eq!(ledgers[1].sequence, 10);
+
+        let other = repo.for_network(StellarNetwork::Mainnet);
+        assert!(other.fetch_recent_ledgers(10).await.unwrap().is_empty());
+    }
+
+    fn make_surge(peak_fee: u64, minutes_ago: i64) -> SurgeEpisode {
+        let start_time = Utc::now() - Duration::minutes(minutes_ago);
+        SurgeEpisode {
+            start_time,
+            end_time: start_time + Duration::minutes(3),
+            duration: Duration::minutes(3),
+            peak_fee,
+            baseline_fee: 100.0,
+            sample_count: 12,
+        }
+    }
+
+    #[tokio::test]
+    async fn surge_episodes_roundtrip_newest_first() {
+        let repo = make_repo().await.with_network(StellarNetwork::Testnet);
+        repo.insert_surge_episodes(&[
+            make_surge(500, 60 * 24 * 40), // 40 days ago — outside window
+            make_surge(800, 60 * 24 * 2),
+            make_surge(300, 30),
+        ])
+        .await
+        .unwrap();
+
+        let since = Utc::now() - Duration::days(30);
+        let surges = repo.fetch_surge_episodes_since(since).await.unwrap();
+        assert_eq!(surges.len(), 2);
+        assert_eq!(surges[0].peak_fee, 300);
+        assert_eq!(surges[0].duration, Duration::minutes(3));
+        assert_eq!(surges[0].sample_count, 12);
+        assert_eq!(surges[1].peak_fee, 800);
+
+        let other = repo.for_network(StellarNetwork::Mainnet);
+        assert!(other
+            .fetch_surge_episodes_since(since)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn insight_snapshots_roundtrip_oldest_first() {
+        let repo = make_repo().await.with_network(StellarNetwork::Testnet);
+        let engine = FeeInsightsEngine::new(InsightsConfig::default());
+        let snapshot = |minutes_ago: i64| {
+            let mut insights = engine.get_current_insights();
+            insights.last_updated = Utc::now() - Duration::minutes(minutes_ago);
+            insights
+        };
+        for minutes_ago in [120, 10, 30] {
+            repo.insert_insight_snapshot(&snapshot(minutes_ago))
+                .await
+                .unwrap();
+        }
+
+        let since = Utc::now() - Duration::hours(1);
+        let snapshots = repo.fetch_insight_snapshots_since(since).await.unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert!(snapshots[0].last_updated < snapshots[1].last_updated);
+        assert_eq!(
+            snapshots[0].rolling_averages.short_term.time_window.name,
+            "short_term"
+        );
+
+        let other = repo.for_network(StellarNetwork::Mainnet);
+        assert!(other
+            .fetch_insight_snapshots_since(since)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let pruned = repo
+            .prune_insight_snapshots_older_than(since)
+            .await
+            .unwrap();
+        assert_eq!(pruned, 1);
+    }
+
+    #[tokio::test]
+    async fn prune_ledgers_removes_old_closes() {
+        let repo = make_repo().await;
+        repo.insert_ledgers(&[make_ledger(1, 10, 7200), make_ledger(2, 10, 5)])
+            .await
+            .unwrap();
+
+        let pruned = repo
+            .prune_ledgers_older_than(Utc::now() - Duration::hours(1))
+            .await
+            .unwrap();
+
+        assert_eq!(pruned, 1);
+        let remaining = repo.fetch_recent_ledgers(10).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].sequence, 2);
+    }
+}
+#[cfg(test)]
+mod alert_tests {
+    use super::*;
+    use crate::db::create_pool;
+
+    async fn make_repo() -> FeeRepository {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        FeeRepository::new(pool)
+    }
+
+    #[tokio::test]
+    async fn insert_and_list_alert_config() {
+        let repo = make_repo().await;
+        let id = repo
+            .insert_alert_config("https://hooks.example.com/webhook", "Major")
+            .await
+            .unwrap();
+        assert!(id > 0);
+        let configs = repo.list_alert_configs().await.unwrap();
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].webhook_url, "https://hooks.example.com/webhook");
+        assert_eq!(configs[0].threshold, "Major");
+        assert!(configs[0].enabled);
+    }
+
+    #[tokio::test]
+    async fn update_alert_config_changes_threshold_and_enabled() {
         let repo = make_repo().await;
         let id = repo
             .insert_alert_config("https://hooks.example.com/a", "Minor")
@@ -604,6 +2468,27 @@ mod alert_tests {
         assert!(!deleted);
     }
 
+    #[tokio::test]
+    async fn revoked_api_keys_are_listed_but_inactive() {
+        let repo = make_repo().await;
+        let id = repo
+            .insert_api_key("dashboard", "hash_a", "sft_1234")
+            .await
+            .unwrap()
+            .id;
+        assert!(repo.is_active_api_key("hash_a").await.unwrap());
+        assert!(!repo.is_active_api_key("hash_b").await.unwrap());
+
+        assert!(repo.revoke_api_key(id).await.unwrap());
+        assert!(!repo.revoke_api_key(id).await.unwrap());
+        assert!(!repo.is_active_api_key("hash_a").await.unwrap());
+
+        let keys = repo.list_api_keys().await.unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].name, "dashboard");
+        assert!(keys[0].revoked_at.is_some());
+    }
+
     #[tokio::test]
     async fn full_crud_cycle() {
         let repo = make_repo().await;
@@ -773,4 +2658,38 @@ mod alert_event_tests {
         assert!(events[0].id.is_some());
         assert!(events[0].id.unwrap() > 0);
     }
+
+    #[tokio::test]
+    async fn ingestion_cursor_roundtrip_is_per_network() {
+        let repo = make_repo().await;
+        let testnet = repo.for_network(StellarNetwork::Testnet);
+        let mainnet = repo.for_network(StellarNetwork::Mainnet);
+        assert!(testnet.load_ingestion_cursor().await.unwrap().is_none());
+
+        let cursor = |token: &str, ledger| PagingCursor {
+            paging_token: token.to_string(),
+            ledger_sequence: ledger,
+        };
+        testnet
+            .save_ingestion_cursor(&cursor("100", 10))
+            .await
+            .unwrap();
+        testnet
+            .save_ingestion_cursor(&cursor("200", 11))
+            .await
+            .unwrap();
+        mainnet
+            .save_ingestion_cursor(&cursor("900", 90))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            testnet.load_ingestion_cursor().await.unwrap(),
+            Some(cursor("200", 11))
+        );
+        let all = repo.list_ingestion_cursors().await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].network, "mainnet");
+        assert_eq!(all[1].paging_token, "200");
+    }
 }
@@ -0,0 +1,525 @@
+//! Downsampled fee rollups.
+//!
+//! Raw fee points are summarised into 1-minute, 5-minute, 1-hour and 1-day
+//! buckets (count, min, max, mean and percentiles) stored in `fee_rollups`,
+//! so long-range queries read a few hundred rows instead of every point.
+//!
+//! Only closed buckets are rolled up, each once, straight from the raw
+//! points: percentiles cannot be merged from finer rollups. Points
+//! backfilled behind the newest rollup are therefore not rolled up. Rollups
+//! are not pruned with the raw points, so they outlive the retention window.
+//!
+//! [`FeeHeatmap`] spreads stored rollups over fee buckets for rendering,
+//! [`merged_quantile`] estimates percentiles across many rollups and
+//! [`bucket_stats`] merges rollups into chart buckets of any whole number
+//! of minutes, all without touching the raw points.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::insights::calculator::fee_distribution;
+use crate::insights::types::FeeDistribution;
+use crate::repository::FeeRepository;
+
+/// Most raw history rolled up per resolution in one call, so catching up
+/// after downtime never loads the whole table at once.
+const MAX_CATCH_UP: Duration = Duration::days(1);
+
+/// Bucket width of a rollup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Resolution {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 4] = [
+        Resolution::OneMinute,
+        Resolution::FiveMinutes,
+        Resolution::OneHour,
+        Resolution::OneDay,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Resolution::OneMinute => "1m",
+            Resolution::FiveMinutes => "5m",
+            Resolution::OneHour => "1h",
+            Resolution::OneDay => "1d",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.as_str() == value)
+    }
+
+    pub fn duration(self) -> Duration {
+        match self {
+            Resolution::OneMinute => Duration::minutes(1),
+            Resolution::FiveMinutes => Duration::minutes(5),
+            Resolution::OneHour => Duration::hours(1),
+            Resolution::OneDay => Duration::days(1),
+        }
+    }
+
+    /// Start of the bucket containing `at`. Buckets align to the Unix
+    /// epoch, so days start at UTC midnight.
+    pub fn bucket_start(self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let width = self.duration().num_seconds();
+        let seconds = at.timestamp().div_euclid(width) * width;
+        Utc.timestamp_opt(seconds, 0).single().unwrap_or(at)
+    }
+}
+
+/// Fees charged within one bucket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeRollup {
+    pub resolution: Resolution,
+    pub bucket_start: DateTime<Utc>,
+    pub sample_count: u64,
+    pub min_fee: u64,
+    pub max_fee: u64,
+    pub avg_fee: f64,
+    pub percentiles: FeeDistribution,
+}
+
+impl FeeRollup {
+    /// Summarise `fees` charged in the bucket starting at `bucket_start`,
+    /// or `None` when it is empty.
+    pub fn summarize(
+        resolution: Resolution,
+        bucket_start: DateTime<Utc>,
+        mut fees: Vec<u64>,
+    ) -> Option<Self> {
+        fees.sort_unstable();
+        let percentiles = fee_distribution(&fees)?;
+        let total: u128 = fees.iter().map(|&fee| fee as u128).sum();
+
+        Some(Self {
+            resolution,
+            bucket_start,
+            sample_count: fees.len() as u64,
+            min_fee: fees[0],
+            max_fee: fees[fees.len() - 1],
+            avg_fee: total as f64 / fees.len() as f64,
+            percentiles,
+        })
+    }
+}
+
+/// Group `fees` (timestamp, fee) into buckets of `resolution`, oldest first.
+pub fn roll_up(resolution: Resolution, fees: &[(DateTime<Utc>, u64)]) -> Vec<FeeRollup> {
+    let mut buckets: BTreeMap<DateTime<Utc>, Vec<u64>> = BTreeMap::new();
+    for &(timestamp, fee) in fees {
+        buckets
+            .entry(resolution.bucket_start(timestamp))
+            .or_default()
+            .push(fee);
+    }
+
+    buckets
+        .into_iter()
+        .filter_map(|(start, fees)| FeeRollup::summarize(resolution, start, fees))
+        .collect()
+}
+
+/// Roll up every bucket that has closed by `now` and is not stored yet,
+/// at each resolution. Returns the number of rollups written.
+pub async fn roll_up_closed_buckets(
+    repo: &FeeRepository,
+    now: DateTime<Utc>,
+) -> Result<usize, sqlx::Error> {
+    let mut written = 0;
+    for resolution in Resolution::ALL {
+        let resume = repo
+            .latest_rollup_start(resolution)
+            .await?
+            .map(|latest| latest + resolution.duration());
+        // Skip straight past gaps with no points
+        let Some(next_point) = repo.first_point_time_since(resume).await? else {
+            continue;
+        };
+        let from = resolution.bucket_start(next_point);
+        // The bucket `now` falls in is still open
+        let to = resolution
+            .bucket_start(from + MAX_CATCH_UP.max(resolution.duration()))
+            .min(resolution.bucket_start(now));
+        if from >= to {
+            continue;
+        }
+
+        let fees = repo.fetch_fees_between(from, to).await?;
+        let rollups = roll_up(resolution, &fees);
+        repo.upsert_rollups(&rollups).await?;
+        written += rollups.len();
+    }
+
+    Ok(written)
+}
+
+/// Fee counts per time bucket and fee bucket, ready to render
+///
+/// Counts are estimates: each rollup's samples are spread evenly between
+/// its neighbouring percentiles, so they are fractional.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeHeatmap {
+    pub resolution: Resolution,
+    /// Start of each time bucket, oldest first.
+    pub time_buckets: Vec<DateTime<Utc>>,
+    /// Fee bucket `i` covers `[fee_edges[i], fee_edges[i + 1])` stroops.
+    pub fee_edges: Vec<u64>,
+    /// `counts[t][f]` samples in time bucket `t` and fee bucket `f`.
+    pub counts: Vec<Vec<f64>>,
+}
+
+impl FeeHeatmap {
+    /// Build a heatmap of `rollups` (oldest first, all at `resolution`)
+    /// with up to `fee_buckets` logarithmic fee buckets spanning every fee.
+    pub fn from_rollups(resolution: Resolution, rollups: &[FeeRollup], fee_buckets: usize) -> Self {
+        let fee_edges = match (
+            rollups.iter().map(|r| r.min_fee).min(),
+            rollups.iter().map(|r| r.max_fee).max(),
+        ) {
+            (Some(min), Some(max)) => log_edges(min, max + 1, fee_buckets.max(1)),
+            _ => Vec::new(),
+        };
+
+        Self {
+            resolution,
+            time_buckets: rollups.iter().map(|r| r.bucket_start).collect(),
+            counts: rollups.iter().map(|r| spread(r, &fee_edges)).collect(),
+            fee_edges,
+        }
+    }
+}
+
+/// `bins + 1` edges from `lower` to `upper`, evenly spaced in log scale.
+fn log_edges(lower: u64, upper: u64, bins: usize) -> Vec<u64> {
+    let step = (upper as f64 / lower.max(1) as f64).powf(1.0 / bins as f64);
+    let mut edges = vec![lower];
+    edges.extend((1..bins).map(|i| (lower.max(1) as f64 * step.powi(i as i32)).round() as u64));
+    edges.push(upper);
+    edges.sort_unstable();
+    edges.dedup();
+    edges
+}
+
+/// Cumulative share of `rollup`'s samples at each percentile it stores.
+fn knots(rollup: &FeeRollup) -> [(f64, u64); 9] {
+    let p = &rollup.percentiles;
+    [
+        (0.0, rollup.min_fee),
+        (0.10, p.p10),
+        (0.25, p.p25),
+        (0.50, p.p50),
+        (0.75, p.p75),
+        (0.90, p.p90),
+        (0.95, p.p95),
+        (0.99, p.p99),
+        (1.0, rollup.max_fee),
+    ]
+}
+
+/// Estimated samples of `rollup` per fee bucket between `edges`.
+fn spread(rollup: &FeeRollup, edges: &[u64]) -> Vec<f64> {
+    let mut counts = vec![0.0; edges.len().saturating_sub(1)];
+    for pair in knots(rollup).windows(2) {
+        let ((q0, low), (q1, high)) = (pair[0], pair[1]);
+        let samples = (q1 - q0) * rollup.sample_count as f64;
+        for (i, count) in counts.iter_mut().enumerate() {
+            let (lower, upper) = (edges[i], edges[i + 1]);
+            if low == high {
+                if (lower..upper).contains(&low) {
+                    *count += samples;
+                }
+            } else {
+                let overlap = high.min(upper).saturating_sub(low.max(lower));
+                *count += samples * overlap as f64 / (high - low) as f64;
+            }
+        }
+    }
+    counts
+}
+
+/// Estimated samples of `rollup` at or below `fee`, spreading them the
+/// same way as [`FeeHeatmap`].
+fn samples_at_or_below(rollup: &FeeRollup, fee: f64) -> f64 {
+    let share: f64 = knots(rollup)
+        .windows(2)
+        .map(|pair| {
+            let ((q0, low), (q1, high)) = (pair[0], pair[1]);
+            let (low, high) = (low as f64, high as f64);
+            if high <= fee {
+                q1 - q0
+            } else if low >= fee {
+                0.0
+            } else {
+                (q1 - q0) * (fee - low) / (high - low)
+            }
+        })
+        .sum();
+    share * rollup.sample_count as f64
+}
+
+/// Estimated `quantile` (0.0–1.0) of the fees across all of `rollups`,
+/// or `None` when they hold no samples.
+pub fn merged_quantile(rollups: &[FeeRollup], quantile: f64) -> Option<f64> {
+    let total: u64 = rollups.iter().map(|r| r.sample_count).sum();
+    let mut low = rollups.iter().map(|r| r.min_fee).min()? as f64;
+    let mut high = rollups.iter().map(|r| r.max_fee).max()? as f64;
+    if total == 0 {
+        return None;
+    }
+
+    let target = quantile.clamp(0.0, 1.0) * total as f64;
+    for _ in 0..64 {
+        let mid = (low + high) / 2.0;
+        let below: f64 = rollups.iter().map(|r| samples_at_or_below(r, mid)).sum();
+        if below >= target {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    Some(high)
+}
+
+/// Fees in one chart bucket, in stroops
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeStatsBucket {
+    pub start: DateTime<Utc>,
+    pub count: u64,
+    pub min: u64,
+    pub avg: f64,
+    pub max: u64,
+    /// Exact when the bucket is a single stored rollup, estimated with
+    /// [`merged_quantile`] when it spans several.
+    #[serde(flatten)]
+    pub percentiles: FeeDistribution,
+}
+
+/// Width of a chart bucket written as a whole number of minutes, hours or
+/// days, e.g. `15m`, `6h` or `7d`.
+pub fn parse_bucket(value: &str) -> Option<Duration> {
+    let split = value.len().checked_sub(1)?;
+    let (count, unit) = value.split_at(split);
+    let count: i64 = count.parse().ok().filter(|count| *count > 0)?;
+    match unit {
+        "m" => Duration::try_minutes(count),
+        "h" => Duration::try_hours(count),
+        "d" => Duration::try_days(count),
+        _ => None,
+    }
+}
+
+/// The coarsest stored resolution that tiles buckets of `bucket`, so
+/// each bucket is merged from as few rollups as possible.
+pub fn source_resolution(bucket: Duration) -> Option<Resolution> {
+    Resolution::ALL
+        .into_iter()
+        .rev()
+        .find(|resolution| bucket.num_seconds() % resolution.duration().num_seconds() == 0)
+}
+
+/// Start of the `bucket`-wide chart bucket holding `at`; buckets are
+/// aligned to the Unix epoch, as rollups are.
+pub fn stats_bucket_start(bucket: Duration, at: DateTime<Utc>) -> DateTime<Utc> {
+    let width = bucket.num_seconds().max(1);
+    let start = at.timestamp().div_euclid(width) * width;
+    Utc.timestamp_opt(start, 0).single().unwrap_or(at)
+}
+
+/// Merge `rollups` (oldest first, all at one resolution that tiles
+/// `bucket`) into `bucket`-wide chart buckets. Buckets without rollups are
+/// left out.
+pub fn bucket_stats(rollups: &[FeeRollup], bucket: Duration) -> Vec<FeeStatsBucket> {
+    let mut groups: BTreeMap<DateTime<Utc>, Vec<FeeRollup>> = BTreeMap::new();
+    for rollup in rollups {
+        groups
+            .entry(stats_bucket_start(bucket, rollup.bucket_start))
+            .or_default()
+            .push(rollup.clone());
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|(start, group)| {
+            let count: u64 = group.iter().map(|r| r.sample_count).sum();
+            if count == 0 {
+                return None;
+            }
+            let total: f64 = group
+                .iter()
+                .map(|r| r.avg_fee * r.sample_count as f64)
+                .sum();
+            let percentiles = match group.as_slice() {
+                [single] => single.percentiles.clone(),
+                _ => {
+                    let estimate =
+                        |q: f64| merged_quantile(&group, q).map_or(0, |fee| fee.round() as u64);
+                    FeeDistribution {
+                        p10: estimate(0.10),
+                        p25: estimate(0.25),
+                        p50: estimate(0.50),
+                        p75: estimate(0.75),
+                        p90: estimate(0.90),
+                        p95: estimate(0.95),
+                        p99: estimate(0.99),
+                    }
+                }
+            };
+            Some(FeeStatsBucket {
+                start,
+                count,
+                min: group.iter().map(|r| r.min_fee).min()?,
+                avg: total / count as f64,
+                max: group.iter().map(|r| r.max_fee).max()?,
+                percentiles,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 15, hour, minute, second)
+            .unwrap()
+    }
+
+    #[test]
+    fn buckets_align_to_their_resolution() {
+        let t = at(10, 7, 42);
+        assert_eq!(Resolution::OneMinute.bucket_start(t), at(10, 7, 0));
+        assert_eq!(Resolution::FiveMinutes.bucket_start(t), at(10, 5, 0));
+        assert_eq!(Resolution::OneHour.bucket_start(t), at(10, 0, 0));
+        assert_eq!(Resolution::OneDay.bucket_start(t), at(0, 0, 0));
+        assert_eq!(Resolution::parse("5m"), Some(Resolution::FiveMinutes));
+        assert_eq!(Resolution::parse("2h"), None);
+    }
+
+    #[test]
+    fn roll_up_summarises_each_bucket() {
+        let fees = [
+            (at(10, 0, 5), 100),
+            (at(10, 0, 50), 300),
+            (at(10, 0, 30), 200),
+            (at(10, 2, 0), 1_000),
+        ];
+
+        let rollups = roll_up(Resolution::OneMinute, &fees);
+        assert_eq!(rollups.len(), 2);
+        assert_eq!(rollups[0].bucket_start, at(10, 0, 0));
+        assert_eq!(rollups[0].sample_count, 3);
+        assert_eq!((rollups[0].min_fee, rollups[0].max_fee), (100, 300));
+        assert_eq!(rollups[0].avg_fee, 200.0);
+        assert_eq!(rollups[0].percentiles.p50, 200);
+        assert_eq!(rollups[1].bucket_start, at(10, 2, 0));
+
+        let hourly = roll_up(Resolution::OneHour, &fees);
+        assert_eq!(hourly.len(), 1);
+        assert_eq!(hourly[0].sample_count, 4);
+        assert_eq!(hourly[0].max_fee, 1_000);
+    }
+
+    #[test]
+    fn buckets_parse_and_pick_the_coarsest_tiling_resolution() {
+        assert_eq!(parse_bucket("15m"), Some(Duration::minutes(15)));
+        assert_eq!(parse_bucket("6h"), Some(Duration::hours(6)));
+        assert_eq!(parse_bucket("7d"), Some(Duration::days(7)));
+        for invalid in ["", "m", "0m", "-5m", "5s", "1.5h"] {
+            assert_eq!(parse_bucket(invalid), None, "{}", invalid);
+        }
+        assert_eq!(
+            source_resolution(Duration::minutes(15)),
+            Some(Resolution::FiveMinutes)
+        );
+        assert_eq!(
+            source_resolution(Duration::hours(6)),
+            Some(Resolution::OneHour)
+        );
+        assert_eq!(
+            source_resolution(Duration::minutes(7)),
+            Some(Resolution::OneMinute)
+        );
+    }
+
+    #[test]
+    fn bucket_stats_merge_rollups() {
+        let fees: Vec<(DateTime<Utc>, u64)> = (0..30)
+            .map(|minute| (at(10, minute, 0), 100 + minute as u64 * 10))
+            .collect();
+        let rollups = roll_up(Resolution::FiveMinutes, &fees);
+
+        // One rollup per bucket keeps its exact percentiles
+        let stats = bucket_stats(&rollups, Duration::minutes(5));
+        assert_eq!(stats.len(), 6);
+        assert_eq!(stats[0].percentiles, rollups[0].percentiles);
+
+        let stats = bucket_stats(&rollups, Duration::minutes(15));
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].start, at(10, 0, 0));
+        assert_eq!(stats[1].start, at(10, 15, 0));
+        assert_eq!(stats[0].count, 15);
+        assert_eq!((stats[0].min, stats[0].max), (100, 240));
+        assert!((stats[0].avg - 170.0).abs() < 1e-9);
+        let p50 = stats[0].percentiles.p50;
+        assert!((150..=190).contains(&p50), "p50 {}", p50);
+    }
+
+    #[test]
+    fn heatmap_spreads_rollups_over_fee_buckets() {
+        let flat = |start, fee, sample_count| FeeRollup {
+            resolution: Resolution::OneHour,
+            bucket_start: start,
+            sample_count,
+            min_fee: fee,
+            max_fee: fee,
+            avg_fee: fee as f64,
+            percentiles: FeeDistribution {
+                p10: fee,
+                p25: fee,
+                p50: fee,
+                p75: fee,
+                p90: fee,
+                p95: fee,
+                p99: fee,
+            },
+        };
+        let mut spread_out = roll_up(Resolution::OneHour, &[(at(12, 0, 0), 100)])[0].clone();
+        spread_out.bucket_start = at(11, 0, 0);
+        spread_out.max_fee = 10_000;
+        spread_out.sample_count = 10;
+        let rollups = [
+            flat(at(10, 0, 0), 100, 4),
+            spread_out,
+            flat(at(12, 0, 0), 10_000, 2),
+        ];
+
+        let heatmap = FeeHeatmap::from_rollups(Resolution::OneHour, &rollups, 2);
+        assert_eq!(
+            heatmap.time_buckets,
+            vec![at(10, 0, 0), at(11, 0, 0), at(12, 0, 0)]
+        );
+        assert_eq!(heatmap.fee_edges, vec![100, 1_000, 10_001]);
+        assert_eq!(heatmap.counts[0], vec![4.0, 0.0]);
+        assert_eq!(heatmap.counts[2], vec![0.0, 2.0]);
+        // Only the top 1% of the second hour lies between p99 and the max
+        let total: f64 = heatmap.counts[1].iter().sum();
+        assert!((total - 10.0).abs() < 1e-9);
+        assert!((heatmap.counts[1][0] - 9.9 - 0.1 * 900.0 / 9_900.0).abs() < 1e-9);
+
+        let empty = FeeHeatmap::from_rollups(Resolution::OneHour, &[], 10);
+        assert!(empty.fee_edges.is_empty() && empty.counts.is_empty());
+    }
+}
@@ -7,23 +7,58 @@
 //! Network errors are retried with exponential backoff + jitter (Issue #10).
 //! Parse errors are not retried — malformed data won't fix itself.
 //! DB write errors are logged but never crash the scheduler.
+//!
+//! `run_fee_streaming` is the push-based alternative: points arrive from a
+//! `StreamingFeeDataProvider` and are ingested one ledger at a time.
+//!
+//! Both loops beat a [`SchedulerHeartbeat`] so health checks can tell a
+//! stalled scheduler from a quiet one.
 
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
-use chrono::Utc;
+use chrono::{DateTime, TimeZone, Utc};
 use tokio::signal;
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
 use tokio::time;
 
 use crate::alerts::AlertManager;
 use crate::insights::error::ProviderError;
 use crate::insights::types::FeeDataPoint;
-use crate::insights::{FeeDataProvider, FeeInsightsEngine};
+use crate::insights::{FeeDataProvider, FeeInsightsEngine, StreamingFeeDataProvider};
 use crate::metrics::AppMetrics;
 use crate::repository::FeeRepository;
+use crate::rollup::roll_up_closed_buckets;
+use crate::sla::SAMPLE_RETENTION;
 use crate::store::FeeHistoryStore;
 
+/// When the scheduler last completed a poll or received a streamed point
+#[derive(Debug, Default)]
+pub struct SchedulerHeartbeat {
+    /// Unix milliseconds; 0 before the first beat.
+    last_beat_ms: AtomicI64,
+}
+
+impl SchedulerHeartbeat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn beat(&self) {
+        self.last_beat_ms
+            .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    /// `None` before the first beat.
+    pub fn last_beat(&self) -> Option<DateTime<Utc>> {
+        match self.last_beat_ms.load(Ordering::Relaxed) {
+            0 => None,
+            ms => Utc.timestamp_millis_opt(ms).single(),
+        }
+    }
+}
+
 /// Full polling loop with configurable retry parameters and optional DB persistence.
 #[allow(clippy::too_many_arguments)]
 pub async fn run_fee_polling_with_retry(
@@ -37,6 +72,7 @@ pub async fn run_fee_polling_with_retry(
     storage_retention_days: u64,
     metrics: Option<Arc<AppMetrics>>,
     alert_manager: Option<Arc<AlertManager>>,
+    heartbeat: Option<Arc<SchedulerHeartbeat>>,
 ) {
     let mut interval = time::interval(Duration::from_secs(poll_interval_seconds));
 
@@ -61,6 +97,9 @@ pub async fn run_fee_polling_with_retry(
                     metrics.as_deref(),
                     alert_manager.as_deref(),
                 ).await;
+                if let Some(heartbeat) = &heartbeat {
+                    heartbeat.beat();
+                }
             }
 
             _ = signal::ctrl_c() => {
@@ -74,8 +113,9 @@ pub async fn run_fee_polling_with_retry(
 }
 
 /// Execute a single poll cycle with retry and optional persistence.
+/// Returns the number of fee data points ingested.
 #[allow(clippy::too_many_arguments)]
-async fn poll_once(
+pub async fn poll_once(
     horizon_provider: &Arc<dyn FeeDataProvider + Send + Sync>,
     history_store: &Arc<RwLock<FeeHistoryStore>>,
     insights_engine: &Arc<RwLock<FeeInsightsEngine>>,
@@ -85,18 +125,24 @@ async fn poll_once(
     storage_retention_days: u64,
     metrics: Option<&AppMetrics>,
     alert_manager: Option<&AlertManager>,
-) {
+) -> usize {
     if let Some(m) = metrics {
         m.polls_total.inc();
     }
 
-    let points = match fetch_with_retry(
+    let fetched = fetch_with_retry(
         horizon_provider.as_ref(),
         max_retry_attempts,
         base_retry_delay_ms,
     )
-    .await
-    {
+    .await;
+
+    let status = horizon_provider.provider_status();
+    if let Some(m) = metrics {
+        m.record_provider_status(&status);
+    }
+
+    let points = match fetched {
         Some(p) => p,
         None => {
             if let Some(m) = metrics {
@@ -106,19 +152,102 @@ async fn poll_once(
                 "All {} retry attempts exhausted — skipping tick",
                 max_retry_attempts
             );
-            return;
+            return 0;
         }
     };
 
     if points.is_empty() {
         tracing::warn!("Provider returned no fee data points this tick");
-        return;
+        return 0;
+    }
+
+    // Stale data from an open circuit breaker has already been ingested.
+    if status.circuit_breaker.is_some_and(|b| b.serving_stale) {
+        tracing::warn!("Provider circuit is open — skipping ingestion of stale data");
+        return 0;
+    }
+
+    ingest_points(
+        &points,
+        history_store,
+        insights_engine,
+        repository,
+        storage_retention_days,
+        metrics,
+        alert_manager,
+    )
+    .await;
+    record_fee_stats(horizon_provider.as_ref(), insights_engine).await;
+    record_ledgers(
+        horizon_provider.as_ref(),
+        insights_engine,
+        repository,
+        storage_retention_days,
+    )
+    .await;
+    points.len()
+}
+
+/// Feed the provider's `/fee_stats` view into the engine as a cross-check.
+/// Providers without fee stats are skipped silently.
+async fn record_fee_stats(
+    provider: &(dyn FeeDataProvider + Send + Sync),
+    insights_engine: &Arc<RwLock<FeeInsightsEngine>>,
+) {
+    match provider.fetch_fee_stats().await {
+        Ok(snapshot) => insights_engine.write().await.record_fee_stats(snapshot),
+        Err(ProviderError::Unsupported { .. }) => {}
+        Err(err) => tracing::warn!("Failed to fetch fee stats: {}", err),
     }
+}
 
+/// Feed recently closed ledgers into the engine and persist them, so
+/// congestion reflects capacity utilization. Providers without ledger data
+/// are skipped silently.
+async fn record_ledgers(
+    provider: &(dyn FeeDataProvider + Send + Sync),
+    insights_engine: &Arc<RwLock<FeeInsightsEngine>>,
+    repository: Option<&FeeRepository>,
+    storage_retention_days: u64,
+) {
+    let ledgers = match provider.fetch_recent_ledgers().await {
+        Ok(ledgers) => ledgers,
+        Err(ProviderError::Unsupported { .. }) => return,
+        Err(err) => {
+            tracing::warn!("Failed to fetch recent ledgers: {}", err);
+            return;
+        }
+    };
+    insights_engine.write().await.record_ledgers(&ledgers);
+
+    if let Some(repo) = repository {
+        if let Err(err) = repo.insert_ledgers(&ledgers).await {
+            tracing::warn!("Failed to persist ledgers to DB: {}", err);
+        }
+
+        let cutoff = Utc::now() - chrono::Duration::days(storage_retention_days as i64);
+        match repo.prune_ledgers_older_than(cutoff).await {
+            Ok(n) if n > 0 => tracing::debug!("Pruned {} old ledgers from DB", n),
+            Ok(_) => {}
+            Err(err) => tracing::warn!("Failed to prune old ledgers: {}", err),
+        }
+    }
+}
+
+/// Push a batch into the store, run the insights engine, and persist it.
+async fn ingest_points(
+    points: &[FeeDataPoint],
+    history_store: &Arc<RwLock<FeeHistoryStore>>,
+    insights_engine: &Arc<RwLock<FeeInsightsEngine>>,
+    repository: Option<&FeeRepository>,
+    storage_retention_days: u64,
+    metrics: Option<&AppMetrics>,
+    alert_manager: Option<&AlertManager>,
+) {
     // Push into in-memory store
     {
         let mut store = history_store.write().await;
-        for point in &points {
+        for point in points {
             store.push(point.clone());
         }
         let store_len = store.len();
@@ -129,9 +258,9 @@ async fn poll_once(
     }
 
     // Run insights engine
-    {
+    let (completed_surges, congestion, all_time_extremes, snapshot) = {
         let mut engine = insights_engine.write().await;
-        match engine.process_fee_data(&points).await {
+        let (completed_surges, congestion) = match engine.process_fee_data(points).await {
             Ok(update) => {
                 tracing::info!(
                     "Insights updated — {} points processed, short-term avg: {:.1} stroops",
@@ -147,16 +276,30 @@ async fn poll_once(
                 if let Some(manager) = alert_manager {
                     manager.check_and_dispatch(&update).await;
                 }
+                let congestion = (
+                    update.state_transitions,
+                    update.insights.congestion_trends.congestion_state,
+                    update.fee_ratio,
+                    update.insights.last_updated,
+                );
+                (update.completed_surges, Some(congestion))
             }
             Err(err) => {
                 tracing::error!("Insights engine error: {}", err);
+                (Vec::new(), None)
             }
-        }
-    }
+        };
+        (
+            completed_surges,
+            congestion,
+            engine.get_all_time_extremes(),
+            engine.take_due_snapshot(),
+        )
+    };
 
     // Persist to DB (non-fatal on error)
     if let Some(repo) = repository {
-        match repo.insert_fee_points(&points).await {
+        match repo.insert_fee_points(points).await {
             Ok(()) => {
                 tracing::debug!("Persisted {} fee points to DB", points.len());
             }
@@ -165,13 +308,181 @@ async fn poll_once(
             }
         }
 
+        // Surge episodes are kept past the fee retention window
+        if let Err(err) = repo.insert_surge_episodes(&completed_surges).await {
+            tracing::warn!("Failed to persist surge episodes to DB: {}", err);
+        }
+        if let Some((transitions, state, fee_ratio, at)) = &congestion {
+            if let Err(err) = repo
+                .record_congestion_episodes(transitions, *state, *fee_ratio, *at)
+                .await
+            {
+                tracing::warn!("Failed to persist congestion episodes to DB: {}", err);
+            }
+        }
+        if let Some(extremes) = &all_time_extremes {
+            if let Err(err) = repo.save_all_time_extremes(extremes).await {
+                tracing::warn!("Failed to persist all-time fee extremes to DB: {}", err);
+            }
+        }
+
+        if let Some(insights) = &snapshot {
+            if let Err(err) = repo.insert_insight_snapshot(insights).await {
+                tracing::warn!("Failed to persist insight snapshot to DB: {}", err);
+            }
+            let state = insights.congestion_trends.congestion_state;
+            if let Err(err) = repo
+                .insert_congestion_sample(insights.last_updated, state)
+                .await
+            {
+                tracing::warn!("Failed to persist congestion sample to DB: {}", err);
+            }
+        }
+
+        // Roll up before pruning so no point leaves unsummarised
+        match roll_up_closed_buckets(repo, Utc::now()).await {
+            Ok(n) if n > 0 => tracing::debug!("Wrote {} fee rollups to DB", n),
+            Ok(_) => {}
+            Err(err) => tracing::warn!("Failed to roll up fee points: {}", err),
+        }
+
         let cutoff = Utc::now() - chrono::Duration::days(storage_retention_days as i64);
         match repo.prune_older_than(cutoff).await {
             Ok(n) if n > 0 => tracing::debug!("Pruned {} old fee points from DB", n),
             Ok(_) => {}
             Err(err) => tracing::warn!("Failed to prune old fee points: {}", err),
         }
+        match repo.prune_insight_snapshots_older_than(cutoff).await {
+            Ok(n) if n > 0 => tracing::debug!("Pruned {} old insight snapshots from DB", n),
+            Ok(_) => {}
+            Err(err) => tracing::warn!("Failed to prune old insight snapshots: {}", err),
+        }
+        let sample_cutoff = Utc::now() - SAMPLE_RETENTION;
+        match repo
+            .prune_congestion_samples_older_than(sample_cutoff)
+            .await
+        {
+            Ok(n) if n > 0 => tracing::debug!("Pruned {} old congestion samples from DB", n),
+            Ok(_) => {}
+            Err(err) => tracing::warn!("Failed to prune old congestion samples: {}", err),
+        }
+    }
+}
+
+/// Streaming ingestion loop: consumes points pushed by `provider` and ingests
+/// them one ledger at a time.
+///
+/// A ledger's batch is flushed as soon as a point from a later ledger arrives,
+/// or after `flush_after` of silence so the last ledger closed is never held back.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_fee_streaming(
+    provider: Arc<dyn StreamingFeeDataProvider + Send + Sync>,
+    history_store: Arc<RwLock<FeeHistoryStore>>,
+    insights_engine: Arc<RwLock<FeeInsightsEngine>>,
+    flush_after: Duration,
+    repository: Option<Arc<FeeRepository>>,
+    storage_retention_days: u64,
+    metrics: Option<Arc<AppMetrics>>,
+    alert_manager: Option<Arc<AlertManager>>,
+    heartbeat: Option<Arc<SchedulerHeartbeat>>,
+) {
+    let (sender, mut receiver) = mpsc::channel::<FeeDataPoint>(1024);
+    let stream_provider = provider.clone();
+    let stream_task = tokio::spawn(async move { stream_provider.stream_fees(sender).await });
+
+    tracing::info!(
+        "Fee streaming started via {} (retention: {}d)",
+        provider.provider_name(),
+        storage_retention_days,
+    );
+
+    let mut pending: Vec<FeeDataPoint> = Vec::new();
+
+    loop {
+        tokio::select! {
+            received = receiver.recv() => {
+                let Some(point) = received else {
+                    tracing::warn!("Fee stream ended");
+                    break;
+                };
+                if let Some(heartbeat) = &heartbeat {
+                    heartbeat.beat();
+                }
+                let ledger_changed = pending
+                    .last()
+                    .is_some_and(|last| last.ledger_sequence != point.ledger_sequence);
+                if ledger_changed {
+                    flush_ledger(
+                        &mut pending,
+                        &history_store,
+                        &insights_engine,
+                        repository.as_deref(),
+                        storage_retention_days,
+                        metrics.as_deref(),
+                        alert_manager.as_deref(),
+                    ).await;
+                }
+                pending.push(point);
+            }
+
+            _ = time::sleep(flush_after), if !pending.is_empty() => {
+                flush_ledger(
+                    &mut pending,
+                    &history_store,
+                    &insights_engine,
+                    repository.as_deref(),
+                    storage_retention_days,
+                    metrics.as_deref(),
+                    alert_manager.as_deref(),
+                ).await;
+            }
+
+            _ = signal::ctrl_c() => {
+                tracing::info!("Shutdown signal received. Stopping stream.");
+                break;
+            }
+        }
+    }
+
+    flush_ledger(
+        &mut pending,
+        &history_store,
+        &insights_engine,
+        repository.as_deref(),
+        storage_retention_days,
+        metrics.as_deref(),
+        alert_manager.as_deref(),
+    )
+    .await;
+    stream_task.abort();
+
+    tracing::info!("Fee streaming stopped cleanly");
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn flush_ledger(
+    pending: &mut Vec<FeeDataPoint>,
+    history_store: &Arc<RwLock<FeeHistoryStore>>,
+    insights_engine: &Arc<RwLock<FeeInsightsEngine>>,
+    repository: Option<&FeeRepository>,
+    storage_retention_days: u64,
+    metrics: Option<&AppMetrics>,
+    alert_manager: Option<&AlertManager>,
+) {
+    if pending.is_empty() {
+        return;
     }
+    let points = std::mem::take(pending);
+    ingest_points(
+        &points,
+        history_store,
+        insights_engine,
+        repository,
+        storage_retention_days,
+        metrics,
+        alert_manager,
+    )
+    .await;
 }
 
 /// Attempt to fetch fee data, retrying on network errors with exponential
@@ -180,7 +491,7 @@ async fn poll_once(
 /// Returns `Some(points)` on the first successful fetch, or `None` if all
 /// attempts are exhausted.
 pub async fn fetch_with_retry(
-    provider: &dyn FeeDataProvider,
+    provider: &(dyn FeeDataProvider + Send + Sync),
     max_attempts: u32,
     base_delay_ms: u64,
 ) -> Option<Vec<FeeDataPoint>> {
@@ -240,6 +551,8 @@ mod tests {
             timestamp: Utc::now(),
             transaction_hash: format!("hash_{}", fee_amount),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         }
     }
 
@@ -318,6 +631,65 @@ mod tests {
         assert!(store.read().await.is_empty());
     }
 
+    // ---- run_fee_streaming tests ----
+
+    /// Streams a fixed list of points, then closes the stream.
+    struct FiniteStream {
+        points: Vec<FeeDataPoint>,
+    }
+
+    #[async_trait::async_trait]
+    impl FeeDataProvider for FiniteStream {
+        async fn fetch_latest_fees(&self) -> Result<Vec<FeeDataPoint>, ProviderError> {
+            Ok(self.points.clone())
+        }
+
+        fn provider_name(&self) -> &str {
+            "FiniteStream"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl StreamingFeeDataProvider for FiniteStream {
+        async fn stream_fees(
+            &self,
+            sender: mpsc::Sender<FeeDataPoint>,
+        ) -> Result<(), ProviderError> {
+            for point in &self.points {
+                let _ = sender.send(point.clone()).await;
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn run_fee_streaming_ingests_every_ledger_until_stream_ends() {
+        let mut points = vec![make_point(100), make_point(200), make_point(300)];
+        points[2].ledger_sequence = 2;
+        let provider = Arc::new(FiniteStream { points });
+        let store = make_shared_store();
+        let engine = make_shared_engine();
+        let heartbeat = Arc::new(SchedulerHeartbeat::new());
+        assert!(heartbeat.last_beat().is_none());
+
+        run_fee_streaming(
+            provider,
+            store.clone(),
+            engine.clone(),
+            Duration::from_millis(50),
+            None,
+            7,
+            None,
+            None,
+            Some(heartbeat.clone()),
+        )
+        .await;
+
+        assert_eq!(store.read().await.len(), 3);
+        assert!(engine.read().await.get_last_update().is_some());
+        assert!(heartbeat.last_beat().is_some());
+    }
+
     // ---- fetch_with_retry tests ----
 
     #[tokio::test]
@@ -335,6 +707,7 @@ mod tests {
     async fn fetch_with_retry_retries_on_network_error_and_succeeds() {
         let mock = MockHorizonClient::new().with_error(ProviderError::NetworkError {
             message: "timeout".into(),
+            timeout: true,
         });
 
         let result = fetch_with_retry(&mock, 3, 0).await;
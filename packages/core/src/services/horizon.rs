@@ -1,12 +1,74 @@
-use reqwest::Client;
+use reqwest::header::HeaderMap;
+use reqwest::{Certificate, Client, Proxy, RequestBuilder, StatusCode};
 use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
 
 use crate::error::AppError;
+use crate::insights::config::{HorizonAuthConfig, HorizonTransportConfig};
+use crate::insights::provider::RateLimitStatus;
+
+/// Longest we'll wait for a rate-limit window to reset before giving up
+/// and reporting `RateLimitExceeded` to the caller instead.
+const MAX_THROTTLE_WAIT: Duration = Duration::from_secs(10);
 
 #[derive(Clone)]
 pub struct HorizonClient {
     base_url: String,
     http: Client,
+    /// Shared across clones so every caller sees the same rate-limit window.
+    rate_limit: Arc<Mutex<RateLimitState>>,
+    auth: Option<HorizonAuthConfig>,
+    connect_timeout: Option<Duration>,
+    transport: HorizonTransportConfig,
+}
+
+/// Rate-limit window as last reported by Horizon's response headers.
+#[derive(Debug, Default)]
+struct RateLimitState {
+    limit: Option<u32>,
+    remaining: Option<u32>,
+    reset_at: Option<Instant>,
+    /// Set from `Retry-After` on a 429; no requests are sent before it.
+    blocked_until: Option<Instant>,
+}
+
+impl RateLimitState {
+    /// How long to hold off before the next request, if at all.
+    fn required_wait(&self, now: Instant) -> Option<Duration> {
+        if let Some(until) = self.blocked_until.filter(|until| *until > now) {
+            return Some(until - now);
+        }
+        match (self.remaining, self.reset_at) {
+            (Some(0), Some(reset_at)) if reset_at > now => Some(reset_at - now),
+            _ => None,
+        }
+    }
+}
+
+/// Load every certificate in the PEM file at `path`.
+fn read_ca_bundle(path: &str) -> Result<Vec<Certificate>, AppError> {
+    let pem = std::fs::read(path)
+        .map_err(|e| AppError::Config(format!("Cannot read CA bundle {}: {}", path, e)))?;
+
+    const END: &[u8] = b"-----END CERTIFICATE-----";
+    let mut certificates = Vec::new();
+    let mut rest = &pem[..];
+    while let Some(end) = rest.windows(END.len()).position(|w| w == END) {
+        let block = &rest[..end + END.len()];
+        certificates.push(Certificate::from_pem(block).map_err(|e| {
+            AppError::Config(format!("Invalid certificate in CA bundle {}: {}", path, e))
+        })?);
+        rest = &rest[end + END.len()..];
+    }
+    if certificates.is_empty() {
+        return Err(AppError::Config(format!(
+            "CA bundle {} contains no PEM certificates",
+            path
+        )));
+    }
+    Ok(certificates)
 }
 
 impl HorizonClient {
@@ -15,17 +77,170 @@ impl HorizonClient {
             .no_proxy()
             .build()
             .unwrap_or_else(|_| Client::new());
-        Self { base_url, http }
+        Self {
+            base_url,
+            http,
+            rate_limit: Arc::new(Mutex::new(RateLimitState::default())),
+            auth: None,
+            connect_timeout: None,
+            transport: HorizonTransportConfig::default(),
+        }
+    }
+
+    /// Give up on establishing a connection after `timeout`.
+    ///
+    /// Replaces the HTTP client, so clones made earlier keep their own pool.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        // Only the transport settings can fail, and they were validated
+        // when they were applied.
+        if let Ok(http) = self.build_http() {
+            self.http = http;
+        }
+        self
+    }
+
+    /// Route requests through a proxy and adjust TLS trust.
+    ///
+    /// Fails if the proxy URL is invalid or the CA bundle cannot be read.
+    /// Replaces the HTTP client, so clones made earlier keep their own pool.
+    pub fn with_transport(mut self, transport: HorizonTransportConfig) -> Result<Self, AppError> {
+        self.transport = transport;
+        self.http = self.build_http()?;
+        Ok(self)
+    }
+
+    fn build_http(&self) -> Result<Client, AppError> {
+        let transport = &self.transport;
+        let mut builder = Client::builder();
+        builder = match &transport.proxy_url {
+            Some(url) => builder.proxy(
+                Proxy::all(url)
+                    .map_err(|e| AppError::Config(format!("Invalid proxy URL: {}", e)))?,
+            ),
+            // Ignore HTTP(S)_PROXY from the environment unless configured here.
+            None => builder.no_proxy(),
+        };
+        if let Some(path) = &transport.ca_bundle_path {
+            for certificate in read_ca_bundle(path)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        if transport.accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        builder
+            .build()
+            .map_err(|e| AppError::Config(format!("Cannot build Horizon HTTP client: {}", e)))
+    }
+
+    /// Send `auth` credentials with every request to this Horizon instance.
+    pub fn with_auth(mut self, auth: HorizonAuthConfig) -> Self {
+        self.auth = Some(auth);
+        self
     }
 
     pub fn base_url(&self) -> &str {
         &self.base_url
     }
 
-    /// Expose the shared HTTP client for adapters that need to make
-    /// additional requests (e.g. `HorizonFeeDataProvider`).
-    pub(crate) fn http_client(&self) -> &Client {
-        &self.http
+    /// Start a GET on the shared HTTP client with the configured credentials
+    /// attached, for adapters that need to make additional requests
+    /// (e.g. `HorizonFeeDataProvider`).
+    pub(crate) fn get(&self, url: &str) -> RequestBuilder {
+        let mut request = self.http.get(url);
+        let Some(auth) = &self.auth else {
+            return request;
+        };
+
+        if let Some(token) = &auth.token {
+            let value = if auth.uses_authorization_header() {
+                format!("Bearer {}", token)
+            } else {
+                token.clone()
+            };
+            request = request.header(auth.header_name.as_str(), value);
+        }
+        if let Some(basic) = &auth.basic {
+            request = request.basic_auth(&basic.username, basic.password.as_ref());
+        }
+        request
+    }
+
+    /// Wait out an exhausted rate-limit window before sending a request.
+    ///
+    /// Returns the remaining wait as an error when it exceeds
+    /// `MAX_THROTTLE_WAIT`, so callers can fail fast instead of stalling.
+    pub(crate) async fn throttle(&self) -> Result<(), Duration> {
+        let wait = self.state().required_wait(Instant::now());
+        match wait {
+            None => Ok(()),
+            Some(wait) if wait <= MAX_THROTTLE_WAIT => {
+                tracing::debug!(
+                    "Horizon rate limit exhausted — waiting {}ms",
+                    wait.as_millis()
+                );
+                tokio::time::sleep(wait).await;
+                Ok(())
+            }
+            Some(wait) => Err(wait),
+        }
+    }
+
+    /// Update the rate-limit window from a Horizon response.
+    pub(crate) fn record_rate_limit(&self, status: StatusCode, headers: &HeaderMap) {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        let now = Instant::now();
+        let mut state = self.state();
+
+        if let Some(limit) = header("x-ratelimit-limit") {
+            state.limit = Some(limit as u32);
+        }
+        if let Some(remaining) = header("x-ratelimit-remaining") {
+            state.remaining = Some(remaining as u32);
+        }
+        if let Some(reset) = header("x-ratelimit-reset") {
+            state.reset_at = Some(now + Duration::from_secs(reset));
+        }
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = header("retry-after")
+                .map(Duration::from_secs)
+                .or_else(|| {
+                    state
+                        .reset_at
+                        .map(|reset_at| reset_at.saturating_duration_since(now))
+                })
+                .unwrap_or(Duration::from_secs(1));
+            state.remaining = Some(0);
+            state.blocked_until = Some(now + retry_after);
+        }
+    }
+
+    /// Current rate-limit window as seen by this client.
+    pub fn rate_limit_status(&self) -> RateLimitStatus {
+        let now = Instant::now();
+        let state = self.state();
+        RateLimitStatus {
+            limit: state.limit,
+            remaining: state.remaining,
+            reset_in_seconds: state
+                .reset_at
+                .filter(|reset_at| *reset_at > now)
+                .map(|reset_at| (reset_at - now).as_secs()),
+            throttled: state.required_wait(now).is_some(),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, RateLimitState> {
+        self.rate_limit.lock().unwrap_or_else(|e| e.into_inner())
     }
 }
 
@@ -60,12 +275,29 @@ impl HorizonClient {
     pub async fn fetch_fee_stats(&self) -> Result<HorizonFeeStats, AppError> {
         let url = format!("{}/fee_stats", self.base_url);
 
+        self.throttle().await.map_err(|wait| {
+            AppError::Network(format!(
+                "Horizon rate limit exhausted; resets in {}s",
+                wait.as_secs()
+            ))
+        })?;
+
         let response = self
-            .http
             .get(&url)
             .send()
             .await
             .map_err(|err| AppError::Network(err.to_string()))?;
+        self.record_rate_limit(response.status(), response.headers());
+
+        if matches!(
+            response.status(),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+        ) {
+            return Err(AppError::Network(format!(
+                "Horizon rejected credentials (HTTP {})",
+                response.status()
+            )));
+        }
 
         if !response.status().is_success() {
             return Err(AppError::Network(format!(
@@ -93,6 +325,158 @@ mod tests {
         assert_eq!(client.base_url(), "https://horizon-testnet.stellar.org");
     }
 
+    fn authed(auth: HorizonAuthConfig) -> HeaderMap {
+        HorizonClient::new("http://localhost".into())
+            .with_auth(auth)
+            .get("http://localhost/fee_stats")
+            .build()
+            .unwrap()
+            .headers()
+            .clone()
+    }
+
+    #[test]
+    fn bearer_token_is_sent_in_authorization_header() {
+        let headers = authed(HorizonAuthConfig {
+            header_name: "Authorization".into(),
+            token: Some("s3cret".into()),
+            basic: None,
+        });
+        assert_eq!(headers["authorization"], "Bearer s3cret");
+    }
+
+    #[test]
+    fn custom_header_receives_token_verbatim() {
+        let headers = authed(HorizonAuthConfig {
+            header_name: "X-Api-Key".into(),
+            token: Some("s3cret".into()),
+            basic: None,
+        });
+        assert_eq!(headers["x-api-key"], "s3cret");
+        assert!(!headers.contains_key("authorization"));
+    }
+
+    #[test]
+    fn basic_auth_is_encoded() {
+        let headers = authed(HorizonAuthConfig {
+            header_name: "X-Api-Key".into(),
+            token: None,
+            basic: Some(crate::insights::config::BasicAuthConfig {
+                username: "user".into(),
+                password: Some("pass".into()),
+            }),
+        });
+        assert_eq!(headers["authorization"], "Basic dXNlcjpwYXNz");
+    }
+
+    #[tokio::test]
+    async fn requests_go_through_configured_proxy() {
+        use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
+
+        let proxy = MockServer::start().await;
+        Mock::given(path("/fee_stats"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("via proxy"))
+            .mount(&proxy)
+            .await;
+
+        // The host does not resolve; only the proxy can answer.
+        let client = HorizonClient::new("http://horizon.invalid".into())
+            .with_transport(HorizonTransportConfig {
+                proxy_url: Some(proxy.uri()),
+                ..HorizonTransportConfig::default()
+            })
+            .unwrap();
+        let body = client
+            .get("http://horizon.invalid/fee_stats")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "via proxy");
+    }
+
+    #[test]
+    fn invalid_transport_settings_are_rejected() {
+        let client = || HorizonClient::new("http://localhost".into());
+
+        let missing_bundle = client().with_transport(HorizonTransportConfig {
+            ca_bundle_path: Some("/nonexistent/ca.pem".into()),
+            ..HorizonTransportConfig::default()
+        });
+        assert!(matches!(missing_bundle, Err(AppError::Config(_))));
+
+        let bad_proxy = client().with_transport(HorizonTransportConfig {
+            proxy_url: Some("not a url".into()),
+            ..HorizonTransportConfig::default()
+        });
+        assert!(matches!(bad_proxy, Err(AppError::Config(_))));
+    }
+
+    #[test]
+    fn ca_bundle_without_certificates_is_rejected() {
+        let path = std::env::temp_dir().join("horizon-empty-ca-bundle.pem");
+        std::fs::write(&path, "not a certificate\n").unwrap();
+        let result = read_ca_bundle(path.to_str().unwrap());
+        std::fs::remove_file(&path).ok();
+        assert!(matches!(result, Err(AppError::Config(msg)) if msg.contains("no PEM")));
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, value.parse().unwrap());
+        }
+        map
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limit_headers_are_tracked() {
+        let client = HorizonClient::new("http://localhost".into());
+        client.record_rate_limit(
+            StatusCode::OK,
+            &headers(&[
+                ("x-ratelimit-limit", "3600"),
+                ("x-ratelimit-remaining", "42"),
+                ("x-ratelimit-reset", "30"),
+            ]),
+        );
+
+        let status = client.rate_limit_status();
+        assert_eq!(status.limit, Some(3600));
+        assert_eq!(status.remaining, Some(42));
+        assert_eq!(status.reset_in_seconds, Some(30));
+        assert!(!status.throttled);
+        assert!(client.throttle().await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn exhausted_window_waits_for_short_reset() {
+        let client = HorizonClient::new("http://localhost".into());
+        client.record_rate_limit(
+            StatusCode::OK,
+            &headers(&[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "2")]),
+        );
+        assert!(client.rate_limit_status().throttled);
+
+        let started = Instant::now();
+        assert!(client.throttle().await.is_ok());
+        assert!(started.elapsed() >= Duration::from_secs(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retry_after_longer_than_max_wait_fails_fast() {
+        let client = HorizonClient::new("http://localhost".into());
+        client.record_rate_limit(
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers(&[("retry-after", "120")]),
+        );
+
+        let result = client.throttle().await;
+        assert!(matches!(result, Err(wait) if wait > MAX_THROTTLE_WAIT));
+    }
+
     #[test]
     fn fee_charged_deserialises_all_percentile_fields() {
         let json = r#"{
@@ -31,6 +31,8 @@ pub struct MockHorizonClient {
     error: Option<ProviderError>,
     /// Tracks total number of `fetch_latest_fees` calls.
     pub call_count: Arc<AtomicUsize>,
+    /// Tracks total number of `health_check` calls.
+    pub health_checks: Arc<AtomicUsize>,
     /// Controls whether `health_check` succeeds or returns `ServiceUnavailable`.
     healthy: bool,
 }
@@ -42,6 +44,7 @@ impl MockHorizonClient {
             responses: Vec::new(),
             error: None,
             call_count: Arc::new(AtomicUsize::new(0)),
+            health_checks: Arc::new(AtomicUsize::new(0)),
             healthy: true,
         }
     }
@@ -84,8 +87,9 @@ impl FeeDataProvider for MockHorizonClient {
         if let Some(ref err) = self.error {
             // Clone the error into a new matching variant — ProviderError is not Clone
             return Err(match err {
-                ProviderError::NetworkError { message } => ProviderError::NetworkError {
+                ProviderError::NetworkError { message, timeout } => ProviderError::NetworkError {
                     message: message.clone(),
+                    timeout: *timeout,
                 },
                 ProviderError::FormatError { message } => ProviderError::FormatError {
                     message: message.clone(),
@@ -95,6 +99,9 @@ impl FeeDataProvider for MockHorizonClient {
                 },
                 ProviderError::RateLimitExceeded => ProviderError::RateLimitExceeded,
                 ProviderError::ServiceUnavailable => ProviderError::ServiceUnavailable,
+                ProviderError::Unsupported { operation } => ProviderError::Unsupported {
+                    operation: operation.clone(),
+                },
             });
         }
 
@@ -106,6 +113,7 @@ impl FeeDataProvider for MockHorizonClient {
     }
 
     async fn health_check(&self) -> Result<(), ProviderError> {
+        self.health_checks.fetch_add(1, Ordering::SeqCst);
         if self.healthy {
             Ok(())
         } else {
@@ -119,6 +127,7 @@ impl FeeDataProvider for MockHorizonClient {
             max_batch_size: 100,
             rate_limit_per_minute: None,
             data_freshness_seconds: 5,
+            ..ProviderMetadata::default()
         }
     }
 }
@@ -134,6 +143,8 @@ mod tests {
             timestamp: Utc::now(),
             transaction_hash: format!("hash_{}", fee_amount),
             ledger_sequence: 1,
+            envelope: None,
+            soroban: None,
         }
     }
 
@@ -159,6 +170,7 @@ mod tests {
     async fn returns_configured_error() {
         let mock = MockHorizonClient::new().with_error(ProviderError::NetworkError {
             message: "simulated timeout".into(),
+            timeout: true,
         });
 
         let result = mock.fetch_latest_fees().await;
@@ -211,6 +223,7 @@ mod tests {
         let mock = MockHorizonClient::new()
             .with_error(ProviderError::NetworkError {
                 message: "down".into(),
+                timeout: false,
             })
             .with_healthy(true);
 
@@ -1,4 +1,6 @@
 pub mod horizon;
+pub mod soroban_rpc;
+pub mod sse;
 
 #[cfg(test)]
 pub mod mock_horizon;
@@ -0,0 +1,219 @@
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::AppError;
+
+/// Minimal JSON-RPC client for a soroban-rpc endpoint.
+#[derive(Clone)]
+pub struct SorobanRpcClient {
+    rpc_url: String,
+    http: Client,
+}
+
+impl SorobanRpcClient {
+    pub fn new(rpc_url: String) -> Self {
+        let http = Client::builder()
+            .no_proxy()
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        Self { rpc_url, http }
+    }
+
+    #[allow(dead_code)]
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_url
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SorobanHealth {
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestLedger {
+    pub sequence: u64,
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub protocol_version: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkInfo {
+    pub passphrase: String,
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub protocol_version: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetTransactionsResult {
+    pub transactions: Vec<SorobanTransactionInfo>,
+    pub latest_ledger: u64,
+    /// Resumes after the last transaction of this page.
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// A single transaction as returned by `getTransactions`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SorobanTransactionInfo {
+    pub status: String,
+    #[serde(default)]
+    pub tx_hash: String,
+    pub ledger: u64,
+    /// Ledger close time as a Unix timestamp (seconds).
+    pub created_at: i64,
+    pub envelope_xdr: String,
+    pub result_xdr: String,
+    /// Base64 `TransactionMeta`; carries the charged resource fee split.
+    #[serde(default)]
+    pub result_meta_xdr: Option<String>,
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub fee_bump: bool,
+}
+
+impl SorobanRpcClient {
+    /// Issue a JSON-RPC call and decode its `result` member.
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, AppError> {
+        let mut request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+        });
+        if !params.is_null() {
+            request["params"] = params;
+        }
+
+        let response = self
+            .http
+            .post(&self.rpc_url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|err| AppError::Network(err.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::Network(format!(
+                "soroban-rpc returned HTTP {}",
+                response.status()
+            )));
+        }
+
+        let body = response
+            .json::<JsonRpcResponse<T>>()
+            .await
+            .map_err(|err| AppError::Parse(err.to_string()))?;
+
+        if let Some(err) = body.error {
+            return Err(AppError::Network(format!(
+                "soroban-rpc {} failed ({}): {}",
+                method, err.code, err.message
+            )));
+        }
+
+        body.result
+            .ok_or_else(|| AppError::Parse(format!("soroban-rpc {} returned no result", method)))
+    }
+
+    pub async fn get_health(&self) -> Result<SorobanHealth, AppError> {
+        self.call("getHealth", Value::Null).await
+    }
+
+    pub async fn get_latest_ledger(&self) -> Result<LatestLedger, AppError> {
+        self.call("getLatestLedger", Value::Null).await
+    }
+
+    pub async fn get_network(&self) -> Result<NetworkInfo, AppError> {
+        self.call("getNetwork", Value::Null).await
+    }
+
+    /// Fetch up to `limit` transactions starting at `start_ledger`, oldest first.
+    pub async fn get_transactions(
+        &self,
+        start_ledger: u64,
+        limit: u32,
+    ) -> Result<GetTransactionsResult, AppError> {
+        self.call(
+            "getTransactions",
+            json!({
+                "startLedger": start_ledger,
+                "pagination": { "limit": limit },
+            }),
+        )
+        .await
+    }
+
+    /// Fetch up to `limit` transactions following `cursor`, oldest first.
+    pub async fn get_transactions_after(
+        &self,
+        cursor: &str,
+        limit: u32,
+    ) -> Result<GetTransactionsResult, AppError> {
+        self.call(
+            "getTransactions",
+            json!({
+                "pagination": { "cursor": cursor, "limit": limit },
+            }),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soroban_rpc_client_url_is_stored() {
+        let client = SorobanRpcClient::new("https://soroban-testnet.stellar.org".into());
+        assert_eq!(client.rpc_url(), "https://soroban-testnet.stellar.org");
+    }
+
+    #[test]
+    fn get_transactions_result_deserialises() {
+        let json = r#"{
+            "transactions": [{
+                "status": "SUCCESS",
+                "txHash": "abc123",
+                "applicationOrder": 1,
+                "feeBump": false,
+                "envelopeXdr": "AAAA",
+                "resultXdr": "AAAA",
+                "resultMetaXdr": "AAAA",
+                "ledger": 1234,
+                "createdAt": 1736851500
+            }],
+            "latestLedger": 1240,
+            "latestLedgerCloseTimestamp": 1736851530,
+            "oldestLedger": 1000,
+            "oldestLedgerCloseTimestamp": 1736800000,
+            "cursor": "5299989567848448"
+        }"#;
+        let result: GetTransactionsResult = serde_json::from_str(json).unwrap();
+        assert_eq!(result.latest_ledger, 1240);
+        assert_eq!(result.transactions.len(), 1);
+        assert_eq!(result.transactions[0].tx_hash, "abc123");
+        assert_eq!(result.transactions[0].created_at, 1736851500);
+        assert!(!result.transactions[0].fee_bump);
+    }
+}
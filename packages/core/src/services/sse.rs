@@ -0,0 +1,105 @@
+//! Minimal Server-Sent Events decoder.
+//!
+//! Horizon streams resources as `text/event-stream`; this decoder turns the
+//! raw byte chunks from a response body into complete events. Only the
+//! `id`, `event` and `data` fields are kept — `retry` hints are ignored since
+//! reconnect timing is owned by the caller.
+
+/// A single dispatched SSE event.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SseEvent {
+    pub id: Option<String>,
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Incremental decoder: feed it chunks, collect complete events.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: String,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a chunk and return every event completed by it.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.push_str(&String::from_utf8_lossy(chunk));
+        if self.buffer.contains('\r') {
+            self.buffer = self.buffer.replace("\r\n", "\n").replace('\r', "\n");
+        }
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.find("\n\n") {
+            let block: String = self.buffer.drain(..end + 2).collect();
+            if let Some(event) = parse_block(&block) {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+fn parse_block(block: &str) -> Option<SseEvent> {
+    let mut event = SseEvent::default();
+    let mut data_lines = Vec::new();
+
+    for line in block.lines() {
+        if line.is_empty() || line.starts_with(':') {
+            continue;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "id" => event.id = Some(value.to_string()),
+            "event" => event.event = Some(value.to_string()),
+            "data" => data_lines.push(value),
+            _ => {}
+        }
+    }
+
+    if data_lines.is_empty() && event.id.is_none() {
+        return None;
+    }
+    event.data = data_lines.join("\n");
+    Some(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_event_split_across_chunks() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.push(b"id: 42\ndata: {\"a\"").is_empty());
+        let events = decoder.push(b":1}\n\n");
+        assert_eq!(
+            events,
+            vec![SseEvent {
+                id: Some("42".into()),
+                event: None,
+                data: "{\"a\":1}".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn ignores_comments_and_handles_crlf() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.push(b": keep-alive\r\n\r\nretry: 1000\r\ndata: \"hello\"\r\n\r\n");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "\"hello\"");
+    }
+
+    #[test]
+    fn joins_multiline_data() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.push(b"data: a\ndata: b\n\n");
+        assert_eq!(events[0].data, "a\nb");
+    }
+}
@@ -0,0 +1,126 @@
+//! Congestion SLA reporting.
+//!
+//! The congestion state is sampled whenever an insight snapshot is
+//! persisted. Each sample's state counts until the next sample, for at most
+//! [`MAX_SAMPLE_SPAN`], so downtime is not credited to any state; shares
+//! are of the time actually observed.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::insights::CongestionState;
+use crate::repository::FeeRepository;
+
+/// Longest time a single sample's state is assumed to hold.
+pub const MAX_SAMPLE_SPAN: Duration = Duration::minutes(15);
+
+/// Reporting periods, longest last.
+const PERIODS: [(&str, Duration); 3] = [
+    ("24h", Duration::hours(24)),
+    ("7d", Duration::days(7)),
+    ("30d", Duration::days(30)),
+];
+
+/// How long samples are kept; a day past the longest period.
+pub const SAMPLE_RETENTION: Duration = Duration::days(31);
+
+/// Time spent in each congestion state over one rolling period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CongestionSla {
+    /// `24h`, `7d` or `30d`.
+    pub period: String,
+    /// Time covered by samples, in seconds.
+    pub observed_seconds: i64,
+    /// Shares (0.0–1.0) of the observed time; all zero when nothing was
+    /// observed.
+    pub normal_share: f64,
+    pub elevated_share: f64,
+    pub congested_share: f64,
+}
+
+/// Time-in-state over the last 24 hours, 7 days and 30 days before `now`.
+pub async fn congestion_sla(
+    repo: &FeeRepository,
+    now: DateTime<Utc>,
+) -> Result<Vec<CongestionSla>, sqlx::Error> {
+    let longest = PERIODS[PERIODS.len() - 1].1;
+    let samples = repo.fetch_congestion_samples_since(now - longest).await?;
+
+    Ok(PERIODS
+        .iter()
+        .map(|&(period, length)| summarize(period, &samples, now - length, now))
+        .collect())
+}
+
+/// Shares of `from..to` covered by each state in the ascending `samples`.
+fn summarize(
+    period: &str,
+    samples: &[(DateTime<Utc>, CongestionState)],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> CongestionSla {
+    let mut seconds = [0i64; 3];
+    for (i, &(captured_at, state)) in samples.iter().enumerate() {
+        let next = samples.get(i + 1).map_or(to, |&(next, _)| next);
+        let end = next.min(captured_at + MAX_SAMPLE_SPAN).min(to);
+        let start = captured_at.max(from);
+        if end > start {
+            let slot = match state {
+                CongestionState::Normal => 0,
+                CongestionState::Elevated => 1,
+                CongestionState::Congested => 2,
+            };
+            seconds[slot] += (end - start).num_seconds();
+        }
+    }
+
+    let observed: i64 = seconds.iter().sum();
+    let share = |slot: usize| {
+        if observed > 0 {
+            seconds[slot] as f64 / observed as f64
+        } else {
+            0.0
+        }
+    };
+
+    CongestionSla {
+        period: period.to_string(),
+        observed_seconds: observed,
+        normal_share: share(0),
+        elevated_share: share(1),
+        congested_share: share(2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_sample_holds_until_the_next_or_its_span_runs_out() {
+        let now = Utc::now();
+        let ago = |minutes| now - Duration::minutes(minutes);
+        let samples = [
+            (ago(120), CongestionState::Normal),
+            (ago(115), CongestionState::Normal),
+            // Followed by a gap: only 15 of these 60 minutes count
+            (ago(110), CongestionState::Congested),
+            (ago(50), CongestionState::Elevated),
+            (ago(45), CongestionState::Normal),
+        ];
+
+        let sla = summarize("24h", &samples, ago(24 * 60), now);
+        assert_eq!(sla.observed_seconds, 45 * 60);
+        assert_eq!(sla.congested_share, 15.0 / 45.0);
+        assert_eq!(sla.elevated_share, 5.0 / 45.0);
+        assert_eq!(sla.normal_share, 25.0 / 45.0);
+
+        // Samples before the period only count from its start
+        let recent = summarize("1h", &samples, ago(60), now);
+        assert_eq!(recent.observed_seconds, 20 * 60);
+        assert_eq!(recent.congested_share, 0.0);
+
+        let empty = summarize("24h", &[], ago(60), now);
+        assert_eq!((empty.observed_seconds, empty.normal_share), (0, 0.0));
+    }
+}
@@ -88,6 +88,8 @@ mod tests {
             timestamp: Utc::now() - Duration::minutes(minutes_ago),
             transaction_hash: format!("hash_{}", fee_amount),
             ledger_sequence: fee_amount,
+            envelope: None,
+            soroban: None,
         }
     }
 
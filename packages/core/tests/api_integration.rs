@@ -35,10 +35,12 @@ use stellar_fee_tracker::{
     api,
     cache::ResponseCache,
     db,
+    insights::providers::simulated::{SimulatedFeeDataProvider, SimulationConfig},
     insights::types::FeeDataPoint,
     insights::{FeeInsightsEngine, InsightsConfig},
     metrics::AppMetrics,
     repository::FeeRepository,
+    scheduler::SchedulerHeartbeat,
     services::horizon::HorizonClient,
     store::{FeeHistoryStore, DEFAULT_CAPACITY},
 };
@@ -99,6 +101,8 @@ fn make_fee_points(count: usize) -> Vec<FeeDataPoint> {
             timestamp: now - ChronoDuration::minutes((count - i) as i64),
             transaction_hash: format!("txhash{:06}", i),
             ledger_sequence: 50_000_000 + i as u64,
+            envelope: None,
+            soroban: None,
         })
         .collect()
 }
@@ -164,11 +168,27 @@ async fn build_test_app() -> (Router, MockServer) {
             fee_cache,
             fee_store: fee_store.clone(),
             insights_engine: Some(insights_engine.clone()),
+            repository: None,
         }));
 
     // ---- Full router (mirrors main.rs assembly) ----
+    // ---- Health ----
+    let scheduler_heartbeat = Arc::new(SchedulerHeartbeat::new());
+    scheduler_heartbeat.beat();
+    let health_state = api::health::HealthState {
+        repository: repository.clone(),
+        provider: Arc::new(SimulatedFeeDataProvider::new(SimulationConfig::default())),
+        insights_engine: insights_engine.clone(),
+        heartbeat: scheduler_heartbeat,
+        stale_after: ChronoDuration::minutes(1),
+        provider_probe: Arc::new(api::health::ProviderProbe::default()),
+    };
+
     let app = Router::new()
         .route("/health", get(api::health::health))
+        .route("/health/live", get(api::health::live))
+        .route("/health/ready", get(api::health::ready))
+        .with_state(health_state)
         .route(
             "/metrics",
             get(move || {
@@ -232,7 +252,7 @@ async fn json_body(body: Body) -> Value {
 // ---- GET /health ------------------------------------------------------------
 
 #[tokio::test]
-async fn health_returns_200_with_ok_body() {
+async fn health_returns_200_with_every_component_ok() {
     let (app, _mock) = build_test_app().await;
     let resp = app
         .oneshot(
@@ -251,8 +271,25 @@ async fn health_returns_200_with_ok_body() {
             .and_then(|v| v.to_str().ok()),
         Some("no-store")
     );
-    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
-    assert_eq!(&bytes[..], b"ok");
+    let json = json_body(resp.into_body()).await;
+    assert_eq!(json["status"], "ok");
+    for component in ["database", "provider", "scheduler", "data"] {
+        assert_eq!(json["checks"][component]["status"], "ok", "{}", component);
+    }
+}
+
+#[tokio::test]
+async fn live_and_ready_return_200_while_polling() {
+    let (app, _mock) = build_test_app().await;
+    for uri in ["/health/live", "/health/ready"] {
+        let resp = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "{}", uri);
+        assert_eq!(json_body(resp.into_body()).await["status"], "ok");
+    }
 }
 
 // ---- GET /fees/current ------------------------------------------------------
@@ -455,7 +492,8 @@ async fn fees_history_invalid_window_returns_400() {
 
     assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     let json = json_body(resp.into_body()).await;
-    assert!(json["error"].is_string());
+    assert_eq!(json["code"], "invalid_request");
+    assert!(json["detail"].is_string());
 }
 
 #[tokio::test]
@@ -724,6 +762,24 @@ async fn insights_health_returns_200_with_status() {
     assert_eq!(json["status"], "healthy");
 }
 
+// ---- GET /insights/fee-stats ------------------------------------------------
+
+#[tokio::test]
+async fn insights_fee_stats_returns_404_before_first_snapshot() {
+    let (app, _mock) = build_test_app().await;
+    let resp = app
+        .oneshot(
+            Request::builder()
+                .uri("/insights/fee-stats")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
 // ---- GET /metrics -----------------------------------------------------------
 
 #[tokio::test]
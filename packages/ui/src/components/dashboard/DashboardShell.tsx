@@ -93,7 +93,7 @@ export function DashboardShell({ initialData }: Props) {
         />
 
         {/* Row 2 — Percentile strip */}
-        {current && (
+        {current?.percentiles && (
           <PercentileRow percentiles={current.percentiles} tick={tick} />
         )}
 
@@ -48,8 +48,8 @@ function StatCard({
 }
 
 export function StatCards({ current, trend, tick }: Props) {
-  const baseFee    = current ? formatStroops(current.base_fee)  : '—'
-  const avgFee     = current ? formatStroops(current.avg_fee)   : '—'
+  const baseFee    = current?.base_fee ? formatStroops(current.base_fee) : '—'
+  const avgFee     = current?.avg_fee  ? formatStroops(current.avg_fee)  : '—'
   const status     = trend?.status ?? 'Normal'
   const spikes     = trend?.recent_spike_count ?? 0
   const strength   = trend?.trend_strength ?? '—'
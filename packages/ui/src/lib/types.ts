@@ -13,12 +13,13 @@ export interface PercentileFees {
   p99: string
 }
 
+// Horizon's fields are missing while the backend cannot reach Horizon
 export interface CurrentFeeResponse {
-  base_fee: string
-  min_fee: string
-  max_fee: string
-  avg_fee: string
-  percentiles: PercentileFees
+  base_fee?: string
+  min_fee?: string
+  max_fee?: string
+  avg_fee?: string
+  percentiles?: PercentileFees
 }
 
 // ---- /fees/history ----